@@ -0,0 +1,47 @@
+use crate::types::portal::{
+    CompactionInfo, DataRadius, LifetimeStatsInfo, PeerStatsInfo, StorageSummaryInfo,
+    UtpTransferInfo,
+};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Node administration JSON-RPC endpoints
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "admin"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "admin"))]
+pub trait AdminApi {
+    /// Returns activity counters for every peer the local node has interacted with.
+    #[method(name = "peerStats")]
+    async fn peer_stats(&self) -> RpcResult<Vec<PeerStatsInfo>>;
+
+    /// Returns the activity of the local node on the history network across restarts.
+    #[method(name = "lifetimeStats")]
+    async fn lifetime_stats(&self) -> RpcResult<LifetimeStatsInfo>;
+
+    /// Pings the nodes in a bucket of the history routing table and looks up new nodes for it,
+    /// or does so for every bucket within the table depth if no bucket is given.
+    #[method(name = "refreshHistoryRoutingTable")]
+    async fn refresh_history_routing_table(&self, bucket: Option<u8>) -> RpcResult<bool>;
+
+    /// Returns the uTP transfers of the history network that are in progress, oldest first.
+    #[method(name = "utpTransfers")]
+    async fn utp_transfers(&self) -> RpcResult<Vec<UtpTransferInfo>>;
+
+    /// Changes the storage capacity of the history network, pruning content immediately if the
+    /// capacity shrinks. Returns the storage summary after resizing.
+    #[method(name = "setStorageCapacity")]
+    async fn set_storage_capacity(&self, capacity_kb: u64) -> RpcResult<StorageSummaryInfo>;
+
+    /// Compacts the history network content database, returning the space left by pruned content
+    /// to the file system.
+    #[method(name = "compactStorage")]
+    async fn compact_storage(&self) -> RpcResult<CompactionInfo>;
+
+    /// Pins the data radius of the history network, or derives it from storage utilization
+    /// again if no radius is given. Returns the new radius. Only served over IPC.
+    #[method(name = "setHistoryRadius")]
+    async fn set_history_radius(&self, radius: Option<DataRadius>) -> RpcResult<DataRadius>;
+
+    /// Shuts the node down gracefully, as on SIGTERM. Requires the token that the node was
+    /// started with through `--admin-shutdown-token`; the endpoint is disabled without one.
+    #[method(name = "shutdown")]
+    async fn shutdown(&self, token: String) -> RpcResult<bool>;
+}
@@ -0,0 +1,19 @@
+use ethereum_types::H256;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Debug JSON-RPC endpoints
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "debug"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "debug"))]
+pub trait DebugApi {
+    /// Returns the hex encoded RLP of the header of the block with the given hash.
+    #[method(name = "getRawHeader")]
+    async fn get_raw_header(&self, block_hash: H256) -> RpcResult<String>;
+
+    /// Returns the hex encoded RLP of the block with the given hash.
+    #[method(name = "getRawBlock")]
+    async fn get_raw_block(&self, block_hash: H256) -> RpcResult<String>;
+
+    /// Returns the hex encoded binary encoding of each receipt of the block with the given hash.
+    #[method(name = "getRawReceipts")]
+    async fn get_raw_receipts(&self, block_hash: H256) -> RpcResult<Vec<String>>;
+}
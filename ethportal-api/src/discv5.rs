@@ -4,7 +4,8 @@ use trin_types::enr::Enr;
 use trin_types::node_id::NodeId;
 
 /// Discv5 JSON-RPC endpoints
-#[rpc(client, server, namespace = "discv5")]
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "discv5"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "discv5"))]
 pub trait Discv5Api {
     /// Returns ENR and Node ID information of the local discv5 node.
     #[method(name = "nodeInfo")]
@@ -0,0 +1,25 @@
+use crate::types::eth::{BlockId, TransactionReceiptInfo};
+use ethereum_types::{H256, U256};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Ethereum JSON-RPC endpoints
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "eth"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "eth"))]
+pub trait EthApi {
+    /// Returns a gas price suggestion, based on the fees paid in recent blocks.
+    #[method(name = "gasPrice")]
+    async fn gas_price(&self) -> RpcResult<U256>;
+
+    /// Returns a priority fee suggestion, based on the tips paid in recent blocks.
+    #[method(name = "maxPriorityFeePerGas")]
+    async fn max_priority_fee_per_gas(&self) -> RpcResult<U256>;
+
+    /// Submits a signed transaction to the network, returning the transaction hash.
+    #[method(name = "sendRawTransaction")]
+    async fn send_raw_transaction(&self, raw_transaction: String) -> RpcResult<H256>;
+
+    /// Returns the receipts of all transactions in the block with the given hash or number,
+    /// looked up on the history network.
+    #[method(name = "getBlockReceipts")]
+    async fn get_block_receipts(&self, block: BlockId) -> RpcResult<Vec<TransactionReceiptInfo>>;
+}
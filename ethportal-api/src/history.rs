@@ -1,18 +1,21 @@
 use crate::types::portal::FindNodesInfo;
 use crate::types::{
     discv5::RoutingTableInfo,
+    ping_extensions::PeerCapabilitiesInfo,
     portal::{
-        AcceptInfo, ContentInfo, DataRadius, PaginateLocalContentInfo, PongInfo, TraceContentInfo,
+        AcceptInfo, ContentChunkInfo, ContentInfo, DataRadius, NetworkEstimateInfo,
+        PaginateLocalContentInfo, PongInfo, ReplicationInfo, SummaryInfo, TraceContentInfo,
     },
 };
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use trin_types::content_key::HistoryContentKey;
-use trin_types::content_value::{HistoryContentValue, PossibleHistoryContentValue};
+use trin_types::content_value::{PossibleHistoryContentValue, RawContentValue};
 use trin_types::enr::Enr;
 use trin_types::node_id::NodeId;
 
 /// Portal History JSON-RPC endpoints
-#[rpc(client, server, namespace = "portal")]
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "portal"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "portal"))]
 pub trait HistoryNetworkApi {
     /// Returns meta information about overlay routing table.
     #[method(name = "historyRoutingTableInfo")]
@@ -22,6 +25,29 @@ pub trait HistoryNetworkApi {
     #[method(name = "historyRadius")]
     async fn radius(&self) -> RpcResult<DataRadius>;
 
+    /// Returns storage utilization and message counts for the local node.
+    #[method(name = "historySummary")]
+    async fn summary(&self) -> RpcResult<SummaryInfo>;
+
+    /// Returns the client info and capabilities advertised by routing table peers through
+    /// PING/PONG extensions.
+    #[method(name = "historyPeerCapabilities")]
+    async fn peer_capabilities(&self) -> RpcResult<Vec<PeerCapabilitiesInfo>>;
+
+    /// Returns estimates of the network size, replication and total storage, from the data radii
+    /// advertised by routing table peers.
+    #[method(name = "historyNetworkEstimate")]
+    async fn network_estimate(&self) -> RpcResult<NetworkEstimateInfo>;
+
+    /// Look up the `nodes` nodes closest to the content id in the network and ask each of them
+    /// for the content. Returns how many of them hold it.
+    #[method(name = "historyReplication")]
+    async fn replication(
+        &self,
+        content_key: HistoryContentKey,
+        nodes: Option<u64>,
+    ) -> RpcResult<ReplicationInfo>;
+
     /// Write an Ethereum Node Record to the overlay routing table.
     #[method(name = "historyAddEnr")]
     async fn add_enr(&self, enr: Enr) -> RpcResult<bool>;
@@ -59,11 +85,13 @@ pub trait HistoryNetworkApi {
         content_key: HistoryContentKey,
     ) -> RpcResult<ContentInfo>;
 
-    /// Lookup a target content key in the network
+    /// Lookup a target content key in the network. Recent lookup results are served from a
+    /// cache, unless `bypass_cache` is set.
     #[method(name = "historyRecursiveFindContent")]
     async fn recursive_find_content(
         &self,
         content_key: HistoryContentKey,
+        bypass_cache: Option<bool>,
     ) -> RpcResult<PossibleHistoryContentValue>;
 
     /// Lookup a target content key in the network. Return tracing info.
@@ -81,13 +109,26 @@ pub trait HistoryNetworkApi {
         limit: u64,
     ) -> RpcResult<PaginateLocalContentInfo>;
 
+    /// Return a slice of the content value, starting at `offset` and at most `max_bytes` long.
+    /// Looks up the content on the network if it is not stored locally. Lets clients fetch
+    /// large values, like epoch accumulators, without a single multi-megabyte response.
+    #[method(name = "historyContentChunk")]
+    async fn content_chunk(
+        &self,
+        content_key: HistoryContentKey,
+        offset: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> RpcResult<ContentChunkInfo>;
+
     /// Send the provided content value to interested peers. Clients may choose to send to some or all peers.
     /// Return the number of peers that the content was gossiped to.
+    ///
+    /// Content values are decoded as the type that the content key refers to.
     #[method(name = "historyGossip")]
     async fn gossip(
         &self,
         content_key: HistoryContentKey,
-        content_value: HistoryContentValue,
+        content_value: RawContentValue,
     ) -> RpcResult<u32>;
 
     /// Send an OFFER request with given ContentKey, to the designated peer and wait for a response.
@@ -97,7 +138,7 @@ pub trait HistoryNetworkApi {
         &self,
         enr: Enr,
         content_key: HistoryContentKey,
-        content_value: Option<HistoryContentValue>,
+        content_value: Option<RawContentValue>,
     ) -> RpcResult<AcceptInfo>;
 
     /// Store content key with a content data to the local database.
@@ -105,7 +146,7 @@ pub trait HistoryNetworkApi {
     async fn store(
         &self,
         content_key: HistoryContentKey,
-        content_value: HistoryContentValue,
+        content_value: RawContentValue,
     ) -> RpcResult<bool>;
 
     /// Get a content from the local database
@@ -1,28 +1,59 @@
 //! # ethportal-api
 //!
 //! `ethportal_api` is a collection of Portal Network APIs and types.
+//!
+//! Without the default `native` feature, the crate builds for `wasm32-unknown-unknown`, with the
+//! content key and value codecs, the API types and the client traits.
 #![warn(clippy::unwrap_used)]
 
+mod admin;
+mod debug;
 mod discv5;
+mod eth;
 mod history;
+mod state;
 pub mod types;
 mod web3;
 
-pub use crate::discv5::{Discv5ApiClient, Discv5ApiServer};
-pub use history::{HistoryNetworkApiClient, HistoryNetworkApiServer};
-pub use web3::{Web3ApiClient, Web3ApiServer};
+pub use crate::discv5::Discv5ApiClient;
+pub use admin::AdminApiClient;
+pub use debug::DebugApiClient;
+pub use eth::EthApiClient;
+pub use history::HistoryNetworkApiClient;
+pub use state::StateNetworkApiClient;
+pub use web3::Web3ApiClient;
+
+#[cfg(feature = "native")]
+pub use crate::discv5::Discv5ApiServer;
+#[cfg(feature = "native")]
+pub use admin::AdminApiServer;
+#[cfg(feature = "native")]
+pub use debug::DebugApiServer;
+#[cfg(feature = "native")]
+pub use eth::EthApiServer;
+#[cfg(feature = "native")]
+pub use history::HistoryNetworkApiServer;
+#[cfg(feature = "native")]
+pub use state::StateNetworkApiServer;
+#[cfg(feature = "native")]
+pub use web3::Web3ApiServer;
 
 // Re-exports trin-types
 pub use trin_types::content_key::{
-    BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey, HistoryContentKey,
-    OverlayContentKey, StateContentKey,
+    BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey, EphemeralHeaderKey,
+    EpochAccumulatorKey, HistoryContentKey, OverlayContentKey, StateContentKey,
 };
 pub use trin_types::content_value::{
     ContentValue, ContentValueError, HistoryContentValue, PossibleHistoryContentValue,
+    RawContentValue,
 };
 pub use trin_types::execution::block_body::*;
 pub use trin_types::execution::header::*;
 pub use trin_types::execution::receipts::*;
 
+// Re-exports trin-validation
+#[cfg(feature = "native")]
+pub use trin_validation::accumulator::MasterAccumulator;
+
 // Re-exports jsonrpsee crate
 pub use jsonrpsee;
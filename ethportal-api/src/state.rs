@@ -0,0 +1,15 @@
+use crate::types::portal::{DataRadius, SummaryInfo};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+
+/// Portal State JSON-RPC endpoints
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "portal"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "portal"))]
+pub trait StateNetworkApi {
+    /// Returns the current data storage radius being used for the State network.
+    #[method(name = "stateRadius")]
+    async fn radius(&self) -> RpcResult<DataRadius>;
+
+    /// Returns storage utilization and message counts for the local node.
+    #[method(name = "stateSummary")]
+    async fn summary(&self) -> RpcResult<SummaryInfo>;
+}
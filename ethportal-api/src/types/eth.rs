@@ -0,0 +1,251 @@
+use ethereum_types::{Bloom, H160, H256, U256, U64};
+use serde::{Deserialize, Serialize};
+use trin_types::execution::{
+    block_body::BlockBody,
+    header::Header,
+    receipts::{Receipts, TransactionOutcome},
+};
+use trin_utils::bytes::hex_encode;
+
+/// Identifies a block by hash or by number. Block tags such as `latest` are not supported, since
+/// the history network can't tell which block is the latest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockId {
+    Hash(H256),
+    Number(U64),
+}
+
+/// Response item for eth_getBlockReceipts, in the format of eth_getTransactionReceipt.
+///
+/// The sender and the address of created contracts are left out, since they require recovering
+/// the sender from the transaction signature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceiptInfo {
+    pub transaction_hash: H256,
+    pub transaction_index: U64,
+    pub block_hash: H256,
+    pub block_number: U64,
+    /// `None` for transactions that create a contract
+    pub to: Option<H160>,
+    pub cumulative_gas_used: U256,
+    pub gas_used: U256,
+    pub effective_gas_price: U256,
+    pub logs: Vec<LogInfo>,
+    pub logs_bloom: Bloom,
+    #[serde(rename = "type")]
+    pub transaction_type: U64,
+    /// Whether the transaction succeeded, for blocks since the Byzantium fork
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<U64>,
+    /// Post-transaction state root, for blocks before the Byzantium fork
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<H256>,
+}
+
+/// Part of a TransactionReceiptInfo
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogInfo {
+    pub address: H160,
+    pub topics: Vec<H256>,
+    /// Hex-encoded data of the log
+    pub data: String,
+    pub block_hash: H256,
+    pub block_number: U64,
+    pub transaction_hash: H256,
+    pub transaction_index: U64,
+    /// Position of the log among all logs of the block
+    pub log_index: U64,
+    pub removed: bool,
+}
+
+impl TransactionReceiptInfo {
+    /// Returns the receipts of the block, combined with its header and transactions.
+    pub fn from_block(
+        header: &Header,
+        body: &BlockBody,
+        receipts: &Receipts,
+    ) -> Result<Vec<Self>, String> {
+        if body.txs.len() != receipts.receipt_list.len() {
+            return Err(format!(
+                "Block {} has {} transactions, but {} receipts",
+                header.number,
+                body.txs.len(),
+                receipts.receipt_list.len()
+            ));
+        }
+        let block_hash = header.hash();
+        let block_number = U64::from(header.number);
+        let mut previous_cumulative_gas_used = U256::zero();
+        let mut log_index = 0u64;
+        let mut block_receipts = vec![];
+        for (index, (tx, receipt)) in body.txs.iter().zip(&receipts.receipt_list).enumerate() {
+            let transaction_hash = tx.hash();
+            let transaction_index = U64::from(index as u64);
+            let receipt = receipt.receipt();
+            let logs = receipt
+                .logs
+                .iter()
+                .map(|log| {
+                    let log_info = LogInfo {
+                        address: log.address,
+                        topics: log.topics.clone(),
+                        data: hex_encode(&log.data),
+                        block_hash,
+                        block_number,
+                        transaction_hash,
+                        transaction_index,
+                        log_index: U64::from(log_index),
+                        removed: false,
+                    };
+                    log_index += 1;
+                    log_info
+                })
+                .collect();
+            let (status, root) = match receipt.outcome {
+                TransactionOutcome::StatusCode(code) => (Some(U64::from(code)), None),
+                TransactionOutcome::StateRoot(root) => (None, Some(root)),
+            };
+            block_receipts.push(Self {
+                transaction_hash,
+                transaction_index,
+                block_hash,
+                block_number,
+                to: tx.to(),
+                cumulative_gas_used: receipt.cumulative_gas_used,
+                gas_used: receipt
+                    .cumulative_gas_used
+                    .saturating_sub(previous_cumulative_gas_used),
+                effective_gas_price: tx.effective_gas_price(header.base_fee_per_gas),
+                logs,
+                logs_bloom: receipt.log_bloom,
+                transaction_type: U64::from(tx.transaction_id() as u8),
+                status,
+                root,
+            });
+            previous_cumulative_gas_used = receipt.cumulative_gas_used;
+        }
+        Ok(block_receipts)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use ssz::Decode;
+
+    #[test]
+    fn block_id_is_a_hash_or_a_number() {
+        let hash = "0x720704f3aa11c53cf344ea069db95cecb81ad7453c8f276b2a1062979611f09c";
+        assert_eq!(
+            serde_json::from_str::<BlockId>(&format!("\"{hash}\"")).unwrap(),
+            BlockId::Hash(hash.parse().unwrap())
+        );
+        assert_eq!(
+            serde_json::from_str::<BlockId>("\"0xe1b6ed\"").unwrap(),
+            BlockId::Number(U64::from(14792429))
+        );
+        assert!(serde_json::from_str::<BlockId>("\"latest\"").is_err());
+    }
+
+    #[test]
+    fn receipts_from_block_14764013() {
+        let block: Value = serde_json::from_str(
+            &std::fs::read_to_string("../trin-types/src/assets/trin/block_14764013_value.json")
+                .unwrap(),
+        )
+        .unwrap();
+        let header: Header = serde_json::from_value(block["result"].clone()).unwrap();
+        let body = BlockBody::from_ssz_bytes(
+            &std::fs::read("src/assets/test/block_body_14764013.bin").unwrap(),
+        )
+        .unwrap();
+        let receipts = Receipts::from_ssz_bytes(
+            &std::fs::read("src/assets/test/receipts_14764013.bin").unwrap(),
+        )
+        .unwrap();
+
+        let block_receipts = TransactionReceiptInfo::from_block(&header, &body, &receipts).unwrap();
+
+        // Transaction hashes as reported by mainnet nodes for the block.
+        let tx_hashes: Vec<H256> = block["result"]["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tx| tx["hash"].as_str().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(block_receipts.len(), 19);
+        for (index, receipt) in block_receipts.iter().enumerate() {
+            assert_eq!(receipt.transaction_hash, tx_hashes[index]);
+            assert_eq!(receipt.transaction_index, U64::from(index as u64));
+            assert_eq!(receipt.block_hash, header.hash());
+            assert_eq!(receipt.block_number, U64::from(14764013));
+            // Transactions 4 and 5 of the block reverted.
+            let expected_status = if index == 4 || index == 5 { 0 } else { 1 };
+            assert_eq!(receipt.status, Some(U64::from(expected_status)));
+            assert_eq!(receipt.root, None);
+        }
+
+        // Gas used by each transaction, from https://etherscan.io/txs?block=14764013
+        let gas_used: Vec<u64> = block_receipts
+            .iter()
+            .map(|receipt| receipt.gas_used.as_u64())
+            .collect();
+        assert_eq!(
+            gas_used,
+            vec![
+                189807, 27938, 46551, 200761, 22237, 71073, 21000, 46109, 63185, 43718, 21000,
+                21000, 174990, 48537, 65625, 63209, 112082, 54403, 21000
+            ]
+        );
+        assert_eq!(
+            block_receipts.last().unwrap().cumulative_gas_used,
+            header.gas_used
+        );
+
+        // The first log of each transaction, numbered across the whole block.
+        let first_log_indices: Vec<Option<u64>> = block_receipts
+            .iter()
+            .map(|receipt| receipt.logs.first().map(|log| log.log_index.as_u64()))
+            .collect();
+        assert_eq!(
+            first_log_indices,
+            vec![
+                Some(0),
+                Some(7),
+                Some(8),
+                Some(9),
+                None,
+                None,
+                None,
+                Some(18),
+                Some(19),
+                Some(20),
+                None,
+                None,
+                Some(21),
+                Some(24),
+                Some(25),
+                Some(26),
+                Some(27),
+                None,
+                None
+            ]
+        );
+        let log_indices: Vec<u64> = block_receipts
+            .iter()
+            .flat_map(|receipt| receipt.logs.iter().map(|log| log.log_index.as_u64()))
+            .collect();
+        assert_eq!(log_indices, (0..28).collect::<Vec<u64>>());
+        for receipt in block_receipts.iter() {
+            for log in receipt.logs.iter() {
+                assert_eq!(log.transaction_hash, receipt.transaction_hash);
+                assert_eq!(log.transaction_index, receipt.transaction_index);
+            }
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod discv5;
+pub mod eth;
+pub mod ping_extensions;
 pub mod portal;
+pub mod web3;
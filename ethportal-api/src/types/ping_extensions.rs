@@ -0,0 +1,206 @@
+use ethereum_types::U256;
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+
+use crate::types::portal::DataRadius;
+use trin_types::node_id::NodeId;
+
+/// Number of bytes used to encode the data radius at the start of a PING/PONG custom payload.
+const DATA_RADIUS_LENGTH: usize = 32;
+
+/// Maximum number of bytes of client info carried in PING/PONG extensions.
+pub const MAX_CLIENT_INFO_LENGTH: usize = 64;
+
+/// A feature that a node advertises through PING/PONG extensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    HistoryNetwork,
+    StateNetwork,
+    /// Receives each accepted item of an offer over its own uTP stream.
+    ConcurrentOfferStreams,
+}
+
+impl Capability {
+    const ALL: [Capability; 3] = [
+        Capability::HistoryNetwork,
+        Capability::StateNetwork,
+        Capability::ConcurrentOfferStreams,
+    ];
+
+    fn flag(&self) -> u64 {
+        match self {
+            Capability::HistoryNetwork => 1 << 0,
+            Capability::StateNetwork => 1 << 1,
+            Capability::ConcurrentOfferStreams => 1 << 2,
+        }
+    }
+}
+
+/// Optional information appended to the data radius in the custom payload of PING and PONG
+/// messages.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PingExtensions {
+    /// Client name and version, e.g. "trin v0.1.0".
+    pub client_info: String,
+    pub capabilities: Vec<Capability>,
+}
+
+/// Wire representation of `PingExtensions`.
+#[derive(Encode, Decode)]
+struct SszPingExtensions {
+    capabilities: u64,
+    client_info: Vec<u8>,
+}
+
+impl PingExtensions {
+    fn as_ssz_bytes(&self) -> Vec<u8> {
+        let mut client_info = self.client_info.as_bytes().to_vec();
+        client_info.truncate(MAX_CLIENT_INFO_LENGTH);
+        SszPingExtensions {
+            capabilities: self
+                .capabilities
+                .iter()
+                .fold(0, |flags, capability| flags | capability.flag()),
+            client_info,
+        }
+        .as_ssz_bytes()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let extensions = SszPingExtensions::from_ssz_bytes(bytes)?;
+        if extensions.client_info.len() > MAX_CLIENT_INFO_LENGTH {
+            return Err(DecodeError::BytesInvalid(format!(
+                "Client info longer than {MAX_CLIENT_INFO_LENGTH} bytes"
+            )));
+        }
+        let client_info = String::from_utf8(extensions.client_info)
+            .map_err(|err| DecodeError::BytesInvalid(format!("Invalid client info: {err}")))?;
+        // Flags for capabilities unknown to this client are ignored.
+        let capabilities = Capability::ALL
+            .into_iter()
+            .filter(|capability| extensions.capabilities & capability.flag() != 0)
+            .collect();
+        Ok(Self {
+            client_info,
+            capabilities,
+        })
+    }
+}
+
+/// Typed contents of the custom payload of PING and PONG messages.
+///
+/// The payload starts with the 32 byte little-endian data radius. Nodes that advertise
+/// extensions append the SSZ encoding of `PingExtensions` after the radius, so that the radius
+/// remains readable by nodes that are unaware of extensions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PingPayload {
+    pub data_radius: DataRadius,
+    pub extensions: Option<PingExtensions>,
+}
+
+impl PingPayload {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = self.data_radius.as_ssz_bytes();
+        if let Some(extensions) = &self.extensions {
+            bytes.append(&mut extensions.as_ssz_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a custom payload. Payloads of at most 32 bytes carry only a data radius.
+    /// Extensions that fail to decode are dropped rather than failing the whole payload, since
+    /// the data radius is still usable.
+    pub fn decode(bytes: &[u8]) -> Self {
+        let (radius_bytes, extension_bytes) = bytes.split_at(bytes.len().min(DATA_RADIUS_LENGTH));
+        let data_radius = U256::from_little_endian(radius_bytes);
+        let extensions = if extension_bytes.is_empty() {
+            None
+        } else {
+            PingExtensions::from_ssz_bytes(extension_bytes).ok()
+        };
+        Self {
+            data_radius,
+            extensions,
+        }
+    }
+}
+
+/// Response item for PeerCapabilities endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerCapabilitiesInfo {
+    pub node_id: NodeId,
+    pub client_info: String,
+    pub capabilities: Vec<Capability>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn payload_without_extensions_is_only_radius() {
+        let payload = PingPayload {
+            data_radius: U256::MAX / 2,
+            extensions: None,
+        };
+        let bytes = payload.encode();
+        assert_eq!(bytes.len(), DATA_RADIUS_LENGTH);
+        assert_eq!(PingPayload::decode(&bytes), payload);
+    }
+
+    #[test]
+    fn payload_with_extensions_roundtrips() {
+        let payload = PingPayload {
+            data_radius: U256::from(1234),
+            extensions: Some(PingExtensions {
+                client_info: "trin v0.1.0".to_string(),
+                capabilities: vec![Capability::HistoryNetwork, Capability::StateNetwork],
+            }),
+        };
+        let bytes = payload.encode();
+        assert_eq!(PingPayload::decode(&bytes), payload);
+        // The radius can still be read by nodes that are unaware of extensions.
+        assert_eq!(
+            U256::from_little_endian(&bytes[..DATA_RADIUS_LENGTH]),
+            payload.data_radius
+        );
+    }
+
+    #[test]
+    fn unknown_capabilities_are_ignored() {
+        let extensions = SszPingExtensions {
+            capabilities: Capability::HistoryNetwork.flag() | 1 << 63,
+            client_info: b"other".to_vec(),
+        };
+        let mut bytes = U256::MAX.as_ssz_bytes();
+        bytes.append(&mut extensions.as_ssz_bytes());
+
+        let payload = PingPayload::decode(&bytes);
+        assert_eq!(
+            payload.extensions.unwrap().capabilities,
+            vec![Capability::HistoryNetwork]
+        );
+    }
+
+    #[test]
+    fn invalid_extensions_are_dropped() {
+        let mut bytes = U256::MAX.as_ssz_bytes();
+        bytes.extend_from_slice(&[0x01, 0x02, 0x03]);
+
+        let payload = PingPayload::decode(&bytes);
+        assert_eq!(payload.data_radius, U256::MAX);
+        assert_eq!(payload.extensions, None);
+    }
+
+    #[test]
+    fn short_payload_is_radius() {
+        let payload = PingPayload::decode(&[0xff]);
+        assert_eq!(payload.data_radius, U256::from(0xff));
+        assert_eq!(payload.extensions, None);
+    }
+}
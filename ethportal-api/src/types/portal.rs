@@ -3,6 +3,8 @@ use ssz_types::{typenum, BitList};
 use trin_types::content_key::HistoryContentKey;
 use trin_types::content_value::{HistoryContentValue, PossibleHistoryContentValue};
 use trin_types::enr::Enr;
+use trin_types::node_id::NodeId;
+use trin_utils::bytes::hex_encode;
 
 pub type DataRadius = ethereum_types::U256;
 pub type Distance = ethereum_types::U256;
@@ -68,3 +70,234 @@ pub struct PaginateLocalContentInfo {
     pub content_keys: Vec<HistoryContentKey>,
     pub total_entries: u64,
 }
+
+/// Default number of content bytes returned by the ContentChunk endpoint
+pub const DEFAULT_CONTENT_CHUNK_SIZE: u64 = 256 * 1024;
+
+/// Maximum number of content bytes returned by the ContentChunk endpoint
+pub const MAX_CONTENT_CHUNK_SIZE: u64 = 2 * 1024 * 1024;
+
+/// Response for ContentChunk endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentChunkInfo {
+    /// Hex encoded slice of the content value, starting at `offset`. "0x" if the content is
+    /// absent.
+    pub chunk: String,
+    pub offset: u64,
+    pub total_size: u64,
+    /// Offset of the following chunk, `None` if this is the last chunk.
+    pub next_offset: Option<u64>,
+}
+
+impl ContentChunkInfo {
+    /// Slices at most `max_bytes` bytes out of `content`, starting at `offset`.
+    /// Returns `None` if `offset` lies beyond the end of the content.
+    pub fn from_content(content: &[u8], offset: u64, max_bytes: u64) -> Option<Self> {
+        let total_size = content.len() as u64;
+        if offset > total_size {
+            return None;
+        }
+        let end = offset.saturating_add(max_bytes).min(total_size);
+        let next_offset = (end < total_size).then_some(end);
+        Some(Self {
+            chunk: hex_encode(&content[offset as usize..end as usize]),
+            offset,
+            total_size,
+            next_offset,
+        })
+    }
+}
+
+/// Response for Summary endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryInfo {
+    pub storage: StorageSummaryInfo,
+    pub messages: MessageSummaryInfo,
+}
+
+/// Part of a Summary response, describing local storage utilization
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageSummaryInfo {
+    pub data_radius: DataRadius,
+    pub radius_percent: f64,
+    pub content_storage_usage_kb: f64,
+    pub total_storage_usage_kb: f64,
+    pub storage_capacity_kb: f64,
+    pub entry_count: u64,
+}
+
+/// Response for NetworkEstimate endpoint, estimating the health of the network from the data
+/// radii advertised by routing table peers
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkEstimateInfo {
+    /// Number of routing table peers whose radius is known.
+    pub peer_count: u64,
+    pub mean_radius_percent: f64,
+    pub median_radius_percent: f64,
+    /// Estimated number of nodes in the network, from the distances to the closest peers.
+    pub estimated_network_size: Option<f64>,
+    /// Estimated number of nodes storing any given piece of content.
+    pub estimated_replication: Option<f64>,
+    /// Estimated size of all distinct content in the network, from the local content density.
+    pub estimated_content_kb: Option<f64>,
+    /// Estimated size of all content stored in the network, including replicas.
+    pub estimated_network_storage_kb: Option<f64>,
+}
+
+/// Default number of the closest nodes asked for the content by the Replication endpoint
+pub const DEFAULT_REPLICATION_NODES: u64 = 8;
+
+/// Maximum number of the closest nodes asked for the content by the Replication endpoint
+pub const MAX_REPLICATION_NODES: u64 = 16;
+
+/// Response for Replication endpoint, measuring how many of the nodes closest to a content id
+/// store the content
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationInfo {
+    /// Number of nodes that were asked for the content
+    pub queried: u64,
+    /// Number of the asked nodes that returned the content
+    pub holders: u64,
+    pub nodes: Vec<ReplicaInfo>,
+}
+
+/// Part of a Replication response
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicaInfo {
+    pub enr: Enr,
+    /// Log2 distance between the node and the content id, 0 if they are equal
+    pub log2_distance: u16,
+    /// Whether the node returned the content, `None` if the request failed.
+    pub has_content: Option<bool>,
+}
+
+/// Response for CompactStorage endpoint
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionInfo {
+    /// Disk space returned to the file system by the compaction.
+    pub reclaimed_kb: f64,
+    /// Disk space used by the node after the compaction.
+    pub total_storage_usage_kb: f64,
+}
+
+/// Part of a Summary response, counting overlay messages by direction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSummaryInfo {
+    pub sent: MessageCountInfo,
+    pub received: MessageCountInfo,
+}
+
+/// Number of overlay messages of each type
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageCountInfo {
+    pub ping: u64,
+    pub pong: u64,
+    pub find_nodes: u64,
+    pub nodes: u64,
+    pub find_content: u64,
+    pub content: u64,
+    pub offer: u64,
+    pub accept: u64,
+}
+
+/// Direction of a uTP transfer, from the point of view of the local node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Inbound,
+    Outbound,
+}
+
+/// Response item for UtpTransfers endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtpTransferInfo {
+    pub peer: NodeId,
+    pub direction: TransferDirection,
+    pub subnetwork: String,
+    /// Hex-encoded content keys of the content being transferred
+    pub content_keys: Vec<String>,
+    /// Size of the payload once it is known. Inbound payloads are read in one piece, so their
+    /// size is zero until the transfer completes.
+    pub bytes: u64,
+    /// Time since the transfer was set up, including the time waiting for the connection
+    pub age_ms: u64,
+}
+
+/// Response item for PeerStats endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerStatsInfo {
+    pub node_id: NodeId,
+    /// Number of requests from the peer that the local node answered
+    pub requests_served: u64,
+    /// Number of requests to the peer that failed or timed out
+    pub request_failures: u64,
+    pub utp_bytes_sent: u64,
+    pub utp_bytes_received: u64,
+    /// Number of offered content items the peer accepted
+    pub offers_accepted: u64,
+    /// Number of offered content items the peer declined
+    pub offers_declined: u64,
+}
+
+/// Response for LifetimeStats endpoint, counting the node's activity across restarts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LifetimeStatsInfo {
+    pub uptime_secs: u64,
+    /// Number of content requests the node answered with content
+    pub content_served: u64,
+    /// Content bytes sent to peers over uTP
+    pub bytes_uploaded: u64,
+    /// Content bytes received from peers over uTP
+    pub bytes_downloaded: u64,
+    /// Number of content items the node offered that peers accepted
+    pub offers_accepted: u64,
+}
+
+impl LifetimeStatsInfo {
+    pub fn add(&self, other: &Self) -> Self {
+        Self {
+            uptime_secs: self.uptime_secs.saturating_add(other.uptime_secs),
+            content_served: self.content_served.saturating_add(other.content_served),
+            bytes_uploaded: self.bytes_uploaded.saturating_add(other.bytes_uploaded),
+            bytes_downloaded: self.bytes_downloaded.saturating_add(other.bytes_downloaded),
+            offers_accepted: self.offers_accepted.saturating_add(other.offers_accepted),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn content_chunks_cover_whole_value() {
+        let content: Vec<u8> = (0..10).collect();
+        let first = ContentChunkInfo::from_content(&content, 0, 4).unwrap();
+        assert_eq!(first.chunk, "0x00010203");
+        assert_eq!(first.total_size, 10);
+        assert_eq!(first.next_offset, Some(4));
+
+        let last = ContentChunkInfo::from_content(&content, 8, 4).unwrap();
+        assert_eq!(last.chunk, "0x0809");
+        assert_eq!(last.next_offset, None);
+
+        let empty = ContentChunkInfo::from_content(&content, 10, 4).unwrap();
+        assert_eq!(empty.chunk, "0x");
+        assert_eq!(empty.next_offset, None);
+
+        assert!(ContentChunkInfo::from_content(&content, 11, 4).is_none());
+    }
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Response for BuildInfo endpoint
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildInfo {
+    /// The same version string as returned by web3_clientVersion.
+    pub client_version: String,
+    /// Semantic version of trin.
+    pub version: String,
+    /// Git commit that trin was built from, empty if unknown.
+    pub git_commit: String,
+    /// UTC date that trin was built on.
+    pub build_date: String,
+    /// Target triple that trin was built for.
+    pub target: String,
+    /// Cargo features that trin was built with.
+    pub features: Vec<String>,
+    /// Subnetworks that the node is running.
+    pub networks: Vec<String>,
+}
@@ -1,8 +1,16 @@
+use crate::types::web3::BuildInfo;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 
 /// Web3 JSON-RPC endpoints
-#[rpc(client, server, namespace = "web3")]
+#[cfg_attr(feature = "native", rpc(client, server, namespace = "web3"))]
+#[cfg_attr(not(feature = "native"), rpc(client, namespace = "web3"))]
 pub trait Web3Api {
+    /// Returns the client name and version, e.g. `trin v0.1.0-abcdef`.
     #[method(name = "clientVersion")]
     async fn client_version(&self) -> RpcResult<String>;
+
+    /// Returns the version, git commit, build date, target and features that the node was built
+    /// with, along with the subnetworks that it runs.
+    #[method(name = "buildInfo")]
+    async fn build_info(&self) -> RpcResult<BuildInfo>;
 }
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use structopt::StructOpt;
+
+use ethportal_peertest::interop::{run_interop, ExternalClient, LocalNode};
+use trin_types::enr::Enr;
+use trin_utils::log::init_tracing_logger;
+
+/// Run interop scenarios between a local trin node and an externally run Portal client, and
+/// print a compatibility report.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "interop")]
+struct InteropConfig {
+    /// Name of the external client in the report, e.g. fluffy or ultralight.
+    #[structopt(long)]
+    client_name: String,
+
+    /// ENR of the external client.
+    #[structopt(long)]
+    client_enr: String,
+
+    /// URL of the external client's HTTP JSON-RPC endpoint.
+    #[structopt(long)]
+    client_rpc_url: String,
+
+    /// Address advertised by the local trin node, which must be reachable by the external client.
+    #[structopt(long, default_value = "127.0.0.1:9900")]
+    external_address: String,
+
+    /// Discovery port of the local trin node.
+    #[structopt(long, default_value = "9900")]
+    discovery_port: u16,
+
+    /// IPC path of the local trin node's JSON-RPC server.
+    #[structopt(long, default_value = "/tmp/ethportal-peertest-interop.ipc")]
+    web3_ipc_path: String,
+
+    /// Also write the report as JSON to this file.
+    #[structopt(long, parse(from_os_str))]
+    report: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_tracing_logger(None);
+    let config = InteropConfig::from_args();
+    let enr = Enr::from_str(&config.client_enr)
+        .map_err(|err| anyhow::anyhow!("Invalid client ENR: {err}"))?;
+
+    let report = run_interop(
+        LocalNode {
+            external_address: config.external_address,
+            discovery_port: config.discovery_port,
+            web3_ipc_path: config.web3_ipc_path,
+        },
+        ExternalClient {
+            name: config.client_name,
+            enr,
+            rpc_url: config.client_rpc_url,
+        },
+    )
+    .await?;
+
+    report.print();
+    if let Some(path) = config.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report.to_json())?)?;
+    }
+    if !report.passed() {
+        anyhow::bail!("Interop checks failed against {}", report.client);
+    }
+    Ok(())
+}
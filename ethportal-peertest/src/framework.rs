@@ -0,0 +1,159 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{self, BoxFuture, FutureExt};
+use tracing::{error, info};
+
+use ethportal_api::{HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient};
+
+use crate::{launch_peertest_nodes_from, Peertest, PeertestNode};
+
+/// Default time a scenario's assertions may take, once its nodes are launched and preloaded.
+pub const DEFAULT_SCENARIO_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of peertest ids reserved for each scenario run by `run_scenarios`.
+const SCENARIO_ID_STRIDE: u16 = 10;
+
+/// First peertest id handed out by `run_scenarios`, chosen to stay clear of the ids used by
+/// `launch_peertest_nodes`.
+const FIRST_SCENARIO_ID: u16 = 10;
+
+type ScenarioFn = Box<dyn Fn(Arc<Peertest>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Content stored on a node before a scenario's assertions run.
+struct Preload {
+    node: usize,
+    content_key: HistoryContentKey,
+    content_value: HistoryContentValue,
+}
+
+/// An integration test running against its own network of peertest nodes.
+///
+/// The assertions are an async closure over the launched `Peertest`. They fail the scenario by
+/// panicking, like the other peertest scenarios.
+pub struct Scenario {
+    name: &'static str,
+    node_count: u16,
+    preload: Vec<Preload>,
+    timeout: Duration,
+    run: ScenarioFn,
+}
+
+impl Scenario {
+    pub fn new<F, Fut>(name: &'static str, run: F) -> Self
+    where
+        F: Fn(Arc<Peertest>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        Self {
+            name,
+            node_count: 2,
+            preload: vec![],
+            timeout: DEFAULT_SCENARIO_TIMEOUT,
+            run: Box::new(move |peertest| run(peertest).boxed()),
+        }
+    }
+
+    /// Sets the number of nodes in the network, including the bootnode. Defaults to 2.
+    pub fn nodes(mut self, node_count: u16) -> Self {
+        assert!(
+            (1..SCENARIO_ID_STRIDE).contains(&node_count),
+            "A scenario runs between 1 and {} nodes",
+            SCENARIO_ID_STRIDE - 1
+        );
+        self.node_count = node_count;
+        self
+    }
+
+    /// Stores content on a node before the scenario runs. Node 0 is the bootnode, and node `i`
+    /// is `Peertest::nodes[i - 1]`.
+    pub fn preload(
+        mut self,
+        node: usize,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> Self {
+        self.preload.push(Preload {
+            node,
+            content_key,
+            content_value,
+        });
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn execute(self, first_id: u16) -> Result<(), String> {
+        let peertest = Arc::new(launch_peertest_nodes_from(first_id, self.node_count).await);
+
+        for preload in self.preload.iter() {
+            let node = peertest_node(&peertest, preload.node)
+                .ok_or_else(|| format!("No node {} to preload content on", preload.node))?;
+            let stored = node
+                .ipc_client
+                .store(
+                    preload.content_key.clone(),
+                    preload.content_value.clone().into(),
+                )
+                .await
+                .map_err(|err| format!("Unable to preload content: {err}"))?;
+            if !stored {
+                return Err(format!(
+                    "Node {} did not store preloaded content",
+                    preload.node
+                ));
+            }
+        }
+
+        let mut handle = tokio::spawn((self.run)(Arc::clone(&peertest)));
+        let result = match tokio::time::timeout(self.timeout, &mut handle).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => Err(format!("Assertions failed: {err}")),
+            Err(_) => {
+                handle.abort();
+                Err(format!("Timed out after {:?}", self.timeout))
+            }
+        };
+
+        peertest.exit_all_nodes();
+        result
+    }
+}
+
+fn peertest_node(peertest: &Peertest, node: usize) -> Option<&PeertestNode> {
+    match node {
+        0 => Some(&peertest.bootnode),
+        i => peertest.nodes.get(i - 1),
+    }
+}
+
+/// Runs scenarios in parallel, each against its own network, and fails if any scenario failed.
+pub async fn run_scenarios(scenarios: Vec<Scenario>) -> anyhow::Result<()> {
+    let runs = scenarios.into_iter().enumerate().map(|(i, scenario)| {
+        let name = scenario.name;
+        let first_id = FIRST_SCENARIO_ID + i as u16 * SCENARIO_ID_STRIDE;
+        async move {
+            info!(scenario = name, "Running peertest scenario");
+            let result = scenario.execute(first_id).await;
+            match &result {
+                Ok(()) => info!(scenario = name, "Peertest scenario passed"),
+                Err(err) => error!(scenario = name, %err, "Peertest scenario failed"),
+            }
+            (name, result)
+        }
+    });
+
+    let failures: Vec<String> = future::join_all(runs)
+        .await
+        .into_iter()
+        .filter_map(|(name, result)| result.err().map(|err| format!("{name}: {err}")))
+        .collect();
+    if !failures.is_empty() {
+        anyhow::bail!("Peertest scenarios failed:\n{}", failures.join("\n"));
+    }
+    Ok(())
+}
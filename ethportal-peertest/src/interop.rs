@@ -0,0 +1,405 @@
+//! Interop scenarios against an externally run Portal client, e.g. fluffy or ultralight.
+//!
+//! A local trin node is launched with the external client as its bootnode. The scenarios then
+//! exchange offers, perform lookups in both directions and compare the byte-level encoding of
+//! the content that each client returns, recording the outcome of every check in a report.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tracing::info;
+
+use ethportal_api::types::portal::ContentInfo;
+use ethportal_api::{
+    Discv5ApiClient, HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient,
+};
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use trin_types::cli::TrinConfig;
+use trin_types::content_value::{ContentValue, PossibleHistoryContentValue};
+use trin_types::enr::Enr;
+use trin_utils::bytes::{hex_decode, hex_encode, hex_encode_compact};
+
+use crate::constants::{HISTORY_CONTENT_KEY, HISTORY_CONTENT_VALUE};
+use crate::{launch_node, PeertestNode};
+
+/// Content vectors shared with the content vector tests of trin-types, used as the content
+/// exchanged with the external client.
+const CONTENT_VECTORS: &str =
+    include_str!("../../trin-types/src/assets/test/content_vectors/history/content_values.json");
+
+/// How long to wait for offered content to be validated and stored by the receiving client.
+const OFFER_STORE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An externally run Portal client to test against.
+pub struct ExternalClient {
+    /// Name of the client in the report, e.g. "fluffy".
+    pub name: String,
+    pub enr: Enr,
+    /// URL of the client's HTTP JSON-RPC endpoint.
+    pub rpc_url: String,
+}
+
+/// The local trin node taking part in the interop scenarios.
+pub struct LocalNode {
+    /// Address the node advertises in its ENR, which must be reachable by the external client.
+    pub external_address: String,
+    pub discovery_port: u16,
+    pub web3_ipc_path: String,
+}
+
+/// Outcome of a single interop check.
+pub struct InteropCheck {
+    pub name: String,
+    pub result: Result<String, String>,
+}
+
+/// Compatibility report of an interop run against one external client.
+pub struct InteropReport {
+    pub client: String,
+    pub checks: Vec<InteropCheck>,
+}
+
+impl InteropReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.result.is_ok())
+    }
+
+    pub fn print(&self) {
+        println!("Interop report: trin <-> {}", self.client);
+        for check in self.checks.iter() {
+            match &check.result {
+                Ok(detail) => println!("PASS {}: {detail}", check.name),
+                Err(err) => println!("FAIL {}: {err}", check.name),
+            }
+        }
+        let passed = self
+            .checks
+            .iter()
+            .filter(|check| check.result.is_ok())
+            .count();
+        println!("{passed}/{} checks passed", self.checks.len());
+    }
+
+    pub fn to_json(&self) -> Value {
+        let checks: Vec<Value> = self
+            .checks
+            .iter()
+            .map(|check| match &check.result {
+                Ok(detail) => json!({"name": check.name, "passed": true, "detail": detail}),
+                Err(err) => json!({"name": check.name, "passed": false, "detail": err}),
+            })
+            .collect();
+        json!({
+            "client": self.client,
+            "passed": self.passed(),
+            "checks": checks,
+        })
+    }
+}
+
+/// A content key and value pair exchanged between the clients.
+struct Content {
+    name: String,
+    key: HistoryContentKey,
+    value: HistoryContentValue,
+    /// The canonical encoding of the value, which both clients must return.
+    bytes: Vec<u8>,
+}
+
+fn content(name: &str, key: &str, value: &str) -> Result<Content, String> {
+    let key: HistoryContentKey = serde_json::from_value(json!(key))
+        .map_err(|err| format!("Invalid vector {name}: {err}"))?;
+    let bytes = hex_decode(value).map_err(|err| format!("Invalid vector {name}: {err}"))?;
+    let value = HistoryContentValue::decode_with_key(&key, &bytes)
+        .map_err(|err| format!("Invalid vector {name}: {err:?}"))?;
+    Ok(Content {
+        name: name.to_string(),
+        key,
+        value,
+        bytes,
+    })
+}
+
+fn content_vectors() -> Result<Vec<Content>, String> {
+    let vectors: Vec<Value> = serde_json::from_str(CONTENT_VECTORS)
+        .map_err(|err| format!("Invalid content vectors: {err}"))?;
+    vectors
+        .iter()
+        .map(|vector| {
+            let field = |name: &str| vector[name].as_str().unwrap_or_default().to_string();
+            content(
+                &field("name"),
+                &field("content_key"),
+                &field("content_value"),
+            )
+        })
+        .collect()
+}
+
+/// Launches a local trin node bootstrapped from the external client, and runs the interop
+/// scenarios between them.
+pub async fn run_interop(
+    local: LocalNode,
+    external: ExternalClient,
+) -> anyhow::Result<InteropReport> {
+    let external_address = local.external_address.clone();
+    let discovery_port = local.discovery_port.to_string();
+    let bootnode = external.enr.to_base64();
+    let trin_config = TrinConfig::new_from(
+        [
+            "trin",
+            "--networks",
+            "history",
+            "--external-address",
+            external_address.as_str(),
+            "--bootnodes",
+            bootnode.as_str(),
+            "--discovery-port",
+            discovery_port.as_str(),
+            "--web3-ipc-path",
+            local.web3_ipc_path.as_str(),
+            "--ephemeral",
+        ]
+        .iter(),
+    )?;
+    let trin = launch_node(trin_config).await?;
+    let client = HttpClientBuilder::default().build(&external.rpc_url)?;
+
+    let report = run_checks(&trin, &client, &external).await;
+    trin.rpc_handle.stop()?;
+    Ok(report)
+}
+
+async fn run_checks(
+    trin: &PeertestNode,
+    client: &HttpClient,
+    external: &ExternalClient,
+) -> InteropReport {
+    let name = &external.name;
+    let mut report = InteropReport {
+        client: name.clone(),
+        checks: vec![],
+    };
+    let mut check = |check_name: String, result: Result<String, String>| {
+        info!(check = %check_name, passed = result.is_ok(), "Interop check");
+        report.checks.push(InteropCheck {
+            name: check_name,
+            result,
+        });
+    };
+
+    let vectors = match content_vectors() {
+        Ok(vectors) => vectors,
+        Err(err) => {
+            check("load content vectors".to_string(), Err(err));
+            return report;
+        }
+    };
+    let find = |name: &str| vectors.iter().find(|content| content.name == name);
+    let (Some(header), Some(body), Some(receipts)) = (
+        find("block_header_1000001"),
+        find("block_body_14764013"),
+        find("receipts_14764013"),
+    ) else {
+        check(
+            "load content vectors".to_string(),
+            Err("Missing header, body or receipts vector".to_string()),
+        );
+        return report;
+    };
+    let trin_only = match content(
+        "block_header_1000010",
+        HISTORY_CONTENT_KEY,
+        HISTORY_CONTENT_VALUE,
+    ) {
+        Ok(content) => content,
+        Err(err) => {
+            check("load content vectors".to_string(), Err(err));
+            return report;
+        }
+    };
+
+    let client_enr = match client.node_info().await {
+        Ok(node_info) if node_info.enr.node_id() == external.enr.node_id() => Ok(node_info.enr),
+        Ok(node_info) => Err(format!(
+            "Client reports ENR {}, expected {}",
+            node_info.enr, external.enr
+        )),
+        Err(err) => Err(format!("Unable to reach {}: {err}", external.rpc_url)),
+    };
+    let client_enr = match client_enr {
+        Ok(enr) => {
+            check(format!("{name} node info"), Ok(enr.to_string()));
+            enr
+        }
+        Err(err) => {
+            check(format!("{name} node info"), Err(err));
+            return report;
+        }
+    };
+
+    check(
+        format!("ping trin -> {name}"),
+        trin.ipc_client
+            .ping(client_enr.clone(), None)
+            .await
+            .map(|pong| format!("radius {}", pong.data_radius))
+            .map_err(|err| err.to_string()),
+    );
+    check(
+        format!("ping {name} -> trin"),
+        client
+            .ping(trin.enr.clone(), None)
+            .await
+            .map(|pong| format!("radius {}", pong.data_radius))
+            .map_err(|err| err.to_string()),
+    );
+
+    check(
+        format!("offer trin -> {name}"),
+        offer(&trin.ipc_client, client, &client_enr, header).await,
+    );
+    check(
+        format!("offer {name} -> trin"),
+        offer(client, &trin.ipc_client, &trin.enr, body).await,
+    );
+
+    // Each client now holds the content offered to it, and can serve it back to the other.
+    check(
+        format!("find content trin -> {name}"),
+        find_content(&trin.ipc_client, &client_enr, header).await,
+    );
+    check(
+        format!("find content {name} -> trin"),
+        find_content(client, &trin.enr, body).await,
+    );
+
+    check(
+        format!("recursive find content trin -> {name}"),
+        recursive_find_content(client, &trin.ipc_client, receipts).await,
+    );
+    check(
+        format!("recursive find content {name} -> trin"),
+        recursive_find_content(&trin.ipc_client, client, &trin_only).await,
+    );
+
+    for content in vectors.iter() {
+        check(
+            format!("{name} encoding of {}", content.name),
+            round_trip(client, content).await,
+        );
+    }
+    report
+}
+
+/// Checks that the value returned by a client has the canonical encoding of `content`.
+fn compare_encoding(value: &HistoryContentValue, content: &Content) -> Result<String, String> {
+    let bytes = value.encode();
+    if bytes != content.bytes {
+        return Err(format!(
+            "Encoding {} differs from the expected {}",
+            hex_encode_compact(&bytes),
+            hex_encode_compact(&content.bytes)
+        ));
+    }
+    Ok(format!("{} bytes match", bytes.len()))
+}
+
+/// Stores `content` on the client and reads it back.
+async fn round_trip<C: HistoryNetworkApiClient + Sync>(
+    client: &C,
+    content: &Content,
+) -> Result<String, String> {
+    client
+        .store(content.key.clone(), content.value.clone().into())
+        .await
+        .map_err(|err| format!("Unable to store: {err}"))?;
+    match client.local_content(content.key.clone()).await {
+        Ok(PossibleHistoryContentValue::ContentPresent(value)) => compare_encoding(&value, content),
+        Ok(PossibleHistoryContentValue::ContentAbsent) => Err("Stored content is absent".into()),
+        Err(err) => Err(format!("Unable to read stored content: {err}")),
+    }
+}
+
+/// Stores `content` on the sender and offers it to the receiver, which must accept and store it.
+async fn offer<S, R>(
+    sender: &S,
+    receiver: &R,
+    receiver_enr: &Enr,
+    content: &Content,
+) -> Result<String, String>
+where
+    S: HistoryNetworkApiClient + Sync,
+    R: HistoryNetworkApiClient + Sync,
+{
+    sender
+        .store(content.key.clone(), content.value.clone().into())
+        .await
+        .map_err(|err| format!("Unable to store content to offer: {err}"))?;
+    let accept = sender
+        .offer(receiver_enr.clone(), content.key.clone(), None)
+        .await
+        .map_err(|err| format!("Offer failed: {err}"))?;
+    if !matches!(accept.content_keys.get(0), Ok(true)) {
+        return Err(format!(
+            "Content not accepted: {}",
+            hex_encode(accept.content_keys.into_bytes())
+        ));
+    }
+
+    let deadline = tokio::time::Instant::now() + OFFER_STORE_TIMEOUT;
+    loop {
+        if let Ok(PossibleHistoryContentValue::ContentPresent(value)) =
+            receiver.local_content(content.key.clone()).await
+        {
+            return compare_encoding(&value, content);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!(
+                "Accepted content was not stored within {OFFER_STORE_TIMEOUT:?}"
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// Requests `content` from the peer with a single FINDCONTENT request.
+async fn find_content<C: HistoryNetworkApiClient + Sync>(
+    client: &C,
+    peer: &Enr,
+    content: &Content,
+) -> Result<String, String> {
+    match client.find_content(peer.clone(), content.key.clone()).await {
+        Ok(ContentInfo::Content { content: value }) => compare_encoding(&value, content),
+        Ok(ContentInfo::ConnectionId { connection_id }) => Err(format!(
+            "Content of {} was sent over uTP connection {connection_id}, expected it inline",
+            content.name
+        )),
+        Ok(ContentInfo::Enrs { enrs }) => Err(format!("Peer returned {} ENRs", enrs.len())),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Stores `content` on `holder` only, and looks it up on the network from `seeker`.
+async fn recursive_find_content<H, S>(
+    holder: &H,
+    seeker: &S,
+    content: &Content,
+) -> Result<String, String>
+where
+    H: HistoryNetworkApiClient + Sync,
+    S: HistoryNetworkApiClient + Sync,
+{
+    holder
+        .store(content.key.clone(), content.value.clone().into())
+        .await
+        .map_err(|err| format!("Unable to store content to look up: {err}"))?;
+    match seeker
+        .recursive_find_content(content.key.clone(), Some(true))
+        .await
+    {
+        Ok(PossibleHistoryContentValue::ContentPresent(value)) => compare_encoding(&value, content),
+        Ok(PossibleHistoryContentValue::ContentAbsent) => Err("Content not found".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -1,7 +1,12 @@
-use crate::constants::{HISTORY_CONTENT_KEY, HISTORY_CONTENT_VALUE};
+use crate::constants::{
+    HISTORY_CONTENT_KEY, HISTORY_CONTENT_VALUE, RAW_TRANSACTION, RAW_TRANSACTION_HASH,
+};
 use crate::Peertest;
 use ethereum_types::U256;
-use ethportal_api::{Discv5ApiClient, HistoryNetworkApiClient, Web3ApiClient};
+use ethportal_api::{
+    AdminApiClient, DebugApiClient, Discv5ApiClient, EthApiClient, HistoryNetworkApiClient,
+    Web3ApiClient,
+};
 use jsonrpsee::async_client::Client;
 use serde_json::json;
 use ssz::Encode;
@@ -9,7 +14,8 @@ use tracing::info;
 use trin_types::content_key::HistoryContentKey;
 use trin_types::content_value::{HistoryContentValue, PossibleHistoryContentValue};
 use trin_types::distance::Distance;
-use trin_utils::version::get_trin_version;
+use trin_utils::bytes::hex_encode;
+use trin_utils::version::{get_trin_version, TRIN_VERSION};
 
 pub async fn test_web3_client_version(target: &Client) {
     info!("Testing web3_clientVersion");
@@ -18,6 +24,55 @@ pub async fn test_web3_client_version(target: &Client) {
     assert_eq!(result, expected_version);
 }
 
+pub async fn test_web3_build_info(target: &Client) {
+    info!("Testing web3_buildInfo");
+    let result = target.build_info().await.unwrap();
+    assert_eq!(
+        result.client_version,
+        format!("trin v{}", get_trin_version())
+    );
+    assert_eq!(result.version, TRIN_VERSION);
+    assert!(result.features.contains(&"history".to_string()));
+    assert_eq!(
+        result.networks,
+        vec!["history".to_string(), "state".to_string()]
+    );
+}
+
+pub async fn test_eth_send_raw_transaction(target: &Client) {
+    info!("Testing eth_sendRawTransaction");
+    // The mock trusted provider only accepts the unmodified transaction.
+    let result = target
+        .send_raw_transaction(RAW_TRANSACTION.to_string())
+        .await
+        .unwrap();
+    assert_eq!(hex_encode(result), RAW_TRANSACTION_HASH);
+
+    // Transactions that can't be decoded are rejected before reaching the trusted provider.
+    target
+        .send_raw_transaction("0x02deadbeef".to_string())
+        .await
+        .unwrap_err();
+}
+
+pub async fn test_eth_gas_price(target: &Client) {
+    info!("Testing eth_gasPrice and eth_maxPriorityFeePerGas");
+    // Fees are sampled from the blocks verified by the light client. The peertest nodes run
+    // without --consensus-rpc, so both endpoints report that there is no chain head.
+    let err = target.max_priority_fee_per_gas().await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("No chain head has been verified by the light client"),
+        "unexpected error: {err}"
+    );
+    let err = target.gas_price().await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("No chain head has been verified by the light client"),
+        "unexpected error: {err}"
+    );
+}
+
 pub async fn test_discv5_node_info(peertest: &Peertest) {
     info!("Testing discv5_nodeInfo");
     let result = peertest.bootnode.ipc_client.node_info().await.unwrap();
@@ -42,6 +97,18 @@ pub async fn test_history_radius(target: &Client) {
     );
 }
 
+pub async fn test_history_summary(target: &Client) {
+    info!("Testing portal_historySummary");
+    let result = target.summary().await.unwrap();
+    assert_eq!(
+        result.storage.data_radius,
+        U256::from_big_endian(Distance::MAX.as_ssz_bytes().as_slice())
+    );
+    // The target has already pinged and sent find nodes requests to the bootnode
+    assert!(result.messages.sent.ping > 0);
+    assert!(result.messages.sent.find_nodes > 0);
+}
+
 pub async fn test_history_ping(target: &Client, peertest: &Peertest) {
     info!("Testing portal_historyPing");
     let result = target
@@ -86,10 +153,28 @@ pub async fn test_history_store(target: &Client) {
     let content_value: HistoryContentValue =
         serde_json::from_value(json!(HISTORY_CONTENT_VALUE)).unwrap();
 
-    let result = target.store(content_key, content_value).await.unwrap();
+    let result = target
+        .store(content_key, content_value.into())
+        .await
+        .unwrap();
     assert!(result);
 }
 
+pub async fn test_debug_get_raw_header(target: &Client) {
+    info!("Testing debug_getRawHeader");
+
+    let content_value: HistoryContentValue =
+        serde_json::from_value(json!(HISTORY_CONTENT_VALUE)).unwrap();
+    let header = match content_value {
+        HistoryContentValue::BlockHeaderWithProof(header_with_proof) => header_with_proof.header,
+        _ => panic!("Expected a header with proof"),
+    };
+
+    // The header was stored in the target's local storage by `test_history_store`.
+    let result = target.get_raw_header(header.hash()).await.unwrap();
+    assert_eq!(result, hex_encode(rlp::encode(&header)));
+}
+
 pub async fn test_history_routing_table_info(target: &Client) {
     info!("Testing portal_historyRoutingTableInfo");
     let result = HistoryNetworkApiClient::routing_table_info(target)
@@ -99,6 +184,12 @@ pub async fn test_history_routing_table_info(target: &Client) {
     assert!(result.get("numBuckets").unwrap().is_u64());
     assert!(result.get("numNodes").unwrap().is_u64());
     assert!(result.get("numConnected").unwrap().is_u64());
+    for bucket in result["buckets"].as_object().unwrap().values() {
+        for node in bucket.as_array().unwrap() {
+            assert!(node.get("status").is_some());
+            assert!(node.get("direction").is_some());
+        }
+    }
 }
 
 pub async fn test_history_local_content_absent(target: &Client) {
@@ -114,3 +205,25 @@ pub async fn test_history_local_content_absent(target: &Client) {
         panic!("Expected absent content");
     };
 }
+
+pub async fn test_admin_peer_stats(target: &Client, peertest: &Peertest) {
+    info!("Testing admin_peerStats");
+    let result = target.peer_stats().await.unwrap();
+    let bootnode_stats = result
+        .iter()
+        .find(|stats| stats.node_id.0 == peertest.bootnode.enr.node_id().raw())
+        .unwrap();
+    // The target has already offered content to the bootnode, which accepted it
+    assert!(bootnode_stats.offers_accepted > 0);
+    assert!(bootnode_stats.utp_bytes_sent > 0);
+}
+
+pub async fn test_admin_lifetime_stats(target: &Client) {
+    info!("Testing admin_lifetimeStats");
+    let result = target.lifetime_stats().await.unwrap();
+    // The lifetime totals include the activity of the current run
+    let peer_stats = target.peer_stats().await.unwrap();
+    let offers_accepted: u64 = peer_stats.iter().map(|stats| stats.offers_accepted).sum();
+    assert!(result.offers_accepted >= offers_accepted);
+    assert!(result.bytes_uploaded > 0);
+}
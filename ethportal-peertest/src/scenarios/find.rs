@@ -21,7 +21,7 @@ pub async fn test_trace_recursive_find_content(peertest: &Peertest) {
     let store_result = peertest
         .bootnode
         .ipc_client
-        .store(content_key.clone(), content_value.clone())
+        .store(content_key.clone(), content_value.clone().into())
         .await
         .unwrap();
     assert!(store_result);
@@ -80,7 +80,7 @@ pub async fn test_trace_recursive_find_content_local_db(peertest: &Peertest) {
     let store_result = peertest
         .bootnode
         .ipc_client
-        .store(content_key.clone(), content_value.clone())
+        .store(content_key.clone(), content_value.clone().into())
         .await
         .unwrap();
     assert!(store_result);
@@ -0,0 +1,131 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::future;
+use ssz::Decode;
+use tracing::info;
+use tree_hash::TreeHash;
+
+use ethportal_api::{
+    BlockReceiptsKey, EpochAccumulatorKey, HistoryContentKey, HistoryContentValue,
+    HistoryNetworkApiClient,
+};
+use trin_types::content_value::PossibleHistoryContentValue;
+use trin_types::execution::{accumulator::EpochAccumulator, receipts::Receipts};
+use trin_utils::bytes::hex_decode;
+
+use crate::{framework::Scenario, Peertest};
+
+/// Time allowed for offering, storing and re-serving multi-megabyte content.
+const LARGE_CONTENT_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// Hash of block #14764013, whose header is served by the mock trusted provider.
+const RECEIPTS_BLOCK_HASH: &str =
+    "0x720704f3aa11c53cf344ea069db95cecb81ad7453c8f276b2a1062979611f09c";
+
+/// Offers every full epoch accumulator in the test assets (~512KB each) to the bootnode at once,
+/// then retrieves them from the bootnode with a lookup from another node.
+pub fn offer_epoch_accumulators() -> Scenario {
+    Scenario::new(
+        "offer and re-serve epoch accumulators",
+        |peertest| async move { offer_and_reserve(&peertest, epoch_accumulators()).await },
+    )
+    .nodes(3)
+    .timeout(LARGE_CONTENT_TIMEOUT)
+}
+
+/// Offers the receipts of block #14764013 to the bootnode, which validates them against the
+/// header from the trusted provider, then retrieves them with a lookup from another node.
+pub fn offer_receipts() -> Scenario {
+    Scenario::new("offer and re-serve receipts", |peertest| async move {
+        offer_and_reserve(&peertest, vec![receipts()]).await
+    })
+    .nodes(3)
+    .timeout(LARGE_CONTENT_TIMEOUT)
+}
+
+/// Offers all `content` concurrently from the first node to the bootnode, waits until the
+/// bootnode has validated and stored it, then looks it up from the second node.
+async fn offer_and_reserve(
+    peertest: &Peertest,
+    content: Vec<(HistoryContentKey, HistoryContentValue)>,
+) {
+    let offers = content.iter().map(|(content_key, content_value)| {
+        info!(content.key = %content_key, "Offering large content");
+        peertest.nodes[0].ipc_client.offer(
+            peertest.bootnode.enr.clone(),
+            content_key.clone(),
+            Some(content_value.clone().into()),
+        )
+    });
+    for accept in future::join_all(offers).await {
+        accept.unwrap();
+    }
+
+    for (content_key, content_value) in content.iter() {
+        // The offered content is transferred to the bootnode in the background.
+        loop {
+            let result = peertest
+                .bootnode
+                .ipc_client
+                .local_content(content_key.clone())
+                .await;
+            if let Ok(PossibleHistoryContentValue::ContentPresent(value)) = result {
+                assert_eq!(&value, content_value, "Bootnode stored corrupted content");
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    for (content_key, content_value) in content {
+        let result = peertest.nodes[1]
+            .ipc_client
+            .recursive_find_content(content_key, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            PossibleHistoryContentValue::ContentPresent(content_value)
+        );
+    }
+}
+
+fn epoch_accumulators() -> Vec<(HistoryContentKey, HistoryContentValue)> {
+    let assets =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../trin-validation/src/assets/epoch_accs");
+    let mut content: Vec<_> = fs::read_dir(assets)
+        .unwrap()
+        .map(|entry| {
+            let epoch_acc = fs::read(entry.unwrap().path()).unwrap();
+            let epoch_acc = EpochAccumulator::from_ssz_bytes(&epoch_acc).unwrap();
+            let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+                epoch_hash: epoch_acc.tree_hash_root(),
+            });
+            (
+                content_key,
+                HistoryContentValue::EpochAccumulator(epoch_acc),
+            )
+        })
+        .collect();
+    assert!(
+        !content.is_empty(),
+        "No epoch accumulator test assets found"
+    );
+    content.sort_by_key(|(content_key, _)| content_key.to_string());
+    content
+}
+
+fn receipts() -> (HistoryContentKey, HistoryContentValue) {
+    let receipts = fs::read(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../trin-types/src/assets/trin/receipts_14764013.bin"),
+    )
+    .unwrap();
+    let receipts = Receipts::from_ssz_bytes(&receipts).unwrap();
+    let mut block_hash = [0u8; 32];
+    block_hash.copy_from_slice(&hex_decode(RECEIPTS_BLOCK_HASH).unwrap());
+    let content_key = HistoryContentKey::BlockReceipts(BlockReceiptsKey { block_hash });
+    (content_key, HistoryContentValue::Receipts(receipts))
+}
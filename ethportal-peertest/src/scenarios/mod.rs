@@ -1,4 +1,5 @@
 pub mod basic;
 pub mod find;
+pub mod large_content;
 pub mod offer_accept;
 pub mod paginate;
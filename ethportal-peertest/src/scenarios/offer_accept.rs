@@ -25,7 +25,7 @@ pub async fn test_unpopulated_offer(peertest: &Peertest, target: &Client) {
 
     // Store content to offer in the testnode db
     let store_result = target
-        .store(content_key.clone(), content_value.clone())
+        .store(content_key.clone(), content_value.clone().into())
         .await
         .unwrap();
 
@@ -71,7 +71,7 @@ pub async fn test_populated_offer(peertest: &Peertest, target: &Client) {
         .offer(
             Enr::from_str(&peertest.bootnode.enr.to_base64()).unwrap(),
             content_key.clone(),
-            Some(content_value.clone()),
+            Some(content_value.clone().into()),
         )
         .await
         .unwrap();
@@ -29,7 +29,7 @@ pub async fn test_paginate_local_storage(peertest: &Peertest) {
         let store_result = ipc_client
             .store(
                 serde_json::from_str(&content_key).unwrap(),
-                dummy_content_value,
+                dummy_content_value.into(),
             )
             .await
             .unwrap();
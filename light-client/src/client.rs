@@ -375,6 +375,10 @@ impl<DB: Database> Client<DB> {
         self.node.read().await.get_block_number()
     }
 
+    pub async fn get_block_hash(&self, block: BlockTag) -> Result<Vec<u8>> {
+        self.node.read().await.get_block_hash(block)
+    }
+
     pub async fn chain_id(&self) -> u64 {
         self.node.read().await.chain_id()
     }
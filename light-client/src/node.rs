@@ -159,6 +159,12 @@ impl Node {
         Ok(*payload.block_number())
     }
 
+    /// Returns the hash of a block whose execution payload was verified by the light client.
+    pub fn get_block_hash(&self, block: BlockTag) -> Result<Vec<u8>> {
+        let payload = self.get_payload(block)?;
+        Ok(payload.block_hash().to_vec())
+    }
+
     pub fn chain_id(&self) -> u64 {
         self.config.chain.chain_id
     }
@@ -0,0 +1,569 @@
+//! SQLite database holding the content of a `PortalStorage`.
+//!
+//! Each content item is a single row, with its value next to the content key, size and the
+//! short content id used for distance-ordered queries. Writes and evictions are therefore atomic,
+//! and a data directory is backed up by copying its SQLite files.
+//!
+//! Shards are created with incremental auto-vacuum, so that the pages freed by deleted content
+//! can be returned to the file system without rebuilding the whole shard.
+//!
+//! The database may be sharded across several directories, e.g. on separate disks. Each content
+//! item lives in the shard for the first byte of its content id, so that shards cover contiguous
+//! ranges of content ids.
+
+use std::path::{Path, PathBuf};
+
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+use tracing::info;
+
+use crate::storage::ContentStoreError;
+use trin_types::distance::{Distance, Metric, XorMetric};
+
+/// Name of the SQLite file of a shard.
+pub const CONTENT_DB_FILE: &str = "content.sqlite";
+
+/// Name of the file that records the position of a shard in a sharded database.
+const SHARD_FILE: &str = "SHARD";
+
+const CREATE_QUERY: &str = "PRAGMA auto_vacuum = INCREMENTAL;
+                            CREATE TABLE IF NOT EXISTS content_data (
+                                content_id_long BLOB PRIMARY KEY,
+                                content_id_short INTEGER NOT NULL,
+                                content_key TEXT NOT NULL,
+                                content_size INTEGER NOT NULL,
+                                content_value BLOB NOT NULL
+                            );
+                            CREATE INDEX IF NOT EXISTS content_size_idx ON content_data(content_size);
+                            CREATE INDEX IF NOT EXISTS content_id_short_idx ON content_data(content_id_short);
+                            CREATE INDEX IF NOT EXISTS content_key_idx ON content_data(content_key);";
+
+const INSERT_QUERY: &str =
+    "INSERT OR IGNORE INTO content_data (content_id_long, content_id_short, content_key, content_size, content_value)
+                            VALUES (?1, ?2, ?3, ?4, ?5)";
+
+const DELETE_QUERY: &str = "DELETE FROM content_data WHERE content_id_long = (?1)";
+
+const VALUE_LOOKUP_QUERY: &str =
+    "SELECT content_value FROM content_data WHERE content_id_long = (?1)";
+
+const CONTAINS_QUERY: &str = "SELECT 1 FROM content_data WHERE content_id_long = (?1)";
+
+const CONTENT_KEY_LOOKUP_QUERY: &str =
+    "SELECT content_key FROM content_data WHERE content_id_long = (?1)";
+
+const CONTENT_SIZE_LOOKUP_QUERY: &str =
+    "SELECT content_size FROM content_data WHERE content_id_long = (?1)";
+
+const XOR_FIND_FARTHEST_QUERY: &str = "SELECT
+                                    content_id_long
+                                    FROM content_data
+                                    ORDER BY ((?1 | content_id_short) - (?1 & content_id_short)) DESC
+                                    LIMIT 1";
+
+const XOR_WITHIN_DISTANCE_QUERY: &str = "SELECT
+                                    content_id_long, content_key
+                                    FROM content_data
+                                    WHERE ((?1 | content_id_short) - (?1 & content_id_short)) <= ?2
+                                    ORDER BY ((?1 | content_id_short) - (?1 & content_id_short))
+                                    LIMIT ?3";
+
+const TOTAL_DATA_SIZE_QUERY: &str = "SELECT TOTAL(content_size) FROM content_data";
+
+const TOTAL_ENTRY_COUNT_QUERY: &str = "SELECT COUNT(content_id_long) FROM content_data";
+
+const PAGINATE_QUERY: &str =
+    "SELECT content_key FROM content_data ORDER BY content_key LIMIT :limit OFFSET :offset";
+
+const CONTENT_IDS_QUERY: &str = "SELECT content_id_long FROM content_data";
+
+const AUTO_VACUUM_QUERY: &str = "PRAGMA auto_vacuum";
+
+/// Value of `PRAGMA auto_vacuum` for incremental auto-vacuum.
+const AUTO_VACUUM_INCREMENTAL: u8 = 2;
+
+/// Converts a shard created without incremental auto-vacuum, which takes a full rebuild.
+const ENABLE_INCREMENTAL_VACUUM_QUERY: &str = "PRAGMA auto_vacuum = INCREMENTAL; VACUUM;";
+
+const INCREMENTAL_VACUUM_QUERY: &str = "PRAGMA incremental_vacuum";
+
+/// The content of a `PortalStorage`, in one or more SQLite shards.
+#[derive(Debug)]
+pub struct ContentDb {
+    paths: Vec<PathBuf>,
+    shards: Vec<Pool<SqliteConnectionManager>>,
+}
+
+impl ContentDb {
+    /// Opens the database sharded across the directories `paths`, creating them if they do not
+    /// exist. A single path gives an unsharded database.
+    ///
+    /// Content is assigned to shards by position, so a sharded database must always be reopened
+    /// with the same number of paths, in the same order.
+    pub fn open(paths: &[PathBuf]) -> Result<Self, ContentStoreError> {
+        if paths.is_empty() {
+            return Err(ContentStoreError::InvalidData {
+                message: "The content database needs at least one path".to_string(),
+            });
+        }
+        let mut shards = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            std::fs::create_dir_all(path)?;
+            if paths.len() > 1 {
+                check_shard_position(path, index, paths.len())?;
+            }
+            let data_path = path.join(CONTENT_DB_FILE);
+            info!(path = %data_path.display(), "Setting up content database");
+            let pool = Pool::new(SqliteConnectionManager::file(data_path))?;
+            pool.get()?.execute_batch(CREATE_QUERY)?;
+            shards.push(pool);
+        }
+        Ok(Self {
+            paths: paths.to_vec(),
+            shards,
+        })
+    }
+
+    /// Returns the directories that hold the database.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Returns the shard that holds `content_id`.
+    fn shard(&self, content_id: &[u8; 32]) -> &Pool<SqliteConnectionManager> {
+        &self.shards[self.shard_index(content_id)]
+    }
+
+    fn shard_index(&self, content_id: &[u8; 32]) -> usize {
+        content_id[0] as usize * self.shards.len() / 256
+    }
+
+    /// Inserts a content item, with its content key hex-encoded without the 0x prefix. Returns
+    /// whether the item was inserted, which it is not if the content id is already present.
+    pub fn insert(
+        &self,
+        content_id: &[u8; 32],
+        content_key: &str,
+        value: &[u8],
+    ) -> Result<bool, ContentStoreError> {
+        if content_key.starts_with("0x") {
+            return Err(ContentStoreError::InvalidData {
+                message: "Content key should not start with 0x".to_string(),
+            });
+        }
+        let inserted = self.shard(content_id).get()?.execute(
+            INSERT_QUERY,
+            params![
+                content_id.to_vec(),
+                short_id(content_id),
+                content_key,
+                value.len(),
+                value
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// Inserts content items like `insert`, with a single transaction per shard. Returns the
+    /// number of items inserted.
+    pub fn insert_batch(
+        &self,
+        items: &[([u8; 32], String, Vec<u8>)],
+    ) -> Result<usize, ContentStoreError> {
+        let mut inserted = 0;
+        for (index, shard) in self.shards.iter().enumerate() {
+            let mut shard_items = items
+                .iter()
+                .filter(|(content_id, _, _)| self.shard_index(content_id) == index)
+                .peekable();
+            if shard_items.peek().is_none() {
+                continue;
+            }
+            let mut conn = shard.get()?;
+            let tx = conn.transaction()?;
+            {
+                let mut query = tx.prepare_cached(INSERT_QUERY)?;
+                for (content_id, content_key, value) in shard_items {
+                    if content_key.starts_with("0x") {
+                        return Err(ContentStoreError::InvalidData {
+                            message: "Content key should not start with 0x".to_string(),
+                        });
+                    }
+                    inserted += query.execute(params![
+                        content_id.to_vec(),
+                        short_id(content_id),
+                        content_key,
+                        value.len(),
+                        value
+                    ])?;
+                }
+            }
+            tx.commit()?;
+        }
+        Ok(inserted)
+    }
+
+    /// Deletes a content item. Returns whether the item was present.
+    pub fn delete(&self, content_id: &[u8; 32]) -> Result<bool, ContentStoreError> {
+        let deleted = self
+            .shard(content_id)
+            .get()?
+            .execute(DELETE_QUERY, [content_id.to_vec()])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn get(&self, content_id: &[u8; 32]) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self
+            .shard(content_id)
+            .get()?
+            .query_row(VALUE_LOOKUP_QUERY, [content_id.to_vec()], |row| row.get(0))
+            .optional()?)
+    }
+
+    pub fn contains(&self, content_id: &[u8; 32]) -> Result<bool, ContentStoreError> {
+        Ok(self
+            .shard(content_id)
+            .get()?
+            .query_row(CONTAINS_QUERY, [content_id.to_vec()], |_| Ok(()))
+            .optional()?
+            .is_some())
+    }
+
+    /// Returns the content key of a content item, hex-encoded without the 0x prefix.
+    pub fn content_key(&self, content_id: &[u8; 32]) -> Result<Option<String>, ContentStoreError> {
+        Ok(self
+            .shard(content_id)
+            .get()?
+            .query_row(CONTENT_KEY_LOOKUP_QUERY, [content_id.to_vec()], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Returns the size of the value of a content item, in bytes.
+    pub fn content_size(&self, content_id: &[u8; 32]) -> Result<Option<u64>, ContentStoreError> {
+        Ok(self
+            .shard(content_id)
+            .get()?
+            .query_row(CONTENT_SIZE_LOOKUP_QUERY, [content_id.to_vec()], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    /// Returns the total size of the values of all content items, in bytes.
+    pub fn total_size(&self) -> Result<u64, ContentStoreError> {
+        let mut total = 0.0;
+        for shard in self.shards.iter() {
+            let size: f64 = shard
+                .get()?
+                .query_row(TOTAL_DATA_SIZE_QUERY, [], |row| row.get(0))?;
+            total += size;
+        }
+        Ok(total as u64)
+    }
+
+    /// Returns the number of content items.
+    pub fn entry_count(&self) -> Result<u64, ContentStoreError> {
+        let mut total = 0;
+        for shard in self.shards.iter() {
+            let count: u64 = shard
+                .get()?
+                .query_row(TOTAL_ENTRY_COUNT_QUERY, [], |row| row.get(0))?;
+            total += count;
+        }
+        Ok(total)
+    }
+
+    /// Returns the content id that is farthest from `node_id` by XOR distance. Within a shard,
+    /// content ids are compared by their short id.
+    pub fn find_farthest(&self, node_id: &[u8; 32]) -> Result<Option<[u8; 32]>, ContentStoreError> {
+        let mut farthest: Option<[u8; 32]> = None;
+        for shard in self.shards.iter() {
+            let content_id: Option<Vec<u8>> = shard
+                .get()?
+                .query_row(XOR_FIND_FARTHEST_QUERY, [short_id(node_id)], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            let content_id = match content_id {
+                Some(content_id) => to_content_id(content_id)?,
+                None => continue,
+            };
+            let is_farther = match farthest {
+                Some(farthest) => {
+                    XorMetric::distance(&content_id, node_id)
+                        > XorMetric::distance(&farthest, node_id)
+                }
+                None => true,
+            };
+            if is_farther {
+                farthest = Some(content_id);
+            }
+        }
+        Ok(farthest)
+    }
+
+    /// Returns the content ids and content keys, hex-encoded without the 0x prefix, of at most
+    /// `limit` content items within `distance` of `node_id` by XOR distance, closest first.
+    ///
+    /// Each shard is queried by short id for the closest `limit` items whose short id is within
+    /// the distance, which are then checked against the full content id. Items that only fall
+    /// outside the distance beyond their short id may take the place of items within it.
+    pub fn find_within(
+        &self,
+        node_id: &[u8; 32],
+        distance: Distance,
+        limit: u64,
+    ) -> Result<Vec<([u8; 32], String)>, ContentStoreError> {
+        let short_distance = short_id(&distance.big_endian());
+        let mut content = vec![];
+        for shard in self.shards.iter() {
+            let conn = shard.get()?;
+            let mut query = conn.prepare(XOR_WITHIN_DISTANCE_QUERY)?;
+            let rows = query
+                .query_map(params![short_id(node_id), short_distance, limit], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<Result<Vec<(Vec<u8>, String)>, rusqlite::Error>>()?;
+            for (content_id, content_key) in rows {
+                let content_id = to_content_id(content_id)?;
+                if XorMetric::distance(&content_id, node_id) <= distance {
+                    content.push((content_id, content_key));
+                }
+            }
+        }
+        content.sort_by_key(|(content_id, _)| XorMetric::distance(content_id, node_id));
+        content.truncate(limit as usize);
+        Ok(content)
+    }
+
+    /// Returns `limit` content keys, hex-encoded without the 0x prefix, in order after skipping
+    /// the first `offset` keys.
+    pub fn paginate(&self, offset: u64, limit: u64) -> Result<Vec<String>, ContentStoreError> {
+        if let [shard] = &self.shards[..] {
+            return Self::paginate_shard(shard, offset, limit);
+        }
+        // Every shard may hold keys of the requested page, so take enough keys from each to fill
+        // it, and merge them.
+        let mut content_keys = vec![];
+        for shard in self.shards.iter() {
+            content_keys.extend(Self::paginate_shard(shard, 0, offset + limit)?);
+        }
+        content_keys.sort();
+        Ok(content_keys
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    fn paginate_shard(
+        shard: &Pool<SqliteConnectionManager>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<String>, ContentStoreError> {
+        let conn = shard.get()?;
+        let mut query = conn.prepare(PAGINATE_QUERY)?;
+        let content_keys = query
+            .query_map(
+                &[
+                    (":offset", offset.to_string().as_str()),
+                    (":limit", limit.to_string().as_str()),
+                ],
+                |row| row.get(0),
+            )?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        Ok(content_keys)
+    }
+
+    /// Returns the free pages left by deleted content in each shard to the file system, and
+    /// returns the number of bytes reclaimed. This is a blocking call, which should be run off the
+    /// async runtime.
+    ///
+    /// Shards created before incremental auto-vacuum was enabled are rebuilt once to enable it,
+    /// which may take a while for large shards, and writes to the shard wait until it is done.
+    pub fn compact(&self) -> Result<u64, ContentStoreError> {
+        let mut reclaimed = 0;
+        for (path, shard) in self.paths.iter().zip(self.shards.iter()) {
+            let data_path = path.join(CONTENT_DB_FILE);
+            let size_before = std::fs::metadata(&data_path)?.len();
+            let conn = shard.get()?;
+            let auto_vacuum: u8 = conn.query_row(AUTO_VACUUM_QUERY, [], |row| row.get(0))?;
+            if auto_vacuum == AUTO_VACUUM_INCREMENTAL {
+                conn.execute_batch(INCREMENTAL_VACUUM_QUERY)?;
+            } else {
+                info!(
+                    path = %data_path.display(),
+                    "Enabling incremental vacuum of content database"
+                );
+                conn.execute_batch(ENABLE_INCREMENTAL_VACUUM_QUERY)?;
+            }
+            let size_after = std::fs::metadata(&data_path)?.len();
+            reclaimed += size_before.saturating_sub(size_after);
+        }
+        Ok(reclaimed)
+    }
+
+    /// Returns the content ids of all content items.
+    pub fn content_ids(&self) -> Result<Vec<[u8; 32]>, ContentStoreError> {
+        let mut content_ids = vec![];
+        for shard in self.shards.iter() {
+            let conn = shard.get()?;
+            let mut query = conn.prepare(CONTENT_IDS_QUERY)?;
+            let shard_ids = query
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<Vec<u8>>, rusqlite::Error>>()?;
+            for content_id in shard_ids {
+                content_ids.push(to_content_id(content_id)?);
+            }
+        }
+        Ok(content_ids)
+    }
+}
+
+/// Records the position of the shard in `path`, or checks it against the recorded position,
+/// so that a change to the list of paths does not silently lose track of stored content.
+fn check_shard_position(path: &Path, index: usize, count: usize) -> Result<(), ContentStoreError> {
+    let position = format!("{index}/{count}");
+    let shard_file = path.join(SHARD_FILE);
+    match std::fs::read_to_string(&shard_file) {
+        Ok(recorded) if recorded.trim() == position => Ok(()),
+        Ok(recorded) => Err(ContentStoreError::InvalidData {
+            message: format!(
+                "Shard {} was created as shard {}, but is now opened as shard {position}. \
+                Sharded databases must be reopened with the same paths, in the same order.",
+                path.display(),
+                recorded.trim()
+            ),
+        }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            std::fs::write(shard_file, position)?;
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Returns the most significant 4 bytes of `id`, which index content for distance queries.
+fn short_id(id: &[u8; 32]) -> u32 {
+    u32::from_be_bytes([id[0], id[1], id[2], id[3]])
+}
+
+fn to_content_id(bytes: Vec<u8>) -> Result<[u8; 32], ContentStoreError> {
+    let length = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| ContentStoreError::InvalidData {
+            message: format!("content ID of length {length} != 32"),
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use crate::utils::db::setup_temp_dir;
+    use trin_utils::bytes::hex_encode;
+
+    #[test]
+    fn test_sharded_content_db() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let paths = vec![temp_dir.path().join("a"), temp_dir.path().join("b")];
+        let db = ContentDb::open(&paths).unwrap();
+
+        let low_id = [0x00; 32];
+        let high_id = [0xff; 32];
+        assert!(db.insert(&low_id, "00", b"low").unwrap());
+        assert!(db.insert(&high_id, "ff", b"high value").unwrap());
+        assert!(!db.insert(&high_id, "ff", b"high value").unwrap());
+
+        assert_eq!(db.get(&low_id).unwrap(), Some(b"low".to_vec()));
+        assert_eq!(db.content_key(&high_id).unwrap(), Some("ff".to_string()));
+        assert_eq!(db.content_size(&high_id).unwrap(), Some(10));
+        assert_eq!(db.total_size().unwrap(), 13);
+        assert_eq!(db.entry_count().unwrap(), 2);
+        assert_eq!(db.find_farthest(&[0x00; 32]).unwrap(), Some(high_id));
+        assert_eq!(db.find_farthest(&[0xf0; 32]).unwrap(), Some(low_id));
+        assert_eq!(db.paginate(1, 5).unwrap(), vec!["ff".to_string()]);
+        assert_eq!(db.content_ids().unwrap().len(), 2);
+        assert_eq!(
+            db.find_within(&[0xf0; 32], Distance::MAX, 1).unwrap(),
+            vec![(high_id, "ff".to_string())]
+        );
+        let distance = XorMetric::distance(&[0x0f; 32], &[0xff; 32]);
+        assert_eq!(
+            db.find_within(&[0xff; 32], distance, 5).unwrap(),
+            vec![(high_id, "ff".to_string())]
+        );
+
+        assert!(db.delete(&low_id).unwrap());
+        assert!(!db.contains(&low_id).unwrap());
+        assert_eq!(db.entry_count().unwrap(), 1);
+        drop(db);
+
+        // Reordering the paths would lose track of content, so it is refused.
+        let reordered = vec![paths[1].clone(), paths[0].clone()];
+        assert!(ContentDb::open(&reordered).is_err());
+    }
+
+    #[test]
+    fn test_insert_batch() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let paths = vec![temp_dir.path().join("a"), temp_dir.path().join("b")];
+        let db = ContentDb::open(&paths).unwrap();
+
+        let items = vec![
+            ([0x00; 32], "00".to_string(), b"low".to_vec()),
+            ([0xff; 32], "ff".to_string(), b"high".to_vec()),
+        ];
+        assert_eq!(db.insert_batch(&items).unwrap(), 2);
+        // Items already present are skipped.
+        assert_eq!(db.insert_batch(&items).unwrap(), 0);
+        assert_eq!(db.get(&[0xff; 32]).unwrap(), Some(b"high".to_vec()));
+        assert_eq!(db.entry_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_compact_reclaims_deleted_content() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let db = ContentDb::open(&[temp_dir.path().to_path_buf()]).unwrap();
+
+        let value = vec![0xab; 10_000];
+        for i in 0..=255u8 {
+            assert!(db.insert(&[i; 32], &hex_encode([i])[2..], &value).unwrap());
+        }
+        for i in 0..=255u8 {
+            assert!(db.delete(&[i; 32]).unwrap());
+        }
+
+        assert!(db.compact().unwrap() > 0);
+        // Nothing is left to reclaim.
+        assert_eq!(db.compact().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_compact_enables_incremental_vacuum() {
+        let temp_dir = setup_temp_dir().unwrap();
+        // A shard created before incremental auto-vacuum was enabled.
+        let data_path = temp_dir.path().join(CONTENT_DB_FILE);
+        rusqlite::Connection::open(&data_path)
+            .unwrap()
+            .execute_batch(&CREATE_QUERY.replace("PRAGMA auto_vacuum = INCREMENTAL;", ""))
+            .unwrap();
+
+        let db = ContentDb::open(&[temp_dir.path().to_path_buf()]).unwrap();
+        let auto_vacuum = |db: &ContentDb| -> u8 {
+            db.shards[0]
+                .get()
+                .unwrap()
+                .query_row(AUTO_VACUUM_QUERY, [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(auto_vacuum(&db), 0);
+        db.compact().unwrap();
+        assert_eq!(auto_vacuum(&db), AUTO_VACUUM_INCREMENTAL);
+    }
+}
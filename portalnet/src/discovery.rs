@@ -8,12 +8,13 @@ use ethereum_types::H256;
 use lru::LruCache;
 use parking_lot::RwLock;
 use serde_json::{json, Value};
+use thiserror::Error;
 use tokio::sync::mpsc;
 use tracing::{debug, info, warn};
 use utp_rs::{cid::ConnectionPeer, udp::AsyncUdpSocket};
 
 use super::types::messages::{PortalnetConfig, ProtocolId};
-use crate::socket;
+use crate::{overlay::RequestTimeouts, socket};
 use ethportal_api::types::discv5::NodeInfo;
 use std::str::FromStr;
 use std::{
@@ -21,6 +22,7 @@ use std::{
     fmt, io,
     net::{IpAddr, SocketAddr},
     sync::Arc,
+    time::Duration,
 };
 use trin_types::enr::Enr;
 use trin_types::node_id::NodeId as EthportalNodeId;
@@ -33,6 +35,30 @@ const TALKREQ_CHANNEL_BUFFER: usize = 100;
 /// ENR key for portal network client version.
 const ENR_PORTAL_CLIENT_KEY: &str = "c";
 
+/// An error raised while setting up or starting the discovery protocol.
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    /// The private key does not make a valid secp256k1 key.
+    #[error("invalid private key: {0}")]
+    InvalidPrivateKey(String),
+
+    /// The local ENR could not be built, e.g. because it exceeds the maximum ENR size.
+    #[error("unable to build local ENR: {0}")]
+    InvalidEnr(String),
+
+    /// A bootnode ENR was rejected by discv5.
+    #[error("invalid bootnode ENR: {0}")]
+    InvalidBootnode(String),
+
+    /// The discovery port is bound by another socket.
+    #[error("discovery port {0} is already in use")]
+    PortInUse(u16),
+
+    /// Any other failure of discv5.
+    #[error("discv5 failure: {0}")]
+    Discv5(String),
+}
+
 #[derive(Clone)]
 pub struct Config {
     pub enr_address: Option<IpAddr>,
@@ -67,8 +93,8 @@ pub struct NodeAddress {
 
 /// Base Node Discovery Protocol v5 layer
 pub struct Discovery {
-    /// The inner Discv5 service.
-    discv5: Discv5,
+    /// The inner Discv5 service, write-locked only to shut it down.
+    discv5: RwLock<Discv5>,
     /// A cache of the latest observed `NodeAddress` for a node ID.
     node_addr_cache: Arc<RwLock<LruCache<NodeId, NodeAddress>>>,
     /// Indicates if the Discv5 service has been started.
@@ -82,7 +108,7 @@ impl fmt::Debug for Discovery {
         write!(
             f,
             "Discovery: ( enr: {}, started: {}, listen_socket: {} )",
-            self.discv5.local_enr(),
+            self.discv5.read().local_enr(),
             self.started,
             self.listen_socket
         )
@@ -90,7 +116,7 @@ impl fmt::Debug for Discovery {
 }
 
 impl Discovery {
-    pub fn new(portal_config: PortalnetConfig) -> Result<Self, String> {
+    pub fn new(portal_config: PortalnetConfig) -> Result<Self, DiscoveryError> {
         let listen_all_ips = SocketAddr::new(
             "0.0.0.0"
                 .parse()
@@ -111,8 +137,27 @@ impl Discovery {
             }
         };
 
+        // Overlay requests time out by kind of request, but are also failed by discv5 once it has
+        // given up on them, after one retry.
+        let discv5_config = Discv5ConfigBuilder::default()
+            .request_timeout(portal_config.discv5_request_timeout)
+            .build();
+        let discv5_deadline =
+            discv5_config.request_timeout * (discv5_config.request_retries as u32 + 1);
+        let longest_timeout = portal_config
+            .talkreq_timeouts
+            .iter()
+            .map(|timeout| timeout.timeout)
+            .fold(RequestTimeouts::default().max(), Duration::max);
+        if longest_timeout > discv5_deadline {
+            debug!(
+                discv5_deadline = ?discv5_deadline,
+                longest_timeout = ?longest_timeout,
+                "Overlay requests are failed by discv5 before their own timeout, raise --discv5-request-timeout-ms to wait longer"
+            );
+        }
         let config = Config {
-            discv5_config: Discv5ConfigBuilder::default().build(),
+            discv5_config,
             // This is for defining the ENR:
             enr_address: ip_addr,
             listen_port: ip_port,
@@ -122,65 +167,56 @@ impl Discovery {
 
         let enr_key = match config.private_key {
             Some(val) => CombinedKey::secp256k1_from_bytes(val.0.clone().as_mut_slice())
-                .map_err(|e| format!("When building servers key pair: {e:?}"))?,
+                .map_err(|e| DiscoveryError::InvalidPrivateKey(format!("{e:?}")))?,
             None => CombinedKey::generate_secp256k1(),
         };
 
-        let enr = {
-            let mut builder = EnrBuilder::new("v4");
-            if let Some(ip_address) = config.enr_address {
-                builder.ip(ip_address);
-            }
-            builder.udp4(config.listen_port);
-
-            let trin_version = get_trin_version();
-            // Use "t" as short-hand for "Trin" to save bytes in ENR.
-            let client_info = format!("t {trin_version}");
-            // Use "c" as short-hand for "client".
-            builder.add_value(ENR_PORTAL_CLIENT_KEY, client_info.as_bytes());
-            builder
-                .build(&enr_key)
-                .map_err(|e| format!("When adding key to servers ENR: {e:?}"))?
-        };
+        let enr = build_enr(&enr_key, config.enr_address, config.listen_port)?;
 
-        let discv5 = Discv5::new(enr, enr_key, config.discv5_config)
-            .map_err(|e| format!("Failed to create discv5 instance: {e}"))?;
+        let discv5 = Discv5::new(enr, enr_key, config.discv5_config).map_err(|e| {
+            DiscoveryError::Discv5(format!("Failed to create discv5 instance: {e}"))
+        })?;
 
         for enr in config.bootnode_enrs {
             discv5
                 .add_enr(enr)
-                .map_err(|e| format!("Failed to add bootnode enr: {e}"))?;
+                .map_err(|e| DiscoveryError::InvalidBootnode(e.to_string()))?;
         }
 
         let node_addr_cache = LruCache::new(portal_config.node_addr_cache_capacity);
         let node_addr_cache = Arc::new(RwLock::new(node_addr_cache));
 
         Ok(Self {
-            discv5,
+            discv5: RwLock::new(discv5),
             node_addr_cache,
             started: false,
             listen_socket: listen_all_ips,
         })
     }
 
-    pub async fn start(&mut self) -> Result<mpsc::Receiver<TalkRequest>, String> {
+    pub async fn start(&mut self) -> Result<mpsc::Receiver<TalkRequest>, DiscoveryError> {
         info!(
             enr.encoded = ?self.local_enr(),
             enr.decoded = %self.local_enr(),
             "Starting discv5",
         );
 
-        self.discv5
-            .start(self.listen_socket)
-            .await
-            .map_err(|e| format!("Failed to start discv5 server: {e:?}"))?;
+        if let Err(err) = self.discv5.get_mut().start(self.listen_socket).await {
+            // discv5 does not expose why binding failed, so check whether the port is taken.
+            if let Err(bind_err) = std::net::UdpSocket::bind(self.listen_socket) {
+                if bind_err.kind() == io::ErrorKind::AddrInUse {
+                    return Err(DiscoveryError::PortInUse(self.listen_socket.port()));
+                }
+            }
+            return Err(DiscoveryError::Discv5(format!(
+                "Failed to start discv5 server: {err:?}"
+            )));
+        }
         self.started = true;
 
-        let mut event_rx = self
-            .discv5
-            .event_stream()
-            .await
-            .map_err(|e| format!("When launching event stream in new discv5: {e:?}"))?;
+        let mut event_rx = self.discv5.get_mut().event_stream().await.map_err(|e| {
+            DiscoveryError::Discv5(format!("When launching event stream in new discv5: {e:?}"))
+        })?;
 
         let (talk_req_tx, talk_req_rx) = mpsc::channel(TALKREQ_CHANNEL_BUFFER);
 
@@ -220,22 +256,23 @@ impl Discovery {
 
     /// Returns number of connected peers in the Discv5 routing table.
     pub fn connected_peers_len(&self) -> usize {
-        self.discv5.connected_peers()
+        self.discv5.read().connected_peers()
     }
 
     /// Returns the ENRs in the Discv5 routing table.
     pub fn table_entries_enr(&self) -> Vec<Enr> {
-        self.discv5.table_entries_enr()
+        self.discv5.read().table_entries_enr()
     }
 
     /// Returns ENR and nodeId information of the local Discv5 node.
     pub fn node_info(&self) -> anyhow::Result<NodeInfo> {
         Ok(NodeInfo {
-            enr: Enr::from_str(&self.discv5.local_enr().to_base64())
+            enr: Enr::from_str(&self.discv5.read().local_enr().to_base64())
                 .map_err(|err| anyhow!("{err}"))?,
-            node_id: EthportalNodeId::from(self.discv5.local_enr().node_id().raw()),
+            node_id: EthportalNodeId::from(self.discv5.read().local_enr().node_id().raw()),
             ip: self
                 .discv5
+                .read()
                 .local_enr()
                 .ip4()
                 .map_or(Some("None".to_owned()), |ip| Some(ip.to_string())),
@@ -246,6 +283,7 @@ impl Discovery {
     pub fn routing_table_info(&self) -> Value {
         let buckets: Vec<(String, String, String)> = self
             .discv5
+            .read()
             .table_entries()
             .iter()
             .map(|(node_id, enr, node_status)| {
@@ -259,7 +297,7 @@ impl Discovery {
 
         json!(
             {
-                "localNodeId": hex_encode(self.discv5.local_enr().node_id().raw()),
+                "localNodeId": hex_encode(self.discv5.read().local_enr().node_id().raw()),
                 "buckets": buckets
             }
         )
@@ -267,22 +305,27 @@ impl Discovery {
 
     /// Returns the node IDs of connected peers in the Discv5 routing table.
     pub fn connected_peers(&self) -> Vec<NodeId> {
-        self.discv5.table_entries_id()
+        self.discv5.read().table_entries_id()
     }
 
     /// Returns the ENR of the local node.
     pub fn local_enr(&self) -> Enr {
-        self.discv5.local_enr()
+        self.discv5.read().local_enr()
     }
 
     /// Looks up the ENR for `node_id`.
     pub fn find_enr(&self, node_id: &NodeId) -> Option<Enr> {
-        self.discv5.find_enr(node_id)
+        self.discv5.read().find_enr(node_id)
     }
 
     /// Adds `enr` to the discv5 routing table.
     pub fn add_enr(&self, enr: Enr) -> Result<(), &'static str> {
-        self.discv5.add_enr(enr)
+        self.discv5.read().add_enr(enr)
+    }
+
+    /// Shuts down the Discv5 service, which closes its socket and ends the event stream.
+    pub fn shutdown(&self) {
+        self.discv5.write().shutdown();
     }
 
     /// Returns the cached `NodeAddress` or `None` if not cached.
@@ -300,8 +343,9 @@ impl Discovery {
         // Send empty protocol id if unable to convert it to bytes
         let protocol = Vec::try_from(protocol).unwrap_or_default();
 
-        let response = self.discv5.talk_req(enr, protocol, request).await?;
-        Ok(response)
+        // Release the lock before awaiting the response, so that a shutdown is not held up.
+        let response = self.discv5.read().talk_req(enr, protocol, request);
+        Ok(response.await?)
     }
 }
 
@@ -321,6 +365,29 @@ impl Discv5UdpSocket {
     }
 }
 
+/// Builds the local ENR signed with `enr_key`, advertising `ip_address` if it is known and the
+/// UDP port `port`.
+pub fn build_enr(
+    enr_key: &CombinedKey,
+    ip_address: Option<IpAddr>,
+    port: u16,
+) -> Result<Enr, DiscoveryError> {
+    let mut builder = EnrBuilder::new("v4");
+    if let Some(ip_address) = ip_address {
+        builder.ip(ip_address);
+    }
+    builder.udp4(port);
+
+    let trin_version = get_trin_version();
+    // Use "t" as short-hand for "Trin" to save bytes in ENR.
+    let client_info = format!("t {trin_version}");
+    // Use "c" as short-hand for "client".
+    builder.add_value(ENR_PORTAL_CLIENT_KEY, client_info.as_bytes());
+    builder
+        .build(enr_key)
+        .map_err(|e| DiscoveryError::InvalidEnr(format!("{e:?}")))
+}
+
 /// A wrapper around `Enr` that implements `ConnectionPeer`.
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 pub struct UtpEnr(pub Enr);
@@ -195,7 +195,13 @@ where
                         "Query (on failure) reached invalid number of waiting peers"
                     );
                     self.num_waiting -= 1;
-                    entry.get_mut().set_state(QueryPeerState::Failed);
+                    let peer = entry.get_mut();
+                    if peer.attempts() < self.config.peer_attempts {
+                        // Request the peer again when the query is next polled.
+                        peer.set_state(QueryPeerState::NotContacted);
+                    } else {
+                        peer.set_state(QueryPeerState::Failed);
+                    }
                 }
                 QueryPeerState::Unresponsive => entry.get_mut().set_state(QueryPeerState::Failed),
                 _ => {}
@@ -231,6 +237,7 @@ where
                     if !at_capacity {
                         let timeout = now + self.config.peer_timeout;
                         peer.set_state(QueryPeerState::Waiting(timeout));
+                        peer.increment_attempts();
                         self.num_waiting += 1;
                         let peer = peer.key().preimage().clone();
                         return QueryState::Waiting(Some(peer));
@@ -240,7 +247,15 @@ where
                 }
 
                 QueryPeerState::Waiting(timeout) => {
-                    if now >= *timeout {
+                    if now >= *timeout && peer.attempts() < self.config.peer_attempts {
+                        // Peers with attempts remaining are requested again in place of the
+                        // request that timed out.
+                        let timeout = now + self.config.peer_timeout;
+                        peer.set_state(QueryPeerState::Waiting(timeout));
+                        peer.increment_attempts();
+                        let peer = peer.key().preimage().clone();
+                        return QueryState::Waiting(Some(peer));
+                    } else if now >= *timeout {
                         // Peers that don't respond within timeout are set to `Unresponsive`.
                         // Assert that the following subtraction will not overflow.
                         assert!(
@@ -419,6 +434,7 @@ mod tests {
             parallelism: rng.gen_range(1..10),
             num_results: rng.gen_range(1..25),
             peer_timeout: Duration::from_secs(rng.gen_range(10..30)),
+            peer_attempts: 1,
         };
         FindContentQuery::with_config(config, target.into(), known_closest_peers)
     }
@@ -733,4 +749,33 @@ mod tests {
 
         QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _)
     }
+
+    #[test]
+    fn retries_peer_until_attempts_exhausted() {
+        let peer = NodeId::random();
+        let target = NodeId::random();
+        let config = QueryConfig {
+            peer_attempts: 3,
+            ..Default::default()
+        };
+        let mut query = TestQuery::with_config(config, target.into(), vec![Key::from(peer)]);
+        let mut now = Instant::now();
+
+        // The first attempt fails outright, so the peer is requested again.
+        assert!(matches!(query.poll(now), QueryState::Waiting(Some(id)) if id == peer));
+        query.on_failure(&peer);
+        assert!(matches!(query.poll(now), QueryState::Waiting(Some(id)) if id == peer));
+
+        // The second attempt times out, so the peer is requested a third time.
+        now += query.config.peer_timeout;
+        assert!(matches!(query.poll(now), QueryState::Waiting(Some(id)) if id == peer));
+        assert_eq!(query.num_waiting, 1);
+
+        // The third attempt times out with no attempts remaining, so the query gives up.
+        now += query.config.peer_timeout;
+        assert!(matches!(query.poll(now), QueryState::Finished));
+        let peer_state = query.closest_peers.values().next().unwrap();
+        assert_eq!(peer_state.attempts(), 3);
+        assert!(matches!(peer_state.state(), QueryPeerState::Unresponsive));
+    }
 }
@@ -342,6 +342,7 @@ mod tests {
             parallelism: rng.gen_range(1..10),
             num_results: rng.gen_range(1..25),
             peer_timeout: Duration::from_secs(rng.gen_range(10..30)),
+            peer_attempts: 1,
         };
         FindNodeQuery::with_config(config, target.into(), known_closest_peers)
     }
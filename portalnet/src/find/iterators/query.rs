@@ -51,6 +51,12 @@ pub struct QueryConfig {
     /// the peer when evaluating the termination conditions, until and unless a
     /// result is delivered. Defaults to `10` seconds.
     pub peer_timeout: Duration,
+
+    /// The number of times a peer is requested before it is abandoned.
+    ///
+    /// A peer that fails or does not respond within `peer_timeout` is requested again by
+    /// content queries until it has been requested this many times. Defaults to `1`.
+    pub peer_attempts: usize,
 }
 
 impl Default for QueryConfig {
@@ -59,6 +65,7 @@ impl Default for QueryConfig {
             parallelism: 3,
             num_results: 20,
             peer_timeout: Duration::from_secs(10),
+            peer_attempts: 1,
         }
     }
 }
@@ -158,6 +165,9 @@ pub struct QueryPeer<TNodeId> {
     /// The number of peers that have been returned by this peer.
     peers_returned: usize,
 
+    /// The number of requests that have been sent to this peer.
+    attempts: usize,
+
     /// The current query state of this peer.
     state: QueryPeerState,
 }
@@ -169,6 +179,7 @@ impl<TNodeId> QueryPeer<TNodeId> {
         QueryPeer {
             key,
             peers_returned: 0,
+            attempts: 0,
             state,
         }
     }
@@ -188,6 +199,16 @@ impl<TNodeId> QueryPeer<TNodeId> {
         self.peers_returned += num_peers;
     }
 
+    /// Returns the number of requests sent to the query peer.
+    pub fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Increments the number of requests sent to the query peer.
+    pub fn increment_attempts(&mut self) {
+        self.attempts += 1;
+    }
+
     /// Returns the state of the query peer.
     pub fn state(&self) -> &QueryPeerState {
         &self.state
@@ -0,0 +1,100 @@
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+
+use crate::find::query_info::LookupError;
+
+/// The longest time that a lookup which did not find the content is cached. Absent content may
+/// be offered to the network at any time, so these results expire sooner than found content.
+pub const NEGATIVE_LOOKUP_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// A cached content lookup result.
+struct CachedLookup {
+    /// The content, or `None` if the lookup did not find the content.
+    content: Option<Vec<u8>>,
+    /// The instant after which the result is no longer served.
+    expires_at: Instant,
+}
+
+/// A cache of recent content lookup results, keyed by content ID.
+///
+/// Found content is cached for `ttl`, while lookups that did not find the content are cached for
+/// `negative_ttl`. Lookups that timed out or failed are not cached.
+pub struct LookupCache {
+    results: LruCache<[u8; 32], CachedLookup>,
+    ttl: Duration,
+    negative_ttl: Duration,
+}
+
+impl LookupCache {
+    pub fn new(capacity: usize, ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            results: LruCache::new(capacity),
+            ttl,
+            negative_ttl,
+        }
+    }
+
+    /// Returns the cached result of a lookup for `content_id`, if the result has not expired.
+    pub fn get(&mut self, content_id: &[u8; 32]) -> Option<Result<Vec<u8>, LookupError>> {
+        let cached = self.results.get(content_id)?;
+        if Instant::now() >= cached.expires_at {
+            self.results.pop(content_id);
+            return None;
+        }
+        match &cached.content {
+            Some(content) => Some(Ok(content.clone())),
+            None => Some(Err(LookupError::NotFound)),
+        }
+    }
+
+    /// Caches the result of a lookup for `content_id`.
+    pub fn insert(&mut self, content_id: [u8; 32], result: &Result<Vec<u8>, LookupError>) {
+        let (content, ttl) = match result {
+            Ok(content) => (Some(content.clone()), self.ttl),
+            Err(LookupError::NotFound) => (None, self.negative_ttl),
+            Err(_) => return,
+        };
+        if ttl.is_zero() {
+            return;
+        }
+        let cached = CachedLookup {
+            content,
+            expires_at: Instant::now() + ttl,
+        };
+        self.results.put(content_id, cached);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_found_and_absent_content() {
+        let mut cache = LookupCache::new(2, Duration::from_secs(60), Duration::from_secs(60));
+        cache.insert([1; 32], &Ok(vec![0xef]));
+        cache.insert([2; 32], &Err(LookupError::NotFound));
+        cache.insert(
+            [3; 32],
+            &Err(LookupError::TimedOut {
+                elapsed: Duration::from_secs(60),
+            }),
+        );
+
+        assert_eq!(cache.get(&[1; 32]), Some(Ok(vec![0xef])));
+        assert_eq!(cache.get(&[2; 32]), Some(Err(LookupError::NotFound)));
+        assert_eq!(cache.get(&[3; 32]), None);
+    }
+
+    #[test]
+    fn expires_absent_content_separately() {
+        let mut cache = LookupCache::new(2, Duration::from_secs(60), Duration::from_millis(10));
+        cache.insert([1; 32], &Ok(vec![0xef]));
+        cache.insert([2; 32], &Err(LookupError::NotFound));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get(&[1; 32]), Some(Ok(vec![0xef])));
+        assert_eq!(cache.get(&[2; 32]), None);
+    }
+}
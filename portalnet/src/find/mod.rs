@@ -1,3 +1,4 @@
 pub mod iterators;
+pub mod lookup_cache;
 pub mod query_info;
 pub mod query_pool;
@@ -1,9 +1,14 @@
+use std::time::Duration;
+
 use discv5::{enr::NodeId, kbucket::Key, Enr};
 use futures::channel::oneshot;
 use smallvec::SmallVec;
+use thiserror::Error;
+use tracing::Span;
 
 use crate::{
     find::query_pool::TargetKey,
+    node_events::LookupHop,
     types::messages::{FindContent, FindNodes, Request},
 };
 use trin_types::content_key::OverlayContentKey;
@@ -16,9 +21,34 @@ pub struct QueryInfo<TContentKey> {
 
     /// Temporary ENRs used when trying to reach nodes.
     pub untrusted_enrs: SmallVec<[Enr; 16]>,
+
+    /// The span of the operation that started the query, entered while the query progresses so
+    /// that its log lines carry the operation's correlation id.
+    pub span: Span,
+
+    /// The requests made to peers on behalf of the query that have completed, if the query is
+    /// sampled for a `LookupFinished` event.
+    pub hops: Option<Vec<LookupHop>>,
 }
 
-pub type FindContentResult = (Option<Vec<u8>>, Vec<NodeId>);
+/// The content found by a content lookup, along with the peers traversed during the lookup.
+pub type FindContentResult = (Result<Vec<u8>, LookupError>, Vec<NodeId>);
+
+/// The reason a content lookup did not return any content.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum LookupError {
+    /// The closest peers to the content were queried and none of them returned the content.
+    #[error("Content not found")]
+    NotFound,
+
+    /// The lookup deadline elapsed before the closest peers to the content were queried.
+    #[error("Content lookup timed out after {} seconds", elapsed.as_secs())]
+    TimedOut { elapsed: Duration },
+
+    /// The lookup could not be submitted to, or completed by, the overlay service.
+    #[error("Content lookup failed: {0}")]
+    Service(String),
+}
 
 /// Additional information about the query.
 #[derive(Debug)]
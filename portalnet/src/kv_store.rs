@@ -0,0 +1,257 @@
+//! Persistent key-value store backing the state trie.
+//!
+//! With the default `rocksdb` feature the store is a RocksDB instance. Without it, the store is a
+//! single SQLite table, which keeps the build free of the RocksDB C++ toolchain requirements so
+//! that trin can be cross-compiled for constrained targets.
+
+use std::path::{Path, PathBuf};
+
+#[cfg(not(feature = "rocksdb"))]
+use r2d2::Pool;
+#[cfg(not(feature = "rocksdb"))]
+use r2d2_sqlite::SqliteConnectionManager;
+#[cfg(not(feature = "rocksdb"))]
+use rusqlite::{params, OptionalExtension};
+use tracing::{info, warn};
+
+use crate::storage::ContentStoreError;
+use trin_types::cli::{DbCompactionStyle, DbCompression};
+
+#[cfg(not(feature = "rocksdb"))]
+const CREATE_KV_TABLE: &str = "CREATE TABLE IF NOT EXISTS kv (
+    key BLOB PRIMARY KEY,
+    value BLOB NOT NULL
+)";
+
+/// Tuning options of the store. Options left as `None` keep the backend's defaults. They only
+/// apply to RocksDB, and are ignored by the SQLite backend.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvStoreOptions {
+    pub block_cache_bytes: Option<usize>,
+    pub write_buffer_bytes: Option<usize>,
+    pub compression: Option<DbCompression>,
+    pub max_open_files: Option<i32>,
+    pub compaction_style: Option<DbCompactionStyle>,
+}
+
+/// A persistent key-value store, backed by RocksDB or SQLite depending on the `rocksdb` feature.
+pub struct KvStore {
+    path: PathBuf,
+    #[cfg(feature = "rocksdb")]
+    db: rocksdb::DB,
+    #[cfg(not(feature = "rocksdb"))]
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl std::fmt::Debug for KvStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KvStore")
+            .field("backend", &Self::BACKEND)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl KvStore {
+    /// Opens the store in the directory `path` with default options, creating it if it does not
+    /// exist.
+    pub fn open(path: &Path) -> Result<Self, ContentStoreError> {
+        Self::open_with_options(path, &KvStoreOptions::default())
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+impl KvStore {
+    pub const BACKEND: &'static str = "RocksDB";
+
+    /// Opens the store in the directory `path` with `options`, creating it if it does not exist.
+    pub fn open_with_options(
+        path: &Path,
+        options: &KvStoreOptions,
+    ) -> Result<Self, ContentStoreError> {
+        info!(path = %path.display(), ?options, "Setting up RocksDB");
+
+        let mut db_opts = rocksdb::Options::default();
+        db_opts.create_if_missing(true);
+        if let Some(bytes) = options.block_cache_bytes {
+            let cache = rocksdb::Cache::new_lru_cache(bytes)?;
+            let mut block_opts = rocksdb::BlockBasedOptions::default();
+            block_opts.set_block_cache(&cache);
+            db_opts.set_block_based_table_factory(&block_opts);
+        }
+        if let Some(bytes) = options.write_buffer_bytes {
+            db_opts.set_write_buffer_size(bytes);
+        }
+        if let Some(compression) = options.compression {
+            db_opts.set_compression_type(match compression {
+                DbCompression::None => rocksdb::DBCompressionType::None,
+                DbCompression::Snappy => rocksdb::DBCompressionType::Snappy,
+                DbCompression::Lz4 => rocksdb::DBCompressionType::Lz4,
+                DbCompression::Zstd => rocksdb::DBCompressionType::Zstd,
+            });
+        }
+        if let Some(max_open_files) = options.max_open_files {
+            db_opts.set_max_open_files(max_open_files);
+        }
+        // The state trie must keep every node until it is deleted, so compaction never drops
+        // data: level compaction unless universal compaction is configured.
+        db_opts.set_compaction_style(match options.compaction_style {
+            Some(DbCompactionStyle::Universal) => rocksdb::DBCompactionStyle::Universal,
+            Some(DbCompactionStyle::Level) | None => rocksdb::DBCompactionStyle::Level,
+        });
+        Ok(Self {
+            path: path.to_path_buf(),
+            db: rocksdb::DB::open(&db_opts, path)?,
+        })
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self.db.get(key)?)
+    }
+
+    pub fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool, ContentStoreError> {
+        Ok(self.db.get_pinned(key)?.is_some())
+    }
+
+    pub fn put(
+        &self,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<(), ContentStoreError> {
+        Ok(self.db.put(key, value)?)
+    }
+
+    pub fn delete(&self, key: impl AsRef<[u8]>) -> Result<(), ContentStoreError> {
+        Ok(self.db.delete(key)?)
+    }
+
+    /// Returns all keys in the store.
+    pub fn keys(&self) -> Result<Vec<Vec<u8>>, ContentStoreError> {
+        Ok(self
+            .db
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|(key, _)| key.into_vec())
+            .collect())
+    }
+}
+
+#[cfg(not(feature = "rocksdb"))]
+impl KvStore {
+    pub const BACKEND: &'static str = "SQLite";
+
+    /// Opens the store in the directory `path` with `options`, creating it if it does not exist.
+    pub fn open_with_options(
+        path: &Path,
+        options: &KvStoreOptions,
+    ) -> Result<Self, ContentStoreError> {
+        std::fs::create_dir_all(path)?;
+        let data_path = path.join("kv.sqlite");
+        info!(path = %data_path.display(), "Setting up SQLite key-value store");
+        if *options != KvStoreOptions::default() {
+            warn!("Database tuning options only apply to RocksDB, and are ignored");
+        }
+
+        let pool = Pool::new(SqliteConnectionManager::file(data_path))?;
+        pool.get()?.execute(CREATE_KV_TABLE, params![])?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            pool,
+        })
+    }
+
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<Vec<u8>>, ContentStoreError> {
+        Ok(self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT value FROM kv WHERE key = ?1",
+                params![key.as_ref()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn contains(&self, key: impl AsRef<[u8]>) -> Result<bool, ContentStoreError> {
+        Ok(self
+            .pool
+            .get()?
+            .query_row(
+                "SELECT 1 FROM kv WHERE key = ?1",
+                params![key.as_ref()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    pub fn put(
+        &self,
+        key: impl AsRef<[u8]>,
+        value: impl AsRef<[u8]>,
+    ) -> Result<(), ContentStoreError> {
+        self.pool.get()?.execute(
+            "INSERT OR REPLACE INTO kv (key, value) VALUES (?1, ?2)",
+            params![key.as_ref(), value.as_ref()],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete(&self, key: impl AsRef<[u8]>) -> Result<(), ContentStoreError> {
+        self.pool
+            .get()?
+            .execute("DELETE FROM kv WHERE key = ?1", params![key.as_ref()])?;
+        Ok(())
+    }
+
+    /// Returns all keys in the store.
+    pub fn keys(&self) -> Result<Vec<Vec<u8>>, ContentStoreError> {
+        let conn = self.pool.get()?;
+        let mut query = conn.prepare("SELECT key FROM kv")?;
+        let keys = query
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<Vec<u8>>, rusqlite::Error>>()?;
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use crate::utils::db::setup_temp_dir;
+
+    #[test]
+    fn test_put_get_delete() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let store = KvStore::open(&temp_dir.path().join("kv")).unwrap();
+
+        assert_eq!(store.get(b"key").unwrap(), None);
+        assert!(!store.contains(b"key").unwrap());
+
+        store.put(b"key", b"value").unwrap();
+        store.put(b"key", b"new value").unwrap();
+        assert_eq!(store.get(b"key").unwrap(), Some(b"new value".to_vec()));
+        assert!(store.contains(b"key").unwrap());
+        assert_eq!(store.keys().unwrap(), vec![b"key".to_vec()]);
+
+        store.delete(b"key").unwrap();
+        assert_eq!(store.get(b"key").unwrap(), None);
+        assert!(store.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_with_tuning_options() {
+        let temp_dir = setup_temp_dir().unwrap();
+        let options = KvStoreOptions {
+            block_cache_bytes: Some(8 * 1024 * 1024),
+            write_buffer_bytes: Some(4 * 1024 * 1024),
+            compression: Some(DbCompression::None),
+            max_open_files: Some(64),
+            compaction_style: Some(DbCompactionStyle::Universal),
+        };
+        let store = KvStore::open_with_options(&temp_dir.path().join("kv"), &options).unwrap();
+        store.put(b"key", b"value").unwrap();
+        assert_eq!(store.get(b"key").unwrap(), Some(b"value".to_vec()));
+    }
+}
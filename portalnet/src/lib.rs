@@ -1,12 +1,22 @@
 #![warn(clippy::unwrap_used)]
 
+pub mod content_db;
 pub mod discovery;
 pub mod events;
 pub mod find;
+pub mod kv_store;
+pub mod lifetime_stats;
 pub mod metrics;
+mod neighborhood_sync;
+pub mod network_estimate;
+pub mod node_events;
 pub mod overlay;
 mod overlay_service;
+pub mod regossip;
 pub mod socket;
 pub mod storage;
+pub mod telemetry;
 pub mod types;
 pub mod utils;
+pub mod utp_transfers;
+pub mod validation_cache;
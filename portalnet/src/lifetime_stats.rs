@@ -0,0 +1,181 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::anyhow;
+use ethportal_api::types::portal::LifetimeStatsInfo;
+use tracing::warn;
+
+use crate::metrics::OverlayMetrics;
+
+/// Name of the file, in the data directory of a node, that holds the node's lifetime statistics.
+pub const LIFETIME_STATS_FILE_NAME: &str = "lifetime_stats.json";
+
+/// Counters of a node's activity that are carried over across restarts.
+///
+/// The totals of previous runs are read from the node's data directory on startup. The totals of
+/// the current run are added to them whenever they are read or saved, so a crash loses at most
+/// the activity since the last save.
+#[derive(Debug)]
+pub struct LifetimeStats {
+    path: PathBuf,
+    /// Totals of the previous runs.
+    previous: LifetimeStatsInfo,
+    started: Instant,
+}
+
+impl LifetimeStats {
+    /// Loads the totals of the previous runs of the node whose data directory is `node_data_dir`.
+    ///
+    /// Statistics that can't be read don't keep the node from starting: the totals start from
+    /// zero instead, and replace the unreadable file on the next save.
+    pub fn open(node_data_dir: &Path) -> Self {
+        let path = node_data_dir.join(LIFETIME_STATS_FILE_NAME);
+        let previous = match read_lifetime_stats(&path) {
+            Ok(previous) => previous.unwrap_or_default(),
+            Err(err) => {
+                warn!(
+                    %err,
+                    path = %path.display(),
+                    "Unable to read lifetime statistics, starting from zero"
+                );
+                LifetimeStatsInfo::default()
+            }
+        };
+        Self {
+            path,
+            previous,
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the totals of all runs, given the activity of the current run.
+    pub fn totals(&self, activity: &LifetimeStatsInfo) -> LifetimeStatsInfo {
+        let current = LifetimeStatsInfo {
+            uptime_secs: self.started.elapsed().as_secs(),
+            ..*activity
+        };
+        self.previous.add(&current)
+    }
+
+    /// Writes the totals of all runs, given the activity of the current run.
+    pub fn save(&self, activity: &LifetimeStatsInfo) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(&self.totals(activity))?;
+        // Write to a temporary file first, so that a crash doesn't leave a truncated file.
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Saves the lifetime statistics of a node with the activity counted by the metrics of its
+/// overlay, e.g. periodically or when the node stops.
+#[derive(Clone, Debug)]
+pub struct LifetimeStatsSaver {
+    stats: Arc<LifetimeStats>,
+    metrics: Arc<OverlayMetrics>,
+}
+
+impl LifetimeStatsSaver {
+    pub fn new(stats: Arc<LifetimeStats>, metrics: Arc<OverlayMetrics>) -> Self {
+        Self { stats, metrics }
+    }
+
+    /// Writes the totals of all runs, including the activity of the current run so far.
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.stats.save(&self.metrics.activity_totals())
+    }
+}
+
+/// Reads the lifetime statistics at `path`, or `None` if there are none.
+pub fn read_lifetime_stats(path: &Path) -> anyhow::Result<Option<LifetimeStatsInfo>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(path)?;
+    let stats = serde_json::from_str(&json)
+        .map_err(|err| anyhow!("Invalid lifetime statistics in {}: {err}", path.display()))?;
+    Ok(Some(stats))
+}
+
+/// Returns the lifetime statistics of every node that has a data directory under `root`, by data
+/// directory. Directories and statistics that can't be read are skipped with a warning.
+pub fn find_lifetime_stats(root: &Path) -> anyhow::Result<Vec<(PathBuf, LifetimeStatsInfo)>> {
+    let mut found = vec![];
+    for entry in fs::read_dir(root)? {
+        let dir = match entry {
+            Ok(entry) => entry.path(),
+            Err(err) => {
+                warn!(%err, root = %root.display(), "Unable to read data directory entry");
+                continue;
+            }
+        };
+        if !dir.is_dir() {
+            continue;
+        }
+        match read_lifetime_stats(&dir.join(LIFETIME_STATS_FILE_NAME)) {
+            Ok(Some(stats)) => found.push((dir, stats)),
+            Ok(None) => {}
+            Err(err) => {
+                warn!(%err, dir = %dir.display(), "Skipping unreadable lifetime statistics")
+            }
+        }
+    }
+    found.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(found)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn totals_carry_over_across_runs() {
+        let root = tempfile::tempdir().unwrap();
+        let node_data_dir = root.path().join("trin_0x123456");
+        fs::create_dir(&node_data_dir).unwrap();
+        let activity = LifetimeStatsInfo {
+            uptime_secs: 0,
+            content_served: 3,
+            bytes_uploaded: 1024,
+            bytes_downloaded: 2048,
+            offers_accepted: 5,
+        };
+
+        let first_run = LifetimeStats::open(&node_data_dir);
+        first_run.save(&activity).unwrap();
+
+        let second_run = LifetimeStats::open(&node_data_dir);
+        let totals = second_run.totals(&activity);
+        assert_eq!(totals.content_served, 6);
+        assert_eq!(totals.bytes_uploaded, 2048);
+        assert_eq!(totals.bytes_downloaded, 4096);
+        assert_eq!(totals.offers_accepted, 10);
+
+        let found = find_lifetime_stats(root.path()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, node_data_dir);
+        assert_eq!(found[0].1.content_served, 3);
+    }
+
+    #[test]
+    fn corrupt_stats_are_skipped() {
+        let root = tempfile::tempdir().unwrap();
+        let node_data_dir = root.path().join("trin_0x123456");
+        fs::create_dir(&node_data_dir).unwrap();
+        fs::write(
+            node_data_dir.join(LIFETIME_STATS_FILE_NAME),
+            "{\"uptimeSecs\":",
+        )
+        .unwrap();
+
+        let stats = LifetimeStats::open(&node_data_dir);
+        assert_eq!(stats.previous, LifetimeStatsInfo::default());
+        assert!(find_lifetime_stats(root.path()).unwrap().is_empty());
+    }
+}
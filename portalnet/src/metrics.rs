@@ -1,8 +1,22 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::{
+    LifetimeStatsInfo, MessageCountInfo, MessageSummaryInfo, NetworkEstimateInfo, PeerStatsInfo,
+};
+use lru::LruCache;
+use parking_lot::Mutex;
 use prometheus_exporter::{
     self,
     prometheus::{
-        opts, register_int_counter_vec, register_int_counter_vec_with_registry, IntCounterVec,
-        Registry,
+        opts, register_gauge_vec, register_gauge_vec_with_registry, register_int_counter_vec,
+        register_int_counter_vec_with_registry, GaugeVec, IntCounterVec, Registry,
     },
 };
 use tracing::error;
@@ -17,6 +31,7 @@ use crate::types::messages::{ProtocolId, Request, Response};
 
 /// Protocol Labels
 /// - These label values identify the protocol in the metrics
+#[derive(Clone, Copy)]
 pub enum ProtocolLabel {
     State,
     History,
@@ -27,6 +42,7 @@ pub enum ProtocolLabel {
 }
 
 /// Message Direction Labels
+#[derive(Clone, Copy)]
 pub enum MessageDirectionLabel {
     /// Messages sent to the network
     Sent,
@@ -47,10 +63,79 @@ pub enum MessageLabel {
     Accept,
 }
 
+/// Maximum number of peers whose activity counters are kept. The counters of the least recently
+/// active peer are dropped to make room for a new one.
+pub const PEER_STATS_CAPACITY: usize = 4096;
+
+/// Activity counters for a single peer
+#[derive(Clone, Debug, Default)]
+struct PeerStats {
+    requests_served: u64,
+    request_failures: u64,
+    utp_bytes_sent: u64,
+    utp_bytes_received: u64,
+    offers_accepted: u64,
+    offers_declined: u64,
+    /// When the peer last sent a request or a response to the local node
+    last_seen: Option<Instant>,
+}
+
+impl PeerStats {
+    /// Adds the counters of `other` to these.
+    fn add(&mut self, other: &PeerStats) {
+        self.requests_served += other.requests_served;
+        self.request_failures += other.request_failures;
+        self.utp_bytes_sent += other.utp_bytes_sent;
+        self.utp_bytes_received += other.utp_bytes_received;
+        self.offers_accepted += other.offers_accepted;
+        self.offers_declined += other.offers_declined;
+    }
+}
+
+/// The counters of the most recently active peers, along with the sum of the counters of the
+/// peers that were dropped, so that the totals over all peers are kept.
+#[derive(Debug)]
+struct PeerStatsTable {
+    peers: LruCache<NodeId, PeerStats>,
+    evicted: PeerStats,
+}
+
+impl PeerStatsTable {
+    fn new(capacity: usize) -> Self {
+        Self {
+            peers: LruCache::new(capacity),
+            evicted: PeerStats::default(),
+        }
+    }
+
+    fn update(&mut self, peer: &NodeId, update: impl FnOnce(&mut PeerStats)) {
+        if let Some(stats) = self.peers.get_mut(peer) {
+            update(stats);
+            return;
+        }
+        if self.peers.len() >= self.peers.cap() {
+            if let Some((_, stats)) = self.peers.pop_lru() {
+                self.evicted.add(&stats);
+            }
+        }
+        let mut stats = PeerStats::default();
+        update(&mut stats);
+        self.peers.put(*peer, stats);
+    }
+}
+
 /// Overlay Service Metrics Reporter
 #[derive(Clone, Debug)]
 pub struct OverlayMetrics {
     message_count: IntCounterVec,
+    content_lookup_count: IntCounterVec,
+    validation_cache_count: IntCounterVec,
+    network_estimate: GaugeVec,
+    /// Per-peer counters. These are kept in memory rather than in prometheus, to avoid a label
+    /// for every peer ever seen.
+    peer_stats: Arc<Mutex<PeerStatsTable>>,
+    /// Number of content requests answered with content.
+    content_served: Arc<AtomicU64>,
 }
 
 impl OverlayMetrics {
@@ -74,7 +159,56 @@ impl OverlayMetrics {
                 .expect("a gauge can always be added to a new custom registry, without conflict")
         });
 
-        Self { message_count }
+        let content_lookup_options = opts!(
+            "trin_content_lookup_total",
+            "count all content lookups, by whether the content was found"
+        );
+        let content_lookup_labels = &["protocol", "result"];
+        let content_lookup_count = register_int_counter_vec!(content_lookup_options.clone(), content_lookup_labels).unwrap_or_else(|_| {
+            error!("Failed to register prometheus content lookup metrics with default registry, creating new");
+
+            let custom_registry = Registry::new_custom(None, None)
+                .expect("Prometheus docs don't explain when it might fail to create a custom registry, so... hopefully never");
+            register_int_counter_vec_with_registry!(content_lookup_options, content_lookup_labels, custom_registry)
+                .expect("a gauge can always be added to a new custom registry, without conflict")
+        });
+
+        let validation_cache_options = opts!(
+            "trin_validation_cache_total",
+            "count all validations of received content, by whether the verdict was cached"
+        );
+        let validation_cache_labels = &["protocol", "result"];
+        let validation_cache_count = register_int_counter_vec!(validation_cache_options.clone(), validation_cache_labels).unwrap_or_else(|_| {
+            error!("Failed to register prometheus validation cache metrics with default registry, creating new");
+
+            let custom_registry = Registry::new_custom(None, None)
+                .expect("Prometheus docs don't explain when it might fail to create a custom registry, so... hopefully never");
+            register_int_counter_vec_with_registry!(validation_cache_options, validation_cache_labels, custom_registry)
+                .expect("a gauge can always be added to a new custom registry, without conflict")
+        });
+
+        let network_estimate_options = opts!(
+            "trin_network_estimate",
+            "estimates of the network size and replication, from the radii of routing table peers"
+        );
+        let network_estimate_labels = &["protocol", "quantity"];
+        let network_estimate = register_gauge_vec!(network_estimate_options.clone(), network_estimate_labels).unwrap_or_else(|_| {
+            error!("Failed to register prometheus network estimate metrics with default registry, creating new");
+
+            let custom_registry = Registry::new_custom(None, None)
+                .expect("Prometheus docs don't explain when it might fail to create a custom registry, so... hopefully never");
+            register_gauge_vec_with_registry!(network_estimate_options, network_estimate_labels, custom_registry)
+                .expect("a gauge can always be added to a new custom registry, without conflict")
+        });
+
+        Self {
+            message_count,
+            content_lookup_count,
+            validation_cache_count,
+            network_estimate,
+            peer_stats: Arc::new(Mutex::new(PeerStatsTable::new(PEER_STATS_CAPACITY))),
+            content_served: Arc::new(AtomicU64::new(0)),
+        }
     }
 
     /// Returns the value of the given metric with the specified labels.
@@ -88,6 +222,75 @@ impl OverlayMetrics {
         self.message_count.with_label_values(&labels).get()
     }
 
+    pub fn report_content_lookup(&self, protocol: &ProtocolId, found: bool) {
+        let result = if found { "found" } else { "not_found" };
+        let labels = [ProtocolLabel::from(protocol).into(), result];
+        self.content_lookup_count.with_label_values(&labels).inc();
+    }
+
+    /// Returns the number of content lookups on the given protocol, and how many of them found
+    /// the content.
+    pub fn content_lookup_counts(&self, protocol: &ProtocolId) -> (u64, u64) {
+        let protocol: MetricLabel = ProtocolLabel::from(protocol).into();
+        let found = self
+            .content_lookup_count
+            .with_label_values(&[protocol, "found"])
+            .get();
+        let not_found = self
+            .content_lookup_count
+            .with_label_values(&[protocol, "not_found"])
+            .get();
+        (found + not_found, found)
+    }
+
+    /// Records whether the verdict of a content validation was served from the validation cache.
+    pub fn report_validation_cache(&self, protocol: &ProtocolId, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        let labels = [ProtocolLabel::from(protocol).into(), result];
+        self.validation_cache_count.with_label_values(&labels).inc();
+    }
+
+    /// Returns the number of validations on the given protocol, and how many of them were served
+    /// from the validation cache.
+    pub fn validation_cache_counts(&self, protocol: &ProtocolId) -> (u64, u64) {
+        let protocol: MetricLabel = ProtocolLabel::from(protocol).into();
+        let hits = self
+            .validation_cache_count
+            .with_label_values(&[protocol, "hit"])
+            .get();
+        let misses = self
+            .validation_cache_count
+            .with_label_values(&[protocol, "miss"])
+            .get();
+        (hits + misses, hits)
+    }
+
+    /// Returns the number of messages of each type sent and received on the given protocol.
+    pub fn message_summary(&self, protocol: &ProtocolId) -> MessageSummaryInfo {
+        MessageSummaryInfo {
+            sent: self.message_counts(protocol.into(), MessageDirectionLabel::Sent),
+            received: self.message_counts(protocol.into(), MessageDirectionLabel::Received),
+        }
+    }
+
+    fn message_counts(
+        &self,
+        network: ProtocolLabel,
+        direction: MessageDirectionLabel,
+    ) -> MessageCountInfo {
+        let count = |message_name| self.message_count_by_labels(network, direction, message_name);
+        MessageCountInfo {
+            ping: count(MessageLabel::Ping),
+            pong: count(MessageLabel::Pong),
+            find_nodes: count(MessageLabel::FindNodes),
+            nodes: count(MessageLabel::Nodes),
+            find_content: count(MessageLabel::FindContent),
+            content: count(MessageLabel::Content),
+            offer: count(MessageLabel::Offer),
+            accept: count(MessageLabel::Accept),
+        }
+    }
+
     pub fn report_outbound_request(&self, protocol: &ProtocolId, request: &Request) {
         self.increment_message_count(protocol.into(), MessageDirectionLabel::Sent, request.into());
     }
@@ -116,6 +319,108 @@ impl OverlayMetrics {
         );
     }
 
+    pub fn report_network_estimate(&self, protocol: &ProtocolId, estimate: &NetworkEstimateInfo) {
+        let protocol: MetricLabel = ProtocolLabel::from(protocol).into();
+        let quantities = [
+            ("peer_count", Some(estimate.peer_count as f64)),
+            ("mean_radius_percent", Some(estimate.mean_radius_percent)),
+            ("network_size", estimate.estimated_network_size),
+            ("replication", estimate.estimated_replication),
+        ];
+        for (quantity, value) in quantities {
+            if let Some(value) = value {
+                self.network_estimate
+                    .with_label_values(&[protocol, quantity])
+                    .set(value);
+            }
+        }
+    }
+
+    pub fn report_request_served(&self, peer: &NodeId) {
+        self.update_peer_stats(peer, |stats| stats.requests_served += 1);
+    }
+
+    /// Records that a request or a response was received from `peer`.
+    pub fn report_peer_seen(&self, peer: &NodeId) {
+        self.update_peer_stats(peer, |stats| stats.last_seen = Some(Instant::now()));
+    }
+
+    /// Returns when a request or a response was last received from `peer`.
+    pub fn last_seen(&self, peer: &NodeId) -> Option<Instant> {
+        self.peer_stats
+            .lock()
+            .peers
+            .peek(peer)
+            .and_then(|stats| stats.last_seen)
+    }
+
+    pub fn report_request_failure(&self, peer: &NodeId) {
+        self.update_peer_stats(peer, |stats| stats.request_failures += 1);
+    }
+
+    pub fn report_utp_bytes_sent(&self, peer: &NodeId, bytes: usize) {
+        self.update_peer_stats(peer, |stats| stats.utp_bytes_sent += bytes as u64);
+    }
+
+    pub fn report_utp_bytes_received(&self, peer: &NodeId, bytes: usize) {
+        self.update_peer_stats(peer, |stats| stats.utp_bytes_received += bytes as u64);
+    }
+
+    /// Records how many of the content items offered to `peer` were accepted and declined.
+    pub fn report_offer_accept(&self, peer: &NodeId, accepted: usize, declined: usize) {
+        self.update_peer_stats(peer, |stats| {
+            stats.offers_accepted += accepted as u64;
+            stats.offers_declined += declined as u64;
+        });
+    }
+
+    /// Returns the counters of the peers the local node has most recently interacted with, up to
+    /// `PEER_STATS_CAPACITY`, ordered by node ID.
+    pub fn peer_stats(&self) -> Vec<PeerStatsInfo> {
+        let mut peer_stats: Vec<PeerStatsInfo> = self
+            .peer_stats
+            .lock()
+            .peers
+            .iter()
+            .map(|(node_id, stats)| PeerStatsInfo {
+                node_id: node_id.raw().into(),
+                requests_served: stats.requests_served,
+                request_failures: stats.request_failures,
+                utp_bytes_sent: stats.utp_bytes_sent,
+                utp_bytes_received: stats.utp_bytes_received,
+                offers_accepted: stats.offers_accepted,
+                offers_declined: stats.offers_declined,
+            })
+            .collect();
+        peer_stats.sort_by_key(|stats| stats.node_id.0);
+        peer_stats
+    }
+
+    pub fn report_content_served(&self) {
+        self.content_served.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the activity of the local node since the metrics were created, summed over all
+    /// peers. The uptime is left at zero.
+    pub fn activity_totals(&self) -> LifetimeStatsInfo {
+        let peer_stats = self.peer_stats.lock();
+        let mut totals = peer_stats.evicted.clone();
+        for (_, stats) in peer_stats.peers.iter() {
+            totals.add(stats);
+        }
+        LifetimeStatsInfo {
+            uptime_secs: 0,
+            content_served: self.content_served.load(Ordering::Relaxed),
+            bytes_uploaded: totals.utp_bytes_sent,
+            bytes_downloaded: totals.utp_bytes_received,
+            offers_accepted: totals.offers_accepted,
+        }
+    }
+
+    fn update_peer_stats(&self, peer: &NodeId, update: impl FnOnce(&mut PeerStats)) {
+        self.peer_stats.lock().update(peer, update);
+    }
+
     fn increment_message_count(
         &self,
         protocol: ProtocolLabel,
@@ -208,3 +513,65 @@ impl Default for OverlayMetrics {
         Self::new()
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn peer_stats_are_tracked_per_peer() {
+        let metrics = OverlayMetrics::new();
+        let peer_a = NodeId::random();
+        let peer_b = NodeId::random();
+
+        metrics.report_request_served(&peer_a);
+        metrics.report_request_served(&peer_a);
+        metrics.report_utp_bytes_sent(&peer_a, 100);
+        metrics.report_offer_accept(&peer_a, 2, 1);
+        metrics.report_request_failure(&peer_b);
+        metrics.report_utp_bytes_received(&peer_b, 50);
+
+        metrics.report_content_served();
+
+        let totals = metrics.activity_totals();
+        assert_eq!(totals.content_served, 1);
+        assert_eq!(totals.bytes_uploaded, 100);
+        assert_eq!(totals.bytes_downloaded, 50);
+        assert_eq!(totals.offers_accepted, 2);
+
+        let peer_stats = metrics.peer_stats();
+        assert_eq!(peer_stats.len(), 2);
+
+        let stats_a = peer_stats
+            .iter()
+            .find(|stats| stats.node_id.0 == peer_a.raw())
+            .unwrap();
+        assert_eq!(stats_a.requests_served, 2);
+        assert_eq!(stats_a.request_failures, 0);
+        assert_eq!(stats_a.utp_bytes_sent, 100);
+        assert_eq!(stats_a.offers_accepted, 2);
+        assert_eq!(stats_a.offers_declined, 1);
+
+        let stats_b = peer_stats
+            .iter()
+            .find(|stats| stats.node_id.0 == peer_b.raw())
+            .unwrap();
+        assert_eq!(stats_b.requests_served, 0);
+        assert_eq!(stats_b.request_failures, 1);
+        assert_eq!(stats_b.utp_bytes_received, 50);
+    }
+
+    #[test]
+    fn evicted_peers_count_towards_totals() {
+        let mut table = PeerStatsTable::new(2);
+        let peers: Vec<NodeId> = (0..3).map(|_| NodeId::random()).collect();
+        for peer in &peers {
+            table.update(peer, |stats| stats.utp_bytes_sent += 10);
+        }
+
+        assert_eq!(table.peers.len(), 2);
+        assert!(!table.peers.contains(&peers[0]));
+        assert_eq!(table.evicted.utp_bytes_sent, 10);
+    }
+}
@@ -0,0 +1,192 @@
+//! Offers stored content to peers that join the routing table nearby, so that a newly started
+//! node fills its store from its neighbors instead of waiting for new content to be gossiped.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use discv5::enr::NodeId;
+use futures::channel::oneshot;
+use lru::LruCache;
+use parking_lot::RwLock;
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+    time,
+};
+use tracing::{debug, warn, Instrument};
+
+use crate::{
+    overlay_service::{OverlayCommand, OverlayRequest, RequestDirection},
+    storage::ContentStore,
+    types::{
+        messages::{Offer, PopulatedOffer, Request, Response},
+        node::Node,
+    },
+};
+use trin_types::content_key::{OverlayContentKey, RawContentKey};
+use trin_types::distance::{Distance, Metric, XorMetric};
+use trin_types::enr::Enr;
+
+/// Time between the offers made to a peer, which limits the rate at which a new neighbor is sent
+/// content.
+const OFFER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time after which a peer that rejoins the routing table is offered content again.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Number of peers whose last sync is remembered.
+const SYNCED_PEERS_CAPACITY: usize = 1024;
+
+/// Spawns the task that offers the content within the radius of each new neighbor to it, and
+/// returns the channel on which nodes that joined the routing table are passed to the task,
+/// along with the task.
+///
+/// Peers are synced one at a time, with at most `max_content_keys` content keys offered to each.
+pub(crate) fn spawn_neighborhood_sync<TContentKey, TStore>(
+    store: Arc<RwLock<TStore>>,
+    command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+    local_node_id: NodeId,
+    max_content_keys: usize,
+) -> (UnboundedSender<Node>, JoinHandle<()>)
+where
+    TContentKey: 'static + OverlayContentKey + Send + Sync,
+    TStore: 'static + ContentStore + Send + Sync,
+{
+    let (node_tx, mut node_rx) = mpsc::unbounded_channel::<Node>();
+    let task = tokio::spawn(
+        async move {
+            let mut synced: LruCache<NodeId, Instant> = LruCache::new(SYNCED_PEERS_CAPACITY);
+            while let Some(node) = node_rx.recv().await {
+                let peer = node.enr().node_id();
+                if matches!(synced.get(&peer), Some(synced_at) if synced_at.elapsed() < RESYNC_INTERVAL)
+                {
+                    continue;
+                }
+                let local_radius = store.read().radius();
+                if !neighborhoods_overlap(&local_node_id, local_radius, &peer, node.data_radius())
+                {
+                    continue;
+                }
+                let content_keys =
+                    store
+                        .read()
+                        .content_keys_within(&peer, node.data_radius(), max_content_keys);
+                // The content database may be shared with other subnetworks, whose content is
+                // left out.
+                let content_keys: Vec<RawContentKey> = match content_keys {
+                    Ok(content_keys) => content_keys
+                        .into_iter()
+                        .filter(|key| TContentKey::try_from(key.clone()).is_ok())
+                        .collect(),
+                    Err(err) => {
+                        warn!(%err, %peer, "Unable to find content to sync with new neighbor");
+                        continue;
+                    }
+                };
+                synced.put(peer, Instant::now());
+                if !content_keys.is_empty() {
+                    sync_peer(&command_tx, node.enr(), content_keys).await;
+                }
+            }
+        }
+        .in_current_span(),
+    );
+    (node_tx, task)
+}
+
+/// Returns whether the content within `radius` of `node_id` may overlap the content within
+/// `peer_radius` of `peer`.
+fn neighborhoods_overlap(
+    node_id: &NodeId,
+    radius: Distance,
+    peer: &NodeId,
+    peer_radius: Distance,
+) -> bool {
+    let distance = XorMetric::distance(&node_id.raw(), &peer.raw());
+    *distance <= radius.saturating_add(*peer_radius)
+}
+
+/// Offers `content_keys` to the peer at `enr`, one offer every `OFFER_INTERVAL`. Stops early if
+/// the peer fails to answer.
+async fn sync_peer<TContentKey>(
+    command_tx: &UnboundedSender<OverlayCommand<TContentKey>>,
+    enr: Enr,
+    content_keys: Vec<RawContentKey>,
+) {
+    let peer = enr.node_id();
+    let offers = PopulatedOffer {
+        content_items: content_keys.into_iter().map(|key| (key, vec![])).collect(),
+    }
+    .split();
+    let (mut offered, mut accepted) = (0, 0);
+    for (index, offer) in offers.into_iter().enumerate() {
+        if index > 0 {
+            time::sleep(OFFER_INTERVAL).await;
+        }
+        let offer = Offer::from(offer);
+        offered += offer.content_keys.len();
+        let (tx, rx) = oneshot::channel();
+        let request = OverlayRequest::new(
+            Request::Offer(offer),
+            RequestDirection::Outgoing {
+                destination: enr.clone(),
+            },
+            Some(tx),
+            None,
+        );
+        if command_tx.send(OverlayCommand::Request(request)).is_err() {
+            return;
+        }
+        match rx.await {
+            Ok(Ok(Response::Accept(accept))) => accepted += accept.content_keys.num_set_bits(),
+            Ok(Ok(_)) => {
+                debug!(%peer, "Invalid response to neighborhood sync offer");
+                break;
+            }
+            Ok(Err(err)) => {
+                debug!(%err, %peer, "Neighborhood sync offer failed");
+                break;
+            }
+            Err(_) => return,
+        }
+    }
+    debug!(%peer, offered, accepted, "Synced content with new neighbor");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethereum_types::U256;
+
+    #[test]
+    fn test_neighborhoods_overlap() {
+        let node_id = NodeId::new(&[0x00; 32]);
+        let mut peer = [0x00; 32];
+        peer[31] = 0x10;
+        let peer = NodeId::new(&peer);
+
+        let radius = |radius: u64| Distance::from(U256::from(radius));
+        assert!(neighborhoods_overlap(&node_id, radius(8), &peer, radius(8)));
+        assert!(!neighborhoods_overlap(
+            &node_id,
+            radius(8),
+            &peer,
+            radius(7)
+        ));
+        assert!(neighborhoods_overlap(
+            &node_id,
+            Distance::MAX,
+            &peer,
+            Distance::MAX
+        ));
+        assert!(!neighborhoods_overlap(
+            &node_id,
+            Distance::ZERO,
+            &peer,
+            Distance::ZERO
+        ));
+    }
+}
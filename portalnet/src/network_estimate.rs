@@ -0,0 +1,125 @@
+//! Estimates of the size and storage of the network, from the data radii advertised by peers.
+//!
+//! All estimates are from the vantage point of the local node, and assume that node ids and
+//! content ids are uniformly distributed and that a node's radius is independent of its position.
+
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::NetworkEstimateInfo;
+use trin_types::distance::{Distance, Metric, XorMetric};
+
+use crate::storage::radius_ratio;
+
+/// Number of peers closest to the local node used to estimate the network size.
+const NETWORK_SIZE_SAMPLE: usize = 8;
+
+/// Estimates the network from the node ids and data radii of the peers known to the local node.
+pub fn estimate_network(
+    local_node_id: NodeId,
+    peers: &[(NodeId, Distance)],
+) -> NetworkEstimateInfo {
+    let mut radius_ratios: Vec<f64> = peers
+        .iter()
+        .map(|(_, radius)| radius_ratio(*radius))
+        .collect();
+    radius_ratios.sort_by(f64::total_cmp);
+    let mean_radius_ratio = match radius_ratios.len() {
+        0 => 0.0,
+        count => radius_ratios.iter().sum::<f64>() / count as f64,
+    };
+    let median_radius_ratio = match radius_ratios.len() {
+        0 => 0.0,
+        count if count % 2 == 0 => (radius_ratios[count / 2 - 1] + radius_ratios[count / 2]) / 2.0,
+        count => radius_ratios[count / 2],
+    };
+
+    // With N nodes spread uniformly over the key space, the k-th closest peer is expected at a
+    // distance covering k / N of the key space.
+    let mut peer_distances: Vec<Distance> = peers
+        .iter()
+        .map(|(node_id, _)| XorMetric::distance(&local_node_id.raw(), &node_id.raw()))
+        .collect();
+    peer_distances.sort();
+    let sample = peer_distances.len().min(NETWORK_SIZE_SAMPLE);
+    let estimated_network_size = match sample {
+        0 => None,
+        sample => {
+            // The fraction of the key space within a distance is computed like that of a radius.
+            let ratio = radius_ratio(peer_distances[sample - 1]);
+            (ratio > 0.0).then(|| (sample as f64 / ratio).max(peers.len() as f64 + 1.0))
+        }
+    };
+
+    NetworkEstimateInfo {
+        peer_count: peers.len() as u64,
+        mean_radius_percent: mean_radius_ratio * 100.0,
+        median_radius_percent: median_radius_ratio * 100.0,
+        estimated_network_size,
+        estimated_replication: estimated_network_size.map(|size| size * mean_radius_ratio),
+        estimated_content_kb: None,
+        estimated_network_storage_kb: None,
+    }
+}
+
+/// Adds storage estimates to `estimate`, extrapolating the density of content within the local
+/// node's radius to the whole key space.
+pub fn add_local_storage(
+    estimate: &mut NetworkEstimateInfo,
+    local_radius_ratio: f64,
+    local_content_kb: f64,
+) {
+    if local_radius_ratio <= 0.0 {
+        return;
+    }
+    let content_kb = local_content_kb / local_radius_ratio;
+    estimate.estimated_content_kb = Some(content_kb);
+    estimate.estimated_network_storage_kb = estimate
+        .estimated_replication
+        .map(|replication| content_kb * replication);
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use ethereum_types::U256;
+
+    fn node_id_at_ratio(ratio_denominator: u64) -> NodeId {
+        let mut raw = [0u8; 32];
+        raw[..8].copy_from_slice(&(u64::MAX / ratio_denominator).to_be_bytes());
+        NodeId::new(&raw)
+    }
+
+    #[test]
+    fn estimate_without_peers() {
+        let estimate = estimate_network(NodeId::new(&[0u8; 32]), &[]);
+        assert_eq!(estimate.peer_count, 0);
+        assert_eq!(estimate.estimated_network_size, None);
+        assert_eq!(estimate.estimated_replication, None);
+    }
+
+    #[test]
+    fn estimate_from_evenly_spread_peers() {
+        let local_node_id = NodeId::new(&[0u8; 32]);
+        // The k-th closest of 8 peers sits at k / 128 of the key space, as in a network of 128.
+        let peers: Vec<(NodeId, Distance)> = (1..=8)
+            .map(|k| {
+                let node_id = node_id_at_ratio(128 / k);
+                (node_id, Distance::from(U256::MAX / 4))
+            })
+            .collect();
+
+        let mut estimate = estimate_network(local_node_id, &peers);
+        assert_eq!(estimate.peer_count, 8);
+        assert!((estimate.mean_radius_percent - 25.0).abs() < 1e-6);
+        assert!((estimate.median_radius_percent - 25.0).abs() < 1e-6);
+        let network_size = estimate.estimated_network_size.unwrap();
+        assert!((network_size - 128.0).abs() < 1e-6);
+        let replication = estimate.estimated_replication.unwrap();
+        assert!((replication - 32.0).abs() < 1e-6);
+
+        add_local_storage(&mut estimate, 0.25, 1000.0);
+        assert!((estimate.estimated_content_kb.unwrap() - 4000.0).abs() < 1e-6);
+        assert!((estimate.estimated_network_storage_kb.unwrap() - 128_000.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,185 @@
+use std::{sync::Arc, time::Duration};
+
+use discv5::enr::NodeId;
+use parking_lot::RwLock;
+use tokio::sync::broadcast;
+
+use crate::types::messages::ProtocolId;
+use trin_types::content_key::RawContentKey;
+use trin_types::distance::Distance;
+
+/// Number of events buffered for each subscriber. A subscriber that falls further behind misses
+/// the oldest events.
+pub const NODE_EVENTS_CAPACITY: usize = 1024;
+
+/// Activity of the node that applications embedding it may react to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeEvent {
+    /// A peer was inserted into the routing table of an overlay network.
+    PeerAdded { protocol: ProtocolId, peer: NodeId },
+    /// A peer was evicted from the routing table of an overlay network.
+    PeerRemoved { protocol: ProtocolId, peer: NodeId },
+    /// Content received from the network was validated and stored.
+    ContentStored {
+        protocol: ProtocolId,
+        content_key: RawContentKey,
+    },
+    /// Content offered by a peer was received, before it is validated.
+    GossipReceived {
+        protocol: ProtocolId,
+        peer: NodeId,
+        content_keys: Vec<RawContentKey>,
+    },
+    /// Content failed validation.
+    ValidationFailed {
+        protocol: ProtocolId,
+        content_key: RawContentKey,
+        error: String,
+    },
+    /// The data radius of an overlay network changed.
+    RadiusChanged {
+        protocol: ProtocolId,
+        radius: Distance,
+    },
+    /// A node or content lookup finished, successfully or not. Only emitted for the lookups
+    /// sampled by `NodeEvents::sample_lookup`.
+    LookupFinished {
+        protocol: ProtocolId,
+        kind: LookupKind,
+        /// The node ID or content ID that was looked up.
+        target: [u8; 32],
+        /// Whether the content, or the target node, was found.
+        success: bool,
+        elapsed: Duration,
+        /// The requests to peers made by the lookup, in the order in which they completed.
+        hops: Vec<LookupHop>,
+    },
+    /// Content was gossiped to peers whose radius contains it. Only emitted for the gossip
+    /// decisions sampled by `NodeEvents::sample_gossip`.
+    GossipSent {
+        protocol: ProtocolId,
+        content_id: [u8; 32],
+        /// Number of connected peers whose radius contains the content.
+        interested: usize,
+        /// The peers that were offered the content, and their distance to it.
+        recipients: Vec<(NodeId, Distance)>,
+    },
+}
+
+/// The kind of a lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LookupKind {
+    Node,
+    Content,
+}
+
+/// A request to a peer made by a lookup.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupHop {
+    pub peer: NodeId,
+    /// Distance of the peer to the target of the lookup.
+    pub distance: Distance,
+    /// Time between sending the request and receiving the response, or the request failing.
+    pub latency: Duration,
+    /// Whether the peer responded.
+    pub success: bool,
+}
+
+/// Fractions of the lookups and gossip decisions that events are emitted for, between 0 and 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SampleRates {
+    lookup: f64,
+    gossip: f64,
+}
+
+/// A broadcast channel of `NodeEvent`s. Clones share the same channel and sample rates.
+#[derive(Clone, Debug)]
+pub struct NodeEvents {
+    tx: broadcast::Sender<NodeEvent>,
+    /// Lookup and gossip events are costly to build, so they are only built for a sample of
+    /// lookups and gossip decisions. None are sampled until the rates are set.
+    sample_rates: Arc<RwLock<SampleRates>>,
+}
+
+impl NodeEvents {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self {
+            tx,
+            sample_rates: Arc::new(RwLock::new(SampleRates::default())),
+        }
+    }
+
+    /// Sets the fractions of lookups and gossip decisions that `LookupFinished` and `GossipSent`
+    /// events are emitted for.
+    pub fn set_sample_rates(&self, lookup_sample_rate: f64, gossip_sample_rate: f64) {
+        *self.sample_rates.write() = SampleRates {
+            lookup: lookup_sample_rate,
+            gossip: gossip_sample_rate,
+        };
+    }
+
+    /// Returns whether a `LookupFinished` event should be built for a new lookup.
+    pub fn sample_lookup(&self) -> bool {
+        is_sampled(self.sample_rates.read().lookup)
+    }
+
+    /// Returns whether a `GossipSent` event should be built for a gossip decision.
+    pub fn sample_gossip(&self) -> bool {
+        is_sampled(self.sample_rates.read().gossip)
+    }
+
+    /// Returns a receiver of the events emitted from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Sends `event` to all subscribers. Events are dropped if there are no subscribers.
+    pub fn emit(&self, event: NodeEvent) {
+        let _ = self.tx.send(event);
+    }
+}
+
+impl Default for NodeEvents {
+    fn default() -> Self {
+        Self::new(NODE_EVENTS_CAPACITY)
+    }
+}
+
+fn is_sampled(sample_rate: f64) -> bool {
+    sample_rate > 0.0 && rand::random::<f64>() < sample_rate
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_receive_events_emitted_after_subscribing() {
+        let events = NodeEvents::default();
+        let event = NodeEvent::RadiusChanged {
+            protocol: ProtocolId::History,
+            radius: Distance::MAX,
+        };
+        // Without subscribers the event is dropped.
+        events.emit(event.clone());
+
+        let mut rx = events.clone().subscribe();
+        events.emit(event.clone());
+        assert_eq!(rx.recv().await.unwrap(), event);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn sampling() {
+        let events = NodeEvents::default();
+        assert!(!events.sample_lookup());
+        assert!(!events.sample_gossip());
+
+        // Clones share the sample rates.
+        events.clone().set_sample_rates(1.0, 0.0);
+        assert!(events.sample_lookup());
+        assert!(!events.sample_gossip());
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     fmt::{Debug, Display},
     marker::{PhantomData, Sync},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use discv5::{
@@ -11,19 +11,24 @@ use discv5::{
     kbucket::{Filter, KBucketsTable, NodeStatus, MAX_NODES_PER_BUCKET},
     TalkRequest,
 };
-use futures::channel::oneshot;
-use parking_lot::RwLock;
-use ssz::Encode;
-use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, error, info, warn};
+use futures::{channel::oneshot, future::join_all};
+use parking_lot::{Mutex, RwLock};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+use tracing::{debug, debug_span, error, field, info, info_span, warn, Instrument, Span};
 use utp_rs::socket::UtpSocket;
 
 use crate::{
     discovery::{Discovery, UtpEnr},
+    find::{
+        lookup_cache::{LookupCache, NEGATIVE_LOOKUP_CACHE_TTL},
+        query_info::{FindContentResult, LookupError},
+    },
     metrics::{MessageDirectionLabel, MessageLabel, OverlayMetrics, ProtocolLabel},
+    network_estimate::estimate_network,
+    node_events::NodeEvents,
     overlay_service::{
         OverlayCommand, OverlayRequest, OverlayRequestError, OverlayService, RequestDirection,
-        UTP_CONN_CFG,
+        DEFAULT_BUCKET_REFRESH_INTERVAL, EXPECTED_NON_EMPTY_BUCKETS, UTP_CONN_CFG,
     },
     storage::ContentStore,
     types::{
@@ -33,8 +38,20 @@ use crate::{
         },
         node::Node,
     },
+    utp_transfers::UtpTransfers,
+    validation_cache::ValidationCache,
+};
+use ethportal_api::{
+    types::{
+        ping_extensions::{PeerCapabilitiesInfo, PingExtensions, PingPayload},
+        portal::{
+            LifetimeStatsInfo, MessageSummaryInfo, NetworkEstimateInfo, PeerStatsInfo, ReplicaInfo,
+            ReplicationInfo, TransferDirection, UtpTransferInfo,
+        },
+    },
+    OverlayContentKey,
 };
-use ethportal_api::OverlayContentKey;
+use trin_types::cli::{TalkReqKind, TalkReqTimeout};
 use trin_types::content_key::RawContentKey;
 use trin_types::distance::{Distance, Metric};
 use trin_types::enr::Enr;
@@ -53,8 +70,42 @@ pub struct OverlayConfig {
     pub query_parallelism: usize,
     pub query_timeout: Duration,
     pub query_peer_timeout: Duration,
+    /// Number of times a peer is requested during a content lookup before it is abandoned.
+    pub query_peer_attempts: usize,
     pub query_num_results: usize,
     pub findnodes_query_distances_per_peer: usize,
+    /// Extensions to advertise in the custom payload of PING and PONG messages. No extensions
+    /// are sent by default, because not all clients accept a custom payload beyond the data
+    /// radius.
+    pub ping_extensions: Option<PingExtensions>,
+    /// Interval between random lookups to refresh sparse buckets of the routing table.
+    pub bucket_refresh_interval: Duration,
+    /// Maximum number of nodes per bucket of the routing table, up to `MAX_NODES_PER_BUCKET`.
+    /// Nodes that do not fit in a smaller bucket are rejected rather than kept as pending entries.
+    pub bucket_size: usize,
+    /// Number of buckets farthest from the local node that are expected to hold nodes, between 1
+    /// and 255. These buckets are looked up when the routing table is initialized and refreshed.
+    pub table_depth: usize,
+    /// Timeouts of outbound requests, by kind of request.
+    pub request_timeouts: RequestTimeouts,
+    /// Number of interested peers that gossiped content is offered to.
+    pub gossip_fanout: GossipFanout,
+    /// Maximum number of recent content lookup results to cache. Zero disables the cache.
+    pub lookup_cache_size: usize,
+    /// Time for which found content is served from the lookup cache. Lookups that did not find
+    /// the content are cached for at most `NEGATIVE_LOOKUP_CACHE_TTL`.
+    pub lookup_cache_ttl: Duration,
+    /// Maximum number of content validation verdicts to cache. Zero disables the cache.
+    pub validation_cache_size: usize,
+    /// Maximum number of uTP streams over which the content accepted from one offer is sent
+    /// concurrently, one item per stream. With a limit of one, all content is sent over a single
+    /// stream, which is what peers other than trin expect.
+    pub utp_transfer_limit: usize,
+    /// Maximum number of content keys within the radius of a peer that joins the routing table
+    /// to offer it, closest first. Zero disables the offers.
+    pub neighborhood_sync_limit: usize,
+    /// Bus on which the overlay emits its activity.
+    pub events: NodeEvents,
 }
 
 impl Default for OverlayConfig {
@@ -68,14 +119,131 @@ impl Default for OverlayConfig {
             ping_queue_interval: None,
             query_parallelism: 3, // (recommended α from kademlia paper)
             query_peer_timeout: Duration::from_secs(10),
+            query_peer_attempts: 1,
             query_timeout: Duration::from_secs(60),
             query_num_results: MAX_NODES_PER_BUCKET,
             findnodes_query_distances_per_peer: 3,
+            ping_extensions: None,
+            bucket_refresh_interval: DEFAULT_BUCKET_REFRESH_INTERVAL,
+            bucket_size: MAX_NODES_PER_BUCKET,
+            table_depth: EXPECTED_NON_EMPTY_BUCKETS,
+            request_timeouts: RequestTimeouts::default(),
+            gossip_fanout: GossipFanout::default(),
+            lookup_cache_size: 256,
+            lookup_cache_ttl: Duration::from_secs(30),
+            validation_cache_size: 1024,
+            utp_transfer_limit: 1,
+            neighborhood_sync_limit: 256,
+            events: NodeEvents::default(),
+        }
+    }
+}
+
+/// Timeouts of outbound overlay requests, by kind of request. Content lookups and offers may take
+/// the peer longer to answer than a liveness check, so they are given more time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequestTimeouts {
+    pub ping: Duration,
+    pub find_nodes: Duration,
+    pub find_content: Duration,
+    pub offer: Duration,
+}
+
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        Self {
+            ping: Duration::from_secs(2),
+            find_nodes: Duration::from_secs(3),
+            find_content: Duration::from_secs(5),
+            offer: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RequestTimeouts {
+    /// Returns the timeouts with those configured for `subnetwork` in `overrides` applied.
+    pub fn with_overrides(mut self, subnetwork: &str, overrides: &[TalkReqTimeout]) -> Self {
+        for timeout in overrides.iter().filter(|t| t.subnetwork == subnetwork) {
+            match timeout.kind {
+                TalkReqKind::Ping => self.ping = timeout.timeout,
+                TalkReqKind::FindNodes => self.find_nodes = timeout.timeout,
+                TalkReqKind::FindContent => self.find_content = timeout.timeout,
+                TalkReqKind::Offer => self.offer = timeout.timeout,
+            }
         }
+        self
+    }
+
+    /// Returns the timeout for `request`.
+    pub fn for_request(&self, request: &Request) -> Duration {
+        match request {
+            Request::Ping(_) => self.ping,
+            Request::FindNodes(_) => self.find_nodes,
+            Request::FindContent(_) => self.find_content,
+            Request::Offer(_) | Request::PopulatedOffer(_) => self.offer,
+        }
+    }
+
+    /// Returns the longest of the timeouts.
+    pub fn max(&self) -> Duration {
+        self.ping
+            .max(self.find_nodes)
+            .max(self.find_content)
+            .max(self.offer)
     }
 }
 
-type BucketEntry = (NodeId, Enr, NodeStatus, Distance, Option<String>);
+/// Number of interested peers that content is offered to when it is gossiped. Content is offered
+/// to the `closest` peers to the content, and to `random` peers drawn from the remaining
+/// interested peers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GossipFanout {
+    pub closest: usize,
+    pub random: usize,
+}
+
+impl Default for GossipFanout {
+    fn default() -> Self {
+        Self {
+            closest: 4,
+            random: 4,
+        }
+    }
+}
+
+/// Bucket filter that limits the number of nodes in a bucket below `MAX_NODES_PER_BUCKET`, on top
+/// of any other bucket filter.
+#[derive(Clone)]
+struct BucketSizeFilter {
+    max_nodes: usize,
+    inner: Option<Box<dyn Filter<Node>>>,
+}
+
+impl Filter<Node> for BucketSizeFilter {
+    fn filter(&self, value: &Node, other_vals: &mut dyn Iterator<Item = &Node>) -> bool {
+        let other_vals: Vec<&Node> = other_vals
+            .filter(|node| node.enr.node_id() != value.enr.node_id())
+            .collect();
+        if other_vals.len() >= self.max_nodes {
+            return false;
+        }
+        match &self.inner {
+            Some(inner) => inner.filter(value, &mut other_vals.into_iter()),
+            None => true,
+        }
+    }
+}
+
+/// A routing table entry: the node's ID, ENR, status, radius, client info and the time since
+/// it last sent a request or a response.
+type BucketEntry = (
+    NodeId,
+    Enr,
+    NodeStatus,
+    Distance,
+    Option<String>,
+    Option<Duration>,
+);
 
 /// Overlay protocol is a layer on top of discv5 that handles all requests from the overlay networks
 /// (state, history etc.) and dispatch them to the discv5 protocol TalkReq. Each network should
@@ -93,6 +261,8 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     protocol: ProtocolId,
     /// A sender to send commands to the OverlayService.
     command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+    /// The OverlayService and the tasks it spawned, until taken by the owner of the node.
+    service_tasks: Mutex<Vec<JoinHandle<()>>>,
     /// uTP socket.
     utp_socket: Arc<UtpSocket<UtpEnr>>,
     /// Declare the allowed content key types for a given overlay network.
@@ -106,6 +276,18 @@ pub struct OverlayProtocol<TContentKey, TMetric, TValidator, TStore> {
     validator: Arc<TValidator>,
     /// Runtime telemetry metrics for the overlay network.
     metrics: Arc<OverlayMetrics>,
+    /// Extensions advertised in the custom payload of outgoing PING messages.
+    ping_extensions: Option<PingExtensions>,
+    /// Results of recent content lookups, if caching is enabled.
+    lookup_cache: Option<RwLock<LookupCache>>,
+    /// Cache of the verdicts of content validations, shared with the overlay service.
+    validation_cache: Arc<ValidationCache>,
+    /// uTP transfers in progress, shared with the overlay service.
+    utp_transfers: Arc<UtpTransfers>,
+    /// Number of interested peers that gossiped content is offered to.
+    gossip_fanout: GossipFanout,
+    /// Bus on which the overlay emits its activity.
+    events: NodeEvents,
 }
 
 impl<
@@ -125,34 +307,73 @@ where
         protocol: ProtocolId,
         validator: Arc<TValidator>,
     ) -> Self {
+        let bucket_filter = match config.bucket_size {
+            bucket_size if bucket_size < MAX_NODES_PER_BUCKET => {
+                let filter: Box<dyn Filter<Node>> = Box::new(BucketSizeFilter {
+                    max_nodes: bucket_size,
+                    inner: config.bucket_filter,
+                });
+                Some(filter)
+            }
+            _ => config.bucket_filter,
+        };
         let kbuckets = Arc::new(RwLock::new(KBucketsTable::new(
             discovery.local_enr().node_id().into(),
             config.bucket_pending_timeout,
             config.max_incoming_per_bucket,
             config.table_filter,
-            config.bucket_filter,
+            bucket_filter,
         )));
 
         // Initialize metrics, keep a reference in order to build metrics summaries for logging
         let metrics = Arc::new(OverlayMetrics::new());
 
-        let command_tx = OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
-            Arc::clone(&discovery),
-            Arc::clone(&store),
-            Arc::clone(&kbuckets),
-            config.bootnode_enrs,
-            config.ping_queue_interval,
+        let lookup_cache = (config.lookup_cache_size > 0).then(|| {
+            RwLock::new(LookupCache::new(
+                config.lookup_cache_size,
+                config.lookup_cache_ttl,
+                config.lookup_cache_ttl.min(NEGATIVE_LOOKUP_CACHE_TTL),
+            ))
+        });
+
+        let validation_cache = Arc::new(ValidationCache::new(
+            config.validation_cache_size,
             protocol.clone(),
-            Arc::clone(&utp_socket),
             Arc::clone(&metrics),
-            Arc::clone(&validator),
-            config.query_timeout,
-            config.query_peer_timeout,
-            config.query_parallelism,
-            config.query_num_results,
-            config.findnodes_query_distances_per_peer,
-        )
-        .await;
+            config.events.clone(),
+        ));
+
+        let utp_transfers = Arc::new(UtpTransfers::new(protocol.clone()));
+
+        let (command_tx, service_tasks) =
+            OverlayService::<TContentKey, TMetric, TValidator, TStore>::spawn(
+                Arc::clone(&discovery),
+                Arc::clone(&store),
+                Arc::clone(&kbuckets),
+                config.bootnode_enrs,
+                config.ping_queue_interval,
+                protocol.clone(),
+                Arc::clone(&utp_socket),
+                Arc::clone(&metrics),
+                Arc::clone(&validator),
+                Arc::clone(&validation_cache),
+                Arc::clone(&utp_transfers),
+                config.events.clone(),
+                config.query_timeout,
+                config.query_peer_timeout,
+                config.query_peer_attempts.max(1),
+                config.query_parallelism,
+                config.query_num_results,
+                config.findnodes_query_distances_per_peer,
+                config.ping_extensions.clone(),
+                config.bucket_refresh_interval,
+                config.table_depth.clamp(1, 255),
+                config.request_timeouts,
+                config.gossip_fanout,
+                config.utp_transfer_limit,
+                config.neighborhood_sync_limit,
+            )
+            .await;
 
         Self {
             discovery,
@@ -160,14 +381,27 @@ where
             store,
             protocol,
             command_tx,
+            service_tasks: Mutex::new(service_tasks),
             utp_socket,
             phantom_content_key: PhantomData,
             phantom_metric: PhantomData,
             validator,
             metrics,
+            ping_extensions: config.ping_extensions,
+            lookup_cache,
+            validation_cache,
+            utp_transfers,
+            gossip_fanout: config.gossip_fanout,
+            events: config.events,
         }
     }
 
+    /// Takes the handles of the overlay service and the tasks it spawned, which run until they
+    /// are aborted. Later calls return no handles.
+    pub fn take_service_tasks(&self) -> Vec<JoinHandle<()>> {
+        std::mem::take(&mut *self.service_tasks.lock())
+    }
+
     /// Returns the subnetwork protocol of the overlay protocol.
     pub fn protocol(&self) -> &ProtocolId {
         &self.protocol
@@ -211,9 +445,144 @@ where
             content,
             kbuckets,
             self.command_tx.clone(),
+            self.gossip_fanout,
+            &self.events,
+            &self.protocol,
         )
     }
 
+    /// Asks up to `max_peers` of the closest connected peers whose radius covers `content_key`
+    /// for the content, and returns the number of peers asked and how many of them hold it.
+    ///
+    /// Content that a peer offers over uTP is not read, see `peer_has_content`.
+    pub async fn count_content_holders(
+        &self,
+        content_key: &TContentKey,
+        max_peers: usize,
+    ) -> (usize, usize) {
+        let content_id = content_key.content_id();
+        let mut interested: Vec<(Distance, Enr)> = self
+            .kbuckets
+            .write()
+            .iter()
+            .filter(|entry| entry.status.is_connected())
+            .filter_map(|entry| {
+                let distance = TMetric::distance(&content_id, &entry.node.key.preimage().raw());
+                (distance <= entry.node.value.data_radius())
+                    .then(|| (distance, entry.node.value.enr()))
+            })
+            .collect();
+        interested.sort_by_key(|(distance, _)| *distance);
+        interested.truncate(max_peers);
+
+        let asked = interested.len();
+        let requests = interested
+            .into_iter()
+            .map(|(_, enr)| self.peer_has_content(enr, content_key.clone().into()));
+        let holders = join_all(requests)
+            .await
+            .into_iter()
+            .filter(|response| matches!(response, Ok(true)))
+            .count();
+        (asked, holders)
+    }
+
+    /// Asks `enr` for the content and returns whether it holds it. Unlike `send_find_content`,
+    /// a connection id counts as holding the content without reading the uTP stream, so large
+    /// content is not transferred. The stream is opened and closed right away, so that the peer
+    /// doesn't wait for it until its listener times out.
+    async fn peer_has_content(
+        &self,
+        enr: Enr,
+        content_key: Vec<u8>,
+    ) -> Result<bool, OverlayRequestError> {
+        let request = Request::FindContent(FindContent { content_key });
+        let direction = RequestDirection::Outgoing {
+            destination: enr.clone(),
+        };
+        match self.send_overlay_request(request, direction).await? {
+            Response::Content(Content::Content(_)) => Ok(true),
+            Response::Content(Content::ConnectionId(conn_id)) => {
+                let conn_id = u16::from_be(conn_id);
+                let cid = utp_rs::cid::ConnectionId {
+                    recv: conn_id,
+                    send: conn_id.wrapping_add(1),
+                    peer: crate::discovery::UtpEnr(enr.clone()),
+                };
+                match self.utp_socket.connect_with_cid(cid, UTP_CONN_CFG).await {
+                    // Dropping the stream closes it without reading the content.
+                    Ok(stream) => drop(stream),
+                    Err(err) => debug!(
+                        protocol = %self.protocol,
+                        ?err,
+                        node_id = %enr.node_id(),
+                        "Unable to close uTP stream of content holder"
+                    ),
+                }
+                Ok(true)
+            }
+            Response::Content(Content::Enrs(_)) => Ok(false),
+            _ => Err(OverlayRequestError::InvalidResponse),
+        }
+    }
+
+    /// Looks up the `max_nodes` nodes closest to the id of `content_key` in the network, and asks
+    /// each of them for the content.
+    ///
+    /// Unlike `count_content_holders`, nodes are asked whether or not their radius covers the
+    /// content, so the result reflects how the content is actually replicated rather than what
+    /// the peers advertise.
+    pub async fn measure_replication(
+        &self,
+        content_key: &TContentKey,
+        max_nodes: usize,
+    ) -> ReplicationInfo {
+        let content_id = content_key.content_id();
+        let local_node_id = self.local_enr().node_id();
+        let mut closest: Vec<(Distance, Enr)> = self
+            .lookup_node(NodeId::new(&content_id))
+            .await
+            .into_iter()
+            .filter(|enr| enr.node_id() != local_node_id)
+            .map(|enr| (TMetric::distance(&content_id, &enr.node_id().raw()), enr))
+            .collect();
+        closest.sort_by_key(|(distance, _)| *distance);
+        closest.dedup_by_key(|(_, enr)| enr.node_id());
+        closest.truncate(max_nodes);
+
+        let requests = closest.into_iter().map(|(distance, enr)| async move {
+            let has_content = match self
+                .peer_has_content(enr.clone(), content_key.clone().into())
+                .await
+            {
+                Ok(has_content) => Some(has_content),
+                Err(err) => {
+                    debug!(
+                        protocol = %self.protocol,
+                        %err,
+                        node_id = %enr.node_id(),
+                        "Replication request failed"
+                    );
+                    None
+                }
+            };
+            ReplicaInfo {
+                enr,
+                log2_distance: distance.log2().unwrap_or(0) as u16,
+                has_content,
+            }
+        });
+        let nodes = join_all(requests).await;
+        ReplicationInfo {
+            queried: nodes.len() as u64,
+            holders: nodes
+                .iter()
+                .filter(|node| node.has_content == Some(true))
+                .count() as u64,
+            nodes,
+        }
+    }
+
     /// Returns a vector of all ENR node IDs of nodes currently contained in the routing table.
     pub fn table_entries_id(&self) -> Vec<NodeId> {
         self.kbuckets
@@ -266,6 +635,9 @@ where
                                 node.status,
                                 node.value.data_radius(),
                                 client_info,
+                                self.metrics
+                                    .last_seen(node.key.preimage())
+                                    .map(|last_seen| last_seen.elapsed()),
                             )
                         })
                         .collect(),
@@ -278,8 +650,10 @@ where
     pub async fn send_ping(&self, enr: Enr) -> Result<Pong, OverlayRequestError> {
         // Construct the request.
         let enr_seq = self.discovery.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload = CustomPayload::from(PingPayload {
+            data_radius: *self.data_radius(),
+            extensions: self.ping_extensions.clone(),
+        });
         let request = Ping {
             enr_seq,
             custom_payload,
@@ -346,15 +720,17 @@ where
                     // Init uTP stream if `connection_id`is received
                     Content::ConnectionId(conn_id) => {
                         let conn_id = u16::from_be(conn_id);
-                        let content = self.init_find_content_stream(enr, conn_id).await?;
+                        let content = self
+                            .init_find_content_stream(enr, conn_id, &content_key)
+                            .await?;
                         let content_key = TContentKey::try_from(content_key).map_err(|err| {
                             OverlayRequestError::FailedValidation(format!(
                                 "Error decoding content key for received utp content: {err}"
                             ))
                         })?;
                         match self
-                            .validator
-                            .validate_content(&content_key, &content)
+                            .validation_cache
+                            .validate(self.validator.as_ref(), &content_key, &content)
                             .await
                         {
                             Ok(_) => Ok(Content::Content(content)),
@@ -376,7 +752,40 @@ where
         &self,
         enr: Enr,
         conn_id: u16,
+        content_key: &[u8],
     ) -> Result<Vec<u8>, OverlayRequestError> {
+        let peer = enr.node_id();
+        let span = debug_span!(
+            "utp_transfer",
+            peer = %peer,
+            bytes = field::Empty,
+            elapsed_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let result = self
+            .read_find_content_stream(enr, conn_id, content_key)
+            .instrument(span.clone())
+            .await;
+        if let Ok(data) = &result {
+            span.record("bytes", data.len());
+        }
+        span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+        result
+    }
+
+    /// Reads the content from the uTP stream of a FindContent request.
+    async fn read_find_content_stream(
+        &self,
+        enr: Enr,
+        conn_id: u16,
+        content_key: &[u8],
+    ) -> Result<Vec<u8>, OverlayRequestError> {
+        let peer = enr.node_id();
+        let transfer = self.utp_transfers.start(
+            peer,
+            TransferDirection::Inbound,
+            vec![hex_encode(content_key)],
+        );
         let cid = utp_rs::cid::ConnectionId {
             recv: conn_id,
             send: conn_id.wrapping_add(1),
@@ -392,6 +801,8 @@ where
             .read_to_eof(&mut data)
             .await
             .map_err(|err| OverlayRequestError::UtpError(format!("{:?}", err)))?;
+        transfer.set_bytes(data.len());
+        self.metrics.report_utp_bytes_received(&peer, data.len());
 
         Ok(data)
     }
@@ -473,6 +884,7 @@ where
         if let Err(err) = self.command_tx.send(OverlayCommand::FindNodeQuery {
             target,
             callback: tx,
+            span: Span::current(),
         }) {
             warn!(
                 protocol = %self.protocol,
@@ -492,14 +904,71 @@ where
     }
 
     /// Performs a content lookup for `target`.
-    /// Returns the target content along with the peers traversed during content lookup.
-    pub async fn lookup_content(&self, target: TContentKey) -> (Option<Vec<u8>>, Vec<NodeId>) {
+    /// Returns the target content, or the reason it was not found, along with the peers
+    /// traversed during content lookup.
+    ///
+    /// Recent results are served from the lookup cache without traversing any peers, unless
+    /// `bypass_cache` is set. The result of every lookup updates the cache.
+    ///
+    /// The lookup is recorded in a `content_lookup` span, which contains the spans of each of its
+    /// stages.
+    pub async fn lookup_content(
+        &self,
+        target: TContentKey,
+        bypass_cache: bool,
+    ) -> FindContentResult {
+        let span = info_span!(
+            "content_lookup",
+            content.key = %target,
+            cached = field::Empty,
+            found = field::Empty,
+            peers = field::Empty,
+            elapsed_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let (result, closest_nodes, cached) = self
+            .lookup_content_with_cache(target, bypass_cache)
+            .instrument(span.clone())
+            .await;
+        span.record("cached", cached);
+        span.record("found", result.is_ok());
+        span.record("peers", closest_nodes.len());
+        span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+        (result, closest_nodes)
+    }
+
+    /// Looks up `target` in the lookup cache, and on the network if it is not cached. Returns
+    /// whether the result was served from the cache.
+    async fn lookup_content_with_cache(
+        &self,
+        target: TContentKey,
+        bypass_cache: bool,
+    ) -> (Result<Vec<u8>, LookupError>, Vec<NodeId>, bool) {
+        let content_id = target.content_id();
+        if let Some(cache) = &self.lookup_cache {
+            if !bypass_cache {
+                if let Some(result) = cache.write().get(&content_id) {
+                    return (result, vec![], true);
+                }
+            }
+        }
+
+        let (result, closest_nodes) = self.lookup_content_on_network(target).await;
+        if let Some(cache) = &self.lookup_cache {
+            cache.write().insert(content_id, &result);
+        }
+        (result, closest_nodes, false)
+    }
+
+    /// Performs a content lookup for `target` on the network.
+    async fn lookup_content_on_network(&self, target: TContentKey) -> FindContentResult {
         let (tx, rx) = oneshot::channel();
         let content_id = target.content_id();
 
         if let Err(err) = self.command_tx.send(OverlayCommand::FindContentQuery {
             target,
             callback: tx,
+            span: Span::current(),
         }) {
             warn!(
                 protocol = %self.protocol,
@@ -507,7 +976,7 @@ where
                 content.id = %hex_encode(content_id),
                 "Error submitting FindContent query to service"
             );
-            return (None, vec![]);
+            return (Err(LookupError::Service(err.to_string())), vec![]);
         }
 
         rx.await.unwrap_or_else(|err| {
@@ -517,7 +986,7 @@ where
                 content.id = %hex_encode(content_id),
                 "Error receiving content from service",
             );
-            (None, vec![])
+            (Err(LookupError::Service(err.to_string())), vec![])
         })
     }
 
@@ -586,6 +1055,75 @@ where
         }
     }
 
+    /// Returns the client info and capabilities advertised by the peers in the routing table that
+    /// support PING/PONG extensions.
+    pub fn get_peer_capabilities(&self) -> Vec<PeerCapabilitiesInfo> {
+        self.kbuckets
+            .write()
+            .iter()
+            .filter_map(|entry| {
+                let node = entry.node.value;
+                node.extensions().map(|extensions| PeerCapabilitiesInfo {
+                    node_id: node.enr().node_id().raw().into(),
+                    client_info: extensions.client_info.clone(),
+                    capabilities: extensions.capabilities.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns estimates of the network size and replication, from the data radii of the peers
+    /// in the routing table.
+    pub fn get_network_estimate(&self) -> NetworkEstimateInfo {
+        let peers: Vec<(NodeId, Distance)> = self
+            .kbuckets
+            .write()
+            .iter()
+            .map(|entry| (*entry.node.key.preimage(), entry.node.value.data_radius()))
+            .collect();
+        estimate_network(self.local_enr().node_id(), &peers)
+    }
+
+    /// Returns the activity counters of every peer the local node has interacted with.
+    pub fn get_peer_stats(&self) -> Vec<PeerStatsInfo> {
+        self.metrics.peer_stats()
+    }
+
+    /// Returns the activity of the overlay since it started, without the uptime.
+    pub fn get_activity_totals(&self) -> LifetimeStatsInfo {
+        self.metrics.activity_totals()
+    }
+
+    /// Returns the metrics of the overlay, which keep counting for as long as the overlay runs.
+    pub fn metrics(&self) -> Arc<OverlayMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Refreshes `bucket` of the routing table, or every bucket within the table depth if no
+    /// bucket is given. The nodes in the refreshed buckets are pinged and a random node ID in
+    /// each bucket is looked up in the background.
+    pub fn refresh_buckets(&self, bucket: Option<u8>) -> Result<(), OverlayRequestError> {
+        self.command_tx
+            .send(OverlayCommand::RefreshBuckets { bucket })
+            .map_err(|err| OverlayRequestError::ChannelFailure(err.to_string()))
+    }
+
+    /// Returns the uTP transfers in progress, oldest first.
+    pub fn get_utp_transfers(&self) -> Vec<UtpTransferInfo> {
+        self.utp_transfers.list()
+    }
+
+    /// Returns the number of content lookups performed by the local node, and how many of them
+    /// found the content.
+    pub fn get_content_lookup_counts(&self) -> (u64, u64) {
+        self.metrics.content_lookup_counts(&self.protocol)
+    }
+
+    /// Returns the number of overlay messages of each type sent and received by the local node.
+    pub fn get_message_summary(&self) -> MessageSummaryInfo {
+        self.metrics.message_summary(&self.protocol)
+    }
+
     pub fn get_summary_info(&self) -> String {
         format!(
             "offers={}/{}, accepts={}/{}",
@@ -645,6 +1183,8 @@ mod test {
     use super::*;
     use rstest::rstest;
 
+    use crate::utils::node_id::generate_random_remote_enr;
+
     #[rstest]
     #[case(vec![0u16])]
     #[case(vec![256u16])]
@@ -666,4 +1206,20 @@ mod test {
             Err(err) => assert!(err.to_string().contains(&msg)),
         }
     }
+
+    #[test]
+    fn test_bucket_size_filter() {
+        let filter = BucketSizeFilter {
+            max_nodes: 2,
+            inner: None,
+        };
+        let nodes: Vec<Node> = (0..3)
+            .map(|_| Node::new(generate_random_remote_enr().1, Distance::MAX))
+            .collect();
+
+        assert!(filter.filter(&nodes[1], &mut nodes[..1].iter()));
+        assert!(!filter.filter(&nodes[2], &mut nodes[..2].iter()));
+        // A node already in a full bucket may still be updated.
+        assert!(filter.filter(&nodes[1], &mut nodes[..2].iter()));
+    }
 }
@@ -1,11 +1,11 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
     marker::{PhantomData, Sync},
     str::FromStr,
     sync::Arc,
     task::Poll,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
@@ -15,12 +15,12 @@ use discv5::{
     enr::NodeId,
     kbucket::{
         self, ConnectionDirection, ConnectionState, FailureReason, InsertResult, KBucketsTable,
-        Key, NodeStatus, UpdateResult,
+        Key, NodeStatus, UpdateResult, MAX_NODES_PER_BUCKET,
     },
     rpc::RequestId,
 };
 use futures::{channel::oneshot, future::join_all, prelude::*};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use rand::seq::{IteratorRandom, SliceRandom};
 use smallvec::SmallVec;
 use ssz::Encode;
@@ -29,9 +29,10 @@ use thiserror::Error;
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
     task::JoinHandle,
+    time,
 };
-use tracing::{debug, error, info, trace, warn};
-use utp_rs::{conn::ConnectionConfig, socket::UtpSocket};
+use tracing::{debug, debug_span, error, field, info, trace, warn, Instrument, Span};
+use utp_rs::{cid::ConnectionId, conn::ConnectionConfig, socket::UtpSocket};
 
 use crate::{
     discovery::Discovery,
@@ -41,10 +42,14 @@ use crate::{
             findnodes::FindNodeQuery,
             query::{Query, QueryConfig},
         },
-        query_info::{FindContentResult, QueryInfo, QueryType},
+        query_info::{FindContentResult, LookupError, QueryInfo, QueryType},
         query_pool::{QueryId, QueryPool, QueryPoolState, TargetKey},
     },
     metrics::OverlayMetrics,
+    neighborhood_sync::spawn_neighborhood_sync,
+    network_estimate::estimate_network,
+    node_events::{LookupHop, LookupKind, NodeEvent, NodeEvents},
+    overlay::{GossipFanout, RequestTimeouts},
     storage::ContentStore,
     types::{
         messages::{
@@ -55,9 +60,18 @@ use crate::{
         node::Node,
     },
     utils::{node_id, portal_wire},
+    utp_transfers::UtpTransfers,
+    validation_cache::ValidationCache,
+};
+use ethportal_api::{
+    types::{
+        ping_extensions::{Capability, PingExtensions, PingPayload},
+        portal::TransferDirection,
+    },
+    OverlayContentKey,
 };
-use ethportal_api::OverlayContentKey;
 use trin_types::content_key::RawContentKey;
+use trin_types::correlation::current_or_operation_span;
 use trin_types::distance::{Distance, Metric, XorMetric};
 use trin_types::enr::{Enr, SszEnr};
 use trin_utils::bytes::{hex_encode, hex_encode_compact};
@@ -71,10 +85,10 @@ pub const FIND_CONTENT_MAX_NODES: usize = 32;
 
 /// With even distribution assumptions, 2**17 is enough to put each node (estimating 100k nodes,
 /// which is more than 10x the ethereum mainnet node count) into a unique bucket by the 17th bucket index.
-const EXPECTED_NON_EMPTY_BUCKETS: usize = 17;
+pub const EXPECTED_NON_EMPTY_BUCKETS: usize = 17;
 
-/// Bucket refresh lookup interval in seconds
-const BUCKET_REFRESH_INTERVAL_SECS: u64 = 60;
+/// Default interval between bucket refresh lookups.
+pub const DEFAULT_BUCKET_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
 
 /// The default configuration to use for uTP connections.
 pub const UTP_CONN_CFG: ConnectionConfig = ConnectionConfig {
@@ -86,6 +100,9 @@ pub const UTP_CONN_CFG: ConnectionConfig = ConnectionConfig {
     target_delay: Duration::from_millis(250),
 };
 
+/// Time that an acceptor waits for each stream of an offer sent over several uTP streams.
+const OFFER_STREAM_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// A network-based action that the overlay may perform.
 ///
 /// The overlay performs network-based actions on behalf of the command issuer. The issuer may be
@@ -106,12 +123,22 @@ pub enum OverlayCommand<TContentKey> {
         target: TContentKey,
         /// A callback channel to transmit the result of the query.
         callback: oneshot::Sender<(Option<Vec<u8>>, Vec<NodeId>)>,
+        /// The span of the operation that requested the query.
+        span: Span,
     },
     FindNodeQuery {
         /// The query target.
         target: NodeId,
         /// A callback channel to transmit the result of the query.
         callback: oneshot::Sender<Vec<Enr>>,
+        /// The span of the operation that requested the query.
+        span: Span,
+    },
+    /// Refresh a bucket of the routing table, or every bucket within the table depth if no
+    /// bucket is given.
+    RefreshBuckets {
+        /// The index of the bucket, as in the routing table info.
+        bucket: Option<u8>,
     },
 }
 
@@ -211,6 +238,8 @@ pub struct OverlayRequest {
     /// ID of query that request's response will advance.
     /// Will be None for requests that are not associated with a query.
     pub query_id: Option<QueryId>,
+    /// The span the request was created in. The response to the request is processed in it.
+    pub span: Span,
 }
 
 impl OverlayRequest {
@@ -227,6 +256,7 @@ impl OverlayRequest {
             direction,
             responder,
             query_id,
+            span: Span::current(),
         }
     }
 }
@@ -240,6 +270,10 @@ struct ActiveOutgoingRequest {
     pub request: Request,
     /// An optional QueryID for the query that this request is associated with.
     pub query_id: Option<QueryId>,
+    /// The span of the request.
+    pub span: Span,
+    /// The time the request was sent.
+    pub sent: Instant,
 }
 
 /// A response for a particular overlay request.
@@ -279,6 +313,8 @@ pub struct OverlayService<TContentKey, TMetric, TValidator, TStore> {
     find_content_query_pool: QueryPool<NodeId, FindContentQuery<NodeId>, TContentKey>,
     /// Timeout after which a peer in an ongoing query is marked unresponsive.
     query_peer_timeout: Duration,
+    /// Number of times a peer in an ongoing content query is requested before it is abandoned.
+    query_peer_attempts: usize,
     /// Number of peers to request data from in parallel for a single query.
     query_parallelism: usize,
     /// Number of new peers to discover before considering a FINDNODES query complete.
@@ -299,6 +335,29 @@ pub struct OverlayService<TContentKey, TMetric, TValidator, TStore> {
     metrics: Arc<OverlayMetrics>,
     /// Validator for overlay network content.
     validator: Arc<TValidator>,
+    /// Cache of the verdicts of content validations, shared with the overlay protocol.
+    validation_cache: Arc<ValidationCache>,
+    /// uTP transfers in progress, shared with the overlay protocol.
+    utp_transfers: Arc<UtpTransfers>,
+    /// Bus on which routing table changes, received gossip and stored content are emitted.
+    events: NodeEvents,
+    /// Extensions advertised in the custom payload of outgoing PING and PONG messages.
+    ping_extensions: Option<PingExtensions>,
+    /// Number of buckets farthest from the local node that are expected to hold nodes, which are
+    /// looked up to populate the routing table.
+    table_depth: usize,
+    /// Timeouts of outbound requests, by kind of request.
+    request_timeouts: RequestTimeouts,
+    /// Number of interested peers that gossiped content is offered to.
+    gossip_fanout: GossipFanout,
+    /// Maximum number of uTP streams over which the content accepted from one offer is sent
+    /// concurrently, to peers that receive each item over its own stream.
+    utp_transfer_limit: usize,
+    /// Connection ids of the incoming uTP streams that the service waits for.
+    reserved_cids: CidReservations,
+    /// Channel on which nodes that joined the routing table are passed to be offered the local
+    /// content within their radius, if neighborhood sync is enabled.
+    neighborhood_sync_tx: Option<UnboundedSender<Node>>,
 }
 
 impl<
@@ -326,12 +385,26 @@ where
         utp_socket: Arc<UtpSocket<crate::discovery::UtpEnr>>,
         metrics: Arc<OverlayMetrics>,
         validator: Arc<TValidator>,
+        validation_cache: Arc<ValidationCache>,
+        utp_transfers: Arc<UtpTransfers>,
+        events: NodeEvents,
         query_timeout: Duration,
         query_peer_timeout: Duration,
+        query_peer_attempts: usize,
         query_parallelism: usize,
         query_num_results: usize,
         findnodes_query_distances_per_peer: usize,
-    ) -> UnboundedSender<OverlayCommand<TContentKey>>
+        ping_extensions: Option<PingExtensions>,
+        bucket_refresh_interval: Duration,
+        table_depth: usize,
+        request_timeouts: RequestTimeouts,
+        gossip_fanout: GossipFanout,
+        utp_transfer_limit: usize,
+        neighborhood_sync_limit: usize,
+    ) -> (
+        UnboundedSender<OverlayCommand<TContentKey>>,
+        Vec<JoinHandle<()>>,
+    )
     where
         <TContentKey as TryFrom<Vec<u8>>>::Error: Send,
     {
@@ -348,7 +421,19 @@ where
 
         let (response_tx, response_rx) = mpsc::unbounded_channel();
 
-        tokio::spawn(async move {
+        let mut tasks = vec![];
+        let neighborhood_sync_tx = (neighborhood_sync_limit > 0).then(|| {
+            let (node_tx, task) = spawn_neighborhood_sync(
+                Arc::clone(&store),
+                command_tx.clone(),
+                discovery.local_enr().node_id(),
+                neighborhood_sync_limit,
+            );
+            tasks.push(task);
+            node_tx
+        });
+
+        let service_task = tokio::spawn(async move {
             let mut service = Self {
                 discovery,
                 store,
@@ -361,6 +446,7 @@ where
                 find_node_query_pool: QueryPool::new(query_timeout),
                 find_content_query_pool: QueryPool::new(query_timeout),
                 query_peer_timeout,
+                query_peer_attempts,
                 query_parallelism,
                 query_num_results,
                 findnodes_query_distances_per_peer,
@@ -371,14 +457,25 @@ where
                 phantom_metric: PhantomData,
                 metrics,
                 validator,
+                validation_cache,
+                utp_transfers,
+                events,
+                ping_extensions,
+                table_depth,
+                request_timeouts,
+                gossip_fanout,
+                utp_transfer_limit,
+                reserved_cids: CidReservations::default(),
+                neighborhood_sync_tx,
             };
 
             info!(protocol = %overlay_protocol, "Starting overlay service");
             service.initialize_routing_table(bootnode_enrs);
-            service.start().await;
+            service.start(bucket_refresh_interval).await;
         });
+        tasks.push(service_task);
 
-        command_tx
+        (command_tx, tasks)
     }
 
     fn add_bootnodes(&mut self, bootnode_enrs: Vec<Enr>) {
@@ -411,12 +508,18 @@ where
                         "Error inserting bootnode into routing table",
                     );
                 }
-                _ => {
+                result => {
                     debug!(
                         protocol = %self.protocol,
                         bootnode = %node_id,
                         "Inserted bootnode into routing table",
                     );
+                    if matches!(result, InsertResult::Inserted) {
+                        self.events.emit(NodeEvent::PeerAdded {
+                            protocol: self.protocol.clone(),
+                            peer: node_id,
+                        });
+                    }
 
                     // Queue the node in the ping queue.
                     self.peers_to_ping.insert(node_id);
@@ -433,7 +536,7 @@ where
         // Begin request for our local node ID.
         self.init_find_nodes_query(&local_node_id, None);
 
-        for bucket_index in (255 - EXPECTED_NON_EMPTY_BUCKETS as u8)..255 {
+        for bucket_index in (255 - self.table_depth as u8)..255 {
             let target_node_id =
                 node_id::generate_random_node_id(bucket_index, self.local_enr().node_id());
             self.init_find_nodes_query(&target_node_id, None);
@@ -453,17 +556,21 @@ where
     /// information relevant to the overlay network.
     ///
     /// Bucket maintenance: Maintain the routing table (more info documented above function).
-    async fn start(&mut self) {
+    ///
+    /// Bucket refresh: Look up a random node ID in a sparse bucket every
+    /// `bucket_refresh_interval`, to keep the routing table populated when there is little
+    /// organic traffic.
+    async fn start(&mut self, bucket_refresh_interval: Duration) {
         // Construct bucket refresh interval
-        let mut bucket_refresh_interval =
-            tokio::time::interval(Duration::from_secs(BUCKET_REFRESH_INTERVAL_SECS));
+        let mut bucket_refresh_interval = tokio::time::interval(bucket_refresh_interval);
 
         loop {
             tokio::select! {
                 Some(command) = self.command_rx.recv() => {
                     match command {
                         OverlayCommand::Request(request) => self.process_request(request),
-                        OverlayCommand::FindContentQuery { target, callback } => {
+                        OverlayCommand::FindContentQuery { target, callback, span } => {
+                            let _enter = span.enter();
                             if let Some(query_id) = self.init_find_content_query(target.clone(), Some(callback)) {
                                 trace!(
                                     query.id = %query_id,
@@ -473,7 +580,8 @@ where
                                 );
                             }
                         }
-                        OverlayCommand::FindNodeQuery { target, callback } => {
+                        OverlayCommand::FindNodeQuery { target, callback, span } => {
+                            let _enter = span.enter();
                             if let Some(query_id) = self.init_find_nodes_query(&target, Some(callback)) {
                                 trace!(
                                     query.id = %query_id,
@@ -482,12 +590,26 @@ where
                                 );
                             }
                         }
+                        OverlayCommand::RefreshBuckets { bucket } => self.refresh_buckets(bucket),
                     }
                 }
                 Some(response) = self.response_rx.recv() => {
                     // Look up active request that corresponds to the response.
                     let optional_active_request = self.active_outgoing_requests.write().remove(&response.request_id);
                     if let Some(active_request) = optional_active_request {
+                        let span = active_request.span;
+                        let _enter = span.enter();
+                        span.record("elapsed_ms", active_request.sent.elapsed().as_millis() as u64);
+                        span.record("success", response.response.is_ok());
+                        if let Some(query_id) = active_request.query_id {
+                            self.record_lookup_hop(
+                                query_id,
+                                &active_request.request,
+                                &active_request.destination,
+                                active_request.sent.elapsed(),
+                                response.response.is_ok(),
+                            );
+                        }
 
                         // Send response to responder if present.
                         if let Some(responder) = active_request.responder {
@@ -522,10 +644,11 @@ where
                 query_event = OverlayService::<TContentKey, TMetric, TValidator, TStore>::query_event_poll(&mut self.find_content_query_pool) => {
                     self.handle_find_content_query_event(query_event);
                 }
-                _ = OverlayService::<TContentKey, TMetric, TValidator, TStore>::bucket_maintenance_poll(self.protocol.clone(), &self.kbuckets) => {}
+                _ = OverlayService::<TContentKey, TMetric, TValidator, TStore>::bucket_maintenance_poll(self.protocol.clone(), &self.kbuckets, &self.events) => {}
                 _ = bucket_refresh_interval.tick() => {
                     trace!(protocol = %self.protocol, "Routing table bucket refresh");
                     self.bucket_refresh_lookup();
+                    self.report_network_estimate();
                 }
             }
         }
@@ -533,16 +656,25 @@ where
 
     /// Main bucket refresh lookup logic
     fn bucket_refresh_lookup(&mut self) {
-        // Look at local routing table and select the largest 17 buckets.
-        // We only need the 17 bits furthest from our own node ID, because the closest 239 bits of
-        // buckets are going to be empty-ish.
+        // Look at local routing table and select the largest `table_depth` buckets (17 by
+        // default). We only need the 17 bits furthest from our own node ID, because the closest
+        // 239 bits of buckets are going to be empty-ish.
         let target_node_id = {
             let buckets = self.kbuckets.read();
             let buckets = buckets.buckets_iter().enumerate().collect::<Vec<_>>();
-            let buckets = &buckets[256 - EXPECTED_NON_EMPTY_BUCKETS..];
+            let buckets = &buckets[256 - self.table_depth..];
 
-            // Randomly pick one of these buckets.
-            let target_bucket = buckets.choose(&mut rand::thread_rng());
+            // Randomly pick one of these buckets, preferring buckets with room for more nodes.
+            // A lookup for a full bucket still refreshes it, so fall back to any bucket once all
+            // of them are full.
+            let sparse_buckets = buckets
+                .iter()
+                .filter(|(_, bucket)| bucket.num_entries() < MAX_NODES_PER_BUCKET)
+                .collect::<Vec<_>>();
+            let target_bucket = match sparse_buckets.choose(&mut rand::thread_rng()) {
+                Some(bucket) => Some(*bucket),
+                None => buckets.choose(&mut rand::thread_rng()),
+            };
             match target_bucket {
                 Some(bucket) => {
                     trace!(protocol = %self.protocol, bucket = %bucket.0, "Refreshing routing table bucket");
@@ -566,6 +698,61 @@ where
         self.init_find_nodes_query(&target_node_id, None);
     }
 
+    /// Refreshes `bucket`, or every bucket within the table depth, on request: pings the nodes in
+    /// the refreshed buckets to check their liveness, and looks up a random node ID in each
+    /// bucket to find new nodes.
+    fn refresh_buckets(&mut self, bucket: Option<u8>) {
+        let buckets: Vec<u8> = match bucket {
+            Some(bucket) => vec![bucket],
+            None => ((256 - self.table_depth) as u8..=u8::MAX).collect(),
+        };
+        let enrs: Vec<Enr> = self
+            .kbuckets
+            .read()
+            .buckets_iter()
+            .enumerate()
+            .filter(|(index, _)| buckets.iter().any(|bucket| usize::from(*bucket) == *index))
+            .flat_map(|(_, bucket)| {
+                bucket
+                    .iter()
+                    .map(|node| node.value.enr())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        info!(
+            protocol = %self.protocol,
+            buckets = ?bucket,
+            nodes = enrs.len(),
+            "Refreshing routing table on request"
+        );
+        for enr in enrs.iter() {
+            self.ping_node(enr);
+        }
+
+        let local_node_id = self.local_enr().node_id();
+        if bucket.is_none() {
+            self.init_find_nodes_query(&local_node_id, None);
+        }
+        for bucket in buckets {
+            let target_node_id = node_id::generate_random_node_id(bucket, local_node_id);
+            self.init_find_nodes_query(&target_node_id, None);
+        }
+    }
+
+    /// Reports estimates of the network size and replication, from the data radii of the peers
+    /// in the routing table.
+    fn report_network_estimate(&self) {
+        let peers: Vec<(NodeId, Distance)> = self
+            .kbuckets
+            .write()
+            .iter()
+            .map(|entry| (*entry.node.key.preimage(), entry.node.value.data_radius()))
+            .collect();
+        let estimate = estimate_network(self.local_enr().node_id(), &peers);
+        self.metrics
+            .report_network_estimate(&self.protocol, &estimate);
+    }
+
     /// Returns the local ENR of the node.
     fn local_enr(&self) -> Enr {
         self.discovery.local_enr()
@@ -576,6 +763,15 @@ where
         self.store.read().radius()
     }
 
+    /// Returns the custom payload for outgoing PING and PONG messages.
+    fn custom_payload(&self) -> CustomPayload {
+        PingPayload {
+            data_radius: *self.data_radius(),
+            extensions: self.ping_extensions.clone(),
+        }
+        .into()
+    }
+
     /// Maintains the routing table.
     ///
     /// Consumes previously applied pending entries from the `KBucketsTable`. An `AppliedPending`
@@ -584,17 +780,30 @@ where
     async fn bucket_maintenance_poll(
         protocol: ProtocolId,
         kbuckets: &Arc<RwLock<KBucketsTable<NodeId, Node>>>,
+        events: &NodeEvents,
     ) {
         future::poll_fn(move |_cx| {
             // Drain applied pending entries from the routing table.
             if let Some(entry) = kbuckets.write().take_applied_pending() {
+                let inserted = entry.inserted.into_preimage();
+                let evicted = entry.evicted.map(|n| n.key.into_preimage());
                 debug!(
                     %protocol,
-                    inserted = %entry.inserted.into_preimage(),
-                    evicted = ?entry.evicted.map(|n| n.key.into_preimage()),
+                    %inserted,
+                    ?evicted,
                     "Pending node inserted",
 
                 );
+                if let Some(evicted) = evicted {
+                    events.emit(NodeEvent::PeerRemoved {
+                        protocol: protocol.clone(),
+                        peer: evicted,
+                    });
+                }
+                events.emit(NodeEvent::PeerAdded {
+                    protocol: protocol.clone(),
+                    peer: inserted,
+                });
                 return Poll::Ready(());
             }
             Poll::Pending
@@ -644,6 +853,8 @@ where
         match query_event {
             // Send a FINDNODES on behalf of the query.
             QueryEvent::Waiting(query_id, node_id, request) => {
+                let span = self.query_span(QueryKind::FindNode, query_id);
+                let _enter = span.enter();
                 // Look up the node's ENR.
                 if let Some(enr) = self.find_enr(&node_id) {
                     let request = OverlayRequest::new(
@@ -668,6 +879,13 @@ where
             // Query has ended.
             QueryEvent::Finished(query_id, mut query_info, query)
             | QueryEvent::TimedOut(query_id, mut query_info, query) => {
+                let span = query_info.span.clone();
+                let _enter = span.enter();
+                let elapsed = query
+                    .started()
+                    .map(|started| started.elapsed())
+                    .unwrap_or_default();
+                let target = query_info.key().preimage().raw();
                 let result = query.into_result();
                 // Obtain the ENRs for the resulting nodes.
                 let mut found_enrs = Vec::new();
@@ -689,6 +907,16 @@ where
                         );
                     }
                 }
+                if let Some(hops) = query_info.hops.take() {
+                    self.events.emit(NodeEvent::LookupFinished {
+                        protocol: self.protocol.clone(),
+                        kind: LookupKind::Node,
+                        target,
+                        success: found_enrs.iter().any(|enr| enr.node_id().raw() == target),
+                        elapsed,
+                        hops,
+                    });
+                }
                 if let QueryType::FindNode {
                     callback: Some(callback),
                     ..
@@ -717,9 +945,21 @@ where
         &mut self,
         query_event: QueryEvent<FindContentQuery<NodeId>, TContentKey>,
     ) {
+        let timed_out = matches!(query_event, QueryEvent::TimedOut(..));
         match query_event {
             QueryEvent::Waiting(query_id, node_id, request) => {
+                let query_span = self.query_span(QueryKind::FindContent, query_id);
+                let _enter = query_span.enter();
                 if let Some(enr) = self.find_enr(&node_id) {
+                    // The request, and the processing of its response, are recorded in a span of
+                    // their own, which is closed when the response is processed.
+                    let span = debug_span!(
+                        "peer_query",
+                        peer = %node_id,
+                        success = field::Empty,
+                        elapsed_ms = field::Empty,
+                    );
+                    let _enter = span.enter();
                     // If we find the node's ENR, then send the request on behalf of the
                     // query. No callback channel is necessary for the request, because the
                     // response will be incorporated into the query.
@@ -744,8 +984,15 @@ where
                     }
                 }
             }
-            QueryEvent::Finished(query_id, query_info, query)
-            | QueryEvent::TimedOut(query_id, query_info, query) => {
+            QueryEvent::Finished(query_id, mut query_info, query)
+            | QueryEvent::TimedOut(query_id, mut query_info, query) => {
+                let span = query_info.span.clone();
+                let _enter = span.enter();
+                let elapsed = query
+                    .started()
+                    .map(|started| started.elapsed())
+                    .unwrap_or_default();
+                let target = query_info.key().preimage().raw();
                 let result = query.into_result();
                 let (content, closest_nodes, content_provider) = match result {
                     FindContentQueryResult::ClosestNodes(closest_nodes) => {
@@ -757,6 +1004,18 @@ where
                         content_provider,
                     } => (Some(content), closest_nodes, Some(content_provider)),
                 };
+                self.metrics
+                    .report_content_lookup(&self.protocol, content.is_some());
+                if let Some(hops) = query_info.hops.take() {
+                    self.events.emit(NodeEvent::LookupFinished {
+                        protocol: self.protocol.clone(),
+                        kind: LookupKind::Content,
+                        target,
+                        success: content.is_some(),
+                        elapsed,
+                        hops,
+                    });
+                }
 
                 if let QueryType::FindContent {
                     callback: Some(callback),
@@ -767,8 +1026,15 @@ where
                     if let Some(val) = content_provider {
                         all_closest_nodes.push(val);
                     }
-                    let response = (content.clone(), all_closest_nodes);
-                    // Send (possibly `None`) content on callback channel.
+                    // A lookup that times out is reported separately from a lookup that
+                    // queried the closest peers without finding the content.
+                    let lookup_result = match (&content, timed_out) {
+                        (Some(content), _) => Ok(content.clone()),
+                        (None, true) => Err(LookupError::TimedOut { elapsed }),
+                        (None, false) => Err(LookupError::NotFound),
+                    };
+                    let response = (lookup_result, all_closest_nodes);
+                    // Send content or the lookup error on callback channel.
                     if let Err(err) = callback.send(response) {
                         error!(
                             query.id = %query_id,
@@ -850,6 +1116,7 @@ where
                     if let Ok(ref response) = response {
                         self.metrics
                             .report_outbound_response(&self.protocol, response);
+                        self.metrics.report_request_served(&source);
                     }
                     let _ = responder.send(response);
                 }
@@ -864,6 +1131,8 @@ where
                         responder: request.responder,
                         request: request.request.clone(),
                         query_id: request.query_id,
+                        span: request.span,
+                        sent: Instant::now(),
                     },
                 );
                 self.metrics
@@ -909,8 +1178,7 @@ where
         );
 
         let enr_seq = self.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload = self.custom_payload();
         Pong {
             enr_seq,
             custom_payload,
@@ -964,6 +1232,7 @@ where
         };
         match self.store.read().get(&content_key) {
             Ok(Some(content)) => {
+                self.metrics.report_content_served();
                 if content.len() <= MAX_PORTAL_CONTENT_PAYLOAD_SIZE {
                     Ok(Content::Content(content))
                 } else {
@@ -974,49 +1243,63 @@ where
                         )
                     })?;
                     let enr = crate::discovery::UtpEnr(node_addr.enr);
-                    let cid = self.utp_socket.cid(enr, false);
+                    let (cid, reservation) = self.reserved_cids.allocate(&self.utp_socket, enr, 1);
                     let cid_send = cid.send;
 
                     // Wait for an incoming connection with the given CID. Then, write the data
                     // over the uTP stream.
                     let utp = Arc::clone(&self.utp_socket);
-                    tokio::spawn(async move {
-                        let mut stream = match utp.accept_with_cid(cid.clone(), UTP_CONN_CFG).await
-                        {
-                            Ok(stream) => stream,
-                            Err(err) => {
-                                error!(
-                                    %err,
-                                    %cid.send,
-                                    %cid.recv,
-                                    peer = ?cid.peer.client(),
-                                    "unable to accept uTP stream for CID"
-                                );
-                                return;
-                            }
-                        };
-
-                        match stream.write(&content).await {
-                            Ok(..) => {
-                                debug!(
-                                    %cid.send,
-                                    %cid.recv,
-                                    peer = ?cid.peer.client(),
-                                    content_id = %hex_encode(content_key.content_id()),
-                                    "wrote content to uTP stream"
-                                );
-                            }
-                            Err(err) => {
-                                error!(
-                                    %cid.send,
-                                    %cid.recv,
-                                    peer = ?cid.peer.client(),
-                                    %err,
-                                    "error writing content to uTP stream"
-                                );
+                    let metrics = Arc::clone(&self.metrics);
+                    let source = *source;
+                    let transfer = self.utp_transfers.start(
+                        source,
+                        TransferDirection::Outbound,
+                        vec![content_key.to_hex()],
+                    );
+                    transfer.set_bytes(content.len());
+                    tokio::spawn(
+                        async move {
+                            let _transfer = transfer;
+                            let _reservation = reservation;
+                            let mut stream =
+                                match utp.accept_with_cid(cid.clone(), UTP_CONN_CFG).await {
+                                    Ok(stream) => stream,
+                                    Err(err) => {
+                                        error!(
+                                            %err,
+                                            %cid.send,
+                                            %cid.recv,
+                                            peer = ?cid.peer.client(),
+                                            "unable to accept uTP stream for CID"
+                                        );
+                                        return;
+                                    }
+                                };
+
+                            match stream.write(&content).await {
+                                Ok(..) => {
+                                    metrics.report_utp_bytes_sent(&source, content.len());
+                                    debug!(
+                                        %cid.send,
+                                        %cid.recv,
+                                        peer = ?cid.peer.client(),
+                                        content_id = %hex_encode(content_key.content_id()),
+                                        "wrote content to uTP stream"
+                                    );
+                                }
+                                Err(err) => {
+                                    error!(
+                                        %cid.send,
+                                        %cid.recv,
+                                        peer = ?cid.peer.client(),
+                                        %err,
+                                        "error writing content to uTP stream"
+                                    );
+                                }
                             }
                         }
-                    });
+                        .instrument(current_or_operation_span("utp")),
+                    );
 
                     // Connection id is send as BE because uTP header values are stored also as BE
                     Ok(Content::ConnectionId(cid_send.to_be()))
@@ -1102,45 +1385,82 @@ where
         let node_addr = self.discovery.cached_node_addr(source).ok_or_else(|| {
             OverlayRequestError::AcceptError("unable to find ENR for NodeId".to_string())
         })?;
+        let accepted_keys: Vec<TContentKey> = content_keys
+            .into_iter()
+            .zip(requested_keys.iter())
+            .filter(|(_, accepted)| *accepted)
+            .map(|(key, _)| key)
+            .collect();
+        let streams = match self.uses_offer_streams(source) {
+            true => accepted_keys.len(),
+            false => 1,
+        };
         let enr = crate::discovery::UtpEnr(node_addr.enr);
-        let cid = self.utp_socket.cid(enr, false);
+        let (cid, reservation) = self.reserved_cids.allocate(&self.utp_socket, enr, streams);
         let cid_send = cid.send;
 
         let validator = Arc::clone(&self.validator);
+        let validation_cache = Arc::clone(&self.validation_cache);
         let store = Arc::clone(&self.store);
         let kbuckets = Arc::clone(&self.kbuckets);
         let command_tx = self.command_tx.clone();
         let utp = Arc::clone(&self.utp_socket);
+        let metrics = Arc::clone(&self.metrics);
+        let events = self.events.clone();
+        let protocol = self.protocol.clone();
+        let gossip_fanout = self.gossip_fanout;
+        let source = *source;
+        let transfer = self.utp_transfers.start(
+            source,
+            TransferDirection::Inbound,
+            accepted_keys.iter().map(|key| key.to_hex()).collect(),
+        );
 
-        tokio::spawn(async move {
-            // Wait for an incoming connection with the given CID. Then, read the data from the uTP
-            // stream.
-            let mut stream = match utp.accept_with_cid(cid.clone(), UTP_CONN_CFG).await {
-                Ok(stream) => stream,
-                Err(err) => {
-                    warn!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to accept uTP stream");
-                    return;
-                }
-            };
-
-            let mut data = vec![];
-            if let Err(err) = stream.read_to_eof(&mut data).await {
-                warn!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "error reading data from uTP stream");
-            }
+        tokio::spawn(
+            async move {
+                // Wait for incoming connections with the given CID, and the CIDs derived from it
+                // if the content is sent over several streams. Then, read the content from the
+                // uTP streams.
+                let content =
+                    Self::read_accepted_content(utp, cid.clone(), accepted_keys.len(), streams)
+                        .await;
+                drop(reservation);
+                let (content_values, bytes) = match content {
+                    Ok(content) => content,
+                    Err(err) => {
+                        warn!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to read accepted content");
+                        return;
+                    }
+                };
+                metrics.report_utp_bytes_received(&source, bytes);
+                transfer.set_bytes(bytes);
+                // The transfer is complete, only the content remains to be validated and stored.
+                drop(transfer);
+                events.emit(NodeEvent::GossipReceived {
+                    protocol: protocol.clone(),
+                    peer: source,
+                    content_keys: accepted_keys.iter().map(|key| key.clone().into()).collect(),
+                });
 
-            if let Err(err) = Self::process_accept_utp_payload(
-                validator,
-                store,
-                kbuckets,
-                command_tx,
-                content_keys,
-                data,
-            )
-            .await
-            {
-                error!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to process uTP payload");
+                if let Err(err) = Self::process_accept_utp_payload(
+                    validator,
+                    validation_cache,
+                    store,
+                    kbuckets,
+                    command_tx,
+                    events,
+                    protocol,
+                    gossip_fanout,
+                    accepted_keys,
+                    content_values,
+                )
+                .await
+                {
+                    error!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "unable to process uTP payload");
+                }
             }
-        });
+            .instrument(current_or_operation_span("utp")),
+        );
 
         let accept = Accept {
             connection_id: cid_send.to_be(),
@@ -1150,28 +1470,30 @@ where
         Ok(accept)
     }
 
-    /// Sends a TALK request via Discovery v5 to some destination node.
+    /// Sends a TALK request via Discovery v5 to some destination node. The request fails with a
+    /// timeout if there is no response within the timeout for its kind of request.
     fn send_talk_req(&self, request: Request, request_id: OverlayRequestId, destination: Enr) {
         let discovery = Arc::clone(&self.discovery);
         let protocol = self.protocol.clone();
         let response_tx = self.response_tx.clone();
+        let timeout = self.request_timeouts.for_request(&request);
 
         // Spawn a new thread to send the TALK request. Otherwise we would delay processing of
         // other tasks until we receive the response. Send the response over the response channel,
         // which will be received in the main loop.
         tokio::spawn(async move {
-            let response = match discovery
-                .send_talk_req(destination, protocol, Message::from(request).into())
-                .await
-            {
-                Ok(talk_resp) => match Message::try_from(talk_resp) {
+            let talk_req =
+                discovery.send_talk_req(destination, protocol, Message::from(request).into());
+            let response = match tokio::time::timeout(timeout, talk_req).await {
+                Ok(Ok(talk_resp)) => match Message::try_from(talk_resp) {
                     Ok(message) => match Response::try_from(message) {
                         Ok(response) => Ok(response),
                         Err(_) => Err(OverlayRequestError::InvalidResponse),
                     },
                     Err(_) => Err(OverlayRequestError::DecodeError),
                 },
-                Err(error) => Err(error.into()),
+                Ok(Err(error)) => Err(error.into()),
+                Err(_) => Err(OverlayRequestError::Timeout),
             };
 
             let _ = response_tx.send(OverlayResponse {
@@ -1192,6 +1514,8 @@ where
 
     /// Register source NodeId activity in overlay routing table
     fn register_node_activity(&mut self, source: NodeId) {
+        self.metrics.report_peer_seen(&source);
+
         // Look up the node in the routing table.
         let key = kbucket::Key::from(source);
         let is_node_in_table = matches!(
@@ -1219,12 +1543,38 @@ where
                 let node = Node {
                     enr: node_addr.enr,
                     data_radius: Distance::MAX,
+                    extensions: None,
                 };
                 self.connect_node(node, ConnectionDirection::Incoming);
             }
         }
     }
 
+    /// Returns true if the accepted content of offers to and from the node is sent with one uTP
+    /// stream per item, which requires both nodes to advertise
+    /// `Capability::ConcurrentOfferStreams`.
+    fn uses_offer_streams(&self, node_id: &NodeId) -> bool {
+        let local = self.ping_extensions.as_ref().map_or(false, |extensions| {
+            extensions
+                .capabilities
+                .contains(&Capability::ConcurrentOfferStreams)
+        });
+        if !local {
+            return false;
+        }
+        let key = kbucket::Key::from(*node_id);
+        let extensions = match self.kbuckets.write().entry(&key) {
+            kbucket::Entry::Present(ref mut entry, _) => entry.value().extensions.clone(),
+            kbucket::Entry::Pending(ref mut entry, _) => entry.value().extensions.clone(),
+            _ => None,
+        };
+        extensions.map_or(false, |extensions| {
+            extensions
+                .capabilities
+                .contains(&Capability::ConcurrentOfferStreams)
+        })
+    }
+
     /// Processes a ping request from some source node.
     fn process_ping(&self, ping: Ping, source: NodeId) {
         // Look up the node in the routing table.
@@ -1247,9 +1597,10 @@ where
                 self.request_node(&node.enr());
             }
 
-            let data_radius: Distance = ping.custom_payload.into();
-            if node.data_radius != data_radius {
-                self.update_node_radius(node.enr(), data_radius);
+            let payload = PingPayload::from(ping.custom_payload);
+            let data_radius: Distance = payload.data_radius.into();
+            if node.data_radius != data_radius || node.extensions != payload.extensions {
+                self.update_node_custom_payload(node.enr(), data_radius, payload.extensions);
             }
         }
     }
@@ -1271,6 +1622,7 @@ where
 
         // Attempt to mark the node as disconnected.
         let node_id = destination.node_id();
+        self.metrics.report_request_failure(&node_id);
         let _ = self.update_node_connection_state(node_id, ConnectionState::Disconnected);
         // Remove the node from the ping queue.
         self.peers_to_ping.remove(&node_id);
@@ -1284,6 +1636,8 @@ where
         request: Request,
         query_id: Option<QueryId>,
     ) {
+        self.metrics.report_peer_seen(&source.node_id());
+
         // If the node is present in the routing table, but the node is not connected, then
         // use the existing entry's value and direction. Otherwise, build a new entry from
         // the source ENR and establish a connection in the outgoing direction, because this
@@ -1360,6 +1714,11 @@ where
             }
         };
 
+        let peer = enr.node_id();
+        let accepted = response.content_keys.num_set_bits();
+        self.metrics
+            .report_offer_accept(&peer, accepted, response.content_keys.len() - accepted);
+
         // Do not initialize uTP stream if remote node doesn't have interest in the offered content keys
         if response.content_keys.is_zero() {
             return Ok(response);
@@ -1375,100 +1734,245 @@ where
 
         let store = Arc::clone(&self.store);
         let response_clone = response.clone();
+        let offer_streams = self.uses_offer_streams(&peer);
+        let utp_transfer_limit = self.utp_transfer_limit.max(1);
 
         let utp = Arc::clone(&self.utp_socket);
-        tokio::spawn(async move {
-            let mut stream = match utp.connect_with_cid(cid.clone(), UTP_CONN_CFG).await {
-                Ok(stream) => stream,
-                Err(err) => {
-                    warn!(
-                        %err,
-                        cid.send,
-                        cid.recv,
-                        peer = ?cid.peer.client(),
-                        "Unable to establish uTP conn based on Accept",
-                    );
-                    return;
-                }
-            };
+        let metrics = Arc::clone(&self.metrics);
+        let offered_keys: Vec<String> = match &offer {
+            Request::Offer(offer) => offer.content_keys.iter().map(hex_encode).collect(),
+            Request::PopulatedOffer(offer) => offer
+                .content_items
+                .iter()
+                .map(|(key, _)| hex_encode(key))
+                .collect(),
+            _ => vec![],
+        };
+        let transfer = self.utp_transfers.start(
+            peer,
+            TransferDirection::Outbound,
+            offered_keys
+                .into_iter()
+                .zip(response.content_keys.iter())
+                .filter(|(_, accepted)| *accepted)
+                .map(|(key, _)| key)
+                .collect(),
+        );
+        tokio::spawn(
+            async move {
+                let content_items = match offer {
+                    Request::Offer(offer) => {
+                        Self::provide_requested_content(store, &response_clone, offer.content_keys)
+                    }
+                    Request::PopulatedOffer(offer) => Ok(response_clone
+                        .content_keys
+                        .iter()
+                        .zip(offer.content_items.into_iter())
+                        .filter(|(is_accepted, _item)| *is_accepted)
+                        .map(|(_is_accepted, (_key, val))| val)
+                        .collect()),
+                    // Unreachable because of early return at top of method:
+                    _ => Err(anyhow!("Invalid request message paired with ACCEPT")),
+                };
 
-            let content_items = match offer {
-                Request::Offer(offer) => {
-                    Self::provide_requested_content(store, &response_clone, offer.content_keys)
-                }
-                Request::PopulatedOffer(offer) => Ok(response_clone
-                    .content_keys
-                    .iter()
-                    .zip(offer.content_items.into_iter())
-                    .filter(|(is_accepted, _item)| *is_accepted)
-                    .map(|(_is_accepted, (_key, val))| val)
-                    .collect()),
-                // Unreachable because of early return at top of method:
-                _ => Err(anyhow!("Invalid request message paired with ACCEPT")),
-            };
+                let content_items: Vec<Bytes> = match content_items {
+                    Ok(items) => items
+                        .into_iter()
+                        .map(|item| Bytes::from(item.to_vec()))
+                        .collect(),
+                    Err(err) => {
+                        error!(
+                            %err,
+                            cid.send,
+                            cid.recv,
+                            peer = ?cid.peer.client(),
+                            "Error decoding previously offered content items"
+                        );
+                        return;
+                    }
+                };
 
-            let content_items: Vec<Bytes> = match content_items {
-                Ok(items) => items
-                    .into_iter()
-                    .map(|item| Bytes::from(item.to_vec()))
-                    .collect(),
-                Err(err) => {
-                    error!(
-                        %err,
-                        cid.send,
-                        cid.recv,
-                        peer = ?cid.peer.client(),
-                        "Error decoding previously offered content items"
-                    );
-                    return;
-                }
-            };
+                // Send all content over one stream, unless both nodes agreed on one stream per item
+                let content_payloads = if offer_streams && content_items.len() > 1 {
+                    content_items
+                        .chunks(1)
+                        .map(portal_wire::encode_content_payload)
+                        .collect::<anyhow::Result<Vec<_>>>()
+                } else {
+                    portal_wire::encode_content_payload(&content_items).map(|payload| vec![payload])
+                };
+                let content_payloads = match content_payloads {
+                    Ok(payloads) => payloads,
+                    Err(err) => {
+                        warn!(%err, "Unable to build content payload");
+                        return;
+                    }
+                };
 
-            let content_payload = match portal_wire::encode_content_payload(&content_items) {
-                Ok(payload) => payload,
-                Err(err) => {
-                    warn!(%err, "Unable to build content payload");
-                    return;
-                }
-            };
+                // send the content to the acceptor over uTP streams
+                transfer.set_bytes(content_payloads.iter().map(|payload| payload.len()).sum());
+                stream::iter(content_payloads.into_iter().enumerate())
+                    .map(|(index, payload)| {
+                        let utp = Arc::clone(&utp);
+                        let metrics = Arc::clone(&metrics);
+                        let cid = offer_stream_cid(&cid, index);
+                        async move {
+                            if Self::send_content_payload(utp, cid, &payload).await {
+                                metrics.report_utp_bytes_sent(&peer, payload.len());
+                            }
+                        }
+                    })
+                    .buffer_unordered(utp_transfer_limit)
+                    .collect::<Vec<()>>()
+                    .await;
+            }
+            .instrument(current_or_operation_span("utp")),
+        );
 
-            // send the content to the acceptor over a uTP stream
-            if let Err(err) = stream.write(&content_payload).await {
+        Ok(response)
+    }
+
+    /// Sends a content payload over a new uTP stream with the given CID. Returns whether the
+    /// payload was sent.
+    async fn send_content_payload(
+        utp: Arc<UtpSocket<crate::discovery::UtpEnr>>,
+        cid: ConnectionId<crate::discovery::UtpEnr>,
+        payload: &[u8],
+    ) -> bool {
+        let mut stream = match utp.connect_with_cid(cid.clone(), UTP_CONN_CFG).await {
+            Ok(stream) => stream,
+            Err(err) => {
                 warn!(
                     %err,
                     cid.send,
                     cid.recv,
                     peer = ?cid.peer.client(),
-                    "Error sending content over uTP connection"
+                    "Unable to establish uTP conn based on Accept",
                 );
+                return false;
             }
+        };
 
-            // close uTP connection
-            if let Err(err) = stream.shutdown() {
+        let sent = match stream.write(payload).await {
+            Ok(..) => true,
+            Err(err) => {
                 warn!(
                     %err,
                     cid.send,
                     cid.recv,
                     peer = ?cid.peer.client(),
-                    "Error closing uTP connection"
+                    "Error sending content over uTP connection"
                 );
-            };
-        });
+                false
+            }
+        };
 
-        Ok(response)
+        // close uTP connection
+        if let Err(err) = stream.shutdown() {
+            warn!(
+                %err,
+                cid.send,
+                cid.recv,
+                peer = ?cid.peer.client(),
+                "Error closing uTP connection"
+            );
+        };
+        sent
+    }
+
+    /// Reads the `accepted` content items of an offer, and returns them with the number of bytes
+    /// received.
+    ///
+    /// All accepted content is sent over a single stream with the CID of the ACCEPT, unless both
+    /// nodes advertise `Capability::ConcurrentOfferStreams`. Then `streams` equals `accepted`, and
+    /// each item is sent over its own stream with the CID given by `offer_stream_cid`.
+    async fn read_accepted_content(
+        utp: Arc<UtpSocket<crate::discovery::UtpEnr>>,
+        cid: ConnectionId<crate::discovery::UtpEnr>,
+        accepted: usize,
+        streams: usize,
+    ) -> anyhow::Result<(Vec<Vec<u8>>, usize)> {
+        if streams <= 1 {
+            let data = Self::read_utp_stream(utp, cid).await?;
+            let bytes = data.len();
+            let content_values = portal_wire::decode_content_payload(data)?;
+            if content_values.len() != accepted {
+                return Err(anyhow!(
+                    "Received {} content items for {accepted} accepted content keys",
+                    content_values.len()
+                ));
+            }
+            return Ok((content_values, bytes));
+        }
+
+        let mut item_streams: Vec<JoinHandle<anyhow::Result<Vec<u8>>>> = (0..streams)
+            .map(|index| {
+                let utp = Arc::clone(&utp);
+                let cid = offer_stream_cid(&cid, index);
+                tokio::spawn(Self::read_utp_stream(utp, cid).in_current_span())
+            })
+            .collect();
+
+        let content: anyhow::Result<(Vec<Vec<u8>>, usize)> = async {
+            let mut content_values = Vec::with_capacity(streams);
+            let mut bytes = 0;
+            for handle in item_streams.iter_mut() {
+                let data = match time::timeout(OFFER_STREAM_TIMEOUT, handle).await {
+                    Ok(Ok(data)) => data?,
+                    Ok(Err(err)) => return Err(anyhow!("uTP stream task failed: {err}")),
+                    Err(_) => return Err(anyhow!("Timed out waiting for uTP stream")),
+                };
+                bytes += data.len();
+                let mut values = portal_wire::decode_content_payload(data)?;
+                if values.len() != 1 {
+                    return Err(anyhow!(
+                        "Received {} content items over the uTP stream of one item",
+                        values.len()
+                    ));
+                }
+                content_values.append(&mut values);
+            }
+            Ok((content_values, bytes))
+        }
+        .await;
+
+        // Once the offer has failed, the remaining streams are of no use
+        if content.is_err() {
+            item_streams.iter().for_each(|handle| handle.abort());
+        }
+        content
+    }
+
+    /// Accepts a uTP stream with the given CID and reads it to the end.
+    async fn read_utp_stream(
+        utp: Arc<UtpSocket<crate::discovery::UtpEnr>>,
+        cid: ConnectionId<crate::discovery::UtpEnr>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut stream = utp
+            .accept_with_cid(cid.clone(), UTP_CONN_CFG)
+            .await
+            .map_err(|err| anyhow!("Unable to accept uTP stream: {err}"))?;
+        let mut data = vec![];
+        if let Err(err) = stream.read_to_eof(&mut data).await {
+            warn!(%err, cid.send, cid.recv, peer = ?cid.peer.client(), "error reading data from uTP stream");
+        }
+        Ok(data)
     }
 
     /// Process accepted uTP payload of the OFFER/ACCEPT stream
+    #[allow(clippy::too_many_arguments)]
     async fn process_accept_utp_payload(
         validator: Arc<TValidator>,
+        validation_cache: Arc<ValidationCache>,
         store: Arc<RwLock<TStore>>,
         kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
         command_tx: UnboundedSender<OverlayCommand<TContentKey>>,
+        events: NodeEvents,
+        protocol: ProtocolId,
+        gossip_fanout: GossipFanout,
         content_keys: Vec<TContentKey>,
-        payload: Vec<u8>,
+        content_values: Vec<Vec<u8>>,
     ) -> anyhow::Result<()> {
-        let content_values = portal_wire::decode_content_payload(payload)?;
-
         // Accepted content keys len should match content value len
         let keys_len = content_keys.len();
         let vals_len = content_values.len();
@@ -1488,50 +1992,59 @@ where
                 // - Checks if validated content should be stored, and stores it if true
                 // - Propagate all validated content
                 let validator = Arc::clone(&validator);
+                let validation_cache = Arc::clone(&validation_cache);
                 let store = Arc::clone(&store);
-                tokio::spawn(async move {
-                    // Validated received content
-                    if let Err(err) = validator
-                        .validate_content(&key, &content_value.to_vec())
-                        .await
-                    {
-                        // Skip storing & propagating content if it's not valid
-                        warn!(
-                            error = %err,
-                            content.key = %key.to_hex(),
-                            "Error validating accepted content"
-                        );
-                        return None;
-                    }
+                let events = events.clone();
+                let protocol = protocol.clone();
+                tokio::spawn(
+                    async move {
+                        // Validated received content. Content gossiped by several peers is only
+                        // validated once.
+                        if let Err(err) = validation_cache
+                            .validate(validator.as_ref(), &key, &content_value)
+                            .await
+                        {
+                            // Skip storing & propagating content if it's not valid
+                            warn!(
+                                error = %err,
+                                content.key = %key.to_hex(),
+                                "Error validating accepted content"
+                            );
+                            return None;
+                        }
 
-                    // Check if data should be stored, and store if true.
-                    let key_desired = store.read().is_key_within_radius_and_unavailable(&key);
-                    match key_desired {
-                        Ok(true) => {
-                            if let Err(err) = store.write().put(key.clone(), &content_value) {
-                                warn!(
+                        // Check if data should be stored, and store if true.
+                        let key_desired = store.read().is_key_within_radius_and_unavailable(&key);
+                        match key_desired {
+                            Ok(true) => match store.write().put(key.clone(), &content_value) {
+                                Ok(()) => events.emit(NodeEvent::ContentStored {
+                                    protocol,
+                                    content_key: key.clone().into(),
+                                }),
+                                Err(err) => warn!(
                                     error = %err,
                                     content.key = %key.to_hex(),
                                     "Error storing accepted content"
+                                ),
+                            },
+                            Ok(false) => {
+                                warn!(
+                                    content.key = %key.to_hex(),
+                                    "Accepted content outside radius or already stored"
+                                );
+                            }
+                            Err(err) => {
+                                warn!(
+                                    error = %err,
+                                    content.key = %key.to_hex(),
+                                    "Error checking data store for content key"
                                 );
                             }
                         }
-                        Ok(false) => {
-                            warn!(
-                                content.key = %key.to_hex(),
-                                "Accepted content outside radius or already stored"
-                            );
-                        }
-                        Err(err) => {
-                            warn!(
-                                error = %err,
-                                content.key = %key.to_hex(),
-                                "Error checking data store for content key"
-                            );
-                        }
+                        Some((key, content_value))
                     }
-                    Some((key, content_value))
-                })
+                    .in_current_span(),
+                )
             })
             .collect();
         let validated_content: Vec<(TContentKey, Vec<u8>)> = join_all(handles)
@@ -1546,7 +2059,14 @@ where
             .map(|(k, _)| hex_encode_compact(k.content_id()))
             .collect();
         debug!(ids = ?validated_ids, "propagating validated content");
-        propagate_gossip_cross_thread(validated_content, kbuckets, command_tx.clone());
+        propagate_gossip_cross_thread(
+            validated_content,
+            kbuckets,
+            command_tx.clone(),
+            gossip_fanout,
+            &events,
+            &protocol,
+        );
 
         Ok(())
     }
@@ -1577,19 +2097,29 @@ where
                 self.request_node(&node.enr());
             }
 
-            let data_radius: Distance = pong.custom_payload.into();
-            if node.data_radius != data_radius {
-                self.update_node_radius(source, data_radius);
+            let payload = PingPayload::from(pong.custom_payload);
+            let data_radius: Distance = payload.data_radius.into();
+            if node.data_radius != data_radius || node.extensions != payload.extensions {
+                self.update_node_custom_payload(source, data_radius, payload.extensions);
             }
         }
     }
 
-    /// Update the recorded radius of a node in our routing table.
-    fn update_node_radius(&self, enr: Enr, data_radius: Distance) {
+    /// Update the recorded radius and extensions of a node in our routing table.
+    fn update_node_custom_payload(
+        &self,
+        enr: Enr,
+        data_radius: Distance,
+        extensions: Option<PingExtensions>,
+    ) {
         let node_id = enr.node_id();
         let key = kbucket::Key::from(node_id);
 
-        let updated_node = Node { enr, data_radius };
+        let updated_node = Node {
+            enr,
+            data_radius,
+            extensions,
+        };
 
         if let UpdateResult::Failed(_) = self.kbuckets.write().update_node(&key, updated_node, None)
         {
@@ -1678,29 +2208,46 @@ where
         {
             Ok(true) => {
                 let validator = Arc::clone(&self.validator);
+                let validation_cache = Arc::clone(&self.validation_cache);
                 let store = Arc::clone(&self.store);
+                let events = self.events.clone();
+                let protocol = self.protocol.clone();
                 // Spawn task that validates content before storing.
                 // Allows for non-blocking requests to this/other overlay services.
-                tokio::spawn(async move {
-                    if let Err(err) = validator.validate_content(&content_key, &content).await {
-                        warn!(
-                            error = ?err,
-                            content.id = %hex_encode_compact(content_id),
-                            content.key = %content_key,
-                            "Error validating content"
-                        );
-                        return;
-                    };
+                tokio::spawn(
+                    async move {
+                        if let Err(err) = validation_cache
+                            .validate(validator.as_ref(), &content_key, &content)
+                            .await
+                        {
+                            warn!(
+                                error = ?err,
+                                content.id = %hex_encode_compact(content_id),
+                                content.key = %content_key,
+                                "Error validating content"
+                            );
+                            return;
+                        };
 
-                    if let Err(err) = store.write().put(content_key.clone(), content) {
-                        error!(
-                            error = %err,
-                            content.id = %hex_encode_compact(content_id),
-                            content.key = %content_key,
-                            "Error storing content"
-                        );
+                        let span = debug_span!("storage", elapsed_ms = field::Empty);
+                        let _enter = span.enter();
+                        let started = Instant::now();
+                        match store.write().put(content_key.clone(), content) {
+                            Ok(()) => events.emit(NodeEvent::ContentStored {
+                                protocol,
+                                content_key: content_key.clone().into(),
+                            }),
+                            Err(err) => error!(
+                                error = %err,
+                                content.id = %hex_encode_compact(content_id),
+                                content.key = %content_key,
+                                "Error storing content"
+                            ),
+                        }
+                        span.record("elapsed_ms", started.elapsed().as_millis() as u64);
                     }
-                });
+                    .in_current_span(),
+                );
             }
             Ok(false) => {
                 debug!(
@@ -1753,6 +2300,7 @@ where
                     let updated_node = Node {
                         enr,
                         data_radius: node.data_radius(),
+                        extensions: node.extensions,
                     };
 
                     // The update removed the node because it would violate the incoming peers condition
@@ -1777,6 +2325,10 @@ where
                 match kbuckets.insert_or_update(&key, node, status) {
                     InsertResult::Inserted => {
                         debug!(inserted = %node_id, "Inserted discovered node into routing table");
+                        self.events.emit(NodeEvent::PeerAdded {
+                            protocol: self.protocol.clone(),
+                            peer: node_id,
+                        });
                         self.peers_to_ping.insert(node_id);
                     }
                     InsertResult::Pending { disconnected } => {
@@ -1844,6 +2396,43 @@ where
         Ok(content_items)
     }
 
+    /// Records a completed request made on behalf of a lookup, which is reported when the lookup
+    /// finishes.
+    fn record_lookup_hop(
+        &mut self,
+        query_id: QueryId,
+        request: &Request,
+        peer: &Enr,
+        latency: Duration,
+        success: bool,
+    ) {
+        let query_info = match request {
+            Request::FindNodes(_) => self
+                .find_node_query_pool
+                .get_mut(query_id)
+                .map(|(query_info, _)| query_info),
+            Request::FindContent(_) => self
+                .find_content_query_pool
+                .get_mut(query_id)
+                .map(|(query_info, _)| query_info),
+            _ => None,
+        };
+        if let Some(query_info) = query_info {
+            let target = query_info.key().preimage().raw();
+            let hops = match query_info.hops.as_mut() {
+                Some(hops) => hops,
+                None => return,
+            };
+            let peer = peer.node_id();
+            hops.push(LookupHop {
+                peer,
+                distance: TMetric::distance(&peer.raw(), &target),
+                latency,
+                success,
+            });
+        }
+    }
+
     /// Advances a find node query (if one is active for the node) using the received ENRs.
     /// Does nothing if called with a node_id that does not have a corresponding active query request.
     fn advance_find_node_query(&mut self, source: Enr, enrs: Vec<Enr>, query_id: QueryId) {
@@ -1926,8 +2515,7 @@ where
         );
 
         let enr_seq = self.local_enr().seq();
-        let data_radius = self.data_radius();
-        let custom_payload = CustomPayload::from(data_radius.as_ssz_bytes());
+        let custom_payload = self.custom_payload();
         let ping = Request::Ping(Ping {
             enr_seq,
             custom_payload,
@@ -1965,6 +2553,8 @@ where
             state: ConnectionState::Connected,
             direction: connection_direction,
         };
+        // The radius of a connected node is known, so it can be offered content in its radius.
+        let sync_node = self.neighborhood_sync_tx.as_ref().map(|_| node.clone());
 
         let mut node_to_ping = None;
         match self.kbuckets.write().insert_or_update(&key, node, status) {
@@ -1975,8 +2565,13 @@ where
                     inserted = %node_id,
                     "Node inserted into routing table",
                 );
+                self.events.emit(NodeEvent::PeerAdded {
+                    protocol: self.protocol.clone(),
+                    peer: node_id,
+                });
 
                 self.peers_to_ping.insert(node_id);
+                self.sync_neighborhood(sync_node);
             }
             InsertResult::Pending { disconnected } => {
                 // The disconnected node is the least-recently connected entry that is
@@ -1998,6 +2593,7 @@ where
                         "Node promoted to connected",
                     );
                     self.peers_to_ping.insert(node_id);
+                    self.sync_neighborhood(sync_node);
                 }
             }
             InsertResult::ValueUpdated | InsertResult::UpdatedPending => {}
@@ -2020,6 +2616,14 @@ where
         }
     }
 
+    /// Passes a node that connected to the routing table to the neighborhood sync, if it is
+    /// enabled.
+    fn sync_neighborhood(&self, node: Option<Node>) {
+        if let (Some(sync_tx), Some(node)) = (&self.neighborhood_sync_tx, node) {
+            let _ = sync_tx.send(node);
+        }
+    }
+
     /// Attempts to update the connection state of a node.
     fn update_node_connection_state(
         &mut self,
@@ -2142,6 +2746,7 @@ where
             parallelism: self.query_parallelism,
             num_results: self.query_num_results,
             peer_timeout: self.query_peer_timeout,
+            peer_attempts: self.query_peer_attempts,
         };
 
         let query_info = QueryInfo {
@@ -2151,6 +2756,8 @@ where
                 callback,
             },
             untrusted_enrs: SmallVec::from_vec(closest_enrs),
+            span: current_or_operation_span("lookup"),
+            hops: self.events.sample_lookup().then(Vec::new),
         };
 
         let known_closest_peers: Vec<Key<NodeId>> = query_info
@@ -2186,21 +2793,33 @@ where
             parallelism: self.query_parallelism,
             num_results: self.query_num_results,
             peer_timeout: self.query_peer_timeout,
+            peer_attempts: self.query_peer_attempts,
         };
 
         // Look up the closest ENRs to the target.
         // Limit the number of ENRs according to the query config.
-        let closest_enrs = self
+        let span = debug_span!(
+            "candidate_selection",
+            candidates = field::Empty,
+            elapsed_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let closest_enrs: Vec<Enr> = self
             .kbuckets
             .write()
             .closest_values(&target_key)
             .map(|closest| closest.value.enr)
             .take(query_config.num_results)
             .collect();
+        span.record("candidates", closest_enrs.len());
+        span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+        drop(span);
 
         let query_info = QueryInfo {
             query_type: QueryType::FindContent { target, callback },
             untrusted_enrs: SmallVec::from_vec(closest_enrs),
+            span: current_or_operation_span("lookup"),
+            hops: self.events.sample_lookup().then(Vec::new),
         };
 
         // Convert ENRs into k-bucket keys.
@@ -2221,6 +2840,21 @@ where
         }
     }
 
+    /// Returns the span of an active query, or a disabled span if the query is no longer active.
+    fn query_span(&mut self, kind: QueryKind, query_id: QueryId) -> Span {
+        let span = match kind {
+            QueryKind::FindNode => self
+                .find_node_query_pool
+                .get_mut(query_id)
+                .map(|(query_info, _)| &query_info.span),
+            QueryKind::FindContent => self
+                .find_content_query_pool
+                .get_mut(query_id)
+                .map(|(query_info, _)| &query_info.span),
+        };
+        span.cloned().unwrap_or_else(Span::none)
+    }
+
     /// Returns an ENR if one is known for the given NodeId.
     pub fn find_enr(&self, node_id: &NodeId) -> Option<Enr> {
         // Check whether we know this node id in our routing table.
@@ -2260,6 +2894,9 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
     content: Vec<(TContentKey, Vec<u8>)>,
     kbuckets: Arc<RwLock<KBucketsTable<NodeId, Node>>>,
     command_tx: mpsc::UnboundedSender<OverlayCommand<TContentKey>>,
+    gossip_fanout: GossipFanout,
+    events: &NodeEvents,
+    protocol: &ProtocolId,
 ) -> usize {
     // Get all connected nodes from overlay routing table
     let kbuckets = kbuckets.read();
@@ -2279,20 +2916,20 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
 
     // Filter all nodes from overlay routing table where XOR_distance(content_id, nodeId) < node radius
     for (content_key, content_value) in content {
-        let mut interested_enrs: Vec<Enr> = all_nodes
-            .clone()
-            .into_iter()
-            .filter(|node| {
-                XorMetric::distance(&content_key.content_id(), &node.key.preimage().raw())
-                    < node.value.data_radius()
+        // The content ID is computed once per content key, as it requires hashing the key.
+        let content_id = content_key.content_id();
+        let mut interested_enrs: Vec<(Distance, Enr)> = all_nodes
+            .iter()
+            .filter_map(|node| {
+                let distance = XorMetric::distance(&content_id, &node.key.preimage().raw());
+                (distance < node.value.data_radius()).then(|| (distance, node.value.enr()))
             })
-            .map(|node| node.value.enr())
             .collect();
 
         // Continue if no nodes are interested in the content
         if interested_enrs.is_empty() {
             debug!(
-                content.id = %hex_encode(content_key.content_id()),
+                content.id = %hex_encode(content_id),
                 kbuckets.len = all_nodes.len(),
                 "No peers eligible for neighborhood gossip"
             );
@@ -2300,16 +2937,25 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
         }
 
         // Sort all eligible nodes by proximity to the content.
-        interested_enrs.sort_by(|a, b| {
-            let distance_a = XorMetric::distance(&content_key.content_id(), &a.node_id().raw());
-            let distance_b = XorMetric::distance(&content_key.content_id(), &b.node_id().raw());
-            distance_a.partial_cmp(&distance_b).unwrap_or_else(|| {
-                warn!(a = %distance_a, b = %distance_b, "Error comparing two distances");
-                std::cmp::Ordering::Less
-            })
-        });
-
-        let gossip_recipients = select_gossip_recipients(interested_enrs);
+        interested_enrs.sort_by_key(|(distance, _)| *distance);
+        let interested = interested_enrs.len();
+        let interested_enrs = interested_enrs.into_iter().map(|(_, enr)| enr).collect();
+
+        let gossip_recipients = select_gossip_recipients(interested_enrs, gossip_fanout);
+        if events.sample_gossip() {
+            events.emit(NodeEvent::GossipSent {
+                protocol: protocol.clone(),
+                content_id,
+                interested,
+                recipients: gossip_recipients
+                    .iter()
+                    .map(|enr| {
+                        let node_id = enr.node_id();
+                        (node_id, XorMetric::distance(&content_id, &node_id.raw()))
+                    })
+                    .collect(),
+            });
+        }
 
         // Temporarily store all randomly selected nodes with the content of interest.
         // We want this so we can offer all the content to interested node in one request.
@@ -2333,25 +2979,100 @@ pub fn propagate_gossip_cross_thread<TContentKey: OverlayContentKey>(
             }
         };
 
-        let offer_request = Request::PopulatedOffer(PopulatedOffer {
+        // Split the content into as many OFFER messages as necessary. Each OFFER is answered by
+        // its own ACCEPT, whose bitlist refers to the content keys of that OFFER alone.
+        let offers = PopulatedOffer {
             content_items: interested_content,
-        });
-
-        let overlay_request = OverlayRequest::new(
-            offer_request,
-            RequestDirection::Outgoing { destination: enr },
-            None,
-            None,
-        );
+        }
+        .split();
+        for offer in offers {
+            let overlay_request = OverlayRequest::new(
+                Request::PopulatedOffer(offer),
+                RequestDirection::Outgoing {
+                    destination: enr.clone(),
+                },
+                None,
+                None,
+            );
 
-        if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
-            error!(error = %err, "Error sending OFFER message to service")
+            if let Err(err) = command_tx.send(OverlayCommand::Request(overlay_request)) {
+                error!(error = %err, "Error sending OFFER message to service")
+            }
         }
     }
 
     num_propagated_peers
 }
 
+/// Connection IDs that the service waits for incoming uTP streams on, shared across requests so
+/// that the CIDs derived for the streams of one offer are not handed out to another transfer.
+#[derive(Clone, Default)]
+struct CidReservations(Arc<Mutex<HashSet<(NodeId, u16)>>>);
+
+impl CidReservations {
+    /// Returns a CID for an incoming transfer from `peer` over `streams` uTP streams, whose
+    /// CID and derived CIDs are reserved until the returned reservation is dropped.
+    fn allocate(
+        &self,
+        utp: &UtpSocket<crate::discovery::UtpEnr>,
+        peer: crate::discovery::UtpEnr,
+        streams: usize,
+    ) -> (ConnectionId<crate::discovery::UtpEnr>, CidReservation) {
+        let node_id = peer.node_id();
+        loop {
+            let cid = utp.cid(peer.clone(), false);
+            let recv_ids: Vec<u16> = (0..streams.max(1))
+                .map(|index| offer_stream_cid(&cid, index).recv)
+                .collect();
+            if let Some(reservation) = self.reserve(node_id, recv_ids) {
+                return (cid, reservation);
+            }
+        }
+    }
+
+    /// Reserves the receive IDs for `node_id`, unless any of them is already reserved.
+    fn reserve(&self, node_id: NodeId, recv_ids: Vec<u16>) -> Option<CidReservation> {
+        let mut reserved = self.0.lock();
+        if recv_ids.iter().any(|id| reserved.contains(&(node_id, *id))) {
+            return None;
+        }
+        reserved.extend(recv_ids.iter().map(|id| (node_id, *id)));
+        Some(CidReservation {
+            reservations: self.clone(),
+            node_id,
+            recv_ids,
+        })
+    }
+}
+
+/// Receive IDs reserved by `CidReservations`, released on drop.
+struct CidReservation {
+    reservations: CidReservations,
+    node_id: NodeId,
+    recv_ids: Vec<u16>,
+}
+
+impl Drop for CidReservation {
+    fn drop(&mut self) {
+        let mut reserved = self.reservations.0.lock();
+        for id in &self.recv_ids {
+            reserved.remove(&(self.node_id, *id));
+        }
+    }
+}
+
+/// Returns the CID of the uTP stream over which the `index`th accepted item of an offer is sent,
+/// when each item is sent over its own stream. The first item is sent over the stream with the
+/// CID of the ACCEPT, and each further item over the next pair of connection IDs.
+fn offer_stream_cid<P: Clone>(cid: &ConnectionId<P>, index: usize) -> ConnectionId<P> {
+    let offset = (2 * index) as u16;
+    ConnectionId {
+        send: cid.send.wrapping_add(offset),
+        recv: cid.recv.wrapping_add(offset),
+        peer: cid.peer.clone(),
+    }
+}
+
 /// Randomly select `num_enrs` nodes from `enrs`.
 fn select_random_enrs(num_enrs: usize, enrs: Vec<Enr>) -> Vec<Enr> {
     let random_enrs: Vec<Enr> = enrs
@@ -2360,13 +3081,11 @@ fn select_random_enrs(num_enrs: usize, enrs: Vec<Enr>) -> Vec<Enr> {
     random_enrs
 }
 
-const NUM_CLOSEST_NODES: usize = 4;
-const NUM_FARTHER_NODES: usize = 4;
 /// Selects gossip recipients from a vec of sorted interested ENRs.
 /// Returned vec is a concatenation of, at most:
-/// 1. First `NUM_CLOSEST_NODES` elements of `interested_sorted_enrs`.
-/// 2. `NUM_FARTHER_NODES` elements randomly selected from `interested_sorted_enrs[NUM_CLOSEST_NODES..]`
-fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>) -> Vec<Enr> {
+/// 1. First `fanout.closest` elements of `interested_sorted_enrs`.
+/// 2. `fanout.random` elements randomly selected from `interested_sorted_enrs[fanout.closest..]`
+fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>, fanout: GossipFanout) -> Vec<Enr> {
     let mut gossip_recipients: Vec<Enr> = vec![];
 
     // Get first n closest nodes
@@ -2374,12 +3093,12 @@ fn select_gossip_recipients(interested_sorted_enrs: Vec<Enr>) -> Vec<Enr> {
         interested_sorted_enrs
             .clone()
             .into_iter()
-            .take(NUM_CLOSEST_NODES),
+            .take(fanout.closest),
     );
-    if interested_sorted_enrs.len() > NUM_CLOSEST_NODES {
-        let farther_enrs = interested_sorted_enrs[NUM_CLOSEST_NODES..].to_vec();
+    if interested_sorted_enrs.len() > fanout.closest {
+        let farther_enrs = interested_sorted_enrs[fanout.closest..].to_vec();
         // Get random non-close ENRs to gossip to.
-        let random_farther_enrs = select_random_enrs(NUM_FARTHER_NODES, farther_enrs);
+        let random_farther_enrs = select_random_enrs(fanout.random, farther_enrs);
         gossip_recipients.extend(random_farther_enrs);
     }
     gossip_recipients
@@ -2396,6 +3115,13 @@ pub enum QueryEvent<TQuery, TContentKey> {
     Finished(QueryId, QueryInfo<TContentKey>, TQuery),
 }
 
+/// The kind of a query. Query IDs are only unique within the query pool for their kind.
+#[derive(Clone, Copy, Debug)]
+enum QueryKind {
+    FindNode,
+    FindContent,
+}
+
 /// Limits a to a maximum packet size, including the discv5 header overhead.
 fn pop_while_ssz_bytes_len_gt(enrs: &mut Vec<SszEnr>, max_size: usize) {
     while enrs.ssz_bytes_len() > max_size {
@@ -2469,6 +3195,12 @@ mod tests {
         let (response_tx, response_rx) = mpsc::unbounded_channel();
         let metrics = Arc::new(OverlayMetrics::new());
         let validator = Arc::new(MockValidator {});
+        let validation_cache = Arc::new(ValidationCache::new(
+            overlay_config.validation_cache_size,
+            protocol.clone(),
+            Arc::clone(&metrics),
+            NodeEvents::default(),
+        ));
 
         OverlayService {
             discovery,
@@ -2483,6 +3215,7 @@ mod tests {
             find_node_query_pool: QueryPool::new(overlay_config.query_timeout),
             find_content_query_pool: QueryPool::new(overlay_config.query_timeout),
             query_peer_timeout: overlay_config.query_peer_timeout,
+            query_peer_attempts: overlay_config.query_peer_attempts,
             query_parallelism: overlay_config.query_parallelism,
             query_num_results: overlay_config.query_num_results,
             findnodes_query_distances_per_peer: overlay_config.findnodes_query_distances_per_peer,
@@ -2492,6 +3225,16 @@ mod tests {
             phantom_metric: PhantomData,
             metrics,
             validator,
+            validation_cache,
+            utp_transfers: Arc::new(UtpTransfers::new(ProtocolId::History)),
+            events: NodeEvents::default(),
+            ping_extensions: None,
+            table_depth: overlay_config.table_depth,
+            request_timeouts: overlay_config.request_timeouts,
+            gossip_fanout: overlay_config.gossip_fanout,
+            utp_transfer_limit: overlay_config.utp_transfer_limit,
+            reserved_cids: CidReservations::default(),
+            neighborhood_sync_tx: None,
         }
     }
 
@@ -2836,6 +3579,7 @@ mod tests {
         let peer = Node {
             enr,
             data_radius: Distance::MAX,
+            extensions: None,
         };
         let _ = service
             .kbuckets
@@ -2899,6 +3643,7 @@ mod tests {
         let peer1 = Node {
             enr: enr1,
             data_radius: Distance::MAX,
+            extensions: None,
         };
         let _ = service
             .kbuckets
@@ -2911,6 +3656,7 @@ mod tests {
         let peer2 = Node {
             enr: enr2,
             data_radius: Distance::from(U256::zero()),
+            extensions: None,
         };
         let _ = service
             .kbuckets
@@ -3001,6 +3747,38 @@ mod tests {
         assert!(matches!(request.request, Request::Ping { .. }));
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn refresh_buckets() {
+        let mut service = task::spawn(build_service());
+
+        let (_, destination) = generate_random_remote_enr();
+        let node = Node::new(destination.clone(), Distance::MAX);
+        service.connect_node(node, ConnectionDirection::Outgoing);
+        let bucket = service
+            .kbuckets
+            .read()
+            .buckets_iter()
+            .position(|bucket| bucket.num_entries() > 0)
+            .unwrap();
+
+        service.refresh_buckets(Some(bucket as u8));
+
+        // The node in the bucket is pinged, and a node in the bucket is looked up.
+        let command = assert_ready!(poll_command_rx!(service)).unwrap();
+        let request = if let OverlayCommand::Request(request) = command {
+            request
+        } else {
+            panic!("Unexpected overlay command variant");
+        };
+        assert!(matches!(request.request, Request::Ping { .. }));
+        assert_eq!(
+            RequestDirection::Outgoing { destination },
+            request.direction
+        );
+        assert_eq!(service.find_node_query_pool.iter().count(), 1);
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn connect_node() {
@@ -3020,6 +3798,7 @@ mod tests {
             kbucket::Entry::Absent { .. }
         ));
 
+        let mut events = service.events.subscribe();
         service.connect_node(node, connection_direction);
 
         assert!(service.peers_to_ping.contains_key(&node_id));
@@ -3031,6 +3810,13 @@ mod tests {
             }
             _ => panic!(),
         };
+        assert_eq!(
+            events.try_recv().unwrap(),
+            NodeEvent::PeerAdded {
+                protocol: ProtocolId::History,
+                peer: node_id,
+            }
+        );
     }
 
     #[test_log::test(tokio::test)]
@@ -3357,6 +4143,7 @@ mod tests {
         let bootnode = Node {
             enr: bootnode_enr.clone(),
             data_radius,
+            extensions: None,
         };
 
         let connection_direction = ConnectionDirection::Outgoing;
@@ -3411,6 +4198,7 @@ mod tests {
         let bootnode = Node {
             enr: bootnode_enr.clone(),
             data_radius,
+            extensions: None,
         };
 
         let connection_direction = ConnectionDirection::Outgoing;
@@ -3476,6 +4264,7 @@ mod tests {
         let bootnode = Node {
             enr: bootnode_enr.clone(),
             data_radius,
+            extensions: None,
         };
 
         let connection_direction = ConnectionDirection::Outgoing;
@@ -3537,6 +4326,7 @@ mod tests {
         let bootnode = Node {
             enr: bootnode_enr.clone(),
             data_radius,
+            extensions: None,
         };
 
         let connection_direction = ConnectionDirection::Outgoing;
@@ -3612,25 +4402,89 @@ mod tests {
             .await
             .expect("Expected result on callback channel receiver")
         {
-            (Some(result_content), _) => {
+            (Ok(result_content), _) => {
                 assert_eq!(result_content, content);
             }
             _ => panic!("Unexpected find content query result type"),
         }
     }
 
+    const CLOSEST: usize = 4;
+    const RANDOM: usize = 3;
+
     #[rstest]
     #[case(vec![generate_random_remote_enr().1; 0], 0)]
-    #[case(vec![generate_random_remote_enr().1; NUM_CLOSEST_NODES - 1], NUM_CLOSEST_NODES - 1)]
-    #[case(vec![generate_random_remote_enr().1; NUM_CLOSEST_NODES], NUM_CLOSEST_NODES)]
-    #[case(vec![generate_random_remote_enr().1; NUM_CLOSEST_NODES + 1], NUM_CLOSEST_NODES + 1)]
-    #[case(vec![generate_random_remote_enr().1; NUM_CLOSEST_NODES + NUM_FARTHER_NODES], NUM_CLOSEST_NODES + NUM_FARTHER_NODES)]
-    #[case(vec![generate_random_remote_enr().1; 256], NUM_CLOSEST_NODES + NUM_FARTHER_NODES)]
+    #[case(vec![generate_random_remote_enr().1; CLOSEST - 1], CLOSEST - 1)]
+    #[case(vec![generate_random_remote_enr().1; CLOSEST], CLOSEST)]
+    #[case(vec![generate_random_remote_enr().1; CLOSEST + 1], CLOSEST + 1)]
+    #[case(vec![generate_random_remote_enr().1; CLOSEST + RANDOM], CLOSEST + RANDOM)]
+    #[case(vec![generate_random_remote_enr().1; 256], CLOSEST + RANDOM)]
     fn test_select_gossip_recipients_no_panic(
         #[case] all_nodes: Vec<Enr>,
         #[case] expected_size: usize,
     ) {
-        let gossip_recipients = select_gossip_recipients(all_nodes);
+        let fanout = GossipFanout {
+            closest: CLOSEST,
+            random: RANDOM,
+        };
+        let gossip_recipients = select_gossip_recipients(all_nodes, fanout);
         assert_eq!(gossip_recipients.len(), expected_size);
     }
+
+    #[test]
+    fn test_select_gossip_recipients_zero_fanout() {
+        let all_nodes = vec![generate_random_remote_enr().1; 16];
+        let fanout = GossipFanout {
+            closest: 0,
+            random: 2,
+        };
+        assert_eq!(select_gossip_recipients(all_nodes.clone(), fanout).len(), 2);
+        let fanout = GossipFanout {
+            closest: 0,
+            random: 0,
+        };
+        assert!(select_gossip_recipients(all_nodes, fanout).is_empty());
+    }
+
+    #[test]
+    fn test_offer_stream_cid() {
+        // The CIDs of the offerer and the acceptor mirror each other for every stream.
+        let conn_id = u16::MAX - 1;
+        let offerer = ConnectionId {
+            send: conn_id.wrapping_add(1),
+            recv: conn_id,
+            peer: (),
+        };
+        let acceptor = ConnectionId {
+            send: conn_id,
+            recv: conn_id.wrapping_add(1),
+            peer: (),
+        };
+        assert_eq!(offer_stream_cid(&offerer, 0), offerer);
+        for index in 1..4 {
+            let offerer = offer_stream_cid(&offerer, index);
+            let acceptor = offer_stream_cid(&acceptor, index);
+            assert_eq!(offerer.send, acceptor.recv);
+            assert_eq!(offerer.recv, acceptor.send);
+        }
+        assert_eq!(offer_stream_cid(&acceptor, 1).send, 0);
+    }
+
+    #[test]
+    fn test_cid_reservations() {
+        let reservations = CidReservations::default();
+        let node_id = NodeId::random();
+        let other_node_id = NodeId::random();
+
+        let reservation = reservations.reserve(node_id, vec![10, 12, 14]).unwrap();
+        // Overlapping IDs of the same peer are refused, those of other peers are not.
+        assert!(reservations.reserve(node_id, vec![14, 16]).is_none());
+        assert!(reservations.reserve(other_node_id, vec![14]).is_some());
+        assert!(reservations.reserve(node_id, vec![16]).is_some());
+
+        // Dropping a reservation releases all of its IDs.
+        drop(reservation);
+        assert!(reservations.reserve(node_id, vec![10, 12, 14]).is_some());
+        assert!(reservations.0.lock().is_empty());
+    }
 }
@@ -0,0 +1,146 @@
+//! Re-gossip of under-replicated content, which keeps content that is rarely requested alive on
+//! the network as the nodes that store it leave.
+
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+    time::Duration,
+};
+
+use discv5::enr::NodeId;
+use tokio::{task::JoinHandle, time::interval};
+use tracing::{debug, info, warn};
+
+use crate::{overlay::OverlayProtocol, storage::ContentStore};
+use trin_types::content_key::OverlayContentKey;
+use trin_types::distance::{Distance, Metric};
+use trin_validation::validator::Validator;
+
+/// Configuration of the periodic re-gossip of under-replicated content.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegossipConfig {
+    /// Time between samples of the local content.
+    pub interval: Duration,
+    /// Number of content items checked in each sample.
+    pub sample_size: usize,
+    /// Number of the closest peers whose radius covers an item that are asked for it.
+    pub peers_per_item: usize,
+    /// Number of the asked peers that must return an item for it to be considered replicated.
+    pub min_holders: usize,
+}
+
+impl Default for RegossipConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10 * 60),
+            sample_size: 8,
+            peers_per_item: 3,
+            min_holders: 2,
+        }
+    }
+}
+
+/// Outcome of checking the replication of a sample of the local content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RegossipSummary {
+    /// Number of content items checked.
+    pub sampled: usize,
+    /// Number of content items that were found under-replicated and offered to peers again.
+    pub regossiped: usize,
+}
+
+/// Samples the local content every `config.interval`, starting one interval after startup, and
+/// gossips the items that too few peers can serve again.
+pub fn spawn_regossip<TContentKey, TMetric, TValidator, TStore>(
+    overlay: Arc<OverlayProtocol<TContentKey, TMetric, TValidator, TStore>>,
+    config: RegossipConfig,
+) -> JoinHandle<()>
+where
+    TContentKey: 'static + OverlayContentKey + Send + Sync,
+    TMetric: 'static + Metric + Send + Sync,
+    TValidator: 'static + Validator<TContentKey> + Send + Sync,
+    TStore: 'static + ContentStore + Send + Sync,
+    <TContentKey as TryFrom<Vec<u8>>>::Error: Debug + Display + Send,
+{
+    tokio::spawn(async move {
+        let mut regossip_interval = interval(config.interval);
+        // The first tick completes immediately, before the routing table is populated.
+        regossip_interval.tick().await;
+
+        loop {
+            regossip_interval.tick().await;
+            let summary = regossip_sample(&overlay, &config).await;
+            if summary.regossiped > 0 {
+                info!(
+                    protocol = %overlay.protocol(),
+                    sampled = summary.sampled,
+                    regossiped = summary.regossiped,
+                    "Re-gossiped under-replicated content"
+                );
+            }
+        }
+    })
+}
+
+/// Checks the replication of the content closest to a random point of the local store, and
+/// gossips the items that fewer than `config.min_holders` of the asked peers hold.
+///
+/// Items are only judged when at least `config.min_holders` peers were asked for them, so that a
+/// node with few peers does not re-gossip everything it stores.
+pub async fn regossip_sample<TContentKey, TMetric, TValidator, TStore>(
+    overlay: &OverlayProtocol<TContentKey, TMetric, TValidator, TStore>,
+    config: &RegossipConfig,
+) -> RegossipSummary
+where
+    TContentKey: 'static + OverlayContentKey + Send + Sync,
+    TMetric: 'static + Metric + Send + Sync,
+    TValidator: 'static + Validator<TContentKey> + Send + Sync,
+    TStore: 'static + ContentStore + Send + Sync,
+    <TContentKey as TryFrom<Vec<u8>>>::Error: Debug + Display + Send,
+{
+    let content_keys = overlay.store.read().content_keys_within(
+        &NodeId::random(),
+        Distance::MAX,
+        config.sample_size,
+    );
+    let content_keys = match content_keys {
+        Ok(content_keys) => content_keys,
+        Err(err) => {
+            warn!(%err, "Unable to sample local content for re-gossip");
+            return RegossipSummary::default();
+        }
+    };
+
+    let mut summary = RegossipSummary::default();
+    // The content database may be shared with other subnetworks, whose content is skipped.
+    for content_key in content_keys
+        .into_iter()
+        .filter_map(|key| TContentKey::try_from(key).ok())
+    {
+        summary.sampled += 1;
+        let (asked, holders) = overlay
+            .count_content_holders(&content_key, config.peers_per_item)
+            .await;
+        if asked < config.min_holders || holders >= config.min_holders {
+            continue;
+        }
+        let content = match overlay.store.read().get(&content_key) {
+            Ok(Some(content)) => content,
+            // The content was evicted in the meantime.
+            Ok(None) => continue,
+            Err(err) => {
+                warn!(%err, content.key = %content_key, "Unable to read content for re-gossip");
+                continue;
+            }
+        };
+        debug!(
+            content.key = %content_key,
+            asked,
+            holders,
+            "Re-gossiping under-replicated content"
+        );
+        overlay.propagate_gossip(vec![(content_key, content)]);
+        summary.regossiped += 1;
+    }
+    summary
+}
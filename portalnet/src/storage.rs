@@ -3,30 +3,52 @@ use std::{
     fs,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use anyhow::anyhow;
 use discv5::enr::NodeId;
-use ethportal_api::types::portal::PaginateLocalContentInfo;
+use ethportal_api::types::portal::{PaginateLocalContentInfo, StorageSummaryInfo};
+use lru::LruCache;
 use prometheus_exporter::{
     self,
     prometheus::{
-        opts, register_gauge, register_gauge_with_registry, register_int_gauge_with_registry,
-        Gauge, IntGauge, Registry,
+        exponential_buckets, histogram_opts, opts, register_gauge, register_gauge_with_registry,
+        register_histogram_with_registry, register_int_counter_vec_with_registry,
+        register_int_counter_with_registry, register_int_gauge_with_registry, Gauge, Histogram,
+        IntCounter, IntCounterVec, IntGauge, Registry,
     },
 };
-use r2d2::Pool;
-use r2d2_sqlite::SqliteConnectionManager;
-use rocksdb::{Options, DB};
-use rusqlite::params;
 use thiserror::Error;
-use tracing::{debug, error, info};
-
-use crate::{types::messages::ProtocolId, utils::db::get_data_dir};
-use trin_types::content_key::{ContentKeyError, HistoryContentKey, OverlayContentKey};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    content_db::ContentDb,
+    kv_store::{KvStore, KvStoreOptions},
+    node_events::{NodeEvent, NodeEvents},
+    types::messages::ProtocolId,
+    utils::db::{get_node_data_dir, get_root_path},
+};
+use trin_types::content_key::{
+    ContentKeyError, HistoryContentKey, OverlayContentKey, RawContentKey,
+};
 use trin_types::distance::{Distance, Metric, XorMetric};
 use trin_utils::bytes::{hex_decode, hex_encode, ByteUtilsError};
 
+/// Directory within the node data directory that held content values, before they moved into
+/// the content database.
+#[cfg(feature = "rocksdb")]
+const LEGACY_CONTENT_DB_DIR: &str = "rocksdb";
+#[cfg(not(feature = "rocksdb"))]
+const LEGACY_CONTENT_DB_DIR: &str = "content_db";
+
+/// SQLite file within the node data directory that held content metadata, before it moved into
+/// the content database.
+const LEGACY_META_DB_FILE: &str = "trin.sqlite";
+
+/// Number of legacy content items copied into the content database per transaction.
+const MIGRATION_BATCH_SIZE: usize = 1000;
+
 // TODO: Replace enum with generic type parameter. This will require that we have a way to
 // associate a "find farthest" query with the generic Metric.
 #[derive(Copy, Clone, Debug)]
@@ -50,7 +72,11 @@ pub enum ContentStoreError {
     /// Unable to store or retrieve data because it is invalid.
     #[error("data invalid {message}")]
     InvalidData { message: String },
+    /// Unable to apply a change that the storage configuration doesn't allow.
+    #[error("not allowed by the storage configuration: {0}")]
+    NotAllowed(String),
 
+    #[cfg(feature = "rocksdb")]
     #[error("rocksdb error {0}")]
     Rocksdb(#[from] rocksdb::Error),
 
@@ -88,12 +114,23 @@ pub trait ContentStore {
 
     /// Returns the radius of the data store.
     fn radius(&self) -> Distance;
+
+    /// Returns the keys of at most `limit` stored content items within `distance` of `node_id`,
+    /// closest first. Ephemeral content is not included.
+    fn content_keys_within(
+        &self,
+        node_id: &NodeId,
+        distance: Distance,
+        limit: usize,
+    ) -> Result<Vec<RawContentKey>, ContentStoreError>;
 }
 
 /// An in-memory `ContentStore`.
 pub struct MemoryContentStore {
     /// The content store.
     store: std::collections::HashMap<Vec<u8>, Vec<u8>>,
+    /// The keys of the stored content, by content ID.
+    content_keys: std::collections::HashMap<Vec<u8>, RawContentKey>,
     /// The `NodeId` of the local node.
     node_id: NodeId,
     /// The distance function used by the store to compute distances.
@@ -107,6 +144,7 @@ impl MemoryContentStore {
     pub fn new(node_id: NodeId, distance_fn: DistanceFunction) -> Self {
         Self {
             store: std::collections::HashMap::new(),
+            content_keys: std::collections::HashMap::new(),
             node_id,
             distance_fn,
             radius: Distance::MAX,
@@ -147,6 +185,7 @@ impl ContentStore for MemoryContentStore {
         let content_id = key.content_id();
         let value: &[u8] = value.as_ref();
         self.store.insert(content_id.to_vec(), value.to_vec());
+        self.content_keys.insert(content_id.to_vec(), key.into());
 
         Ok(())
     }
@@ -166,48 +205,206 @@ impl ContentStore for MemoryContentStore {
     fn radius(&self) -> Distance {
         self.radius
     }
+
+    fn content_keys_within(
+        &self,
+        node_id: &NodeId,
+        distance: Distance,
+        limit: usize,
+    ) -> Result<Vec<RawContentKey>, ContentStoreError> {
+        let mut content: Vec<(Distance, &RawContentKey)> = self
+            .content_keys
+            .iter()
+            .filter_map(|(content_id, content_key)| {
+                let content_id: [u8; 32] = content_id.as_slice().try_into().ok()?;
+                let content_distance = XorMetric::distance(&content_id, &node_id.raw());
+                (content_distance <= distance).then_some((content_distance, content_key))
+            })
+            .collect();
+        content.sort();
+        Ok(content
+            .into_iter()
+            .take(limit)
+            .map(|(_, content_key)| content_key.clone())
+            .collect())
+    }
 }
 
 /// Struct for configuring a `PortalStorage` instance.
 #[derive(Clone)]
 pub struct PortalStorageConfig {
     pub storage_capacity_kb: u64,
+    /// Radius to advertise and enforce regardless of storage utilization, if any.
+    pub fixed_radius: Option<Distance>,
+    /// Keep all content, ignoring radius and capacity. See `PortalStorage::archive`.
+    pub archive: bool,
     pub node_id: NodeId,
+    /// Directory owned by this node, which holds all of its databases.
+    pub node_data_dir: PathBuf,
     pub distance_fn: DistanceFunction,
-    pub db: Arc<rocksdb::DB>,
-    pub sql_connection_pool: Pool<SqliteConnectionManager>,
+    /// Tuning options of the key-value stores of the node, such as the state trie.
+    pub db_options: KvStoreOptions,
+    pub content_db: Arc<ContentDb>,
+    /// Bus on which changes of the radius are emitted.
+    pub events: NodeEvents,
 }
 
 impl PortalStorageConfig {
+    /// Opens the databases of the node with `node_id` under the default root path.
     pub fn new(storage_capacity_kb: u64, node_id: NodeId) -> anyhow::Result<Self> {
-        let db = Arc::new(PortalStorage::setup_rocksdb(node_id)?);
-        let sql_connection_pool = PortalStorage::setup_sql(node_id)?;
+        Self::with_root_path(storage_capacity_kb, node_id, &get_root_path(None)?)
+    }
+
+    /// Opens the databases of the node with `node_id` under `root_path`.
+    pub fn with_root_path(
+        storage_capacity_kb: u64,
+        node_id: NodeId,
+        root_path: &Path,
+    ) -> anyhow::Result<Self> {
+        Self::with_db_paths(
+            storage_capacity_kb,
+            node_id,
+            root_path,
+            &[],
+            KvStoreOptions::default(),
+        )
+    }
+
+    /// Opens the databases of the node with `node_id` under `root_path`, with the content
+    /// database sharded across `db_paths` by content id. Without `db_paths`, the content database
+    /// is kept under `root_path` with the other databases.
+    pub fn with_db_paths(
+        storage_capacity_kb: u64,
+        node_id: NodeId,
+        root_path: &Path,
+        db_paths: &[PathBuf],
+        db_options: KvStoreOptions,
+    ) -> anyhow::Result<Self> {
+        let node_data_dir = get_node_data_dir(root_path, node_id)?;
+        let shard_paths = match db_paths {
+            [] => vec![node_data_dir.clone()],
+            db_paths => db_paths
+                .iter()
+                .map(|path| get_node_data_dir(path, node_id))
+                .collect::<anyhow::Result<Vec<PathBuf>>>()?,
+        };
+        let content_db = ContentDb::open(&shard_paths)?;
+        migrate_legacy_content(&node_data_dir, &content_db)?;
+        let content_db = Arc::new(content_db);
         Ok(Self {
             storage_capacity_kb,
+            fixed_radius: None,
+            archive: false,
             node_id,
+            node_data_dir,
             distance_fn: DistanceFunction::Xor,
-            db,
-            sql_connection_pool,
+            db_options,
+            content_db,
+            events: NodeEvents::default(),
         })
     }
 }
 
+/// Moves the content of a data directory written by an earlier version, which kept content values
+/// in a key-value store and their metadata in a separate SQLite database, into `content_db`. The
+/// legacy databases are deleted once all of their content has been copied.
+///
+/// Content is copied in batches of `MIGRATION_BATCH_SIZE` items, each in a single transaction
+/// per shard. An interrupted migration is resumed on the next start, as content already copied is
+/// skipped.
+fn migrate_legacy_content(node_data_dir: &Path, content_db: &ContentDb) -> anyhow::Result<()> {
+    let meta_db_path = node_data_dir.join(LEGACY_META_DB_FILE);
+    if !meta_db_path.exists() {
+        return Ok(());
+    }
+    let legacy_db_path = node_data_dir.join(LEGACY_CONTENT_DB_DIR);
+    let legacy_db = KvStore::open(&legacy_db_path)?;
+    let conn = rusqlite::Connection::open(&meta_db_path)?;
+    let total: u64 = conn.query_row("SELECT COUNT(*) FROM content_metadata", [], |row| {
+        row.get(0)
+    })?;
+    info!(
+        path = %node_data_dir.display(),
+        total,
+        "Migrating content from legacy databases"
+    );
+
+    let mut query = conn.prepare("SELECT content_id_long, content_key FROM content_metadata")?;
+    let rows = query.query_map([], |row| {
+        Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let (mut read, mut migrated) = (0, 0);
+    let mut batch = Vec::with_capacity(MIGRATION_BATCH_SIZE);
+    for row in rows {
+        let (content_id, content_key) = row?;
+        read += 1;
+        let content_id: [u8; 32] = content_id
+            .try_into()
+            .map_err(|_| anyhow!("Invalid content id in legacy metadata database"))?;
+        match legacy_db.get(content_id)? {
+            Some(value) => batch.push((content_id, content_key, value)),
+            None => warn!(
+                content.id = %hex_encode(content_id),
+                "Legacy content metadata without a value, skipping"
+            ),
+        }
+        if batch.len() == MIGRATION_BATCH_SIZE {
+            migrated += content_db.insert_batch(&batch)?;
+            batch.clear();
+            info!(read, total, "Migrating content from legacy databases");
+        }
+    }
+    migrated += content_db.insert_batch(&batch)?;
+    drop(query);
+    drop(conn);
+    drop(legacy_db);
+
+    fs::remove_dir_all(legacy_db_path)?;
+    fs::remove_file(&meta_db_path)?;
+    info!(migrated, "Migrated content from legacy databases");
+    Ok(())
+}
+
+/// Maximum number of ephemeral content items held in memory. Only recent headers are ephemeral,
+/// so this leaves room for several times the headers that are valid at any time. The least
+/// recently used item is dropped to make room for a new one.
+pub const EPHEMERAL_CAPACITY: usize = 1024;
+
 /// Struct whose public methods abstract away Kademlia-based store behavior.
 #[derive(Debug)]
 pub struct PortalStorage {
     node_id: NodeId,
+    node_data_dir: PathBuf,
     storage_capacity_in_bytes: u64,
     radius: Distance,
-    db: Arc<rocksdb::DB>,
-    sql_connection_pool: Pool<SqliteConnectionManager>,
+    /// Radius pinned by configuration, which takes the place of any radius derived from storage
+    /// utilization. Content is still pruned to stay within capacity.
+    fixed_radius: Option<Distance>,
+    /// In archive mode the radius is pinned at the maximum, content is stored regardless of its
+    /// distance and never pruned. The capacity only determines when to warn about disk usage.
+    archive: bool,
+    /// Whether the archive has been reported to exceed the capacity.
+    archive_capacity_warned: bool,
+    db: Arc<ContentDb>,
     distance_fn: DistanceFunction,
     metrics: StorageMetrics,
+    /// Content with a limited time to live, along with the time it expires. The time to live
+    /// depends on the type of content, see `OverlayContentKey::ttl`. Ephemeral content is kept in
+    /// memory, regardless of radius, and does not count towards storage capacity. Expired content
+    /// is dropped by `expire_ephemeral`, and at most `EPHEMERAL_CAPACITY` items are kept.
+    ephemeral: LruCache<[u8; 32], (Vec<u8>, Instant)>,
+    protocol: ProtocolId,
+    events: NodeEvents,
 }
 
 impl ContentStore for PortalStorage {
     fn get<K: OverlayContentKey>(&self, key: &K) -> Result<Option<Vec<u8>>, ContentStoreError> {
         let content_id = key.content_id();
-        Ok(self.db.get(content_id)?)
+        if key.ttl().is_some() {
+            return Ok(self.get_ephemeral(&content_id));
+        }
+        self.db.get(&content_id)
     }
 
     fn put<K: OverlayContentKey, V: AsRef<[u8]>>(
@@ -215,6 +412,16 @@ impl ContentStore for PortalStorage {
         key: K,
         value: V,
     ) -> Result<(), ContentStoreError> {
+        if let Some(ttl) = key.ttl() {
+            if self.radius == Distance::ZERO {
+                return Err(ContentStoreError::InsufficientRadius {
+                    radius: self.radius,
+                    distance: self.distance_to_key(&key),
+                });
+            }
+            self.store_ephemeral(key.content_id(), value.as_ref().to_vec(), ttl);
+            return Ok(());
+        }
         self.store(&key, &value.as_ref().to_vec())
     }
 
@@ -222,41 +429,83 @@ impl ContentStore for PortalStorage {
         &self,
         key: &K,
     ) -> Result<bool, ContentStoreError> {
-        let distance = self.distance_to_key(key);
-        if distance > self.radius {
+        // A store with zero radius holds nothing, not even ephemeral content.
+        if self.radius == Distance::ZERO {
             return Ok(false);
         }
+        // Offers are filtered with this check for every offered key, so the content ID is only
+        // computed once.
+        let content_id = key.content_id();
+        if key.ttl().is_some() {
+            return Ok(self.get_ephemeral(&content_id).is_none());
+        }
 
-        let key = key.content_id();
-        let is_key_available = self.db.get_pinned(key)?.is_some();
+        if self.distance_to_content_id(&content_id) > self.radius {
+            return Ok(false);
+        }
+
+        let is_key_available = self.db.contains(&content_id)?;
         Ok(!is_key_available)
     }
 
     fn radius(&self) -> Distance {
         self.radius
     }
+
+    fn content_keys_within(
+        &self,
+        node_id: &NodeId,
+        distance: Distance,
+        limit: usize,
+    ) -> Result<Vec<RawContentKey>, ContentStoreError> {
+        self.db
+            .find_within(&node_id.raw(), distance, limit as u64)?
+            .into_iter()
+            .map(|(_, content_key)| {
+                // value is stored without 0x prefix, so we must add it
+                hex_decode(&format!("0x{content_key}")).map_err(ContentStoreError::ByteUtilsError)
+            })
+            .collect()
+    }
 }
 
 impl PortalStorage {
     /// Public constructor for building a `PortalStorage` object.
-    /// Checks whether a populated database already exists vs a fresh instance.
-    pub fn new(
+    /// Checks whether a populated database already exists vs a fresh instance. Content that is
+    /// pruned right away is reported by its type as a key of type `K`.
+    pub fn new<K: OverlayContentKey>(
         config: PortalStorageConfig,
         protocol: ProtocolId,
     ) -> Result<Self, ContentStoreError> {
         // Initialize the instance
         let mut storage = Self {
             node_id: config.node_id,
-            storage_capacity_in_bytes: config.storage_capacity_kb * 1000,
-            radius: Distance::MAX,
-            db: config.db,
-            sql_connection_pool: config.sql_connection_pool,
+            node_data_dir: config.node_data_dir,
+            storage_capacity_in_bytes: capacity_in_bytes(config.storage_capacity_kb)?,
+            radius: config.fixed_radius.unwrap_or(Distance::MAX),
+            fixed_radius: match config.archive {
+                true => Some(Distance::MAX),
+                false => config.fixed_radius,
+            },
+            archive: config.archive,
+            archive_capacity_warned: false,
+            db: config.content_db,
             distance_fn: config.distance_fn,
             metrics: StorageMetrics::new(&protocol),
+            ephemeral: LruCache::new(EPHEMERAL_CAPACITY),
+            protocol: protocol.clone(),
+            events: config.events,
         };
 
         // Set the metrics to the default radius, to start
         storage.metrics.report_radius(storage.radius);
+        storage.metrics.report_archive_mode(storage.archive);
+        if storage.archive {
+            warn!(
+                protocol = ?protocol,
+                "Archive mode: all content is kept regardless of radius, and storage grows without bound"
+            );
+        }
 
         // Check whether we already have data, and use it to set radius
         match storage.total_entry_count()? {
@@ -270,7 +519,7 @@ impl PortalStorage {
             entry_count => {
                 storage.metrics.report_entry_count(entry_count);
 
-                if storage.prune_db()? == 0 {
+                if storage.prune_db::<K>()? == 0 {
                     // No items were pruned, so the radius was never calculated.
                     // Calculate current radius now, rather than waiting for the next overfill.
                     if let Some(farthest) = storage.find_farthest_content_id()? {
@@ -301,10 +550,79 @@ impl PortalStorage {
         Ok(storage)
     }
 
-    /// Sets the radius of the store to `radius`.
+    /// Sets the radius of the store to `radius`, unless the radius is fixed by configuration.
     pub fn set_radius(&mut self, radius: Distance) {
+        let radius = self.fixed_radius.unwrap_or(radius);
+        if radius != self.radius {
+            self.events.emit(NodeEvent::RadiusChanged {
+                protocol: self.protocol.clone(),
+                radius,
+            });
+        }
         self.radius = radius;
-        self.metrics.report_radius(radius);
+        self.metrics.report_radius(self.radius);
+    }
+
+    /// Changes the storage capacity to `storage_capacity_kb` while the node is running.
+    /// Shrinking the capacity immediately prunes the farthest content, contracting the radius,
+    /// until usage fits the new capacity. Growing it resets the radius to the maximum, so that
+    /// the store accepts content again until it next fills up. Returns the number of items pruned.
+    pub fn set_storage_capacity<K: OverlayContentKey>(
+        &mut self,
+        storage_capacity_kb: u64,
+    ) -> Result<usize, ContentStoreError> {
+        let storage_capacity_in_bytes = capacity_in_bytes(storage_capacity_kb)?;
+        let grown = storage_capacity_in_bytes > self.storage_capacity_in_bytes;
+        self.storage_capacity_in_bytes = storage_capacity_in_bytes;
+        self.metrics
+            .report_storage_capacity(storage_capacity_kb as f64);
+
+        if storage_capacity_in_bytes == 0 {
+            self.set_radius(Distance::ZERO);
+        } else if grown {
+            self.set_radius(Distance::MAX);
+        }
+        let num_removed_items = self.prune_db::<K>()?;
+        info!(
+            storage_capacity_kb,
+            num_removed_items, "Resized storage capacity"
+        );
+
+        self.get_total_storage_usage_in_bytes_on_disk()?;
+        Ok(num_removed_items)
+    }
+
+    /// Pins the radius to `radius` while the node is running, or goes back to deriving it from
+    /// storage utilization if `radius` is `None`. Content already stored beyond a pinned radius is
+    /// kept until it is pruned. Returns the new radius.
+    ///
+    /// The radius of an archive is always the maximum, and a store without capacity always has a
+    /// zero radius, so neither can be changed.
+    pub fn set_fixed_radius<K: OverlayContentKey>(
+        &mut self,
+        radius: Option<Distance>,
+    ) -> Result<Distance, ContentStoreError> {
+        if self.archive {
+            return Err(ContentStoreError::NotAllowed(
+                "the radius of an archive is pinned at the maximum".to_string(),
+            ));
+        }
+        if self.storage_capacity_in_bytes == 0 {
+            return Err(ContentStoreError::NotAllowed(
+                "a store without capacity has a zero radius".to_string(),
+            ));
+        }
+        self.fixed_radius = radius;
+        match radius {
+            Some(radius) => self.set_radius(radius),
+            None => {
+                // Like growing the capacity: accept content again until the store fills up.
+                self.set_radius(Distance::MAX);
+                self.prune_db::<K>()?;
+            }
+        }
+        info!(radius = %self.radius, fixed = radius.is_some(), "Changed data radius");
+        Ok(self.radius)
     }
 
     /// Returns a paginated list of all available content keys from local storage (from any
@@ -314,23 +632,13 @@ impl PortalStorage {
         offset: &u64,
         limit: &u64,
     ) -> Result<PaginateLocalContentInfo, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(PAGINATE_QUERY)?;
-
-        let content_keys: Result<Vec<HistoryContentKey>, ContentStoreError> = query
-            .query_map(
-                &[
-                    (":offset", offset.to_string().as_str()),
-                    (":limit", limit.to_string().as_str()),
-                ],
-                |row| {
-                    let row: String = row.get(0)?;
-                    Ok(row)
-                },
-            )?
-            .map(|row| {
+        let content_keys: Result<Vec<HistoryContentKey>, ContentStoreError> = self
+            .db
+            .paginate(*offset, *limit)?
+            .into_iter()
+            .map(|content_key| {
                 // value is stored without 0x prefix, so we must add it
-                let bytes: Vec<u8> = hex_decode(&format!("0x{}", row?))
+                let bytes: Vec<u8> = hex_decode(&format!("0x{content_key}"))
                     .map_err(ContentStoreError::ByteUtilsError)?;
                 HistoryContentKey::try_from(bytes).map_err(ContentStoreError::ContentKey)
             })
@@ -342,17 +650,7 @@ impl PortalStorage {
     }
 
     fn total_entry_count(&self) -> Result<u64, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(TOTAL_ENTRY_COUNT_QUERY)?;
-        let result: Result<Vec<EntryCount>, rusqlite::Error> = query
-            .query_map([], |row| Ok(EntryCount(row.get(0)?)))?
-            .collect();
-        match result?.first() {
-            Some(val) => Ok(val.0),
-            None => Err(ContentStoreError::InvalidData {
-                message: "Invalid total entries count returned from sql query.".to_string(),
-            }),
-        }
+        self.db.entry_count()
     }
 
     /// Returns the distance to `key` from the local `NodeId` according to the distance function.
@@ -363,15 +661,15 @@ impl PortalStorage {
     }
 
     /// Method for storing a given value for a given content-key.
-    fn store(
+    fn store<K: OverlayContentKey>(
         &mut self,
-        key: &impl OverlayContentKey,
+        key: &K,
         value: &Vec<u8>,
     ) -> Result<(), ContentStoreError> {
         let content_id = key.content_id();
         let distance_to_content_id = self.distance_to_content_id(&content_id);
 
-        if distance_to_content_id > self.radius {
+        if !self.archive && distance_to_content_id > self.radius {
             // Return Err if content is outside radius
             debug!("Not storing: {:02X?}", key.clone().into());
             return Err(ContentStoreError::InsufficientRadius {
@@ -380,23 +678,17 @@ impl PortalStorage {
             });
         }
 
-        // Store the data in radius db
-        self.db_insert(&content_id, value)?;
         let content_key: Vec<u8> = key.clone().into();
         // store content key w/o the 0x prefix
         let content_key = hex_encode(content_key).trim_start_matches("0x").to_string();
-        // Revert rocks db action if there's an error with writing to metadata db
-        if let Err(err) = self.meta_db_insert(&content_id, &content_key, value) {
-            debug!(
-                "Error writing content ID {:?} to meta db. Reverting: {:?}",
-                content_id, err
-            );
-            self.db.delete(content_id)?;
-            return Err(err);
-        } else {
+        if self.db.insert(&content_id, &content_key, value)? {
             self.metrics.increase_entry_count();
         }
-        self.prune_db()?;
+        if self.archive {
+            self.warn_if_archive_exceeds_capacity()?;
+        } else {
+            self.prune_db::<K>()?;
+        }
         let total_bytes_on_disk = self.get_total_storage_usage_in_bytes_on_disk()?;
         self.metrics
             .report_total_storage_usage(total_bytes_on_disk as f64 / 1000.0);
@@ -404,14 +696,75 @@ impl PortalStorage {
         Ok(())
     }
 
+    /// Stores ephemeral content until `ttl` has elapsed, and drops any expired content.
+    fn store_ephemeral(&mut self, content_id: [u8; 32], value: Vec<u8>, ttl: Duration) {
+        self.expire_ephemeral();
+        self.ephemeral
+            .put(content_id, (value, Instant::now() + ttl));
+        self.metrics
+            .report_ephemeral_entry_count(self.ephemeral.len());
+    }
+
+    /// Drops all ephemeral content whose time to live has elapsed. Returns the number of items
+    /// dropped.
+    pub fn expire_ephemeral(&mut self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<[u8; 32]> = self
+            .ephemeral
+            .iter()
+            .filter(|(_, (_, expires_at))| *expires_at <= now)
+            .map(|(content_id, _)| *content_id)
+            .collect();
+        for content_id in &expired {
+            self.ephemeral.pop(content_id);
+        }
+        let num_expired = expired.len();
+        if num_expired > 0 {
+            debug!(num_expired, "Expired ephemeral content");
+            self.metrics.report_ephemeral_expired(num_expired);
+            self.metrics
+                .report_ephemeral_entry_count(self.ephemeral.len());
+        }
+        num_expired
+    }
+
+    /// Returns ephemeral content, unless it has expired.
+    fn get_ephemeral(&self, content_id: &[u8; 32]) -> Option<Vec<u8>> {
+        match self.ephemeral.peek(content_id) {
+            Some((value, expires_at)) if *expires_at > Instant::now() => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Logs a warning the first time an archive grows beyond the configured capacity.
+    fn warn_if_archive_exceeds_capacity(&mut self) -> Result<(), ContentStoreError> {
+        if !self.archive_capacity_warned && self.capacity_reached()? {
+            warn!(
+                capacity_kb = self.storage_capacity_in_bytes / 1000,
+                "Archive exceeds the configured storage capacity, and will keep growing"
+            );
+            self.archive_capacity_warned = true;
+        }
+        Ok(())
+    }
+
     /// Internal method for pruning any data that falls outside of the radius of the store.
-    /// Resets the data radius if it prunes any data. Does nothing if the store is empty.
+    /// Resets the data radius if it prunes any data. Does nothing if the store is empty or in
+    /// archive mode. Evictions are reported by the type of their content key, decoded as a `K`.
     /// Returns the number of items removed during pruning
-    fn prune_db(&mut self) -> Result<usize, ContentStoreError> {
+    fn prune_db<K: OverlayContentKey>(&mut self) -> Result<usize, ContentStoreError> {
+        if self.archive {
+            return Ok(0);
+        }
+        let start = Instant::now();
+        let radius_before = self.radius;
+        let mut capacity_was_reached = false;
         let mut farthest_content_id: Option<[u8; 32]> = self.find_farthest_content_id()?;
         let mut num_removed_items = 0;
+        let mut num_removed_bytes = 0;
         // Delete furthest data until our data usage is less than capacity.
         while self.capacity_reached()? {
+            capacity_was_reached = true;
             let id_to_remove =
                 // Always expect a content id if capacity_reached(), even at 0kb capacity
                 farthest_content_id.expect("Capacity reached, but no farthest id found!");
@@ -429,10 +782,14 @@ impl PortalStorage {
                 "Capacity reached, deleting farthest: {}",
                 hex_encode(id_to_remove)
             );
+            let content_size = self.get_content_size(&id_to_remove)?;
+            let content_type = self.content_type::<K>(&id_to_remove);
             if let Err(err) = self.evict(id_to_remove) {
-                debug!("Error writing content ID {id_to_remove:?} to meta db. Reverted: {err:?}",);
+                debug!("Error evicting content ID {id_to_remove:?}: {err:?}");
             } else {
                 num_removed_items += 1;
+                num_removed_bytes += content_size;
+                self.metrics.report_eviction(content_type);
             }
             // Calculate new farthest_content_id and reset radius
             match self.find_farthest_content_id()? {
@@ -448,9 +805,33 @@ impl PortalStorage {
                 }
             }
         }
+        if capacity_was_reached {
+            self.metrics.report_prune(
+                num_removed_items,
+                num_removed_bytes,
+                start.elapsed(),
+                radius_before,
+                self.radius,
+            );
+        }
         Ok(num_removed_items)
     }
 
+    /// Returns the type of the content with `id`, whose key is a `K`, for use as a metrics label.
+    fn content_type<K: OverlayContentKey>(&self, id: &[u8; 32]) -> &'static str {
+        let content_key = match self.db.content_key(id) {
+            Ok(Some(content_key)) => content_key,
+            _ => return "unknown",
+        };
+        // value is stored without 0x prefix, so we must add it
+        let content_key = hex_decode(&format!("0x{content_key}"))
+            .ok()
+            .and_then(|bytes| K::try_from(bytes).ok());
+        content_key
+            .map(|content_key| content_key.content_type())
+            .unwrap_or("unknown")
+    }
+
     /// Internal method for testing if an eviction would cause the store to fall under capacity.
     /// Returns true if the store would fall under capacity, false otherwise.
     /// Raises an error if there is a problem accessing the database.
@@ -465,66 +846,43 @@ impl PortalStorage {
     /// Returns the size of the content item in bytes.
     /// Raises an error if there is a problem accessing the database.
     fn get_content_size(&self, id: &[u8; 32]) -> Result<u64, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_SIZE_LOOKUP_QUERY)?;
-        let id_vec = id.to_vec();
-        let result = query.query_map([id_vec], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-        let byte_size = match result?.next() {
-            Some(data_size) => data_size,
+        match self.db.content_size(id)? {
+            Some(size) => Ok(size),
             None => {
                 // Build error message with hex encoded content id
                 let err = format!("Unable to determine size of item {}", hex_encode(id));
-                return Err(ContentStoreError::Database(err));
+                Err(ContentStoreError::Database(err))
             }
-        }?
-        .num_bytes;
-
-        Ok(byte_size as u64)
+        }
     }
 
-    /// Public method for evicting a certain content id. Will revert RocksDB deletion if meta_db
-    /// deletion fails.
+    /// Public method for evicting a certain content id.
     pub fn evict(&self, id: [u8; 32]) -> anyhow::Result<()> {
-        let deleted_value = self.db.get(id)?;
-        self.db.delete(id)?;
-        // Revert rocksdb action if there's an error with writing to metadata db
-        if let Err(err) = self.meta_db_remove(&id) {
-            if let Some(value) = deleted_value {
-                self.db_insert(&id, &value)?;
-            }
-            return Err(anyhow!("failed deletion {err}"));
+        if self.db.delete(&id)? {
+            self.metrics.decrease_entry_count();
         }
-        self.metrics.decrease_entry_count();
         Ok(())
     }
 
     /// Public method for looking up a content key by its content id
     pub fn lookup_content_key(&self, id: [u8; 32]) -> anyhow::Result<Option<Vec<u8>>> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(CONTENT_KEY_LOOKUP_QUERY)?;
-        let id = id.to_vec();
-        let result: Result<Vec<HistoryContentKey>, ContentStoreError> = query
-            .query_map([id], |row| {
-                let row: String = row.get(0)?;
-                Ok(row)
-            })?
-            .map(|row| {
+        match self.db.content_key(&id)? {
+            Some(content_key) => {
                 // value is stored without 0x prefix, so we must add it
-                let bytes: Vec<u8> = hex_decode(&format!("0x{}", row?))?;
-                HistoryContentKey::try_from(bytes).map_err(ContentStoreError::ContentKey)
-            })
-            .collect();
-
-        match result?.first() {
-            Some(val) => Ok(Some(val.into())),
+                let bytes: Vec<u8> = hex_decode(&format!("0x{content_key}"))?;
+                let content_key =
+                    HistoryContentKey::try_from(bytes).map_err(ContentStoreError::ContentKey)?;
+                Ok(Some(content_key.into()))
+            }
             None => Ok(None),
         }
     }
 
+    /// Returns the content database, e.g. to compact it without holding on to the store.
+    pub fn content_db(&self) -> Arc<ContentDb> {
+        Arc::clone(&self.db)
+    }
+
     /// Public method for retrieving the node's current radius.
     pub fn radius(&self) -> Distance {
         self.radius
@@ -533,59 +891,18 @@ impl PortalStorage {
     /// Public method for determining how much actual disk space is being used to store this node's Portal Network data.
     /// Intended for analysis purposes. PortalStorage's capacity decision-making is not based off of this method.
     pub fn get_total_storage_usage_in_bytes_on_disk(&self) -> Result<u64, ContentStoreError> {
-        let data_dir: PathBuf = get_data_dir(self.node_id).map_err(|err| {
-            ContentStoreError::Database(format!(
-                "Unable to get data dir when calculating total storage usage: {err:?}"
-            ))
-        })?;
-        let storage_usage = Self::get_total_size_of_directory_in_bytes(data_dir)?;
+        let mut storage_usage = Self::get_total_size_of_directory_in_bytes(&self.node_data_dir)?;
+        // Content database shards may live outside the node data directory.
+        for path in self.db.paths() {
+            if !path.starts_with(&self.node_data_dir) {
+                storage_usage += Self::get_total_size_of_directory_in_bytes(path)?;
+            }
+        }
         self.metrics
             .report_total_storage_usage(storage_usage as f64 / 1000.0);
         Ok(storage_usage)
     }
 
-    /// Internal method for inserting data into the db.
-    fn db_insert(&self, content_id: &[u8; 32], value: &Vec<u8>) -> Result<(), ContentStoreError> {
-        self.db.put(content_id, value)?;
-        Ok(())
-    }
-
-    /// Internal method for inserting data into the meta db.
-    fn meta_db_insert(
-        &self,
-        content_id: &[u8; 32],
-        content_key: &String,
-        value: &Vec<u8>,
-    ) -> Result<(), ContentStoreError> {
-        let content_id_as_u32: u32 = Self::byte_vector_to_u32(content_id.to_vec());
-        let value_size = value.len();
-        if content_key.starts_with("0x") {
-            return Err(ContentStoreError::InvalidData {
-                message: "Content key should not start with 0x".to_string(),
-            });
-        }
-        match self.sql_connection_pool.get()?.execute(
-            INSERT_QUERY,
-            params![
-                content_id.to_vec(),
-                content_id_as_u32,
-                content_key,
-                value_size
-            ],
-        ) {
-            Ok(_) => Ok(()),
-            Err(err) => Err(err.into()),
-        }
-    }
-
-    /// Internal method for removing a given content-id from the meta db.
-    fn meta_db_remove(&self, content_id: &[u8; 32]) -> Result<(), ContentStoreError> {
-        self.sql_connection_pool
-            .get()?
-            .execute(DELETE_QUERY, [content_id.to_vec()])?;
-        Ok(())
-    }
-
     /// Internal method for determining whether the node is over-capacity.
     fn capacity_reached(&self) -> Result<bool, ContentStoreError> {
         let storage_usage = self.get_total_storage_usage_in_bytes_from_network()?;
@@ -594,68 +911,19 @@ impl PortalStorage {
 
     /// Internal method for measuring the total amount of requestable data that the node is storing.
     fn get_total_storage_usage_in_bytes_from_network(&self) -> Result<u64, ContentStoreError> {
-        let conn = self.sql_connection_pool.get()?;
-        let mut query = conn.prepare(TOTAL_DATA_SIZE_QUERY)?;
-
-        let result = query.query_map([], |row| {
-            Ok(DataSize {
-                num_bytes: row.get(0)?,
-            })
-        });
-
-        let sum = match result?.next() {
-            Some(total) => total,
-            None => {
-                let err = "Unable to compute sum over content item sizes".to_string();
-                return Err(ContentStoreError::Database(err));
-            }
-        }?
-        .num_bytes;
-
-        self.metrics.report_content_data_storage(sum / 1000.0);
+        let sum = self.db.total_size()?;
+        self.metrics
+            .report_content_data_storage(sum as f64 / 1000.0);
 
-        Ok(sum as u64)
+        Ok(sum)
     }
 
     /// Internal method for finding the piece of stored data that has the farthest content id from our
     /// node id, according to xor distance. Used to determine which data to drop when at a capacity.
     fn find_farthest_content_id(&self) -> Result<Option<[u8; 32]>, ContentStoreError> {
-        let result = match self.distance_fn {
-            DistanceFunction::Xor => {
-                let node_id_u32 = Self::byte_vector_to_u32(self.node_id.raw().to_vec());
-
-                let conn = self.sql_connection_pool.get()?;
-                let mut query = conn.prepare(XOR_FIND_FARTHEST_QUERY)?;
-
-                let mut result = query.query_map([node_id_u32], |row| {
-                    Ok(ContentId {
-                        id_long: row.get(0)?,
-                    })
-                })?;
-
-                let result = match result.next() {
-                    Some(row) => row,
-                    None => {
-                        return Ok(None);
-                    }
-                };
-                let result = result?.id_long;
-                let result_vec: [u8; 32] = match result.len() {
-                    // If exact data size, safe to expect conversion.
-                    32 => result.try_into().expect(
-                        "Unexpectedly failed to convert 32 element vec to 32 element array.",
-                    ),
-                    // Received data of size other than 32 bytes.
-                    length => {
-                        let err = format!("content ID of length {} != 32", length);
-                        return Err(ContentStoreError::InvalidData { message: err });
-                    }
-                };
-                result_vec
-            }
-        };
-
-        Ok(Some(result))
+        match self.distance_fn {
+            DistanceFunction::Xor => self.db.find_farthest(&self.node_id.raw()),
+        }
     }
 
     /// Internal method used to measure on-disk storage usage.
@@ -698,52 +966,36 @@ impl PortalStorage {
         }
     }
 
-    /// Converts most significant 4 bytes of a vector to a u32.
-    fn byte_vector_to_u32(vec: Vec<u8>) -> u32 {
-        if vec.len() < 4 {
-            debug!("Error: XOR returned less than 4 bytes.");
-            return 0;
-        }
-
-        let mut array: [u8; 4] = [0, 0, 0, 0];
-        for (index, byte) in vec.iter().take(4).enumerate() {
-            array[index] = *byte;
-        }
-
-        u32::from_be_bytes(array)
+    /// Get a summary of the current state of storage
+    pub fn get_summary_info(&self) -> String {
+        self.metrics.get_summary()
     }
 
-    /// Helper function for opening a RocksDB connection for the radius-constrained db.
-    pub fn setup_rocksdb(node_id: NodeId) -> Result<rocksdb::DB, ContentStoreError> {
-        let mut data_path: PathBuf = get_data_dir(node_id).map_err(|err| {
-            ContentStoreError::Database(format!("Unable to get data dir for rocksdb: {err:?}"))
-        })?;
-        data_path.push("rocksdb");
-        info!(path = %data_path.display(), "Setting up RocksDB");
-
-        let mut db_opts = Options::default();
-        db_opts.create_if_missing(true);
-        Ok(DB::open(&db_opts, data_path)?)
+    /// Get a structured summary of the current state of storage
+    pub fn get_storage_summary(&self) -> StorageSummaryInfo {
+        self.metrics.get_storage_summary(self.radius)
     }
+}
 
-    /// Helper function for opening a SQLite connection.
-    pub fn setup_sql(node_id: NodeId) -> Result<Pool<SqliteConnectionManager>, ContentStoreError> {
-        let mut data_path: PathBuf = get_data_dir(node_id).map_err(|err| {
-            ContentStoreError::Database(format!("Unable to get data dir for sql: {err:?}"))
-        })?;
-        data_path.push("trin.sqlite");
-        info!(path = %data_path.display(), "Setting up SqliteDB");
-
-        let manager = SqliteConnectionManager::file(data_path);
-        let pool = Pool::new(manager)?;
-        pool.get()?.execute(CREATE_QUERY, params![])?;
-        Ok(pool)
-    }
+/// Converts a storage capacity in kilobytes to bytes, failing if it doesn't fit in a `u64`.
+fn capacity_in_bytes(storage_capacity_kb: u64) -> Result<u64, ContentStoreError> {
+    storage_capacity_kb
+        .checked_mul(1000)
+        .ok_or_else(|| ContentStoreError::InvalidData {
+            message: format!("storage capacity of {storage_capacity_kb} kB is too large"),
+        })
+}
 
-    /// Get a summary of the current state of storage
-    pub fn get_summary_info(&self) -> String {
-        self.metrics.get_summary()
-    }
+/// Returns the fraction of the whole data ring covered by `radius`.
+pub fn radius_ratio(radius: Distance) -> f64 {
+    let radius_high_bytes = [
+        radius.byte(31),
+        radius.byte(30),
+        radius.byte(29),
+        radius.byte(28),
+    ];
+    let radius_int = u32::from_be_bytes(radius_high_bytes);
+    radius_int as f64 / u32::MAX as f64
 }
 
 #[derive(Debug)]
@@ -753,6 +1005,15 @@ struct StorageMetrics {
     storage_capacity_kb: Gauge,
     radius_ratio: Gauge,
     entry_count: IntGauge,
+    archive_mode: IntGauge,
+    ephemeral_entry_count: IntGauge,
+    ephemeral_expired: IntCounter,
+    evictions: IntCounterVec,
+    prune_evicted_items: Histogram,
+    prune_evicted_bytes: Histogram,
+    prune_duration: Histogram,
+    prune_radius_ratio_before: Gauge,
+    prune_radius_ratio_after: Gauge,
 }
 
 impl StorageMetrics {
@@ -802,6 +1063,71 @@ impl StorageMetrics {
             registry,
         )
         .unwrap();
+        let archive_mode = register_int_gauge_with_registry!(
+            format!("trin_archive_mode_{protocol:?}"),
+            "1 if storage keeps all content without pruning, 0 otherwise",
+            registry,
+        )
+        .unwrap();
+        let ephemeral_entry_count = register_int_gauge_with_registry!(
+            format!("trin_ephemeral_entry_count_{protocol:?}"),
+            "number of ephemeral content items held in memory",
+            registry,
+        )
+        .unwrap();
+        let ephemeral_expired = register_int_counter_with_registry!(
+            format!("trin_ephemeral_expired_total_{protocol:?}"),
+            "number of ephemeral content items dropped after their time to live elapsed",
+            registry,
+        )
+        .unwrap();
+        let evictions = register_int_counter_vec_with_registry!(
+            opts!(
+                format!("trin_evictions_total_{protocol:?}"),
+                "number of content items evicted to stay within capacity, by content type"
+            ),
+            &["content_type"],
+            registry,
+        )
+        .unwrap();
+        let prune_evicted_items = register_histogram_with_registry!(
+            histogram_opts!(
+                format!("trin_prune_evicted_items_{protocol:?}"),
+                "number of content items evicted per pruning run",
+                exponential_buckets(1.0, 4.0, 8).unwrap()
+            ),
+            registry,
+        )
+        .unwrap();
+        let prune_evicted_bytes = register_histogram_with_registry!(
+            histogram_opts!(
+                format!("trin_prune_evicted_bytes_{protocol:?}"),
+                "size of the content evicted per pruning run, in bytes",
+                exponential_buckets(1000.0, 4.0, 10).unwrap()
+            ),
+            registry,
+        )
+        .unwrap();
+        let prune_duration = register_histogram_with_registry!(
+            histogram_opts!(
+                format!("trin_prune_duration_seconds_{protocol:?}"),
+                "time taken by a pruning run, in seconds"
+            ),
+            registry,
+        )
+        .unwrap();
+        let prune_radius_ratio_before = register_gauge_with_registry!(
+            format!("trin_prune_radius_ratio_before_{protocol:?}"),
+            "the radius ratio before the latest pruning run",
+            registry,
+        )
+        .unwrap();
+        let prune_radius_ratio_after = register_gauge_with_registry!(
+            format!("trin_prune_radius_ratio_after_{protocol:?}"),
+            "the radius ratio after the latest pruning run",
+            registry,
+        )
+        .unwrap();
 
         Self {
             content_storage_usage_kb,
@@ -809,6 +1135,15 @@ impl StorageMetrics {
             storage_capacity_kb,
             radius_ratio,
             entry_count,
+            archive_mode,
+            ephemeral_entry_count,
+            ephemeral_expired,
+            evictions,
+            prune_evicted_items,
+            prune_evicted_bytes,
+            prune_duration,
+            prune_radius_ratio_before,
+            prune_radius_ratio_after,
         }
     }
 
@@ -825,15 +1160,40 @@ impl StorageMetrics {
     }
 
     pub fn report_radius(&self, radius: Distance) {
-        let radius_high_bytes = [
-            radius.byte(31),
-            radius.byte(30),
-            radius.byte(29),
-            radius.byte(28),
-        ];
-        let radius_int = u32::from_be_bytes(radius_high_bytes);
-        let coverage_ratio = radius_int as f64 / u32::MAX as f64;
-        self.radius_ratio.set(coverage_ratio);
+        self.radius_ratio.set(radius_ratio(radius));
+    }
+
+    pub fn report_eviction(&self, content_type: &str) {
+        self.evictions.with_label_values(&[content_type]).inc();
+    }
+
+    pub fn report_prune(
+        &self,
+        evicted_items: usize,
+        evicted_bytes: u64,
+        duration: Duration,
+        radius_before: Distance,
+        radius_after: Distance,
+    ) {
+        self.prune_evicted_items.observe(evicted_items as f64);
+        self.prune_evicted_bytes.observe(evicted_bytes as f64);
+        self.prune_duration.observe(duration.as_secs_f64());
+        self.prune_radius_ratio_before
+            .set(radius_ratio(radius_before));
+        self.prune_radius_ratio_after
+            .set(radius_ratio(radius_after));
+    }
+
+    pub fn report_ephemeral_entry_count(&self, count: usize) {
+        self.ephemeral_entry_count.set(count as i64);
+    }
+
+    pub fn report_ephemeral_expired(&self, count: usize) {
+        self.ephemeral_expired.inc_by(count as u64);
+    }
+
+    pub fn report_archive_mode(&self, archive: bool) {
+        self.archive_mode.set(archive.into());
     }
 
     pub fn report_entry_count(&self, count: u64) {
@@ -864,6 +1224,17 @@ impl StorageMetrics {
         )
     }
 
+    pub fn get_storage_summary(&self, radius: Distance) -> StorageSummaryInfo {
+        StorageSummaryInfo {
+            data_radius: *radius,
+            radius_percent: self.radius_ratio.get() * 100.0,
+            content_storage_usage_kb: self.content_storage_usage_kb.get(),
+            total_storage_usage_kb: self.total_storage_usage_kb.get(),
+            storage_capacity_kb: self.storage_capacity_kb.get(),
+            entry_count: self.entry_count.get().max(0) as u64,
+        }
+    }
+
     fn precision_for_percentage(percent: f64) -> usize {
         match percent {
             x if x >= 10.0 => 0,
@@ -875,65 +1246,19 @@ impl StorageMetrics {
     }
 }
 
-// SQLite Statements
-const CREATE_QUERY: &str = "CREATE TABLE IF NOT EXISTS content_metadata (
-                                content_id_long TEXT PRIMARY KEY,
-                                content_id_short INTEGER NOT NULL,
-                                content_key TEXT NOT NULL,
-                                content_size INTEGER
-                            );
-                            CREATE INDEX content_size_idx ON content_metadata(content_size);
-                            CREATE INDEX content_id_short_idx ON content_metadata(content_id_short);
-                            CREATE INDEX content_id_long_idx ON content_metadata(content_id_long);";
-
-const INSERT_QUERY: &str =
-    "INSERT OR IGNORE INTO content_metadata (content_id_long, content_id_short, content_key, content_size)
-                            VALUES (?1, ?2, ?3, ?4)";
-
-const DELETE_QUERY: &str = "DELETE FROM content_metadata
-                            WHERE content_id_long = (?1)";
-
-const XOR_FIND_FARTHEST_QUERY: &str = "SELECT
-                                    content_id_long
-                                    FROM content_metadata
-                                    ORDER BY ((?1 | content_id_short) - (?1 & content_id_short)) DESC";
-
-const CONTENT_KEY_LOOKUP_QUERY: &str =
-    "SELECT content_key FROM content_metadata WHERE content_id_long = (?1)";
-
-const TOTAL_DATA_SIZE_QUERY: &str = "SELECT TOTAL(content_size) FROM content_metadata";
-
-const TOTAL_ENTRY_COUNT_QUERY: &str = "SELECT COUNT(content_id_long) FROM content_metadata";
-
-const PAGINATE_QUERY: &str =
-    "SELECT content_key FROM content_metadata ORDER BY content_key LIMIT :limit OFFSET :offset";
-
-const CONTENT_SIZE_LOOKUP_QUERY: &str =
-    "SELECT content_size FROM content_metadata WHERE content_id_long = (?1)";
-
-// SQLite Result Containers
-struct ContentId {
-    id_long: Vec<u8>,
-}
-
-struct DataSize {
-    num_bytes: f64,
-}
-
-struct EntryCount(u64);
-
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 pub mod test {
 
     use super::*;
 
+    use ethereum_types::U256;
     use quickcheck::{quickcheck, QuickCheck, TestResult};
     use rand::RngCore;
     use serial_test::serial;
 
     use crate::utils::db::setup_temp_dir;
-    use trin_types::content_key::IdentityContentKey;
+    use trin_types::content_key::{BlockBodyKey, EphemeralHeaderKey, IdentityContentKey};
 
     const CAPACITY: u64 = 100;
 
@@ -943,6 +1268,14 @@ pub mod test {
         IdentityContentKey::new(key)
     }
 
+    #[test]
+    fn test_radius_ratio() {
+        assert_eq!(radius_ratio(Distance::ZERO), 0.0);
+        assert_eq!(radius_ratio(Distance::MAX), 1.0);
+        let half = Distance::from(U256::MAX / 2);
+        assert!((radius_ratio(half) - 0.5).abs() < 1e-9);
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_new() -> Result<(), ContentStoreError> {
@@ -951,7 +1284,8 @@ pub mod test {
         let node_id = NodeId::random();
 
         let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-        let storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         // Assert that configs match the storage object's fields
         assert_eq!(storage.node_id, node_id);
@@ -971,7 +1305,9 @@ pub mod test {
 
             let node_id = NodeId::random();
             let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-            let mut storage = PortalStorage::new(storage_config, ProtocolId::History).unwrap();
+            let mut storage =
+                PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)
+                    .unwrap();
             let content_key = generate_random_content_key();
             let mut value = [0u8; 32];
             rand::thread_rng().fill_bytes(&mut value);
@@ -994,7 +1330,8 @@ pub mod test {
 
         let node_id = NodeId::random();
         let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-        let mut storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
         let content_key = generate_random_content_key();
         let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
         storage.store(&content_key, &value)?;
@@ -1008,6 +1345,71 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_ephemeral_content_expires() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+        let content_key = HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+            block_hash: [1; 32],
+        });
+        let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+        storage.put(content_key.clone(), &value)?;
+
+        assert_eq!(storage.get(&content_key)?, Some(value));
+        assert!(!storage.is_key_within_radius_and_unavailable(&content_key)?);
+        // Ephemeral content is not persisted.
+        assert_eq!(storage.total_entry_count()?, 0);
+
+        // Expire the content.
+        let content_id = content_key.content_id();
+        let (value, _) = storage.ephemeral.pop(&content_id).unwrap();
+        storage.ephemeral.put(content_id, (value, Instant::now()));
+        assert_eq!(storage.get(&content_key)?, None);
+        assert!(storage.is_key_within_radius_and_unavailable(&content_key)?);
+
+        // The sweep drops the expired content from memory.
+        assert_eq!(storage.expire_ephemeral(), 1);
+        assert!(storage.ephemeral.is_empty());
+        assert_eq!(storage.expire_ephemeral(), 0);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_ephemeral_content_is_capped() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+        let content_key = |index: usize| {
+            let mut block_hash = [0; 32];
+            block_hash[..8].copy_from_slice(&index.to_be_bytes());
+            HistoryContentKey::EphemeralHeader(EphemeralHeaderKey { block_hash })
+        };
+        for index in 0..=EPHEMERAL_CAPACITY {
+            storage.put(content_key(index), [1, 2, 3])?;
+        }
+
+        // The least recently stored item made room for the last one.
+        assert_eq!(storage.ephemeral.len(), EPHEMERAL_CAPACITY);
+        assert_eq!(storage.get(&content_key(0))?, None);
+        assert!(storage.get(&content_key(EPHEMERAL_CAPACITY))?.is_some());
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_get_total_storage() -> Result<(), ContentStoreError> {
@@ -1015,7 +1417,8 @@ pub mod test {
 
         let node_id = NodeId::random();
         let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-        let mut storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         let content_key = generate_random_content_key();
         let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
@@ -1037,7 +1440,8 @@ pub mod test {
 
         let node_id = NodeId::random();
         let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-        let mut storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         for _ in 0..50 {
             let content_key = generate_random_content_key();
@@ -1052,7 +1456,8 @@ pub mod test {
 
         // test with 1kb capacity
         let new_storage_config = PortalStorageConfig::new(1, node_id).unwrap();
-        let new_storage = PortalStorage::new(new_storage_config, ProtocolId::History)?;
+        let new_storage =
+            PortalStorage::new::<IdentityContentKey>(new_storage_config, ProtocolId::History)?;
 
         // test that previously set value has been pruned
         let bytes = new_storage.get_total_storage_usage_in_bytes_from_network()?;
@@ -1065,7 +1470,8 @@ pub mod test {
 
         // test with 0kb capacity
         let new_storage_config = PortalStorageConfig::new(0, node_id).unwrap();
-        let new_storage = PortalStorage::new(new_storage_config, ProtocolId::History)?;
+        let new_storage =
+            PortalStorage::new::<IdentityContentKey>(new_storage_config, ProtocolId::History)?;
 
         // test that previously set value has been pruned
         assert_eq!(new_storage.storage_capacity_in_bytes, 0);
@@ -1077,6 +1483,224 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_resizing_storage_capacity() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+
+        for _ in 0..50 {
+            let content_key = generate_random_content_key();
+            let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+            storage.store(&content_key, &value)?;
+        }
+
+        // a capacity that overflows in bytes is rejected and leaves the capacity unchanged
+        assert!(matches!(
+            storage.set_storage_capacity::<IdentityContentKey>(u64::MAX),
+            Err(ContentStoreError::InvalidData { .. })
+        ));
+        assert_eq!(storage.storage_capacity_in_bytes, CAPACITY * 1000);
+
+        // shrinking prunes down to the new capacity, and contracts the radius
+        assert_eq!(storage.set_storage_capacity::<IdentityContentKey>(1)?, 18);
+        assert_eq!(storage.storage_capacity_in_bytes, 1000);
+        assert_eq!(
+            1024,
+            storage.get_total_storage_usage_in_bytes_from_network()?
+        );
+        assert!(storage.radius < Distance::MAX);
+
+        // growing keeps the content, and re-opens the radius
+        assert_eq!(
+            storage.set_storage_capacity::<IdentityContentKey>(CAPACITY)?,
+            0
+        );
+        assert_eq!(32, storage.total_entry_count()?);
+        assert_eq!(storage.radius, Distance::MAX);
+
+        // shrinking to zero removes everything
+        storage.set_storage_capacity::<IdentityContentKey>(0)?;
+        assert_eq!(0, storage.total_entry_count()?);
+        assert_eq!(storage.radius, Distance::ZERO);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_content_type_decodes_key_as_given_type() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<HistoryContentKey>(storage_config, ProtocolId::History)?;
+
+        let content_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: [1; 32],
+        });
+        storage.store(&content_key, &vec![0xef])?;
+        let content_id = content_key.content_id();
+        assert_eq!(
+            storage.content_type::<HistoryContentKey>(&content_id),
+            "block_body"
+        );
+        // A history content key is not a valid identity content key.
+        assert_eq!(
+            storage.content_type::<IdentityContentKey>(&content_id),
+            "unknown"
+        );
+        assert_eq!(
+            storage.content_type::<HistoryContentKey>(&[2; 32]),
+            "unknown"
+        );
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_fixed_radius_survives_pruning() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let mut storage_config = PortalStorageConfig::new(1, node_id).unwrap();
+        storage_config.fixed_radius = Some(Distance::MAX);
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+
+        for _ in 0..50 {
+            let content_key = generate_random_content_key();
+            let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+            storage.store(&content_key, &value)?;
+        }
+
+        // content is pruned to capacity, but the radius stays pinned
+        assert_eq!(
+            1024,
+            storage.get_total_storage_usage_in_bytes_from_network()?
+        );
+        assert_eq!(storage.radius, Distance::MAX);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_set_fixed_radius() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+        let radius = Distance::from(U256::from(1024));
+
+        // a pinned radius survives capacity changes
+        assert_eq!(
+            storage.set_fixed_radius::<IdentityContentKey>(Some(radius))?,
+            radius
+        );
+        storage.set_storage_capacity::<IdentityContentKey>(CAPACITY * 2)?;
+        assert_eq!(storage.radius, radius);
+
+        // unpinning opens the radius again
+        assert_eq!(
+            storage.set_fixed_radius::<IdentityContentKey>(None)?,
+            Distance::MAX
+        );
+
+        // a store without capacity keeps its zero radius
+        storage.set_storage_capacity::<IdentityContentKey>(0)?;
+        assert!(storage
+            .set_fixed_radius::<IdentityContentKey>(Some(radius))
+            .is_err());
+        assert_eq!(storage.radius, Distance::ZERO);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_archive_keeps_everything() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+
+        let node_id = NodeId::random();
+        let mut storage_config = PortalStorageConfig::new(1, node_id).unwrap();
+        storage_config.archive = true;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+
+        for _ in 0..50 {
+            let content_key = generate_random_content_key();
+            let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+            storage.store(&content_key, &value)?;
+        }
+
+        // nothing is pruned, even far beyond capacity
+        assert_eq!(
+            1600,
+            storage.get_total_storage_usage_in_bytes_from_network()?
+        );
+        assert_eq!(50, storage.total_entry_count()?);
+        assert_eq!(storage.radius, Distance::MAX);
+        assert!(storage.archive_capacity_warned);
+
+        // shrinking the capacity does not prune an archive either
+        assert_eq!(storage.set_storage_capacity::<IdentityContentKey>(0)?, 0);
+        assert_eq!(storage.radius, Distance::MAX);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_pruning_reports_evictions() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = NodeId::random();
+        let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+
+        for _ in 0..50 {
+            let content_key = generate_random_content_key();
+            let value: Vec<u8> = vec![0xef; 32];
+            storage.store(&content_key, &value)?;
+        }
+        // No pruning is needed below capacity.
+        assert_eq!(storage.metrics.prune_duration.get_sample_count(), 0);
+
+        // 1kb holds 32 items of 32 bytes, the others are evicted in a single run.
+        assert_eq!(storage.set_storage_capacity::<IdentityContentKey>(1)?, 18);
+        let metrics = &storage.metrics;
+        let evicted = metrics.evictions.with_label_values(&["unknown"]).get();
+        assert_eq!(evicted, 18);
+        assert_eq!(metrics.prune_duration.get_sample_count(), 1);
+        assert_eq!(metrics.prune_evicted_items.get_sample_sum(), 18.0);
+        assert_eq!(metrics.prune_evicted_bytes.get_sample_sum(), 18.0 * 32.0);
+        assert_eq!(metrics.prune_radius_ratio_before.get(), 1.0);
+        assert!(metrics.prune_radius_ratio_after.get() < 1.0);
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_restarting_full_storage_with_same_capacity() -> Result<(), ContentStoreError> {
@@ -1087,7 +1711,8 @@ pub mod test {
         let min_capacity = 1;
         // Use a tiny storage capacity, to fill up as quickly as possible
         let storage_config = PortalStorageConfig::new(min_capacity, node_id).unwrap();
-        let mut storage = PortalStorage::new(storage_config.clone(), ProtocolId::History)?;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config.clone(), ProtocolId::History)?;
 
         // Fill up the storage. This is overkill for the 1kb capacity, but an upcoming
         // change will make the minimum storage size 1MB, so 32 keys should still be sufficient then.
@@ -1111,7 +1736,8 @@ pub mod test {
         assert!(radius < Distance::MAX);
 
         // Restart a filled-up store with the same capacity
-        let new_storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let new_storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         // The restarted store should have the same number of items
         assert_eq!(total_entry_count, new_storage.total_entry_count().unwrap());
@@ -1131,12 +1757,21 @@ pub mod test {
 
         let node_id = NodeId::random();
         let storage_config = PortalStorageConfig::new(0, node_id).unwrap();
-        let mut storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let mut storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         let content_key = generate_random_content_key();
         let value: Vec<u8> = "OGFWs179fWnqmjvHQFGHszXloc3Wzdb4".into();
+        assert!(!storage.is_key_within_radius_and_unavailable(&content_key)?);
         assert!(storage.store(&content_key, &value).is_err());
 
+        let ephemeral_key = HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+            block_hash: [1; 32],
+        });
+        assert!(!storage.is_key_within_radius_and_unavailable(&ephemeral_key)?);
+        assert!(storage.put(ephemeral_key.clone(), &value).is_err());
+        assert_eq!(storage.get(&ephemeral_key)?, None);
+
         let bytes = storage.get_total_storage_usage_in_bytes_from_network()?;
 
         assert_eq!(0, bytes);
@@ -1147,6 +1782,57 @@ pub mod test {
         Ok(())
     }
 
+    #[test_log::test(tokio::test)]
+    #[serial]
+    async fn test_migrate_legacy_content() -> Result<(), ContentStoreError> {
+        let temp_dir = setup_temp_dir().unwrap();
+        let node_id = NodeId::random();
+        let node_data_dir = get_node_data_dir(temp_dir.path(), node_id).unwrap();
+
+        // Write content the way earlier versions did: the value in a key-value store, and the
+        // metadata in a separate SQLite database.
+        let key = generate_random_content_key();
+        let content_id = key.content_id();
+        let value: Vec<u8> = vec![0xef; 32];
+        let legacy_db = KvStore::open(&node_data_dir.join(LEGACY_CONTENT_DB_DIR))?;
+        legacy_db.put(content_id, &value)?;
+        drop(legacy_db);
+        let conn = rusqlite::Connection::open(node_data_dir.join(LEGACY_META_DB_FILE))?;
+        conn.execute(
+            "CREATE TABLE content_metadata (
+                content_id_long TEXT PRIMARY KEY,
+                content_id_short INTEGER NOT NULL,
+                content_key TEXT NOT NULL,
+                content_size INTEGER
+            )",
+            [],
+        )?;
+        let content_key: Vec<u8> = key.clone().into();
+        conn.execute(
+            "INSERT INTO content_metadata VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![
+                content_id.to_vec(),
+                0,
+                hex_encode(content_key).trim_start_matches("0x"),
+                value.len()
+            ],
+        )?;
+        drop(conn);
+
+        let storage_config =
+            PortalStorageConfig::with_root_path(CAPACITY, node_id, temp_dir.path()).unwrap();
+        let storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
+        assert_eq!(storage.get(&key)?, Some(value));
+        assert_eq!(storage.total_entry_count()?, 1);
+        assert!(!node_data_dir.join(LEGACY_META_DB_FILE).exists());
+        assert!(!node_data_dir.join(LEGACY_CONTENT_DB_DIR).exists());
+
+        std::mem::drop(storage);
+        temp_dir.close()?;
+        Ok(())
+    }
+
     #[test_log::test(tokio::test)]
     #[serial]
     async fn test_find_farthest_empty_db() -> Result<(), ContentStoreError> {
@@ -1154,7 +1840,8 @@ pub mod test {
 
         let node_id = NodeId::random();
         let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-        let storage = PortalStorage::new(storage_config, ProtocolId::History)?;
+        let storage =
+            PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)?;
 
         let result = storage.find_farthest_content_id()?;
         assert!(result.is_none());
@@ -1173,7 +1860,9 @@ pub mod test {
             let node_id = NodeId::random();
             let val = vec![0x00, 0x01, 0x02, 0x03, 0x04];
             let storage_config = PortalStorageConfig::new(CAPACITY, node_id).unwrap();
-            let mut storage = PortalStorage::new(storage_config, ProtocolId::History).unwrap();
+            let mut storage =
+                PortalStorage::new::<IdentityContentKey>(storage_config, ProtocolId::History)
+                    .unwrap();
             storage.store(&x, &val).unwrap();
             storage.store(&y, &val).unwrap();
 
@@ -1241,6 +1930,29 @@ pub mod test {
         assert_eq!(store.get(&arb_key).unwrap(), Some(val));
     }
 
+    #[test]
+    fn memory_store_content_keys_within() {
+        let node_id = NodeId::new(&[0x00; 32]);
+        let mut store = MemoryContentStore::new(NodeId::random(), DistanceFunction::Xor);
+
+        let near_key = IdentityContentKey::new([0x01; 32]);
+        let far_key = IdentityContentKey::new([0xf0; 32]);
+        let _ = store.put(far_key.clone(), vec![0xef]);
+        let _ = store.put(near_key.clone(), vec![0xef]);
+
+        let keys = store
+            .content_keys_within(&node_id, Distance::MAX, 8)
+            .unwrap();
+        assert_eq!(keys, vec![near_key.to_bytes(), far_key.to_bytes()]);
+        let keys = store
+            .content_keys_within(&node_id, Distance::MAX, 1)
+            .unwrap();
+        assert_eq!(keys, vec![near_key.to_bytes()]);
+        let distance = XorMetric::distance(&[0x00; 32], &[0x10; 32]);
+        let keys = store.content_keys_within(&node_id, distance, 8).unwrap();
+        assert_eq!(keys, vec![near_key.to_bytes()]);
+    }
+
     #[test]
     fn memory_store_is_within_radius_and_unavailable() {
         let node_id = NodeId::random();
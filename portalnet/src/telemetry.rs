@@ -0,0 +1,234 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::mpsc::{self, RecvTimeoutError, TrySendError},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::{json, Value};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use crate::node_events::{LookupKind, NodeEvent, NodeEvents, NODE_EVENTS_CAPACITY};
+use trin_types::distance::Distance;
+use trin_utils::bytes::hex_encode;
+
+/// Interval at which recorded telemetry is flushed to the file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Configuration of the recording of lookups and gossip decisions for offline analysis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TelemetryConfig {
+    /// File that records are appended to, as JSON lines.
+    pub path: PathBuf,
+    /// Fraction of lookups to record, between 0 and 1.
+    pub lookup_sample_rate: f64,
+    /// Fraction of gossip decisions to record, between 0 and 1.
+    pub gossip_sample_rate: f64,
+}
+
+/// Appends a JSON record of every lookup and gossip decision emitted on `events` to the file at
+/// `config.path`, and sets the sample rates of `events` so that only a sample of them is emitted.
+///
+/// Records are written on a dedicated thread, buffered and flushed every `FLUSH_INTERVAL`. If the
+/// file can't keep up, records are skipped.
+pub fn spawn_telemetry_recorder(events: &NodeEvents, config: TelemetryConfig) -> io::Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config.path)?;
+    let (record_tx, record_rx) = mpsc::sync_channel(NODE_EVENTS_CAPACITY);
+    let path = config.path.clone();
+    thread::Builder::new()
+        .name("telemetry-recorder".to_string())
+        .spawn(move || write_records(BufWriter::new(file), record_rx, path))?;
+
+    let mut events_rx = events.subscribe();
+    events.set_sample_rates(config.lookup_sample_rate, config.gossip_sample_rate);
+    tokio::spawn(async move {
+        loop {
+            let event = match events_rx.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Telemetry recorder fell behind, skipped events");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let record = match build_record(&event, unix_timestamp_ms()) {
+                Some(record) => record,
+                None => continue,
+            };
+            match record_tx.try_send(record) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!("Telemetry file fell behind, skipped record");
+                }
+                Err(TrySendError::Disconnected(_)) => break,
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Writes the records received on `record_rx` to `writer`, flushing at least every
+/// `FLUSH_INTERVAL`, until the sender is dropped.
+fn write_records(mut writer: BufWriter<File>, record_rx: mpsc::Receiver<Value>, path: PathBuf) {
+    let mut last_flush = Instant::now();
+    loop {
+        match record_rx.recv_timeout(FLUSH_INTERVAL) {
+            Ok(record) => {
+                if let Err(err) = writeln!(writer, "{record}") {
+                    warn!(%err, path = %path.display(), "Unable to write telemetry record");
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            if let Err(err) = writer.flush() {
+                warn!(%err, path = %path.display(), "Unable to flush telemetry records");
+            }
+            last_flush = Instant::now();
+        }
+    }
+    let _ = writer.flush();
+}
+
+/// Returns the JSON record of a lookup or gossip decision, or `None` for other events.
+fn build_record(event: &NodeEvent, timestamp_ms: u64) -> Option<Value> {
+    let record = match event {
+        NodeEvent::LookupFinished {
+            protocol,
+            kind,
+            target,
+            success,
+            elapsed,
+            hops,
+        } => json!({
+            "type": "lookup",
+            "timestamp": timestamp_ms,
+            "subnetwork": protocol.to_string(),
+            "kind": match kind {
+                LookupKind::Node => "node",
+                LookupKind::Content => "content",
+            },
+            "target": hex_encode(target),
+            "success": success,
+            "elapsedMs": elapsed.as_millis() as u64,
+            "hops": hops
+                .iter()
+                .map(|hop| json!({
+                    "peer": hex_encode(hop.peer.raw()),
+                    "log2Distance": log2_distance(&hop.distance),
+                    "latencyMs": hop.latency.as_millis() as u64,
+                    "success": hop.success,
+                }))
+                .collect::<Vec<Value>>(),
+        }),
+        NodeEvent::GossipSent {
+            protocol,
+            content_id,
+            interested,
+            recipients,
+        } => json!({
+            "type": "gossip",
+            "timestamp": timestamp_ms,
+            "subnetwork": protocol.to_string(),
+            "contentId": hex_encode(content_id),
+            "interested": interested,
+            "recipients": recipients
+                .iter()
+                .map(|(peer, distance)| json!({
+                    "peer": hex_encode(peer.raw()),
+                    "log2Distance": log2_distance(distance),
+                }))
+                .collect::<Vec<Value>>(),
+        }),
+        _ => return None,
+    };
+    Some(record)
+}
+
+/// Returns the base-2 logarithm of `distance`, or zero for a distance of zero.
+fn log2_distance(distance: &Distance) -> usize {
+    distance.log2().unwrap_or(0)
+}
+
+fn unix_timestamp_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::{node_events::LookupHop, types::messages::ProtocolId};
+    use discv5::enr::NodeId;
+    use ethereum_types::U256;
+
+    #[test]
+    fn lookup_record() {
+        let peer = NodeId::new(&[1; 32]);
+        let event = NodeEvent::LookupFinished {
+            protocol: ProtocolId::History,
+            kind: LookupKind::Content,
+            target: [2; 32],
+            success: true,
+            elapsed: Duration::from_millis(250),
+            hops: vec![LookupHop {
+                peer,
+                distance: Distance::from(U256::from(1024)),
+                latency: Duration::from_millis(40),
+                success: true,
+            }],
+        };
+        let record = build_record(&event, 1000).unwrap();
+        assert_eq!(
+            record,
+            json!({
+                "type": "lookup",
+                "timestamp": 1000,
+                "subnetwork": "History",
+                "kind": "content",
+                "target": hex_encode([2; 32]),
+                "success": true,
+                "elapsedMs": 250,
+                "hops": [{
+                    "peer": hex_encode([1; 32]),
+                    "log2Distance": 11,
+                    "latencyMs": 40,
+                    "success": true,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn gossip_record() {
+        let event = NodeEvent::GossipSent {
+            protocol: ProtocolId::History,
+            content_id: [3; 32],
+            interested: 5,
+            recipients: vec![(NodeId::new(&[4; 32]), Distance::ZERO)],
+        };
+        let record = build_record(&event, 1000).unwrap();
+        assert_eq!(record["type"], "gossip");
+        assert_eq!(record["interested"], 5);
+        assert_eq!(record["recipients"][0]["log2Distance"], 0);
+    }
+
+    #[test]
+    fn other_events_are_not_recorded() {
+        let radius_changed = NodeEvent::RadiusChanged {
+            protocol: ProtocolId::History,
+            radius: Distance::MAX,
+        };
+        assert!(build_record(&radius_changed, 1000).is_none());
+    }
+}
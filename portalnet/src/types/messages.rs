@@ -4,9 +4,11 @@ use std::{
     net::SocketAddr,
     ops::Deref,
     str::FromStr,
+    time::Duration,
 };
 
-use ethereum_types::{H256, U256};
+use discv5::kbucket::MAX_NODES_PER_BUCKET;
+use ethereum_types::H256;
 use rlp::Encodable;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -16,7 +18,11 @@ use ssz_types::{typenum, BitList};
 use thiserror::Error;
 use validator::ValidationError;
 
+use crate::node_events::NodeEvents;
+use crate::overlay_service::{DEFAULT_BUCKET_REFRESH_INTERVAL, EXPECTED_NON_EMPTY_BUCKETS};
+use ethportal_api::types::ping_extensions::{PingExtensions, PingPayload};
 use trin_types::bytes::ByteList;
+use trin_types::cli::TalkReqTimeout;
 use trin_types::content_key::RawContentKey;
 use trin_types::distance::Distance;
 use trin_types::enr::{Enr, SszEnr};
@@ -64,6 +70,19 @@ pub(crate) const MAX_PORTAL_CONTENT_PAYLOAD_SIZE: usize = MAX_DISCV5_TALK_REQ_PA
     - (ssz::BYTES_PER_UNION_SELECTOR * 2)
     - ssz::BYTES_PER_LENGTH_OFFSET;
 
+/// The maximum size of the `content_keys` of a portal OFFER, including the SSZ length offset of
+/// each content key.
+///
+/// Portal wire overhead:
+///   * portal message SSZ union selector
+///   * OFFER SSZ length offset for List `content_keys`
+pub(crate) const MAX_PORTAL_OFFER_CONTENT_KEYS_SIZE: usize =
+    MAX_DISCV5_TALK_REQ_PAYLOAD_SIZE - ssz::BYTES_PER_UNION_SELECTOR - ssz::BYTES_PER_LENGTH_OFFSET;
+
+/// The maximum number of content keys in a portal OFFER, which is the capacity of the
+/// `content_keys` bitlist of the corresponding ACCEPT.
+pub const MAX_PORTAL_OFFER_CONTENT_KEYS: usize = 8;
+
 /// Custom payload element of Ping and Pong overlay messages
 #[derive(Debug, PartialEq, Clone)]
 pub struct CustomPayload {
@@ -98,10 +117,21 @@ impl From<Vec<u8>> for CustomPayload {
     }
 }
 
+impl From<PingPayload> for CustomPayload {
+    fn from(payload: PingPayload) -> Self {
+        Self::from(payload.encode())
+    }
+}
+
+impl From<CustomPayload> for PingPayload {
+    fn from(val: CustomPayload) -> Self {
+        PingPayload::decode(val.payload.deref())
+    }
+}
+
 impl From<CustomPayload> for Distance {
     fn from(val: CustomPayload) -> Self {
-        let bytes = val.payload;
-        U256::from_little_endian(bytes.deref()).into()
+        PingPayload::from(val).data_radius.into()
     }
 }
 
@@ -155,7 +185,7 @@ pub enum DiscoveryRequestError {
 /// Capacity of the cache for observed `NodeAddress` values.
 /// Provides capacity for 32 full k-buckets. This capacity will be shared among all active portal
 /// subnetworks.
-const NODE_ADDR_CACHE_CAPACITY: usize = discv5::kbucket::MAX_NODES_PER_BUCKET * 32;
+const NODE_ADDR_CACHE_CAPACITY: usize = MAX_NODES_PER_BUCKET * 32;
 
 #[derive(Clone)]
 pub struct PortalnetConfig {
@@ -167,6 +197,44 @@ pub struct PortalnetConfig {
     pub internal_ip: bool,
     pub no_stun: bool,
     pub node_addr_cache_capacity: usize,
+    /// Extensions advertised in PING and PONG messages, if any.
+    pub ping_extensions: Option<PingExtensions>,
+    /// Interval between random lookups to refresh sparse buckets of each overlay routing table.
+    pub bucket_refresh_interval: Duration,
+    /// Maximum number of nodes per bucket of each overlay routing table.
+    pub bucket_size: usize,
+    /// Number of buckets of each overlay routing table that are expected to hold nodes.
+    pub table_depth: usize,
+    /// Time after which a pending routing table entry replaces an unresponsive node.
+    pub bucket_pending_timeout: Duration,
+    /// Maximum number of incoming connections per bucket of each overlay routing table.
+    pub max_incoming_per_bucket: usize,
+    /// Timeouts of outbound requests that override the defaults of a subnetwork.
+    pub talkreq_timeouts: Vec<TalkReqTimeout>,
+    /// Time after which discv5 retries an unanswered request, and then fails it. Bounds every
+    /// outbound request, whatever its timeout in `talkreq_timeouts`.
+    pub discv5_request_timeout: Duration,
+    /// Time after which a node or content lookup is abandoned.
+    pub lookup_timeout: Duration,
+    /// Time a node or content lookup waits for a response from a single peer.
+    pub lookup_peer_timeout: Duration,
+    /// Number of times a content lookup requests a peer before abandoning it.
+    pub lookup_peer_attempts: usize,
+    /// Number of closest peers that must respond before a node or content lookup finishes.
+    pub lookup_num_results: usize,
+    /// Number of recent content lookup results to cache. Zero disables the cache.
+    pub lookup_cache_size: usize,
+    /// Time for which content found by a lookup is cached.
+    pub lookup_cache_ttl: Duration,
+    /// Number of content validation verdicts to cache. Zero disables the cache.
+    pub validation_cache_size: usize,
+    /// Maximum number of uTP streams over which the content accepted from one offer is sent
+    /// concurrently.
+    pub utp_transfer_limit: usize,
+    /// Maximum number of content keys offered to each peer that joins a routing table nearby.
+    pub neighborhood_sync_limit: usize,
+    /// Bus on which the overlay networks emit their activity.
+    pub events: NodeEvents,
 }
 
 impl Default for PortalnetConfig {
@@ -180,6 +248,24 @@ impl Default for PortalnetConfig {
             internal_ip: false,
             no_stun: false,
             node_addr_cache_capacity: NODE_ADDR_CACHE_CAPACITY,
+            ping_extensions: None,
+            bucket_refresh_interval: DEFAULT_BUCKET_REFRESH_INTERVAL,
+            bucket_size: MAX_NODES_PER_BUCKET,
+            table_depth: EXPECTED_NON_EMPTY_BUCKETS,
+            bucket_pending_timeout: Duration::from_secs(60),
+            max_incoming_per_bucket: 16,
+            talkreq_timeouts: vec![],
+            discv5_request_timeout: Duration::from_secs(1),
+            lookup_timeout: Duration::from_secs(60),
+            lookup_peer_timeout: Duration::from_secs(10),
+            lookup_peer_attempts: 1,
+            lookup_num_results: MAX_NODES_PER_BUCKET,
+            lookup_cache_size: 256,
+            lookup_cache_ttl: Duration::from_secs(30),
+            validation_cache_size: 1024,
+            utp_transfer_limit: 1,
+            neighborhood_sync_limit: 256,
+            events: NodeEvents::default(),
         }
     }
 }
@@ -408,19 +494,15 @@ impl fmt::Display for Pong {
 /// Convert to JSON Value from Pong ssz bytes
 impl From<Pong> for Value {
     fn from(val: Pong) -> Self {
-        match U256::from_ssz_bytes(&val.custom_payload.payload.as_ssz_bytes()) {
-            Ok(data_radius) => {
-                let mut result = Map::new();
-                result.insert("enrSeq".to_owned(), Value::String(val.enr_seq.to_string()));
-                result.insert(
-                    "dataRadius".to_owned(),
-                    Value::String(data_radius.to_string()),
-                );
-
-                Value::Object(result)
-            }
-            Err(msg) => Value::String(format!("Unable to ssz decode data radius!: {:?}", msg)),
-        }
+        let payload = PingPayload::from(val.custom_payload);
+        let mut result = Map::new();
+        result.insert("enrSeq".to_owned(), Value::String(val.enr_seq.to_string()));
+        result.insert(
+            "dataRadius".to_owned(),
+            Value::String(payload.data_radius.to_string()),
+        );
+
+        Value::Object(result)
     }
 }
 
@@ -560,6 +642,37 @@ pub struct PopulatedOffer {
     pub content_items: Vec<(RawContentKey, Vec<u8>)>,
 }
 
+impl PopulatedOffer {
+    /// Splits the offered content into offers that each fit in a single OFFER message, keeping the
+    /// order of the content.
+    ///
+    /// Each offer holds at most `MAX_PORTAL_OFFER_CONTENT_KEYS` content keys, so that every ACCEPT
+    /// bitlist corresponds to the content keys of exactly one offer. A content key that is too
+    /// large to share an OFFER with other content keys is offered on its own.
+    pub fn split(self) -> Vec<PopulatedOffer> {
+        let mut offers = vec![];
+        let mut content_items = vec![];
+        let mut content_keys_size = 0;
+        for (content_key, content_value) in self.content_items {
+            let content_key_size = ssz::BYTES_PER_LENGTH_OFFSET + content_key.len();
+            let is_full = content_items.len() == MAX_PORTAL_OFFER_CONTENT_KEYS
+                || content_keys_size + content_key_size > MAX_PORTAL_OFFER_CONTENT_KEYS_SIZE;
+            if !content_items.is_empty() && is_full {
+                offers.push(PopulatedOffer {
+                    content_items: std::mem::take(&mut content_items),
+                });
+                content_keys_size = 0;
+            }
+            content_keys_size += content_key_size;
+            content_items.push((content_key, content_value));
+        }
+        if !content_items.is_empty() {
+            offers.push(PopulatedOffer { content_items });
+        }
+        offers
+    }
+}
+
 impl From<PopulatedOffer> for Offer {
     fn from(val: PopulatedOffer) -> Self {
         let content_keys = val
@@ -587,6 +700,7 @@ impl From<Accept> for Value {
 #[allow(clippy::unwrap_used)]
 mod test {
     use super::*;
+    use ethereum_types::U256;
     use test_log::test;
     use trin_utils::bytes::hex_encode_upper;
 
@@ -773,6 +887,40 @@ mod test {
         assert_eq!(decoded, offer);
     }
 
+    #[test]
+    fn split_populated_offer_by_number_of_keys() {
+        let content_items = (0..20u8).map(|i| (vec![i; 33], vec![i])).collect();
+        let offers = PopulatedOffer { content_items }.split();
+
+        let lengths: Vec<usize> = offers
+            .iter()
+            .map(|offer| offer.content_items.len())
+            .collect();
+        assert_eq!(lengths, vec![8, 8, 4]);
+        let first_keys: Vec<u8> = offers
+            .iter()
+            .map(|offer| offer.content_items[0].0[0])
+            .collect();
+        assert_eq!(first_keys, vec![0, 8, 16]);
+    }
+
+    #[test]
+    fn split_populated_offer_by_size() {
+        let content_key_len = MAX_PORTAL_OFFER_CONTENT_KEYS_SIZE / 3;
+        let content_items = (0..4u8)
+            .map(|i| (vec![i; content_key_len], vec![i]))
+            .collect();
+        let offers = PopulatedOffer { content_items }.split();
+
+        assert_eq!(offers.len(), 2);
+        for offer in offers {
+            assert_eq!(offer.content_items.len(), 2);
+            let offer = Message::Offer(offer.into());
+            let encoded: Vec<u8> = offer.into();
+            assert!(encoded.len() <= MAX_DISCV5_TALK_REQ_PAYLOAD_SIZE);
+        }
+    }
+
     #[test]
     fn message_encoding_accept() {
         let connection_id = u16::from_le_bytes([0x01, 0x02]);
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use ethportal_api::types::ping_extensions::PingExtensions;
 use trin_types::distance::Distance;
 use trin_types::enr::Enr;
 
@@ -10,12 +11,18 @@ pub struct Node {
     pub enr: Enr,
     /// The node's data radius.
     pub data_radius: Distance,
+    /// The extensions the node advertised in its most recent PING or PONG, if any.
+    pub extensions: Option<PingExtensions>,
 }
 
 impl Node {
     /// Creates a new node.
     pub fn new(enr: Enr, data_radius: Distance) -> Node {
-        Node { enr, data_radius }
+        Node {
+            enr,
+            data_radius,
+            extensions: None,
+        }
     }
 
     /// Returns the ENR of the node.
@@ -33,6 +40,11 @@ impl Node {
         self.enr = enr;
     }
 
+    /// Returns the extensions advertised by the node.
+    pub fn extensions(&self) -> Option<&PingExtensions> {
+        self.extensions.as_ref()
+    }
+
     /// Sets the data radius of the node.
     pub fn set_data_radius(&mut self, radius: Distance) {
         self.data_radius = radius;
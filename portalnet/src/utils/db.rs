@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 
 use anyhow::anyhow;
@@ -23,23 +23,69 @@ pub fn setup_temp_dir() -> anyhow::Result<TempDir> {
     Ok(temp_dir)
 }
 
+/// Returns the data directory of the node with `node_id` under the default root path, creating it
+/// if necessary.
 pub fn get_data_dir(node_id: NodeId) -> anyhow::Result<PathBuf> {
-    let mut trin_data_dir = get_root_path()?;
+    get_node_data_dir(&get_root_path(None)?, node_id)
+}
 
-    // Append first 8 characters of Node ID
-    let mut application_string = "trin_".to_owned();
-    let node_id_string = hex_encode(node_id.raw());
-    let suffix = &node_id_string[..8];
-    application_string.push_str(suffix);
+/// Returns the directory owned by the node with `node_id` under `root`, creating it if necessary.
+///
+/// Each node keeps all of its databases in a subdirectory named after the first 8 characters of
+/// its `0x`-prefixed node id, so that nodes with different private keys can share a root path.
+pub fn get_node_data_dir(root: &Path, node_id: NodeId) -> anyhow::Result<PathBuf> {
+    let node_data_dir = node_data_dir_path(root, node_id);
 
-    trin_data_dir.push(application_string);
+    fs::create_dir_all(&node_data_dir).map_err(|err| {
+        anyhow!(
+            "Unable to create data directory {}: {err}",
+            node_data_dir.display()
+        )
+    })?;
+    Ok(node_data_dir)
+}
+
+/// Moves the directory owned by the node with `old_node_id` under `root` to the directory of the
+/// node with `new_node_id`. Returns the new directory, or `None` if the old node has no directory
+/// under `root`.
+pub fn move_node_data_dir(
+    root: &Path,
+    old_node_id: NodeId,
+    new_node_id: NodeId,
+) -> anyhow::Result<Option<PathBuf>> {
+    let old_dir = node_data_dir_path(root, old_node_id);
+    if !old_dir.is_dir() {
+        return Ok(None);
+    }
+    let new_dir = node_data_dir_path(root, new_node_id);
+    if new_dir.exists() {
+        return Err(anyhow!(
+            "Unable to move {} to {}: the directory already exists",
+            old_dir.display(),
+            new_dir.display()
+        ));
+    }
+    fs::rename(&old_dir, &new_dir).map_err(|err| {
+        anyhow!(
+            "Unable to move {} to {}: {err}",
+            old_dir.display(),
+            new_dir.display()
+        )
+    })?;
+    Ok(Some(new_dir))
+}
 
-    fs::create_dir_all(&trin_data_dir).expect("Unable to create data directory folder");
-    Ok(trin_data_dir)
+fn node_data_dir_path(root: &Path, node_id: NodeId) -> PathBuf {
+    let node_id_string = hex_encode(node_id.raw());
+    root.join(format!("trin_{}", &node_id_string[..8]))
 }
 
-/// Get root data path
-pub fn get_root_path() -> anyhow::Result<PathBuf> {
+/// Returns the root data path. In order of precedence, this is `data_dir`, the path in the
+/// `TRIN_DATA_PATH` environment variable, or the platform's default data directory.
+pub fn get_root_path(data_dir: Option<&Path>) -> anyhow::Result<PathBuf> {
+    if let Some(data_dir) = data_dir {
+        return Ok(data_dir.to_path_buf());
+    }
     let trin_data_dir = match env::var(TRIN_DATA_ENV_VAR) {
         Ok(val) => val,
         Err(_) => get_default_data_dir()?,
@@ -48,9 +94,9 @@ pub fn get_root_path() -> anyhow::Result<PathBuf> {
 }
 
 pub fn get_default_data_dir() -> anyhow::Result<String> {
-    // Windows: C:\Users\Username\AppData\Roaming\trin
+    // Windows: C:\Users\Username\AppData\Local\trin\data
     // macOS: ~/Library/Application Support/trin
-    // Unix-like: $HOME/.local/share/trin
+    // Unix-like: $XDG_DATA_HOME/trin, or $HOME/.local/share/trin
     match ProjectDirs::from("", "", "trin") {
         Some(proj_dirs) => match proj_dirs.data_local_dir().to_str() {
             Some(val) => Ok(val.to_string()),
@@ -59,3 +105,52 @@ pub fn get_default_data_dir() -> anyhow::Result<String> {
         None => Err(anyhow!("Unable to find default data directory")),
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn data_dir_overrides_root_path() {
+        let data_dir = Path::new("/tmp/trin-data-dir");
+        assert_eq!(get_root_path(Some(data_dir)).unwrap(), data_dir);
+    }
+
+    #[test]
+    fn node_data_dir_is_named_after_node_id() {
+        let root = TempDir::new().unwrap();
+        let node_id = NodeId::new(&[0xab; 32]);
+
+        let node_data_dir = get_node_data_dir(root.path(), node_id).unwrap();
+        assert_eq!(node_data_dir, root.path().join("trin_0xababab"));
+        assert!(node_data_dir.is_dir());
+    }
+
+    #[test]
+    fn node_data_dir_moves_to_new_node_id() {
+        let root = TempDir::new().unwrap();
+        let old_node_id = NodeId::new(&[0xab; 32]);
+        let new_node_id = NodeId::new(&[0xcd; 32]);
+        assert_eq!(
+            move_node_data_dir(root.path(), old_node_id, new_node_id).unwrap(),
+            None
+        );
+
+        let old_dir = get_node_data_dir(root.path(), old_node_id).unwrap();
+        fs::write(old_dir.join("content.sqlite"), b"content").unwrap();
+        let new_dir = move_node_data_dir(root.path(), old_node_id, new_node_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(new_dir, root.path().join("trin_0xcdcdcd"));
+        assert!(!old_dir.exists());
+        assert_eq!(
+            fs::read(new_dir.join("content.sqlite")).unwrap(),
+            b"content"
+        );
+
+        // A node's directory is never overwritten.
+        get_node_data_dir(root.path(), old_node_id).unwrap();
+        assert!(move_node_data_dir(root.path(), old_node_id, new_node_id).is_err());
+    }
+}
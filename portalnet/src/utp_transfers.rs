@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use discv5::enr::NodeId;
+use ethportal_api::types::portal::{TransferDirection, UtpTransferInfo};
+use parking_lot::Mutex;
+
+use crate::types::messages::ProtocolId;
+
+/// The uTP transfers of an overlay network that are in progress, for operators to see what a
+/// saturated node is transferring.
+pub struct UtpTransfers {
+    protocol: ProtocolId,
+    next_id: AtomicU64,
+    transfers: Mutex<HashMap<u64, Transfer>>,
+}
+
+struct Transfer {
+    peer: NodeId,
+    direction: TransferDirection,
+    content_keys: Vec<String>,
+    bytes: u64,
+    started: Instant,
+}
+
+/// Registration of a transfer, which is removed from the transfers in progress when the guard
+/// is dropped, however the transfer ends.
+pub struct TransferGuard {
+    transfers: Arc<UtpTransfers>,
+    id: u64,
+}
+
+impl UtpTransfers {
+    pub fn new(protocol: ProtocolId) -> Self {
+        Self {
+            protocol,
+            next_id: AtomicU64::new(0),
+            transfers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a transfer of the content with the hex-encoded `content_keys`, as soon as its
+    /// connection is set up.
+    pub fn start(
+        self: &Arc<Self>,
+        peer: NodeId,
+        direction: TransferDirection,
+        content_keys: Vec<String>,
+    ) -> TransferGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.transfers.lock().insert(
+            id,
+            Transfer {
+                peer,
+                direction,
+                content_keys,
+                bytes: 0,
+                started: Instant::now(),
+            },
+        );
+        TransferGuard {
+            transfers: Arc::clone(self),
+            id,
+        }
+    }
+
+    /// Returns the transfers in progress, oldest first.
+    pub fn list(&self) -> Vec<UtpTransferInfo> {
+        let now = Instant::now();
+        let mut transfers: Vec<(u64, UtpTransferInfo)> = self
+            .transfers
+            .lock()
+            .iter()
+            .map(|(id, transfer)| {
+                let info = UtpTransferInfo {
+                    peer: transfer.peer.raw().into(),
+                    direction: transfer.direction,
+                    subnetwork: self.protocol.to_string(),
+                    content_keys: transfer.content_keys.clone(),
+                    bytes: transfer.bytes,
+                    age_ms: now.duration_since(transfer.started).as_millis() as u64,
+                };
+                (*id, info)
+            })
+            .collect();
+        transfers.sort_by_key(|(id, _)| *id);
+        transfers.into_iter().map(|(_, info)| info).collect()
+    }
+}
+
+impl TransferGuard {
+    /// Records the size of the payload that is transferred.
+    pub fn set_bytes(&self, bytes: usize) {
+        if let Some(transfer) = self.transfers.transfers.lock().get_mut(&self.id) {
+            transfer.bytes = bytes as u64;
+        }
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        self.transfers.transfers.lock().remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_transfers_in_progress() {
+        let transfers = Arc::new(UtpTransfers::new(ProtocolId::History));
+        let peer = NodeId::random();
+        let inbound = transfers.start(peer, TransferDirection::Inbound, vec!["0x00".to_string()]);
+        let outbound = transfers.start(peer, TransferDirection::Outbound, vec![]);
+        outbound.set_bytes(1024);
+
+        let list = transfers.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].direction, TransferDirection::Inbound);
+        assert_eq!(list[0].content_keys, vec!["0x00".to_string()]);
+        assert_eq!(list[0].subnetwork, "History");
+        assert_eq!(list[1].bytes, 1024);
+
+        drop(inbound);
+        let list = transfers.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].direction, TransferDirection::Outbound);
+    }
+}
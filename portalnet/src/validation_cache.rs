@@ -0,0 +1,222 @@
+use std::{sync::Arc, time::Instant};
+
+use anyhow::anyhow;
+use lru::LruCache;
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use tracing::{debug_span, field, Instrument, Span};
+
+use crate::{
+    metrics::OverlayMetrics,
+    node_events::{NodeEvent, NodeEvents},
+    types::messages::ProtocolId,
+};
+use trin_types::content_key::OverlayContentKey;
+use trin_validation::validator::Validator;
+
+/// The content ID of validated content, paired with the SHA-256 digest of the content. Content
+/// from different peers for the same content ID is validated separately unless it is identical.
+type VerdictKey = ([u8; 32], [u8; 32]);
+
+/// The verdict of a validation, which is set once the first validation of the content finishes.
+type Verdict = Arc<OnceCell<Result<(), String>>>;
+
+/// A cache of content validation verdicts.
+///
+/// Content is often received from several peers at once, e.g. when a new block is gossiped.
+/// The cache makes sure that such content is validated once, with validations that start while
+/// the content is being validated waiting on the verdict of the first one.
+pub struct ValidationCache {
+    /// The verdicts of recent validations, or `None` if the cache is disabled.
+    verdicts: Option<Mutex<LruCache<VerdictKey, Verdict>>>,
+    protocol: ProtocolId,
+    metrics: Arc<OverlayMetrics>,
+    events: NodeEvents,
+}
+
+impl ValidationCache {
+    /// Creates a cache of the verdicts of up to `capacity` validations. A capacity of zero
+    /// disables the cache.
+    pub fn new(
+        capacity: usize,
+        protocol: ProtocolId,
+        metrics: Arc<OverlayMetrics>,
+        events: NodeEvents,
+    ) -> Self {
+        let verdicts = (capacity > 0).then(|| Mutex::new(LruCache::new(capacity)));
+        Self {
+            verdicts,
+            protocol,
+            metrics,
+            events,
+        }
+    }
+
+    /// Validates `content` for `content_key` with `validator`, unless the same content has
+    /// already been validated.
+    ///
+    /// Only successful validations are cached, because a validation may fail for reasons other
+    /// than the content, e.g. when a header needed for validation cannot be found.
+    pub async fn validate<TContentKey, TValidator>(
+        &self,
+        validator: &TValidator,
+        content_key: &TContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        TContentKey: OverlayContentKey + Sync,
+        TValidator: Validator<TContentKey> + Sync,
+    {
+        let span = debug_span!(
+            "validation",
+            content.key = %content_key,
+            cached = field::Empty,
+            elapsed_ms = field::Empty,
+        );
+        let started = Instant::now();
+        let result = self
+            .validate_inner(validator, content_key, content)
+            .instrument(span.clone())
+            .await;
+        span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+        if let Err(err) = &result {
+            self.events.emit(NodeEvent::ValidationFailed {
+                protocol: self.protocol.clone(),
+                content_key: content_key.clone().into(),
+                error: err.to_string(),
+            });
+        }
+        result
+    }
+
+    async fn validate_inner<TContentKey, TValidator>(
+        &self,
+        validator: &TValidator,
+        content_key: &TContentKey,
+        content: &[u8],
+    ) -> anyhow::Result<()>
+    where
+        TContentKey: OverlayContentKey + Sync,
+        TValidator: Validator<TContentKey> + Sync,
+    {
+        let verdicts = match &self.verdicts {
+            Some(verdicts) => verdicts,
+            None => return validator.validate_content(content_key, content).await,
+        };
+
+        let key: VerdictKey = (content_key.content_id(), Sha256::digest(content).into());
+        let (verdict, hit) = {
+            let mut verdicts = verdicts.lock();
+            match verdicts.get(&key) {
+                Some(verdict) => (Arc::clone(verdict), true),
+                None => {
+                    let verdict = Verdict::default();
+                    verdicts.put(key, Arc::clone(&verdict));
+                    (verdict, false)
+                }
+            }
+        };
+        self.metrics.report_validation_cache(&self.protocol, hit);
+        Span::current().record("cached", hit);
+
+        let result = verdict
+            .get_or_init(|| async {
+                validator
+                    .validate_content(content_key, content)
+                    .await
+                    .map_err(|err| err.to_string())
+            })
+            .await;
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => {
+                // Evict the failed verdict, unless it was already replaced by a new validation.
+                let mut verdicts = verdicts.lock();
+                if matches!(verdicts.peek(&key), Some(cached) if Arc::ptr_eq(cached, &verdict)) {
+                    verdicts.pop(&key);
+                }
+                Err(anyhow!(err.clone()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+    use trin_types::content_key::IdentityContentKey;
+
+    /// A validator that counts its validations and rejects empty content.
+    #[derive(Default)]
+    struct CountingValidator {
+        validations: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Validator<IdentityContentKey> for CountingValidator {
+        async fn validate_content(
+            &self,
+            _content_key: &IdentityContentKey,
+            content: &[u8],
+        ) -> anyhow::Result<()>
+        where
+            IdentityContentKey: 'async_trait,
+        {
+            self.validations.fetch_add(1, Ordering::SeqCst);
+            if content.is_empty() {
+                return Err(anyhow!("empty content"));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn validates_identical_content_once() {
+        let metrics = Arc::new(OverlayMetrics::new());
+        let cache = ValidationCache::new(
+            16,
+            ProtocolId::History,
+            Arc::clone(&metrics),
+            NodeEvents::default(),
+        );
+        let validator = CountingValidator::default();
+        let content_key = IdentityContentKey::new([0xef; 32]);
+
+        for _ in 0..3 {
+            cache
+                .validate(&validator, &content_key, &[0x01])
+                .await
+                .unwrap();
+        }
+        assert_eq!(validator.validations.load(Ordering::SeqCst), 1);
+
+        // Different content for the same content key is validated separately.
+        cache
+            .validate(&validator, &content_key, &[0x02])
+            .await
+            .unwrap();
+        assert_eq!(validator.validations.load(Ordering::SeqCst), 2);
+
+        let (validations, hits) = metrics.validation_cache_counts(&ProtocolId::History);
+        assert!(validations >= 4);
+        assert!(hits >= 2);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_failed_validations() {
+        let metrics = Arc::new(OverlayMetrics::new());
+        let cache = ValidationCache::new(16, ProtocolId::History, metrics, NodeEvents::default());
+        let validator = CountingValidator::default();
+        let content_key = IdentityContentKey::new([0xef; 32]);
+
+        assert!(cache.validate(&validator, &content_key, &[]).await.is_err());
+        assert!(cache.validate(&validator, &content_key, &[]).await.is_err());
+        assert_eq!(validator.validations.load(Ordering::SeqCst), 2);
+    }
+}
@@ -228,12 +228,12 @@ async fn overlay() {
         .write()
         .put(content_key.clone(), &content)
         .expect("Unable to store content");
-    match overlay_one.lookup_content(content_key).await {
-        (Some(found_content), _) => {
+    match overlay_one.lookup_content(content_key, false).await {
+        (Ok(found_content), _) => {
             assert_eq!(found_content, content);
         }
-        (None, _) => {
-            panic!("Unable to find content stored with peer");
+        (Err(err), _) => {
+            panic!("Unable to find content stored with peer: {err}");
         }
     }
 }
@@ -0,0 +1,159 @@
+use crate::jsonrpsee::core::{async_trait, RpcResult};
+use crate::jsonrpsee::server::ServerHandle;
+use crate::HistoryNetworkApi;
+use anyhow::anyhow;
+use ethportal_api::types::portal::{
+    CompactionInfo, DataRadius, LifetimeStatsInfo, PeerStatsInfo, StorageSummaryInfo,
+    UtpTransferInfo,
+};
+use ethportal_api::AdminApiServer;
+use serde_json::from_value;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tracing::info;
+use trin_types::jsonrpc::endpoints::HistoryEndpoint;
+
+pub struct AdminApi {
+    history: HistoryNetworkApi,
+    /// Token required by `admin_shutdown`, which is disabled if no token is configured.
+    shutdown_token: Option<String>,
+    /// Handle of the server that serves this API, set once the server has started. Stopping
+    /// the server is what signals the node to shut down.
+    server_handle: Arc<OnceCell<ServerHandle>>,
+    /// Whether the API is served over the IPC socket, which only local users with access to the
+    /// socket can reach. `admin_setHistoryRadius` is disabled over HTTP, which has no auth.
+    ipc: bool,
+}
+
+impl AdminApi {
+    pub fn new(
+        history: HistoryNetworkApi,
+        shutdown_token: Option<String>,
+        server_handle: Arc<OnceCell<ServerHandle>>,
+        ipc: bool,
+    ) -> Self {
+        Self {
+            history,
+            shutdown_token,
+            server_handle,
+            ipc,
+        }
+    }
+}
+
+/// Compares tokens in constant time, so that response times don't reveal the expected token.
+fn tokens_match(expected: &str, token: &str) -> bool {
+    expected.len() == token.len()
+        && expected
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[async_trait]
+impl AdminApiServer for AdminApi {
+    /// Returns activity counters for every peer the local node has interacted with.
+    async fn peer_stats(&self) -> RpcResult<Vec<PeerStatsInfo>> {
+        let endpoint = HistoryEndpoint::PeerStats;
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: Vec<PeerStatsInfo> = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns the activity of the local node on the history network across restarts.
+    async fn lifetime_stats(&self) -> RpcResult<LifetimeStatsInfo> {
+        let endpoint = HistoryEndpoint::LifetimeStats;
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: LifetimeStatsInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Refreshes a bucket of the history routing table, or the whole table.
+    async fn refresh_history_routing_table(&self, bucket: Option<u8>) -> RpcResult<bool> {
+        let endpoint = HistoryEndpoint::RefreshRoutingTable(bucket);
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: bool = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns the uTP transfers of the history network that are in progress.
+    async fn utp_transfers(&self) -> RpcResult<Vec<UtpTransferInfo>> {
+        let endpoint = HistoryEndpoint::UtpTransfers;
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: Vec<UtpTransferInfo> = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Changes the storage capacity of the history network, pruning content immediately if the
+    /// capacity shrinks.
+    async fn set_storage_capacity(&self, capacity_kb: u64) -> RpcResult<StorageSummaryInfo> {
+        let endpoint = HistoryEndpoint::SetStorageCapacity(capacity_kb);
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: StorageSummaryInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Compacts the history network content database.
+    async fn compact_storage(&self) -> RpcResult<CompactionInfo> {
+        let endpoint = HistoryEndpoint::CompactStorage;
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: CompactionInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Pins the data radius of the history network, or unpins it. Only served over IPC.
+    async fn set_history_radius(&self, radius: Option<DataRadius>) -> RpcResult<DataRadius> {
+        if !self.ipc {
+            return Err(anyhow!(
+                "admin_setHistoryRadius is only served over IPC, start trin with --web3-transport ipc to use it"
+            )
+            .into());
+        }
+        let endpoint = HistoryEndpoint::SetRadius(radius);
+        let result = self.history.proxy_query_to_history_subnet(endpoint).await?;
+        let result: DataRadius = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Shuts the node down gracefully, as on SIGTERM.
+    async fn shutdown(&self, token: String) -> RpcResult<bool> {
+        let expected = self.shutdown_token.as_ref().ok_or_else(|| {
+            anyhow!(
+                "admin_shutdown is disabled, start trin with --admin-shutdown-token to enable it"
+            )
+        })?;
+        if !tokens_match(expected, &token) {
+            return Err(anyhow!("Invalid admin shutdown token").into());
+        }
+        let server_handle = self
+            .server_handle
+            .get()
+            .ok_or_else(|| anyhow!("JSON-RPC server has not started yet"))?;
+        info!("Shutdown requested over JSON-RPC");
+        // In-flight requests, including this one, are answered before the server stops.
+        server_handle
+            .stop()
+            .map_err(|err| anyhow!("Unable to shut down: {err}"))?;
+        Ok(true)
+    }
+}
+
+impl std::fmt::Debug for AdminApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdminApi").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_tokens() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secret", "secreT"));
+        assert!(!tokens_match("secret", "secret2"));
+        assert!(!tokens_match("secret", ""));
+    }
+}
@@ -0,0 +1,217 @@
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use hyper::header::{
+    HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, VARY,
+};
+use hyper::{Body, Request, Response};
+use tower::{Layer, Service};
+
+/// Responses smaller than this are sent uncompressed, since compression wouldn't pay off.
+const MIN_COMPRESSION_SIZE: usize = 1024;
+
+/// Content codings supported for HTTP JSON-RPC responses, in order of preference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// Picks the preferred coding accepted by the client, ignoring codings with a zero quality
+    /// value. Returns `None` if the client accepts neither gzip nor deflate.
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let mut gzip = false;
+        let mut deflate = false;
+        let mut wildcard = false;
+        let mut rejected_gzip = false;
+        for value in headers.get_all(ACCEPT_ENCODING) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            for coding in value.split(',') {
+                let mut params = coding.split(';');
+                let name = params.next().unwrap_or_default().trim();
+                let rejected = params.any(|param| {
+                    param
+                        .trim()
+                        .strip_prefix("q=")
+                        .and_then(|q| q.parse::<f32>().ok())
+                        .map_or(false, |q| q == 0.0)
+                });
+                if name.eq_ignore_ascii_case("gzip") {
+                    gzip = !rejected;
+                    rejected_gzip = rejected;
+                } else if name.eq_ignore_ascii_case("deflate") {
+                    deflate = !rejected;
+                } else if name == "*" {
+                    wildcard = !rejected;
+                }
+            }
+        }
+        if gzip || (wildcard && !rejected_gzip) {
+            Some(Encoding::Gzip)
+        } else if deflate {
+            Some(Encoding::Deflate)
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Tower layer that compresses HTTP JSON-RPC responses with gzip or deflate, as negotiated with
+/// the client through the `Accept-Encoding` header.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompressionLayer;
+
+impl<S> Layer<S> for CompressionLayer {
+    type Service = CompressionService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CompressionService { inner }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompressionService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request<Body>> for CompressionService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: From<hyper::Error> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let encoding = Encoding::from_headers(request.headers());
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            match encoding {
+                Some(encoding) => compress_response(response, encoding).await,
+                None => Ok(response),
+            }
+        })
+    }
+}
+
+async fn compress_response<E: From<hyper::Error>>(
+    response: Response<Body>,
+    encoding: Encoding,
+) -> Result<Response<Body>, E> {
+    // Leave WebSocket upgrades, errors and already encoded responses untouched.
+    if !response.status().is_success() || response.headers().contains_key(CONTENT_ENCODING) {
+        return Ok(response);
+    }
+    let (mut parts, body) = response.into_parts();
+    let body = hyper::body::to_bytes(body).await?;
+    if body.len() < MIN_COMPRESSION_SIZE {
+        return Ok(Response::from_parts(parts, Body::from(body)));
+    }
+    let compressed = match encoding.encode(&body) {
+        Ok(compressed) => compressed,
+        Err(_) => return Ok(Response::from_parts(parts, Body::from(body))),
+    };
+    parts.headers.insert(
+        CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    parts
+        .headers
+        .insert(CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+    Ok(Response::from_parts(parts, Body::from(compressed)))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn accept_encoding(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn negotiates_encoding() {
+        assert_eq!(Encoding::from_headers(&HeaderMap::new()), None);
+        assert_eq!(Encoding::from_headers(&accept_encoding("identity")), None);
+        assert_eq!(
+            Encoding::from_headers(&accept_encoding("deflate, gzip")),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(
+            Encoding::from_headers(&accept_encoding("gzip;q=0, deflate;q=0.5")),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(
+            Encoding::from_headers(&accept_encoding("br, *")),
+            Some(Encoding::Gzip)
+        );
+    }
+
+    #[tokio::test]
+    async fn compresses_large_responses_only() {
+        let small = Response::new(Body::from("0x1234"));
+        let small = compress_response::<hyper::Error>(small, Encoding::Gzip)
+            .await
+            .unwrap();
+        assert!(!small.headers().contains_key(CONTENT_ENCODING));
+
+        let content = format!("0x{}", "00".repeat(MIN_COMPRESSION_SIZE));
+        let large = Response::new(Body::from(content.clone()));
+        let large = compress_response::<hyper::Error>(large, Encoding::Gzip)
+            .await
+            .unwrap();
+        assert_eq!(large.headers()[CONTENT_ENCODING], "gzip");
+        let body = hyper::body::to_bytes(large.into_body()).await.unwrap();
+        assert!(body.len() < content.len());
+        let mut decompressed = String::new();
+        GzDecoder::new(&body[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, content);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::jsonrpsee::core::{async_trait, RpcResult};
+use crate::HistoryNetworkApi;
+use ethereum_types::H256;
+use ethportal_api::DebugApiServer;
+use trin_utils::bytes::hex_encode;
+
+pub struct DebugApi {
+    history: HistoryNetworkApi,
+}
+
+impl DebugApi {
+    pub fn new(history: HistoryNetworkApi) -> Self {
+        Self { history }
+    }
+}
+
+#[async_trait]
+impl DebugApiServer for DebugApi {
+    /// Returns the hex encoded RLP of the header of the block with the given hash.
+    async fn get_raw_header(&self, block_hash: H256) -> RpcResult<String> {
+        let header = self.history.find_header(block_hash).await?.header;
+        Ok(hex_encode(rlp::encode(&header)))
+    }
+
+    /// Returns the hex encoded RLP of the block with the given hash.
+    async fn get_raw_block(&self, block_hash: H256) -> RpcResult<String> {
+        let header = self.history.find_header(block_hash).await?.header;
+        let body = self.history.find_block_body(block_hash).await?;
+        Ok(hex_encode(body.rlp_encode_block(&header)))
+    }
+
+    /// Returns the hex encoded binary encoding of each receipt of the block with the given hash.
+    async fn get_raw_receipts(&self, block_hash: H256) -> RpcResult<Vec<String>> {
+        let receipts = self.history.find_receipts(block_hash).await?;
+        Ok(receipts
+            .receipt_list
+            .iter()
+            .map(|receipt| hex_encode(receipt.encode()))
+            .collect())
+    }
+}
+
+impl std::fmt::Debug for DebugApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugApi").finish_non_exhaustive()
+    }
+}
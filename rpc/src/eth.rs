@@ -0,0 +1,243 @@
+use crate::jsonrpsee::core::{async_trait, RpcResult};
+use crate::HistoryNetworkApi;
+use anyhow::anyhow;
+use ethereum_types::{H256, U256};
+use ethportal_api::types::eth::{BlockId, TransactionReceiptInfo};
+use ethportal_api::{EthApiServer, Header, Transaction};
+use serde_json::{from_value, json};
+use tokio::sync::Mutex;
+use tokio::task::JoinSet;
+use tokio::time::{timeout_at, Duration, Instant};
+use tracing::debug;
+use trin_types::jsonrpc::params::Params;
+use trin_types::provider::{TrustedProvider, NO_TRUSTED_PROVIDER_ERROR};
+use trin_utils::bytes::hex_decode;
+
+/// Number of recent blocks sampled when suggesting a priority fee.
+const FEE_SAMPLE_BLOCKS: usize = 20;
+
+/// Number of the lowest tips sampled from each block, so that a few generous transactions don't
+/// skew the suggestion.
+const FEE_SAMPLES_PER_BLOCK: usize = 3;
+
+/// Percentile of the sampled tips that is suggested as the priority fee.
+const FEE_PERCENTILE: usize = 60;
+
+/// Priority fee suggested when no recent transactions could be sampled: 1 gwei.
+const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000;
+
+/// Time allowed for sampling recent blocks. Blocks that aren't found in time are left out of the
+/// suggestion.
+const FEE_SAMPLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A priority fee suggestion, along with the chain head that it was computed at.
+#[derive(Clone)]
+struct FeeSuggestion {
+    head_hash: H256,
+    /// Header of the newest sampled block
+    latest_header: Header,
+    priority_fee: U256,
+}
+
+pub struct EthApi {
+    history: HistoryNetworkApi,
+    trusted_provider: Option<TrustedProvider>,
+    /// The last suggestion, which is reused until the chain head moves.
+    fee_suggestion: Mutex<Option<FeeSuggestion>>,
+}
+
+impl EthApi {
+    pub fn new(history: HistoryNetworkApi, trusted_provider: Option<TrustedProvider>) -> Self {
+        Self {
+            history,
+            trusted_provider,
+            fee_suggestion: Mutex::new(None),
+        }
+    }
+
+    /// Returns the trusted provider that requests are proxied to, or an error if none is
+    /// configured.
+    fn trusted_provider(&self) -> anyhow::Result<&TrustedProvider> {
+        self.trusted_provider
+            .as_ref()
+            .ok_or_else(|| anyhow!(NO_TRUSTED_PROVIDER_ERROR))
+    }
+
+    /// Returns the header of the newest sampled block, and a priority fee suggestion based on the
+    /// tips paid by transactions in recent blocks.
+    ///
+    /// The recent blocks are the ones verified by the light client. Their headers and bodies are
+    /// looked up on the history network concurrently, within `FEE_SAMPLE_TIMEOUT`. The suggestion
+    /// is cached until the light client verifies a new head.
+    async fn suggest_priority_fee(&self) -> anyhow::Result<(Header, U256)> {
+        let block_hashes = self.history.recent_block_hashes(FEE_SAMPLE_BLOCKS).await?;
+        let head_hash = *block_hashes.first().ok_or_else(|| {
+            anyhow!("No chain head has been verified by the light client: fees cannot be sampled")
+        })?;
+
+        // Holding the lock while sampling lets concurrent requests reuse the result.
+        let mut fee_suggestion = self.fee_suggestion.lock().await;
+        if let Some(suggestion) = fee_suggestion.as_ref() {
+            if suggestion.head_hash == head_hash {
+                return Ok((suggestion.latest_header.clone(), suggestion.priority_fee));
+            }
+        }
+
+        let mut samples = JoinSet::new();
+        for block_hash in block_hashes {
+            let history = self.history.clone();
+            samples.spawn(async move {
+                let (header, body) = tokio::try_join!(
+                    history.find_ephemeral_header(block_hash),
+                    history.find_block_body(block_hash)
+                )
+                .map_err(|err| anyhow!("Unable to sample fees from block {block_hash:?}: {err}"))?;
+                let tips: Vec<U256> = body
+                    .txs
+                    .iter()
+                    .map(|tx| tx.effective_tip(header.base_fee_per_gas))
+                    .collect();
+                anyhow::Ok((header, tips))
+            });
+        }
+        let deadline = Instant::now() + FEE_SAMPLE_TIMEOUT;
+        let mut latest_header: Option<Header> = None;
+        let mut block_tips = vec![];
+        while let Ok(Some(sample)) = timeout_at(deadline, samples.join_next()).await {
+            match sample
+                .map_err(anyhow::Error::from)
+                .and_then(|sample| sample)
+            {
+                Ok((header, tips)) => {
+                    if latest_header
+                        .as_ref()
+                        .map_or(true, |latest| latest.number < header.number)
+                    {
+                        latest_header = Some(header);
+                    }
+                    block_tips.push(tips);
+                }
+                Err(err) => debug!(%err, "Block left out of the fee suggestion"),
+            }
+        }
+        // Dropping the set aborts the lookups that didn't finish in time.
+        drop(samples);
+
+        let latest_header = latest_header.ok_or_else(|| {
+            anyhow!("None of the recent blocks were found on the history network")
+        })?;
+        let priority_fee = priority_fee_from_tips(block_tips);
+        *fee_suggestion = Some(FeeSuggestion {
+            head_hash,
+            latest_header: latest_header.clone(),
+            priority_fee,
+        });
+        Ok((latest_header, priority_fee))
+    }
+}
+
+/// Returns the configured percentile of the lowest tips of each block.
+fn priority_fee_from_tips(block_tips: Vec<Vec<U256>>) -> U256 {
+    let mut samples = vec![];
+    for mut tips in block_tips {
+        tips.sort();
+        samples.extend(tips.into_iter().take(FEE_SAMPLES_PER_BLOCK));
+    }
+    if samples.is_empty() {
+        return U256::from(DEFAULT_PRIORITY_FEE);
+    }
+    samples.sort();
+    samples[(samples.len() - 1) * FEE_PERCENTILE / 100]
+}
+
+#[async_trait]
+impl EthApiServer for EthApi {
+    /// Returns a gas price suggestion: the base fee of the newest sampled block plus a priority
+    /// fee suggestion.
+    async fn gas_price(&self) -> RpcResult<U256> {
+        let (latest_header, priority_fee) = self.suggest_priority_fee().await?;
+        let base_fee = latest_header.base_fee_per_gas.unwrap_or_default();
+        Ok(base_fee.saturating_add(priority_fee))
+    }
+
+    /// Returns a priority fee suggestion, based on the tips paid in recent blocks.
+    async fn max_priority_fee_per_gas(&self) -> RpcResult<U256> {
+        let (_, priority_fee) = self.suggest_priority_fee().await?;
+        Ok(priority_fee)
+    }
+
+    /// Validates the encoding of the transaction and forwards it, unmodified, to the trusted
+    /// provider. The portal network does not gossip transactions.
+    async fn send_raw_transaction(&self, raw_transaction: String) -> RpcResult<H256> {
+        let tx = hex_decode(&raw_transaction)
+            .map_err(|err| anyhow!("Invalid transaction hex encoding: {err}"))?;
+        Transaction::decode(&tx).map_err(|err| anyhow!("Invalid transaction encoding: {err}"))?;
+
+        let method = "eth_sendRawTransaction".to_string();
+        let params = Params::Array(vec![json!(raw_transaction)]);
+        let response = self
+            .trusted_provider()?
+            .dispatch_http_request(method, params)?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("Trusted provider rejected transaction: {error}").into());
+        }
+        let tx_hash: H256 = from_value(response["result"].clone())?;
+        Ok(tx_hash)
+    }
+
+    /// Looks up the header, the body and the receipts of the block on the history network, and
+    /// combines them into the receipt of each transaction.
+    async fn get_block_receipts(&self, block: BlockId) -> RpcResult<Vec<TransactionReceiptInfo>> {
+        let header = match block {
+            BlockId::Hash(block_hash) => self.history.find_header(block_hash).await?,
+            BlockId::Number(block_number) => {
+                self.history
+                    .find_header_by_number(block_number.as_u64())
+                    .await?
+            }
+        }
+        .header;
+        let block_hash = header.hash();
+        let (body, receipts) = tokio::try_join!(
+            self.history.find_block_body(block_hash),
+            self.history.find_receipts(block_hash)
+        )?;
+        let receipts = TransactionReceiptInfo::from_block(&header, &body, &receipts)
+            .map_err(|err| anyhow!(err))?;
+        Ok(receipts)
+    }
+}
+
+impl std::fmt::Debug for EthApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EthApi").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tips(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|value| U256::from(*value)).collect()
+    }
+
+    #[test]
+    fn priority_fee_defaults_without_samples() {
+        assert_eq!(
+            priority_fee_from_tips(vec![]),
+            U256::from(DEFAULT_PRIORITY_FEE)
+        );
+        assert_eq!(
+            priority_fee_from_tips(vec![vec![], vec![]]),
+            U256::from(DEFAULT_PRIORITY_FEE)
+        );
+    }
+
+    #[test]
+    fn priority_fee_ignores_highest_tips_of_each_block() {
+        let block_tips = vec![tips(&[1000, 5, 3, 1]), tips(&[900, 800, 4, 2])];
+        // Samples are 1, 2, 3, 4, 5 and 800.
+        assert_eq!(priority_fee_from_tips(block_tips), U256::from(4));
+    }
+}
@@ -1,14 +1,23 @@
 use crate::jsonrpsee::core::{async_trait, Error, RpcResult};
 use anyhow::anyhow;
+use ethereum_types::H256;
 use ethportal_api::types::discv5::RoutingTableInfo;
+use ethportal_api::types::ping_extensions::PeerCapabilitiesInfo;
 use ethportal_api::types::portal::{
-    AcceptInfo, ContentInfo, DataRadius, FindNodesInfo, PaginateLocalContentInfo, PongInfo,
-    TraceContentInfo,
+    AcceptInfo, ContentChunkInfo, ContentInfo, DataRadius, FindNodesInfo, NetworkEstimateInfo,
+    PaginateLocalContentInfo, PongInfo, ReplicationInfo, SummaryInfo, TraceContentInfo,
+    DEFAULT_CONTENT_CHUNK_SIZE, DEFAULT_REPLICATION_NODES, MAX_CONTENT_CHUNK_SIZE,
+    MAX_REPLICATION_NODES,
 };
 use ethportal_api::HistoryContentKey;
-use ethportal_api::HistoryContentValue;
 use ethportal_api::HistoryNetworkApiServer;
+use ethportal_api::{
+    BlockBody, BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey,
+    EphemeralHeaderKey, Header, HeaderWithProof, Receipts,
+};
+use ethportal_api::{HistoryContentValue, RawContentValue};
 use serde_json::{from_value, Value};
+use ssz::Decode;
 use tokio::sync::mpsc;
 use trin_types::constants::CONTENT_ABSENT;
 use trin_types::content_value::PossibleHistoryContentValue;
@@ -16,7 +25,9 @@ use trin_types::enr::Enr;
 use trin_types::jsonrpc::endpoints::HistoryEndpoint;
 use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
 use trin_types::node_id::NodeId;
+use trin_utils::bytes::hex_decode;
 
+#[derive(Clone)]
 pub struct HistoryNetworkApi {
     network: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
 }
@@ -47,6 +58,112 @@ impl HistoryNetworkApi {
             )),
         }
     }
+
+    /// Looks up the content for `content_key` on the history network, returning its SSZ bytes.
+    async fn find_content(&self, content_key: HistoryContentKey) -> anyhow::Result<Vec<u8>> {
+        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key.clone(), false);
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: String = from_value(result)?;
+        if result == CONTENT_ABSENT {
+            return Err(anyhow!("Content not found for key: {content_key}"));
+        }
+        hex_decode(&result).map_err(|err| anyhow!("Invalid content for key {content_key}: {err}"))
+    }
+
+    /// Decodes a content value returned by the history subnetwork, using the selector of
+    /// `content_key` to determine its type.
+    fn decode_content_value(
+        content_key: &HistoryContentKey,
+        result: Value,
+    ) -> anyhow::Result<PossibleHistoryContentValue> {
+        let result: String = from_value(result)?;
+        if result == CONTENT_ABSENT {
+            return Ok(PossibleHistoryContentValue::ContentAbsent);
+        }
+        let content = hex_decode(&result)?;
+        let content = HistoryContentValue::decode_with_key(content_key, &content)?;
+        Ok(PossibleHistoryContentValue::ContentPresent(content))
+    }
+
+    /// Decodes a content value received from a client as the type that the selector of
+    /// `content_key` refers to.
+    fn decode_raw_content_value(
+        content_key: &HistoryContentKey,
+        content_value: &RawContentValue,
+    ) -> anyhow::Result<HistoryContentValue> {
+        HistoryContentValue::decode_with_key(content_key, &content_value.0).map_err(|err| {
+            anyhow!("Content value does not match the type of content key {content_key}: {err}")
+        })
+    }
+
+    /// Looks up the header of the block with the given hash on the history network.
+    pub(crate) async fn find_header(&self, block_hash: H256) -> anyhow::Result<HeaderWithProof> {
+        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: block_hash.0,
+        });
+        let content = self.find_content(content_key).await?;
+        HeaderWithProof::from_ssz_bytes(&content)
+            .map_err(|err| anyhow!("Unable to decode header for block {block_hash:?}: {err:?}"))
+    }
+
+    /// Looks up the header of the block with the given number on the history network.
+    pub(crate) async fn find_header_by_number(
+        &self,
+        block_number: u64,
+    ) -> anyhow::Result<HeaderWithProof> {
+        let content_key =
+            HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey { block_number });
+        let content = self.find_content(content_key).await?;
+        let header_with_proof = HeaderWithProof::from_ssz_bytes(&content).map_err(|err| {
+            anyhow!("Unable to decode header for block number {block_number}: {err:?}")
+        })?;
+        if header_with_proof.header.number != block_number {
+            return Err(anyhow!(
+                "Header for block number {block_number} has number {}",
+                header_with_proof.header.number
+            ));
+        }
+        Ok(header_with_proof)
+    }
+
+    /// Looks up the header of a recent block near the chain tip on the history network.
+    pub(crate) async fn find_ephemeral_header(&self, block_hash: H256) -> anyhow::Result<Header> {
+        let content_key = HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+            block_hash: block_hash.0,
+        });
+        let content = self.find_content(content_key).await?;
+        let header_with_proof = HeaderWithProof::from_ssz_bytes(&content)
+            .map_err(|err| anyhow!("Unable to decode header for block {block_hash:?}: {err:?}"))?;
+        Ok(header_with_proof.header)
+    }
+
+    /// Returns the hashes of up to `count` of the most recent blocks verified by the light
+    /// client, newest first.
+    pub(crate) async fn recent_block_hashes(&self, count: usize) -> anyhow::Result<Vec<H256>> {
+        let endpoint = HistoryEndpoint::RecentBlockHashes(count);
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        Ok(from_value(result)?)
+    }
+
+    /// Looks up the body of the block with the given hash on the history network.
+    pub(crate) async fn find_block_body(&self, block_hash: H256) -> anyhow::Result<BlockBody> {
+        let content_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: block_hash.0,
+        });
+        let content = self.find_content(content_key).await?;
+        BlockBody::from_ssz_bytes(&content)
+            .map_err(|err| anyhow!("Unable to decode body for block {block_hash:?}: {err:?}"))
+    }
+
+    /// Looks up the receipts of the block with the given hash on the history network.
+    pub(crate) async fn find_receipts(&self, block_hash: H256) -> anyhow::Result<Receipts> {
+        let content_key = HistoryContentKey::BlockReceipts(BlockReceiptsKey {
+            block_hash: block_hash.0,
+        });
+        let content = self.find_content(content_key).await?;
+        Receipts::from_ssz_bytes(&content)
+            .map_err(|err| anyhow!("Unable to decode receipts for block {block_hash:?}: {err:?}"))
+    }
 }
 
 #[async_trait]
@@ -112,6 +229,48 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Returns storage utilization and message counts for the local node.
+    async fn summary(&self) -> RpcResult<SummaryInfo> {
+        let endpoint = HistoryEndpoint::Summary;
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: SummaryInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns the client info and capabilities advertised by routing table peers through
+    /// PING/PONG extensions.
+    async fn peer_capabilities(&self) -> RpcResult<Vec<PeerCapabilitiesInfo>> {
+        let endpoint = HistoryEndpoint::PeerCapabilities;
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: Vec<PeerCapabilitiesInfo> = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns estimates of the network size, replication and total storage, from the data radii
+    /// advertised by routing table peers.
+    async fn network_estimate(&self) -> RpcResult<NetworkEstimateInfo> {
+        let endpoint = HistoryEndpoint::NetworkEstimate;
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: NetworkEstimateInfo = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Look up the `nodes` nodes closest to the content id in the network and ask each of them
+    /// for the content. Returns how many of them hold it.
+    async fn replication(
+        &self,
+        content_key: HistoryContentKey,
+        nodes: Option<u64>,
+    ) -> RpcResult<ReplicationInfo> {
+        let nodes = nodes
+            .unwrap_or(DEFAULT_REPLICATION_NODES)
+            .clamp(1, MAX_REPLICATION_NODES);
+        let endpoint = HistoryEndpoint::Replication(content_key, nodes);
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: ReplicationInfo = from_value(result)?;
+        Ok(result)
+    }
+
     /// Send FINDCONTENT message to get the content with a content key.
     async fn find_content(
         &self,
@@ -128,14 +287,14 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
     async fn recursive_find_content(
         &self,
         content_key: HistoryContentKey,
+        bypass_cache: Option<bool>,
     ) -> RpcResult<PossibleHistoryContentValue> {
-        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key);
+        let endpoint = HistoryEndpoint::RecursiveFindContent(
+            content_key.clone(),
+            bypass_cache.unwrap_or_default(),
+        );
         let result = self.proxy_query_to_history_subnet(endpoint).await?;
-        if result == serde_json::Value::String(CONTENT_ABSENT.to_string()) {
-            return Ok(PossibleHistoryContentValue::ContentAbsent);
-        };
-        let result: HistoryContentValue = from_value(result)?;
-        Ok(PossibleHistoryContentValue::ContentPresent(result))
+        Ok(Self::decode_content_value(&content_key, result)?)
     }
 
     /// Lookup a target content key in the network. Return tracing info.
@@ -161,13 +320,30 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         Ok(result)
     }
 
+    /// Return a slice of the content value, starting at `offset` and at most `max_bytes` long.
+    async fn content_chunk(
+        &self,
+        content_key: HistoryContentKey,
+        offset: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> RpcResult<ContentChunkInfo> {
+        let max_bytes = max_bytes
+            .unwrap_or(DEFAULT_CONTENT_CHUNK_SIZE)
+            .clamp(1, MAX_CONTENT_CHUNK_SIZE);
+        let endpoint = HistoryEndpoint::ContentChunk(content_key, offset.unwrap_or(0), max_bytes);
+        let result = self.proxy_query_to_history_subnet(endpoint).await?;
+        let result: ContentChunkInfo = from_value(result)?;
+        Ok(result)
+    }
+
     /// Send the provided content to interested peers. Clients may choose to send to some or all peers.
     /// Return the number of peers that the content was gossiped to.
     async fn gossip(
         &self,
         content_key: HistoryContentKey,
-        content_value: HistoryContentValue,
+        content_value: RawContentValue,
     ) -> RpcResult<u32> {
+        let content_value = Self::decode_raw_content_value(&content_key, &content_value)?;
         let endpoint = HistoryEndpoint::Gossip(content_key, content_value);
         let result = self.proxy_query_to_history_subnet(endpoint).await?;
         let result: u32 = from_value(result)?;
@@ -180,8 +356,11 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         &self,
         enr: Enr,
         content_key: HistoryContentKey,
-        content_value: Option<HistoryContentValue>,
+        content_value: Option<RawContentValue>,
     ) -> RpcResult<AcceptInfo> {
+        let content_value = content_value
+            .map(|content_value| Self::decode_raw_content_value(&content_key, &content_value))
+            .transpose()?;
         let endpoint = HistoryEndpoint::Offer(enr, content_key, content_value);
         let result = self.proxy_query_to_history_subnet(endpoint).await?;
         let result: AcceptInfo = from_value(result)?;
@@ -192,8 +371,9 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
     async fn store(
         &self,
         content_key: HistoryContentKey,
-        content_value: HistoryContentValue,
+        content_value: RawContentValue,
     ) -> RpcResult<bool> {
+        let content_value = Self::decode_raw_content_value(&content_key, &content_value)?;
         let endpoint = HistoryEndpoint::Store(content_key, content_value);
         let result = self.proxy_query_to_history_subnet(endpoint).await?;
         let result: bool = from_value(result)?;
@@ -205,13 +385,9 @@ impl HistoryNetworkApiServer for HistoryNetworkApi {
         &self,
         content_key: HistoryContentKey,
     ) -> RpcResult<PossibleHistoryContentValue> {
-        let endpoint = HistoryEndpoint::LocalContent(content_key);
+        let endpoint = HistoryEndpoint::LocalContent(content_key.clone());
         let result = self.proxy_query_to_history_subnet(endpoint).await?;
-        if result == serde_json::Value::String(CONTENT_ABSENT.to_string()) {
-            return Ok(PossibleHistoryContentValue::ContentAbsent);
-        };
-        let content: HistoryContentValue = from_value(result)?;
-        Ok(PossibleHistoryContentValue::ContentPresent(content))
+        Ok(Self::decode_content_value(&content_key, result)?)
     }
 }
 
@@ -0,0 +1,74 @@
+use std::{future::Future, path::Path};
+
+/// Access mode and owning group applied to the IPC socket once the server is listening, so that
+/// access to the node doesn't depend on the umask trin happens to run with.
+///
+/// The socket is created while the umask only grants access to the owner, so it is never
+/// reachable with wider permissions than configured.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IpcPermissions {
+    /// Permission bits of the socket, e.g. `0o660`.
+    pub mode: Option<u32>,
+    /// Name or numeric id of the group owning the socket.
+    pub group: Option<String>,
+}
+
+impl IpcPermissions {
+    /// Awaits `bind`, which creates the socket, with a umask that denies access to the group and
+    /// others if a mode or group is configured. The previous umask is restored afterwards.
+    #[cfg(unix)]
+    pub(crate) async fn bind<F: Future>(&self, bind: F) -> F::Output {
+        use nix::sys::stat::{umask, Mode};
+
+        if self.mode.is_none() && self.group.is_none() {
+            return bind.await;
+        }
+        let previous = umask(Mode::from_bits_truncate(0o177));
+        let output = bind.await;
+        umask(previous);
+        output
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) async fn bind<F: Future>(&self, bind: F) -> F::Output {
+        bind.await
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn apply(&self, path: &Path) -> anyhow::Result<()> {
+        use anyhow::{anyhow, Context};
+        use nix::unistd::{chown, Gid, Group};
+        use std::fs::{set_permissions, Permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Some(group) = &self.group {
+            let gid = match group.parse::<u32>() {
+                Ok(gid) => Gid::from_raw(gid),
+                Err(_) => {
+                    Group::from_name(group)
+                        .with_context(|| format!("Unable to look up group '{group}'"))?
+                        .ok_or_else(|| anyhow!("Unknown group '{group}'"))?
+                        .gid
+                }
+            };
+            chown(path, None, Some(gid)).with_context(|| {
+                format!("Unable to change group of {} to {group}", path.display())
+            })?;
+        }
+        if let Some(mode) = self.mode {
+            set_permissions(path, Permissions::from_mode(mode))
+                .with_context(|| format!("Unable to set mode {mode:o} on {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn apply(&self, _path: &Path) -> anyhow::Result<()> {
+        if self.mode.is_some() || self.group.is_some() {
+            return Err(anyhow::anyhow!(
+                "IPC socket permissions are only supported on unix"
+            ));
+        }
+        Ok(())
+    }
+}
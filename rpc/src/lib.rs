@@ -1,12 +1,25 @@
 #![warn(clippy::unwrap_used)]
 
+mod admin;
+mod compression;
+mod debug;
 mod discv5;
+mod eth;
 mod history;
+mod ipc;
 mod server;
+mod state;
+mod tls;
 mod web3;
 
+pub use admin::AdminApi;
+pub use debug::DebugApi;
 pub use discv5::Discv5Api;
+pub use eth::EthApi;
 pub use ethportal_api::jsonrpsee;
 pub use history::HistoryNetworkApi;
-pub use server::JsonRpcServer;
+pub use ipc::IpcPermissions;
+pub use server::{JsonRpcServer, PayloadLimits};
+pub use state::StateNetworkApi;
+pub use tls::TlsConfig;
 pub use web3::Web3Api;
@@ -1,47 +1,225 @@
+use crate::compression::CompressionLayer;
+use crate::ipc::IpcPermissions;
 use crate::jsonrpsee::server::{ServerBuilder as HttpServerBuilder, ServerHandle};
-use crate::{Discv5Api, HistoryNetworkApi, Web3Api};
-use ethportal_api::{Discv5ApiServer, HistoryNetworkApiServer, Web3ApiServer};
+use crate::jsonrpsee::RpcModule;
+use crate::tls::{serve_tls, TlsConfig};
+use crate::{AdminApi, DebugApi, Discv5Api, EthApi, HistoryNetworkApi, StateNetworkApi, Web3Api};
+use anyhow::anyhow;
+use ethportal_api::types::web3::BuildInfo;
+use ethportal_api::{
+    AdminApiServer, DebugApiServer, Discv5ApiServer, EthApiServer, HistoryNetworkApiServer,
+    StateNetworkApiServer, Web3ApiServer,
+};
 use portalnet::discovery::Discovery;
 use reth_ipc::server::Builder as IpcServerBuilder;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::mpsc;
-use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch, OnceCell};
+use tower::ServiceBuilder;
+use trin_types::cli::HttpListener;
+use trin_types::jsonrpc::request::{HistoryJsonRpcRequest, StateJsonRpcRequest};
+use trin_types::provider::TrustedProvider;
 
 use url::Url;
 
 pub struct JsonRpcServer;
 
+/// Maximum sizes of JSON-RPC request and response bodies, in bytes. Requests over the limit are
+/// rejected, and responses over the limit are replaced with an error, by jsonrpsee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayloadLimits {
+    pub max_request_size: u32,
+    pub max_response_size: u32,
+}
+
+/// Everything needed to instantiate the JSON-RPC APIs, once per listener.
+struct RpcApis {
+    discv5: Arc<Discovery>,
+    history_handler: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+    /// `None` if the state network is not selected
+    state_handler: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+    trusted_provider: Option<TrustedProvider>,
+    admin_shutdown_token: Option<String>,
+    build_info: BuildInfo,
+    server_handle: Arc<OnceCell<ServerHandle>>,
+    /// Whether the APIs are served over the IPC socket rather than HTTP.
+    ipc: bool,
+}
+
+impl RpcApis {
+    /// Merges the APIs of the given namespaces, see `trin_types::cli::RPC_MODULES`.
+    fn rpc_module(&self, modules: &[impl AsRef<str>]) -> anyhow::Result<RpcModule<()>> {
+        let mut api = RpcModule::new(());
+        for module in modules {
+            match module.as_ref() {
+                "admin" => api.merge(
+                    AdminApi::new(
+                        HistoryNetworkApi::new(self.history_handler.clone()),
+                        self.admin_shutdown_token.clone(),
+                        Arc::clone(&self.server_handle),
+                        self.ipc,
+                    )
+                    .into_rpc(),
+                )?,
+                "debug" => api.merge(
+                    DebugApi::new(HistoryNetworkApi::new(self.history_handler.clone())).into_rpc(),
+                )?,
+                "discv5" => api.merge(Discv5Api::new(Arc::clone(&self.discv5)).into_rpc())?,
+                "eth" => api.merge(
+                    EthApi::new(
+                        HistoryNetworkApi::new(self.history_handler.clone()),
+                        self.trusted_provider.clone(),
+                    )
+                    .into_rpc(),
+                )?,
+                "history" => {
+                    api.merge(HistoryNetworkApi::new(self.history_handler.clone()).into_rpc())?
+                }
+                // Without the state network, there are no state methods to expose.
+                "state" => {
+                    if let Some(state_handler) = &self.state_handler {
+                        api.merge(StateNetworkApi::new(state_handler.clone()).into_rpc())?;
+                    }
+                }
+                "web3" => api.merge(Web3Api::new(self.build_info.clone()).into_rpc())?,
+                other => return Err(anyhow!("Unknown JSON-RPC module '{other}'")),
+            }
+        }
+        Ok(api)
+    }
+}
+
 impl JsonRpcServer {
+    /// Serves JSON-RPC over HTTP and WebSocket, with `modules` on `web3_http_address` and the
+    /// selected modules on each of `extra_listeners`. The extra listeners stop along with the
+    /// main one, whose handle is returned.
+    ///
+    /// With a TLS config, each server is bound to an ephemeral loopback port, and TLS connections
+    /// accepted on the configured address are forwarded to it.
+    #[allow(clippy::too_many_arguments)]
     pub async fn run_http(
         web3_http_address: Url,
+        modules: Vec<String>,
+        extra_listeners: Vec<HttpListener>,
+        tls: Option<TlsConfig>,
+        limits: PayloadLimits,
         discv5: Arc<Discovery>,
         history_handler: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        state_handler: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        trusted_provider: Option<TrustedProvider>,
+        admin_shutdown_token: Option<String>,
+        build_info: BuildInfo,
     ) -> anyhow::Result<ServerHandle> {
-        let socket_addr: SocketAddr = web3_http_address.socket_addrs(|| None)?[0];
-
-        let server = HttpServerBuilder::default().build(socket_addr).await?;
-        let discv5_api = Discv5Api::new(discv5);
-        let history_network_api = HistoryNetworkApi::new(history_handler);
-        let mut api = discv5_api.into_rpc();
-        api.merge(history_network_api.into_rpc())?;
-        api.merge(Web3Api.into_rpc())?;
+        let server_handle = Arc::new(OnceCell::new());
+        let apis = RpcApis {
+            discv5,
+            history_handler,
+            state_handler,
+            trusted_provider,
+            admin_shutdown_token,
+            build_info,
+            server_handle: Arc::clone(&server_handle),
+            ipc: false,
+        };
+        let handle = Self::start_http(
+            &web3_http_address,
+            tls.as_ref(),
+            limits,
+            apis.rpc_module(&modules)?,
+        )
+        .await?;
+        server_handle
+            .set(handle.clone())
+            .expect("Server handle to be set once");
+
+        for listener in extra_listeners {
+            let extra_handle = match apis.rpc_module(&listener.modules) {
+                Ok(api) => Self::start_http(&listener.address, tls.as_ref(), limits, api).await,
+                Err(err) => Err(err),
+            };
+            let extra_handle = match extra_handle {
+                Ok(extra_handle) => extra_handle,
+                Err(err) => {
+                    let _ = handle.stop();
+                    return Err(err.context(format!(
+                        "Launching JSON-RPC listener on {} failed",
+                        listener.address
+                    )));
+                }
+            };
+            let main_handle = handle.clone();
+            tokio::spawn(async move {
+                main_handle.stopped().await;
+                let _ = extra_handle.stop();
+            });
+        }
+        Ok(handle)
+    }
+
+    async fn start_http(
+        address: &Url,
+        tls: Option<&TlsConfig>,
+        limits: PayloadLimits,
+        api: RpcModule<()>,
+    ) -> anyhow::Result<ServerHandle> {
+        let socket_addr: SocketAddr = address.socket_addrs(|| None)?[0];
+        if let Some(tls) = tls {
+            let acceptor = tls.acceptor()?;
+            let listener = TcpListener::bind(socket_addr).await?;
+            let (stop_tx, stop_rx) = watch::channel(());
+            tokio::spawn(serve_tls(listener, acceptor, api.into(), limits, stop_rx));
+            return Ok(ServerHandle::new(stop_tx));
+        }
+
+        let server = HttpServerBuilder::default()
+            .max_request_body_size(limits.max_request_size)
+            .max_response_body_size(limits.max_response_size)
+            .set_middleware(ServiceBuilder::new().layer(CompressionLayer))
+            .build(socket_addr)
+            .await?;
         let handle = server.start(api)?;
         Ok(handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn run_ipc(
         ipc_path: String,
+        modules: Vec<String>,
+        ipc_permissions: IpcPermissions,
+        limits: PayloadLimits,
         discv5: Arc<Discovery>,
         history_handler: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
+        state_handler: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
+        trusted_provider: Option<TrustedProvider>,
+        admin_shutdown_token: Option<String>,
+        build_info: BuildInfo,
     ) -> anyhow::Result<ServerHandle> {
-        let server = IpcServerBuilder::default().build(ipc_path)?;
-        let discv5_api = Discv5Api::new(discv5);
-        let history_network_api = HistoryNetworkApi::new(history_handler);
-        let mut api = discv5_api.into_rpc();
-        api.merge(history_network_api.into_rpc())?;
-        api.merge(Web3Api.into_rpc())?;
-        let handle = server.start(api).await?;
+        let server = IpcServerBuilder::default()
+            .max_request_body_size(limits.max_request_size)
+            .max_response_body_size(limits.max_response_size)
+            .build(ipc_path.clone())?;
+        let server_handle = Arc::new(OnceCell::new());
+        let apis = RpcApis {
+            discv5,
+            history_handler,
+            state_handler,
+            trusted_provider,
+            admin_shutdown_token,
+            build_info,
+            server_handle: Arc::clone(&server_handle),
+            ipc: true,
+        };
+        let rpc_module = apis.rpc_module(&modules)?;
+        let handle = ipc_permissions.bind(server.start(rpc_module)).await?;
+        server_handle
+            .set(handle.clone())
+            .expect("Server handle to be set once");
+        if let Err(err) = ipc_permissions.apply(Path::new(&ipc_path)) {
+            let _ = handle.stop();
+            return Err(err);
+        }
         Ok(handle)
     }
 }
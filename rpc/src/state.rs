@@ -0,0 +1,60 @@
+use crate::jsonrpsee::core::{async_trait, RpcResult};
+use anyhow::anyhow;
+use ethportal_api::types::portal::{DataRadius, SummaryInfo};
+use ethportal_api::StateNetworkApiServer;
+use serde_json::{from_value, Value};
+use tokio::sync::mpsc;
+use trin_types::jsonrpc::endpoints::StateEndpoint;
+use trin_types::jsonrpc::request::StateJsonRpcRequest;
+
+pub struct StateNetworkApi {
+    network: mpsc::UnboundedSender<StateJsonRpcRequest>,
+}
+
+impl StateNetworkApi {
+    pub fn new(network: mpsc::UnboundedSender<StateJsonRpcRequest>) -> Self {
+        Self { network }
+    }
+
+    pub async fn proxy_query_to_state_subnet(
+        &self,
+        endpoint: StateEndpoint,
+    ) -> anyhow::Result<Value> {
+        let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
+        let message = StateJsonRpcRequest {
+            endpoint,
+            resp: resp_tx,
+        };
+        let _ = self.network.send(message);
+
+        match resp_rx.recv().await {
+            Some(val) => val.map_err(|msg| anyhow!(msg)),
+            None => Err(anyhow!("Internal error: No response from state subnetwork")),
+        }
+    }
+}
+
+#[async_trait]
+impl StateNetworkApiServer for StateNetworkApi {
+    /// Returns the data radius of the state network.
+    async fn radius(&self) -> RpcResult<DataRadius> {
+        let endpoint = StateEndpoint::DataRadius;
+        let result = self.proxy_query_to_state_subnet(endpoint).await?;
+        let result: DataRadius = from_value(result)?;
+        Ok(result)
+    }
+
+    /// Returns storage utilization and message counts of the state network.
+    async fn summary(&self) -> RpcResult<SummaryInfo> {
+        let endpoint = StateEndpoint::Summary;
+        let result = self.proxy_query_to_state_subnet(endpoint).await?;
+        let result: SummaryInfo = from_value(result)?;
+        Ok(result)
+    }
+}
+
+impl std::fmt::Debug for StateNetworkApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateNetworkApi").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use rustls_pemfile::Item;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tower::Layer;
+use tracing::{debug, warn};
+
+use crate::compression::CompressionLayer;
+use crate::jsonrpsee::core::server::rpc_module::Methods;
+use crate::jsonrpsee::types::error::{
+    INVALID_REQUEST_CODE, INVALID_REQUEST_MSG, OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG,
+    PARSE_ERROR_CODE, PARSE_ERROR_MSG,
+};
+use crate::server::PayloadLimits;
+
+/// Certificate and private key used to serve the HTTP JSON-RPC server over TLS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// PEM encoded certificate chain, leaf certificate first.
+    pub cert_path: PathBuf,
+    /// PEM encoded PKCS#8, RSA or EC private key.
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    pub(crate) fn acceptor(&self) -> anyhow::Result<TlsAcceptor> {
+        let certs = load_certs(&self.cert_path)?;
+        let key = load_key(&self.key_path)?;
+        let mut config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .context("Invalid TLS certificate or key")?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<Certificate>> {
+    let file = File::open(path)
+        .with_context(|| format!("Unable to open TLS certificate {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .with_context(|| format!("Unable to read TLS certificate {}", path.display()))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificate found in {}", path.display()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_key(path: &Path) -> anyhow::Result<PrivateKey> {
+    let file =
+        File::open(path).with_context(|| format!("Unable to open TLS key {}", path.display()))?;
+    let items = rustls_pemfile::read_all(&mut BufReader::new(file))
+        .with_context(|| format!("Unable to read TLS key {}", path.display()))?;
+    items
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("No private key found in {}", path.display()))
+}
+
+/// Serves JSON-RPC over HTTP on the TLS connections accepted on `listener`, until `stop` is
+/// signalled. Requests are answered in-process by `methods`, so that no plaintext listener sits
+/// behind TLS. WebSocket connections are not supported over TLS.
+pub(crate) async fn serve_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    methods: Methods,
+    limits: PayloadLimits,
+    mut stop: watch::Receiver<()>,
+) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = stop.changed() => return,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!(error = %err, "Failed to accept JSON-RPC TLS connection");
+                    continue;
+                }
+            },
+        };
+        let acceptor = acceptor.clone();
+        let methods = methods.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    debug!(error = %err, %peer, "JSON-RPC TLS handshake failed");
+                    return;
+                }
+            };
+            let service = CompressionLayer.layer(service_fn(move |request| {
+                let methods = methods.clone();
+                async move {
+                    let response = handle_request(request, &methods, limits).await;
+                    Ok::<_, hyper::Error>(response)
+                }
+            }));
+            if let Err(err) = Http::new()
+                .http1_only(true)
+                .serve_connection(stream, service)
+                .await
+            {
+                debug!(error = %err, %peer, "JSON-RPC TLS connection closed with error");
+            }
+        });
+    }
+}
+
+/// Answers a JSON-RPC request, or batch of requests, sent in the body of an HTTP POST request.
+async fn handle_request(
+    request: Request<Body>,
+    methods: &Methods,
+    limits: PayloadLimits,
+) -> Response<Body> {
+    if request.method() != Method::POST {
+        return status_response(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let body = match read_body(request.into_body(), limits.max_request_size as usize).await {
+        Ok(Some(body)) => body,
+        Ok(None) => return status_response(StatusCode::PAYLOAD_TOO_LARGE),
+        Err(_) => return status_response(StatusCode::BAD_REQUEST),
+    };
+    let mut response = call(methods, &body).await;
+    if response.len() > limits.max_response_size as usize {
+        response = error_response(OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG);
+    }
+    Response::builder()
+        .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+        .body(Body::from(response))
+        .unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+/// Reads the request body, or returns `None` if it is larger than `max_size` bytes.
+async fn read_body(mut body: Body, max_size: usize) -> Result<Option<Vec<u8>>, hyper::Error> {
+    let mut bytes = vec![];
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if bytes.len() + chunk.len() > max_size {
+            return Ok(None);
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(Some(bytes))
+}
+
+/// Returns the JSON response to a JSON-RPC request, or to each request of a batch.
+async fn call(methods: &Methods, body: &[u8]) -> String {
+    match serde_json::from_slice(body) {
+        Ok(Value::Array(requests)) if !requests.is_empty() => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in requests {
+                responses.push(call_single(methods, &request.to_string()).await);
+            }
+            format!("[{}]", responses.join(","))
+        }
+        Ok(Value::Array(_)) => error_response(INVALID_REQUEST_CODE, INVALID_REQUEST_MSG),
+        Ok(request) => call_single(methods, &request.to_string()).await,
+        Err(_) => error_response(PARSE_ERROR_CODE, PARSE_ERROR_MSG),
+    }
+}
+
+async fn call_single(methods: &Methods, request: &str) -> String {
+    match methods.raw_json_request(request).await {
+        Ok((response, _)) => response.result,
+        Err(_) => error_response(INVALID_REQUEST_CODE, INVALID_REQUEST_MSG),
+    }
+}
+
+/// Returns a JSON-RPC error response that is not tied to a request id.
+fn error_response(code: i32, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": null,
+    })
+    .to_string()
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    use crate::jsonrpsee::RpcModule;
+
+    fn methods() -> Methods {
+        let mut module = RpcModule::new(());
+        module
+            .register_method("web3_clientVersion", |_, _| {
+                Ok::<_, crate::jsonrpsee::core::Error>("trin")
+            })
+            .unwrap();
+        module.into()
+    }
+
+    #[tokio::test]
+    async fn answers_single_and_batch_requests() {
+        let methods = methods();
+        let request = json!({"jsonrpc": "2.0", "method": "web3_clientVersion", "id": 1});
+
+        let response: Value =
+            serde_json::from_str(&call(&methods, request.to_string().as_bytes()).await).unwrap();
+        assert_eq!(response["result"], "trin");
+
+        let batch = json!([request, {"jsonrpc": "2.0", "method": "unknown", "id": 2}]);
+        let responses: Value =
+            serde_json::from_str(&call(&methods, batch.to_string().as_bytes()).await).unwrap();
+        assert_eq!(responses[0]["result"], "trin");
+        assert_eq!(responses[1]["id"], 2);
+        assert!(responses[1]["error"].is_object());
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_requests() {
+        let methods = methods();
+        let response: Value = serde_json::from_str(&call(&methods, b"{").await).unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR_CODE);
+        let response: Value = serde_json::from_str(&call(&methods, b"[]").await).unwrap();
+        assert_eq!(response["error"]["code"], INVALID_REQUEST_CODE);
+    }
+}
@@ -1,15 +1,32 @@
 use crate::jsonrpsee::core::{async_trait, RpcResult};
+use ethportal_api::types::web3::BuildInfo;
 use ethportal_api::Web3ApiServer;
 use trin_utils::version::get_trin_version;
 
-pub struct Web3Api;
+/// Serves the web3 namespace, which identifies the client.
+pub struct Web3Api {
+    /// What the node was built from and with, computed once at startup.
+    build_info: BuildInfo,
+}
+
+impl Web3Api {
+    pub fn new(build_info: BuildInfo) -> Self {
+        Self { build_info }
+    }
+}
 
 #[async_trait]
 impl Web3ApiServer for Web3Api {
+    /// Returns the trin version, including the short git revision.
     async fn client_version(&self) -> RpcResult<String> {
         let trin_version = get_trin_version();
         Ok(format!("trin v{trin_version}"))
     }
+
+    /// Returns the build information, along with the subnetworks that the node runs.
+    async fn build_info(&self) -> RpcResult<BuildInfo> {
+        Ok(self.build_info.clone())
+    }
 }
 
 impl std::fmt::Debug for Web3Api {
@@ -1,15 +1,16 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use clap::{Parser, ValueEnum};
 use discv5::enr::{CombinedKey, EnrBuilder};
 use ethereum_types::H256;
-use rocksdb::IteratorMode;
 use ssz::Decode;
 use tracing::{info, warn};
 
 use ethportal_api::HistoryContentKey;
 use portalnet::storage::{PortalStorage, PortalStorageConfig};
 use portalnet::types::messages::ProtocolId;
-use portalnet::utils::db::get_data_dir;
+use portalnet::utils::db::{get_node_data_dir, get_root_path};
 use trin_types::execution::accumulator::EpochAccumulator;
 use trin_types::execution::block_body::BlockBody;
 use trin_types::execution::header::HeaderWithProof;
@@ -18,37 +19,35 @@ use trin_utils::bytes::hex_encode;
 use trin_utils::log::init_tracing_logger;
 
 ///
-/// This script will iterate through all content id / key pairs in rocksd & meta db.
+/// This script will iterate through all content id / key pairs in the content db.
 /// However, if it is run in "invalid-only" mode, it will error if it encounters any
 /// non-history network content. Since we only support history network content, this
 /// shouldn't be a problem, but as we add support for more sub-networks this script will
 /// need to be updated to avoid panicking.
 ///
 pub fn main() -> Result<()> {
-    init_tracing_logger();
+    init_tracing_logger(None);
     let purge_config = PurgeConfig::parse();
 
     let enr_key =
         CombinedKey::secp256k1_from_bytes(&mut purge_config.private_key.to_fixed_bytes()).unwrap();
     let enr = EnrBuilder::new("v4").build(&enr_key).unwrap();
     let node_id = enr.node_id();
-    let data_dir_path = get_data_dir(node_id);
+    let root_path = get_root_path(purge_config.data_dir.as_deref())?;
+    let data_dir_path = get_node_data_dir(&root_path, node_id)?;
     info!("Purging data for NodeID: {}", node_id);
     info!("DB Path: {:?}", data_dir_path);
 
     // Capacity is 0 since it (eg. for data radius calculation) is irrelevant when only removing data.
     let capacity = 0;
     let protocol = ProtocolId::History;
-    let config = PortalStorageConfig::new(capacity, node_id)?;
-    let storage =
-        PortalStorage::new(config.clone(), protocol).expect("Failed to create portal storage");
-    let iter = config.db.iterator(IteratorMode::Start);
+    let config = PortalStorageConfig::with_root_path(capacity, node_id, &root_path)?;
+    let storage = PortalStorage::new::<HistoryContentKey>(config.clone(), protocol)
+        .expect("Failed to create portal storage");
     let mut item_count = 0;
     let mut remove_count = 0;
-    for (id, value) in iter {
+    for content_id in config.content_db.content_ids()? {
         item_count += 1;
-        let mut content_id = [0u8; 32];
-        content_id.copy_from_slice(&id);
         match purge_config.mode {
             PurgeMode::All => match storage.evict(content_id) {
                 Ok(_) => remove_count += 1,
@@ -64,14 +63,14 @@ pub fn main() -> Result<()> {
                     Ok(None) => {
                         warn!(
                             content.id = hex_encode(content_id),
-                            "Couldn't find corresponding content key in meta db",
+                            "Couldn't find corresponding content key in content db",
                         );
                         continue;
                     }
                     Err(e) => {
                         warn!(
                             content.id = hex_encode(content_id),
-                            "Error during lookup of content key in meta db {e}",
+                            "Error during lookup of content key in content db {e}",
                         );
                         continue;
                     }
@@ -88,7 +87,18 @@ pub fn main() -> Result<()> {
                     }
                 };
 
-                if !is_content_valid(&content_key, &value.into_vec()) {
+                let value = match config.content_db.get(&content_id) {
+                    Ok(Some(value)) => value,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!(
+                            content.id = hex_encode(content_id),
+                            "Error during lookup of content value {e}",
+                        );
+                        continue;
+                    }
+                };
+                if !is_content_valid(&content_key, &value) {
                     match storage.evict(content_id) {
                         Ok(_) => remove_count += 1,
                         Err(err) => warn!(
@@ -109,9 +119,9 @@ pub fn main() -> Result<()> {
 
 fn is_content_valid(content_key: &HistoryContentKey, value: &[u8]) -> bool {
     match content_key {
-        HistoryContentKey::BlockHeaderWithProof(_) => {
-            HeaderWithProof::from_ssz_bytes(value).is_ok()
-        }
+        HistoryContentKey::BlockHeaderWithProof(_)
+        | HistoryContentKey::BlockHeaderByNumber(_)
+        | HistoryContentKey::EphemeralHeader(_) => HeaderWithProof::from_ssz_bytes(value).is_ok(),
         HistoryContentKey::BlockBody(_) => BlockBody::from_ssz_bytes(value).is_ok(),
         HistoryContentKey::BlockReceipts(_) => Receipts::from_ssz_bytes(value).is_ok(),
         HistoryContentKey::EpochAccumulator(_) => EpochAccumulator::from_ssz_bytes(value).is_ok(),
@@ -137,6 +147,12 @@ pub struct PurgeConfig {
         help = "Purge all content or only invalidly encoded content"
     )]
     pub mode: PurgeMode,
+
+    #[arg(
+        long,
+        help = "Root directory of the node data (defaults to TRIN_DATA_PATH or the platform's data directory)"
+    )]
+    pub data_dir: Option<PathBuf>,
 }
 
 #[derive(ValueEnum, Debug, PartialEq, Eq, Clone)]
@@ -0,0 +1,262 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Parser;
+use ethereum_types::{H256, U256};
+use serde_json::{json, Value};
+use ssz::Encode;
+use ssz_types::{typenum, BitList};
+
+use ethportal_api::{
+    BlockBody, BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey,
+    ContentValue, EncodableHeaderList, EphemeralHeaderKey, EpochAccumulatorKey, HistoryContentKey,
+    HistoryContentValue, OverlayContentKey, Receipts,
+};
+use portalnet::types::messages::{
+    Accept, Content, CustomPayload, FindContent, FindNodes, Message, Nodes, Offer, Ping, Pong,
+};
+use trin_types::schema::{history_content_key_schema, history_content_value_schema};
+use trin_utils::bytes::{hex_decode, hex_encode};
+
+/// Block hash used as the input for the content keys of block-specific content.
+const BLOCK_HASH: &str = "0xd1c390624d3bd4e409a61a858e5dcc5517729a9170d014a6c96530d64dd8621d";
+/// Epoch hash used as the input for the epoch accumulator content key.
+const EPOCH_HASH: &str = "0xe242814b90ed3950e13aac7e56ce116540c71b41d1516605aada26c6c07cc491";
+const BLOCK_NUMBER: u64 = 1_000_001;
+
+///
+/// Generates test vectors for canonical inputs in the JSON layout of portal-spec-tests:
+/// history network content keys with their content ids, encoded history content values, and
+/// Portal wire protocol messages. Also writes the JSON schemas of history content keys and
+/// values, whose published copies live in `trin-types/src/assets/schemas`.
+///
+pub fn main() -> Result<()> {
+    let config = TestVectorsConfig::parse();
+
+    let vectors = [
+        ("history/content_keys.json", history_content_keys()?),
+        ("history/content_values.json", history_content_values()),
+        ("wire/messages.json", wire_messages()?),
+        (
+            "schemas/history_content_key.json",
+            history_content_key_schema(),
+        ),
+        (
+            "schemas/history_content_value.json",
+            history_content_value_schema(),
+        ),
+    ];
+
+    match config.output_dir {
+        Some(output_dir) => {
+            for (path, vector) in vectors {
+                let path = output_dir.join(path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, serde_json::to_string_pretty(&vector)? + "\n")?;
+                println!("Wrote {}", path.display());
+            }
+        }
+        None => {
+            let vectors: serde_json::Map<String, Value> = vectors
+                .into_iter()
+                .map(|(path, vector)| (path.to_string(), vector))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&vectors)?);
+        }
+    }
+    Ok(())
+}
+
+fn history_content_keys() -> Result<Value> {
+    let block_hash: [u8; 32] = H256::from_slice(&hex_decode(BLOCK_HASH)?).into();
+    let epoch_hash = H256::from_slice(&hex_decode(EPOCH_HASH)?);
+
+    let keys = [
+        (
+            "block_header",
+            json!({ "block_hash": BLOCK_HASH }),
+            HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey { block_hash }),
+        ),
+        (
+            "block_body",
+            json!({ "block_hash": BLOCK_HASH }),
+            HistoryContentKey::BlockBody(BlockBodyKey { block_hash }),
+        ),
+        (
+            "receipts",
+            json!({ "block_hash": BLOCK_HASH }),
+            HistoryContentKey::BlockReceipts(BlockReceiptsKey { block_hash }),
+        ),
+        (
+            "epoch_accumulator",
+            json!({ "epoch_hash": EPOCH_HASH }),
+            HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash }),
+        ),
+        (
+            "block_header_by_number",
+            json!({ "block_number": BLOCK_NUMBER }),
+            HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey {
+                block_number: BLOCK_NUMBER,
+            }),
+        ),
+        (
+            "ephemeral_header",
+            json!({ "block_hash": BLOCK_HASH }),
+            HistoryContentKey::EphemeralHeader(EphemeralHeaderKey { block_hash }),
+        ),
+    ];
+
+    Ok(Value::Array(
+        keys.into_iter()
+            .map(|(name, input, content_key)| {
+                let content_id = content_key.content_id();
+                json!({
+                    "name": name,
+                    "input": input,
+                    "content_key": content_key.to_hex(),
+                    "content_id": hex_encode(content_id),
+                    "content_id_u256": U256::from_big_endian(&content_id).to_string(),
+                })
+            })
+            .collect(),
+    ))
+}
+
+fn history_content_values() -> Value {
+    let values = [
+        (
+            "empty_block_body",
+            HistoryContentValue::BlockBody(BlockBody {
+                txs: vec![],
+                uncles: EncodableHeaderList { list: vec![] },
+            }),
+        ),
+        (
+            "empty_receipts",
+            HistoryContentValue::Receipts(Receipts {
+                receipt_list: vec![],
+            }),
+        ),
+    ];
+
+    Value::Array(
+        values
+            .into_iter()
+            .map(|(name, content_value)| {
+                json!({
+                    "name": name,
+                    "content_value": hex_encode(content_value.encode()),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn wire_messages() -> Result<Value> {
+    let mut content_keys = BitList::<typenum::U8>::with_capacity(8)
+        .map_err(|err| anyhow::anyhow!("Invalid bitlist capacity: {err:?}"))?;
+    content_keys
+        .set(0, true)
+        .map_err(|err| anyhow::anyhow!("Invalid bitlist index: {err:?}"))?;
+
+    let messages = [
+        (
+            "ping",
+            json!({ "enr_seq": 1, "data_radius": "2^256-2" }),
+            Message::Ping(Ping {
+                enr_seq: 1,
+                custom_payload: CustomPayload::from((U256::MAX - U256::from(1u8)).as_ssz_bytes()),
+            }),
+        ),
+        (
+            "pong",
+            json!({ "enr_seq": 1, "data_radius": "(2^256-1)/2" }),
+            Message::Pong(Pong {
+                enr_seq: 1,
+                custom_payload: CustomPayload::from((U256::MAX / U256::from(2u8)).as_ssz_bytes()),
+            }),
+        ),
+        (
+            "find_nodes",
+            json!({ "distances": [256, 255] }),
+            Message::FindNodes(FindNodes {
+                distances: vec![256, 255],
+            }),
+        ),
+        (
+            "nodes_zero_enrs",
+            json!({ "total": 1, "enrs": [] }),
+            Message::Nodes(Nodes {
+                total: 1,
+                enrs: vec![],
+            }),
+        ),
+        (
+            "find_content",
+            json!({ "content_key": "0x706f7274616c" }),
+            Message::FindContent(FindContent {
+                content_key: hex_decode("0x706f7274616c")?,
+            }),
+        ),
+        (
+            "content_connection_id",
+            json!({ "connection_id": "0x0102" }),
+            Message::Content(Content::ConnectionId(u16::from_le_bytes([0x01, 0x02]))),
+        ),
+        (
+            "content_content",
+            json!({ "content": "0x7468652063616b652069732061206c6965" }),
+            Message::Content(Content::Content(hex_decode(
+                "0x7468652063616b652069732061206c6965",
+            )?)),
+        ),
+        (
+            "offer",
+            json!({ "content_keys": ["0x010203"] }),
+            Message::Offer(Offer {
+                content_keys: vec![hex_decode("0x010203")?],
+            }),
+        ),
+        (
+            "accept",
+            json!({
+                "connection_id": "0x0102",
+                "content_keys": [true, false, false, false, false, false, false, false],
+            }),
+            Message::Accept(Accept {
+                connection_id: u16::from_le_bytes([0x01, 0x02]),
+                content_keys,
+            }),
+        ),
+    ];
+
+    Ok(Value::Array(
+        messages
+            .into_iter()
+            .map(|(name, input, message)| {
+                json!({
+                    "name": name,
+                    "input": input,
+                    "encoded": hex_encode(Into::<Vec<u8>>::into(message)),
+                })
+            })
+            .collect(),
+    ))
+}
+
+// CLI Parameter Handling
+#[derive(Parser, Debug, PartialEq)]
+#[command(
+    name = "Trin Test Vectors",
+    about = "Generate Portal Network test vectors for canonical inputs"
+)]
+pub struct TestVectorsConfig {
+    #[arg(
+        long,
+        help = "Directory to write the test vector files to. Vectors are printed to stdout if omitted"
+    )]
+    pub output_dir: Option<PathBuf>,
+}
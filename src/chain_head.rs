@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use ethereum_types::H256;
+use light_client::config::networks::Network;
+use light_client::database::ConfigDB;
+use light_client::types::BlockTag;
+use light_client::{Client, ClientBuilder};
+use tokio::sync::RwLock;
+use tokio::time::{interval, Duration};
+use tracing::{debug, info, warn};
+use trin_types::content_key::EPHEMERAL_HEADER_COUNT;
+
+use trin_validation::chain_head::ChainHead;
+
+/// The light client is local, so it can be polled often, to learn of new blocks as soon as they
+/// are verified.
+const LIGHT_CLIENT_POLL_RATE: Duration = Duration::from_secs(1);
+
+/// Syncs a light client from `consensus_rpc`, and then records the blocks that it verifies in
+/// `chain_head`, for as long as the node runs.
+pub async fn follow_light_client(
+    consensus_rpc: String,
+    checkpoint: Option<String>,
+    chain_head: Arc<RwLock<ChainHead>>,
+) {
+    let client = match start_light_client(&consensus_rpc, checkpoint.as_deref()).await {
+        Ok(client) => client,
+        Err(err) => {
+            warn!(%err, "Recent headers cannot be validated without a light client");
+            return;
+        }
+    };
+    let mut poll_interval = interval(LIGHT_CLIENT_POLL_RATE);
+    let mut last_number = None;
+    loop {
+        poll_interval.tick().await;
+        let head_number = match client.get_block_number().await {
+            Ok(head_number) => head_number,
+            Err(err) => {
+                debug!(%err, "Light client has no verified head");
+                continue;
+            }
+        };
+        if last_number == Some(head_number) {
+            continue;
+        }
+        // Record every block since the last poll that the light client still knows of.
+        let first_number = match last_number {
+            Some(last_number) if last_number < head_number => last_number + 1,
+            _ => head_number.saturating_sub(EPHEMERAL_HEADER_COUNT),
+        };
+        for block_number in first_number..=head_number {
+            let block_hash = client
+                .get_block_hash(BlockTag::Number(block_number))
+                .await
+                .ok()
+                .and_then(|block_hash| <[u8; 32]>::try_from(block_hash.as_slice()).ok());
+            if let Some(block_hash) = block_hash {
+                chain_head
+                    .write()
+                    .await
+                    .insert(block_number, H256::from(block_hash));
+            }
+        }
+        last_number = Some(head_number);
+    }
+}
+
+async fn start_light_client(
+    consensus_rpc: &str,
+    checkpoint: Option<&str>,
+) -> anyhow::Result<Client<ConfigDB>> {
+    let mut builder = ClientBuilder::new()
+        .network(Network::Mainnet)
+        .consensus_rpc(consensus_rpc)
+        .load_external_fallback();
+    if let Some(checkpoint) = checkpoint {
+        builder = builder.checkpoint(checkpoint);
+    }
+    let mut client: Client<ConfigDB> = builder
+        .build()
+        .map_err(|err| anyhow!("Unable to build light client: {err}"))?;
+    info!("Syncing light client from {consensus_rpc}");
+    client
+        .start()
+        .await
+        .map_err(|err| anyhow!("Unable to sync light client: {err}"))?;
+    Ok(client)
+}
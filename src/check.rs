@@ -0,0 +1,350 @@
+//! Pre-flight checks of a configuration, run with `trin --check` instead of starting the node.
+
+use std::{
+    net::{SocketAddr, TcpListener, UdpSocket},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use ethereum_types::{H256, U256};
+use serde_json::{json, Value};
+
+use ethportal_api::types::ping_extensions::PingPayload;
+use portalnet::{
+    discovery::Discovery,
+    types::messages::{
+        CustomPayload, Message, Ping, PortalnetConfig, ProtocolId, Request, Response,
+    },
+};
+use trin_types::bootnodes::with_extra_bootnodes;
+use trin_types::cli::{TrinConfig, Web3TransportType, DEFAULT_MASTER_ACC_PATH};
+use trin_types::enr::Enr;
+use trin_types::jsonrpc::params::Params;
+use trin_types::provider::TrustedProvider;
+use trin_validation::{accumulator::MasterAccumulator, constants::DEFAULT_MASTER_ACC_HASH};
+
+use crate::check_networks_compiled_in;
+
+/// Time a bootnode has to answer the PING.
+const BOOTNODE_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of bootnodes pinged before the bootnode check fails.
+const MAX_BOOTNODE_PINGS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// The check does not apply to the configuration, or depends on a check that failed.
+    Skipped,
+}
+
+impl CheckStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// Outcome of a single check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                status: CheckStatus::Ok,
+                detail,
+            },
+            Err(detail) => Self {
+                name,
+                status: CheckStatus::Failed,
+                detail,
+            },
+        }
+    }
+
+    fn skipped(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Skipped,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Outcome of all checks of a configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    pub results: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    /// Returns whether no check failed.
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|result| result.status != CheckStatus::Failed)
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "passed": self.passed(),
+            "checks": self
+                .results
+                .iter()
+                .map(|result| json!({
+                    "name": result.name,
+                    "status": result.status.as_str(),
+                    "detail": result.detail,
+                }))
+                .collect::<Vec<Value>>(),
+        })
+    }
+}
+
+/// Checks the configuration, that the discovery ports of all nodes and the JSON-RPC ports are
+/// free, that the master
+/// accumulator loads, that the trusted provider answers and that a bootnode answers a PING.
+/// Ports are released right after they are bound.
+pub async fn run_check(trin_config: &TrinConfig) -> CheckReport {
+    let mut report = CheckReport::default();
+
+    report
+        .results
+        .push(CheckResult::new("config", check_config(trin_config)));
+
+    let discovery_addr = SocketAddr::from(([0, 0, 0, 0], trin_config.discovery_port));
+    let discovery_port = CheckResult::new("discovery_port", check_udp_port(discovery_addr));
+    let discovery_port_free = discovery_port.status == CheckStatus::Ok;
+    report.results.push(discovery_port);
+    // An invalid extra nodes config already failed the config check.
+    for node in trin_config.extra_nodes().unwrap_or_default() {
+        let addr = SocketAddr::from(([0, 0, 0, 0], node.discovery_port));
+        report.results.push(CheckResult::new(
+            "extra_node_discovery_port",
+            check_udp_port(addr),
+        ));
+    }
+    report
+        .results
+        .push(CheckResult::new("jsonrpc", check_jsonrpc(trin_config)));
+
+    report.results.push(CheckResult::new(
+        "master_accumulator",
+        check_master_accumulator(&trin_config.master_acc_path),
+    ));
+
+    report
+        .results
+        .push(match TrustedProvider::from_trin_config(trin_config) {
+            Ok(Some(provider)) => {
+                CheckResult::new("trusted_provider", check_trusted_provider(provider).await)
+            }
+            Ok(None) => CheckResult::skipped("trusted_provider", "No trusted provider configured"),
+            Err(err) => CheckResult::new("trusted_provider", Err(err.to_string())),
+        });
+
+    report.results.push(if discovery_port_free {
+        check_bootnodes(trin_config).await
+    } else {
+        CheckResult::skipped("bootnode", "The discovery port is not available")
+    });
+
+    report
+}
+
+fn check_config(trin_config: &TrinConfig) -> Result<String, String> {
+    check_networks_compiled_in(&trin_config.networks)?;
+    let extra_nodes = trin_config.extra_nodes().map_err(|err| err.to_string())?;
+    Ok(format!(
+        "Networks {:?}, {} extra nodes",
+        trin_config.networks,
+        extra_nodes.len()
+    ))
+}
+
+/// Binds and releases the UDP socket at `addr`.
+fn check_udp_port(addr: SocketAddr) -> Result<String, String> {
+    UdpSocket::bind(addr).map_err(|err| format!("Unable to bind UDP {addr}: {err}"))?;
+    Ok(format!("UDP {addr} is available"))
+}
+
+/// Binds and releases the TCP socket at `addr`.
+fn check_tcp_port(addr: SocketAddr) -> Result<String, String> {
+    TcpListener::bind(addr).map_err(|err| format!("Unable to bind TCP {addr}: {err}"))?;
+    Ok(format!("TCP {addr} is available"))
+}
+
+fn check_jsonrpc(trin_config: &TrinConfig) -> Result<String, String> {
+    match trin_config.web3_transport {
+        Web3TransportType::HTTP => {
+            let addresses = std::iter::once(&trin_config.web3_http_address)
+                .chain(trin_config.web3_http_listeners.iter().map(|l| &l.address));
+            let mut details = vec![];
+            for address in addresses {
+                let socket_addrs = address
+                    .socket_addrs(|| None)
+                    .map_err(|err| format!("Unable to resolve {address}: {err}"))?;
+                let addr = socket_addrs
+                    .first()
+                    .ok_or_else(|| format!("Unable to resolve {address}"))?;
+                details.push(check_tcp_port(*addr)?);
+            }
+            Ok(details.join(", "))
+        }
+        Web3TransportType::IPC => {
+            let path = Path::new(&trin_config.web3_ipc_path);
+            match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => Err(format!(
+                    "IPC socket directory {} does not exist",
+                    parent.display()
+                )),
+                _ if path.exists() => Err(format!(
+                    "IPC socket {} already exists, is another node running?",
+                    path.display()
+                )),
+                _ => Ok(format!("IPC socket {} is available", path.display())),
+            }
+        }
+    }
+}
+
+fn check_master_accumulator(path: &Path) -> Result<String, String> {
+    let master_acc =
+        MasterAccumulator::try_from_file(path.to_path_buf()).map_err(|err| err.to_string())?;
+    let root = master_acc.tree_hash_root();
+    // Only the default accumulator has a known root.
+    if path == Path::new(DEFAULT_MASTER_ACC_PATH) {
+        let expected = H256::from_str(DEFAULT_MASTER_ACC_HASH)
+            .map_err(|err| format!("Invalid default master accumulator root: {err}"))?;
+        if root != expected {
+            return Err(format!(
+                "Master accumulator root {root:?} does not match the expected root {expected:?}"
+            ));
+        }
+    }
+    Ok(format!(
+        "{} epochs, root {root:?}",
+        master_acc.epoch_count()
+    ))
+}
+
+async fn check_trusted_provider(provider: TrustedProvider) -> Result<String, String> {
+    // The provider client is blocking.
+    let response = tokio::task::spawn_blocking(move || {
+        provider.dispatch_http_request("eth_blockNumber".to_string(), Params::None)
+    })
+    .await
+    .map_err(|err| format!("Trusted provider request panicked: {err}"))?
+    .map_err(|err| err.to_string())?;
+    match response.get("result") {
+        Some(block_number) => Ok(format!("Latest block {block_number}")),
+        None => Err(format!("Unexpected response: {response}")),
+    }
+}
+
+/// Starts discovery on the configured port and sends a history network PING to the bootnodes,
+/// one at a time, until one of them answers.
+async fn check_bootnodes(trin_config: &TrinConfig) -> CheckResult {
+    let bootnodes = with_extra_bootnodes(
+        trin_config.bootnodes.clone(),
+        trin_config.extra_bootnodes.clone(),
+    );
+    if bootnodes.is_empty() {
+        return CheckResult::skipped("bootnode", "No bootnodes configured");
+    }
+    let portalnet_config = PortalnetConfig {
+        external_addr: trin_config.external_addr,
+        private_key: trin_config.private_key,
+        listen_port: trin_config.discovery_port,
+        no_stun: trin_config.no_stun,
+        ..Default::default()
+    };
+    let mut discovery = match Discovery::new(portalnet_config) {
+        Ok(discovery) => discovery,
+        Err(err) => return CheckResult::new("bootnode", Err(err.to_string())),
+    };
+    if let Err(err) = discovery.start().await {
+        return CheckResult::new("bootnode", Err(err.to_string()));
+    }
+
+    let mut failures = vec![];
+    for bootnode in bootnodes.into_iter().take(MAX_BOOTNODE_PINGS) {
+        let node_id = bootnode.node_id();
+        match ping(&discovery, bootnode).await {
+            Ok(()) => return CheckResult::new("bootnode", Ok(format!("{node_id} answered"))),
+            Err(err) => failures.push(format!("{node_id}: {err}")),
+        }
+    }
+    CheckResult::new("bootnode", Err(failures.join(", ")))
+}
+
+async fn ping(discovery: &Discovery, enr: Enr) -> Result<(), String> {
+    // A zero radius keeps the bootnode from offering content to the check.
+    let request = Request::Ping(Ping {
+        enr_seq: discovery.local_enr().seq(),
+        custom_payload: CustomPayload::from(PingPayload {
+            data_radius: U256::zero(),
+            extensions: None,
+        }),
+    });
+    let talk_req = discovery.send_talk_req(enr, ProtocolId::History, Message::from(request).into());
+    let response = tokio::time::timeout(BOOTNODE_PING_TIMEOUT, talk_req)
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|err| format!("{err:?}"))?;
+    let message = Message::try_from(response).map_err(|err| format!("{err:?}"))?;
+    match Response::try_from(message) {
+        Ok(Response::Pong(_)) => Ok(()),
+        _ => Err("invalid response".to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bound_port_fails_check() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        assert!(check_udp_port(addr).is_err());
+        drop(socket);
+        assert!(check_udp_port(addr).is_ok());
+    }
+
+    #[test]
+    fn report_fails_on_any_failed_check() {
+        let mut report = CheckReport {
+            results: vec![
+                CheckResult::new("config", Ok("fine".to_string())),
+                CheckResult::skipped("bootnode", "No bootnodes configured"),
+            ],
+        };
+        assert!(report.passed());
+        assert_eq!(report.to_json()["checks"][1]["status"], "skipped");
+
+        report
+            .results
+            .push(CheckResult::new("jsonrpc", Err("in use".to_string())));
+        assert!(!report.passed());
+        assert_eq!(report.to_json()["passed"], false);
+    }
+
+    #[test]
+    fn default_master_accumulator_passes_check() {
+        assert!(check_master_accumulator(Path::new(DEFAULT_MASTER_ACC_PATH)).is_ok());
+    }
+}
@@ -0,0 +1,108 @@
+use std::{io, net::SocketAddr, path::PathBuf};
+
+use portalnet::discovery::DiscoveryError;
+
+/// An error that prevents trin from starting.
+///
+/// The messages say what failed and which flags to check. The underlying error, if any, is the
+/// error's source, which `main` prints below the message.
+#[derive(Debug, thiserror::Error)]
+pub enum TrinError {
+    /// The configuration is inconsistent, e.g. it selects a subnetwork that was not compiled in.
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+    /// The private key is invalid, or the local ENR cannot be built from it.
+    #[error("Invalid node key or ENR: {0}. Check --unsafe-private-key and --external-address")]
+    InvalidEnr(String),
+    /// A bootnode ENR was rejected.
+    #[error("Invalid bootnode ENR: {0}. Check --bootnodes and --extra-bootnodes")]
+    InvalidBootnode(String),
+    /// The discovery port is bound by another socket.
+    #[error("Discovery port {0} is already in use, pick another one with --discovery-port")]
+    PortInUse(u16),
+    /// The discovery protocol failed to start for another reason.
+    #[error("Unable to start discovery: {0}")]
+    Discovery(String),
+    /// The trusted provider cannot be set up, e.g. because its credentials are missing.
+    #[error(
+        "Unable to set up the trusted provider. Check --trusted-provider and --trusted-provider-url"
+    )]
+    TrustedProvider(#[source] anyhow::Error),
+    /// The master accumulator file is missing or corrupt.
+    #[error(
+        "Unable to load the master accumulator from {}. Check --master-accumulator-path",
+        .path.display()
+    )]
+    Accumulator {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// The data directory cannot be created or opened.
+    #[error("Unable to set up the data directory. Check --data-dir and --db-paths")]
+    DataDir(#[source] anyhow::Error),
+    /// The prometheus exporter cannot listen on the metrics address.
+    #[error("Unable to serve metrics on {addr}: {reason}. Check --enable-metrics-with-url")]
+    Metrics { addr: SocketAddr, reason: String },
+    /// The telemetry recording cannot be opened.
+    #[error("Unable to open telemetry recording {}", .path.display())]
+    Telemetry {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A subnetwork failed to initialize, e.g. because its database cannot be opened.
+    #[error("Unable to start subnetwork")]
+    Network(#[source] anyhow::Error),
+    /// The JSON-RPC server failed to start.
+    #[error("Unable to start JSON-RPC server: {0}")]
+    JsonRpc(String),
+}
+
+impl From<DiscoveryError> for TrinError {
+    fn from(err: DiscoveryError) -> Self {
+        match err {
+            DiscoveryError::InvalidPrivateKey(reason) | DiscoveryError::InvalidEnr(reason) => {
+                Self::InvalidEnr(reason)
+            }
+            DiscoveryError::InvalidBootnode(reason) => Self::InvalidBootnode(reason),
+            DiscoveryError::PortInUse(port) => Self::PortInUse(port),
+            DiscoveryError::Discv5(reason) => Self::Discovery(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn discovery_errors_map_to_actionable_errors() {
+        let err = TrinError::from(DiscoveryError::PortInUse(9000));
+        assert!(matches!(err, TrinError::PortInUse(9000)));
+        assert_eq!(
+            err.to_string(),
+            "Discovery port 9000 is already in use, pick another one with --discovery-port"
+        );
+
+        let err = TrinError::from(DiscoveryError::InvalidPrivateKey("bad key".to_string()));
+        assert!(matches!(err, TrinError::InvalidEnr(_)));
+    }
+
+    #[test]
+    fn underlying_errors_are_sources() {
+        let err = TrinError::Accumulator {
+            path: PathBuf::from("/tmp/acc.ssz"),
+            source: anyhow::anyhow!("No such file"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Unable to load the master accumulator from /tmp/acc.ssz. Check --master-accumulator-path"
+        );
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "No such file"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use discv5::enr::NodeId;
+use ethportal_api::jsonrpsee::server::ServerHandle;
+use portalnet::{discovery::Discovery, lifetime_stats::LifetimeStatsSaver};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Handles to the components that `run_trin` spawns.
+///
+/// Dropping the handle leaves the components running. Every task runs for as long as the node
+/// does, so a task that finishes on its own has failed; awaiting its `JoinHandle` tells whether
+/// it panicked.
+#[derive(Debug)]
+pub struct TrinHandle {
+    /// The JSON-RPC server, which serves the first node.
+    pub rpc_handle: ServerHandle,
+    /// Follows the chain head with a light client, `None` without `--consensus-rpc`. Shared by
+    /// all nodes.
+    pub light_client: Option<JoinHandle<()>>,
+    /// The node configured on the command line, followed by the extra nodes.
+    pub nodes: Vec<NodeHandle>,
+}
+
+impl TrinHandle {
+    /// Stops the JSON-RPC server and the tasks of all nodes.
+    pub fn stop(&self) {
+        if !self.rpc_handle.is_stopped() {
+            // The server may stop in between, e.g. from admin_shutdown.
+            let _ = self.rpc_handle.stop();
+        }
+        if let Some(light_client) = &self.light_client {
+            light_client.abort();
+        }
+        self.nodes.iter().for_each(NodeHandle::abort);
+    }
+
+    /// Returns the names of the tasks that have finished, per node id.
+    pub fn finished_tasks(&self) -> Vec<(NodeId, &'static str)> {
+        self.nodes
+            .iter()
+            .flat_map(|node| {
+                node.finished_tasks()
+                    .into_iter()
+                    .map(|task| (node.node_id, task))
+            })
+            .collect()
+    }
+}
+
+/// Handles to the tasks of a node. Subnetwork tasks are `None` if the subnetwork is not selected.
+#[derive(Debug)]
+pub struct NodeHandle {
+    pub node_id: NodeId,
+    /// Routes the node's talk requests to the subnetworks and the uTP socket.
+    pub events: JoinHandle<()>,
+    /// Serves the history network's JSON-RPC requests.
+    pub history_handler: Option<JoinHandle<()>>,
+    /// Handles the history network's overlay messages.
+    pub history_network: Option<JoinHandle<()>>,
+    /// Serves the state network's JSON-RPC requests.
+    pub state_handler: Option<JoinHandle<()>>,
+    /// Handles the state network's overlay messages.
+    pub state_network: Option<JoinHandle<()>>,
+    /// The overlay services and periodic tasks of the subnetworks, e.g. re-gossip and compaction.
+    pub background: Vec<(&'static str, JoinHandle<()>)>,
+    /// The node's discv5 service, which is shared by its subnetworks and its uTP socket.
+    pub discovery: Arc<Discovery>,
+    /// Saves the lifetime statistics of the history network, `None` if it is not selected.
+    pub lifetime_stats: Option<LifetimeStatsSaver>,
+}
+
+impl NodeHandle {
+    /// Aborts all tasks of the node, saves its lifetime statistics, then shuts down its discv5
+    /// service, which closes the socket shared by discovery and uTP.
+    pub fn abort(&self) {
+        self.tasks().for_each(|(_, task)| task.abort());
+        if let Some(lifetime_stats) = &self.lifetime_stats {
+            if let Err(err) = lifetime_stats.save() {
+                warn!(%err, "Unable to save lifetime statistics");
+            }
+        }
+        self.discovery.shutdown();
+    }
+
+    /// Returns the names of the tasks that have finished.
+    pub fn finished_tasks(&self) -> Vec<&'static str> {
+        self.tasks()
+            .filter(|(_, task)| task.is_finished())
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    fn tasks(&self) -> impl Iterator<Item = (&'static str, &JoinHandle<()>)> {
+        [
+            ("history_handler", self.history_handler.as_ref()),
+            ("history_network", self.history_network.as_ref()),
+            ("state_handler", self.state_handler.as_ref()),
+            ("state_network", self.state_network.as_ref()),
+        ]
+        .into_iter()
+        .filter_map(|(name, task)| task.map(|task| (name, task)))
+        .chain(self.background.iter().map(|(name, task)| (*name, task)))
+        .chain(std::iter::once(("events", &self.events)))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use portalnet::types::messages::PortalnetConfig;
+
+    #[tokio::test]
+    async fn abort_finishes_all_tasks() {
+        let discovery = Discovery::new(PortalnetConfig {
+            no_stun: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let node = NodeHandle {
+            node_id: NodeId::random(),
+            events: tokio::spawn(std::future::pending()),
+            history_handler: Some(tokio::spawn(std::future::pending())),
+            history_network: Some(tokio::spawn(async {})),
+            state_handler: None,
+            state_network: None,
+            background: vec![("regossip", tokio::spawn(std::future::pending()))],
+            discovery: Arc::new(discovery),
+            lifetime_stats: None,
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(node.finished_tasks(), vec!["history_network"]);
+
+        node.abort();
+        tokio::task::yield_now().await;
+        assert_eq!(
+            node.finished_tasks(),
+            vec!["history_handler", "history_network", "regossip", "events"]
+        );
+    }
+}
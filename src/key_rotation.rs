@@ -0,0 +1,183 @@
+//! Rotation of the node key. The node id is derived from the key and decides both the name of
+//! the node's data directories and which content is close to the node, so both are carried over
+//! to the new node id.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use discv5::enr::{CombinedKey, EnrBuilder, NodeId};
+use ethereum_types::H256;
+use tracing::{info, warn};
+
+use portalnet::{
+    discovery::build_enr,
+    kv_store::KvStoreOptions,
+    storage::{PortalStorage, PortalStorageConfig},
+    types::messages::ProtocolId,
+    utils::db::{get_root_path, move_node_data_dir},
+};
+use trin_types::cli::TrinConfig;
+use trin_types::content_key::HistoryContentKey;
+use trin_types::distance::{Distance, Metric, XorMetric};
+use trin_types::enr::Enr;
+use trin_utils::bytes::hex_encode;
+
+/// Name of the file, in the data directory, that the new private key is written to before any
+/// data is moved, so that the key isn't lost if the rotation is interrupted.
+pub const ROTATED_KEY_FILE_NAME: &str = "rotated_private_key";
+
+/// Outcome of a key rotation.
+#[derive(Debug)]
+pub struct KeyRotation {
+    /// ENR of the node with the new key. It only carries an IP address if one is given with
+    /// `--external-address`, since the address found with STUN at startup is not known here.
+    pub enr: Enr,
+    pub old_node_id: NodeId,
+    pub new_node_id: NodeId,
+    /// File that holds the new private key.
+    pub key_path: PathBuf,
+    /// Data directories that were renamed after the new node id.
+    pub moved_dirs: Vec<PathBuf>,
+    /// Number of content items dropped because they lie outside the radius around the new node
+    /// id.
+    pub pruned: u64,
+}
+
+/// Generates a new node key and moves the data of the node keyed by `--unsafe-private-key` to
+/// the new node id.
+///
+/// Content is kept within the radius the old node had, measured from the new node id, so that
+/// the node advertises the same radius and keeps only content it is still expected to store.
+/// With `keep_content`, all content is kept, and the farthest content is evicted as new content
+/// arrives instead.
+///
+/// The new key is saved to `ROTATED_KEY_FILE_NAME` before any data is moved. If moving or pruning
+/// fails, the directories are moved back and the key file is removed.
+pub fn rotate_key(trin_config: &TrinConfig, keep_content: bool) -> anyhow::Result<KeyRotation> {
+    let old_private_key = trin_config.private_key.ok_or_else(|| {
+        anyhow!("Without --unsafe-private-key, a new node key is generated on every start, so there is no key to rotate")
+    })?;
+    let old_node_id = node_id(&old_private_key)?;
+
+    let new_key = CombinedKey::generate_secp256k1();
+    let private_key = H256::from_slice(&new_key.encode());
+    let new_node_id = node_id(&private_key)?;
+    let (ip_address, port) = match trin_config.external_addr {
+        Some(addr) if !trin_config.no_stun => (Some(addr.ip()), addr.port()),
+        _ => (None, trin_config.discovery_port),
+    };
+    let enr = build_enr(&new_key, ip_address, port)?;
+
+    let root_path = get_root_path(trin_config.data_dir.as_deref())?;
+    let storage_config = |node_id| {
+        let mut config = PortalStorageConfig::with_db_paths(
+            trin_config.kb.into(),
+            node_id,
+            &root_path,
+            &trin_config.db_paths,
+            KvStoreOptions::default(),
+        )?;
+        config.fixed_radius = trin_config.fixed_radius;
+        config.archive = trin_config.archive;
+        anyhow::Ok(config)
+    };
+
+    // The storage is opened as it would be on startup, to learn the radius of the old node.
+    let old_radius =
+        PortalStorage::new::<HistoryContentKey>(storage_config(old_node_id)?, ProtocolId::History)?
+            .radius();
+
+    let key_path = root_path.join(ROTATED_KEY_FILE_NAME);
+    save_private_key(&key_path, &private_key)?;
+    info!(path = %key_path.display(), "Saved new private key");
+
+    let roots: Vec<&PathBuf> = std::iter::once(&root_path)
+        .chain(&trin_config.db_paths)
+        .collect();
+    let mut moved_roots = vec![];
+    let mut moved_dirs = vec![];
+    let mut move_and_prune = || {
+        for root in &roots {
+            if let Some(dir) = move_node_data_dir(root, old_node_id, new_node_id)? {
+                info!(path = %dir.display(), "Moved data directory");
+                moved_roots.push(*root);
+                moved_dirs.push(dir);
+            }
+        }
+
+        let mut pruned = 0;
+        if !keep_content && old_radius < Distance::MAX {
+            let content_db = storage_config(new_node_id)?.content_db;
+            for content_id in content_db.content_ids()? {
+                if XorMetric::distance(&content_id, &new_node_id.raw()) > old_radius
+                    && content_db.delete(&content_id)?
+                {
+                    pruned += 1;
+                }
+            }
+            info!(
+                pruned,
+                radius = %old_radius,
+                "Pruned content outside the radius of the new node id"
+            );
+        }
+        anyhow::Ok(pruned)
+    };
+
+    match move_and_prune() {
+        Ok(pruned) => Ok(KeyRotation {
+            enr,
+            old_node_id,
+            new_node_id,
+            key_path,
+            moved_dirs,
+            pruned,
+        }),
+        Err(err) => {
+            // Content pruned before the failure stays dropped, which only costs the node some
+            // content it would have been offered again.
+            for root in moved_roots.into_iter().rev() {
+                if let Err(err) = move_node_data_dir(root, new_node_id, old_node_id) {
+                    warn!(%err, "Unable to move data directory back");
+                }
+            }
+            if let Err(err) = fs::remove_file(&key_path) {
+                warn!(%err, path = %key_path.display(), "Unable to remove new private key");
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Writes `private_key` to `path`, readable only by the current user.
+fn save_private_key(path: &Path, private_key: &H256) -> anyhow::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|err| {
+        anyhow!(
+            "Unable to save new private key to {}: {err}",
+            path.display()
+        )
+    })?;
+    writeln!(file, "{}", hex_encode(private_key))?;
+    file.sync_all()?;
+    Ok(())
+}
+
+fn node_id(private_key: &H256) -> anyhow::Result<NodeId> {
+    let key = CombinedKey::secp256k1_from_bytes(&mut private_key.to_fixed_bytes())
+        .map_err(|err| anyhow!("Invalid private key: {err:?}"))?;
+    let enr = EnrBuilder::new("v4")
+        .build(&key)
+        .map_err(|err| anyhow!("Unable to build ENR: {err:?}"))?;
+    Ok(enr.node_id())
+}
@@ -1,55 +1,273 @@
 #![warn(clippy::unwrap_used)]
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use ethportal_api::jsonrpsee::server::ServerHandle;
-use rpc::JsonRpcServer;
+use ethportal_api::types::ping_extensions::{Capability, PingExtensions};
+use ethportal_api::types::web3::BuildInfo;
+use rpc::{IpcPermissions, JsonRpcServer, PayloadLimits, TlsConfig};
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::info;
 use utp_rs::socket::UtpSocket;
 
+#[cfg(feature = "history")]
+use portalnet::regossip::RegossipConfig;
 use portalnet::{
     discovery::{Discovery, Discv5UdpSocket},
     events::PortalnetEvents,
+    kv_store::KvStoreOptions,
+    node_events::NodeEvents,
     storage::PortalStorageConfig,
+    telemetry::{spawn_telemetry_recorder, TelemetryConfig},
     types::messages::PortalnetConfig,
-    utils::db::setup_temp_dir,
+    utils::db::{get_root_path, setup_temp_dir},
 };
-use trin_history::initialize_history_network;
+#[cfg(feature = "history")]
+use trin_history::{initialize_history_network, webhook::WebhookConfig};
+#[cfg(feature = "state")]
 use trin_state::initialize_state_network;
-use trin_types::cli::{TrinConfig, Web3TransportType, HISTORY_NETWORK, STATE_NETWORK};
-use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
+use trin_types::bootnodes::with_extra_bootnodes;
+use trin_types::cli::{
+    HttpListener, TrinConfig, Web3TransportType, HISTORY_NETWORK, STATE_NETWORK,
+};
+use trin_types::distance::Distance;
+use trin_types::jsonrpc::request::{HistoryJsonRpcRequest, StateJsonRpcRequest};
 use trin_types::provider::TrustedProvider;
-use trin_utils::version::get_trin_version;
-use trin_validation::{accumulator::MasterAccumulator, oracle::HeaderOracle};
+use trin_utils::version::{get_trin_version, BUILD_DATE, GIT_HASH, TARGET_TRIPLE, TRIN_VERSION};
+use trin_validation::{
+    accumulator::MasterAccumulator, chain_head::ChainHead, oracle::HeaderOracle,
+};
+
+mod chain_head;
+pub mod check;
+mod error;
+mod handle;
+pub mod key_rotation;
+
+pub use error::TrinError;
+pub use handle::{NodeHandle, TrinHandle};
 
 pub async fn run_trin(
     trin_config: TrinConfig,
-    trusted_provider: TrustedProvider,
-) -> Result<ServerHandle, Box<dyn std::error::Error>> {
+    trusted_provider: Option<TrustedProvider>,
+) -> Result<TrinHandle, TrinError> {
+    run_trin_with_events(trin_config, trusted_provider, NodeEvents::default()).await
+}
+
+/// Runs trin like `run_trin`, emitting the activity of the node on `events`.
+///
+/// Applications embedding trin can subscribe to `events` before calling this function, in order
+/// to react to the node's activity from the moment it starts.
+pub async fn run_trin_with_events(
+    trin_config: TrinConfig,
+    trusted_provider: Option<TrustedProvider>,
+    events: NodeEvents,
+) -> Result<TrinHandle, TrinError> {
     let trin_version = get_trin_version();
     info!("Launching Trin: v{trin_version}");
     info!(config = %trin_config, "With:");
+    check_networks_compiled_in(&trin_config.networks).map_err(TrinError::Config)?;
+    let extra_nodes = trin_config
+        .extra_nodes()
+        .map_err(|err| TrinError::Config(err.to_string()))?;
+
+    // Record lookups and gossip decisions from the start, if requested
+    if let Some(path) = trin_config.telemetry_record.clone() {
+        info!(path = %path.display(), "Recording lookups and gossip decisions");
+        spawn_telemetry_recorder(
+            &events,
+            TelemetryConfig {
+                path: path.clone(),
+                lookup_sample_rate: trin_config.telemetry_lookup_sample_rate,
+                gossip_sample_rate: trin_config.telemetry_gossip_sample_rate,
+            },
+        )
+        .map_err(|source| TrinError::Telemetry { path, source })?;
+    }
+
+    // Initialize prometheus metrics
+    if let Some(addr) = trin_config.enable_metrics_with_url {
+        prometheus_exporter::start(addr).map_err(|err| TrinError::Metrics {
+            addr,
+            reason: err.to_string(),
+        })?;
+    }
+
+    // Load the master accumulator, which the nodes of the process validate against
+    let master_accumulator = MasterAccumulator::try_from_file(trin_config.master_acc_path.clone())
+        .map_err(|source| TrinError::Accumulator {
+            path: trin_config.master_acc_path.clone(),
+            source,
+        })?;
+    info!(
+        "Loaded master accumulator from: {:?}",
+        trin_config.master_acc_path
+    );
+    if trusted_provider.is_none() {
+        info!("Running without a trusted provider: recent headers cannot be validated");
+    }
+
+    // Follow the chain head with a light client, which recent headers are validated against
+    let chain_head = Arc::new(RwLock::new(ChainHead::default()));
+    let light_client = trin_config.consensus_rpc.clone().map(|consensus_rpc| {
+        tokio::spawn(chain_head::follow_light_client(
+            consensus_rpc.to_string(),
+            trin_config.light_client_checkpoint.clone(),
+            Arc::clone(&chain_head),
+        ))
+    });
+    if light_client.is_none() {
+        info!("Running without a light client: recent headers cannot be validated");
+    }
+
+    let (discovery, jsonrpc_txs, node) = launch_node(
+        &trin_config,
+        trusted_provider.clone(),
+        master_accumulator.clone(),
+        Arc::clone(&chain_head),
+        events,
+    )
+    .await?;
+    let mut nodes = vec![node];
+
+    // Stops the nodes and the light client launched so far, if launching the rest fails
+    let abort_launched = |nodes: &[NodeHandle], err: TrinError| {
+        nodes.iter().for_each(NodeHandle::abort);
+        if let Some(light_client) = &light_client {
+            light_client.abort();
+        }
+        err
+    };
 
+    // Launch the extra nodes, which share the runtime but not the JSON-RPC server
+    for node in extra_nodes {
+        let (node_discovery, _, node_handle) = launch_node(
+            &trin_config.for_extra_node(&node),
+            trusted_provider.clone(),
+            master_accumulator.clone(),
+            Arc::clone(&chain_head),
+            NodeEvents::default(),
+        )
+        .await
+        .map_err(|err| abort_launched(&nodes, err))?;
+        info!(
+            node_id = %node_discovery.local_enr().node_id(),
+            port = node.discovery_port,
+            "Launched extra node"
+        );
+        nodes.push(node_handle);
+    }
+
+    // Launch JSON-RPC server
+    let rpc_handle = launch_jsonrpc_server(trin_config, discovery, jsonrpc_txs, trusted_provider)
+        .await
+        .map_err(|err| abort_launched(&nodes, TrinError::JsonRpc(err)))?;
+    Ok(TrinHandle {
+        rpc_handle,
+        light_client,
+        nodes,
+    })
+}
+
+/// Starts the discovery protocol, uTP socket, storage and subnetworks of the node configured by
+/// `trin_config`, emitting its activity on `events`. Returns the discovery service of the node,
+/// the channels on which its subnetworks serve JSON-RPC requests, and the node's tasks.
+async fn launch_node(
+    trin_config: &TrinConfig,
+    trusted_provider: Option<TrustedProvider>,
+    master_accumulator: MasterAccumulator,
+    chain_head: Arc<RwLock<ChainHead>>,
+    events: NodeEvents,
+) -> Result<(Arc<Discovery>, JsonRpcChannels, NodeHandle), TrinError> {
+    let trin_version = get_trin_version();
+    let ping_extensions = trin_config.ping_extensions.then(|| PingExtensions {
+        client_info: format!("trin v{trin_version}"),
+        capabilities: trin_config
+            .networks
+            .iter()
+            .filter_map(|network| match network.as_str() {
+                HISTORY_NETWORK => Some(Capability::HistoryNetwork),
+                STATE_NETWORK => Some(Capability::StateNetwork),
+                _ => None,
+            })
+            .chain(
+                (trin_config.utp_transfer_limit > 1).then_some(Capability::ConcurrentOfferStreams),
+            )
+            .collect(),
+    });
     let portalnet_config = PortalnetConfig {
         external_addr: trin_config.external_addr,
         private_key: trin_config.private_key,
         listen_port: trin_config.discovery_port,
         no_stun: trin_config.no_stun,
-        bootnode_enrs: trin_config.bootnodes.clone().into(),
+        bootnode_enrs: with_extra_bootnodes(
+            trin_config.bootnodes.clone(),
+            trin_config.extra_bootnodes.clone(),
+        ),
+        ping_extensions,
+        bucket_refresh_interval: Duration::from_secs(trin_config.bucket_refresh_interval_secs),
+        bucket_size: trin_config.bucket_size,
+        table_depth: trin_config.table_depth,
+        bucket_pending_timeout: Duration::from_secs(trin_config.bucket_pending_timeout_secs),
+        max_incoming_per_bucket: trin_config.max_incoming_per_bucket,
+        talkreq_timeouts: trin_config.talkreq_timeouts.clone(),
+        discv5_request_timeout: Duration::from_millis(trin_config.discv5_request_timeout_ms),
+        lookup_timeout: Duration::from_secs(trin_config.lookup_timeout_secs),
+        lookup_peer_timeout: Duration::from_secs(trin_config.lookup_peer_timeout_secs),
+        lookup_peer_attempts: trin_config.lookup_peer_attempts,
+        lookup_num_results: trin_config.lookup_num_results,
+        lookup_cache_size: trin_config.lookup_cache_size,
+        lookup_cache_ttl: Duration::from_secs(trin_config.lookup_cache_ttl_secs),
+        validation_cache_size: trin_config.validation_cache_size,
+        utp_transfer_limit: trin_config.utp_transfer_limit,
+        neighborhood_sync_limit: trin_config.neighborhood_sync_limit,
+        events: events.clone(),
         ..Default::default()
     };
 
-    // Initialize base discovery protocol
+    // Initialize base discovery protocol, which is started once the storage is set up so that
+    // nothing needs to be shut down if setting up the storage fails
     let mut discovery = Discovery::new(portalnet_config.clone())?;
-    let talk_req_rx = discovery.start().await?;
-    let discovery = Arc::new(discovery);
 
-    // Initialize prometheus metrics
-    if let Some(addr) = trin_config.enable_metrics_with_url {
-        prometheus_exporter::start(addr)?;
+    // Initialize Storage config
+    let root_path = if trin_config.ephemeral || trin_config.no_storage {
+        setup_temp_dir().map_err(TrinError::DataDir)?.into_path()
+    } else {
+        get_root_path(trin_config.data_dir.as_deref()).map_err(TrinError::DataDir)?
+    };
+    info!(path = %root_path.display(), "Using data directory");
+
+    let mut storage_config = PortalStorageConfig::with_db_paths(
+        trin_config.kb.into(),
+        discovery.local_enr().node_id(),
+        &root_path,
+        &trin_config.db_paths,
+        KvStoreOptions {
+            block_cache_bytes: trin_config.db_block_cache_mb.map(|mb| mb * 1024 * 1024),
+            write_buffer_bytes: trin_config.db_write_buffer_mb.map(|mb| mb * 1024 * 1024),
+            compression: trin_config.db_compression,
+            max_open_files: trin_config.db_max_open_files,
+            compaction_style: trin_config.db_compaction_style,
+        },
+    )
+    .map_err(TrinError::DataDir)?;
+    if let Some(radius) = trin_config.fixed_radius {
+        info!(%radius, "Using fixed data radius");
+        storage_config.fixed_radius = Some(radius);
     }
+    if trin_config.no_storage {
+        info!("Running without storage: advertising zero radius and declining all offers");
+        storage_config.storage_capacity_kb = 0;
+        storage_config.fixed_radius = Some(Distance::ZERO);
+    }
+    storage_config.archive = trin_config.archive;
+    storage_config.events = events;
+
+    // Start base discovery protocol
+    let talk_req_rx = discovery.start().await?;
+    let discovery = Arc::new(discovery);
 
     // Initialize and spawn uTP socket
     let (utp_talk_reqs_tx, utp_talk_reqs_rx) = mpsc::unbounded_channel();
@@ -57,25 +275,14 @@ pub async fn run_trin(
     let utp_socket = UtpSocket::with_socket(discv5_utp_socket);
     let utp_socket = Arc::new(utp_socket);
 
-    // Initialize Storage config
-    if trin_config.ephemeral {
-        setup_temp_dir()?;
-    }
-
-    let storage_config =
-        PortalStorageConfig::new(trin_config.kb.into(), discovery.local_enr().node_id())?;
-
     // Initialize validation oracle
-    let master_accumulator = MasterAccumulator::try_from_file(trin_config.master_acc_path.clone())?;
-    info!(
-        "Loaded master accumulator from: {:?}",
-        trin_config.master_acc_path
-    );
-    let header_oracle = HeaderOracle::new(trusted_provider.clone(), master_accumulator);
+    let mut header_oracle = HeaderOracle::new(trusted_provider, master_accumulator);
+    header_oracle.chain_head = chain_head;
     let header_oracle = Arc::new(RwLock::new(header_oracle));
 
     // Initialize state sub-network service and event handlers, if selected
-    let (state_handler, state_network_task, state_event_tx, _state_jsonrpc_tx) =
+    #[cfg(feature = "state")]
+    let (state_handler, state_network_task, state_event_tx, state_jsonrpc_tx, state_background) =
         if trin_config.networks.iter().any(|val| val == STATE_NETWORK) {
             initialize_state_network(
                 &discovery,
@@ -84,45 +291,91 @@ pub async fn run_trin(
                 storage_config.clone(),
                 header_oracle.clone(),
             )
-            .await?
+            .await
+            .map_err(|err| {
+                discovery.shutdown();
+                TrinError::Network(err)
+            })?
         } else {
-            (None, None, None, None)
+            (None, None, None, None, vec![])
         };
+    #[cfg(not(feature = "state"))]
+    let (state_network_task, state_event_tx, state_jsonrpc_tx, state_background): (
+        Option<JoinHandle<()>>,
+        _,
+        _,
+        Vec<(&'static str, JoinHandle<()>)>,
+    ) = (None, None, None, vec![]);
 
     // Initialize chain history sub-network service and event handlers, if selected
-    let (history_handler, history_network_task, history_event_tx, history_jsonrpc_tx) =
-        if trin_config
-            .networks
-            .iter()
-            .any(|val| val == HISTORY_NETWORK)
-        {
-            initialize_history_network(
-                &discovery,
-                utp_socket,
-                portalnet_config.clone(),
-                storage_config.clone(),
-                header_oracle.clone(),
-            )
-            .await?
-        } else {
-            (None, None, None, None)
-        };
-
-    // Launch JSON-RPC server
-    let jsonrpc_trin_config = trin_config.clone();
-    let jsonrpc_discovery = Arc::clone(&discovery);
-    let rpc_handle =
-        launch_jsonrpc_server(jsonrpc_trin_config, jsonrpc_discovery, history_jsonrpc_tx).await;
+    #[cfg(feature = "history")]
+    let (
+        history_handler,
+        history_network_task,
+        history_event_tx,
+        history_jsonrpc_tx,
+        history_background,
+        lifetime_stats,
+    ) = if trin_config
+        .networks
+        .iter()
+        .any(|val| val == HISTORY_NETWORK)
+    {
+        initialize_history_network(
+            &discovery,
+            Arc::clone(&utp_socket),
+            portalnet_config.clone(),
+            storage_config.clone(),
+            header_oracle.clone(),
+            trin_config.glados_report_url.clone(),
+            trin_config.stats_export.clone(),
+            match trin_config.compaction_interval_hours {
+                0 => None,
+                hours => Some(Duration::from_secs(hours * 60 * 60)),
+            },
+            match trin_config.regossip_interval_secs {
+                0 => None,
+                secs => Some(RegossipConfig {
+                    interval: Duration::from_secs(secs),
+                    sample_size: trin_config.regossip_sample_size,
+                    ..RegossipConfig::default()
+                }),
+            },
+            trin_config.webhook_url.clone().map(|url| WebhookConfig {
+                url,
+                content_types: trin_config.webhook_content_types.clone(),
+                key_prefixes: trin_config.webhook_key_prefixes.clone(),
+            }),
+        )
+        .await
+        .map_err(|err| {
+            // Stop the state network launched above, along with the node's discv5 service
+            state_network_task
+                .iter()
+                .chain(state_background.iter().map(|(_, task)| task))
+                .for_each(JoinHandle::abort);
+            discovery.shutdown();
+            TrinError::Network(err)
+        })?
+    } else {
+        (None, None, None, None, vec![], None)
+    };
+    #[cfg(not(feature = "history"))]
+    let (history_event_tx, history_jsonrpc_tx, history_background, lifetime_stats) =
+        (None, None, vec![], None);
 
-    if let Some(handler) = state_handler {
-        tokio::spawn(handler.handle_client_queries());
-    }
-    if let Some(handler) = history_handler {
-        tokio::spawn(handler.handle_client_queries());
-    }
+    #[cfg(feature = "state")]
+    let state_handler = state_handler.map(|handler| tokio::spawn(handler.handle_client_queries()));
+    #[cfg(not(feature = "state"))]
+    let state_handler = None;
+    #[cfg(feature = "history")]
+    let history_handler =
+        history_handler.map(|handler| tokio::spawn(handler.handle_client_queries()));
+    #[cfg(not(feature = "history"))]
+    let (history_handler, history_network_task) = (None, None);
 
     // Spawn main portal events handler
-    tokio::spawn(async move {
+    let events = tokio::spawn(async move {
         let events = PortalnetEvents::new(
             talk_req_rx,
             history_event_tx,
@@ -133,40 +386,142 @@ pub async fn run_trin(
         events.start().await;
     });
 
-    if let Some(network) = history_network_task {
-        tokio::spawn(async { network.await });
+    let node = NodeHandle {
+        node_id: discovery.local_enr().node_id(),
+        events,
+        history_handler,
+        history_network: history_network_task,
+        state_handler,
+        state_network: state_network_task,
+        background: history_background
+            .into_iter()
+            .chain(state_background)
+            .collect(),
+        discovery: Arc::clone(&discovery),
+        lifetime_stats,
+    };
+    let jsonrpc_txs = JsonRpcChannels {
+        history: history_jsonrpc_tx,
+        state: state_jsonrpc_tx,
+    };
+    Ok((discovery, jsonrpc_txs, node))
+}
+
+/// Returns what trin was built from and with, along with the subnetworks that it runs.
+pub fn build_info(networks: &[String]) -> BuildInfo {
+    let features = [
+        ("history", cfg!(feature = "history")),
+        ("state", cfg!(feature = "state")),
+        ("rocksdb", cfg!(feature = "rocksdb")),
+    ];
+    BuildInfo {
+        client_version: format!("trin v{}", get_trin_version()),
+        version: TRIN_VERSION.to_string(),
+        git_commit: GIT_HASH.to_string(),
+        build_date: BUILD_DATE.to_string(),
+        target: TARGET_TRIPLE.to_string(),
+        features: features
+            .into_iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| feature.to_string())
+            .collect(),
+        networks: networks.to_vec(),
     }
-    if let Some(network) = state_network_task {
-        tokio::spawn(async { network.await });
+}
+
+/// Returns an error if a selected subnetwork was compiled out by its cargo feature.
+fn check_networks_compiled_in(networks: &[String]) -> Result<(), String> {
+    for network in networks {
+        let compiled_in = match network.as_str() {
+            HISTORY_NETWORK => cfg!(feature = "history"),
+            STATE_NETWORK => cfg!(feature = "state"),
+            _ => true,
+        };
+        if !compiled_in {
+            return Err(format!(
+                "The {network} network was selected, but trin was built without the `{network}` feature"
+            ));
+        }
     }
+    Ok(())
+}
 
-    Ok(rpc_handle?)
+/// The channels on which the subnetworks of a node serve JSON-RPC requests, `None` for the
+/// subnetworks that are not selected.
+struct JsonRpcChannels {
+    history: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    state: Option<mpsc::UnboundedSender<StateJsonRpcRequest>>,
 }
 
 async fn launch_jsonrpc_server(
     trin_config: TrinConfig,
     discv5: Arc<Discovery>,
-    history_handler: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
+    jsonrpc_txs: JsonRpcChannels,
+    trusted_provider: Option<TrustedProvider>,
 ) -> Result<ServerHandle, String> {
-    let history_handler = history_handler.ok_or_else(|| {
+    let state_handler = jsonrpc_txs.state;
+    let history_handler = jsonrpc_txs.history.ok_or_else(|| {
         "History network must be available to use IPC transport for JSON-RPC server".to_string()
     })?;
+    let modules = trin_config.exposed_modules(&trin_config.web3_modules);
+    let limits = PayloadLimits {
+        max_request_size: trin_config.web3_max_request_size,
+        max_response_size: trin_config.web3_max_response_size,
+    };
     match trin_config.web3_transport {
         Web3TransportType::IPC => {
             // Launch jsonrpsee server with IPC transport
-            let rpc_handle =
-                JsonRpcServer::run_ipc(trin_config.web3_ipc_path, discv5, history_handler)
-                    .await
-                    .map_err(|e| format!("Launching IPC JSON-RPC server failed: {e:?}"))?;
+            let rpc_handle = JsonRpcServer::run_ipc(
+                trin_config.web3_ipc_path,
+                modules,
+                IpcPermissions {
+                    mode: trin_config.web3_ipc_mode,
+                    group: trin_config.web3_ipc_group,
+                },
+                limits,
+                discv5,
+                history_handler,
+                state_handler,
+                trusted_provider,
+                trin_config.admin_shutdown_token,
+                build_info(&trin_config.networks),
+            )
+            .await
+            .map_err(|e| format!("Launching IPC JSON-RPC server failed: {e:?}"))?;
             info!("IPC JSON-RPC server launched.");
             Ok(rpc_handle)
         }
         Web3TransportType::HTTP => {
             // Launch jsonrpsee server with http and WS transport
-            let rpc_handle =
-                JsonRpcServer::run_http(trin_config.web3_http_address, discv5, history_handler)
-                    .await
-                    .map_err(|e| format!("Launching HTTP JSON-RPC server failed: {e:?}"))?;
+            let extra_listeners = trin_config
+                .web3_http_listeners
+                .iter()
+                .map(|listener| HttpListener {
+                    address: listener.address.clone(),
+                    modules: trin_config.exposed_modules(&listener.modules),
+                })
+                .collect();
+            let tls = trin_config.web3_tls_cert.zip(trin_config.web3_tls_key).map(
+                |(cert_path, key_path)| TlsConfig {
+                    cert_path,
+                    key_path,
+                },
+            );
+            let rpc_handle = JsonRpcServer::run_http(
+                trin_config.web3_http_address,
+                modules,
+                extra_listeners,
+                tls,
+                limits,
+                discv5,
+                history_handler,
+                state_handler,
+                trusted_provider,
+                trin_config.admin_shutdown_token,
+                build_info(&trin_config.networks),
+            )
+            .await
+            .map_err(|e| format!("Launching HTTP JSON-RPC server failed: {e:?}"))?;
             info!("HTTP JSON-RPC server launched.");
             Ok(rpc_handle)
         }
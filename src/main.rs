@@ -1,25 +1,162 @@
 #![warn(clippy::unwrap_used)]
 
-use tracing::error;
-use trin_types::{cli::TrinConfig, provider::TrustedProvider};
-use trin_utils::log::init_tracing_logger;
+use portalnet::{lifetime_stats::find_lifetime_stats, utils::db::get_root_path};
+use serde_json::json;
+use tracing::info;
+use trin_types::{
+    cli::{TrinCommand, TrinConfig},
+    provider::TrustedProvider,
+};
+use trin_utils::log::{init_stderr_tracing_logger, init_tracing_logger};
 
-use trin::run_trin;
+use trin::{build_info, check::run_check, key_rotation::rotate_key, run_trin, TrinError};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_tracing_logger();
+async fn main() {
+    if let Err(err) = run().await {
+        eprintln!("Error: {}", format_error(err.as_ref()));
+        std::process::exit(1);
+    }
+}
+
+/// Formats an error followed by the chain of errors that caused it, one per line.
+fn format_error(err: &dyn std::error::Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message.push_str(&format!("\n  Caused by: {err}"));
+        source = err.source();
+    }
+    message
+}
+
+async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let trin_config = TrinConfig::from_cli();
-    let trusted_provider = TrustedProvider::from_trin_config(&trin_config);
-    let rpc_handle = run_trin(trin_config, trusted_provider).await?;
+    // The report of --check is the only output on stdout, so that it can be parsed.
+    if trin_config.check {
+        init_stderr_tracing_logger(trin_config.log_filter.as_deref());
+    } else {
+        init_tracing_logger(trin_config.log_filter.as_deref());
+    }
+    match trin_config.command {
+        Some(TrinCommand::Version { json }) => {
+            print_build_info(&trin_config, json)?;
+            return Ok(());
+        }
+        Some(TrinCommand::RotateKey { keep_content }) => {
+            print_key_rotation(&trin_config, keep_content)?;
+            return Ok(());
+        }
+        Some(TrinCommand::Stats { json }) => {
+            print_lifetime_stats(&trin_config, json)?;
+            return Ok(());
+        }
+        None => {}
+    }
+    if trin_config.check {
+        let report = run_check(&trin_config).await;
+        println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let trusted_provider =
+        TrustedProvider::from_trin_config(&trin_config).map_err(TrinError::TrustedProvider)?;
+    let trin_handle = run_trin(trin_config, trusted_provider).await?;
 
-    tokio::signal::ctrl_c()
-        .await
-        .expect("failed to pause until ctrl-c");
+    // The RPC server is stopped by admin_shutdown, which then shuts down like a signal does.
+    tokio::select! {
+        _ = shutdown_signal() => info!("Shutdown signal received"),
+        _ = trin_handle.rpc_handle.clone().stopped() => {}
+    }
+
+    // Stopping the nodes also saves their lifetime statistics.
+    trin_handle.stop();
 
-    if let Err(err) = rpc_handle.stop() {
-        error!(err = %err, "Failed to close RPC server")
+    Ok(())
+}
+
+/// Prints what trin was built from and with, as JSON or as one line per item, for
+/// `trin version`.
+fn print_build_info(trin_config: &TrinConfig, json: bool) -> serde_json::Result<()> {
+    let build_info = build_info(&trin_config.networks);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&build_info)?);
+        return Ok(());
     }
+    println!("{}", build_info.client_version);
+    println!("Git commit: {}", build_info.git_commit);
+    println!("Build date: {}", build_info.build_date);
+    println!("Target: {}", build_info.target);
+    println!("Features: {}", build_info.features.join(", "));
+    Ok(())
+}
 
+fn print_key_rotation(trin_config: &TrinConfig, keep_content: bool) -> anyhow::Result<()> {
+    let rotation = rotate_key(trin_config, keep_content)?;
+    println!("Old node id: {}", rotation.old_node_id);
+    println!("New node id: {}", rotation.new_node_id);
+    for dir in &rotation.moved_dirs {
+        println!("Moved data to: {}", dir.display());
+    }
+    if !keep_content {
+        println!(
+            "Dropped {} content items outside the new radius",
+            rotation.pruned
+        );
+    }
+    println!("Saved new private key to: {}", rotation.key_path.display());
+    println!("New ENR: {}", rotation.enr.to_base64());
+    println!(
+        "Start trin with --unsafe-private-key set to the key in that file. Peers know the node by its new ENR only, so publish the new ENR wherever the old one was shared."
+    );
+    Ok(())
+}
+
+fn print_lifetime_stats(trin_config: &TrinConfig, json: bool) -> anyhow::Result<()> {
+    let root_path = get_root_path(trin_config.data_dir.as_deref())?;
+    let node_stats = find_lifetime_stats(&root_path)?;
+    if json {
+        let node_stats: Vec<serde_json::Value> = node_stats
+            .iter()
+            .map(|(dir, stats)| json!({ "dataDir": dir, "stats": stats }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&node_stats)?);
+        return Ok(());
+    }
+    if node_stats.is_empty() {
+        println!("No statistics found under {}", root_path.display());
+    }
+    for (dir, stats) in node_stats {
+        println!("{}", dir.display());
+        println!("  Uptime: {}", format_uptime(stats.uptime_secs));
+        println!("  Content served: {}", stats.content_served);
+        println!("  Uploaded: {} KB", stats.bytes_uploaded / 1024);
+        println!("  Downloaded: {} KB", stats.bytes_downloaded / 1024);
+        println!("  Offers accepted: {}", stats.offers_accepted);
+    }
     Ok(())
 }
+
+fn format_uptime(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    format!("{days}d {hours}h {minutes}m")
+}
+
+/// Waits for ctrl-c or, on unix, SIGTERM.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sigterm = signal(SignalKind::terminate()).expect("failed to listen for SIGTERM");
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.expect("failed to pause until ctrl-c"),
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("failed to pause until ctrl-c");
+}
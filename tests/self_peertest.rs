@@ -5,15 +5,20 @@ mod test {
         thread, time,
     };
 
+    use ethportal_api::{HistoryContentKey, HistoryContentValue, HistoryNetworkApiClient};
     use ethportal_peertest as peertest;
+    use ethportal_peertest::constants::HISTORY_CONTENT_VALUE;
+    use ethportal_peertest::framework::{run_scenarios, Scenario};
+    use serde_json::json;
     use trin_types::cli::{TrinConfig, DEFAULT_WEB3_IPC_PATH};
+    use trin_types::content_value::PossibleHistoryContentValue;
     use trin_types::provider::TrustedProvider;
     use trin_utils::log::init_tracing_logger;
 
     // Logs don't show up when trying to use test_log here, maybe because of multi_thread
     #[tokio::test(flavor = "multi_thread")]
     async fn test_launches() {
-        init_tracing_logger();
+        init_tracing_logger(None);
 
         // Run a client, as a buddy peer for ping tests, etc.
         let peertest = peertest::launch_peertest_nodes(2).await;
@@ -35,6 +40,8 @@ mod test {
                 external_addr.as_str(),
                 "--web3-ipc-path",
                 DEFAULT_WEB3_IPC_PATH,
+                "--web3-modules",
+                "admin,debug,discv5,eth,history,state,web3",
                 "--ephemeral",
                 "--discovery-port",
                 test_discovery_port.to_string().as_ref(),
@@ -48,25 +55,36 @@ mod test {
         let server = peertest::setup_mock_trusted_http_server();
         let trusted_provider = TrustedProvider {
             http: ureq::post(&server.url("/")),
+            jwt_auth: None,
         };
-        let test_client_rpc_handle = trin::run_trin(trin_config, trusted_provider).await.unwrap();
+        let test_client_rpc_handle = trin::run_trin(trin_config, Some(trusted_provider))
+            .await
+            .unwrap()
+            .rpc_handle;
         peertest::scenarios::paginate::test_paginate_local_storage(&peertest).await;
         let target = reth_ipc::client::IpcClientBuilder::default()
             .build(DEFAULT_WEB3_IPC_PATH)
             .await
             .unwrap();
         peertest::scenarios::basic::test_web3_client_version(&target).await;
+        peertest::scenarios::basic::test_web3_build_info(&target).await;
+        peertest::scenarios::basic::test_eth_send_raw_transaction(&target).await;
+        peertest::scenarios::basic::test_eth_gas_price(&target).await;
         peertest::scenarios::basic::test_discv5_node_info(&peertest).await;
         peertest::scenarios::basic::test_discv5_routing_table_info(&target).await;
         peertest::scenarios::basic::test_history_radius(&target).await;
         peertest::scenarios::basic::test_history_ping(&target, &peertest).await;
         peertest::scenarios::basic::test_history_find_nodes(&target, &peertest).await;
         peertest::scenarios::basic::test_history_find_nodes_zero_distance(&target, &peertest).await;
+        peertest::scenarios::basic::test_history_summary(&target).await;
         peertest::scenarios::basic::test_history_store(&target).await;
+        peertest::scenarios::basic::test_debug_get_raw_header(&target).await;
         peertest::scenarios::basic::test_history_routing_table_info(&target).await;
         peertest::scenarios::basic::test_history_local_content_absent(&target).await;
         peertest::scenarios::offer_accept::test_unpopulated_offer(&peertest, &target).await;
         peertest::scenarios::offer_accept::test_populated_offer(&peertest, &target).await;
+        peertest::scenarios::basic::test_admin_peer_stats(&target, &peertest).await;
+        peertest::scenarios::basic::test_admin_lifetime_stats(&target).await;
         peertest::scenarios::find::test_recursive_find_nodes_self(&peertest).await;
         peertest::scenarios::find::test_recursive_find_nodes_peer(&peertest).await;
         peertest::scenarios::find::test_recursive_find_nodes_random(&peertest).await;
@@ -78,4 +96,80 @@ mod test {
         peertest.exit_all_nodes();
         test_client_rpc_handle.stop().unwrap();
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scenarios() {
+        init_tracing_logger(None);
+
+        let content_value: HistoryContentValue =
+            serde_json::from_value(json!(HISTORY_CONTENT_VALUE)).unwrap();
+        let lookup_key: HistoryContentKey = serde_json::from_value(json!(
+            "0x0035b11b918355b1ef9c5db810302ebad0bf2544255b530cdce90674d5887bb286"
+        ))
+        .unwrap();
+        let offer_key: HistoryContentKey = serde_json::from_value(json!(
+            "0x0045b11b918355b1ef9c5db810302ebad0bf2544255b530cdce90674d5887bb286"
+        ))
+        .unwrap();
+
+        let (preload_key, expected_value) = (lookup_key.clone(), content_value.clone());
+        let lookup = Scenario::new("recursive find content from bootnode", move |peertest| {
+            let (content_key, content_value) = (lookup_key.clone(), expected_value.clone());
+            async move {
+                let result = peertest.nodes[0]
+                    .ipc_client
+                    .recursive_find_content(content_key, None)
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    result,
+                    PossibleHistoryContentValue::ContentPresent(content_value)
+                );
+            }
+        })
+        .preload(0, preload_key, content_value.clone());
+
+        let offer = Scenario::new("populated offer to bootnode", move |peertest| {
+            let (content_key, content_value) = (offer_key.clone(), content_value.clone());
+            async move {
+                peertest.nodes[0]
+                    .ipc_client
+                    .offer(
+                        peertest.bootnode.enr.clone(),
+                        content_key.clone(),
+                        Some(content_value.clone().into()),
+                    )
+                    .await
+                    .unwrap();
+                // The offered content is transferred to the bootnode in the background.
+                loop {
+                    let result = peertest
+                        .bootnode
+                        .ipc_client
+                        .local_content(content_key.clone())
+                        .await;
+                    if let Ok(PossibleHistoryContentValue::ContentPresent(value)) = result {
+                        assert_eq!(value, content_value);
+                        break;
+                    }
+                    tokio::time::sleep(time::Duration::from_millis(500)).await;
+                }
+            }
+        })
+        .nodes(3);
+
+        run_scenarios(vec![lookup, offer]).await.unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_large_content_transfers() {
+        init_tracing_logger(None);
+
+        run_scenarios(vec![
+            peertest::scenarios::large_content::offer_epoch_accumulators(),
+            peertest::scenarios::large_content::offer_receipts(),
+        ])
+        .await
+        .unwrap();
+    }
 }
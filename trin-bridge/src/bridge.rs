@@ -1,29 +1,31 @@
 use crate::cli::BridgeMode;
 use crate::constants::PANDAOPS_URL;
+use crate::gossip::Gossiper;
+use crate::head::ChainHead;
+use crate::progress::BackfillProgress;
 use crate::utils::get_ranges;
 use anyhow::{anyhow, bail};
 use ethereum_types::H256;
-use ethportal_api::jsonrpsee::http_client::HttpClient;
-use ethportal_api::HistoryNetworkApiClient;
 use serde_json::{json, Value};
 use ssz::Decode;
 use std::env;
 use std::fs;
 use std::ops::Range;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time;
 use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
 use tracing::{debug, info, warn};
 use trin_types::content_key::{
-    BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey, HistoryContentKey,
+    BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EphemeralHeaderKey, EpochAccumulatorKey,
+    HistoryContentKey,
 };
 use trin_types::content_value::HistoryContentValue;
 use trin_types::execution::accumulator::EpochAccumulator;
 use trin_types::execution::block_body::{BlockBody, EncodableHeaderList};
 use trin_types::execution::header::{
-    AccumulatorProof, BlockHeaderProof, FullHeader, FullHeaderBatch, Header, HeaderWithProof,
-    SszNone,
+    BlockHeaderProof, FullHeader, FullHeaderBatch, Header, HeaderWithProof, SszNone,
 };
 use trin_types::execution::receipts::Receipts;
 use trin_types::jsonrpc::params::Params;
@@ -35,27 +37,30 @@ use trin_validation::oracle::HeaderOracle;
 
 pub struct Bridge {
     pub mode: BridgeMode,
-    pub portal_clients: Vec<HttpClient>,
+    pub gossiper: Arc<Gossiper>,
     pub header_oracle: HeaderOracle,
     pub epoch_acc_path: PathBuf,
+    pub chain_head: ChainHead,
+    /// File that backfill progress is persisted to, if any.
+    pub progress_path: Option<PathBuf>,
 }
 
 // todo: calculate / test optimal saturation delay
 const HEADER_SATURATION_DELAY: u64 = 10; // seconds
-const LATEST_BLOCK_POLL_RATE: u64 = 5; // seconds
 const BACKFILL_THREAD_COUNT: usize = 8;
 const EPOCH_SIZE: u64 = EPOCH_SIZE_USIZE as u64;
 impl Bridge {
     // Devops nodes don't have websockets available, so we can't actually poll the latest block.
-    // Instead we loop on a short interval and fetch the latest blocks not yet served.
+    // Instead we loop on a short interval and fetch the latest blocks not yet served, either from
+    // the provider or from the light client.
     // We only need one thread for this mode
     pub async fn launch_latest(&self) {
-        let mut block_index = get_latest_block_number().await.expect(
-            "Error launching bridge in latest mode. Unable to get latest block from provider.",
+        let mut block_index = self.chain_head.latest_block_number().await.expect(
+            "Error launching bridge in latest mode. Unable to get latest block from chain head.",
         );
         loop {
-            sleep(Duration::from_secs(LATEST_BLOCK_POLL_RATE)).await;
-            let latest_block = match get_latest_block_number().await {
+            sleep(self.chain_head.poll_rate()).await;
+            let latest_block = match self.chain_head.latest_block_number().await {
                 Ok(val) => val,
                 Err(msg) => {
                     warn!("error getting latest block, skipping iteration: {msg:?}");
@@ -76,8 +81,16 @@ impl Bridge {
                         continue;
                     }
                 };
+                if let Err(msg) = self.chain_head.verify_headers(&full_headers).await {
+                    warn!("error verifying headers, skipping iteration: {msg:?}");
+                    block_index = latest_block + 1;
+                    continue;
+                }
                 // epoch index is None here bc it's irrelevant for post-merge headers
                 let mut gossip_batch = GossipBatch::new(block_range_to_gossip, None, full_headers);
+                // Gossip the new chain tip headers first, so that nodes learn of new blocks with
+                // as little latency as possible.
+                self.gossip_ephemeral_headers(&mut gossip_batch).await;
                 if let Err(msg) = self.gossip_headers(&mut gossip_batch).await {
                     warn!("error gossiping headers, skipping iteration: {msg:?}");
                     block_index = latest_block + 1;
@@ -93,15 +106,26 @@ impl Bridge {
                     warn!("error gossiping receipts: {err:?}");
                 };
                 gossip_batch.display_stats();
+                self.gossiper.display_stats();
+                if !gossip_batch.is_complete() {
+                    warn!(
+                        "Some content in range {:?} reached no peer, and is not gossiped again",
+                        gossip_batch.range
+                    );
+                }
                 block_index = gossip_batch.range.end;
             }
         }
     }
 
     pub async fn launch_backfill(&self, starting_epoch: Option<u64>) {
-        let latest_block = get_latest_block_number().await.expect(
-            "Error launching bridge in backfill mode. Unable to get latest block from provider.",
+        let latest_block = self.chain_head.latest_block_number().await.expect(
+            "Error launching bridge in backfill mode. Unable to get latest block from chain head.",
         );
+        let mut progress = match &self.progress_path {
+            Some(path) => BackfillProgress::load(path).expect("Error loading backfill progress."),
+            None => BackfillProgress::default(),
+        };
         let mut epoch_index = match starting_epoch {
             Some(val) => {
                 if val * EPOCH_SIZE > latest_block {
@@ -112,48 +136,75 @@ impl Bridge {
                 }
                 val
             }
-            None => 0,
+            None => {
+                if progress.next_epoch > 0 {
+                    info!("Resuming backfill from epoch {}", progress.next_epoch);
+                }
+                progress.next_epoch
+            }
         };
+        // Retry the epochs that failed in earlier runs first.
+        for failed_epoch in progress.failed_epochs.clone() {
+            info!("Retrying failed backfill of epoch {failed_epoch}");
+            let complete = self.backfill_epoch(failed_epoch).await;
+            progress.record_epoch(failed_epoch, complete);
+            self.save_backfill_progress(&progress);
+        }
         let current_epoch = latest_block / EPOCH_SIZE;
         while epoch_index < current_epoch {
-            let start = epoch_index * EPOCH_SIZE;
-            let end = start + EPOCH_SIZE;
-            // Using epoch_size chunks & epoch boundaries ensures that every
-            // "chunk" shares an epoch accumulator avoiding the need to
-            // look up the epoch acc on a header by header basis
-            let block_range_to_gossip = Range { start, end };
-            let full_headers = match Bridge::get_headers(&block_range_to_gossip).await {
-                Ok(val) => val,
-                Err(msg) => {
-                    warn!("Error fetching headers in range: {block_range_to_gossip:?} - {msg:?}. Skipping iteration.");
-                    epoch_index += 1;
-                    continue;
-                }
-            };
-            let mut gossip_batch = GossipBatch::new(
-                block_range_to_gossip.clone(),
-                Some(epoch_index),
-                full_headers,
-            );
-            if let Err(msg) = self.gossip_headers(&mut gossip_batch).await {
-                warn!("Error gossiping headers in range: {block_range_to_gossip:?} - {msg:?}. Skipping iteration.");
-                epoch_index += 1;
-                continue;
-            };
-            // Sleep for 5 seconds to allow headers to saturate network,
-            // since they must be available for body / receipt validation
-            sleep(Duration::from_secs(HEADER_SATURATION_DELAY)).await;
-            if let Err(err) = self.serve_bodies(&mut gossip_batch).await {
-                warn!("error gossiping bodies: {err:?}");
-            };
-            if let Err(err) = self.serve_receipts(&mut gossip_batch).await {
-                warn!("error gossiping receipts: {err:?}");
-            };
-            gossip_batch.display_stats();
+            let complete = self.backfill_epoch(epoch_index).await;
+            progress.record_epoch(epoch_index, complete);
+            self.save_backfill_progress(&progress);
             epoch_index += 1;
         }
     }
 
+    /// Gossips all content in an epoch, and returns whether all of it was gossiped.
+    async fn backfill_epoch(&self, epoch_index: u64) -> bool {
+        let start = epoch_index * EPOCH_SIZE;
+        let end = start + EPOCH_SIZE;
+        // Using epoch_size chunks & epoch boundaries ensures that every
+        // "chunk" shares an epoch accumulator avoiding the need to
+        // look up the epoch acc on a header by header basis
+        let block_range_to_gossip = Range { start, end };
+        let full_headers = match Bridge::get_headers(&block_range_to_gossip).await {
+            Ok(val) => val,
+            Err(msg) => {
+                warn!("Error fetching headers in range: {block_range_to_gossip:?} - {msg:?}. Skipping iteration.");
+                return false;
+            }
+        };
+        let mut gossip_batch = GossipBatch::new(
+            block_range_to_gossip.clone(),
+            Some(epoch_index),
+            full_headers,
+        );
+        if let Err(msg) = self.gossip_headers(&mut gossip_batch).await {
+            warn!("Error gossiping headers in range: {block_range_to_gossip:?} - {msg:?}. Skipping iteration.");
+            return false;
+        };
+        // Sleep for 5 seconds to allow headers to saturate network,
+        // since they must be available for body / receipt validation
+        sleep(Duration::from_secs(HEADER_SATURATION_DELAY)).await;
+        if let Err(err) = self.serve_bodies(&mut gossip_batch).await {
+            warn!("error gossiping bodies: {err:?}");
+        };
+        if let Err(err) = self.serve_receipts(&mut gossip_batch).await {
+            warn!("error gossiping receipts: {err:?}");
+        };
+        gossip_batch.display_stats();
+        self.gossiper.display_stats();
+        gossip_batch.is_complete()
+    }
+
+    fn save_backfill_progress(&self, progress: &BackfillProgress) {
+        if let Some(path) = &self.progress_path {
+            if let Err(err) = progress.save(path) {
+                warn!("Error saving backfill progress: {err:?}");
+            }
+        }
+    }
+
     async fn gossip_headers(&self, gossip_batch: &mut GossipBatch) -> anyhow::Result<()> {
         info!("Gossiping headers in range: {:?}", gossip_batch.range);
         if gossip_batch.range.end <= MERGE_BLOCK_NUMBER {
@@ -193,7 +244,9 @@ impl Bridge {
             );
 
             // Gossip HeaderWithProof
-            if Bridge::gossip_content(self.portal_clients.clone(), content_key, content_value)
+            if self
+                .gossiper
+                .gossip(content_key, content_value)
                 .await
                 .is_ok()
             {
@@ -217,7 +270,9 @@ impl Bridge {
                 "Gossip: Block #{:?} HeaderWithProof",
                 full_header.header.number
             );
-            if Bridge::gossip_content(self.portal_clients.clone(), content_key, content_value)
+            if self
+                .gossiper
+                .gossip(content_key, content_value)
                 .await
                 .is_ok()
             {
@@ -227,10 +282,40 @@ impl Bridge {
         Ok(())
     }
 
+    /// Gossip the headers of a batch of blocks near the chain tip as ephemeral headers.
+    async fn gossip_ephemeral_headers(&self, gossip_batch: &mut GossipBatch) {
+        for full_header in gossip_batch.full_headers.iter() {
+            let content_key = HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+                block_hash: full_header.header.hash().to_fixed_bytes(),
+            });
+            let header_with_proof = HeaderWithProof {
+                header: full_header.header.clone(),
+                proof: BlockHeaderProof::None(SszNone { value: None }),
+            };
+            let content_value = HistoryContentValue::BlockHeaderWithProof(header_with_proof);
+            debug!(
+                "Gossip: Block #{:?} EphemeralHeader",
+                full_header.header.number
+            );
+            if self
+                .gossiper
+                .gossip(content_key, content_value)
+                .await
+                .is_ok()
+            {
+                gossip_batch.ephemeral_header_count += 1;
+            }
+        }
+    }
+
     /// Attempt to lookup an epoch accumulator from local portal-accumulators path provided via cli
     /// arg. Fallback to retrieving epoch acc from network if unable to find epoch acc locally.
     async fn get_epoch_acc(&self, epoch_index: u64) -> anyhow::Result<EpochAccumulator> {
-        let epoch_hash = self.header_oracle.master_acc.historical_epochs[epoch_index as usize];
+        let epoch_hash = self
+            .header_oracle
+            .master_acc
+            .epoch_root(epoch_index as usize)
+            .ok_or_else(|| anyhow!("Epoch #{epoch_index} is missing from master accumulator"))?;
         let epoch_hash_pretty = hex_encode(epoch_hash);
         let epoch_hash_pretty = epoch_hash_pretty.trim_start_matches("0x");
         let epoch_acc_path = format!(
@@ -248,8 +333,7 @@ impl Bridge {
         // Gossip epoch acc to network if found locally
         let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash });
         let content_value = HistoryContentValue::EpochAccumulator(local_epoch_acc.clone());
-        let _ =
-            Bridge::gossip_content(self.portal_clients.clone(), content_key, content_value).await;
+        let _ = self.gossiper.gossip(content_key, content_value).await;
         Ok(local_epoch_acc)
     }
 
@@ -266,7 +350,7 @@ impl Bridge {
             let futures: Vec<JoinHandle<anyhow::Result<()>>> = (0..num_of_tasks)
                 .map(|t| {
                     Bridge::spawn_body_task(
-                        self.portal_clients.clone(),
+                        Arc::clone(&self.gossiper),
                         gossip_batch.full_headers[header_index + t].clone(),
                     )
                 })
@@ -282,12 +366,12 @@ impl Bridge {
     }
 
     fn spawn_body_task(
-        portal_clients: Vec<HttpClient>,
+        gossiper: Arc<Gossiper>,
         full_header: FullHeader,
     ) -> JoinHandle<anyhow::Result<()>> {
-        tokio::spawn(async move {
-            Bridge::construct_and_gossip_block_body(portal_clients, full_header).await
-        })
+        tokio::spawn(
+            async move { Bridge::construct_and_gossip_block_body(gossiper, full_header).await },
+        )
     }
 
     /// Fetch batch of Receipts from provider & gossip them to the network
@@ -303,7 +387,7 @@ impl Bridge {
             let futures: Vec<JoinHandle<anyhow::Result<()>>> = (0..num_of_tasks)
                 .map(|t| {
                     Bridge::spawn_receipt_task(
-                        self.portal_clients.clone(),
+                        Arc::clone(&self.gossiper),
                         gossip_batch.full_headers[header_index + t].clone(),
                     )
                 })
@@ -319,16 +403,16 @@ impl Bridge {
     }
 
     fn spawn_receipt_task(
-        portal_clients: Vec<HttpClient>,
+        gossiper: Arc<Gossiper>,
         full_header: FullHeader,
     ) -> JoinHandle<anyhow::Result<()>> {
-        tokio::spawn(async move {
-            Bridge::construct_and_gossip_receipt(portal_clients, full_header).await
-        })
+        tokio::spawn(
+            async move { Bridge::construct_and_gossip_receipt(gossiper, full_header).await },
+        )
     }
 
     async fn construct_and_gossip_receipt(
-        portal_clients: Vec<HttpClient>,
+        gossiper: Arc<Gossiper>,
         full_header: FullHeader,
     ) -> anyhow::Result<()> {
         let receipts = match full_header.txs.len() {
@@ -338,9 +422,14 @@ impl Bridge {
             _ => Bridge::get_trusted_receipts(full_header.tx_hashes.hashes).await?,
         };
 
-        // Validate Receipts
+        // Validate Receipts. Receipts that don't match the header, e.g. because of a buggy
+        // provider, must never be gossiped.
         let receipts_root = receipts.root()?;
         if receipts_root != full_header.header.receipts_root {
+            warn!(
+                "Refusing to gossip receipts for block #{}: provider returned receipts that don't match the header",
+                full_header.header.number
+            );
             bail!(
                 "Receipts root doesn't match header receipts root: {receipts_root:?} - {:?}",
                 full_header.header.receipts_root
@@ -351,7 +440,7 @@ impl Bridge {
         });
         let content_value = HistoryContentValue::Receipts(receipts);
         debug!("Gossip: Block #{:?} Receipts", full_header.header.number,);
-        Bridge::gossip_content(portal_clients, content_key, content_value).await
+        gossiper.gossip(content_key, content_value).await
     }
 
     async fn get_trusted_receipts(tx_hashes: Vec<H256>) -> anyhow::Result<Receipts> {
@@ -370,7 +459,7 @@ impl Bridge {
     }
 
     async fn construct_and_gossip_block_body(
-        portal_clients: Vec<HttpClient>,
+        gossiper: Arc<Gossiper>,
         full_header: FullHeader,
     ) -> anyhow::Result<()> {
         let uncle_headers = match full_header.uncles.len() {
@@ -405,7 +494,7 @@ impl Bridge {
         });
         let content_value = HistoryContentValue::BlockBody(block_body);
         debug!("Gossip: Block #{:?} BlockBody", full_header.header.number);
-        Bridge::gossip_content(portal_clients, content_key, content_value).await
+        gossiper.gossip(content_key, content_value).await
     }
 
     async fn get_trusted_uncles(hashes: Vec<H256>) -> anyhow::Result<Vec<Header>> {
@@ -438,32 +527,13 @@ impl Bridge {
         header: &Header,
         epoch_acc: &EpochAccumulator,
     ) -> anyhow::Result<(HistoryContentKey, HeaderWithProof)> {
-        let proof = MasterAccumulator::construct_proof(header, epoch_acc)?;
-        let proof = BlockHeaderProof::AccumulatorProof(AccumulatorProof { proof });
-        let hwp = HeaderWithProof {
-            header: header.clone(),
-            proof,
-        };
+        let hwp = MasterAccumulator::construct_header_with_proof(header, epoch_acc)?;
         let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
             block_hash: header.hash().to_fixed_bytes(),
         });
         Ok((content_key, hwp))
     }
 
-    /// Gossip any given content key / value to the history network.
-    async fn gossip_content(
-        portal_clients: Vec<HttpClient>,
-        content_key: HistoryContentKey,
-        content_value: HistoryContentValue,
-    ) -> anyhow::Result<()> {
-        for client in portal_clients {
-            client
-                .gossip(content_key.clone(), content_value.clone())
-                .await?;
-        }
-        Ok(())
-    }
-
     /// Fetch batch of headers from Provider
     async fn get_headers(range: &Range<u64>) -> anyhow::Result<Vec<FullHeader>> {
         let mut headers: Vec<FullHeader> = vec![];
@@ -510,6 +580,7 @@ struct GossipBatch {
     range: Range<u64>,
     epoch_index: Option<u64>,
     full_headers: Vec<FullHeader>,
+    ephemeral_header_count: u64,
     hwp_count: u64,
     bodies_count: u64,
     receipts_count: u64,
@@ -522,6 +593,7 @@ impl GossipBatch {
             range,
             epoch_index,
             full_headers,
+            ephemeral_header_count: 0,
             hwp_count: 0,
             bodies_count: 0,
             receipts_count: 0,
@@ -529,16 +601,28 @@ impl GossipBatch {
         }
     }
 
+    /// Returns whether all content in the batch reached at least one peer. Content only counts
+    /// once an offer or gossip of it succeeded, so a batch whose offers all failed is incomplete.
+    fn is_complete(&self) -> bool {
+        let count = self.full_headers.len() as u64;
+        // Headers are counted whether they are gossiped with a proof or, post-merge, without one.
+        self.hwp_count == count && self.bodies_count == count && self.receipts_count == count
+    }
+
     fn display_stats(&self) {
         info!(
-            "Header Group: Range {:?} - HWP: {:?} - Bodies: {:?} - Receipts: {:?}",
-            self.range, self.hwp_count, self.bodies_count, self.receipts_count
+            "Header Group: Range {:?} - Ephemeral: {:?} - HWP: {:?} - Bodies: {:?} - Receipts: {:?}",
+            self.range,
+            self.ephemeral_header_count,
+            self.hwp_count,
+            self.bodies_count,
+            self.receipts_count
         );
         info!("Group took: {:?}", time::Instant::now() - self.start_time);
     }
 }
 
-async fn get_latest_block_number() -> anyhow::Result<u64> {
+pub(crate) async fn get_latest_block_number() -> anyhow::Result<u64> {
     let params = Params::Array(vec![json!("latest"), json!(false)]);
     let method = "eth_getBlockByNumber".to_string();
     let request = json_request(method, params, 1);
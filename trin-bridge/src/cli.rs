@@ -1,6 +1,11 @@
+use std::fmt;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
 use structopt::StructOpt;
+use trin_types::enr::Enr;
+
+use crate::constants::DEFAULT_CONSENSUS_RPC;
 
 // max value of 16 b/c...
 // - reliably calculate spaced private keys in a reasonable time
@@ -35,6 +40,60 @@ pub struct BridgeConfig {
         parse(from_os_str)
     )]
     pub epoch_acc_path: PathBuf,
+
+    #[structopt(
+        long = "head-source",
+        default_value = "provider",
+        help = "How to follow the chain head: 'provider' polls the trusted provider, 'light-client' follows consensus-layer light client updates"
+    )]
+    pub head_source: HeadSource,
+
+    #[structopt(
+        long = "consensus-rpc",
+        default_value(DEFAULT_CONSENSUS_RPC),
+        help = "Beacon API that serves light client updates, used with '--head-source light-client'"
+    )]
+    pub consensus_rpc: String,
+
+    #[structopt(
+        long = "light-client-checkpoint",
+        help = "Trusted beacon block root to bootstrap the light client from. Defaults to the light client's built-in checkpoint"
+    )]
+    pub light_client_checkpoint: Option<String>,
+
+    #[structopt(
+        long = "gossip-strategy",
+        default_value = "neighborhood",
+        help = "How to choose the nodes that content is offered to: 'neighborhood' (the trin nodes' own gossip), 'closest' (the closest nodes to the content id), 'random' (a random sample of the network) or 'enrs' (the nodes given by --gossip-enrs)"
+    )]
+    pub gossip_strategy: GossipStrategy,
+
+    #[structopt(
+        long = "gossip-target-count",
+        default_value = "8",
+        help = "Number of nodes that content is offered to, with the 'closest' and 'random' gossip strategies"
+    )]
+    pub gossip_target_count: usize,
+
+    #[structopt(
+        long = "gossip-enrs",
+        help = "Comma-separated list of ENRs that content is offered to, with the 'enrs' gossip strategy",
+        use_delimiter = true
+    )]
+    pub gossip_enrs: Vec<Enr>,
+
+    #[structopt(
+        long = "data-dir",
+        help = "Directory to persist backfill progress to, so that an interrupted backfill resumes where it left off",
+        parse(from_os_str)
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    #[structopt(
+        long = "enable-metrics-with-url",
+        help = "Address to serve prometheus metrics on, e.g. '127.0.0.1:9100', including the gossip offers by strategy and outcome"
+    )]
+    pub enable_metrics_with_url: Option<SocketAddr>,
 }
 
 fn check_node_count(val: String) -> Result<(), String> {
@@ -59,6 +118,65 @@ pub enum BridgeMode {
 
 type ParseError = &'static str;
 
+/// Used to help decode cli args identifying how gossip targets are chosen.
+/// - Neighborhood: gossips through the trin nodes, which offer content to interested peers
+/// - Closest: offers content to the closest nodes to the content id
+/// - Random: offers content to a random sample of the network
+/// - Enrs: offers content to an explicit list of nodes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GossipStrategy {
+    Neighborhood,
+    Closest,
+    Random,
+    Enrs,
+}
+
+impl fmt::Display for GossipStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Neighborhood => write!(f, "neighborhood"),
+            Self::Closest => write!(f, "closest"),
+            Self::Random => write!(f, "random"),
+            Self::Enrs => write!(f, "enrs"),
+        }
+    }
+}
+
+impl FromStr for GossipStrategy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "neighborhood" => Ok(GossipStrategy::Neighborhood),
+            "closest" => Ok(GossipStrategy::Closest),
+            "random" => Ok(GossipStrategy::Random),
+            "enrs" => Ok(GossipStrategy::Enrs),
+            _ => Err("Invalid gossip strategy arg"),
+        }
+    }
+}
+
+/// Used to help decode cli args identifying the source of the chain head.
+/// - Provider: polls the trusted provider for the latest block
+/// - LightClient: follows verified light client updates from a beacon API
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HeadSource {
+    Provider,
+    LightClient,
+}
+
+impl FromStr for HeadSource {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "provider" => Ok(HeadSource::Provider),
+            "light-client" => Ok(HeadSource::LightClient),
+            _ => Err("Invalid head source arg"),
+        }
+    }
+}
+
 impl FromStr for BridgeMode {
     type Err = ParseError;
 
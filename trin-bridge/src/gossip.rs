@@ -0,0 +1,185 @@
+use anyhow::{anyhow, bail};
+use ethportal_api::jsonrpsee::http_client::HttpClient;
+use ethportal_api::HistoryNetworkApiClient;
+use prometheus_exporter::prometheus::{
+    opts, register_int_counter_vec, register_int_counter_vec_with_registry, IntCounter, Registry,
+};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+use trin_types::content_key::{HistoryContentKey, OverlayContentKey};
+use trin_types::content_value::HistoryContentValue;
+use trin_types::enr::Enr;
+use trin_types::node_id::NodeId;
+
+use crate::cli::{BridgeConfig, GossipStrategy};
+
+/// Gossips content to the network through the bridge's portal clients, choosing the recipients
+/// according to the configured strategy.
+pub struct Gossiper {
+    portal_clients: Vec<HttpClient>,
+    strategy: GossipStrategy,
+    target_count: usize,
+    enrs: Vec<Enr>,
+    stats: GossipStats,
+}
+
+/// Counts of the offers made by the bridge with its strategy, exported to prometheus with the
+/// strategy as a label, to compare acceptance rates across strategies.
+#[derive(Clone)]
+struct GossipStats {
+    /// Number of peers that content was offered to.
+    offered: IntCounter,
+    /// Number of offers that the peer accepted. Unknown for neighborhood gossip.
+    accepted: IntCounter,
+    /// Number of offers that failed, e.g. because the peer was unreachable.
+    failed: IntCounter,
+}
+
+impl GossipStats {
+    fn new(strategy: &GossipStrategy) -> Self {
+        let offers_options = opts!(
+            "trin_bridge_gossip_offers_total",
+            "count the offers of gossiped content, by gossip strategy and outcome"
+        );
+        let offers_labels = &["strategy", "result"];
+        let offers = register_int_counter_vec!(offers_options.clone(), offers_labels)
+            .unwrap_or_else(|_| {
+                // Only happens when more than one gossiper is created in a process, e.g. in tests.
+                error!("Failed to register prometheus gossip metrics with default registry, creating new");
+                let custom_registry = Registry::new_custom(None, None)
+                    .expect("Prometheus docs don't explain when it might fail to create a custom registry, so... hopefully never");
+                register_int_counter_vec_with_registry!(offers_options, offers_labels, custom_registry)
+                    .expect("a counter can always be added to a new custom registry, without conflict")
+            });
+        let strategy = strategy.to_string();
+        Self {
+            offered: offers.with_label_values(&[&strategy, "offered"]),
+            accepted: offers.with_label_values(&[&strategy, "accepted"]),
+            failed: offers.with_label_values(&[&strategy, "failed"]),
+        }
+    }
+}
+
+impl Gossiper {
+    pub fn new(portal_clients: Vec<HttpClient>, config: &BridgeConfig) -> anyhow::Result<Self> {
+        if portal_clients.is_empty() {
+            bail!("At least one portal client is required to gossip content");
+        }
+        if config.gossip_strategy == GossipStrategy::Enrs && config.gossip_enrs.is_empty() {
+            bail!("The 'enrs' gossip strategy requires the --gossip-enrs flag");
+        }
+        Ok(Self {
+            portal_clients,
+            strategy: config.gossip_strategy.clone(),
+            target_count: config.gossip_target_count,
+            enrs: config.gossip_enrs.clone(),
+            stats: GossipStats::new(&config.gossip_strategy),
+        })
+    }
+
+    /// Gossips content to the recipients chosen by the strategy. Offers to individual
+    /// recipients are spread across the portal clients.
+    ///
+    /// Fails if the content reached no peer: no peer was gossiped to, or every offer failed.
+    pub async fn gossip(
+        &self,
+        content_key: HistoryContentKey,
+        content_value: HistoryContentValue,
+    ) -> anyhow::Result<()> {
+        let targets = match &self.strategy {
+            GossipStrategy::Neighborhood => {
+                let mut total_peers = 0;
+                for client in self.portal_clients.iter() {
+                    let peers = client
+                        .gossip(content_key.clone(), content_value.clone().into())
+                        .await?;
+                    self.stats.offered.inc_by(u64::from(peers));
+                    total_peers += peers;
+                }
+                if total_peers == 0 {
+                    bail!("No peers to gossip content key to: {content_key}");
+                }
+                return Ok(());
+            }
+            GossipStrategy::Closest => self.find_nodes(NodeId(content_key.content_id())).await?,
+            GossipStrategy::Random => self.find_nodes(NodeId(rand::random())).await?,
+            GossipStrategy::Enrs => self.enrs.clone(),
+        };
+        if targets.is_empty() {
+            bail!("No gossip targets found for content key: {content_key}");
+        }
+
+        let offers: Vec<JoinHandle<bool>> = targets
+            .into_iter()
+            .enumerate()
+            .map(|(i, enr)| {
+                let client = self.portal_clients[i % self.portal_clients.len()].clone();
+                let stats = self.stats.clone();
+                let content_key = content_key.clone();
+                let content_value = content_value.clone();
+                tokio::spawn(async move {
+                    stats.offered.inc();
+                    match client
+                        .offer(enr, content_key, Some(content_value.into()))
+                        .await
+                    {
+                        Ok(accept_info) => {
+                            if accept_info.content_keys.get(0).unwrap_or(false) {
+                                stats.accepted.inc();
+                            }
+                            true
+                        }
+                        Err(err) => {
+                            debug!("Gossip offer failed: {err:?}");
+                            stats.failed.inc();
+                            false
+                        }
+                    }
+                })
+            })
+            .collect();
+        let offer_count = offers.len();
+        let mut successful_offers = 0;
+        for offer in offers {
+            if offer.await? {
+                successful_offers += 1;
+            }
+        }
+        if successful_offers == 0 {
+            bail!("All {offer_count} offers of content key {content_key} failed");
+        }
+        Ok(())
+    }
+
+    /// Returns the closest `target_count` nodes to `node_id` that are known to the network.
+    async fn find_nodes(&self, node_id: NodeId) -> anyhow::Result<Vec<Enr>> {
+        let client = &self.portal_clients[rand::random::<usize>() % self.portal_clients.len()];
+        let mut enrs = client
+            .recursive_find_nodes(node_id)
+            .await
+            .map_err(|err| anyhow!("Unable to find gossip targets: {err:?}"))?;
+        enrs.truncate(self.target_count);
+        Ok(enrs)
+    }
+
+    pub fn display_stats(&self) {
+        let offered = self.stats.offered.get();
+        let failed = self.stats.failed.get();
+        match self.strategy {
+            GossipStrategy::Neighborhood => {
+                info!("Gossip strategy: {} - Peers: {offered}", self.strategy)
+            }
+            _ => {
+                let accepted = self.stats.accepted.get();
+                let acceptance_rate = match offered {
+                    0 => 0.0,
+                    _ => accepted as f64 / offered as f64 * 100.0,
+                };
+                info!(
+                    "Gossip strategy: {} - Offers: {offered} - Accepted: {accepted} ({acceptance_rate:.1}%) - Failed: {failed}",
+                    self.strategy
+                );
+            }
+        }
+    }
+}
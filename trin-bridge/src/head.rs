@@ -0,0 +1,120 @@
+use anyhow::{anyhow, bail};
+use light_client::config::networks::Network;
+use light_client::database::ConfigDB;
+use light_client::types::BlockTag;
+use light_client::{Client, ClientBuilder};
+use tokio::time::Duration;
+use tracing::{debug, info};
+use trin_types::execution::header::FullHeader;
+
+use crate::bridge::get_latest_block_number;
+use crate::cli::{BridgeConfig, HeadSource};
+
+const PROVIDER_POLL_RATE: u64 = 5; // seconds
+
+/// The light client is local, so it can be polled often, to gossip new blocks as soon as they
+/// are verified.
+const LIGHT_CLIENT_POLL_RATE: u64 = 1; // seconds
+
+/// The source of the chain head that the bridge follows in latest mode.
+pub enum ChainHead {
+    /// Polls the trusted provider for the latest block.
+    Provider,
+    /// Follows consensus-layer light client updates, so that only blocks verified by the sync
+    /// committee are gossiped.
+    LightClient(Client<ConfigDB>),
+}
+
+impl ChainHead {
+    /// Creates the chain head selected in the config. The light client is synced before it is
+    /// returned.
+    pub async fn from_config(config: &BridgeConfig) -> anyhow::Result<Self> {
+        match config.head_source {
+            HeadSource::Provider => Ok(Self::Provider),
+            HeadSource::LightClient => {
+                let mut builder = ClientBuilder::new()
+                    .network(Network::Mainnet)
+                    .consensus_rpc(&config.consensus_rpc)
+                    .load_external_fallback();
+                if let Some(checkpoint) = &config.light_client_checkpoint {
+                    builder = builder.checkpoint(checkpoint);
+                }
+                let mut client: Client<ConfigDB> = builder
+                    .build()
+                    .map_err(|err| anyhow!("Unable to build light client: {err}"))?;
+                info!("Syncing light client from {}", config.consensus_rpc);
+                client
+                    .start()
+                    .await
+                    .map_err(|err| anyhow!("Unable to sync light client: {err}"))?;
+                Ok(Self::LightClient(client))
+            }
+        }
+    }
+
+    /// Interval between checks for a new chain head.
+    pub fn poll_rate(&self) -> Duration {
+        match self {
+            Self::Provider => Duration::from_secs(PROVIDER_POLL_RATE),
+            Self::LightClient(_) => Duration::from_secs(LIGHT_CLIENT_POLL_RATE),
+        }
+    }
+
+    pub async fn latest_block_number(&self) -> anyhow::Result<u64> {
+        match self {
+            Self::Provider => get_latest_block_number().await,
+            Self::LightClient(client) => client
+                .get_block_number()
+                .await
+                .map_err(|err| anyhow!("Light client has no verified head: {err}")),
+        }
+    }
+
+    /// Verifies headers fetched from the provider against the light client. Headers with a
+    /// verified execution payload must match its block hash, and the remaining headers must
+    /// be linked to them by their parent hashes.
+    ///
+    /// Headers are not verified when following the provider.
+    pub async fn verify_headers(&self, full_headers: &[FullHeader]) -> anyhow::Result<()> {
+        let client = match self {
+            Self::Provider => return Ok(()),
+            Self::LightClient(client) => client,
+        };
+        let mut verified_tip = false;
+        for full_header in full_headers.iter().rev() {
+            let header = &full_header.header;
+            match client.get_block_hash(BlockTag::Number(header.number)).await {
+                Ok(block_hash) => {
+                    if block_hash != header.hash().as_bytes() {
+                        bail!(
+                            "Header #{} doesn't match the light client: {:?}",
+                            header.number,
+                            header.hash()
+                        );
+                    }
+                    verified_tip = true;
+                }
+                Err(_) if verified_tip => {}
+                Err(err) => bail!(
+                    "Header #{} is ahead of the light client's verified blocks: {err}",
+                    header.number
+                ),
+            }
+        }
+        for pair in full_headers.windows(2) {
+            let (parent, child) = (&pair[0].header, &pair[1].header);
+            if child.parent_hash != parent.hash() {
+                bail!(
+                    "Header #{} is not the parent of header #{}",
+                    parent.number,
+                    child.number
+                );
+            }
+        }
+        debug!(
+            "Verified {} headers against the light client",
+            full_headers.len()
+        );
+        Ok(())
+    }
+}
@@ -3,4 +3,7 @@
 pub mod bridge;
 pub mod cli;
 pub mod constants;
+pub mod gossip;
+pub mod head;
+pub mod progress;
 pub mod utils;
@@ -1,4 +1,5 @@
 use ethportal_api::jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use std::sync::Arc;
 use structopt::StructOpt;
 use tokio::process::Command;
 use tokio::time::{sleep, Duration};
@@ -6,6 +7,9 @@ use tracing::info;
 use trin_bridge::bridge::Bridge;
 use trin_bridge::cli::{BridgeConfig, BridgeMode};
 use trin_bridge::constants::PANDAOPS_URL;
+use trin_bridge::gossip::Gossiper;
+use trin_bridge::head::ChainHead;
+use trin_bridge::progress::BACKFILL_PROGRESS_FILE;
 use trin_bridge::utils::generate_spaced_private_keys;
 use trin_types::provider::{build_pandaops_http_client_from_env, TrustedProvider};
 use trin_utils::log::init_tracing_logger;
@@ -14,9 +18,13 @@ use trin_validation::oracle::HeaderOracle;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    init_tracing_logger();
+    init_tracing_logger(None);
 
     let bridge_config = BridgeConfig::from_args();
+    if let Some(addr) = bridge_config.enable_metrics_with_url {
+        prometheus_exporter::start(addr)?;
+    }
+    let chain_head = ChainHead::from_config(&bridge_config).await?;
     let private_keys = generate_spaced_private_keys(bridge_config.node_count);
     let mut handles = vec![];
     let mut http_addresses = vec![];
@@ -39,11 +47,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     sleep(Duration::from_secs(5)).await;
 
     let trusted_provider = TrustedProvider {
-        http: build_pandaops_http_client_from_env(PANDAOPS_URL.to_string()),
+        http: build_pandaops_http_client_from_env(PANDAOPS_URL.to_string())?,
+        jwt_auth: None,
     };
     let master_acc =
         MasterAccumulator::try_from_file("../trin-validation/src/assets/merge_macc.bin".into())?;
-    let header_oracle = HeaderOracle::new(trusted_provider, master_acc);
+    let header_oracle = HeaderOracle::new(Some(trusted_provider), master_acc);
 
     let portal_clients: Result<Vec<HttpClient>, String> = http_addresses
         .iter()
@@ -53,11 +62,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .map_err(|e| e.to_string())
         })
         .collect();
+    let gossiper = Gossiper::new(portal_clients?, &bridge_config)?;
     let bridge = Bridge {
         mode: bridge_config.mode,
-        portal_clients: portal_clients?,
+        gossiper: Arc::new(gossiper),
         header_oracle,
         epoch_acc_path: bridge_config.epoch_acc_path,
+        chain_head,
+        progress_path: bridge_config
+            .data_dir
+            .map(|data_dir| data_dir.join(BACKFILL_PROGRESS_FILE)),
     };
 
     info!("Launching bridge mode: {:?}", bridge.mode);
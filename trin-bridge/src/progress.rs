@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+/// Name of the file in the bridge's data directory that backfill progress is persisted to.
+pub const BACKFILL_PROGRESS_FILE: &str = "backfill_progress.json";
+
+/// Progress of a backfill, persisted after every epoch so that an interrupted backfill resumes
+/// where it left off.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    /// The first epoch that has not been backfilled yet.
+    pub next_epoch: u64,
+    /// Epochs with content that could not be fetched or gossiped, to be retried.
+    pub failed_epochs: Vec<u64>,
+}
+
+impl BackfillProgress {
+    /// Loads the progress from `path`, or returns empty progress if no backfill has been
+    /// persisted yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let progress = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Unable to read backfill progress from {path:?}: {err}"))?;
+        serde_json::from_str(&progress)
+            .map_err(|err| anyhow!("Invalid backfill progress in {path:?}: {err}"))
+    }
+
+    /// Saves the progress to `path`. The progress is written to a temporary file first, so that
+    /// an interruption never leaves a partially written file behind.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut tmp_path = PathBuf::from(path);
+        tmp_path.set_extension("tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Records the outcome of backfilling `epoch`.
+    pub fn record_epoch(&mut self, epoch: u64, complete: bool) {
+        self.failed_epochs
+            .retain(|failed_epoch| *failed_epoch != epoch);
+        if !complete {
+            self.failed_epochs.push(epoch);
+        }
+        self.next_epoch = self.next_epoch.max(epoch + 1);
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_saved_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BACKFILL_PROGRESS_FILE);
+        assert_eq!(
+            BackfillProgress::load(&path).unwrap(),
+            BackfillProgress::default()
+        );
+
+        let mut progress = BackfillProgress::default();
+        progress.record_epoch(0, true);
+        progress.record_epoch(1, false);
+        progress.record_epoch(2, true);
+        progress.save(&path).unwrap();
+        assert_eq!(
+            BackfillProgress::load(&path).unwrap(),
+            BackfillProgress {
+                next_epoch: 3,
+                failed_epochs: vec![1],
+            }
+        );
+    }
+
+    #[test]
+    fn retried_epochs_leave_failed_queue() {
+        let mut progress = BackfillProgress {
+            next_epoch: 10,
+            failed_epochs: vec![3, 5],
+        };
+        progress.record_epoch(3, true);
+        progress.record_epoch(5, false);
+        assert_eq!(progress.next_epoch, 10);
+        assert_eq!(progress.failed_epochs, vec![5]);
+    }
+}
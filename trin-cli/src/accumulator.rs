@@ -0,0 +1,201 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+
+use anyhow::anyhow;
+use ethereum_types::{H256, U256};
+use serde_json::{json, Value};
+use ssz::{Decode, Encode};
+use structopt::StructOpt;
+use tree_hash::TreeHash;
+
+use trin_types::execution::header::Header;
+use trin_types::jsonrpc::params::Params;
+use trin_types::provider::{TrustedProvider, DEFAULT_LOCAL_PROVIDER};
+use trin_utils::bytes::hex_encode;
+use trin_validation::{
+    accumulator::MasterAccumulator,
+    accumulator_builder::AccumulatorBuilder,
+    constants::{DEFAULT_MASTER_ACC_HASH, EPOCH_SIZE, MERGE_BLOCK_NUMBER},
+};
+
+/// Rebuild the pre-merge master accumulator from the headers of an execution node, or extend a
+/// partially built one, and write it as SSZ.
+///
+/// The complete epochs are written to the output after every epoch, so that an interrupted run
+/// can be resumed with `--extend` set to the output.
+#[derive(StructOpt, Debug)]
+pub struct RegenerateAccumulator {
+    /// Path to write the SSZ encoded master accumulator to.
+    #[structopt(long, parse(from_os_str))]
+    output: PathBuf,
+
+    /// Path of a master accumulator of complete epochs to extend, instead of starting from
+    /// genesis. E.g. the output of an interrupted run, or of a run with an --end-block at the end
+    /// of an epoch.
+    #[structopt(long, parse(from_os_str))]
+    extend: Option<PathBuf>,
+
+    /// Number of the last block to include. Defaults to the merge block.
+    #[structopt(long)]
+    end_block: Option<u64>,
+
+    /// Number of headers fetched from the provider at the same time.
+    #[structopt(long, default_value = "16")]
+    concurrency: usize,
+
+    /// HTTP endpoint of a pre-merge archive node to fetch headers and total difficulty from.
+    #[structopt(long, default_value(DEFAULT_LOCAL_PROVIDER))]
+    trusted_provider_url: String,
+}
+
+pub fn regenerate_accumulator(
+    config: RegenerateAccumulator,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let end_block = config.end_block.unwrap_or(MERGE_BLOCK_NUMBER);
+    if end_block > MERGE_BLOCK_NUMBER {
+        return Err(format!(
+            "The master accumulator ends at the merge block #{MERGE_BLOCK_NUMBER}"
+        )
+        .into());
+    }
+    // Only an accumulator of complete epochs can be extended again, except the final one.
+    if config.extend.is_some()
+        && end_block != MERGE_BLOCK_NUMBER
+        && (end_block + 1) % EPOCH_SIZE as u64 != 0
+    {
+        return Err(format!(
+            "An extended master accumulator must end at the end of an epoch or at the merge block, not at block #{}",
+            end_block
+        )
+        .into());
+    }
+    if config.concurrency == 0 {
+        return Err("The concurrency must be at least 1".into());
+    }
+    let provider = TrustedProvider {
+        http: ureq::post(&config.trusted_provider_url),
+        jwt_auth: None,
+    };
+
+    let mut builder = match &config.extend {
+        Some(path) => {
+            let master_acc = MasterAccumulator::from_ssz_bytes(&fs::read(path)?)
+                .map_err(|err| format!("Unable to decode master accumulator: {err:?}"))?;
+            if master_acc.epoch_count() == 0 {
+                return Err("Master accumulator to extend is empty".into());
+            }
+            let last_block = (master_acc.epoch_count() * EPOCH_SIZE) as u64 - 1;
+            let (_, total_difficulty) = fetch_header(&provider, last_block)?;
+            AccumulatorBuilder::from_master_accumulator(&master_acc, total_difficulty)
+        }
+        None => AccumulatorBuilder::new(),
+    };
+
+    let start_block = builder.next_block_number();
+    eprintln!(
+        "Building master accumulator from block #{start_block} to #{}",
+        end_block
+    );
+    // Headers are fetched up to the end of the current epoch at a time, and added in order.
+    let mut batch_start = start_block;
+    while batch_start <= end_block {
+        let epoch_end = (batch_start / EPOCH_SIZE as u64 + 1) * EPOCH_SIZE as u64 - 1;
+        let batch = batch_start..=epoch_end.min(end_block);
+        batch_start = batch.end() + 1;
+        for header in fetch_headers(&provider, batch, config.concurrency)? {
+            let block_number = header.number;
+            if let Some(epoch_acc) = builder.add_header(&header)? {
+                eprintln!(
+                    "Epoch #{} complete: {}",
+                    block_number / EPOCH_SIZE as u64,
+                    hex_encode(epoch_acc.tree_hash_root())
+                );
+                save_checkpoint(&builder, &config.output)?;
+            }
+        }
+    }
+
+    let master_acc = builder.finish()?;
+    fs::write(&config.output, master_acc.as_ssz_bytes())?;
+    let root = master_acc.tree_hash_root();
+    println!(
+        "Wrote master accumulator of {} epochs to {}",
+        master_acc.epoch_count(),
+        config.output.display()
+    );
+    println!("Master accumulator hash: {}", hex_encode(root));
+    if end_block == MERGE_BLOCK_NUMBER {
+        let canonical = H256::from_str(DEFAULT_MASTER_ACC_HASH)?;
+        if root != canonical {
+            return Err(format!(
+                "Hash does not match the canonical master accumulator hash {DEFAULT_MASTER_ACC_HASH}"
+            )
+            .into());
+        }
+        println!("Hash matches the canonical master accumulator");
+    }
+    Ok(())
+}
+
+/// Writes the master accumulator of the complete epochs to `path`.
+fn save_checkpoint(builder: &AccumulatorBuilder, path: &Path) -> anyhow::Result<()> {
+    let master_acc = builder.complete_epochs()?;
+    // Write to a temporary file first, so that an interrupted write doesn't lose the checkpoint.
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, master_acc.as_ssz_bytes())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Fetches the headers of `blocks` in order, splitting the range between `concurrency` threads.
+fn fetch_headers(
+    provider: &TrustedProvider,
+    blocks: RangeInclusive<u64>,
+    concurrency: usize,
+) -> anyhow::Result<Vec<Header>> {
+    let block_numbers: Vec<u64> = blocks.collect();
+    let chunk_size = (block_numbers.len() + concurrency - 1) / concurrency;
+    thread::scope(|scope| {
+        let fetches: Vec<_> = block_numbers
+            .chunks(chunk_size.max(1))
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|block_number| {
+                            fetch_header(provider, *block_number).map(|(header, _)| header)
+                        })
+                        .collect::<anyhow::Result<Vec<Header>>>()
+                })
+            })
+            .collect();
+        let mut headers = Vec::with_capacity(block_numbers.len());
+        for fetch in fetches {
+            let chunk = fetch
+                .join()
+                .map_err(|_| anyhow!("Header fetching thread panicked"))??;
+            headers.extend(chunk);
+        }
+        Ok(headers)
+    })
+}
+
+/// Fetches the header and the total difficulty of a block from the provider.
+fn fetch_header(provider: &TrustedProvider, block_number: u64) -> anyhow::Result<(Header, U256)> {
+    let params = Params::Array(vec![json!(format!("0x{block_number:x}")), json!(false)]);
+    let response: Value =
+        provider.dispatch_http_request("eth_getBlockByNumber".to_string(), params)?;
+    let result = &response["result"];
+    if result.is_null() {
+        return Err(anyhow!("Block #{block_number} not found by provider"));
+    }
+    let header: Header = serde_json::from_value(result.clone())?;
+    let total_difficulty = result["totalDifficulty"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Missing total difficulty of block #{block_number}"))?;
+    let total_difficulty = U256::from_str_radix(total_difficulty.trim_start_matches("0x"), 16)?;
+    Ok((header, total_difficulty))
+}
@@ -0,0 +1,125 @@
+use structopt::StructOpt;
+
+use ethportal_api::{HistoryContentKey, HistoryContentValue, OverlayContentKey};
+use trin_types::execution::{
+    accumulator::EpochAccumulator,
+    block_body::BlockBody,
+    header::{BlockHeaderProof, HeaderWithProof},
+    receipts::{Receipt, Receipts, TransactionOutcome},
+};
+use trin_utils::bytes::{hex_decode, hex_encode};
+
+use crate::validate::read_hex_or_file;
+
+/// Decode a history network content item and print its contents.
+#[derive(StructOpt, Debug)]
+pub struct Decode {
+    /// Hex-encoded content key.
+    content_key: String,
+
+    /// Hex-encoded content value, or the path of a file holding the raw or hex-encoded value.
+    content_value: String,
+}
+
+pub fn decode_content(decode: Decode) -> Result<(), Box<dyn std::error::Error>> {
+    let content_key = HistoryContentKey::try_from(hex_decode(&decode.content_key)?)?;
+    let content_value = read_hex_or_file(&decode.content_value)?;
+    let content = HistoryContentValue::decode_with_key(&content_key, &content_value)
+        .map_err(|err| format!("Unable to decode content value: {err:?}"))?;
+
+    println!("Content key: {content_key}");
+    println!("Content id:  {}", hex_encode(content_key.content_id()));
+    println!("Size:        {} bytes", content_value.len());
+    match content {
+        HistoryContentValue::BlockHeaderWithProof(header) => print_header(&header),
+        HistoryContentValue::BlockBody(body) => print_block_body(&body),
+        HistoryContentValue::Receipts(receipts) => print_receipts(&receipts),
+        HistoryContentValue::EpochAccumulator(epoch_acc) => print_epoch_accumulator(&epoch_acc),
+    }
+    Ok(())
+}
+
+fn print_header(header_with_proof: &HeaderWithProof) {
+    let header = &header_with_proof.header;
+    println!("Header with proof");
+    println!("  number:            {}", header.number);
+    println!("  hash:              {:?}", header.hash());
+    println!("  parent hash:       {:?}", header.parent_hash);
+    println!("  timestamp:         {}", header.timestamp);
+    println!("  author:            {:?}", header.author);
+    println!("  state root:        {:?}", header.state_root);
+    println!("  transactions root: {:?}", header.transactions_root);
+    println!("  receipts root:     {:?}", header.receipts_root);
+    println!("  uncles hash:       {:?}", header.uncles_hash);
+    println!("  difficulty:        {}", header.difficulty);
+    println!("  gas limit:         {}", header.gas_limit);
+    println!("  gas used:          {}", header.gas_used);
+    println!("  extra data:        {}", hex_encode(&header.extra_data));
+    if let Some(base_fee_per_gas) = header.base_fee_per_gas {
+        println!("  base fee per gas:  {base_fee_per_gas}");
+    }
+    if let Some(withdrawals_root) = header.withdrawals_root {
+        println!("  withdrawals root:  {withdrawals_root:?}");
+    }
+    let proof = match &header_with_proof.proof {
+        BlockHeaderProof::None(_) => "none",
+        BlockHeaderProof::AccumulatorProof(_) => "accumulator proof",
+        BlockHeaderProof::HistoricalRootsBlockProof(_) => "historical roots block proof",
+        BlockHeaderProof::HistoricalSummariesBlockProof(_) => "historical summaries block proof",
+    };
+    println!("  proof:             {proof}");
+}
+
+fn print_block_body(body: &BlockBody) {
+    println!("Block body");
+    println!("  transactions: {}", body.txs.len());
+    println!("  uncles:       {}", body.uncles.list.len());
+    for uncle in body.uncles.list.iter() {
+        println!("    #{} {:?}", uncle.number, uncle.hash());
+    }
+}
+
+fn print_receipts(receipts: &Receipts) {
+    println!("Receipts");
+    println!("  receipts: {}", receipts.receipt_list.len());
+    for (index, receipt) in receipts.receipt_list.iter().enumerate() {
+        let receipt_type = match receipt {
+            Receipt::Legacy(_) => "legacy",
+            Receipt::AccessList(_) => "access list",
+            Receipt::EIP1559(_) => "EIP-1559",
+            Receipt::Blob(_) => "blob",
+        };
+        let outcome = match &receipt.outcome {
+            TransactionOutcome::StateRoot(state_root) => format!("state root {state_root:?}"),
+            TransactionOutcome::StatusCode(status) => format!("status {status}"),
+        };
+        println!(
+            "  receipt {index}: {receipt_type}, {outcome}, cumulative gas used {}, {} logs",
+            receipt.cumulative_gas_used,
+            receipt.logs.len()
+        );
+        for (log_index, log) in receipt.logs.iter().enumerate() {
+            println!(
+                "    log {log_index}: address {:?}, {} topics, {} bytes of data",
+                log.address,
+                log.topics.len(),
+                log.data.len()
+            );
+        }
+    }
+}
+
+fn print_epoch_accumulator(epoch_acc: &EpochAccumulator) {
+    println!("Epoch accumulator");
+    println!("  header records: {}", epoch_acc.len());
+    if let (Some(first), Some(last)) = (epoch_acc.first(), epoch_acc.last()) {
+        println!(
+            "  first record:   {:?} (total difficulty {})",
+            first.block_hash, first.total_difficulty
+        );
+        println!(
+            "  last record:    {:?} (total difficulty {})",
+            last.block_hash, last.total_difficulty
+        );
+    }
+}
@@ -1,4 +1,7 @@
+pub mod accumulator;
 pub mod dashboard;
+pub mod decode;
+pub mod validate;
 
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
@@ -12,10 +15,15 @@ use serde_json::value::RawValue;
 use structopt::StructOpt;
 use thiserror::Error;
 
+use accumulator::{regenerate_accumulator, RegenerateAccumulator};
 use dashboard::grafana::GrafanaAPI;
-use ethportal_api::{BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, HistoryContentKey};
+use decode::{decode_content, Decode};
+use ethportal_api::{
+    BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey, HistoryContentKey,
+};
 use trin_types::cli::DEFAULT_WEB3_IPC_PATH;
 use trin_utils::bytes::hex_encode;
+use validate::{validate_content, ValidateContent};
 
 #[derive(StructOpt)]
 #[structopt(
@@ -28,6 +36,9 @@ enum Trin {
     JsonRpc(JsonRpc),
     EncodeKey(EncodeKey),
     CreateDashboard(DashboardConfig),
+    ValidateContent(ValidateContent),
+    Decode(Decode),
+    RegenerateAccumulator(RegenerateAccumulator),
 }
 
 /// Run JSON-RPC commands against a trin node.
@@ -69,6 +80,12 @@ enum EncodeKey {
         #[structopt(long)]
         block_hash: H256,
     },
+    /// Encode the content key for a block header, by block number.
+    BlockHeaderByNumber {
+        /// Number of the block.
+        #[structopt(long)]
+        block_number: u64,
+    },
 }
 
 #[derive(StructOpt)]
@@ -95,6 +112,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Trin::JsonRpc(rpc) => json_rpc(rpc),
         Trin::EncodeKey(content_key) => encode_content_key(content_key),
         Trin::CreateDashboard(dashboard_config) => create_dashboard(dashboard_config),
+        Trin::ValidateContent(config) => validate_content(config),
+        Trin::Decode(decode) => decode_content(decode),
+        Trin::RegenerateAccumulator(config) => regenerate_accumulator(config),
     }
 }
 
@@ -133,6 +153,9 @@ fn encode_content_key(content_key: EncodeKey) -> Result<(), Box<dyn std::error::
                 block_hash: block_hash.into(),
             })
         }
+        EncodeKey::BlockHeaderByNumber { block_number } => {
+            HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey { block_number })
+        }
     };
 
     println!("{}", hex_encode(Into::<Vec<u8>>::into(key)));
@@ -0,0 +1,229 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde_json::Value;
+use ssz::Decode;
+use structopt::StructOpt;
+use tokio::sync::{mpsc, RwLock};
+
+use ethportal_api::{BlockHeaderKey, HistoryContentKey, HistoryContentValue};
+use trin_history::validation::ChainHistoryValidator;
+use trin_types::cli::DEFAULT_MASTER_ACC_PATH;
+use trin_types::execution::header::HeaderWithProof;
+use trin_types::jsonrpc::endpoints::HistoryEndpoint;
+use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
+use trin_types::provider::{TrustedProvider, DEFAULT_LOCAL_PROVIDER};
+use trin_utils::bytes::{hex_decode, hex_encode};
+use trin_validation::{accumulator::MasterAccumulator, oracle::HeaderOracle, validator::Validator};
+
+/// Validate a history network content item offline, as a node would before storing it.
+#[derive(StructOpt, Debug)]
+pub struct ValidateContent {
+    /// Hex-encoded content key.
+    #[structopt(long)]
+    key: String,
+
+    /// Hex-encoded content value, or the path of a file holding the raw or hex-encoded value.
+    #[structopt(long)]
+    value: String,
+
+    /// Header with proof of the block that a block body or receipts belong to, hex-encoded or
+    /// a file path. Without it, the header is requested from the trusted provider.
+    #[structopt(long)]
+    header: Option<String>,
+
+    /// HTTP endpoint of the trusted provider, used for headers not supplied with --header.
+    #[structopt(long, default_value(DEFAULT_LOCAL_PROVIDER))]
+    trusted_provider_url: String,
+
+    /// Path to the pre-merge master accumulator.
+    #[structopt(long, default_value(DEFAULT_MASTER_ACC_PATH), parse(from_os_str))]
+    master_acc_path: PathBuf,
+}
+
+/// Outcome of a single step of the validation pipeline.
+struct Check {
+    name: &'static str,
+    result: anyhow::Result<String>,
+}
+
+pub fn validate_content(config: ValidateContent) -> Result<(), Box<dyn std::error::Error>> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let checks = runtime.block_on(run_checks(config));
+
+    let mut failed = None;
+    for check in checks.iter() {
+        match &check.result {
+            Ok(detail) => println!("PASS {}: {detail}", check.name),
+            Err(err) => {
+                println!("FAIL {}: {err:#}", check.name);
+                failed = Some(check.name);
+            }
+        }
+    }
+    match failed {
+        None => {
+            println!("Verdict: valid");
+            Ok(())
+        }
+        Some(name) => {
+            println!("Verdict: invalid");
+            Err(format!("Content failed the '{name}' check").into())
+        }
+    }
+}
+
+/// Runs the checks in pipeline order, stopping at the first failure.
+async fn run_checks(config: ValidateContent) -> Vec<Check> {
+    let mut checks = vec![];
+    macro_rules! check {
+        ($name:expr, $result:expr) => {{
+            let result: anyhow::Result<_> = $result;
+            match result {
+                Ok((value, detail)) => {
+                    checks.push(Check {
+                        name: $name,
+                        result: Ok(detail),
+                    });
+                    value
+                }
+                Err(err) => {
+                    checks.push(Check {
+                        name: $name,
+                        result: Err(err),
+                    });
+                    return checks;
+                }
+            }
+        }};
+    }
+
+    let master_acc = check!(
+        "load master accumulator",
+        MasterAccumulator::try_from_file(config.master_acc_path.clone())
+            .map(|acc| (acc, config.master_acc_path.display().to_string()))
+    );
+    let content_key = check!(
+        "decode content key",
+        hex_decode(&config.key)
+            .map_err(|err| anyhow!("Invalid hex: {err}"))
+            .and_then(|bytes| HistoryContentKey::try_from(bytes).map_err(|err| anyhow!(err)))
+            .map(|key| {
+                let detail = key.to_string();
+                (key, detail)
+            })
+    );
+    let content = check!(
+        "read content value",
+        read_hex_or_file(&config.value).map(|bytes| {
+            let detail = format!("{} bytes", bytes.len());
+            (bytes, detail)
+        })
+    );
+    check!(
+        "decode content value",
+        HistoryContentValue::decode_with_key(&content_key, &content)
+            .map(|_| ((), "SSZ decoding matches the content key".to_string()))
+            .map_err(|err| anyhow!("{err:?}"))
+    );
+
+    let header_oracle = HeaderOracle::new(
+        Some(TrustedProvider {
+            http: ureq::post(&config.trusted_provider_url),
+            jwt_auth: None,
+        }),
+        master_acc,
+    );
+    let header = match &config.header {
+        Some(header) => {
+            let (header_key, header) = check!(
+                "decode header",
+                read_hex_or_file(header).and_then(|bytes| {
+                    let hwp = HeaderWithProof::from_ssz_bytes(&bytes)
+                        .map_err(|err| anyhow!("Invalid header with proof: {err:?}"))?;
+                    let block_hash = hwp.header.hash();
+                    let header_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+                        block_hash: block_hash.into(),
+                    });
+                    Ok((
+                        (header_key, bytes),
+                        format!("block #{} ({block_hash:?})", hwp.header.number),
+                    ))
+                })
+            );
+            let validator = ChainHistoryValidator {
+                header_oracle: Arc::new(RwLock::new(header_oracle.clone())),
+            };
+            check!(
+                "validate header",
+                validator
+                    .validate_content(&header_key, &header)
+                    .await
+                    .map(|_| ((), "header is canonical".to_string()))
+            );
+            Some((header_key, header))
+        }
+        None => None,
+    };
+
+    let validator = ChainHistoryValidator {
+        header_oracle: Arc::new(RwLock::new(offline_oracle(header_oracle, header))),
+    };
+    check!(
+        "validate content",
+        validator
+            .validate_content(&content_key, &content)
+            .await
+            .map(|_| (
+                (),
+                "all checks of the history network validator passed".to_string()
+            ))
+    );
+    checks
+}
+
+/// Returns `header_oracle` with its history network requests answered locally: a lookup of the
+/// supplied header returns it, and every other lookup finds nothing, so that the oracle falls
+/// back to the trusted provider.
+fn offline_oracle(
+    mut header_oracle: HeaderOracle,
+    header: Option<(HistoryContentKey, Vec<u8>)>,
+) -> HeaderOracle {
+    let (history_jsonrpc_tx, mut history_jsonrpc_rx) =
+        mpsc::unbounded_channel::<HistoryJsonRpcRequest>();
+    header_oracle.history_jsonrpc_tx = Some(history_jsonrpc_tx);
+    tokio::spawn(async move {
+        while let Some(request) = history_jsonrpc_rx.recv().await {
+            let response = match (&request.endpoint, &header) {
+                (HistoryEndpoint::RecursiveFindContent(key, _), Some((header_key, header)))
+                    if key == header_key =>
+                {
+                    Ok(Value::String(hex_encode(header)))
+                }
+                (HistoryEndpoint::RecursiveFindContent(..), _) => {
+                    Ok(Value::String("0x".to_string()))
+                }
+                _ => Err("The history network is not available offline".to_string()),
+            };
+            let _ = request.resp.send(response);
+        }
+    });
+    header_oracle
+}
+
+/// Reads a value given either as a 0x-prefixed hex string, or as the path of a file holding the
+/// value as hex or raw bytes.
+pub fn read_hex_or_file(value: &str) -> anyhow::Result<Vec<u8>> {
+    if value.starts_with("0x") {
+        return hex_decode(value).map_err(|err| anyhow!("Invalid hex: {err}"));
+    }
+    let bytes = fs::read(value).map_err(|err| anyhow!("Unable to read {value}: {err}"))?;
+    match std::str::from_utf8(&bytes) {
+        Ok(contents) if contents.trim().starts_with("0x") => {
+            hex_decode(contents.trim()).map_err(|err| anyhow!("Invalid hex in {value}: {err}"))
+        }
+        _ => Ok(bytes),
+    }
+}
@@ -0,0 +1,219 @@
+use std::{sync::Arc, time::Instant};
+
+use ethportal_api::{types::portal::SummaryInfo, HistoryContentKey};
+use rand::Rng;
+use serde_json::{json, Value};
+use tokio::{
+    task::JoinHandle,
+    time::{interval, Duration},
+};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::network::HistoryNetwork;
+use trin_types::enr::Enr;
+use trin_utils::version::get_trin_version;
+
+/// How often a report is submitted to the Glados endpoint.
+const GLADOS_REPORT_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Time after which a report that the Glados endpoint hasn't answered is given up, so that an
+/// unresponsive endpoint doesn't hold a blocking thread indefinitely.
+const GLADOS_REPORT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Periodically submits a node health snapshot, along with the result of a content-availability
+/// audit, to a Glados endpoint.
+///
+/// Each audit picks a random content key from local storage and looks it up on the network,
+/// recording whether any peer was able to serve it.
+pub fn spawn_glados_reporter(network: Arc<HistoryNetwork>, report_url: Url) -> JoinHandle<()> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(GLADOS_REPORT_TIMEOUT)
+        .build();
+    tokio::spawn(async move {
+        let mut report_interval = interval(GLADOS_REPORT_INTERVAL);
+
+        loop {
+            report_interval.tick().await;
+
+            let audit = audit_random_local_content(&network).await;
+            let summary = SummaryInfo {
+                storage: network.overlay.store.read().get_storage_summary(),
+                messages: network.overlay.get_message_summary(),
+            };
+            let report = build_report(&network.overlay.local_enr(), summary, audit);
+
+            let url = report_url.clone();
+            let agent = agent.clone();
+            let result =
+                tokio::task::spawn_blocking(move || submit_report(&agent, &url, report)).await;
+            match result {
+                Ok(Ok(_)) => debug!(url = %report_url, "Submitted report to Glados"),
+                Ok(Err(err)) => warn!(url = %report_url, %err, "Unable to submit report to Glados"),
+                Err(err) => warn!(%err, "Glados report task failed"),
+            }
+        }
+    })
+}
+
+/// The outcome of looking up a single content key on the network.
+struct AuditResult {
+    content_key: HistoryContentKey,
+    success: bool,
+    duration: Duration,
+}
+
+/// Looks up a randomly selected, locally stored content key on the network. Returns `None` if
+/// there is no local content to audit.
+async fn audit_random_local_content(network: &HistoryNetwork) -> Option<AuditResult> {
+    let content_key = random_local_content_key(network)?;
+
+    let start = Instant::now();
+    // Audits bypass the lookup cache, so that every audit traverses the network.
+    let (content, _) = network
+        .overlay
+        .lookup_content(content_key.clone(), true)
+        .await;
+    Some(AuditResult {
+        content_key,
+        success: content.is_ok(),
+        duration: start.elapsed(),
+    })
+}
+
+fn random_local_content_key(network: &HistoryNetwork) -> Option<HistoryContentKey> {
+    let store = network.overlay.store.read();
+    let total_entries = match store.paginate(&0, &0) {
+        Ok(page) => page.total_entries,
+        Err(err) => {
+            warn!(%err, "Unable to count local content for Glados audit");
+            return None;
+        }
+    };
+    if total_entries == 0 {
+        return None;
+    }
+    let offset = rand::thread_rng().gen_range(0..total_entries);
+    match store.paginate(&offset, &1) {
+        Ok(page) => page.content_keys.into_iter().next(),
+        Err(err) => {
+            warn!(%err, "Unable to select local content for Glados audit");
+            None
+        }
+    }
+}
+
+fn build_report(enr: &Enr, summary: SummaryInfo, audit: Option<AuditResult>) -> Value {
+    let audit = audit.map(|audit| {
+        json!({
+            "contentKey": audit.content_key,
+            "success": audit.success,
+            "durationMs": audit.duration.as_millis() as u64,
+        })
+    });
+    json!({
+        "client": format!("trin v{}", get_trin_version()),
+        "enr": enr.to_base64(),
+        "subnetwork": "history",
+        "summary": summary,
+        "audit": audit,
+    })
+}
+
+/// POSTs `report` to the Glados endpoint at `url`. Responses with an error status are errors.
+fn submit_report(agent: &ureq::Agent, url: &Url, report: Value) -> Result<(), ureq::Error> {
+    agent.post(url.as_str()).send_json(report)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use discv5::enr::{CombinedKey, EnrBuilder};
+    use ethportal_api::types::portal::{
+        DataRadius, MessageCountInfo, MessageSummaryInfo, StorageSummaryInfo,
+    };
+    use httpmock::prelude::*;
+    use trin_types::content_key::BlockBodyKey;
+
+    fn summary() -> SummaryInfo {
+        SummaryInfo {
+            storage: StorageSummaryInfo {
+                data_radius: DataRadius::from(1000u64),
+                radius_percent: 0.5,
+                content_storage_usage_kb: 10.0,
+                total_storage_usage_kb: 12.0,
+                storage_capacity_kb: 100.0,
+                entry_count: 3,
+            },
+            messages: MessageSummaryInfo {
+                sent: MessageCountInfo::default(),
+                received: MessageCountInfo::default(),
+            },
+        }
+    }
+
+    fn enr() -> Enr {
+        EnrBuilder::new("v4")
+            .build(&CombinedKey::generate_secp256k1())
+            .unwrap()
+    }
+
+    #[test]
+    fn report_includes_summary_and_audit() {
+        let enr = enr();
+        let content_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: [0xab; 32],
+        });
+        let audit = AuditResult {
+            content_key: content_key.clone(),
+            success: true,
+            duration: Duration::from_millis(1500),
+        };
+        let report = build_report(&enr, summary(), Some(audit));
+
+        assert_eq!(report["client"], format!("trin v{}", get_trin_version()));
+        assert_eq!(report["enr"], enr.to_base64());
+        assert_eq!(report["subnetwork"], "history");
+        assert_eq!(report["summary"], json!(summary()));
+        assert_eq!(report["audit"]["contentKey"], json!(content_key));
+        assert_eq!(report["audit"]["success"], true);
+        assert_eq!(report["audit"]["durationMs"], 1500);
+    }
+
+    #[test]
+    fn report_without_local_content_has_no_audit() {
+        let report = build_report(&enr(), summary(), None);
+        assert_eq!(report["audit"], Value::Null);
+        assert_eq!(report["summary"]["storage"]["entryCount"], 3);
+    }
+
+    #[test]
+    fn submits_report_to_endpoint() {
+        let server = MockServer::start();
+        let report = json!({"subnetwork": "history"});
+        let accepted = server.mock(|when, then| {
+            when.method(POST).path("/report").json_body(report.clone());
+            then.status(200);
+        });
+        let rejected = server.mock(|when, then| {
+            when.method(POST).path("/rejected");
+            then.status(500);
+        });
+        let agent = ureq::AgentBuilder::new()
+            .timeout(GLADOS_REPORT_TIMEOUT)
+            .build();
+
+        let url = Url::parse(&server.url("/report")).unwrap();
+        submit_report(&agent, &url, report.clone()).unwrap();
+        accepted.assert();
+
+        let url = Url::parse(&server.url("/rejected")).unwrap();
+        assert!(matches!(
+            submit_report(&agent, &url, report),
+            Err(ureq::Error::Status(500, _))
+        ));
+        rejected.assert();
+    }
+}
@@ -1,193 +1,387 @@
 use std::sync::Arc;
 
+use lru::LruCache;
 use serde_json::{json, Value};
 use tokio::sync::mpsc;
-use tracing::error;
+use tracing::{error, Instrument};
 use trin_types::constants::CONTENT_ABSENT;
 
-use crate::network::HistoryNetwork;
-use portalnet::storage::ContentStore;
+use crate::{compact_storage, network::HistoryNetwork};
+use portalnet::{
+    find::query_info::LookupError, network_estimate::add_local_storage, storage::ContentStore,
+};
 use trin_utils::bytes::hex_encode;
 
 use crate::utils::bucket_entries_to_json;
 use ethportal_api::types::portal::{
-    AcceptInfo, Distance, FindNodesInfo, NodeInfo, PongInfo, TraceContentInfo,
+    AcceptInfo, ContentChunkInfo, Distance, FindNodesInfo, NodeInfo, PongInfo, SummaryInfo,
+    TraceContentInfo,
 };
 use ethportal_api::ContentValue;
 use ethportal_api::{HistoryContentKey, OverlayContentKey};
 use ssz::Encode;
 use trin_types::content_key::RawContentKey;
-use trin_types::distance::{Metric, XorMetric};
+use trin_types::correlation::operation_span;
+use trin_types::distance::{self, Metric, XorMetric};
 use trin_types::enr::Enr;
 use trin_types::jsonrpc::endpoints::HistoryEndpoint;
 use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
 
+/// Number of content values that are kept for serving their later chunks, so that a client may
+/// fetch a few values in chunks at the same time.
+const CHUNKED_CONTENT_CAPACITY: usize = 4;
+
 /// Handles History network JSON-RPC requests
 pub struct HistoryRequestHandler {
     pub network: Arc<HistoryNetwork>,
     pub history_rx: mpsc::UnboundedReceiver<HistoryJsonRpcRequest>,
+    /// Content values served in chunks, by content id. The value is fetched when its first chunk
+    /// is requested, and later chunks are sliced out of the kept value.
+    chunked_content: LruCache<[u8; 32], Arc<Vec<u8>>>,
 }
 
 impl HistoryRequestHandler {
+    pub fn new(
+        network: Arc<HistoryNetwork>,
+        history_rx: mpsc::UnboundedReceiver<HistoryJsonRpcRequest>,
+    ) -> Self {
+        Self {
+            network,
+            history_rx,
+            chunked_content: LruCache::new(CHUNKED_CONTENT_CAPACITY),
+        }
+    }
+
     pub async fn handle_client_queries(mut self) {
         while let Some(request) = self.history_rx.recv().await {
-            match request.endpoint {
-                HistoryEndpoint::LocalContent(content_key) => {
-                    let response =
-                        match &self.network.overlay.store.read().get(&content_key)
-                        {
-                            Ok(val) => match val {
-                                Some(val) => {
-                                    Ok(Value::String(hex_encode(val.clone())))
-                                }
-                                None => {
-                                    Ok(Value::String(CONTENT_ABSENT.to_string()))
-                                }
-                            },
-                            Err(err) => Err(format!(
-                                "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
-                            )),
-                        };
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::PaginateLocalContentKeys(offset, limit) => {
-                    let response =
-                            match &self.network.overlay.store.read().paginate(&offset, &limit)
-                                {
-                                    Ok(val) => Ok(json!(val)),
-                                    Err(err) => Err(format!(
-                                        "Database error while paginating local content keys with offset: {offset:?}, limit: {limit:?}. Error message: {err}"
-                                    )),
-                                };
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::Store(content_key, content_value) => {
-                    let data = content_value.encode();
-                    let response = match self
-                        .network
-                        .overlay
-                        .store
-                        .write()
-                        .put::<HistoryContentKey, Vec<u8>>(content_key, data)
+            // Tag every log line generated while handling the request with its correlation id.
+            let span = operation_span("rpc");
+            self.handle_request(request).instrument(span).await;
+        }
+    }
+
+    async fn handle_request(&mut self, request: HistoryJsonRpcRequest) {
+        match request.endpoint {
+            HistoryEndpoint::LocalContent(content_key) => {
+                let response =
+                    match &self.network.overlay.store.read().get(&content_key)
                     {
-                        Ok(_) => Ok(Value::Bool(true)),
-                        Err(msg) => Ok(Value::String(msg.to_string())),
+                        Ok(val) => match val {
+                            Some(val) => {
+                                Ok(Value::String(hex_encode(val.clone())))
+                            }
+                            None => {
+                                Ok(Value::String(CONTENT_ABSENT.to_string()))
+                            }
+                        },
+                        Err(err) => Err(format!(
+                            "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+                        )),
                     };
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::RecursiveFindContent(content_key) => {
-                    let response = self.recursive_find_content(content_key, false).await;
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::TraceRecursiveFindContent(content_key) => {
-                    let response = self.recursive_find_content(content_key, true).await;
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::DataRadius => {
-                    let radius = &self.network.overlay.data_radius();
-                    let response = Ok(json!(**radius));
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::FindContent(enr, content_key) => {
-                    let response = match self
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::PaginateLocalContentKeys(offset, limit) => {
+                let response =
+                        match &self.network.overlay.store.read().paginate(&offset, &limit)
+                            {
+                                Ok(val) => Ok(json!(val)),
+                                Err(err) => Err(format!(
+                                    "Database error while paginating local content keys with offset: {offset:?}, limit: {limit:?}. Error message: {err}"
+                                )),
+                            };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Store(content_key, content_value) => {
+                let data = content_value.encode();
+                let response = match self
+                    .network
+                    .overlay
+                    .store
+                    .write()
+                    .put::<HistoryContentKey, Vec<u8>>(content_key, data)
+                {
+                    Ok(_) => Ok(Value::Bool(true)),
+                    Err(msg) => Ok(Value::String(msg.to_string())),
+                };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::RecursiveFindContent(content_key, bypass_cache) => {
+                let response = self
+                    .recursive_find_content(content_key, bypass_cache, false)
+                    .await;
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::ContentChunk(content_key, offset, max_bytes) => {
+                let response = self.content_chunk(content_key, offset, max_bytes).await;
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::TraceRecursiveFindContent(content_key) => {
+                // Traces report the route of a lookup, so they always traverse the network.
+                let response = self.recursive_find_content(content_key, true, true).await;
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::DataRadius => {
+                let radius = &self.network.overlay.data_radius();
+                let response = Ok(json!(**radius));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::PeerCapabilities => {
+                let response = Ok(json!(self.network.overlay.get_peer_capabilities()));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::NetworkEstimate => {
+                let mut estimate = self.network.overlay.get_network_estimate();
+                let storage = self.network.overlay.store.read().get_storage_summary();
+                add_local_storage(
+                    &mut estimate,
+                    storage.radius_percent / 100.0,
+                    storage.content_storage_usage_kb,
+                );
+                let response = Ok(json!(estimate));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Replication(content_key, nodes) => {
+                // The lookup and requests take a while, so other requests are handled meanwhile.
+                let network = Arc::clone(&self.network);
+                tokio::spawn(
+                    async move {
+                        let replication = network
+                            .overlay
+                            .measure_replication(&content_key, nodes as usize)
+                            .await;
+                        let _ = request.resp.send(Ok(json!(replication)));
+                    }
+                    .in_current_span(),
+                );
+            }
+            HistoryEndpoint::SetStorageCapacity(capacity_kb) => {
+                let mut store = self.network.overlay.store.write();
+                let response = match store.set_storage_capacity::<HistoryContentKey>(capacity_kb) {
+                    Ok(_) => Ok(json!(store.get_storage_summary())),
+                    Err(err) => Err(format!("Failed to resize storage capacity: {err}")),
+                };
+                drop(store);
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::SetRadius(radius) => {
+                let response = match self
+                    .network
+                    .overlay
+                    .store
+                    .write()
+                    .set_fixed_radius::<HistoryContentKey>(radius.map(distance::Distance::from))
+                {
+                    Ok(radius) => Ok(json!(*radius)),
+                    Err(err) => Err(format!("Failed to set radius: {err}")),
+                };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::CompactStorage => {
+                // Compacting may take a while, so other requests are handled in the meantime.
+                let network = Arc::clone(&self.network);
+                tokio::spawn(
+                    async move {
+                        let response = match compact_storage(&network).await {
+                            Ok(info) => Ok(json!(info)),
+                            Err(err) => Err(format!("Failed to compact storage: {err}")),
+                        };
+                        let _ = request.resp.send(response);
+                    }
+                    .in_current_span(),
+                );
+            }
+            HistoryEndpoint::PeerStats => {
+                let response = Ok(json!(self.network.overlay.get_peer_stats()));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::RecentBlockHashes(count) => {
+                let block_hashes = self
+                    .network
+                    .chain_head
+                    .read()
+                    .await
+                    .recent_block_hashes(count);
+                let _ = request.resp.send(Ok(json!(block_hashes)));
+            }
+            HistoryEndpoint::LifetimeStats => {
+                let activity = self.network.overlay.get_activity_totals();
+                let response = Ok(json!(self.network.lifetime_stats.totals(&activity)));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::RefreshRoutingTable(bucket) => {
+                let response = match self.network.overlay.refresh_buckets(bucket) {
+                    Ok(()) => Ok(json!(true)),
+                    Err(err) => Err(format!("Failed to refresh routing table: {err}")),
+                };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::UtpTransfers => {
+                let response = Ok(json!(self.network.overlay.get_utp_transfers()));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Summary => {
+                let summary = SummaryInfo {
+                    storage: self.network.overlay.store.read().get_storage_summary(),
+                    messages: self.network.overlay.get_message_summary(),
+                };
+                let response = Ok(json!(summary));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::FindContent(enr, content_key) => {
+                let response = match self
+                    .network
+                    .overlay
+                    .send_find_content(enr, content_key.into())
+                    .await
+                {
+                    Ok(content) => match content.try_into() {
+                        Ok(val) => Ok(val),
+                        Err(_) => Err("Content response decoding error".to_string()),
+                    },
+                    Err(msg) => Err(format!("FindContent request timeout: {msg:?}")),
+                };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::FindNodes(enr, distances) => {
+                let response = match self.network.overlay.send_find_nodes(enr, distances).await {
+                    Ok(nodes) => Ok(json!(FindNodesInfo {
+                        total: nodes.total,
+                        enrs: nodes
+                            .enrs
+                            .into_iter()
+                            .map(|enr| enr.into())
+                            .collect::<Vec<Enr>>(),
+                    })),
+                    Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
+                };
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Gossip(content_key, content_value) => {
+                let data = content_value.encode();
+                let content_values = vec![(content_key, data)];
+                let num_peers = self.network.overlay.propagate_gossip(content_values);
+                let response = Ok(num_peers.into());
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Offer(enr, content_key, content_value) => {
+                let response = if let Some(content_value) = content_value {
+                    let content_value = content_value.encode();
+                    match self
                         .network
                         .overlay
-                        .send_find_content(enr, content_key.into())
+                        .send_populated_offer(enr, content_key.into(), content_value)
                         .await
                     {
-                        Ok(content) => match content.try_into() {
-                            Ok(val) => Ok(val),
-                            Err(_) => Err("Content response decoding error".to_string()),
-                        },
-                        Err(msg) => Err(format!("FindContent request timeout: {msg:?}")),
-                    };
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::FindNodes(enr, distances) => {
-                    let response = match self.network.overlay.send_find_nodes(enr, distances).await
-                    {
-                        Ok(nodes) => Ok(json!(FindNodesInfo {
-                            total: nodes.total,
-                            enrs: nodes
-                                .enrs
-                                .into_iter()
-                                .map(|enr| enr.into())
-                                .collect::<Vec<Enr>>(),
+                        Ok(accept) => Ok(json!(AcceptInfo {
+                            content_keys: accept.content_keys,
                         })),
-                        Err(msg) => Err(format!("FindNodes request timeout: {msg:?}")),
-                    };
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::Gossip(content_key, content_value) => {
-                    let data = content_value.encode();
-                    let content_values = vec![(content_key, data)];
-                    let num_peers = self.network.overlay.propagate_gossip(content_values);
-                    let response = Ok(num_peers.into());
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::Offer(enr, content_key, content_value) => {
-                    let response = if let Some(content_value) = content_value {
-                        let content_value = content_value.encode();
-                        match self
-                            .network
-                            .overlay
-                            .send_populated_offer(enr, content_key.into(), content_value)
-                            .await
-                        {
-                            Ok(accept) => Ok(json!(AcceptInfo {
-                                content_keys: accept.content_keys,
-                            })),
-                            Err(msg) => Err(format!("Populated Offer request timeout: {msg:?}")),
-                        }
-                    } else {
-                        let content_key: Vec<RawContentKey> = vec![content_key.as_ssz_bytes()];
-
-                        match self.network.overlay.send_offer(content_key, enr).await {
-                            Ok(accept) => Ok(json!(AcceptInfo {
-                                content_keys: accept.content_keys,
-                            })),
-                            Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
-                        }
-                    };
+                        Err(msg) => Err(format!("Populated Offer request timeout: {msg:?}")),
+                    }
+                } else {
+                    let content_key: Vec<RawContentKey> = vec![content_key.as_ssz_bytes()];
 
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::Ping(enr, _) => {
-                    let response = match self.network.overlay.send_ping(enr).await {
-                        Ok(pong) => Ok(json!(PongInfo {
-                            enr_seq: pong.enr_seq as u32,
-                            data_radius: *self.network.overlay.data_radius(),
+                    match self.network.overlay.send_offer(content_key, enr).await {
+                        Ok(accept) => Ok(json!(AcceptInfo {
+                            content_keys: accept.content_keys,
                         })),
-                        Err(msg) => Err(format!("Ping request timeout: {msg:?}")),
-                    };
+                        Err(msg) => Err(format!("Offer request timeout: {msg:?}")),
+                    }
+                };
 
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::RoutingTableInfo => {
-                    let response = Ok(bucket_entries_to_json(
-                        self.network.overlay.bucket_entries(),
-                    ));
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::Ping(enr, _) => {
+                let response = match self.network.overlay.send_ping(enr).await {
+                    Ok(pong) => Ok(json!(PongInfo {
+                        enr_seq: pong.enr_seq as u32,
+                        data_radius: *self.network.overlay.data_radius(),
+                    })),
+                    Err(msg) => Err(format!("Ping request timeout: {msg:?}")),
+                };
 
-                    let _ = request.resp.send(response);
-                }
-                HistoryEndpoint::RecursiveFindNodes(node_id) => {
-                    let node_id = discv5::enr::NodeId::from(node_id.0);
-                    let mut nodes = self.network.overlay.lookup_node(node_id).await;
-                    nodes.sort_by(|a, b| {
-                        XorMetric::distance(&node_id.raw(), &a.node_id().raw())
-                            .cmp(&XorMetric::distance(&node_id.raw(), &b.node_id().raw()))
-                    });
-                    let nodes: Vec<Enr> = nodes.into_iter().take(16).collect();
-                    let response = Ok(json!(nodes));
-                    let _ = request.resp.send(response);
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::RoutingTableInfo => {
+                let response = Ok(bucket_entries_to_json(
+                    self.network.overlay.bucket_entries(),
+                ));
+
+                let _ = request.resp.send(response);
+            }
+            HistoryEndpoint::RecursiveFindNodes(node_id) => {
+                let node_id = discv5::enr::NodeId::from(node_id.0);
+                let mut nodes = self.network.overlay.lookup_node(node_id).await;
+                nodes.sort_by(|a, b| {
+                    XorMetric::distance(&node_id.raw(), &a.node_id().raw())
+                        .cmp(&XorMetric::distance(&node_id.raw(), &b.node_id().raw()))
+                });
+                let nodes: Vec<Enr> = nodes.into_iter().take(16).collect();
+                let response = Ok(json!(nodes));
+                let _ = request.resp.send(response);
+            }
+        }
+    }
+
+    /// Slices the content value for chunked responses. The value is fetched for the first chunk,
+    /// at offset 0, and kept in `chunked_content` so that later chunks don't fetch it again.
+    async fn content_chunk(
+        &mut self,
+        content_key: HistoryContentKey,
+        offset: u64,
+        max_bytes: u64,
+    ) -> Result<Value, String> {
+        let content_id = content_key.content_id();
+        let content = match self.chunked_content.get(&content_id) {
+            Some(content) if offset > 0 => Arc::clone(content),
+            _ => {
+                let content = Arc::new(self.fetch_content(content_key).await?);
+                if !content.is_empty() {
+                    self.chunked_content.put(content_id, Arc::clone(&content));
                 }
+                content
             }
+        };
+        match ContentChunkInfo::from_content(&content, offset, max_bytes) {
+            Some(chunk) => Ok(json!(chunk)),
+            None => Err(format!(
+                "Offset {offset} is beyond the end of the content, which is {} bytes long",
+                content.len()
+            )),
         }
     }
 
+    /// Returns the content value from local storage, or looks it up on the network if it is not
+    /// stored locally. Returns an empty value if the content is not found.
+    async fn fetch_content(&self, content_key: HistoryContentKey) -> Result<Vec<u8>, String> {
+        let local_content = match self.network.overlay.store.read().get(&content_key) {
+            Ok(content) => content,
+            Err(err) => {
+                return Err(format!(
+                    "Database error while looking for content key in local storage: {content_key:?}, with error: {err}",
+                ))
+            }
+        };
+        let content = match local_content {
+            Some(content) => content,
+            None => match self
+                .network
+                .overlay
+                .lookup_content(content_key, false)
+                .await
+                .0
+            {
+                Ok(content) => content,
+                Err(LookupError::NotFound) => vec![],
+                Err(err) => return Err(err.to_string()),
+            },
+        };
+        Ok(content)
+    }
+
     async fn recursive_find_content(
         &mut self,
         content_key: HistoryContentKey,
+        bypass_cache: bool,
         is_trace: bool,
     ) -> Result<Value, String> {
         // Check whether we have the data locally.
@@ -205,15 +399,22 @@ impl HistoryRequestHandler {
                 }
             };
         // Get data from peer
-        let (possible_content_bytes, closest_nodes) = match local_content {
-            Some(val) => (Some(val), vec![]),
+        let (lookup_result, closest_nodes) = match local_content {
+            Some(val) => (Ok(val), vec![]),
             None => {
                 self.network
                     .overlay
-                    .lookup_content(content_key.clone())
+                    .lookup_content(content_key.clone(), bypass_cache)
                     .await
             }
         };
+        // Content that is absent from the network is returned as "0x", while lookups that ran
+        // out of time or failed are returned as errors.
+        let possible_content_bytes = match lookup_result {
+            Ok(bytes) => Some(bytes),
+            Err(LookupError::NotFound) => None,
+            Err(err) => return Err(err.to_string()),
+        };
 
         // Format as string.
         let content_response_string = match possible_content_bytes {
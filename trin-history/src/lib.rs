@@ -1,26 +1,39 @@
 #![warn(clippy::unwrap_used)]
 
 pub mod events;
+pub mod glados;
 mod jsonrpc;
 pub mod network;
+pub mod stats;
 pub mod utils;
 pub mod validation;
+pub mod webhook;
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use discv5::TalkRequest;
+use ethportal_api::types::portal::CompactionInfo;
 use network::HistoryNetwork;
 use tokio::{
     sync::{mpsc, RwLock},
     task::JoinHandle,
-    time::{interval, Duration},
+    time::{interval, Duration, Instant},
 };
-use tracing::info;
+use tracing::{info, warn};
+use url::Url;
 use utp_rs::socket::UtpSocket;
 
-use crate::{events::HistoryEvents, jsonrpc::HistoryRequestHandler};
+use crate::{
+    events::HistoryEvents,
+    glados::spawn_glados_reporter,
+    jsonrpc::HistoryRequestHandler,
+    stats::{spawn_lifetime_stats_saver, spawn_stats_export},
+    webhook::{spawn_webhook_notifier, WebhookConfig},
+};
 use portalnet::{
     discovery::{Discovery, UtpEnr},
+    lifetime_stats::LifetimeStatsSaver,
+    regossip::{spawn_regossip, RegossipConfig},
     storage::PortalStorageConfig,
     types::messages::PortalnetConfig,
 };
@@ -31,6 +44,8 @@ type HistoryHandler = Option<HistoryRequestHandler>;
 type HistoryNetworkTask = Option<JoinHandle<()>>;
 type HistoryEventTx = Option<mpsc::UnboundedSender<TalkRequest>>;
 type HistoryJsonRpcTx = Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>;
+type HistoryBackgroundTasks = Vec<(&'static str, JoinHandle<()>)>;
+type HistoryLifetimeStats = Option<LifetimeStatsSaver>;
 
 pub async fn initialize_history_network(
     discovery: &Arc<Discovery>,
@@ -39,16 +54,31 @@ pub async fn initialize_history_network(
     portalnet_config: PortalnetConfig,
     storage_config: PortalStorageConfig,
     header_oracle: Arc<RwLock<HeaderOracle>>,
+    glados_report_url: Option<Url>,
+    stats_export: Option<PathBuf>,
+    compaction_interval: Option<Duration>,
+    regossip: Option<RegossipConfig>,
+    webhook: Option<WebhookConfig>,
 ) -> anyhow::Result<(
     HistoryHandler,
     HistoryNetworkTask,
     HistoryEventTx,
     HistoryJsonRpcTx,
+    HistoryBackgroundTasks,
+    HistoryLifetimeStats,
 )> {
     let (history_jsonrpc_tx, history_jsonrpc_rx) =
         mpsc::unbounded_channel::<HistoryJsonRpcRequest>();
     header_oracle.write().await.history_jsonrpc_tx = Some(history_jsonrpc_tx.clone());
     let (history_event_tx, history_event_rx) = mpsc::unbounded_channel::<TalkRequest>();
+    let mut background_tasks: HistoryBackgroundTasks = vec![];
+    // Subscribe before the network starts, so that no stored content is missed.
+    if let Some(webhook) = webhook {
+        background_tasks.push((
+            "webhook_notifier",
+            spawn_webhook_notifier(&portalnet_config.events, webhook),
+        ));
+    }
     let history_network = HistoryNetwork::new(
         Arc::clone(discovery),
         utp_socket,
@@ -58,21 +88,63 @@ pub async fn initialize_history_network(
     )
     .await?;
     let history_network = Arc::new(history_network);
-    let history_handler = HistoryRequestHandler {
-        network: Arc::clone(&history_network),
-        history_rx: history_jsonrpc_rx,
-    };
+    let history_handler =
+        HistoryRequestHandler::new(Arc::clone(&history_network), history_jsonrpc_rx);
     let history_network_task = spawn_history_network(
         Arc::clone(&history_network),
         portalnet_config,
         history_event_rx,
     );
-    spawn_history_heartbeat(Arc::clone(&history_network));
+    background_tasks.extend(
+        history_network
+            .overlay
+            .take_service_tasks()
+            .into_iter()
+            .map(|task| ("history_overlay", task)),
+    );
+    background_tasks.push((
+        "history_heartbeat",
+        spawn_history_heartbeat(Arc::clone(&history_network)),
+    ));
+    background_tasks.push((
+        "ephemeral_sweeper",
+        spawn_ephemeral_sweeper(Arc::clone(&history_network)),
+    ));
+    background_tasks.push((
+        "lifetime_stats_saver",
+        spawn_lifetime_stats_saver(history_network.lifetime_stats_saver()),
+    ));
+    if let Some(period) = compaction_interval {
+        background_tasks.push((
+            "compaction",
+            spawn_compaction(Arc::clone(&history_network), period),
+        ));
+    }
+    if let Some(config) = regossip {
+        background_tasks.push((
+            "regossip",
+            spawn_regossip(Arc::clone(&history_network.overlay), config),
+        ));
+    }
+    if let Some(url) = glados_report_url {
+        background_tasks.push((
+            "glados_reporter",
+            spawn_glados_reporter(Arc::clone(&history_network), url),
+        ));
+    }
+    if let Some(path) = stats_export {
+        background_tasks.push((
+            "stats_export",
+            spawn_stats_export(Arc::clone(&history_network), path),
+        ));
+    }
     Ok((
         Some(history_handler),
         Some(history_network_task),
         Some(history_event_tx),
         Some(history_jsonrpc_tx),
+        background_tasks,
+        Some(history_network.lifetime_stats_saver()),
     ))
 }
 
@@ -98,19 +170,67 @@ pub fn spawn_history_network(
             event_rx: history_event_rx,
         };
 
-        // Spawn history event handler
-        tokio::spawn(history_events.start());
+        // Handle history events within this task, so that aborting the task stops the handler.
+        // hacky test: meanwhile, make sure we establish a session with the boot node
+        tokio::join!(history_events.start(), network.overlay.ping_bootnodes());
+    })
+}
+
+/// How often expired ephemeral content is dropped from storage.
+const EPHEMERAL_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
 
-        // hacky test: make sure we establish a session with the boot node
-        network.overlay.ping_bootnodes().await;
+/// Periodically drops ephemeral content whose time to live has elapsed, so that it does not
+/// linger in memory until the next ephemeral content is stored.
+pub fn spawn_ephemeral_sweeper(network: Arc<HistoryNetwork>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut sweep_interval = interval(EPHEMERAL_SWEEP_INTERVAL);
 
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to pause until ctrl-c");
+        loop {
+            sweep_interval.tick().await;
+            network.overlay.store.write().expire_ephemeral();
+        }
     })
 }
 
-pub fn spawn_history_heartbeat(network: Arc<HistoryNetwork>) {
+/// Compacts the content database every `period`, starting one `period` after startup.
+pub fn spawn_compaction(network: Arc<HistoryNetwork>, period: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut compaction_interval = interval(period);
+        // The first tick completes immediately, and there is nothing to reclaim at startup.
+        compaction_interval.tick().await;
+
+        loop {
+            compaction_interval.tick().await;
+            if let Err(err) = compact_storage(&network).await {
+                warn!(error = %err, "Scheduled compaction of the content database failed");
+            }
+        }
+    })
+}
+
+/// Compacts the content database, returning the space left by pruned content to the file system.
+/// The database is compacted on a blocking thread, without holding the lock on the store.
+pub async fn compact_storage(network: &HistoryNetwork) -> anyhow::Result<CompactionInfo> {
+    let content_db = network.overlay.store.read().content_db();
+    let start = Instant::now();
+    let reclaimed_bytes = tokio::task::spawn_blocking(move || content_db.compact()).await??;
+    let total_storage_usage = network
+        .overlay
+        .store
+        .read()
+        .get_total_storage_usage_in_bytes_on_disk()?;
+    info!(
+        reclaimed_kb = reclaimed_bytes / 1000,
+        elapsed = ?start.elapsed(),
+        "Compacted the content database"
+    );
+    Ok(CompactionInfo {
+        reclaimed_kb: reclaimed_bytes as f64 / 1000.0,
+        total_storage_usage_kb: total_storage_usage as f64 / 1000.0,
+    })
+}
+
+pub fn spawn_history_heartbeat(network: Arc<HistoryNetwork>) -> JoinHandle<()> {
     tokio::spawn(async move {
         let mut heart_interval = interval(Duration::from_millis(30000));
 
@@ -123,5 +243,5 @@ pub fn spawn_history_heartbeat(network: Arc<HistoryNetwork>) {
             let message_log = network.overlay.get_summary_info();
             info!("reports~ data: {storage_log}; msgs: {message_log}");
         }
-    });
+    })
 }
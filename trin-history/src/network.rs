@@ -7,12 +7,14 @@ use utp_rs::socket::UtpSocket;
 use ethportal_api::HistoryContentKey;
 use portalnet::{
     discovery::{Discovery, UtpEnr},
-    overlay::{OverlayConfig, OverlayProtocol},
+    lifetime_stats::{LifetimeStats, LifetimeStatsSaver},
+    overlay::{OverlayConfig, OverlayProtocol, RequestTimeouts},
     storage::{PortalStorage, PortalStorageConfig},
     types::messages::{PortalnetConfig, ProtocolId},
 };
+use trin_types::cli::HISTORY_NETWORK;
 use trin_types::distance::XorMetric;
-use trin_validation::oracle::HeaderOracle;
+use trin_validation::{chain_head::ChainHead, oracle::HeaderOracle};
 
 use crate::validation::ChainHistoryValidator;
 
@@ -21,6 +23,10 @@ use crate::validation::ChainHistoryValidator;
 pub struct HistoryNetwork {
     pub overlay:
         Arc<OverlayProtocol<HistoryContentKey, XorMetric, ChainHistoryValidator, PortalStorage>>,
+    /// Activity of the node across restarts, kept in the node's data directory.
+    pub lifetime_stats: Arc<LifetimeStats>,
+    /// Recent blocks verified by the light client.
+    pub chain_head: Arc<RwLock<ChainHead>>,
 }
 
 impl HistoryNetwork {
@@ -33,12 +39,32 @@ impl HistoryNetwork {
     ) -> anyhow::Result<Self> {
         let config = OverlayConfig {
             bootnode_enrs: portal_config.bootnode_enrs.clone(),
+            ping_extensions: portal_config.ping_extensions.clone(),
+            bucket_refresh_interval: portal_config.bucket_refresh_interval,
+            bucket_size: portal_config.bucket_size,
+            table_depth: portal_config.table_depth,
+            bucket_pending_timeout: portal_config.bucket_pending_timeout,
+            max_incoming_per_bucket: portal_config.max_incoming_per_bucket,
+            request_timeouts: RequestTimeouts::default()
+                .with_overrides(HISTORY_NETWORK, &portal_config.talkreq_timeouts),
+            query_timeout: portal_config.lookup_timeout,
+            query_peer_timeout: portal_config.lookup_peer_timeout,
+            query_peer_attempts: portal_config.lookup_peer_attempts,
+            query_num_results: portal_config.lookup_num_results,
+            lookup_cache_size: portal_config.lookup_cache_size,
+            lookup_cache_ttl: portal_config.lookup_cache_ttl,
+            validation_cache_size: portal_config.validation_cache_size,
+            utp_transfer_limit: portal_config.utp_transfer_limit,
+            neighborhood_sync_limit: portal_config.neighborhood_sync_limit,
+            events: portal_config.events.clone(),
             ..Default::default()
         };
-        let storage = Arc::new(PLRwLock::new(PortalStorage::new(
+        let lifetime_stats = LifetimeStats::open(&storage_config.node_data_dir);
+        let storage = Arc::new(PLRwLock::new(PortalStorage::new::<HistoryContentKey>(
             storage_config,
             ProtocolId::History,
         )?));
+        let chain_head = Arc::clone(&header_oracle.read().await.chain_head);
         let validator = Arc::new(ChainHistoryValidator { header_oracle });
         let overlay = OverlayProtocol::new(
             config,
@@ -52,6 +78,13 @@ impl HistoryNetwork {
 
         Ok(Self {
             overlay: Arc::new(overlay),
+            lifetime_stats: Arc::new(lifetime_stats),
+            chain_head,
         })
     }
+
+    /// Returns a saver of the lifetime statistics with the activity of this network.
+    pub fn lifetime_stats_saver(&self) -> LifetimeStatsSaver {
+        LifetimeStatsSaver::new(Arc::clone(&self.lifetime_stats), self.overlay.metrics())
+    }
 }
@@ -0,0 +1,279 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tokio::{
+    task::JoinHandle,
+    time::{interval, Duration},
+};
+use tracing::{info, warn};
+
+use crate::network::HistoryNetwork;
+use portalnet::lifetime_stats::LifetimeStatsSaver;
+
+/// How often a snapshot is appended to the stats export file.
+const STATS_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the lifetime statistics are saved to the node's data directory.
+const LIFETIME_STATS_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Column names of the CSV export, in the order the fields of `StatsSnapshot` are written.
+const CSV_HEADER: &str = "timestamp,subnetwork,connected_peers,known_peers,radius_percent,\
+content_storage_usage_kb,total_storage_usage_kb,storage_capacity_kb,entry_count,lookups,\
+lookups_found,lookup_success_rate,utp_bytes_sent,utp_bytes_received,utp_send_bytes_per_sec,\
+utp_receive_bytes_per_sec";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StatsExportFormat {
+    Csv,
+    JsonLines,
+}
+
+impl StatsExportFormat {
+    /// Exports to CSV if the file has a `.csv` extension, and to JSON lines otherwise.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::JsonLines,
+        }
+    }
+}
+
+/// A point-in-time view of the node's behavior on the History network.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct StatsSnapshot {
+    timestamp: u64,
+    subnetwork: &'static str,
+    connected_peers: usize,
+    known_peers: usize,
+    radius_percent: f64,
+    content_storage_usage_kb: f64,
+    total_storage_usage_kb: f64,
+    storage_capacity_kb: f64,
+    entry_count: u64,
+    lookups: u64,
+    lookups_found: u64,
+    lookup_success_rate: f64,
+    utp_bytes_sent: u64,
+    utp_bytes_received: u64,
+    utp_send_bytes_per_sec: f64,
+    utp_receive_bytes_per_sec: f64,
+}
+
+impl StatsSnapshot {
+    fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.timestamp,
+            self.subnetwork,
+            self.connected_peers,
+            self.known_peers,
+            self.radius_percent,
+            self.content_storage_usage_kb,
+            self.total_storage_usage_kb,
+            self.storage_capacity_kb,
+            self.entry_count,
+            self.lookups,
+            self.lookups_found,
+            self.lookup_success_rate,
+            self.utp_bytes_sent,
+            self.utp_bytes_received,
+            self.utp_send_bytes_per_sec,
+            self.utp_receive_bytes_per_sec,
+        )
+    }
+}
+
+/// Periodically appends a snapshot of peer, storage, lookup and uTP statistics to `path`.
+pub fn spawn_stats_export(network: Arc<HistoryNetwork>, path: PathBuf) -> JoinHandle<()> {
+    let format = StatsExportFormat::from_path(&path);
+    info!(path = %path.display(), ?format, "Exporting node statistics");
+
+    tokio::spawn(async move {
+        let mut export_interval = interval(STATS_EXPORT_INTERVAL);
+        let mut previous: Option<StatsSnapshot> = None;
+
+        loop {
+            export_interval.tick().await;
+
+            let snapshot = take_snapshot(&network, previous.as_ref());
+            if let Err(err) = append_snapshot(&path, format, &snapshot) {
+                warn!(path = %path.display(), %err, "Unable to export node statistics");
+            }
+            previous = Some(snapshot);
+        }
+    })
+}
+
+/// Periodically saves the node's lifetime statistics, so that they carry over across restarts.
+/// They are also saved when the node stops, by the owner of the saver.
+pub fn spawn_lifetime_stats_saver(saver: LifetimeStatsSaver) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut save_interval = interval(LIFETIME_STATS_SAVE_INTERVAL);
+        // The first tick completes immediately, when there is no activity to save yet.
+        save_interval.tick().await;
+
+        loop {
+            save_interval.tick().await;
+
+            if let Err(err) = saver.save() {
+                warn!(%err, "Unable to save lifetime statistics");
+            }
+        }
+    })
+}
+
+fn take_snapshot(network: &HistoryNetwork, previous: Option<&StatsSnapshot>) -> StatsSnapshot {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let storage = network.overlay.store.read().get_storage_summary();
+    let peer_stats = network.overlay.get_peer_stats();
+    let (lookups, lookups_found) = network.overlay.get_content_lookup_counts();
+    // The totals include the traffic of peers whose statistics have since been evicted, so that
+    // they never decrease between snapshots.
+    let activity = network.overlay.get_activity_totals();
+    let utp_bytes_sent = activity.bytes_uploaded;
+    let utp_bytes_received = activity.bytes_downloaded;
+
+    // Throughput is averaged over the time since the previous snapshot.
+    let (utp_send_bytes_per_sec, utp_receive_bytes_per_sec) = match previous {
+        Some(previous) if timestamp > previous.timestamp => {
+            let elapsed = (timestamp - previous.timestamp) as f64;
+            (
+                utp_bytes_sent.saturating_sub(previous.utp_bytes_sent) as f64 / elapsed,
+                utp_bytes_received.saturating_sub(previous.utp_bytes_received) as f64 / elapsed,
+            )
+        }
+        _ => (0.0, 0.0),
+    };
+
+    StatsSnapshot {
+        timestamp,
+        subnetwork: "history",
+        connected_peers: network.overlay.table_entries_id().len(),
+        known_peers: peer_stats.len(),
+        radius_percent: storage.radius_percent,
+        content_storage_usage_kb: storage.content_storage_usage_kb,
+        total_storage_usage_kb: storage.total_storage_usage_kb,
+        storage_capacity_kb: storage.storage_capacity_kb,
+        entry_count: storage.entry_count,
+        lookups,
+        lookups_found,
+        lookup_success_rate: if lookups == 0 {
+            0.0
+        } else {
+            lookups_found as f64 / lookups as f64
+        },
+        utp_bytes_sent,
+        utp_bytes_received,
+        utp_send_bytes_per_sec,
+        utp_receive_bytes_per_sec,
+    }
+}
+
+fn append_snapshot(
+    path: &Path,
+    format: StatsExportFormat,
+    snapshot: &StatsSnapshot,
+) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    match format {
+        StatsExportFormat::Csv => {
+            if file.metadata()?.len() == 0 {
+                writeln!(file, "{CSV_HEADER}")?;
+            }
+            writeln!(file, "{}", snapshot.to_csv_row())?;
+        }
+        StatsExportFormat::JsonLines => {
+            writeln!(file, "{}", serde_json::to_string(snapshot)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    fn snapshot() -> StatsSnapshot {
+        StatsSnapshot {
+            timestamp: 1_680_000_000,
+            subnetwork: "history",
+            connected_peers: 12,
+            known_peers: 20,
+            radius_percent: 100.0,
+            content_storage_usage_kb: 1.5,
+            total_storage_usage_kb: 80.0,
+            storage_capacity_kb: 100000.0,
+            entry_count: 3,
+            lookups: 4,
+            lookups_found: 3,
+            lookup_success_rate: 0.75,
+            utp_bytes_sent: 2048,
+            utp_bytes_received: 1024,
+            utp_send_bytes_per_sec: 34.1,
+            utp_receive_bytes_per_sec: 17.0,
+        }
+    }
+
+    #[test]
+    fn format_is_chosen_by_extension() {
+        assert_eq!(
+            StatsExportFormat::from_path(Path::new("stats.csv")),
+            StatsExportFormat::Csv
+        );
+        assert_eq!(
+            StatsExportFormat::from_path(Path::new("stats.jsonl")),
+            StatsExportFormat::JsonLines
+        );
+        assert_eq!(
+            StatsExportFormat::from_path(Path::new("stats")),
+            StatsExportFormat::JsonLines
+        );
+    }
+
+    #[test]
+    fn csv_export_writes_header_once() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("stats.csv");
+
+        append_snapshot(&path, StatsExportFormat::Csv, &snapshot()).unwrap();
+        append_snapshot(&path, StatsExportFormat::Csv, &snapshot()).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], snapshot().to_csv_row());
+        assert_eq!(
+            lines[0].split(',').count(),
+            lines[1].split(',').count(),
+            "CSV header and rows must have the same number of columns"
+        );
+    }
+
+    #[test]
+    fn json_lines_export_appends_one_object_per_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("stats.jsonl");
+
+        append_snapshot(&path, StatsExportFormat::JsonLines, &snapshot()).unwrap();
+        append_snapshot(&path, StatsExportFormat::JsonLines, &snapshot()).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        for line in contents.lines() {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["entry_count"], 3);
+            assert_eq!(value["lookup_success_rate"], 0.75);
+        }
+        assert_eq!(contents.lines().count(), 2);
+    }
+}
@@ -11,7 +11,7 @@ use ethportal_api::HistoryContentKey;
 use trin_types::execution::{
     accumulator::EpochAccumulator,
     block_body::BlockBody,
-    header::{Header, HeaderWithProof},
+    header::{BlockHeaderProof, Header, HeaderWithProof},
     receipts::Receipts,
 };
 use trin_validation::{oracle::HeaderOracle, validator::Validator};
@@ -41,6 +41,47 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
                     .await
                     .validate_header_with_proof(header_with_proof)
             }
+            HistoryContentKey::BlockHeaderByNumber(key) => {
+                let header_with_proof =
+                    HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
+                        anyhow!("Header with proof content has invalid encoding: {err:?}")
+                    })?;
+                if header_with_proof.header.number != key.block_number {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid header number. Found: {:?} - Expected: {:?}",
+                        header_with_proof.header.number,
+                        key.block_number
+                    ));
+                }
+                self.header_oracle
+                    .write()
+                    .await
+                    .validate_header_with_proof(header_with_proof)
+            }
+            HistoryContentKey::EphemeralHeader(key) => {
+                let header_with_proof =
+                    HeaderWithProof::from_ssz_bytes(content).map_err(|err| {
+                        anyhow!("Header with proof content has invalid encoding: {err:?}")
+                    })?;
+                if !matches!(header_with_proof.proof, BlockHeaderProof::None(_)) {
+                    return Err(anyhow!(
+                        "Content validation failed: Ephemeral header must not carry a proof."
+                    ));
+                }
+                let header = header_with_proof.header;
+                if header.hash() != H256::from(key.block_hash) {
+                    return Err(anyhow!(
+                        "Content validation failed: Invalid header hash. Found: {:?} - Expected: {:?}",
+                        header.hash(),
+                        H256::from(key.block_hash)
+                    ));
+                }
+                self.header_oracle
+                    .read()
+                    .await
+                    .validate_ephemeral_header(&header)
+                    .await
+            }
             HistoryContentKey::BlockBody(key) => {
                 let block_body = BlockBody::from_ssz_bytes(content)
                     .map_err(|msg| anyhow!("Block Body content has invalid encoding: {:?}", msg))?;
@@ -103,12 +144,9 @@ impl Validator<HistoryContentKey> for ChainHistoryValidator {
                     ));
                 }
                 let master_acc = &self.header_oracle.read().await.master_acc;
-                if !master_acc.historical_epochs.contains(&tree_hash_root) {
-                    return Err(anyhow!(
-                        "Content validation failed: Invalid epoch accumulator, missing from master accumulator."
-                    ));
-                }
-                Ok(())
+                master_acc
+                    .validate_epoch_acc(&epoch_acc)
+                    .map_err(|err| anyhow!("Content validation failed: {err}"))
             }
         }
     }
@@ -127,7 +165,9 @@ mod tests {
     use ssz::Encode;
     use ssz_types::{typenum, VariableList};
 
-    use ethportal_api::{BlockBodyKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey};
+    use ethportal_api::{
+        BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey, EpochAccumulatorKey,
+    };
     use trin_types::cli::DEFAULT_MASTER_ACC_PATH;
     use trin_types::execution::accumulator::HeaderRecord;
     use trin_types::provider::TrustedProvider;
@@ -232,6 +272,37 @@ mod tests {
             .unwrap();
     }
 
+    #[test_log::test(tokio::test)]
+    async fn validate_header_by_number() {
+        let server = setup_mock_infura_server();
+        let hwp_ssz = get_hwp_ssz();
+        let header_oracle = default_header_oracle(server.url("/get_header"));
+        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let content_key = HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey {
+            block_number: 1_000_001,
+        });
+        chain_history_validator
+            .validate_content(&content_key, &hwp_ssz)
+            .await
+            .unwrap();
+    }
+
+    #[test_log::test(tokio::test)]
+    #[should_panic(expected = "Invalid header number")]
+    async fn invalidate_header_by_number_with_mismatched_number() {
+        let server = setup_mock_infura_server();
+        let hwp_ssz = get_hwp_ssz();
+        let header_oracle = default_header_oracle(server.url("/get_header"));
+        let chain_history_validator = ChainHistoryValidator { header_oracle };
+        let content_key = HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey {
+            block_number: 1_000_002,
+        });
+        chain_history_validator
+            .validate_content(&content_key, &hwp_ssz)
+            .await
+            .unwrap();
+    }
+
     #[test_log::test(tokio::test)]
     #[should_panic(expected = "Merkle proof validation failed for pre-merge header")]
     async fn invalidate_header_with_invalid_number() {
@@ -440,11 +511,15 @@ mod tests {
     fn default_header_oracle(infura_url: String) -> Arc<RwLock<HeaderOracle>> {
         let trusted_provider = TrustedProvider {
             http: ureq::post(&infura_url),
+            jwt_auth: None,
         };
         let master_acc =
             MasterAccumulator::try_from_file(PathBuf::from(DEFAULT_MASTER_ACC_PATH.to_string()))
                 .unwrap();
-        Arc::new(RwLock::new(HeaderOracle::new(trusted_provider, master_acc)))
+        Arc::new(RwLock::new(HeaderOracle::new(
+            Some(trusted_provider),
+            master_acc,
+        )))
     }
 
     fn block_14764013_hash() -> H256 {
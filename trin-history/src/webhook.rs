@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use ethportal_api::{HistoryContentKey, OverlayContentKey};
+use serde_json::{json, Value};
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle};
+use tracing::{debug, warn};
+use url::Url;
+
+use portalnet::{
+    node_events::{NodeEvent, NodeEvents},
+    types::messages::ProtocolId,
+};
+use trin_utils::bytes::hex_encode;
+use trin_utils::version::get_trin_version;
+
+/// Time after which a notification that the webhook hasn't answered is given up, so that a hung
+/// webhook doesn't hold up the notifications behind it.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A webhook that is notified when history content that matches its filters is stored.
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub url: Url,
+    /// Types of content to notify about, as named by `HistoryContentKey::content_type`. All
+    /// types are notified about if empty.
+    pub content_types: Vec<String>,
+    /// Prefixes of the content keys to notify about. All content keys are notified about if empty.
+    pub key_prefixes: Vec<Vec<u8>>,
+}
+
+impl WebhookConfig {
+    /// Returns whether the webhook is notified when content with `content_key` is stored.
+    pub fn matches(&self, content_key: &HistoryContentKey) -> bool {
+        let content_type = content_key.content_type();
+        let type_matches =
+            self.content_types.is_empty() || self.content_types.iter().any(|t| t == content_type);
+        let raw_key: Vec<u8> = content_key.into();
+        let prefix_matches = self.key_prefixes.is_empty()
+            || self
+                .key_prefixes
+                .iter()
+                .any(|prefix| raw_key.starts_with(prefix));
+        type_matches && prefix_matches
+    }
+}
+
+/// POSTs a JSON notification to the webhook whenever history content received from the network
+/// that matches the filters of the webhook is stored.
+///
+/// Notifications are sent one at a time, and each is given up after `WEBHOOK_TIMEOUT`. If the
+/// webhook is too slow to keep up, the oldest notifications are skipped.
+pub fn spawn_webhook_notifier(events: &NodeEvents, config: WebhookConfig) -> JoinHandle<()> {
+    let mut events = events.subscribe();
+    let agent = ureq::AgentBuilder::new().timeout(WEBHOOK_TIMEOUT).build();
+    tokio::spawn(async move {
+        loop {
+            let content_key = match events.recv().await {
+                Ok(NodeEvent::ContentStored {
+                    protocol: ProtocolId::History,
+                    content_key,
+                }) => content_key,
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Webhook fell behind, skipped notifications");
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+            let content_key = match HistoryContentKey::try_from(content_key) {
+                Ok(content_key) => content_key,
+                Err(err) => {
+                    warn!(%err, "Unable to decode stored content key for webhook");
+                    continue;
+                }
+            };
+            if !config.matches(&content_key) {
+                continue;
+            }
+
+            let notification = build_notification(&content_key);
+            let url = config.url.clone();
+            let agent = agent.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                agent.post(url.as_str()).send_json(notification)
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => {
+                    debug!(url = %config.url, content.key = %content_key, "Notified webhook")
+                }
+                Ok(Err(err)) => warn!(url = %config.url, %err, "Unable to notify webhook"),
+                Err(err) => warn!(%err, "Webhook notification task failed"),
+            }
+        }
+    })
+}
+
+fn build_notification(content_key: &HistoryContentKey) -> Value {
+    json!({
+        "client": format!("trin v{}", get_trin_version()),
+        "subnetwork": "history",
+        "contentKey": content_key,
+        "contentId": hex_encode(content_key.content_id()),
+        "contentType": content_key.content_type(),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use trin_types::content_key::BlockBodyKey;
+
+    fn webhook(content_types: &[&str], key_prefixes: &[&[u8]]) -> WebhookConfig {
+        WebhookConfig {
+            url: Url::parse("http://localhost:8080/").unwrap(),
+            content_types: content_types.iter().map(|t| t.to_string()).collect(),
+            key_prefixes: key_prefixes.iter().map(|p| p.to_vec()).collect(),
+        }
+    }
+
+    #[test]
+    fn webhook_filters() {
+        let content_key = HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: [0xab; 32],
+        });
+
+        assert!(webhook(&[], &[]).matches(&content_key));
+        assert!(webhook(&["block_body"], &[]).matches(&content_key));
+        assert!(!webhook(&["block_receipts"], &[]).matches(&content_key));
+        assert!(webhook(&[], &[&[0x01, 0xab]]).matches(&content_key));
+        assert!(!webhook(&[], &[&[0x02]]).matches(&content_key));
+        assert!(!webhook(&["block_body"], &[&[0x02]]).matches(&content_key));
+    }
+}
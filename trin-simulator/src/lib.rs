@@ -0,0 +1,10 @@
+//! Simulates the propagation of content by gossip through a network of overlay nodes, to compare
+//! overlay parameters on data from this codebase rather than intuition.
+//!
+//! Every node runs the real overlay protocol over its own Discovery v5 instance on a local UDP
+//! port, with an in-memory content store and a validator that accepts all content.
+
+#![warn(clippy::unwrap_used)]
+
+pub mod network;
+pub mod simulation;
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use ethereum_types::U256;
+use structopt::StructOpt;
+
+use portalnet::overlay::GossipFanout;
+use trin_simulator::{
+    network::NetworkConfig,
+    simulation::{run_simulation, SimulationConfig, SimulationReport},
+};
+use trin_types::distance::Distance;
+use trin_utils::log::init_tracing_logger;
+
+/// Measure how content propagates by gossip through a simulated network, for every combination
+/// of the given gossip fanouts.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "trin-simulator")]
+struct SimulatorConfig {
+    /// Number of nodes in the network, at least 1.
+    #[structopt(long, default_value = "32", parse(try_from_str = parse_node_count))]
+    nodes: usize,
+
+    /// Number of content items to seed in each run.
+    #[structopt(long, default_value = "16")]
+    content_count: usize,
+
+    /// Size of each content item, in bytes.
+    #[structopt(long, default_value = "1024")]
+    content_size: usize,
+
+    /// Data radius of every node, as the maximum distance shifted right by this many bits. Each
+    /// increment halves the share of the content a node is interested in.
+    #[structopt(long, default_value = "0")]
+    radius_shift: usize,
+
+    /// Maximum number of nodes per bucket of each routing table.
+    #[structopt(long, default_value = "16")]
+    bucket_size: usize,
+
+    /// Numbers of closest interested peers to offer content to, e.g. 2,4,8.
+    #[structopt(long, use_delimiter = true, default_value = "4")]
+    closest: Vec<usize>,
+
+    /// Numbers of random interested peers to offer content to, e.g. 0,2,4.
+    #[structopt(long, use_delimiter = true, default_value = "4")]
+    random: Vec<usize>,
+
+    /// Time in seconds after which content that has not reached every interested node is
+    /// abandoned.
+    #[structopt(long, default_value = "30")]
+    timeout: u64,
+
+    /// UDP port of the first node of the first run. Each run uses its own range of ports.
+    #[structopt(long, default_value = "9100")]
+    base_port: u16,
+
+    /// Log directives, as for trin's --log.filter.
+    #[structopt(long, default_value = "warn")]
+    log_filter: String,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let config = SimulatorConfig::from_args();
+    init_tracing_logger(Some(&config.log_filter));
+
+    let radius = Distance::from(U256::MAX >> config.radius_shift);
+    let fanouts: Vec<GossipFanout> = config
+        .closest
+        .iter()
+        .flat_map(|closest| {
+            config.random.iter().map(|random| GossipFanout {
+                closest: *closest,
+                random: *random,
+            })
+        })
+        .collect();
+
+    println!(
+        "{:>8} {:>7} {:>12} {:>11} {:>9} {:>9} {:>9}",
+        "closest", "random", "replication", "full items", "p50", "p90", "max"
+    );
+    for (run, gossip_fanout) in fanouts.into_iter().enumerate() {
+        let base_port = usize::from(config.base_port) + run * config.nodes;
+        if base_port + config.nodes > usize::from(u16::MAX) + 1 {
+            anyhow::bail!("Ran out of UDP ports for run {run}");
+        }
+        let simulation = SimulationConfig {
+            network: NetworkConfig {
+                node_count: config.nodes,
+                base_port: base_port as u16,
+                radius,
+                bucket_size: config.bucket_size,
+                gossip_fanout,
+            },
+            content_count: config.content_count,
+            content_size: config.content_size,
+            timeout: Duration::from_secs(config.timeout),
+        };
+        let report = run_simulation(&simulation).await?;
+        print_row(gossip_fanout, &report);
+    }
+    Ok(())
+}
+
+/// Parses a node count, which must be at least 1 so that content can be seeded.
+fn parse_node_count(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>().map_err(|err| err.to_string())? {
+        0 => Err("The network must have at least 1 node".to_string()),
+        nodes => Ok(nodes),
+    }
+}
+
+fn print_row(gossip_fanout: GossipFanout, report: &SimulationReport) {
+    let latency = |percentile| match report.latency_percentile(percentile) {
+        Some(latency) => format!("{}ms", latency.as_millis()),
+        None => "-".to_string(),
+    };
+    println!(
+        "{:>8} {:>7} {:>11.1}% {:>10.1}% {:>9} {:>9} {:>9}",
+        gossip_fanout.closest,
+        gossip_fanout.random,
+        report.replication() * 100.0,
+        report.fully_replicated() * 100.0,
+        latency(50.0),
+        latency(90.0),
+        latency(100.0),
+    );
+}
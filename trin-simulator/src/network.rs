@@ -0,0 +1,222 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::anyhow;
+use discv5::TalkRequest;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::{debug, warn};
+use utp_rs::socket::UtpSocket;
+
+use portalnet::{
+    discovery::{Discovery, Discv5UdpSocket},
+    events::PortalnetEvents,
+    overlay::{GossipFanout, OverlayConfig, OverlayProtocol},
+    socket,
+    storage::{DistanceFunction, MemoryContentStore},
+    types::messages::{Message, PortalnetConfig, ProtocolId},
+};
+use trin_types::content_key::IdentityContentKey;
+use trin_types::distance::{Distance, XorMetric};
+use trin_types::enr::Enr;
+use trin_validation::validator::MockValidator;
+
+pub type SimOverlay =
+    OverlayProtocol<IdentityContentKey, XorMetric, MockValidator, MemoryContentStore>;
+
+/// Distances requested from a peer when joining the network. Nodes with random IDs are almost
+/// all within the farthest few buckets of each other.
+const JOIN_DISTANCES: [u16; 3] = [256, 255, 254];
+
+/// Parameters of the simulated network, shared by every node.
+#[derive(Clone, Copy, Debug)]
+pub struct NetworkConfig {
+    pub node_count: usize,
+    /// UDP port of the first node. Node `i` listens on `base_port + i`.
+    pub base_port: u16,
+    pub radius: Distance,
+    pub bucket_size: usize,
+    pub gossip_fanout: GossipFanout,
+}
+
+/// A node of the simulated network: a history overlay over its own Discovery v5 instance, which
+/// stores content in memory and accepts all content as valid.
+pub struct SimNode {
+    pub overlay: Arc<SimOverlay>,
+    pub store: Arc<RwLock<MemoryContentStore>>,
+    /// Tasks that route and answer the requests received by the node.
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl SimNode {
+    async fn spawn(
+        config: &NetworkConfig,
+        index: usize,
+        bootnode: Option<Enr>,
+    ) -> anyhow::Result<Self> {
+        let ip_addr = socket::find_assigned_ip()
+            .ok_or_else(|| anyhow!("Could not find an IP for local connections"))?;
+        let port = config.base_port + index as u16;
+        let portal_config = PortalnetConfig {
+            listen_port: port,
+            external_addr: Some(SocketAddr::new(ip_addr, port)),
+            no_stun: true,
+            ..PortalnetConfig::default()
+        };
+        let mut discovery = Discovery::new(portal_config)
+            .map_err(|err| anyhow!("Unable to create discovery for node {index}: {err}"))?;
+        let talk_req_rx = discovery
+            .start()
+            .await
+            .map_err(|err| anyhow!("Unable to start discovery for node {index}: {err}"))?;
+        let discovery = Arc::new(discovery);
+
+        let (utp_talk_req_tx, utp_talk_req_rx) = mpsc::unbounded_channel();
+        let utp_socket = Arc::new(UtpSocket::with_socket(Discv5UdpSocket::new(
+            Arc::clone(&discovery),
+            utp_talk_req_rx,
+        )));
+
+        let mut store =
+            MemoryContentStore::new(discovery.local_enr().node_id(), DistanceFunction::Xor);
+        store.set_radius(config.radius);
+        let store = Arc::new(RwLock::new(store));
+
+        let overlay_config = OverlayConfig {
+            bootnode_enrs: bootnode.into_iter().collect(),
+            bucket_size: config.bucket_size,
+            gossip_fanout: config.gossip_fanout,
+            ..OverlayConfig::default()
+        };
+        let overlay = Arc::new(
+            OverlayProtocol::new(
+                overlay_config,
+                discovery,
+                utp_socket,
+                Arc::clone(&store),
+                ProtocolId::History,
+                Arc::new(MockValidator {}),
+            )
+            .await,
+        );
+
+        let (overlay_tx, overlay_rx) = mpsc::unbounded_channel();
+        let events =
+            PortalnetEvents::new(talk_req_rx, Some(overlay_tx), None, utp_talk_req_tx).await;
+        let tasks = vec![
+            tokio::spawn(events.start()),
+            tokio::spawn(serve_overlay(Arc::clone(&overlay), overlay_rx)),
+        ];
+
+        Ok(Self {
+            overlay,
+            store,
+            tasks,
+        })
+    }
+
+    /// Stops the node: aborts its tasks and those of its overlay service, and shuts down its
+    /// Discovery v5 instance, which closes its UDP socket.
+    fn shutdown(self) {
+        for task in self
+            .tasks
+            .into_iter()
+            .chain(self.overlay.take_service_tasks())
+        {
+            task.abort();
+        }
+        self.overlay.discovery.shutdown();
+    }
+
+    /// Joins the network through `peer`: pings it, asks it for the nodes it knows, and pings those
+    /// nodes in turn.
+    async fn join(&self, peer: Enr) {
+        if let Err(err) = self.overlay.send_ping(peer.clone()).await {
+            warn!(%err, "Unable to ping peer while joining");
+            return;
+        }
+        let nodes = match self
+            .overlay
+            .send_find_nodes(peer, JOIN_DISTANCES.to_vec())
+            .await
+        {
+            Ok(nodes) => nodes,
+            Err(err) => {
+                warn!(%err, "Unable to find nodes while joining");
+                return;
+            }
+        };
+        let local_node_id = self.overlay.local_enr().node_id();
+        for enr in nodes.enrs.into_iter().map(Enr::from) {
+            if enr.node_id() == local_node_id {
+                continue;
+            }
+            if let Err(err) = self.overlay.send_ping(enr).await {
+                debug!(%err, "Unable to ping node found while joining");
+            }
+        }
+    }
+}
+
+/// Answers the overlay requests received by a node.
+async fn serve_overlay(
+    overlay: Arc<SimOverlay>,
+    mut talk_req_rx: mpsc::UnboundedReceiver<TalkRequest>,
+) {
+    while let Some(talk_req) = talk_req_rx.recv().await {
+        let overlay = Arc::clone(&overlay);
+        tokio::spawn(async move {
+            let reply = match overlay.process_one_request(&talk_req).await {
+                Ok(response) => Message::from(response).into(),
+                Err(err) => {
+                    debug!(%err, "Error processing request, responding with empty TALKRESP");
+                    "".into()
+                }
+            };
+            if let Err(err) = talk_req.respond(reply) {
+                debug!(%err, "Error responding to TALKREQ");
+            }
+        });
+    }
+}
+
+/// Spawns a network of `config.node_count` nodes, which all bootstrap from the first node.
+///
+/// Each node joins through the first node as it is spawned. Nodes that joined early only know
+/// the nodes that joined before them, so every node then joins again through a random peer from
+/// its routing table.
+pub async fn spawn_network(config: &NetworkConfig) -> anyhow::Result<Vec<SimNode>> {
+    let mut nodes: Vec<SimNode> = Vec::with_capacity(config.node_count);
+    for index in 0..config.node_count {
+        let bootnode = nodes.first().map(|node| node.overlay.local_enr());
+        let node = match SimNode::spawn(config, index, bootnode.clone()).await {
+            Ok(node) => node,
+            Err(err) => {
+                shutdown_network(nodes);
+                return Err(err);
+            }
+        };
+        if let Some(bootnode) = bootnode {
+            node.join(bootnode).await;
+        }
+        nodes.push(node);
+    }
+    for node in nodes.iter() {
+        let peer = node
+            .overlay
+            .table_entries_enr()
+            .choose(&mut rand::thread_rng())
+            .cloned();
+        if let Some(peer) = peer {
+            node.join(peer).await;
+        }
+    }
+    Ok(nodes)
+}
+
+/// Stops every node of a network, so that a later run does not share the process with it.
+pub fn shutdown_network(nodes: Vec<SimNode>) {
+    for node in nodes {
+        node.shutdown();
+    }
+}
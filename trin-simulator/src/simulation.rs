@@ -0,0 +1,235 @@
+use std::time::{Duration, Instant};
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+use tokio::time;
+use tracing::warn;
+
+use portalnet::storage::ContentStore;
+use trin_types::content_key::{IdentityContentKey, OverlayContentKey};
+use trin_types::distance::{Metric, XorMetric};
+
+use crate::network::{shutdown_network, spawn_network, NetworkConfig, SimNode};
+
+/// Time given to the routing tables to settle after the network is spawned, before content is
+/// seeded.
+const SETTLE_DURATION: Duration = Duration::from_secs(2);
+
+/// Interval at which the stores of the nodes are checked for seeded content.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Parameters of a single simulation run.
+#[derive(Clone, Copy, Debug)]
+pub struct SimulationConfig {
+    pub network: NetworkConfig,
+    /// Number of content items to seed. Each item is seeded at a random node.
+    pub content_count: usize,
+    /// Size of each content item, in bytes.
+    pub content_size: usize,
+    /// Time after which content that has not reached every interested node is abandoned.
+    pub timeout: Duration,
+}
+
+/// How a single content item propagated through the network.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ItemResult {
+    /// Number of nodes, besides the one it was seeded at, whose radius covers the content.
+    pub interested: usize,
+    /// Time after seeding at which the content was stored by each interested node that received
+    /// it.
+    pub arrivals: Vec<Duration>,
+}
+
+impl ItemResult {
+    /// Whether the content reached every interested node.
+    pub fn fully_replicated(&self) -> bool {
+        self.arrivals.len() == self.interested
+    }
+}
+
+/// The outcome of a simulation run.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationReport {
+    pub items: Vec<ItemResult>,
+}
+
+impl SimulationReport {
+    /// Fraction of interested nodes that stored the content, across all items.
+    pub fn replication(&self) -> f64 {
+        let interested: usize = self.items.iter().map(|item| item.interested).sum();
+        if interested == 0 {
+            return 1.0;
+        }
+        let arrivals: usize = self.items.iter().map(|item| item.arrivals.len()).sum();
+        arrivals as f64 / interested as f64
+    }
+
+    /// Fraction of items that reached every interested node.
+    pub fn fully_replicated(&self) -> f64 {
+        if self.items.is_empty() {
+            return 1.0;
+        }
+        let count = self
+            .items
+            .iter()
+            .filter(|item| item.fully_replicated())
+            .count();
+        count as f64 / self.items.len() as f64
+    }
+
+    /// The `percentile` of the times at which content arrived at interested nodes, across all
+    /// items, or `None` if no content arrived anywhere.
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut arrivals: Vec<Duration> = self
+            .items
+            .iter()
+            .flat_map(|item| item.arrivals.iter().copied())
+            .collect();
+        arrivals.sort();
+        percentile_of_sorted(&arrivals, percentile)
+    }
+}
+
+/// Returns the nearest-rank `percentile`, between 0 and 100, of sorted `values`.
+fn percentile_of_sorted(values: &[Duration], percentile: f64) -> Option<Duration> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (percentile / 100.0 * values.len() as f64).ceil() as usize;
+    Some(values[rank.clamp(1, values.len()) - 1])
+}
+
+/// A seeded content item, and the interested nodes that have yet to store it.
+struct PendingItem {
+    key: IdentityContentKey,
+    seeded_at: Instant,
+    pending: Vec<usize>,
+    result: ItemResult,
+}
+
+/// Spawns a network, seeds content at random nodes, and measures how the content propagates by
+/// gossip. The network is shut down once the run ends.
+pub async fn run_simulation(config: &SimulationConfig) -> anyhow::Result<SimulationReport> {
+    let nodes = spawn_network(&config.network).await?;
+    time::sleep(SETTLE_DURATION).await;
+
+    let mut items: Vec<PendingItem> = (0..config.content_count)
+        .map(|_| seed_content(&nodes, config))
+        .collect();
+
+    let deadline = Instant::now() + config.timeout;
+    while Instant::now() < deadline && items.iter().any(|item| !item.pending.is_empty()) {
+        time::sleep(POLL_INTERVAL).await;
+        for item in items.iter_mut() {
+            let elapsed = item.seeded_at.elapsed();
+            let key = &item.key;
+            let result = &mut item.result;
+            item.pending.retain(|index| {
+                let stored = matches!(nodes[*index].store.read().get(key), Ok(Some(_)));
+                if stored {
+                    result.arrivals.push(elapsed);
+                }
+                !stored
+            });
+        }
+    }
+    shutdown_network(nodes);
+
+    Ok(SimulationReport {
+        items: items.into_iter().map(|item| item.result).collect(),
+    })
+}
+
+/// Stores a random content item at a random node and gossips it from there.
+fn seed_content(nodes: &[SimNode], config: &SimulationConfig) -> PendingItem {
+    let mut rng = rand::thread_rng();
+    let key = IdentityContentKey::new(rng.gen());
+    let mut value = vec![0; config.content_size];
+    rng.fill_bytes(&mut value);
+
+    let indices: Vec<usize> = (0..nodes.len()).collect();
+    let seed = *indices
+        .choose(&mut rng)
+        .expect("network has at least one node");
+    // Gossip is offered to peers whose radius strictly covers the content.
+    let content_id = key.content_id();
+    let pending: Vec<usize> = indices
+        .into_iter()
+        .filter(|index| *index != seed)
+        .filter(|index| {
+            let node_id = nodes[*index].overlay.local_enr().node_id();
+            XorMetric::distance(&content_id, &node_id.raw()) < config.network.radius
+        })
+        .collect();
+
+    let seed_node = &nodes[seed];
+    if let Err(err) = seed_node.store.write().put(key.clone(), &value) {
+        warn!(%err, "Unable to store seeded content");
+    }
+    let seeded_at = Instant::now();
+    seed_node
+        .overlay
+        .propagate_gossip(vec![(key.clone(), value)]);
+
+    PendingItem {
+        key,
+        seeded_at,
+        result: ItemResult {
+            interested: pending.len(),
+            arrivals: vec![],
+        },
+        pending,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    fn item(interested: usize, arrivals_ms: &[u64]) -> ItemResult {
+        ItemResult {
+            interested,
+            arrivals: arrivals_ms
+                .iter()
+                .map(|ms| Duration::from_millis(*ms))
+                .collect(),
+        }
+    }
+
+    #[rstest]
+    #[case(0.0, 10)]
+    #[case(50.0, 30)]
+    #[case(90.0, 50)]
+    #[case(100.0, 50)]
+    fn latency_percentile(#[case] percentile: f64, #[case] expected_ms: u64) {
+        let report = SimulationReport {
+            items: vec![item(3, &[50, 10, 30]), item(2, &[20, 40])],
+        };
+        assert_eq!(
+            report.latency_percentile(percentile),
+            Some(Duration::from_millis(expected_ms))
+        );
+    }
+
+    #[test]
+    fn replication() {
+        let report = SimulationReport {
+            items: vec![item(4, &[10, 20]), item(2, &[10, 20]), item(0, &[])],
+        };
+        assert_eq!(report.replication(), 4.0 / 6.0);
+        assert_eq!(report.fully_replicated(), 2.0 / 3.0);
+        assert_eq!(
+            report.latency_percentile(50.0),
+            Some(Duration::from_millis(10))
+        );
+    }
+
+    #[test]
+    fn empty_report() {
+        let report = SimulationReport::default();
+        assert_eq!(report.replication(), 1.0);
+        assert_eq!(report.fully_replicated(), 1.0);
+        assert_eq!(report.latency_percentile(50.0), None);
+    }
+}
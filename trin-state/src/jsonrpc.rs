@@ -1,9 +1,11 @@
 use std::sync::Arc;
 
+use serde_json::{json, Value};
 use tokio::sync::mpsc;
-use tracing::error;
 
 use crate::network::StateNetwork;
+use ethportal_api::types::portal::SummaryInfo;
+use trin_types::jsonrpc::endpoints::StateEndpoint;
 use trin_types::jsonrpc::request::StateJsonRpcRequest;
 
 /// Handles State network JSON-RPC requests
@@ -14,8 +16,28 @@ pub struct StateRequestHandler {
 
 impl StateRequestHandler {
     pub async fn handle_client_queries(mut self) {
-        while let Some(_request) = self.state_rx.recv().await {
-            error!("State JSON-RPC handler is not implemented!")
+        while let Some(request) = self.state_rx.recv().await {
+            let response = self.handle_request(&request.endpoint);
+            let _ = request.resp.send(response);
+        }
+    }
+
+    fn handle_request(&self, endpoint: &StateEndpoint) -> Result<Value, String> {
+        match endpoint {
+            StateEndpoint::DataRadius => {
+                let radius = &self.network.overlay.data_radius();
+                Ok(json!(**radius))
+            }
+            StateEndpoint::Summary => {
+                let summary = SummaryInfo {
+                    storage: self.network.overlay.store.read().get_storage_summary(),
+                    messages: self.network.overlay.get_message_summary(),
+                };
+                Ok(json!(summary))
+            }
+            other => Err(format!(
+                "State network endpoint {other:?} is not implemented"
+            )),
         }
     }
 }
@@ -31,6 +31,7 @@ type StateHandler = Option<StateRequestHandler>;
 type StateNetworkTask = Option<JoinHandle<()>>;
 type StateEventTx = Option<mpsc::UnboundedSender<TalkRequest>>;
 type StateJsonRpcTx = Option<mpsc::UnboundedSender<StateJsonRpcRequest>>;
+type StateBackgroundTasks = Vec<(&'static str, JoinHandle<()>)>;
 
 pub async fn initialize_state_network(
     discovery: &Arc<Discovery>,
@@ -38,7 +39,13 @@ pub async fn initialize_state_network(
     portalnet_config: PortalnetConfig,
     storage_config: PortalStorageConfig,
     header_oracle: Arc<RwLock<HeaderOracle>>,
-) -> anyhow::Result<(StateHandler, StateNetworkTask, StateEventTx, StateJsonRpcTx)> {
+) -> anyhow::Result<(
+    StateHandler,
+    StateNetworkTask,
+    StateEventTx,
+    StateJsonRpcTx,
+    StateBackgroundTasks,
+)> {
     let (state_jsonrpc_tx, state_jsonrpc_rx) = mpsc::unbounded_channel::<StateJsonRpcRequest>();
     let (state_event_tx, state_event_rx) = mpsc::unbounded_channel::<TalkRequest>();
     let state_network = StateNetwork::new(
@@ -56,11 +63,18 @@ pub async fn initialize_state_network(
     };
     let state_network_task =
         spawn_state_network(Arc::clone(&state_network), portalnet_config, state_event_rx);
+    let background_tasks = state_network
+        .overlay
+        .take_service_tasks()
+        .into_iter()
+        .map(|task| ("state_overlay", task))
+        .collect();
     Ok((
         Some(state_handler),
         Some(state_network_task),
         Some(state_event_tx),
         Some(state_jsonrpc_tx),
+        background_tasks,
     ))
 }
 
@@ -86,14 +100,8 @@ pub fn spawn_state_network(
             event_rx: state_event_rx,
         };
 
-        // Spawn state event handler
-        tokio::spawn(state_events.start());
-
-        // hacky test: make sure we establish a session with the boot node
-        network.overlay.ping_bootnodes().await;
-
-        tokio::signal::ctrl_c()
-            .await
-            .expect("failed to pause until ctrl-c");
+        // Handle state events within this task, so that aborting the task stops the handler.
+        // hacky test: meanwhile, make sure we establish a session with the boot node
+        tokio::join!(state_events.start(), network.overlay.ping_bootnodes());
     })
 }
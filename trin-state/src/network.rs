@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use discv5::enr::NodeId;
 use eth_trie::EthTrie;
 use parking_lot::RwLock as PLRwLock;
 use tokio::sync::RwLock;
@@ -9,10 +8,12 @@ use utp_rs::socket::UtpSocket;
 use ethportal_api::StateContentKey;
 use portalnet::{
     discovery::{Discovery, UtpEnr},
-    overlay::{OverlayConfig, OverlayProtocol},
+    kv_store::KvStore,
+    overlay::{OverlayConfig, OverlayProtocol, RequestTimeouts},
     storage::{PortalStorage, PortalStorageConfig},
     types::messages::{PortalnetConfig, ProtocolId},
 };
+use trin_types::cli::STATE_NETWORK;
 use trin_types::distance::XorMetric;
 use trin_validation::oracle::HeaderOracle;
 
@@ -33,18 +34,38 @@ impl StateNetwork {
         portal_config: PortalnetConfig,
         header_oracle: Arc<RwLock<HeaderOracle>>,
     ) -> anyhow::Result<Self> {
-        // todo: revisit triedb location
-        let db = PortalStorage::setup_rocksdb(NodeId::random())?;
+        let db = KvStore::open_with_options(
+            &storage_config.node_data_dir.join("state_trie"),
+            &storage_config.db_options,
+        )?;
         let triedb = TrieDB::new(Arc::new(db));
         let trie = EthTrie::new(Arc::new(triedb));
 
-        let storage = Arc::new(PLRwLock::new(PortalStorage::new(
+        let storage = Arc::new(PLRwLock::new(PortalStorage::new::<StateContentKey>(
             storage_config,
             ProtocolId::State,
         )?));
         let validator = Arc::new(StateValidator { header_oracle });
         let config = OverlayConfig {
             bootnode_enrs: portal_config.bootnode_enrs.clone(),
+            ping_extensions: portal_config.ping_extensions.clone(),
+            bucket_refresh_interval: portal_config.bucket_refresh_interval,
+            bucket_size: portal_config.bucket_size,
+            table_depth: portal_config.table_depth,
+            bucket_pending_timeout: portal_config.bucket_pending_timeout,
+            max_incoming_per_bucket: portal_config.max_incoming_per_bucket,
+            request_timeouts: RequestTimeouts::default()
+                .with_overrides(STATE_NETWORK, &portal_config.talkreq_timeouts),
+            query_timeout: portal_config.lookup_timeout,
+            query_peer_timeout: portal_config.lookup_peer_timeout,
+            query_peer_attempts: portal_config.lookup_peer_attempts,
+            query_num_results: portal_config.lookup_num_results,
+            lookup_cache_size: portal_config.lookup_cache_size,
+            lookup_cache_ttl: portal_config.lookup_cache_ttl,
+            validation_cache_size: portal_config.validation_cache_size,
+            utp_transfer_limit: portal_config.utp_transfer_limit,
+            neighborhood_sync_limit: portal_config.neighborhood_sync_limit,
+            events: portal_config.events.clone(),
             ..Default::default()
         };
         let overlay = OverlayProtocol::new(
@@ -1,17 +1,19 @@
 use std::sync::Arc;
 
+use portalnet::{kv_store::KvStore, storage::ContentStoreError};
+
 pub struct TrieDB {
-    db: Arc<rocksdb::DB>,
+    db: Arc<KvStore>,
 }
 
 impl TrieDB {
-    pub fn new(db: Arc<rocksdb::DB>) -> TrieDB {
+    pub fn new(db: Arc<KvStore>) -> TrieDB {
         TrieDB { db }
     }
 }
 
 impl eth_trie::DB for TrieDB {
-    type Error = rocksdb::Error;
+    type Error = ContentStoreError;
 
     fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
         self.db.get(key)
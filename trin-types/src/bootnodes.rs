@@ -50,21 +50,50 @@ impl From<Bootnodes> for Vec<Enr> {
 impl FromStr for Bootnodes {
     type Err = anyhow::Error;
 
+    /// Parses `default`, `none`, or a comma-delimited list of ENRs. The list may include the
+    /// `default` keyword, to add the ENRs to the default bootnodes.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "default" => Ok(Bootnodes::Default),
             "none" => Ok(Bootnodes::None),
             _ => {
-                let bootnodes: Result<Vec<Enr>, _> = s.split(',').map(Enr::from_str).collect();
-                match bootnodes {
-                    Ok(val) => Ok(Bootnodes::Custom(val)),
-                    Err(_) => Err(anyhow!("Invalid bootnode argument")),
+                let mut bootnodes = vec![];
+                for bootnode in s.split(',') {
+                    match bootnode {
+                        "default" => bootnodes.extend(DEFAULT_BOOTNODES.iter().cloned()),
+                        _ => bootnodes.push(
+                            Enr::from_str(bootnode)
+                                .map_err(|_| anyhow!("Invalid bootnode argument"))?,
+                        ),
+                    }
                 }
+                Ok(Bootnodes::Custom(dedup_bootnodes(bootnodes)))
             }
         }
     }
 }
 
+/// Returns the bootnodes, followed by the extra bootnodes that they don't already include.
+pub fn with_extra_bootnodes(bootnodes: Bootnodes, extra_bootnodes: Vec<Enr>) -> Vec<Enr> {
+    let mut bootnodes: Vec<Enr> = bootnodes.into();
+    bootnodes.extend(extra_bootnodes);
+    dedup_bootnodes(bootnodes)
+}
+
+/// Removes bootnodes with the same node id as an earlier bootnode.
+fn dedup_bootnodes(bootnodes: Vec<Enr>) -> Vec<Enr> {
+    let mut deduped: Vec<Enr> = Vec::with_capacity(bootnodes.len());
+    for bootnode in bootnodes {
+        if !deduped
+            .iter()
+            .any(|enr| enr.node_id() == bootnode.node_id())
+        {
+            deduped.push(bootnode);
+        }
+    }
+    deduped
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod test {
@@ -72,6 +101,8 @@ mod test {
     use crate::cli::TrinConfig;
     use rstest::rstest;
 
+    const ENR: &str = "enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg";
+
     #[test_log::test]
     fn test_bootnodes_default_with_testnet_bootnodes() {
         let config = TrinConfig::new_from(["trin"].iter()).unwrap();
@@ -88,6 +119,34 @@ mod test {
         assert_eq!(bootnodes.len(), 0);
     }
 
+    #[test_log::test]
+    fn test_bootnodes_default_with_custom_enrs() {
+        let config =
+            TrinConfig::new_from(["trin", "--bootnodes", &format!("default,{ENR}")].iter())
+                .unwrap();
+        let bootnodes: Vec<Enr> = config.bootnodes.into();
+        assert_eq!(bootnodes.len(), DEFAULT_BOOTNODES.len() + 1);
+        assert_eq!(bootnodes.last(), Some(&Enr::from_str(ENR).unwrap()));
+    }
+
+    #[test_log::test]
+    fn test_extra_bootnodes_extend_bootnodes() {
+        let config = TrinConfig::new_from(["trin", "--extra-bootnodes", ENR].iter()).unwrap();
+        assert_eq!(config.bootnodes, Bootnodes::Default);
+        let bootnodes = with_extra_bootnodes(config.bootnodes, config.extra_bootnodes);
+        assert_eq!(bootnodes.len(), DEFAULT_BOOTNODES.len() + 1);
+
+        // Extra bootnodes that are already bootnodes are not duplicated.
+        let bootnodes = with_extra_bootnodes(Bootnodes::Default, DEFAULT_BOOTNODES.to_vec());
+        assert_eq!(bootnodes, DEFAULT_BOOTNODES.to_vec());
+
+        let config =
+            TrinConfig::new_from(["trin", "--bootnodes", "none", "--extra-bootnodes", ENR].iter())
+                .unwrap();
+        let bootnodes = with_extra_bootnodes(config.bootnodes, config.extra_bootnodes);
+        assert_eq!(bootnodes, vec![Enr::from_str(ENR).unwrap()]);
+    }
+
     #[rstest]
     #[case("invalid")]
     #[case("enr:-IS4QBISSFfBzsBrjq61iSIxPMfp5ShBTW6KQUglzH_tj8_SJaehXdlnZI-NAkTGeoclwnTB-pU544BQA44BiDZ2rkMBgmlkgnY0gmlwhKEjVaWJc2VjcDI1NmsxoQOSGugH1jSdiE_fRK1FIBe9oLxaWH8D_7xXSnaOVBe-SYN1ZHCCIyg,invalid")]
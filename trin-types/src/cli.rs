@@ -1,10 +1,26 @@
-use std::{env, ffi::OsString, fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsString,
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use ethereum_types::H256;
+use anyhow::anyhow;
+use ethereum_types::{H256, U256};
+use serde::Deserialize;
 use structopt::StructOpt;
 use url::Url;
 
+use trin_utils::bytes::hex_decode;
+
 use crate::bootnodes::Bootnodes;
+use crate::content_key::HISTORY_CONTENT_TYPES;
+use crate::distance::Distance;
+use crate::enr::Enr;
 use crate::provider::TrustedProviderType;
 
 pub const DEFAULT_MASTER_ACC_PATH: &str = "src/assets/merge_macc.bin";
@@ -14,9 +30,110 @@ const DEFAULT_DISCOVERY_PORT: &str = "9000";
 pub const HISTORY_NETWORK: &str = "history";
 pub const STATE_NETWORK: &str = "state";
 const DEFAULT_SUBNETWORKS: &str = "history";
+
+/// Boolean flags that are raised by their environment variable, named by `env_var_name`.
+const ENV_FLAGS: &[&str] = &[
+    "no-stun",
+    "no-storage",
+    "archive",
+    "ephemeral",
+    "check",
+    "ping-extensions",
+];
+
+/// Flags with a value that is read from their environment variable, named by `env_var_name`,
+/// if they are not on the command line.
+const ENV_OPTIONS: &[&str] = &[
+    "web3-transport",
+    "web3-http-address",
+    "web3-ipc-path",
+    "web3-http-listener",
+    "web3-max-request-size",
+    "web3-max-response-size",
+    "web3-ipc-mode",
+    "web3-ipc-group",
+    "web3-tls-cert",
+    "web3-tls-key",
+    "web3-modules",
+    "web3-exclude-modules",
+    "admin-shutdown-token",
+    "discovery-port",
+    "bootnodes",
+    "extra-bootnodes",
+    "external-address",
+    "unsafe-private-key",
+    "networks",
+    "kb",
+    "fixed-radius",
+    "enable-metrics-with-url",
+    "data-dir",
+    "db-paths",
+    "extra-nodes-config",
+    "db-block-cache-mb",
+    "db-write-buffer-mb",
+    "db-compression",
+    "db-max-open-files",
+    "db-compaction-style",
+    "compaction-interval-hours",
+    "regossip-interval-secs",
+    "regossip-sample-size",
+    "trusted-provider",
+    "trusted-provider-url",
+    "trusted-provider-jwt-secret",
+    "consensus-rpc",
+    "light-client-checkpoint",
+    "master-accumulator-path",
+    "glados-report-url",
+    "stats-export",
+    "webhook-url",
+    "webhook-content-types",
+    "webhook-key-prefixes",
+    "bucket-refresh-interval-secs",
+    "bucket-size",
+    "table-depth",
+    "bucket-pending-timeout-secs",
+    "max-incoming-per-bucket",
+    "talkreq-timeouts",
+    "discv5-request-timeout-ms",
+    "lookup-timeout-secs",
+    "lookup-peer-timeout-secs",
+    "lookup-peer-attempts",
+    "lookup-num-results",
+    "lookup-cache-size",
+    "lookup-cache-ttl-secs",
+    "validation-cache-size",
+    "utp-transfer-limit",
+    "neighborhood-sync-limit",
+    "telemetry.record",
+    "telemetry.lookup-sample-rate",
+    "telemetry.gossip-sample-rate",
+    "log.filter",
+];
 pub const DEFAULT_STORAGE_CAPACITY: &str = "100000"; // 100mb
+const DEFAULT_COMPACTION_INTERVAL_HOURS: &str = "0";
+const DEFAULT_REGOSSIP_INTERVAL_SECS: &str = "600";
+const DEFAULT_REGOSSIP_SAMPLE_SIZE: &str = "8";
+const DEFAULT_BUCKET_REFRESH_INTERVAL_SECS: &str = "60";
+const DEFAULT_BUCKET_SIZE: &str = "16";
+const DEFAULT_TABLE_DEPTH: &str = "17";
+const DEFAULT_BUCKET_PENDING_TIMEOUT_SECS: &str = "60";
+const DEFAULT_MAX_INCOMING_PER_BUCKET: &str = "16";
+const DEFAULT_DISCV5_REQUEST_TIMEOUT_MS: &str = "1000";
+const DEFAULT_LOOKUP_TIMEOUT_SECS: &str = "60";
+const DEFAULT_LOOKUP_PEER_TIMEOUT_SECS: &str = "10";
+const DEFAULT_LOOKUP_PEER_ATTEMPTS: &str = "1";
+const DEFAULT_LOOKUP_NUM_RESULTS: &str = "16";
+const DEFAULT_LOOKUP_CACHE_SIZE: &str = "256";
+const DEFAULT_LOOKUP_CACHE_TTL_SECS: &str = "30";
+const DEFAULT_VALIDATION_CACHE_SIZE: &str = "1024";
+const DEFAULT_UTP_TRANSFER_LIMIT: &str = "1";
+const DEFAULT_NEIGHBORHOOD_SYNC_LIMIT: &str = "256";
+const DEFAULT_TELEMETRY_SAMPLE_RATE: &str = "1.0";
 pub const DEFAULT_TRUSTED_PROVIDER: &str = "infura";
 pub const DEFAULT_WEB3_TRANSPORT: &str = "ipc";
+// 10 MiB, the default of the JSON-RPC servers.
+const DEFAULT_WEB3_MAX_REQUEST_SIZE: &str = "10485760";
+const DEFAULT_WEB3_MAX_RESPONSE_SIZE: &str = "10485760";
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Web3TransportType {
@@ -57,12 +174,198 @@ impl FromStr for Web3TransportType {
     }
 }
 
+/// Compression of RocksDB data blocks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DbCompression {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+impl FromStr for DbCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(DbCompression::None),
+            "snappy" => Ok(DbCompression::Snappy),
+            "lz4" => Ok(DbCompression::Lz4),
+            "zstd" => Ok(DbCompression::Zstd),
+            _ => Err(format!(
+                "Invalid db compression '{s}'. Expected one of 'none', 'snappy', 'lz4' or 'zstd'"
+            )),
+        }
+    }
+}
+
+/// Compaction style of RocksDB.
+///
+/// FIFO compaction is not offered: it drops the oldest files once the database reaches its size
+/// limit, which would delete state trie nodes that are still referenced.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DbCompactionStyle {
+    Level,
+    Universal,
+}
+
+impl FromStr for DbCompactionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "level" => Ok(DbCompactionStyle::Level),
+            "universal" => Ok(DbCompactionStyle::Universal),
+            "fifo" => Err(
+                "FIFO compaction would drop state data, use 'level' or 'universal' instead"
+                    .to_string(),
+            ),
+            _ => Err(format!(
+                "Invalid db compaction style '{s}'. Expected one of 'level' or 'universal'"
+            )),
+        }
+    }
+}
+
+/// Kind of outbound overlay request, for which a timeout can be configured.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TalkReqKind {
+    Ping,
+    FindNodes,
+    FindContent,
+    Offer,
+}
+
+impl FromStr for TalkReqKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ping" => Ok(TalkReqKind::Ping),
+            "find_nodes" => Ok(TalkReqKind::FindNodes),
+            "find_content" => Ok(TalkReqKind::FindContent),
+            "offer" => Ok(TalkReqKind::Offer),
+            _ => Err(format!(
+                "Invalid request kind '{s}'. Expected one of 'ping', 'find_nodes', 'find_content' or 'offer'"
+            )),
+        }
+    }
+}
+
+/// Timeout of one kind of outbound request on one subnetwork, given as
+/// `<subnetwork>.<request kind>=<milliseconds>`, e.g. `history.find_content=8000`, or with a
+/// duration unit, e.g. `history.find_content=8s`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TalkReqTimeout {
+    pub subnetwork: String,
+    pub kind: TalkReqKind,
+    pub timeout: Duration,
+}
+
+impl FromStr for TalkReqTimeout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("Invalid request timeout '{s}'. Expected <subnetwork>.<request kind>=<milliseconds>")
+        };
+        let (target, millis) = s.split_once('=').ok_or_else(invalid)?;
+        let (subnetwork, kind) = target.split_once('.').ok_or_else(invalid)?;
+        if subnetwork != HISTORY_NETWORK && subnetwork != STATE_NETWORK {
+            return Err(format!(
+                "Invalid subnetwork '{subnetwork}'. Expected '{HISTORY_NETWORK}' or '{STATE_NETWORK}'"
+            ));
+        }
+        let millis = parse_duration(millis, Duration::from_millis(1), "millisecond")?;
+        if millis == 0 {
+            return Err(format!(
+                "Request timeout '{s}' must be at least 1 millisecond"
+            ));
+        }
+        Ok(TalkReqTimeout {
+            subnetwork: subnetwork.to_string(),
+            kind: kind.parse()?,
+            timeout: Duration::from_millis(millis),
+        })
+    }
+}
+
+/// Namespaces of the JSON-RPC methods, which can be exposed independently per transport and
+/// HTTP listener.
+///
+/// The `portal_*` methods are split by subnetwork into the `history` and `state` modules.
+pub const RPC_MODULES: [&str; 7] = [
+    "admin", "debug", "discv5", "eth", "history", "state", "web3",
+];
+/// Modules exposed unless others are listed. `admin` and `debug` must be opted into.
+pub const DEFAULT_RPC_MODULES: &str = "discv5,eth,history,state,web3";
+
+/// An additional HTTP JSON-RPC listener, as `<url>=<module>,<module>,...`, e.g.
+/// `http://172.17.0.1:8545/=history,eth`. Without a module list, the default modules are exposed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HttpListener {
+    pub address: Url,
+    pub modules: Vec<String>,
+}
+
+impl FromStr for HttpListener {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, modules) = match s.split_once('=') {
+            Some((address, modules)) => (address, modules.split(',').map(str::trim).collect()),
+            None => (s, DEFAULT_RPC_MODULES.split(',').collect()),
+        };
+        let address = Url::parse(address)
+            .map_err(|err| format!("Invalid listener address '{address}': {err}"))?;
+        let mut listener_modules: Vec<String> = vec![];
+        for module in modules {
+            if !RPC_MODULES.contains(&module) {
+                return Err(format!(
+                    "Invalid JSON-RPC module '{module}'. Expected one of {RPC_MODULES:?}"
+                ));
+            }
+            if !listener_modules.iter().any(|m| m == module) {
+                listener_modules.push(module.to_string());
+            }
+        }
+        Ok(HttpListener {
+            address,
+            modules: listener_modules,
+        })
+    }
+}
+
+/// A logical node run in the same process as the node configured on the command line, with its
+/// own key, discovery port and storage capacity. Read from the JSON list of nodes given with
+/// `--extra-nodes-config`.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ExtraNodeConfig {
+    pub private_key: H256,
+    pub discovery_port: u16,
+    /// Storage capacity in kB, that of the command line node if absent.
+    pub kb: Option<u32>,
+    pub external_address: Option<SocketAddr>,
+}
+
+impl ExtraNodeConfig {
+    /// Reads the list of extra nodes from the JSON file at `path`.
+    pub fn from_file(path: &Path) -> anyhow::Result<Vec<Self>> {
+        let nodes = fs::read_to_string(path)
+            .map_err(|err| anyhow!("Unable to read {}: {err}", path.display()))?;
+        serde_json::from_str(&nodes)
+            .map_err(|err| anyhow!("Invalid extra nodes config {}: {err}", path.display()))
+    }
+}
+
 #[derive(StructOpt, Debug, PartialEq, Clone)]
 #[structopt(
     name = "trin",
     version = "0.0.1",
     author = "carver",
-    about = "Run an eth portal client"
+    about = "Run an eth portal client",
+    after_help = "Every flag can also be set with a TRIN_* environment variable, named after the long flag in upper snake case, e.g. TRIN_WEB3_TRANSPORT for --web3-transport or TRIN_LOG_FILTER for --log.filter. Flags without a value are raised with 'true' or '1'. The command line takes precedence over the environment."
 )]
 pub struct TrinConfig {
     #[structopt(
@@ -86,6 +389,82 @@ pub struct TrinConfig {
     )]
     pub web3_ipc_path: String, // TODO: Change to PathBuf
 
+    #[structopt(
+        long = "web3-http-listener",
+        help = "Additional address to accept json-rpc http connections on, with the modules to expose there, as <url>=<module>,<module>, e.g. http://172.17.0.1:8545/=history,eth. Modules are 'admin', 'debug', 'discv5', 'eth', 'history', 'state' and 'web3', all but 'admin' and 'debug' if omitted. May be repeated",
+        number_of_values = 1
+    )]
+    pub web3_http_listeners: Vec<HttpListener>,
+
+    #[structopt(
+        long = "web3-max-request-size",
+        help = "Maximum size of a json-rpc request body, in bytes or with a unit, e.g. '50MiB'. Larger requests are rejected with an error",
+        default_value(DEFAULT_WEB3_MAX_REQUEST_SIZE),
+        parse(try_from_str = parse_body_size)
+    )]
+    pub web3_max_request_size: u32,
+
+    #[structopt(
+        long = "web3-max-response-size",
+        help = "Maximum size of a json-rpc response body, in bytes or with a unit, e.g. '50MiB'. Larger responses are replaced with an error",
+        default_value(DEFAULT_WEB3_MAX_RESPONSE_SIZE),
+        parse(try_from_str = parse_body_size)
+    )]
+    pub web3_max_response_size: u32,
+
+    #[structopt(
+        long = "web3-ipc-mode",
+        help = "Octal permission bits of the json-rpc IPC socket, e.g. 660. Defaults to the umask trin runs with",
+        parse(try_from_str = parse_file_mode)
+    )]
+    pub web3_ipc_mode: Option<u32>,
+
+    #[structopt(
+        long = "web3-ipc-group",
+        help = "Name or id of the group owning the json-rpc IPC socket"
+    )]
+    pub web3_ipc_group: Option<String>,
+
+    #[structopt(
+        long = "web3-tls-cert",
+        help = "Path to a PEM encoded certificate chain, to serve json-rpc over https. Requires --web3-tls-key",
+        requires = "web3_tls_key",
+        parse(from_os_str)
+    )]
+    pub web3_tls_cert: Option<PathBuf>,
+
+    #[structopt(
+        long = "web3-tls-key",
+        help = "Path to the PEM encoded private key of the --web3-tls-cert certificate",
+        requires = "web3_tls_cert",
+        parse(from_os_str)
+    )]
+    pub web3_tls_key: Option<PathBuf>,
+
+    #[structopt(
+        long = "web3-modules",
+        help = "Comma-separated JSON-RPC modules to expose on the http address or IPC socket. 'admin' and 'debug' are only exposed if listed",
+        default_value = DEFAULT_RPC_MODULES,
+        possible_values(&RPC_MODULES),
+        use_delimiter = true
+    )]
+    pub web3_modules: Vec<String>,
+
+    #[structopt(
+        long = "web3-exclude-modules",
+        help = "Comma-separated JSON-RPC modules to hide from every transport and listener, even if listed by --web3-modules or --web3-http-listener",
+        possible_values(&RPC_MODULES),
+        use_delimiter = true
+    )]
+    pub web3_exclude_modules: Vec<String>,
+
+    #[structopt(
+        long = "admin-shutdown-token",
+        hide_env_values = true,
+        help = "Enable the admin_shutdown json-rpc endpoint, which stops trin gracefully when called with this token"
+    )]
+    pub admin_shutdown_token: Option<String>,
+
     #[structopt(
         default_value(DEFAULT_DISCOVERY_PORT),
         long = "discovery-port",
@@ -100,6 +479,13 @@ pub struct TrinConfig {
     )]
     pub bootnodes: Bootnodes,
 
+    #[structopt(
+        long = "extra-bootnodes",
+        help = "One or more comma-delimited base64-encoded ENR's of peers to add to the bootnodes, e.g. to add private nodes without losing the default bootnodes",
+        use_delimiter = true
+    )]
+    pub extra_bootnodes: Vec<Enr>,
+
     #[structopt(
         long = "external-address",
         group = "external-ips",
@@ -117,6 +503,7 @@ pub struct TrinConfig {
     #[structopt(
         validator(check_private_key_length),
         long = "unsafe-private-key",
+        hide_env_values = true,
         help = "Hex encoded 32 byte private key (with 0x prefix) (considered unsafe as it's stored in terminal history - keyfile support coming soon)"
     )]
     pub private_key: Option<H256>,
@@ -133,10 +520,32 @@ pub struct TrinConfig {
     #[structopt(
         default_value(DEFAULT_STORAGE_CAPACITY),
         long,
-        help = "Maximum number of kilobytes of total data to store in the DB"
+        help = "Maximum total data to store in the DB, in kilobytes or with a unit, e.g. '2GB' or '500MB'",
+        parse(try_from_str = parse_kb)
     )]
     pub kb: u32,
 
+    #[structopt(
+        long = "fixed-radius",
+        help = "Pin the advertised and enforced data radius instead of deriving it from storage utilization. Either a fraction of the key space between 0 and 1, or a 0x-prefixed hex distance.",
+        parse(try_from_str = parse_fixed_radius)
+    )]
+    pub fixed_radius: Option<Distance>,
+
+    #[structopt(
+        long = "no-storage",
+        help = "Participate in the overlay without storing any content, e.g. for bridge and monitoring nodes. Advertises a zero radius, declines all offers and keeps databases in a temporary directory. Lookups and gossip still work.",
+        conflicts_with_all(&["data_dir", "db_paths", "fixed_radius"])
+    )]
+    pub no_storage: bool,
+
+    #[structopt(
+        long = "archive",
+        help = "Keep all content seen, regardless of its distance, with the radius pinned at the maximum and no pruning. Storage grows without bound: --kb only sets the size above which a warning is logged.",
+        conflicts_with_all(&["fixed_radius", "no_storage"])
+    )]
+    pub archive: bool,
+
     #[structopt(
         long = "enable-metrics-with-url",
         help = "Enable prometheus metrics reporting (provide local IP/Port from which your Prometheus server is configured to fetch metrics)"
@@ -150,9 +559,95 @@ pub struct TrinConfig {
     )]
     pub ephemeral: bool,
 
+    #[structopt(
+        long = "data-dir",
+        help = "Root directory for node data. Overrides the TRIN_DATA_PATH environment variable and the platform's default data directory. Each node keeps its data in a subdirectory named after its node id.",
+        conflicts_with = "ephemeral",
+        parse(from_os_str)
+    )]
+    pub data_dir: Option<PathBuf>,
+
+    #[structopt(
+        long = "db-paths",
+        alias = "db.paths",
+        help = "Comma-separated list of directories to shard the content database across, e.g. on several disks. Content is assigned to a directory by content id. The list must not change once content is stored.",
+        use_delimiter = true,
+        parse(from_os_str)
+    )]
+    pub db_paths: Vec<PathBuf>,
+
+    #[structopt(
+        long = "extra-nodes-config",
+        help = "JSON file listing additional nodes to run in this process, each with its own privateKey, discoveryPort and optional kb and externalAddress. They run the same networks and share the data directory, but serve no JSON-RPC.",
+        parse(from_os_str)
+    )]
+    pub extra_nodes_config: Option<PathBuf>,
+
+    #[structopt(
+        long = "check",
+        help = "Check the configuration, the availability of the discovery and JSON-RPC ports, the master accumulator, the trusted provider and a bootnode, print a JSON report and exit, instead of starting the node. Exits with status 1 if a check fails."
+    )]
+    pub check: bool,
+
+    #[structopt(
+        long = "db-block-cache-mb",
+        help = "Size of the RocksDB block cache, in megabytes or with a unit, e.g. '1GB'. Defaults to the RocksDB default.",
+        parse(try_from_str = parse_mb)
+    )]
+    pub db_block_cache_mb: Option<usize>,
+
+    #[structopt(
+        long = "db-write-buffer-mb",
+        help = "Size of the RocksDB write buffer (memtable), in megabytes or with a unit, e.g. '256MB'. Defaults to the RocksDB default.",
+        parse(try_from_str = parse_mb)
+    )]
+    pub db_write_buffer_mb: Option<usize>,
+
+    #[structopt(
+        long = "db-compression",
+        help = "Compression of RocksDB data blocks: 'none', 'snappy', 'lz4' or 'zstd'. Defaults to the RocksDB default."
+    )]
+    pub db_compression: Option<DbCompression>,
+
+    #[structopt(
+        long = "db-max-open-files",
+        help = "Maximum number of files RocksDB keeps open, or -1 for no limit. Defaults to the RocksDB default."
+    )]
+    pub db_max_open_files: Option<i32>,
+
+    #[structopt(
+        long = "db-compaction-style",
+        help = "Compaction style of RocksDB: 'level' or 'universal'. Defaults to 'level'."
+    )]
+    pub db_compaction_style: Option<DbCompactionStyle>,
+
+    #[structopt(
+        long = "compaction-interval-hours",
+        help = "Hours between compactions of the content database, which return the space of pruned content to the file system, or a duration with a unit, e.g. '7d'. Defaults to 0, which disables scheduled compaction.",
+        default_value(DEFAULT_COMPACTION_INTERVAL_HOURS),
+        parse(try_from_str = parse_hours)
+    )]
+    pub compaction_interval_hours: u64,
+
+    #[structopt(
+        long = "regossip-interval-secs",
+        help = "Seconds between checks of whether a sample of the locally stored history content can be served by the closest peers whose radius covers it, or a duration with a unit, e.g. '10m'. Content that too few peers return is gossiped again. 0 disables the checks.",
+        default_value(DEFAULT_REGOSSIP_INTERVAL_SECS),
+        parse(try_from_str = parse_secs)
+    )]
+    pub regossip_interval_secs: u64,
+
+    #[structopt(
+        long = "regossip-sample-size",
+        help = "Number of locally stored content items whose replication is checked every --regossip-interval-secs.",
+        default_value(DEFAULT_REGOSSIP_SAMPLE_SIZE),
+        validator(check_nonzero_count)
+    )]
+    pub regossip_sample_size: usize,
+
     #[structopt(
         long = "trusted-provider",
-        help = "Trusted provider to use. (options: 'infura' (default), 'pandaops' (devops) or 'custom')",
+        help = "Trusted provider to use. (options: 'infura' (default), 'pandaops' (devops), 'custom' or 'none')",
         default_value(DEFAULT_TRUSTED_PROVIDER)
     )]
     pub trusted_provider: TrustedProviderType,
@@ -164,6 +659,27 @@ pub struct TrinConfig {
     )]
     pub trusted_provider_url: Option<Url>,
 
+    #[structopt(
+        long = "trusted-provider-jwt-secret",
+        help = "Path to the hex-encoded JWT secret of a custom trusted provider's authenticated port, e.g. an execution client's engine API port.",
+        parse(from_os_str)
+    )]
+    pub trusted_provider_jwt_secret: Option<PathBuf>,
+
+    #[structopt(
+        long = "consensus-rpc",
+        help = "URL of a beacon node API. A light client follows the chain head through it, so that recent headers can be validated. Without it, recent headers are rejected.",
+        validator(check_url_format)
+    )]
+    pub consensus_rpc: Option<Url>,
+
+    #[structopt(
+        long = "light-client-checkpoint",
+        help = "Trusted beacon block root to bootstrap the light client from. Defaults to the light client's built-in checkpoint",
+        requires = "consensus_rpc"
+    )]
+    pub light_client_checkpoint: Option<String>,
+
     #[structopt(
         long = "master-accumulator-path",
         help = "Path to master accumulator for validation",
@@ -171,6 +687,229 @@ pub struct TrinConfig {
         parse(from_os_str)
     )]
     pub master_acc_path: PathBuf,
+
+    #[structopt(
+        long = "glados-report-url",
+        help = "Periodically submit node health and content audit reports to this Glados endpoint",
+        validator(check_url_format)
+    )]
+    pub glados_report_url: Option<Url>,
+
+    #[structopt(
+        long = "stats-export",
+        help = "Periodically append a snapshot of node statistics to this file, as CSV if the file has a .csv extension or as JSON lines otherwise",
+        parse(from_os_str)
+    )]
+    pub stats_export: Option<PathBuf>,
+
+    #[structopt(
+        long = "webhook-url",
+        help = "POST a JSON notification to this URL whenever history content received from the network is stored",
+        validator(check_url_format)
+    )]
+    pub webhook_url: Option<Url>,
+
+    #[structopt(
+        long = "webhook-content-types",
+        help = "Comma-separated types of content to send webhook notifications for. Defaults to all types.",
+        possible_values(&HISTORY_CONTENT_TYPES),
+        use_delimiter = true
+    )]
+    pub webhook_content_types: Vec<String>,
+
+    #[structopt(
+        long = "webhook-key-prefixes",
+        help = "Comma-separated 0x-prefixed hex prefixes of the content keys to send webhook notifications for, e.g. 0x00,0x01. Defaults to all content keys.",
+        parse(try_from_str = parse_hex_prefix),
+        use_delimiter = true
+    )]
+    pub webhook_key_prefixes: Vec<Vec<u8>>,
+
+    #[structopt(
+        long = "ping-extensions",
+        help = "Advertise client info and supported subnetworks in the custom payload of PING and PONG messages"
+    )]
+    pub ping_extensions: bool,
+
+    #[structopt(
+        long = "bucket-refresh-interval-secs",
+        help = "Seconds between random lookups that refresh sparse buckets of each overlay routing table, or a duration with a unit, e.g. '5m'",
+        default_value(DEFAULT_BUCKET_REFRESH_INTERVAL_SECS),
+        parse(try_from_str = parse_nonzero_secs)
+    )]
+    pub bucket_refresh_interval_secs: u64,
+
+    #[structopt(
+        long = "bucket-size",
+        help = "Maximum number of nodes per bucket of each overlay routing table, between 1 and 16",
+        default_value(DEFAULT_BUCKET_SIZE),
+        validator(check_bucket_size)
+    )]
+    pub bucket_size: usize,
+
+    #[structopt(
+        long = "table-depth",
+        help = "Number of buckets farthest from the local node that are looked up to populate each overlay routing table, between 1 and 255. Larger networks fill more buckets.",
+        default_value(DEFAULT_TABLE_DEPTH),
+        validator(check_table_depth)
+    )]
+    pub table_depth: usize,
+
+    #[structopt(
+        long = "bucket-pending-timeout-secs",
+        help = "Seconds after which a node waiting for a place in a full routing table bucket replaces an unresponsive node, or a duration with a unit, e.g. '1m'",
+        default_value(DEFAULT_BUCKET_PENDING_TIMEOUT_SECS),
+        parse(try_from_str = parse_secs)
+    )]
+    pub bucket_pending_timeout_secs: u64,
+
+    #[structopt(
+        long = "max-incoming-per-bucket",
+        help = "Maximum number of nodes per routing table bucket that connected to the local node, rather than the other way around",
+        default_value(DEFAULT_MAX_INCOMING_PER_BUCKET)
+    )]
+    pub max_incoming_per_bucket: usize,
+
+    #[structopt(
+        long = "talkreq-timeouts",
+        help = "Comma-separated timeouts of outbound requests by subnetwork and request kind, as <subnetwork>.<request kind>=<milliseconds or duration with a unit>, e.g. history.find_content=8s. Request kinds are 'ping', 'find_nodes', 'find_content' and 'offer'.",
+        use_delimiter = true
+    )]
+    pub talkreq_timeouts: Vec<TalkReqTimeout>,
+
+    #[structopt(
+        long = "discv5-request-timeout-ms",
+        help = "Milliseconds after which discv5 retries an unanswered request, once, and then fails it, or a duration with a unit, e.g. '2s'. This bounds every outbound request, including those with longer --talkreq-timeouts.",
+        default_value(DEFAULT_DISCV5_REQUEST_TIMEOUT_MS),
+        parse(try_from_str = parse_nonzero_millis)
+    )]
+    pub discv5_request_timeout_ms: u64,
+
+    #[structopt(
+        long = "lookup-timeout-secs",
+        help = "Seconds after which a node or content lookup is abandoned, or a duration with a unit, e.g. '2m'. Content lookups that are abandoned are reported as timed out.",
+        default_value(DEFAULT_LOOKUP_TIMEOUT_SECS),
+        parse(try_from_str = parse_nonzero_secs)
+    )]
+    pub lookup_timeout_secs: u64,
+
+    #[structopt(
+        long = "lookup-peer-timeout-secs",
+        help = "Seconds a node or content lookup waits for a response from a single peer before moving on, or a duration with a unit, e.g. '30s'",
+        default_value(DEFAULT_LOOKUP_PEER_TIMEOUT_SECS),
+        parse(try_from_str = parse_nonzero_secs)
+    )]
+    pub lookup_peer_timeout_secs: u64,
+
+    #[structopt(
+        long = "lookup-peer-attempts",
+        help = "Number of times a content lookup requests a peer that fails or does not respond before abandoning it",
+        default_value(DEFAULT_LOOKUP_PEER_ATTEMPTS),
+        validator(check_nonzero_count)
+    )]
+    pub lookup_peer_attempts: usize,
+
+    #[structopt(
+        long = "lookup-num-results",
+        help = "Number of closest peers that must respond before a node lookup finishes, or before a content lookup reports the content as absent",
+        default_value(DEFAULT_LOOKUP_NUM_RESULTS),
+        validator(check_nonzero_count)
+    )]
+    pub lookup_num_results: usize,
+
+    #[structopt(
+        long = "lookup-cache-size",
+        help = "Number of recent content lookup results to serve repeated lookups from. 0 disables the cache.",
+        default_value(DEFAULT_LOOKUP_CACHE_SIZE)
+    )]
+    pub lookup_cache_size: usize,
+
+    #[structopt(
+        long = "lookup-cache-ttl-secs",
+        help = "Seconds for which content found by a lookup is served from the lookup cache, or a duration with a unit, e.g. '1m'. Content that was not found is cached for at most 5 seconds.",
+        default_value(DEFAULT_LOOKUP_CACHE_TTL_SECS),
+        parse(try_from_str = parse_secs)
+    )]
+    pub lookup_cache_ttl_secs: u64,
+
+    #[structopt(
+        long = "validation-cache-size",
+        help = "Number of validated content items to remember, so that content received from several peers is validated once. 0 disables the cache.",
+        default_value(DEFAULT_VALIDATION_CACHE_SIZE)
+    )]
+    pub validation_cache_size: usize,
+
+    #[structopt(
+        long = "utp-transfer-limit",
+        help = "Maximum number of uTP streams over which the content accepted from one OFFER is sent concurrently, one item per stream. Values above 1 advertise the concurrentOfferStreams capability in PING/PONG extensions (requires --ping-extensions), and per-item streams are only used with peers that advertise it too. All other transfers use a single stream.",
+        default_value(DEFAULT_UTP_TRANSFER_LIMIT),
+        validator(check_nonzero_count)
+    )]
+    pub utp_transfer_limit: usize,
+
+    #[structopt(
+        long = "neighborhood-sync-limit",
+        help = "Maximum number of locally stored content keys to offer to each peer that joins the routing table with a radius overlapping ours, closest to the peer first. Offers are sent at most once per second. 0 disables the offers.",
+        default_value(DEFAULT_NEIGHBORHOOD_SYNC_LIMIT)
+    )]
+    pub neighborhood_sync_limit: usize,
+
+    #[structopt(
+        long = "log.filter",
+        help = "Comma-separated log filter directives, e.g. 'info,portalnet::overlay=debug,utp=trace'. Defaults to RUST_LOG if set, or else 'info'."
+    )]
+    pub log_filter: Option<String>,
+
+    #[structopt(
+        long = "telemetry.record",
+        help = "Record every lookup, with its hops, and every gossip decision as JSON lines in this file, for offline analysis",
+        parse(from_os_str)
+    )]
+    pub telemetry_record: Option<PathBuf>,
+
+    #[structopt(
+        long = "telemetry.lookup-sample-rate",
+        help = "Fraction of lookups to record with --telemetry.record, between 0 and 1",
+        default_value(DEFAULT_TELEMETRY_SAMPLE_RATE),
+        parse(try_from_str = parse_sample_rate)
+    )]
+    pub telemetry_lookup_sample_rate: f64,
+
+    #[structopt(
+        long = "telemetry.gossip-sample-rate",
+        help = "Fraction of gossip decisions to record with --telemetry.record, between 0 and 1",
+        default_value(DEFAULT_TELEMETRY_SAMPLE_RATE),
+        parse(try_from_str = parse_sample_rate)
+    )]
+    pub telemetry_gossip_sample_rate: f64,
+
+    #[structopt(subcommand)]
+    pub command: Option<TrinCommand>,
+}
+
+/// Commands that trin runs instead of starting a node.
+#[derive(StructOpt, Debug, PartialEq, Eq, Clone)]
+pub enum TrinCommand {
+    /// Print the version, git commit, build date, target and features that trin was built with
+    Version {
+        #[structopt(long = "json", help = "Print the build information as JSON")]
+        json: bool,
+    },
+    /// Replace the node key given with --unsafe-private-key with a new one, moving the node's
+    /// data to the new node id. Stop the node before rotating its key.
+    RotateKey {
+        #[structopt(
+            long = "keep-content",
+            help = "Keep all stored content, instead of dropping the content outside the radius around the new node id"
+        )]
+        keep_content: bool,
+    },
+    /// Print the history network activity counters of every node under the data directory,
+    /// summed over all of its runs
+    Stats {
+        #[structopt(long = "json", help = "Print the counters as JSON")]
+        json: bool,
+    },
 }
 
 impl Default for TrinConfig {
@@ -181,10 +920,28 @@ impl Default for TrinConfig {
             web3_http_address: Url::parse(DEFAULT_WEB3_HTTP_ADDRESS)
                 .expect("Parsing static DEFAULT_WEB3_HTTP_ADDRESS to work"),
             web3_ipc_path: DEFAULT_WEB3_IPC_PATH.to_string(),
+            web3_http_listeners: vec![],
+            web3_modules: DEFAULT_RPC_MODULES
+                .split(',')
+                .map(|m| m.to_string())
+                .collect(),
+            web3_exclude_modules: vec![],
+            web3_max_request_size: DEFAULT_WEB3_MAX_REQUEST_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_WEB3_MAX_REQUEST_SIZE to work"),
+            web3_max_response_size: DEFAULT_WEB3_MAX_RESPONSE_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_WEB3_MAX_RESPONSE_SIZE to work"),
+            web3_ipc_mode: None,
+            web3_ipc_group: None,
+            web3_tls_cert: None,
+            web3_tls_key: None,
+            admin_shutdown_token: None,
             discovery_port: DEFAULT_DISCOVERY_PORT
                 .parse()
                 .expect("Parsing static DEFAULT_DISCOVERY_PORT to work"),
             bootnodes: Bootnodes::Default,
+            extra_bootnodes: vec![],
             external_addr: None,
             no_stun: false,
             private_key: None,
@@ -195,25 +952,128 @@ impl Default for TrinConfig {
             kb: DEFAULT_STORAGE_CAPACITY
                 .parse()
                 .expect("Parsing static DEFAULT_STORAGE_CAPACITY to work"),
+            fixed_radius: None,
+            no_storage: false,
+            archive: false,
             enable_metrics_with_url: None,
             ephemeral: false,
+            data_dir: None,
+            db_paths: vec![],
+            extra_nodes_config: None,
+            check: false,
+            db_block_cache_mb: None,
+            db_write_buffer_mb: None,
+            db_compression: None,
+            db_max_open_files: None,
+            db_compaction_style: None,
+            compaction_interval_hours: DEFAULT_COMPACTION_INTERVAL_HOURS
+                .parse()
+                .expect("Parsing static DEFAULT_COMPACTION_INTERVAL_HOURS to work"),
+            regossip_interval_secs: DEFAULT_REGOSSIP_INTERVAL_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_REGOSSIP_INTERVAL_SECS to work"),
+            regossip_sample_size: DEFAULT_REGOSSIP_SAMPLE_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_REGOSSIP_SAMPLE_SIZE to work"),
             trusted_provider: TrustedProviderType::Infura,
             trusted_provider_url: None,
+            trusted_provider_jwt_secret: None,
+            consensus_rpc: None,
+            light_client_checkpoint: None,
             master_acc_path: PathBuf::from(DEFAULT_MASTER_ACC_PATH.to_string()),
-        }
-    }
-}
+            glados_report_url: None,
+            stats_export: None,
+            webhook_url: None,
+            webhook_content_types: vec![],
+            webhook_key_prefixes: vec![],
+            ping_extensions: false,
+            bucket_refresh_interval_secs: DEFAULT_BUCKET_REFRESH_INTERVAL_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_BUCKET_REFRESH_INTERVAL_SECS to work"),
+            bucket_size: DEFAULT_BUCKET_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_BUCKET_SIZE to work"),
+            table_depth: DEFAULT_TABLE_DEPTH
+                .parse()
+                .expect("Parsing static DEFAULT_TABLE_DEPTH to work"),
+            bucket_pending_timeout_secs: DEFAULT_BUCKET_PENDING_TIMEOUT_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_BUCKET_PENDING_TIMEOUT_SECS to work"),
+            max_incoming_per_bucket: DEFAULT_MAX_INCOMING_PER_BUCKET
+                .parse()
+                .expect("Parsing static DEFAULT_MAX_INCOMING_PER_BUCKET to work"),
+            talkreq_timeouts: vec![],
+            discv5_request_timeout_ms: DEFAULT_DISCV5_REQUEST_TIMEOUT_MS
+                .parse()
+                .expect("Parsing static DEFAULT_DISCV5_REQUEST_TIMEOUT_MS to work"),
+            lookup_timeout_secs: DEFAULT_LOOKUP_TIMEOUT_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_TIMEOUT_SECS to work"),
+            lookup_peer_timeout_secs: DEFAULT_LOOKUP_PEER_TIMEOUT_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_PEER_TIMEOUT_SECS to work"),
+            lookup_peer_attempts: DEFAULT_LOOKUP_PEER_ATTEMPTS
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_PEER_ATTEMPTS to work"),
+            lookup_num_results: DEFAULT_LOOKUP_NUM_RESULTS
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_NUM_RESULTS to work"),
+            lookup_cache_size: DEFAULT_LOOKUP_CACHE_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_CACHE_SIZE to work"),
+            lookup_cache_ttl_secs: DEFAULT_LOOKUP_CACHE_TTL_SECS
+                .parse()
+                .expect("Parsing static DEFAULT_LOOKUP_CACHE_TTL_SECS to work"),
+            validation_cache_size: DEFAULT_VALIDATION_CACHE_SIZE
+                .parse()
+                .expect("Parsing static DEFAULT_VALIDATION_CACHE_SIZE to work"),
+            utp_transfer_limit: DEFAULT_UTP_TRANSFER_LIMIT
+                .parse()
+                .expect("Parsing static DEFAULT_UTP_TRANSFER_LIMIT to work"),
+            neighborhood_sync_limit: DEFAULT_NEIGHBORHOOD_SYNC_LIMIT
+                .parse()
+                .expect("Parsing static DEFAULT_NEIGHBORHOOD_SYNC_LIMIT to work"),
+            log_filter: None,
+            telemetry_record: None,
+            telemetry_lookup_sample_rate: DEFAULT_TELEMETRY_SAMPLE_RATE
+                .parse()
+                .expect("Parsing static DEFAULT_TELEMETRY_SAMPLE_RATE to work"),
+            telemetry_gossip_sample_rate: DEFAULT_TELEMETRY_SAMPLE_RATE
+                .parse()
+                .expect("Parsing static DEFAULT_TELEMETRY_SAMPLE_RATE to work"),
+            command: None,
+        }
+    }
+}
 
 impl TrinConfig {
     pub fn from_cli() -> Self {
         Self::new_from(env::args_os()).unwrap_or_else(|e| e.exit())
     }
+    /// Parses the config from `args`, and from the `TRIN_*` variables of the process
+    /// environment for flags that are not in `args`.
     pub fn new_from<I, T>(args: I) -> Result<Self, clap::Error>
     where
         I: Iterator<Item = T>,
         T: Into<OsString> + Clone,
     {
-        let config = Self::from_iter_safe(args)?;
+        let env_vars: HashMap<String, String> = env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+        Self::new_from_env(args, &env_vars)
+    }
+
+    /// Parses the config from `args`, and from the `TRIN_*` variables in `env_vars` for flags
+    /// that are not in `args`.
+    pub fn new_from_env<I, T>(
+        args: I,
+        env_vars: &HashMap<String, String>,
+    ) -> Result<Self, clap::Error>
+    where
+        I: Iterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let config = Self::from_iter_safe(with_env_vars(args, env_vars)?)?;
 
         match config.web3_transport {
             Web3TransportType::HTTP => match &config.web3_ipc_path[..] {
@@ -225,15 +1085,30 @@ impl TrinConfig {
                 p => panic!("Must not supply an http address when using ipc protocol for json-rpc (received: {p})"),
             }
         }
+        if config.web3_transport == Web3TransportType::IPC && config.web3_tls_cert.is_some() {
+            panic!("--web3-tls-cert and --web3-tls-key require '--web3-transport http'.")
+        }
+        if config.web3_transport == Web3TransportType::IPC && !config.web3_http_listeners.is_empty()
+        {
+            panic!("--web3-http-listener requires '--web3-transport http'.")
+        }
+        if config.web3_transport == Web3TransportType::HTTP
+            && (config.web3_ipc_mode.is_some() || config.web3_ipc_group.is_some())
+        {
+            panic!("--web3-ipc-mode and --web3-ipc-group require '--web3-transport ipc'.")
+        }
 
         match config.trusted_provider_url {
             Some(_) => {
                 if config.trusted_provider == TrustedProviderType::Infura {
                     panic!("--trusted-provider-url flag is incompatible with infura as the trusted provider.")
                 }
+                if config.trusted_provider == TrustedProviderType::None {
+                    panic!("--trusted-provider-url flag is incompatible with '--trusted-provider none'.")
+                }
             }
             None => match config.trusted_provider {
-                TrustedProviderType::Infura => {}
+                TrustedProviderType::Infura | TrustedProviderType::None => {}
                 TrustedProviderType::Pandaops => panic!(
                     "'--trusted-provider pandaops' choice requires the --trusted-provider-url flag."
                 ),
@@ -242,6 +1117,11 @@ impl TrinConfig {
                 ),
             },
         }
+        if config.trusted_provider_jwt_secret.is_some()
+            && config.trusted_provider != TrustedProviderType::Custom
+        {
+            panic!("--trusted-provider-jwt-secret flag requires '--trusted-provider custom'.")
+        }
         // Should not serve http over same port as localhost provider.
         if config.web3_transport == Web3TransportType::HTTP
             && config.trusted_provider == TrustedProviderType::Custom
@@ -256,6 +1136,123 @@ impl TrinConfig {
         }
         Ok(config)
     }
+
+    /// Returns the nodes listed in the `--extra-nodes-config` file, if any. No two nodes of the
+    /// process may share a key or a discovery port.
+    pub fn extra_nodes(&self) -> anyhow::Result<Vec<ExtraNodeConfig>> {
+        let nodes = match &self.extra_nodes_config {
+            Some(path) => ExtraNodeConfig::from_file(path)?,
+            None => return Ok(vec![]),
+        };
+        let mut ports = HashSet::from([self.discovery_port]);
+        let mut keys: HashSet<H256> = self.private_key.into_iter().collect();
+        for node in &nodes {
+            if !ports.insert(node.discovery_port) {
+                return Err(anyhow!(
+                    "Discovery port {} is used by more than one node",
+                    node.discovery_port
+                ));
+            }
+            if !keys.insert(node.private_key) {
+                return Err(anyhow!(
+                    "Extra node on port {} reuses the private key of another node",
+                    node.discovery_port
+                ));
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Returns the configuration of the extra node `node`. It runs the same networks with the same
+    /// tuning and data directory as this node, but without the outputs that only one node of the
+    /// process can own, like the stats export, telemetry recording and webhook.
+    pub fn for_extra_node(&self, node: &ExtraNodeConfig) -> Self {
+        Self {
+            private_key: Some(node.private_key),
+            discovery_port: node.discovery_port,
+            kb: node.kb.unwrap_or(self.kb),
+            external_addr: node.external_address,
+            extra_nodes_config: None,
+            enable_metrics_with_url: None,
+            stats_export: None,
+            telemetry_record: None,
+            webhook_url: None,
+            command: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the `modules` that are not excluded by `--web3-exclude-modules`.
+    pub fn exposed_modules(&self, modules: &[String]) -> Vec<String> {
+        modules
+            .iter()
+            .filter(|module| !self.web3_exclude_modules.contains(module))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Returns the environment variable of a flag, e.g. `TRIN_LOG_FILTER` for `log.filter`.
+fn env_var_name(flag: &str) -> String {
+    format!("TRIN_{}", flag.replace(['-', '.'], "_").to_uppercase())
+}
+
+/// Returns whether the long flag is on the command line, with or without an inline value.
+fn has_flag(args: &[OsString], flag: &str) -> bool {
+    let flag = format!("--{flag}");
+    args.iter().filter_map(|arg| arg.to_str()).any(|arg| {
+        arg == flag
+            || arg
+                .strip_prefix(flag.as_str())
+                .map_or(false, |rest| rest.starts_with('='))
+    })
+}
+
+/// Adds the flags set by variables in `env_vars` to `args`, unless they are already on the
+/// command line. The flags are parsed with the rest of the arguments, so that invalid values and
+/// conflicts with other flags are still reported. Variables set to an empty string are ignored.
+fn with_env_vars<I, T>(
+    args: I,
+    env_vars: &HashMap<String, String>,
+) -> Result<Vec<OsString>, clap::Error>
+where
+    I: Iterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let mut args: Vec<OsString> = args.map(Into::into).collect();
+    let mut env_args: Vec<OsString> = vec![];
+    for flag in ENV_FLAGS {
+        let var = env_var_name(flag);
+        let value = match env_vars.get(&var) {
+            Some(value) => value,
+            None => continue,
+        };
+        let raised = match value.to_lowercase().as_str() {
+            "true" | "1" => true,
+            "false" | "0" | "" => false,
+            _ => {
+                return Err(clap::Error::with_description(
+                    &format!("Invalid value '{value}' for {var}: expected true or false"),
+                    clap::ErrorKind::InvalidValue,
+                ))
+            }
+        };
+        if raised && !has_flag(&args, flag) {
+            env_args.push(format!("--{flag}").into());
+        }
+    }
+    for flag in ENV_OPTIONS {
+        match env_vars.get(&env_var_name(flag)) {
+            Some(value) if !value.is_empty() && !has_flag(&args, flag) => {
+                env_args.push(format!("--{flag}={value}").into());
+            }
+            _ => {}
+        }
+    }
+    // Flags go before any subcommand, right after the program name.
+    let position = args.len().min(1);
+    args.splice(position..position, env_args);
+    Ok(args)
 }
 
 /// A validator function for CLI URL arguments.
@@ -266,6 +1263,217 @@ fn check_url_format(url: String) -> Result<(), String> {
     }
 }
 
+fn check_nonzero_count(count: String) -> Result<(), String> {
+    match count.parse::<usize>() {
+        Ok(0) => Err("Count must be at least 1".to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Invalid count '{count}', {err}")),
+    }
+}
+
+fn check_bucket_size(bucket_size: String) -> Result<(), String> {
+    match bucket_size.parse::<usize>() {
+        Ok(1..=16) => Ok(()),
+        _ => Err(format!(
+            "Invalid bucket size '{bucket_size}', must be between 1 and 16"
+        )),
+    }
+}
+
+fn check_table_depth(table_depth: String) -> Result<(), String> {
+    match table_depth.parse::<usize>() {
+        Ok(1..=255) => Ok(()),
+        _ => Err(format!(
+            "Invalid table depth '{table_depth}', must be between 1 and 255"
+        )),
+    }
+}
+
+/// Parses a data radius given either as a fraction of the key space between 0 and 1, or as a
+/// 0x-prefixed hex distance.
+fn parse_fixed_radius(value: &str) -> Result<Distance, String> {
+    if let Some(hex) = value.strip_prefix("0x") {
+        return U256::from_str_radix(hex, 16)
+            .map(Distance::from)
+            .map_err(|err| format!("Invalid hex radius '{value}': {err}"));
+    }
+    let fraction: f64 = value.parse().map_err(|_| {
+        format!("Invalid radius '{value}', expected a fraction between 0 and 1 or a 0x-prefixed hex distance")
+    })?;
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(format!(
+            "Invalid radius '{value}', the fraction must be between 0 and 1"
+        ));
+    }
+    if fraction == 1.0 {
+        return Ok(Distance::MAX);
+    }
+    // Scale the top 64 bits, which is as much precision as an f64 fraction carries anyway.
+    let top_bits = (fraction * 2f64.powi(64)) as u64;
+    Ok(Distance::from(U256::from(top_bits) << 192))
+}
+
+/// Splits a value like `2GB` or `1.5h` into its number and its lowercase unit, which is empty if
+/// the value is a plain number.
+fn split_unit(value: &str) -> Result<(f64, String), String> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(unit_start);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid value '{value}', expected a number with an optional unit"))?;
+    Ok((number, unit.trim().to_ascii_lowercase()))
+}
+
+/// Parses a size given in `unit_bytes` units, or with a unit: B, KB, MB, GB, TB, KiB, MiB, GiB or
+/// TiB. Returns the size in `unit_bytes` units, rounded down, or an error if it doesn't fit in a
+/// `u64`.
+fn parse_size(value: &str, unit_bytes: u64, unit_name: &str) -> Result<u64, String> {
+    let (number, unit) = split_unit(value)?;
+    let bytes_per_unit: u64 = match unit.as_str() {
+        "" => unit_bytes,
+        "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        "tib" => 1 << 40,
+        _ => {
+            return Err(format!(
+                "Invalid size unit in '{value}', expected B, KB, MB, GB, TB, KiB, MiB, GiB or TiB"
+            ))
+        }
+    };
+    let too_large = || format!("Size '{value}' is too large");
+    let size = if number.fract() == 0.0 && bytes_per_unit % unit_bytes == 0 {
+        if number >= u64::MAX as f64 {
+            return Err(too_large());
+        }
+        (number as u64)
+            .checked_mul(bytes_per_unit / unit_bytes)
+            .ok_or_else(too_large)?
+    } else {
+        let size = number * bytes_per_unit as f64 / unit_bytes as f64;
+        if size >= u64::MAX as f64 {
+            return Err(too_large());
+        }
+        size as u64
+    };
+    if size == 0 && number > 0.0 {
+        return Err(format!("Size '{value}' is smaller than 1 {unit_name}"));
+    }
+    Ok(size)
+}
+
+/// Parses a duration given in `unit` units, or with a unit: ms, s, m, h or d. Returns the
+/// duration in `unit` units, rounded down.
+fn parse_duration(value: &str, unit: Duration, unit_name: &str) -> Result<u64, String> {
+    let (number, suffix) = split_unit(value)?;
+    let millis_per_unit: u64 = match suffix.as_str() {
+        "" => unit.as_millis() as u64,
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => {
+            return Err(format!(
+                "Invalid duration unit in '{value}', expected ms, s, m, h or d"
+            ))
+        }
+    };
+    let duration = (number * millis_per_unit as f64 / unit.as_millis() as f64) as u64;
+    if duration == 0 && number > 0.0 {
+        return Err(format!("Duration '{value}' is shorter than 1 {unit_name}"));
+    }
+    Ok(duration)
+}
+
+/// Parses a storage capacity in kilobytes, e.g. `500000` or `2GB`.
+fn parse_kb(value: &str) -> Result<u32, String> {
+    let kb = parse_size(value, 1_000, "kilobyte")?;
+    u32::try_from(kb).map_err(|_| format!("Storage capacity '{value}' is too large"))
+}
+
+/// Parses a size in megabytes, e.g. `512` or `2GiB`. The size is used as a number of MiB, so
+/// it is rejected if that many MiB don't fit in a `usize`.
+fn parse_mb(value: &str) -> Result<usize, String> {
+    let mb = parse_size(value, 1_000_000, "megabyte")?;
+    usize::try_from(mb)
+        .ok()
+        .filter(|mb| mb.checked_mul(1024 * 1024).is_some())
+        .ok_or_else(|| format!("Size '{value}' is too large"))
+}
+
+/// Parses a json-rpc body size in bytes, e.g. `1048576` or `50MiB`, up to 4 GiB.
+fn parse_body_size(value: &str) -> Result<u32, String> {
+    let bytes = parse_size(value, 1, "byte")?;
+    match u32::try_from(bytes) {
+        Ok(0) => Err("Body size must be at least 1 byte".to_string()),
+        Ok(bytes) => Ok(bytes),
+        Err(_) => Err(format!("Body size '{value}' is too large")),
+    }
+}
+
+/// Parses a duration in seconds, e.g. `30` or `10m`.
+fn parse_secs(value: &str) -> Result<u64, String> {
+    parse_duration(value, Duration::from_secs(1), "second")
+}
+
+/// Parses a duration in seconds that must be at least 1 second.
+fn parse_nonzero_secs(value: &str) -> Result<u64, String> {
+    match parse_secs(value)? {
+        0 => Err("Interval must be at least 1 second".to_string()),
+        secs => Ok(secs),
+    }
+}
+
+/// Parses a duration in milliseconds that must be at least 1 millisecond, e.g. `500` or `2s`.
+fn parse_nonzero_millis(value: &str) -> Result<u64, String> {
+    match parse_duration(value, Duration::from_millis(1), "millisecond")? {
+        0 => Err("Timeout must be at least 1 millisecond".to_string()),
+        millis => Ok(millis),
+    }
+}
+
+/// Parses a duration in hours, e.g. `24` or `7d`.
+fn parse_hours(value: &str) -> Result<u64, String> {
+    parse_duration(value, Duration::from_secs(3600), "hour")
+}
+
+/// Parses a sampling rate, which is a fraction between 0 and 1.
+fn parse_sample_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(format!(
+            "Invalid sample rate '{value}', expected a fraction between 0 and 1"
+        )),
+    }
+}
+
+fn parse_hex_prefix(value: &str) -> Result<Vec<u8>, String> {
+    if !value.starts_with("0x") {
+        return Err(format!(
+            "Invalid prefix '{value}', expected 0x-prefixed hex"
+        ));
+    }
+    hex_decode(value).map_err(|err| format!("Invalid prefix '{value}': {err}"))
+}
+
+fn parse_file_mode(value: &str) -> Result<u32, String> {
+    match u32::from_str_radix(value.trim_start_matches("0o"), 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!(
+            "Invalid mode '{value}', expected octal permission bits, e.g. 660"
+        )),
+    }
+}
+
 fn check_private_key_length(private_key: String) -> Result<(), String> {
     if private_key.len() == 66 {
         return Ok(());
@@ -306,6 +1514,8 @@ mod test {
             TrustedProviderType::Custom => return true,
             // Pandaops node does not require infura id.
             TrustedProviderType::Pandaops => return true,
+            // Running without a trusted provider does not require infura id.
+            TrustedProviderType::None => return true,
             _ => {}
         }
         matches!(env::var("TRIN_INFURA_PROJECT_ID"), Ok(_))
@@ -415,6 +1625,168 @@ mod test {
             .unwrap_err();
     }
 
+    #[test]
+    fn test_web3_modules() {
+        let config = TrinConfig::default();
+        assert_eq!(
+            config.web3_modules,
+            vec!["discv5", "eth", "history", "state", "web3"]
+        );
+        assert!(!config.web3_modules.contains(&"admin".to_string()));
+
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-modules",
+                "history,admin,debug",
+                "--web3-exclude-modules",
+                "debug",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.web3_modules, vec!["history", "admin", "debug"]);
+        assert_eq!(
+            config.exposed_modules(&config.web3_modules),
+            vec!["history", "admin"]
+        );
+
+        TrinConfig::new_from(["trin", "--web3-modules", "net"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--web3-modules", "portal"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--web3-exclude-modules", "net"].iter()).unwrap_err();
+    }
+
+    #[test]
+    fn test_web3_http_listeners() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-http-listener",
+                "http://172.17.0.1:8545/=history,eth,admin",
+                "--web3-http-listener",
+                "http://10.0.0.1:8545/",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.web3_http_listeners,
+            vec![
+                HttpListener {
+                    address: Url::parse("http://172.17.0.1:8545/").unwrap(),
+                    modules: vec![
+                        "history".to_string(),
+                        "eth".to_string(),
+                        "admin".to_string()
+                    ],
+                },
+                HttpListener {
+                    address: Url::parse("http://10.0.0.1:8545/").unwrap(),
+                    modules: DEFAULT_RPC_MODULES
+                        .split(',')
+                        .map(|m| m.to_string())
+                        .collect(),
+                },
+            ]
+        );
+        assert!(TrinConfig::default().web3_http_listeners.is_empty());
+
+        "http://172.17.0.1:8545/=history,net"
+            .parse::<HttpListener>()
+            .unwrap_err();
+        "172.17.0.1:8545=history"
+            .parse::<HttpListener>()
+            .unwrap_err();
+        // The address ends at the first '=', everything after it is the module list.
+        let err = "http://172.17.0.1:8545/=history=eth"
+            .parse::<HttpListener>()
+            .unwrap_err();
+        assert!(err.contains("'history=eth'"), "{err}");
+    }
+
+    #[test]
+    #[should_panic(expected = "--web3-http-listener requires '--web3-transport http'")]
+    fn test_ipc_protocol_rejects_web3_http_listeners() {
+        TrinConfig::new_from(["trin", "--web3-http-listener", "http://10.0.0.1:8545/"].iter())
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_web3_ipc_permissions() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-ipc-mode",
+                "660",
+                "--web3-ipc-group",
+                "portal",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.web3_ipc_mode, Some(0o660));
+        assert_eq!(actual_config.web3_ipc_group, Some("portal".to_string()));
+        assert_eq!(TrinConfig::default().web3_ipc_mode, None);
+
+        TrinConfig::new_from(["trin", "--web3-ipc-mode", "rw-rw----"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--web3-ipc-mode", "17777"].iter()).unwrap_err();
+    }
+
+    #[test]
+    fn test_web3_tls() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-tls-cert",
+                "/path/cert.pem",
+                "--web3-tls-key",
+                "/path/key.pem",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.web3_tls_cert,
+            Some(PathBuf::from("/path/cert.pem"))
+        );
+        assert_eq!(
+            actual_config.web3_tls_key,
+            Some(PathBuf::from("/path/key.pem"))
+        );
+
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-transport",
+                "http",
+                "--web3-tls-cert",
+                "/path/cert.pem",
+            ]
+            .iter(),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    #[should_panic(expected = "require '--web3-transport http'")]
+    fn test_ipc_protocol_rejects_web3_tls() {
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-tls-cert",
+                "/path/cert.pem",
+                "--web3-tls-key",
+                "/path/key.pem",
+            ]
+            .iter(),
+        )
+        .unwrap_err();
+    }
+
     #[test]
     fn test_custom_discovery_port() {
         let expected_config = TrinConfig {
@@ -479,6 +1851,65 @@ mod test {
         assert_eq!(actual_config.ephemeral, expected_config.ephemeral);
     }
 
+    #[test]
+    fn test_data_dir() {
+        let actual_config =
+            TrinConfig::new_from(["trin", "--data-dir", "/tmp/trin-data"].iter()).unwrap();
+        assert_eq!(
+            actual_config.data_dir,
+            Some(PathBuf::from("/tmp/trin-data"))
+        );
+    }
+
+    #[test]
+    fn test_db_paths() {
+        let actual_config =
+            TrinConfig::new_from(["trin", "--db-paths", "/mnt/a,/mnt/b"].iter()).unwrap();
+        assert_eq!(
+            actual_config.db_paths,
+            vec![PathBuf::from("/mnt/a"), PathBuf::from("/mnt/b")]
+        );
+        assert!(TrinConfig::default().db_paths.is_empty());
+    }
+
+    #[test]
+    fn test_db_tuning() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--db-block-cache-mb",
+                "512",
+                "--db-write-buffer-mb",
+                "64",
+                "--db-compression",
+                "zstd",
+                "--db-max-open-files",
+                "-1",
+                "--db-compaction-style",
+                "universal",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.db_block_cache_mb, Some(512));
+        assert_eq!(actual_config.db_write_buffer_mb, Some(64));
+        assert_eq!(actual_config.db_compression, Some(DbCompression::Zstd));
+        assert_eq!(actual_config.db_max_open_files, Some(-1));
+        assert_eq!(
+            actual_config.db_compaction_style,
+            Some(DbCompactionStyle::Universal)
+        );
+        TrinConfig::new_from(["trin", "--db-compression", "gzip"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--db-compaction-style", "fifo"].iter()).unwrap_err();
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be used with")]
+    fn test_data_dir_conflicts_with_ephemeral() {
+        TrinConfig::new_from(["trin", "--data-dir", "/tmp/trin-data", "--ephemeral"].iter())
+            .unwrap();
+    }
+
     #[test]
     fn test_enable_metrics_with_url() {
         let expected_config = TrinConfig {
@@ -531,27 +1962,630 @@ mod test {
     }
 
     #[test]
-    fn test_default_trusted_provider_is_infura() {
-        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
-        assert!(env_is_set(&config));
-        assert_eq!(config.trusted_provider, TrustedProviderType::Infura);
+    fn test_webhook_filters() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--webhook-url",
+                "http://localhost:8080/",
+                "--webhook-content-types",
+                "block_body,block_receipts",
+                "--webhook-key-prefixes",
+                "0x01,0x0203",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.webhook_content_types,
+            vec!["block_body", "block_receipts"]
+        );
+        assert_eq!(
+            actual_config.webhook_key_prefixes,
+            vec![vec![0x01], vec![0x02, 0x03]]
+        );
+
+        TrinConfig::new_from(["trin", "--webhook-content-types", "blocks"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--webhook-key-prefixes", "01"].iter()).unwrap_err();
     }
 
     #[test]
-    fn test_pandaops_trusted_provider() {
-        let config = TrinConfig::new_from(
+    fn test_glados_report_url() {
+        let actual_config = TrinConfig::new_from(
             [
                 "trin",
-                "--trusted-provider",
-                "pandaops",
-                "--trusted-provider-url",
-                "https://www.geth.com/",
+                "--glados-report-url",
+                "https://glados.ethportal.net/",
             ]
             .iter(),
         )
         .unwrap();
-        assert!(env_is_set(&config));
-        assert_eq!(config.trusted_provider, TrustedProviderType::Pandaops);
+        assert_eq!(
+            actual_config.glados_report_url,
+            Some(Url::parse("https://glados.ethportal.net/").unwrap())
+        );
+        assert_eq!(TrinConfig::default().glados_report_url, None);
+    }
+
+    #[test]
+    fn test_stats_export() {
+        let actual_config =
+            TrinConfig::new_from(["trin", "--stats-export", "/tmp/trin-stats.csv"].iter()).unwrap();
+        assert_eq!(
+            actual_config.stats_export,
+            Some(PathBuf::from("/tmp/trin-stats.csv"))
+        );
+        assert_eq!(TrinConfig::default().stats_export, None);
+    }
+
+    #[test]
+    fn test_log_filter() {
+        let actual_config =
+            TrinConfig::new_from(["trin", "--log.filter", "info,portalnet::overlay=debug"].iter())
+                .unwrap();
+        assert_eq!(
+            actual_config.log_filter,
+            Some("info,portalnet::overlay=debug".to_string())
+        );
+        assert_eq!(TrinConfig::default().log_filter, None);
+    }
+
+    #[test]
+    fn test_telemetry_record() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--telemetry.record",
+                "/tmp/trin-telemetry.jsonl",
+                "--telemetry.lookup-sample-rate",
+                "0.1",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.telemetry_record,
+            Some(PathBuf::from("/tmp/trin-telemetry.jsonl"))
+        );
+        assert_eq!(actual_config.telemetry_lookup_sample_rate, 0.1);
+        assert_eq!(actual_config.telemetry_gossip_sample_rate, 1.0);
+        assert_eq!(TrinConfig::default().telemetry_record, None);
+    }
+
+    #[test]
+    fn test_telemetry_sample_rate_invalid() {
+        assert!(parse_sample_rate("1.5").is_err());
+        assert!(parse_sample_rate("-0.1").is_err());
+        assert!(parse_sample_rate("half").is_err());
+        assert_eq!(parse_sample_rate("0"), Ok(0.0));
+    }
+
+    #[test]
+    fn test_compaction_interval_hours() {
+        assert_eq!(TrinConfig::default().compaction_interval_hours, 0);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--compaction-interval-hours", "24"].iter()).unwrap();
+        assert_eq!(actual_config.compaction_interval_hours, 24);
+    }
+
+    #[test]
+    fn test_regossip_params() {
+        let default_config = TrinConfig::default();
+        assert_eq!(default_config.regossip_interval_secs, 600);
+        assert_eq!(default_config.regossip_sample_size, 8);
+
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--regossip-interval-secs",
+                "1h",
+                "--regossip-sample-size",
+                "32",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.regossip_interval_secs, 3600);
+        assert_eq!(actual_config.regossip_sample_size, 32);
+        assert!(TrinConfig::new_from(["trin", "--regossip-sample-size", "0"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_bucket_refresh_interval_secs() {
+        assert_eq!(TrinConfig::default().bucket_refresh_interval_secs, 60);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--bucket-refresh-interval-secs", "300"].iter()).unwrap();
+        assert_eq!(actual_config.bucket_refresh_interval_secs, 300);
+        assert!(
+            TrinConfig::new_from(["trin", "--bucket-refresh-interval-secs", "0"].iter()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_routing_table_params() {
+        let default_config = TrinConfig::default();
+        assert_eq!(default_config.bucket_size, 16);
+        assert_eq!(default_config.table_depth, 17);
+        assert_eq!(default_config.bucket_pending_timeout_secs, 60);
+        assert_eq!(default_config.max_incoming_per_bucket, 16);
+
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--bucket-size",
+                "4",
+                "--table-depth",
+                "3",
+                "--bucket-pending-timeout-secs",
+                "10",
+                "--max-incoming-per-bucket",
+                "2",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.bucket_size, 4);
+        assert_eq!(actual_config.table_depth, 3);
+        assert_eq!(actual_config.bucket_pending_timeout_secs, 10);
+        assert_eq!(actual_config.max_incoming_per_bucket, 2);
+
+        assert!(TrinConfig::new_from(["trin", "--bucket-size", "17"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--table-depth", "0"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_lookup_params() {
+        let default_config = TrinConfig::default();
+        assert_eq!(default_config.lookup_timeout_secs, 60);
+        assert_eq!(default_config.lookup_peer_timeout_secs, 10);
+        assert_eq!(default_config.lookup_peer_attempts, 1);
+        assert_eq!(default_config.lookup_num_results, 16);
+
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--lookup-timeout-secs",
+                "30",
+                "--lookup-peer-timeout-secs",
+                "2",
+                "--lookup-peer-attempts",
+                "3",
+                "--lookup-num-results",
+                "4",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.lookup_timeout_secs, 30);
+        assert_eq!(actual_config.lookup_peer_timeout_secs, 2);
+        assert_eq!(actual_config.lookup_peer_attempts, 3);
+        assert_eq!(actual_config.lookup_num_results, 4);
+
+        assert!(TrinConfig::new_from(["trin", "--lookup-timeout-secs", "0"].iter()).is_err());
+        assert!(TrinConfig::new_from(["trin", "--lookup-peer-attempts", "0"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_lookup_cache_params() {
+        let default_config = TrinConfig::default();
+        assert_eq!(default_config.lookup_cache_size, 256);
+        assert_eq!(default_config.lookup_cache_ttl_secs, 30);
+
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--lookup-cache-size",
+                "0",
+                "--lookup-cache-ttl-secs",
+                "120",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.lookup_cache_size, 0);
+        assert_eq!(actual_config.lookup_cache_ttl_secs, 120);
+    }
+
+    #[test]
+    fn test_validation_cache_size() {
+        assert_eq!(TrinConfig::default().validation_cache_size, 1024);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--validation-cache-size", "0"].iter()).unwrap();
+        assert_eq!(actual_config.validation_cache_size, 0);
+    }
+
+    #[test]
+    fn test_utp_transfer_limit() {
+        assert_eq!(TrinConfig::default().utp_transfer_limit, 1);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--utp-transfer-limit", "4"].iter()).unwrap();
+        assert_eq!(actual_config.utp_transfer_limit, 4);
+        assert!(TrinConfig::new_from(["trin", "--utp-transfer-limit", "0"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_neighborhood_sync_limit() {
+        assert_eq!(TrinConfig::default().neighborhood_sync_limit, 256);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--neighborhood-sync-limit", "0"].iter()).unwrap();
+        assert_eq!(actual_config.neighborhood_sync_limit, 0);
+    }
+
+    #[test]
+    fn test_admin_shutdown_token() {
+        assert_eq!(TrinConfig::default().admin_shutdown_token, None);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--admin-shutdown-token", "secret"].iter()).unwrap();
+        assert_eq!(
+            actual_config.admin_shutdown_token,
+            Some("secret".to_string())
+        );
+    }
+
+    #[test]
+    fn test_version_command() {
+        assert_eq!(TrinConfig::default().command, None);
+        let actual_config = TrinConfig::new_from(["trin", "version", "--json"].iter()).unwrap();
+        assert_eq!(
+            actual_config.command,
+            Some(TrinCommand::Version { json: true })
+        );
+    }
+
+    #[test]
+    fn test_extra_nodes() {
+        assert_eq!(TrinConfig::default().extra_nodes().unwrap(), vec![]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nodes.json");
+        let key = |byte: u8| trin_utils::bytes::hex_encode([byte; 32]);
+        fs::write(
+            &path,
+            format!(
+                r#"[{{"privateKey": "{}", "discoveryPort": 9010, "kb": 1000}},
+                    {{"privateKey": "{}", "discoveryPort": 9011, "externalAddress": "1.2.3.4:9011"}}]"#,
+                key(1),
+                key(2)
+            ),
+        )
+        .unwrap();
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--extra-nodes-config",
+                path.to_str().unwrap(),
+                "--kb",
+                "500",
+                "--webhook-url",
+                "http://localhost:8080/",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        let nodes = config.extra_nodes().unwrap();
+        assert_eq!(nodes.len(), 2);
+
+        let first = config.for_extra_node(&nodes[0]);
+        assert_eq!(first.private_key, Some(H256::from_slice(&[1; 32])));
+        assert_eq!(first.discovery_port, 9010);
+        assert_eq!(first.kb, 1000);
+        assert_eq!(first.extra_nodes_config, None);
+        assert_eq!(first.webhook_url, None);
+        let second = config.for_extra_node(&nodes[1]);
+        assert_eq!(second.kb, 500);
+        assert_eq!(
+            second.external_addr,
+            Some(SocketAddr::from(([1, 2, 3, 4], 9011)))
+        );
+
+        fs::write(
+            &path,
+            format!(r#"[{{"privateKey": "{}", "discoveryPort": 9000}}]"#, key(1)),
+        )
+        .unwrap();
+        assert!(config.extra_nodes().is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        assert!(!TrinConfig::default().check);
+        let actual_config = TrinConfig::new_from(["trin", "--check"].iter()).unwrap();
+        assert!(actual_config.check);
+    }
+
+    #[test]
+    fn test_rotate_key_command() {
+        let actual_config = TrinConfig::new_from(["trin", "rotate-key"].iter()).unwrap();
+        assert_eq!(
+            actual_config.command,
+            Some(TrinCommand::RotateKey {
+                keep_content: false
+            })
+        );
+        let actual_config =
+            TrinConfig::new_from(["trin", "rotate-key", "--keep-content"].iter()).unwrap();
+        assert_eq!(
+            actual_config.command,
+            Some(TrinCommand::RotateKey { keep_content: true })
+        );
+    }
+
+    #[test]
+    fn test_stats_command() {
+        let actual_config = TrinConfig::new_from(["trin", "stats"].iter()).unwrap();
+        assert_eq!(
+            actual_config.command,
+            Some(TrinCommand::Stats { json: false })
+        );
+        let actual_config = TrinConfig::new_from(["trin", "stats", "--json"].iter()).unwrap();
+        assert_eq!(
+            actual_config.command,
+            Some(TrinCommand::Stats { json: true })
+        );
+    }
+
+    #[test]
+    fn test_env_var_overrides() {
+        let mut env_vars: HashMap<String, String> = [
+            ("TRIN_VALIDATION_CACHE_SIZE", "64"),
+            ("TRIN_PING_EXTENSIONS", "true"),
+            ("TRIN_CHECK", "1"),
+            ("TRIN_LOG_FILTER", "debug"),
+            ("TRIN_NETWORKS", "history,state"),
+        ]
+        .into_iter()
+        .map(|(var, value)| (var.to_string(), value.to_string()))
+        .collect();
+        let env_config = TrinConfig::new_from_env(["trin"].iter(), &env_vars).unwrap();
+        assert_eq!(env_config.validation_cache_size, 64);
+        assert!(env_config.ping_extensions);
+        assert!(env_config.check);
+        assert_eq!(env_config.log_filter, Some("debug".to_string()));
+        assert_eq!(env_config.networks, vec!["history", "state"]);
+
+        // The command line takes precedence over the environment.
+        let cli_config = TrinConfig::new_from_env(
+            ["trin", "--validation-cache-size", "8", "--log.filter=info"].iter(),
+            &env_vars,
+        )
+        .unwrap();
+        assert_eq!(cli_config.validation_cache_size, 8);
+        assert_eq!(cli_config.log_filter, Some("info".to_string()));
+
+        // Flags from the environment go before the subcommand.
+        let stats_config =
+            TrinConfig::new_from_env(["trin", "stats", "--json"].iter(), &env_vars).unwrap();
+        assert_eq!(stats_config.validation_cache_size, 64);
+        assert_eq!(
+            stats_config.command,
+            Some(TrinCommand::Stats { json: true })
+        );
+
+        env_vars.insert("TRIN_PING_EXTENSIONS".to_string(), "maybe".to_string());
+        assert!(TrinConfig::new_from_env(["trin"].iter(), &env_vars).is_err());
+    }
+
+    #[test]
+    fn test_human_friendly_units() {
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--kb",
+                "2GB",
+                "--db-block-cache-mb",
+                "1GiB",
+                "--compaction-interval-hours",
+                "7d",
+                "--lookup-timeout-secs",
+                "2m",
+                "--lookup-cache-ttl-secs",
+                "1.5m",
+                "--talkreq-timeouts",
+                "history.find_content=8s",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.kb, 2_000_000);
+        assert_eq!(actual_config.db_block_cache_mb, Some(1073));
+        assert_eq!(actual_config.compaction_interval_hours, 168);
+        assert_eq!(actual_config.lookup_timeout_secs, 120);
+        assert_eq!(actual_config.lookup_cache_ttl_secs, 90);
+        assert_eq!(
+            actual_config.talkreq_timeouts[0].timeout,
+            Duration::from_millis(8000)
+        );
+
+        // Plain numbers keep the unit of the flag.
+        assert_eq!(parse_kb("500").unwrap(), 500);
+        assert_eq!(parse_kb("500 MB").unwrap(), 500_000);
+        assert_eq!(parse_secs("90").unwrap(), 90);
+
+        for (value, error) in [
+            ("2XB", "Invalid size unit"),
+            ("10B", "smaller than 1 kilobyte"),
+            ("5TB", "too large"),
+            ("GB", "Invalid value"),
+        ] {
+            assert!(parse_kb(value).unwrap_err().contains(error), "{value}");
+        }
+        // Sizes that overflow are errors rather than saturating or wrapping.
+        for value in ["99999999999999999999", "20000000000TB", "30000000.5TB"] {
+            assert!(
+                parse_size(value, 1, "byte")
+                    .unwrap_err()
+                    .contains("too large"),
+                "{value}"
+            );
+        }
+        assert_eq!(
+            parse_size("16000000TB", 1, "byte").unwrap(),
+            16 * 10u64.pow(18)
+        );
+        assert!(parse_mb("20000000TB").unwrap_err().contains("too large"));
+        assert!(parse_secs("10y")
+            .unwrap_err()
+            .contains("Invalid duration unit"));
+        assert!(parse_secs("500ms")
+            .unwrap_err()
+            .contains("shorter than 1 second"));
+        assert!(TrinConfig::new_from(["trin", "--lookup-timeout-secs", "0s"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_web3_body_size_limits() {
+        assert_eq!(
+            TrinConfig::default().web3_max_request_size,
+            10 * 1024 * 1024
+        );
+        assert_eq!(
+            TrinConfig::default().web3_max_response_size,
+            10 * 1024 * 1024
+        );
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--web3-max-request-size",
+                "50MiB",
+                "--web3-max-response-size",
+                "1000",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(actual_config.web3_max_request_size, 50 * 1024 * 1024);
+        assert_eq!(actual_config.web3_max_response_size, 1000);
+
+        assert!(parse_body_size("0").is_err());
+        assert!(parse_body_size("5GiB").unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn test_talkreq_timeouts() {
+        assert_eq!(TrinConfig::default().talkreq_timeouts, vec![]);
+        let actual_config = TrinConfig::new_from(
+            [
+                "trin",
+                "--talkreq-timeouts",
+                "history.find_content=8000,state.ping=500",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            actual_config.talkreq_timeouts,
+            vec![
+                TalkReqTimeout {
+                    subnetwork: "history".to_string(),
+                    kind: TalkReqKind::FindContent,
+                    timeout: Duration::from_millis(8000),
+                },
+                TalkReqTimeout {
+                    subnetwork: "state".to_string(),
+                    kind: TalkReqKind::Ping,
+                    timeout: Duration::from_millis(500),
+                },
+            ]
+        );
+
+        for invalid in [
+            "history.find_content",
+            "history=100",
+            "beacon.ping=100",
+            "history.accept=100",
+            "history.ping=0",
+        ] {
+            assert!(TalkReqTimeout::from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_discv5_request_timeout() {
+        assert_eq!(TrinConfig::default().discv5_request_timeout_ms, 1000);
+        let actual_config =
+            TrinConfig::new_from(["trin", "--discv5-request-timeout-ms", "3s"].iter()).unwrap();
+        assert_eq!(actual_config.discv5_request_timeout_ms, 3000);
+        assert!(TrinConfig::new_from(["trin", "--discv5-request-timeout-ms", "0"].iter()).is_err());
+    }
+
+    #[test]
+    fn test_fixed_radius() {
+        assert_eq!(TrinConfig::default().fixed_radius, None);
+
+        let config = TrinConfig::new_from(["trin", "--fixed-radius", "0"].iter()).unwrap();
+        assert_eq!(config.fixed_radius, Some(Distance::ZERO));
+        let config = TrinConfig::new_from(["trin", "--fixed-radius", "1"].iter()).unwrap();
+        assert_eq!(config.fixed_radius, Some(Distance::MAX));
+        let config = TrinConfig::new_from(["trin", "--fixed-radius", "0.5"].iter()).unwrap();
+        assert_eq!(
+            config.fixed_radius,
+            Some(Distance::from(U256::one() << 255))
+        );
+        let config = TrinConfig::new_from(["trin", "--fixed-radius", "0xffff"].iter()).unwrap();
+        assert_eq!(
+            config.fixed_radius,
+            Some(Distance::from(U256::from(0xffff)))
+        );
+
+        TrinConfig::new_from(["trin", "--fixed-radius", "1.5"].iter()).unwrap_err();
+        TrinConfig::new_from(["trin", "--fixed-radius", "half"].iter()).unwrap_err();
+    }
+
+    #[test]
+    fn test_no_storage() {
+        let config = TrinConfig::new_from(["trin", "--no-storage"].iter()).unwrap();
+        assert!(config.no_storage);
+        assert!(!TrinConfig::default().no_storage);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be used with")]
+    fn test_no_storage_conflicts_with_fixed_radius() {
+        TrinConfig::new_from(["trin", "--no-storage", "--fixed-radius", "1"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_archive() {
+        let config = TrinConfig::new_from(["trin", "--archive"].iter()).unwrap();
+        assert!(config.archive);
+        assert!(!TrinConfig::default().archive);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be used with")]
+    fn test_archive_conflicts_with_no_storage() {
+        TrinConfig::new_from(["trin", "--archive", "--no-storage"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_ping_extensions() {
+        let actual_config = TrinConfig::new_from(["trin", "--ping-extensions"].iter()).unwrap();
+        assert!(actual_config.ping_extensions);
+        assert!(!TrinConfig::default().ping_extensions);
+    }
+
+    #[test]
+    fn test_default_trusted_provider_is_infura() {
+        let config = TrinConfig::new_from(["trin"].iter()).unwrap();
+        assert!(env_is_set(&config));
+        assert_eq!(config.trusted_provider, TrustedProviderType::Infura);
+    }
+
+    #[test]
+    fn test_pandaops_trusted_provider() {
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--trusted-provider",
+                "pandaops",
+                "--trusted-provider-url",
+                "https://www.geth.com/",
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert!(env_is_set(&config));
+        assert_eq!(config.trusted_provider, TrustedProviderType::Pandaops);
     }
 
     #[test]
@@ -569,7 +2603,7 @@ mod test {
         .unwrap();
         assert!(env_is_set(&config));
         assert_eq!(config.trusted_provider, TrustedProviderType::Custom);
-        let trusted_provider = TrustedProvider::from_trin_config(&config);
+        let trusted_provider = TrustedProvider::from_trin_config(&config).unwrap().unwrap();
         let url: ureq::RequestUrl = trusted_provider.http.request_url().unwrap();
         assert_eq!(url.host(), "127.0.0.1");
         assert_eq!(url.port(), Some(8546));
@@ -633,6 +2667,64 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn test_engine_api_trusted_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let jwt_secret = dir.path().join("jwt.hex");
+        std::fs::write(&jwt_secret, "42".repeat(32)).unwrap();
+        let config = TrinConfig::new_from(
+            [
+                "trin",
+                "--trusted-provider",
+                "custom",
+                "--trusted-provider-url",
+                "http://127.0.0.1:8551/",
+                "--trusted-provider-jwt-secret",
+                jwt_secret.to_str().unwrap(),
+            ]
+            .iter(),
+        )
+        .unwrap();
+        assert_eq!(config.trusted_provider_jwt_secret, Some(jwt_secret));
+        let trusted_provider = TrustedProvider::from_trin_config(&config).unwrap().unwrap();
+        assert!(trusted_provider.jwt_auth.is_some());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "--trusted-provider-jwt-secret flag requires '--trusted-provider custom'."
+    )]
+    fn test_jwt_secret_requires_custom_trusted_provider() {
+        TrinConfig::new_from(["trin", "--trusted-provider-jwt-secret", "jwt.hex"].iter()).unwrap();
+    }
+
+    #[test]
+    fn test_no_trusted_provider() {
+        let config = TrinConfig::new_from(["trin", "--trusted-provider", "none"].iter()).unwrap();
+        assert_eq!(config.trusted_provider, TrustedProviderType::None);
+        assert!(TrustedProvider::from_trin_config(&config)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "--trusted-provider-url flag is incompatible with '--trusted-provider none'."
+    )]
+    fn test_trusted_provider_url_must_not_be_used_without_trusted_provider() {
+        TrinConfig::new_from(
+            [
+                "trin",
+                "--trusted-provider",
+                "none",
+                "--trusted-provider-url",
+                "http://127.0.0.1:8546/",
+            ]
+            .iter(),
+        )
+        .unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "'--trusted-provider pandaops' choice requires the --trusted-provider-url flag."
@@ -0,0 +1,126 @@
+use ethereum_types::H256;
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use superstruct::superstruct;
+
+use super::{
+    body::{BeaconBlockBodyBellatrix, BeaconBlockBodyCapella},
+    fork::ForkName,
+    signature::BlsSignature,
+};
+
+/// Types based off specs @
+/// https://github.com/ethereum/consensus-specs/blob/5970ae56a1cd50ea06049d8aad6bed74093d49d3/specs/bellatrix/beacon-chain.md
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md
+#[superstruct(
+    variants(Bellatrix, Capella),
+    variant_attributes(
+        derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode),
+        serde(deny_unknown_fields)
+    )
+)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub struct BeaconBlock {
+    pub slot: u64,
+    pub proposer_index: u64,
+    pub parent_root: H256,
+    pub state_root: H256,
+    #[superstruct(only(Bellatrix), partial_getter(rename = "body_bellatrix"))]
+    pub body: BeaconBlockBodyBellatrix,
+    #[superstruct(only(Capella), partial_getter(rename = "body_capella"))]
+    pub body: BeaconBlockBodyCapella,
+}
+
+impl BeaconBlock {
+    /// Decodes an SSZ encoded beacon block of the given fork.
+    pub fn from_ssz_bytes(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => BeaconBlockBellatrix::from_ssz_bytes(bytes).map(Self::Bellatrix),
+            ForkName::Capella => BeaconBlockCapella::from_ssz_bytes(bytes).map(Self::Capella),
+        }
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+        }
+    }
+}
+
+impl Encode for BeaconBlock {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Bellatrix(block) => block.ssz_append(buf),
+            Self::Capella(block) => block.ssz_append(buf),
+        }
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        match self {
+            Self::Bellatrix(block) => block.ssz_bytes_len(),
+            Self::Capella(block) => block.ssz_bytes_len(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+pub struct SignedBeaconBlockBellatrix {
+    pub message: BeaconBlockBellatrix,
+    pub signature: BlsSignature,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+pub struct SignedBeaconBlockCapella {
+    pub message: BeaconBlockCapella,
+    pub signature: BlsSignature,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::{json, Value};
+
+    fn block_value(case: &str) -> Value {
+        let body = std::fs::read_to_string(format!(
+            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/value.yaml"
+        ))
+        .expect("cannot find test asset");
+        let body: Value = serde_yaml::from_str(&body).unwrap();
+        json!({
+            "slot": 6_718_463,
+            "proposer_index": 12,
+            "parent_root": format!("0x{}", "aa".repeat(32)),
+            "state_root": format!("0x{}", "bb".repeat(32)),
+            "body": body,
+        })
+    }
+
+    #[rstest]
+    #[case("case_0")]
+    #[case("case_1")]
+    fn bellatrix_block_round_trip(#[case] case: &str) {
+        let value = block_value(case);
+        let block: BeaconBlock = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(block.fork_name(), ForkName::Bellatrix);
+        assert_eq!(*block.slot(), 6_718_463);
+        assert_eq!(serde_json::to_value(&block).unwrap(), value);
+
+        let encoded = block.as_ssz_bytes();
+        let body_encoded = block.body_bellatrix().unwrap().as_ssz_bytes();
+        // slot, proposer_index, parent_root, state_root and the body offset precede the body.
+        assert_eq!(encoded.len(), 8 + 8 + 32 + 32 + 4 + body_encoded.len());
+        assert!(encoded.ends_with(&body_encoded));
+        assert_eq!(
+            BeaconBlock::from_ssz_bytes(&encoded, ForkName::Bellatrix).unwrap(),
+            block
+        );
+    }
+}
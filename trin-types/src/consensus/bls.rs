@@ -0,0 +1,217 @@
+use ethereum_types::H256;
+use milagro_bls::{AggregatePublicKey as MilagroAggregatePublicKey, AggregateSignature};
+use thiserror::Error;
+
+use super::{body::SyncAggregate, pubkey::BlsPublicKey, signature::BlsSignature};
+
+/// The compressed encoding of the point at infinity, which is the signature of an empty set of
+/// signers.
+const INFINITY_SIGNATURE: [u8; 96] = {
+    let mut signature = [0u8; 96];
+    signature[0] = 0xc0;
+    signature
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BlsError {
+    #[error("invalid BLS public key")]
+    InvalidPublicKey,
+
+    #[error("unable to aggregate an empty set of BLS public keys")]
+    EmptyPublicKeys,
+}
+
+impl BlsPublicKey {
+    /// Decompresses the public key, checking that it is a valid point in the G1 subgroup.
+    fn decompress(&self) -> Result<milagro_bls::PublicKey, BlsError> {
+        milagro_bls::PublicKey::from_bytes(&self.0).map_err(|_| BlsError::InvalidPublicKey)
+    }
+}
+
+/// The aggregate of a set of BLS public keys, e.g. of a sync committee, which can be reused to
+/// verify signatures of the whole set.
+#[derive(Debug)]
+pub struct AggregatePublicKey(MilagroAggregatePublicKey);
+
+impl AggregatePublicKey {
+    pub fn aggregate(pubkeys: &[BlsPublicKey]) -> Result<Self, BlsError> {
+        if pubkeys.is_empty() {
+            return Err(BlsError::EmptyPublicKeys);
+        }
+        let pubkeys = pubkeys
+            .iter()
+            .map(BlsPublicKey::decompress)
+            .collect::<Result<Vec<_>, _>>()?;
+        let pubkeys: Vec<&milagro_bls::PublicKey> = pubkeys.iter().collect();
+        MilagroAggregatePublicKey::aggregate(&pubkeys)
+            .map(Self)
+            .map_err(|_| BlsError::InvalidPublicKey)
+    }
+}
+
+impl BlsSignature {
+    /// Verifies the signature of a single signer over `message`.
+    pub fn verify(&self, pubkey: &BlsPublicKey, message: &[u8]) -> bool {
+        let pubkey = match pubkey.decompress() {
+            Ok(pubkey) => pubkey,
+            Err(_) => return false,
+        };
+        match milagro_bls::Signature::from_bytes(&self.signature) {
+            Ok(signature) => signature.verify(message, &pubkey),
+            Err(_) => false,
+        }
+    }
+
+    /// Verifies an aggregate signature over `message` by all of `pubkeys`, as `FastAggregateVerify`
+    /// of the BLS signature standard. Returns false for an empty set of public keys.
+    pub fn fast_aggregate_verify(&self, message: &[u8], pubkeys: &[BlsPublicKey]) -> bool {
+        let pubkeys = match pubkeys
+            .iter()
+            .map(BlsPublicKey::decompress)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(pubkeys) if !pubkeys.is_empty() => pubkeys,
+            _ => return false,
+        };
+        let pubkeys: Vec<&milagro_bls::PublicKey> = pubkeys.iter().collect();
+        match AggregateSignature::from_bytes(&self.signature) {
+            Ok(signature) => signature.fast_aggregate_verify(message, &pubkeys),
+            Err(_) => false,
+        }
+    }
+
+    /// Verifies an aggregate signature over `message` by all the keys of a pre-aggregated set of
+    /// public keys.
+    pub fn fast_aggregate_verify_pre_aggregated(
+        &self,
+        message: &[u8],
+        pubkey: &AggregatePublicKey,
+    ) -> bool {
+        match AggregateSignature::from_bytes(&self.signature) {
+            Ok(signature) => signature.fast_aggregate_verify_pre_aggregated(message, &pubkey.0),
+            Err(_) => false,
+        }
+    }
+
+    /// `eth_fast_aggregate_verify` of the Altair consensus specs: like `fast_aggregate_verify`,
+    /// but the signature of an empty set of public keys is the point at infinity.
+    pub fn eth_fast_aggregate_verify(&self, message: &[u8], pubkeys: &[BlsPublicKey]) -> bool {
+        if pubkeys.is_empty() {
+            return self.signature == INFINITY_SIGNATURE;
+        }
+        self.fast_aggregate_verify(message, pubkeys)
+    }
+}
+
+impl SyncAggregate {
+    /// Returns the public keys of the members of `committee` that participated in the aggregate.
+    pub fn participants<'a>(&self, committee: &'a [BlsPublicKey]) -> Vec<&'a BlsPublicKey> {
+        committee
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.sync_committee_bits.get(*index).unwrap_or(false))
+            .map(|(_, pubkey)| pubkey)
+            .collect()
+    }
+
+    /// Verifies the sync committee signature over `signing_root`, the signing root of the
+    /// attested block header, given the public keys of the whole sync committee in order.
+    pub fn verify(&self, committee: &[BlsPublicKey], signing_root: H256) -> bool {
+        if committee.len() != self.sync_committee_bits.len() {
+            return false;
+        }
+        let participants: Vec<BlsPublicKey> =
+            self.participants(committee).into_iter().cloned().collect();
+        self.sync_committee_signature
+            .eth_fast_aggregate_verify(signing_root.as_bytes(), &participants)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use milagro_bls::{SecretKey, Signature};
+    use ssz_types::BitVector;
+
+    const MESSAGE: &[u8] = b"portal network";
+
+    fn secret_key(seed: u8) -> SecretKey {
+        let mut bytes = [0u8; 32];
+        bytes[31] = seed;
+        SecretKey::from_bytes(&bytes).unwrap()
+    }
+
+    fn pubkey(seed: u8) -> BlsPublicKey {
+        let pubkey = milagro_bls::PublicKey::from_secret_key(&secret_key(seed));
+        let mut bytes = [0u8; 48];
+        bytes.copy_from_slice(&pubkey.as_bytes());
+        BlsPublicKey(bytes)
+    }
+
+    fn aggregate_signature(seeds: &[u8], message: &[u8]) -> BlsSignature {
+        let mut aggregate = AggregateSignature::new();
+        for seed in seeds {
+            aggregate.add(&Signature::new(message, &secret_key(*seed)));
+        }
+        let mut signature = [0u8; 96];
+        signature.copy_from_slice(&aggregate.as_bytes());
+        BlsSignature { signature }
+    }
+
+    #[test]
+    fn verify_single_signature() {
+        let signature = aggregate_signature(&[1], MESSAGE);
+        assert!(signature.verify(&pubkey(1), MESSAGE));
+        assert!(!signature.verify(&pubkey(2), MESSAGE));
+        assert!(!signature.verify(&pubkey(1), b"other message"));
+        assert!(!signature.verify(&BlsPublicKey([0xff; 48]), MESSAGE));
+    }
+
+    #[test]
+    fn verify_aggregate_signature() {
+        let pubkeys: Vec<BlsPublicKey> = (1..=4).map(pubkey).collect();
+        let signature = aggregate_signature(&[1, 2, 3, 4], MESSAGE);
+        assert!(signature.fast_aggregate_verify(MESSAGE, &pubkeys));
+        assert!(!signature.fast_aggregate_verify(MESSAGE, &pubkeys[..3]));
+        assert!(!signature.fast_aggregate_verify(MESSAGE, &[]));
+
+        let aggregate = AggregatePublicKey::aggregate(&pubkeys).unwrap();
+        assert!(signature.fast_aggregate_verify_pre_aggregated(MESSAGE, &aggregate));
+        assert_eq!(
+            AggregatePublicKey::aggregate(&[]).unwrap_err(),
+            BlsError::EmptyPublicKeys
+        );
+    }
+
+    #[test]
+    fn eth_fast_aggregate_verify_accepts_empty_set() {
+        let infinity = BlsSignature {
+            signature: INFINITY_SIGNATURE,
+        };
+        assert!(infinity.eth_fast_aggregate_verify(MESSAGE, &[]));
+        assert!(!aggregate_signature(&[1], MESSAGE).eth_fast_aggregate_verify(MESSAGE, &[]));
+    }
+
+    #[test]
+    fn verify_sync_aggregate() {
+        let committee: Vec<BlsPublicKey> = (1..=8).cycle().take(512).map(pubkey).collect();
+        let signing_root = H256::repeat_byte(7);
+
+        // The first and third members participate.
+        let mut sync_committee_bits = BitVector::new();
+        sync_committee_bits.set(0, true).unwrap();
+        sync_committee_bits.set(2, true).unwrap();
+        let sync_aggregate = SyncAggregate {
+            sync_committee_bits,
+            sync_committee_signature: aggregate_signature(&[1, 3], signing_root.as_bytes()),
+        };
+        assert_eq!(
+            sync_aggregate.participants(&committee),
+            vec![&committee[0], &committee[2]]
+        );
+        assert!(sync_aggregate.verify(&committee, signing_root));
+        assert!(!sync_aggregate.verify(&committee, H256::repeat_byte(8)));
+        assert!(!sync_aggregate.verify(&committee[..511], signing_root));
+    }
+}
@@ -1,20 +1,32 @@
-use ethereum_types::{H256, U256};
+use ethereum_types::H256;
 use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
 use ssz_derive::{Decode, Encode};
 use ssz_types::{typenum, BitList, BitVector, VariableList};
+use superstruct::superstruct;
 
 use super::{
+    execution_payload::{ExecutionPayloadBellatrix, ExecutionPayloadCapella},
+    fork::ForkName,
     header::BeaconBlockHeader,
     proof::Proof,
-    pubkey::PubKey,
-    serde::{de_hex_to_txs, de_number_to_u256, se_hex_to_number, se_txs_to_hex},
+    pubkey::BlsPublicKey,
     signature::BlsSignature,
 };
-use crate::wrapped::{bloom::Bloom, bytes::Bytes, h160::H160};
+use crate::wrapped::h160::H160;
 
 /// Types based off specs @
 /// https://github.com/ethereum/consensus-specs/blob/5970ae56a1cd50ea06049d8aad6bed74093d49d3/specs/bellatrix/beacon-chain.md
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md
+#[superstruct(
+    variants(Bellatrix, Capella),
+    variant_attributes(
+        derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode),
+        serde(deny_unknown_fields)
+    )
+)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
 pub struct BeaconBlockBody {
     pub randao_reveal: BlsSignature,
     pub eth1_data: Eth1Data,
@@ -25,7 +37,54 @@ pub struct BeaconBlockBody {
     pub deposits: VariableList<Deposit, typenum::U16>,
     pub voluntary_exits: VariableList<SignedVoluntaryExit, typenum::U16>,
     pub sync_aggregate: SyncAggregate,
-    pub execution_payload: ExecutionPayload,
+    #[superstruct(
+        only(Bellatrix),
+        partial_getter(rename = "execution_payload_bellatrix")
+    )]
+    pub execution_payload: ExecutionPayloadBellatrix,
+    #[superstruct(only(Capella), partial_getter(rename = "execution_payload_capella"))]
+    pub execution_payload: ExecutionPayloadCapella,
+    #[superstruct(only(Capella))]
+    pub bls_to_execution_changes: VariableList<SignedBlsToExecutionChange, typenum::U16>,
+}
+
+impl BeaconBlockBody {
+    /// Decodes an SSZ encoded beacon block body of the given fork.
+    pub fn from_ssz_bytes(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                BeaconBlockBodyBellatrix::from_ssz_bytes(bytes).map(Self::Bellatrix)
+            }
+            ForkName::Capella => BeaconBlockBodyCapella::from_ssz_bytes(bytes).map(Self::Capella),
+        }
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+        }
+    }
+}
+
+impl Encode for BeaconBlockBody {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Bellatrix(body) => body.ssz_append(buf),
+            Self::Capella(body) => body.ssz_append(buf),
+        }
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        match self {
+            Self::Bellatrix(body) => body.ssz_bytes_len(),
+            Self::Capella(body) => body.ssz_bytes_len(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
@@ -34,32 +93,6 @@ pub struct SyncAggregate {
     pub sync_committee_signature: BlsSignature,
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
-pub struct ExecutionPayload {
-    pub parent_hash: H256,
-    pub fee_recipient: H160,
-    pub state_root: H256,
-    pub receipts_root: H256,
-    pub logs_bloom: Bloom,
-    pub prev_randao: H256, // 'difficulty' in the yellow paper
-    pub block_number: u64, // 'number' in the yellow paper
-    pub gas_limit: u64,
-    pub gas_used: u64,
-    pub timestamp: u64,
-    pub extra_data: Bytes,
-    #[serde(deserialize_with = "de_number_to_u256")]
-    #[serde(serialize_with = "se_hex_to_number")]
-    pub base_fee_per_gas: U256,
-    // Extra payload fields
-    pub block_hash: H256, // Hash of execution block
-    #[serde(serialize_with = "se_txs_to_hex")]
-    #[serde(deserialize_with = "de_hex_to_txs")]
-    pub transactions: Transactions,
-}
-
-pub type Transaction = VariableList<u8, typenum::U1073741824>;
-pub type Transactions = VariableList<Transaction, typenum::U1048576>;
-
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
 pub struct ProposerSlashing {
     pub signed_header_1: SignedBeaconBlockHeader,
@@ -93,7 +126,7 @@ pub struct Deposit {
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
 pub struct DepositData {
-    pub pubkey: PubKey,
+    pub pubkey: BlsPublicKey,
     pub withdrawal_credentials: H256,
     pub amount: u64,
     pub signature: BlsSignature,
@@ -140,12 +173,62 @@ pub struct Eth1Data {
     pub block_hash: H256,
 }
 
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+pub struct SignedBlsToExecutionChange {
+    pub message: BlsToExecutionChange,
+    pub signature: BlsSignature,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+pub struct BlsToExecutionChange {
+    pub validator_index: u64,
+    pub from_bls_pubkey: BlsPublicKey,
+    pub to_execution_address: H160,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use ::ssz::{Decode, Encode};
+    use ::ssz::Encode;
     use rstest::rstest;
-    use serde_json::Value;
+    use serde_json::{json, Value};
+    use trin_utils::snappy::decompress_raw;
+
+    fn read_value(case: &str) -> Value {
+        let value = std::fs::read_to_string(format!(
+            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/value.yaml"
+        ))
+        .expect("cannot find test asset");
+        serde_yaml::from_str(&value).unwrap()
+    }
+
+    fn read_ssz(case: &str) -> Vec<u8> {
+        let compressed = std::fs::read(format!(
+            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/serialized.ssz_snappy"
+        ))
+        .expect("cannot find test asset");
+        decompress_raw(&compressed).unwrap()
+    }
+
+    /// Extends a Bellatrix body with the fields introduced in Capella.
+    fn capella_value(case: &str) -> Value {
+        let mut value = read_value(case);
+        value["execution_payload"]["withdrawals"] = json!([{
+            "index": 1,
+            "validator_index": 2,
+            "address": "0x4675c7e5baafbffbca748158becba61ef3b0a263",
+            "amount": 3,
+        }]);
+        value["bls_to_execution_changes"] = json!([{
+            "message": {
+                "validator_index": 4,
+                "from_bls_pubkey": format!("0x{}", "11".repeat(48)),
+                "to_execution_address": "0x4675c7e5baafbffbca748158becba61ef3b0a263",
+            },
+            "signature": format!("0x{}", "22".repeat(96)),
+        }]);
+        value
+    }
 
     /// Test vectors sourced from:
     /// https://github.com/ethereum/consensus-spec-tests/commit/c6e69469a75392b35169bc6234d4d3e6c4e288da
@@ -156,12 +239,8 @@ mod test {
     #[case("case_3")]
     #[case("case_4")]
     fn serde(#[case] case: &str) {
-        let value = std::fs::read_to_string(format!(
-            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/value.yaml"
-        ))
-        .expect("cannot find test asset");
-        let value: Value = serde_yaml::from_str(&value).unwrap();
-        let body: BeaconBlockBody = serde_json::from_value(value.clone()).unwrap();
+        let value = read_value(case);
+        let body: BeaconBlockBodyBellatrix = serde_json::from_value(value.clone()).unwrap();
         let serialized = serde_json::to_value(body).unwrap();
         assert_eq!(serialized, value);
     }
@@ -173,20 +252,43 @@ mod test {
     #[case("case_3")]
     #[case("case_4")]
     fn ssz(#[case] case: &str) {
-        let value = std::fs::read_to_string(format!(
-            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/value.yaml"
-        ))
-        .expect("cannot find test asset");
-        let value: Value = serde_yaml::from_str(&value).unwrap();
-        let body: BeaconBlockBody = serde_json::from_value(value).unwrap();
+        let body: BeaconBlockBodyBellatrix = serde_json::from_value(read_value(case)).unwrap();
+        let expected = read_ssz(case);
+        BeaconBlockBodyBellatrix::from_ssz_bytes(&expected).unwrap();
+        assert_eq!(body.as_ssz_bytes(), expected);
+    }
 
-        let compressed = std::fs::read(format!(
-            "./src/assets/test/beacon/BeaconBlockBody/ssz_random/{case}/serialized.ssz_snappy"
-        ))
-        .expect("cannot find test asset");
-        let mut decoder = snap::raw::Decoder::new();
-        let expected = decoder.decompress_vec(&compressed).unwrap();
-        BeaconBlockBody::from_ssz_bytes(&expected).unwrap();
+    #[rstest]
+    #[case("case_0")]
+    #[case("case_1")]
+    fn fork_of_bellatrix_body(#[case] case: &str) {
+        let body: BeaconBlockBody = serde_json::from_value(read_value(case)).unwrap();
+        assert_eq!(body.fork_name(), ForkName::Bellatrix);
+
+        let expected = read_ssz(case);
+        let decoded = BeaconBlockBody::from_ssz_bytes(&expected, ForkName::Bellatrix).unwrap();
+        assert_eq!(decoded, body);
         assert_eq!(body.as_ssz_bytes(), expected);
     }
+
+    #[rstest]
+    #[case("case_0")]
+    #[case("case_1")]
+    fn capella_body_round_trip(#[case] case: &str) {
+        let value = capella_value(case);
+        let body: BeaconBlockBody = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(body.fork_name(), ForkName::Capella);
+        assert_eq!(
+            body.execution_payload_capella().unwrap().withdrawals.len(),
+            1
+        );
+        assert_eq!(serde_json::to_value(&body).unwrap(), value);
+
+        let encoded = body.as_ssz_bytes();
+        assert_eq!(
+            BeaconBlockBody::from_ssz_bytes(&encoded, ForkName::Capella).unwrap(),
+            body
+        );
+        assert!(BeaconBlockBody::from_ssz_bytes(&encoded, ForkName::Bellatrix).is_err());
+    }
 }
@@ -0,0 +1,410 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, ensure};
+use eth_trie::{EthTrie, MemoryDB, Trie};
+use ethereum_types::{H256, H64, U256};
+use keccak_hash::KECCAK_EMPTY_LIST_RLP;
+use rlp::RlpStream;
+use serde::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum, VariableList};
+use superstruct::superstruct;
+
+use super::{
+    fork::ForkName,
+    serde::{de_hex_to_txs, de_number_to_u256, se_hex_to_number, se_txs_to_hex},
+};
+use crate::execution::header::Header;
+use crate::wrapped::{bloom::Bloom, bytes::Bytes, h160::H160};
+
+pub type Transaction = VariableList<u8, typenum::U1073741824>;
+pub type Transactions = VariableList<Transaction, typenum::U1048576>;
+pub type Withdrawals = VariableList<Withdrawal, typenum::U16>;
+
+/// Types based off specs @
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md
+#[superstruct(
+    variants(Bellatrix, Capella),
+    variant_attributes(
+        derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode),
+        serde(deny_unknown_fields)
+    )
+)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub struct ExecutionPayload {
+    pub parent_hash: H256,
+    pub fee_recipient: H160,
+    pub state_root: H256,
+    pub receipts_root: H256,
+    pub logs_bloom: Bloom,
+    pub prev_randao: H256, // 'difficulty' in the yellow paper
+    pub block_number: u64, // 'number' in the yellow paper
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Bytes,
+    #[serde(deserialize_with = "de_number_to_u256")]
+    #[serde(serialize_with = "se_hex_to_number")]
+    pub base_fee_per_gas: U256,
+    // Extra payload fields
+    pub block_hash: H256, // Hash of execution block
+    #[serde(serialize_with = "se_txs_to_hex")]
+    #[serde(deserialize_with = "de_hex_to_txs")]
+    pub transactions: Transactions,
+    #[superstruct(only(Capella))]
+    pub withdrawals: Withdrawals,
+}
+
+impl ExecutionPayload {
+    /// Decodes an SSZ encoded execution payload of the given fork.
+    pub fn from_ssz_bytes(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                ExecutionPayloadBellatrix::from_ssz_bytes(bytes).map(Self::Bellatrix)
+            }
+            ForkName::Capella => ExecutionPayloadCapella::from_ssz_bytes(bytes).map(Self::Capella),
+        }
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+        }
+    }
+}
+
+impl Encode for ExecutionPayload {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Bellatrix(payload) => payload.ssz_append(buf),
+            Self::Capella(payload) => payload.ssz_append(buf),
+        }
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        match self {
+            Self::Bellatrix(payload) => payload.ssz_bytes_len(),
+            Self::Capella(payload) => payload.ssz_bytes_len(),
+        }
+    }
+}
+
+/// Builds the execution block header that the payload commits to through its block hash.
+impl TryFrom<&ExecutionPayload> for Header {
+    type Error = anyhow::Error;
+
+    fn try_from(payload: &ExecutionPayload) -> anyhow::Result<Self> {
+        let withdrawals_root = match payload {
+            ExecutionPayload::Bellatrix(_) => None,
+            ExecutionPayload::Capella(payload) => Some(withdrawals_root(&payload.withdrawals)?),
+        };
+        Ok(Header {
+            parent_hash: *payload.parent_hash(),
+            // Post-merge blocks have no uncles, no difficulty and a zero nonce.
+            uncles_hash: KECCAK_EMPTY_LIST_RLP,
+            author: payload.fee_recipient().clone().into(),
+            state_root: *payload.state_root(),
+            transactions_root: transactions_root(payload.transactions())?,
+            receipts_root: *payload.receipts_root(),
+            logs_bloom: payload.logs_bloom().clone().into(),
+            difficulty: U256::zero(),
+            number: *payload.block_number(),
+            gas_limit: U256::from(*payload.gas_limit()),
+            gas_used: U256::from(*payload.gas_used()),
+            timestamp: *payload.timestamp(),
+            extra_data: payload.extra_data().as_ref().to_vec(),
+            mix_hash: Some(*payload.prev_randao()),
+            nonce: Some(H64::zero()),
+            base_fee_per_gas: Some(*payload.base_fee_per_gas()),
+            withdrawals_root,
+        })
+    }
+}
+
+/// Returns the root of the trie of the payload's transactions, as found in the execution header.
+fn transactions_root(transactions: &Transactions) -> anyhow::Result<H256> {
+    trie_root(transactions.iter().map(|tx| tx.to_vec()))
+        .map_err(|err| anyhow!("Error calculating transactions root: {err}"))
+}
+
+/// Returns the root of the trie of the payload's withdrawals, as found in the execution header.
+fn withdrawals_root(withdrawals: &Withdrawals) -> anyhow::Result<H256> {
+    trie_root(withdrawals.iter().map(Withdrawal::rlp_encode))
+        .map_err(|err| anyhow!("Error calculating withdrawals root: {err}"))
+}
+
+fn trie_root(values: impl Iterator<Item = Vec<u8>>) -> anyhow::Result<H256> {
+    let memdb = Arc::new(MemoryDB::new(true));
+    let mut trie = EthTrie::new(memdb);
+    for (index, value) in values.enumerate() {
+        let path = rlp::encode(&index).freeze().to_vec();
+        trie.insert(&path, &value)
+            .map_err(|err| anyhow!("{err:?}"))?;
+    }
+    trie.root_hash().map_err(|err| anyhow!("{err:?}"))
+}
+
+/// Types based off specs @
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md
+///
+/// Unlike in the execution header, `transactions_root` and `withdrawals_root` are SSZ hash tree
+/// roots of the payload's transactions and withdrawals.
+#[superstruct(
+    variants(Bellatrix, Capella),
+    variant_attributes(
+        derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode),
+        serde(deny_unknown_fields)
+    )
+)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub struct ExecutionPayloadHeader {
+    pub parent_hash: H256,
+    pub fee_recipient: H160,
+    pub state_root: H256,
+    pub receipts_root: H256,
+    pub logs_bloom: Bloom,
+    pub prev_randao: H256,
+    pub block_number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Bytes,
+    #[serde(deserialize_with = "de_number_to_u256")]
+    #[serde(serialize_with = "se_hex_to_number")]
+    pub base_fee_per_gas: U256,
+    pub block_hash: H256,
+    pub transactions_root: H256,
+    #[superstruct(only(Capella))]
+    pub withdrawals_root: H256,
+}
+
+impl ExecutionPayloadHeader {
+    /// Decodes an SSZ encoded execution payload header of the given fork.
+    pub fn from_ssz_bytes(bytes: &[u8], fork_name: ForkName) -> Result<Self, DecodeError> {
+        match fork_name {
+            ForkName::Bellatrix => {
+                ExecutionPayloadHeaderBellatrix::from_ssz_bytes(bytes).map(Self::Bellatrix)
+            }
+            ForkName::Capella => {
+                ExecutionPayloadHeaderCapella::from_ssz_bytes(bytes).map(Self::Capella)
+            }
+        }
+    }
+
+    pub fn fork_name(&self) -> ForkName {
+        match self {
+            Self::Bellatrix(_) => ForkName::Bellatrix,
+            Self::Capella(_) => ForkName::Capella,
+        }
+    }
+
+    /// Builds the payload header of a post-merge execution block header.
+    ///
+    /// The hash tree roots of the block's transactions and withdrawals can't be derived from the
+    /// execution header, so they have to be provided. A Capella payload header is returned if
+    /// `withdrawals_root` is given, which requires the execution header to have a withdrawals
+    /// root as well.
+    pub fn from_header(
+        header: &Header,
+        transactions_root: H256,
+        withdrawals_root: Option<H256>,
+    ) -> anyhow::Result<Self> {
+        ensure!(
+            header.withdrawals_root.is_some() == withdrawals_root.is_some(),
+            "Withdrawals root must be given for, and only for, headers with withdrawals"
+        );
+        let prev_randao = header
+            .mix_hash
+            .ok_or_else(|| anyhow!("Header of block {} has no mix hash", header.number))?;
+        let base_fee_per_gas = header
+            .base_fee_per_gas
+            .ok_or_else(|| anyhow!("Header of block {} has no base fee", header.number))?;
+        ensure!(
+            header.gas_limit <= U256::from(u64::MAX) && header.gas_used <= header.gas_limit,
+            "Header of block {} has an invalid gas limit",
+            header.number
+        );
+
+        let payload_header = ExecutionPayloadHeaderBellatrix {
+            parent_hash: header.parent_hash,
+            fee_recipient: header.author.into(),
+            state_root: header.state_root,
+            receipts_root: header.receipts_root,
+            logs_bloom: header.logs_bloom.into(),
+            prev_randao,
+            block_number: header.number,
+            gas_limit: header.gas_limit.as_u64(),
+            gas_used: header.gas_used.as_u64(),
+            timestamp: header.timestamp,
+            extra_data: header.extra_data.clone().into(),
+            base_fee_per_gas,
+            block_hash: header.hash(),
+            transactions_root,
+        };
+        Ok(match withdrawals_root {
+            None => Self::Bellatrix(payload_header),
+            Some(withdrawals_root) => Self::Capella(ExecutionPayloadHeaderCapella {
+                parent_hash: payload_header.parent_hash,
+                fee_recipient: payload_header.fee_recipient,
+                state_root: payload_header.state_root,
+                receipts_root: payload_header.receipts_root,
+                logs_bloom: payload_header.logs_bloom,
+                prev_randao: payload_header.prev_randao,
+                block_number: payload_header.block_number,
+                gas_limit: payload_header.gas_limit,
+                gas_used: payload_header.gas_used,
+                timestamp: payload_header.timestamp,
+                extra_data: payload_header.extra_data,
+                base_fee_per_gas: payload_header.base_fee_per_gas,
+                block_hash: payload_header.block_hash,
+                transactions_root: payload_header.transactions_root,
+                withdrawals_root,
+            }),
+        })
+    }
+}
+
+impl Encode for ExecutionPayloadHeader {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Bellatrix(header) => header.ssz_append(buf),
+            Self::Capella(header) => header.ssz_append(buf),
+        }
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        match self {
+            Self::Bellatrix(header) => header.ssz_bytes_len(),
+            Self::Capella(header) => header.ssz_bytes_len(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, Decode, Encode)]
+pub struct Withdrawal {
+    pub index: u64,
+    pub validator_index: u64,
+    pub address: H160,
+    pub amount: u64,
+}
+
+impl Withdrawal {
+    /// Returns the RLP encoding of the withdrawal, as committed to by the execution header.
+    fn rlp_encode(&self) -> Vec<u8> {
+        let address: ethereum_types::H160 = self.address.clone().into();
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&self.index)
+            .append(&self.validator_index)
+            .append(&address)
+            .append(&self.amount);
+        stream.out().freeze().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use keccak_hash::KECCAK_NULL_RLP;
+    use serde_json::Value;
+
+    fn hex_to_u64(value: &Value) -> u64 {
+        u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+    }
+
+    /// Returns the header and withdrawals of the first Capella block on mainnet.
+    fn block_17034871() -> (Header, Withdrawals) {
+        let block = std::fs::read_to_string("./src/assets/trin/block_17034871_value.json")
+            .expect("cannot find test asset");
+        let block: Value = serde_json::from_str(&block).unwrap();
+        let block = &block["result"];
+        let header: Header = serde_json::from_value(block.clone()).unwrap();
+        let withdrawals: Vec<Withdrawal> = block["withdrawals"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|withdrawal| Withdrawal {
+                index: hex_to_u64(&withdrawal["index"]),
+                validator_index: hex_to_u64(&withdrawal["validatorIndex"]),
+                address: serde_json::from_value(withdrawal["address"].clone()).unwrap(),
+                amount: hex_to_u64(&withdrawal["amount"]),
+            })
+            .collect();
+        (header, VariableList::from(withdrawals))
+    }
+
+    #[test]
+    fn capella_payload_to_header() {
+        let (header, withdrawals) = block_17034871();
+        let payload = ExecutionPayload::Capella(ExecutionPayloadCapella {
+            parent_hash: header.parent_hash,
+            fee_recipient: header.author.into(),
+            state_root: header.state_root,
+            receipts_root: header.receipts_root,
+            logs_bloom: header.logs_bloom.into(),
+            prev_randao: header.mix_hash.unwrap(),
+            block_number: header.number,
+            gas_limit: header.gas_limit.as_u64(),
+            gas_used: header.gas_used.as_u64(),
+            timestamp: header.timestamp,
+            extra_data: header.extra_data.clone().into(),
+            base_fee_per_gas: header.base_fee_per_gas.unwrap(),
+            block_hash: header.hash(),
+            // The asset only contains the hashes of the block's transactions.
+            transactions: VariableList::empty(),
+            withdrawals,
+        });
+
+        let mut converted = Header::try_from(&payload).unwrap();
+        assert_eq!(converted.withdrawals_root, header.withdrawals_root);
+        assert_eq!(converted.transactions_root, KECCAK_NULL_RLP);
+        converted.transactions_root = header.transactions_root;
+        assert_eq!(converted, header);
+        assert_eq!(converted.hash(), *payload.block_hash());
+    }
+
+    #[test]
+    fn payload_header_from_header() {
+        let (header, _) = block_17034871();
+        let transactions_root = H256::repeat_byte(1);
+        let withdrawals_root = H256::repeat_byte(2);
+
+        let payload_header =
+            ExecutionPayloadHeader::from_header(&header, transactions_root, Some(withdrawals_root))
+                .unwrap();
+        assert_eq!(payload_header.fork_name(), ForkName::Capella);
+        assert_eq!(*payload_header.block_hash(), header.hash());
+        assert_eq!(*payload_header.block_number(), header.number);
+        assert_eq!(*payload_header.transactions_root(), transactions_root);
+        assert_eq!(
+            *payload_header.withdrawals_root().unwrap(),
+            withdrawals_root
+        );
+
+        let encoded = payload_header.as_ssz_bytes();
+        assert_eq!(
+            ExecutionPayloadHeader::from_ssz_bytes(&encoded, ForkName::Capella).unwrap(),
+            payload_header
+        );
+        let value = serde_json::to_value(&payload_header).unwrap();
+        assert_eq!(
+            serde_json::from_value::<ExecutionPayloadHeader>(value).unwrap(),
+            payload_header
+        );
+
+        // Capella headers require the withdrawals root, and vice versa.
+        assert!(ExecutionPayloadHeader::from_header(&header, transactions_root, None).is_err());
+    }
+}
@@ -0,0 +1,9 @@
+/// Consensus layer forks whose beacon block containers differ.
+///
+/// SSZ encodings carry no fork information, so decoding fork-specific containers requires the
+/// fork of the slot they belong to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForkName {
+    Bellatrix,
+    Capella,
+}
@@ -19,6 +19,7 @@ mod test {
     use ::ssz::Encode;
     use rstest::rstest;
     use serde_json::Value;
+    use trin_utils::snappy::decompress_raw;
 
     /// Test vectors sourced from:
     /// https://github.com/ethereum/consensus-spec-tests/commit/c6e69469a75392b35169bc6234d4d3e6c4e288da
@@ -57,8 +58,7 @@ mod test {
             "./src/assets/test/beacon/BeaconBlockHeader/ssz_random/{case}/serialized.ssz_snappy"
         ))
         .expect("cannot find test asset");
-        let mut decoder = snap::raw::Decoder::new();
-        let expected = decoder.decompress_vec(&expected).unwrap();
+        let expected = decompress_raw(&expected).unwrap();
         assert_eq!(body.as_ssz_bytes(), expected);
     }
 }
@@ -1,4 +1,9 @@
+pub mod beacon_block;
+#[cfg(feature = "crypto")]
+pub mod bls;
 pub mod body;
+pub mod execution_payload;
+pub mod fork;
 pub mod header;
 pub mod header_proof;
 pub mod proof;
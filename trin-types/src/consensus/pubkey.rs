@@ -6,9 +6,9 @@ use trin_utils::bytes::{hex_decode, hex_encode};
 /// Types based off specs @
 /// https://github.com/ethereum/consensus-specs/blob/5970ae56a1/specs/phase0/beacon-chain.md
 #[derive(Debug, PartialEq, Clone)]
-pub struct PubKey([u8; 48]);
+pub struct BlsPublicKey(pub [u8; 48]);
 
-impl Decode for PubKey {
+impl Decode for BlsPublicKey {
     fn is_ssz_fixed_len() -> bool {
         true
     }
@@ -23,7 +23,7 @@ impl Decode for PubKey {
     }
 }
 
-impl Encode for PubKey {
+impl Encode for BlsPublicKey {
     fn is_ssz_fixed_len() -> bool {
         true
     }
@@ -39,7 +39,7 @@ impl Encode for PubKey {
     }
 }
 
-impl<'de> Deserialize<'de> for PubKey {
+impl<'de> Deserialize<'de> for BlsPublicKey {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
@@ -52,7 +52,7 @@ impl<'de> Deserialize<'de> for PubKey {
     }
 }
 
-impl Serialize for PubKey {
+impl Serialize for BlsPublicKey {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -3,7 +3,7 @@ use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serializer};
 use serde_json::Value;
 use ssz_types::VariableList;
 
-use super::body::{Transaction, Transactions};
+use super::execution_payload::{Transaction, Transactions};
 use trin_utils::bytes::{hex_decode, hex_encode};
 
 pub fn se_txs_to_hex<S>(value: &Transactions, serializer: S) -> Result<S::Ok, S::Error>
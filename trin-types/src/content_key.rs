@@ -6,6 +6,7 @@ use ssz::{self, Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use ssz_types::{typenum, FixedVector, VariableList};
 use std::fmt;
+use std::time::Duration;
 use thiserror::Error;
 
 use quickcheck::{Arbitrary, Gen};
@@ -94,6 +95,15 @@ pub trait OverlayContentKey:
     fn content_id(&self) -> [u8; 32];
     /// Returns the bytes of the content key.
     fn to_bytes(&self) -> Vec<u8>;
+    /// Returns how long the content may be stored for, or `None` if it may be stored
+    /// indefinitely.
+    fn ttl(&self) -> Option<Duration> {
+        None
+    }
+    /// Returns the name of the type of content the key refers to, e.g. for metrics labels.
+    fn content_type(&self) -> &'static str {
+        "unknown"
+    }
     /// Returns the content key as a hex encoded "0x"-prefixed string.
     fn to_hex(&self) -> String {
         hex_encode(self.to_bytes())
@@ -112,6 +122,10 @@ pub enum HistoryContentKey {
     BlockReceipts(BlockReceiptsKey),
     /// An epoch header accumulator.
     EpochAccumulator(EpochAccumulatorKey),
+    /// A block header with accumulator proof, keyed by block number.
+    BlockHeaderByNumber(BlockHeaderByNumberKey),
+    /// A recent block header that is not yet provable through an accumulator.
+    EphemeralHeader(EphemeralHeaderKey),
 }
 
 impl Serialize for HistoryContentKey {
@@ -191,6 +205,39 @@ pub struct EpochAccumulatorKey {
     pub epoch_hash: H256,
 }
 
+/// A key for a block header, by block number.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+pub struct BlockHeaderByNumberKey {
+    /// Number of the block.
+    pub block_number: u64,
+}
+
+/// Number of the most recent headers near the chain tip that are served as ephemeral content.
+pub const EPHEMERAL_HEADER_COUNT: u64 = 256;
+
+/// How long an ephemeral header is stored for: roughly the time it takes for
+/// `EPHEMERAL_HEADER_COUNT` newer blocks to be produced.
+pub const EPHEMERAL_HEADER_TTL: Duration = Duration::from_secs(EPHEMERAL_HEADER_COUNT * 12);
+
+/// A key for a recent block header near the chain tip.
+#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq)]
+pub struct EphemeralHeaderKey {
+    /// Hash of the block.
+    pub block_hash: [u8; 32],
+}
+
+/// The names of the types of history content, as returned by `HistoryContentKey::content_type`.
+///
+/// The order matches the selectors of the content keys.
+pub const HISTORY_CONTENT_TYPES: [&str; 6] = [
+    "block_header_with_proof",
+    "block_body",
+    "block_receipts",
+    "epoch_accumulator",
+    "block_header_by_number",
+    "ephemeral_header",
+];
+
 impl From<&HistoryContentKey> for Vec<u8> {
     fn from(val: &HistoryContentKey) -> Self {
         val.as_ssz_bytes()
@@ -237,6 +284,16 @@ impl fmt::Display for HistoryContentKey {
                     hex_encode_compact(acc.epoch_hash.as_fixed_bytes())
                 )
             }
+            Self::BlockHeaderByNumber(header) => {
+                format!(
+                    "BlockHeaderByNumber {{ block_number: {} }}",
+                    header.block_number
+                )
+            }
+            Self::EphemeralHeader(header) => format!(
+                "EphemeralHeader {{ block_hash: {} }}",
+                hex_encode_compact(header.block_hash)
+            ),
         };
 
         write!(f, "{s}")
@@ -250,6 +307,17 @@ impl OverlayContentKey for HistoryContentKey {
         sha256.finalize().into()
     }
 
+    fn content_type(&self) -> &'static str {
+        match self {
+            HistoryContentKey::BlockHeaderWithProof(_) => HISTORY_CONTENT_TYPES[0],
+            HistoryContentKey::BlockBody(_) => HISTORY_CONTENT_TYPES[1],
+            HistoryContentKey::BlockReceipts(_) => HISTORY_CONTENT_TYPES[2],
+            HistoryContentKey::EpochAccumulator(_) => HISTORY_CONTENT_TYPES[3],
+            HistoryContentKey::BlockHeaderByNumber(_) => HISTORY_CONTENT_TYPES[4],
+            HistoryContentKey::EphemeralHeader(_) => HISTORY_CONTENT_TYPES[5],
+        }
+    }
+
     fn to_bytes(&self) -> Vec<u8> {
         let mut bytes: Vec<u8> = Vec::new();
 
@@ -270,10 +338,25 @@ impl OverlayContentKey for HistoryContentKey {
                 bytes.push(0x03);
                 bytes.extend_from_slice(&k.epoch_hash.0);
             }
+            HistoryContentKey::BlockHeaderByNumber(k) => {
+                bytes.push(0x04);
+                bytes.extend_from_slice(&k.block_number.to_le_bytes());
+            }
+            HistoryContentKey::EphemeralHeader(k) => {
+                bytes.push(0x05);
+                bytes.extend_from_slice(&k.block_hash);
+            }
         }
 
         bytes
     }
+
+    fn ttl(&self) -> Option<Duration> {
+        match self {
+            HistoryContentKey::EphemeralHeader(_) => Some(EPHEMERAL_HEADER_TTL),
+            _ => None,
+        }
+    }
 }
 
 /// A content key in the state overlay network.
@@ -482,6 +565,16 @@ impl OverlayContentKey for StateContentKey {
     fn to_bytes(&self) -> Vec<u8> {
         panic!("Not implemented: Implement in pr along w/ new state content key spec update.")
     }
+
+    fn content_type(&self) -> &'static str {
+        match self {
+            StateContentKey::AccountTrieNode(_) => "account_trie_node",
+            StateContentKey::ContractStorageTrieNode(_) => "contract_storage_trie_node",
+            StateContentKey::AccountTrieProof(_) => "account_trie_proof",
+            StateContentKey::ContractStorageTrieProof(_) => "contract_storage_trie_proof",
+            StateContentKey::ContractBytecode(_) => "contract_bytecode",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -604,6 +697,62 @@ mod test {
         assert_eq!(key.to_hex(), KEY_STR);
     }
 
+    #[test]
+    fn block_header_by_number() {
+        const KEY_STR: &str = "0x0441420f0000000000";
+        let expected_content_key = hex_decode(KEY_STR).unwrap();
+        let expected_content_id: [u8; 32] = [
+            0x9c, 0x5f, 0x4a, 0x39, 0x5d, 0xac, 0x06, 0xa1, 0x0a, 0x62, 0x3d, 0xc7, 0xa9, 0x0d,
+            0x9b, 0xd9, 0xf2, 0x37, 0xaf, 0x2c, 0x1f, 0xc5, 0x56, 0x0c, 0x17, 0x57, 0xf2, 0xdc,
+            0xe4, 0x57, 0x44, 0xb5,
+        ];
+
+        let key = HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey {
+            block_number: 1_000_001,
+        });
+
+        // round trip
+        let decoded = HistoryContentKey::try_from(key.to_bytes().to_vec()).unwrap();
+        assert_eq!(decoded, key);
+
+        assert_eq!(key.to_bytes(), expected_content_key);
+        assert_eq!(key.content_id(), expected_content_id);
+        assert_eq!(
+            key.to_string(),
+            "BlockHeaderByNumber { block_number: 1000001 }"
+        );
+        assert_eq!(key.to_hex(), KEY_STR);
+    }
+
+    #[test]
+    fn ephemeral_header() {
+        const KEY_STR: &str =
+            "0x05d1c390624d3bd4e409a61a858e5dcc5517729a9170d014a6c96530d64dd8621d";
+        let expected_content_key = hex_decode(KEY_STR).unwrap();
+        let expected_content_id: [u8; 32] = [
+            0x76, 0x73, 0xa0, 0x2c, 0x04, 0x48, 0xcc, 0x46, 0x34, 0x81, 0xab, 0x3e, 0x2f, 0x1a,
+            0x72, 0x32, 0x38, 0xf1, 0xae, 0x07, 0x9d, 0x3c, 0x28, 0xd6, 0x03, 0x00, 0x73, 0x73,
+            0x85, 0x26, 0xc1, 0x6a,
+        ];
+
+        let key = HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+            block_hash: BLOCK_HASH,
+        });
+
+        // round trip
+        let decoded = HistoryContentKey::try_from(key.to_bytes().to_vec()).unwrap();
+        assert_eq!(decoded, key);
+
+        assert_eq!(key.to_bytes(), expected_content_key);
+        assert_eq!(key.content_id(), expected_content_id);
+        assert_eq!(
+            key.to_string(),
+            "EphemeralHeader { block_hash: 0xd1c3..621d }"
+        );
+        assert_eq!(key.to_hex(), KEY_STR);
+        assert_eq!(key.ttl(), Some(EPHEMERAL_HEADER_TTL));
+    }
+
     #[test]
     fn ser_de_block_header() {
         let content_key_json =
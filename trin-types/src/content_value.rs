@@ -1,4 +1,5 @@
 use crate::constants::CONTENT_ABSENT;
+use crate::content_key::HistoryContentKey;
 use crate::execution::accumulator::EpochAccumulator;
 use crate::execution::block_body::BlockBody;
 use crate::execution::header::HeaderWithProof;
@@ -30,6 +31,93 @@ pub enum HistoryContentValue {
     EpochAccumulator(EpochAccumulator),
 }
 
+impl HistoryContentValue {
+    /// Decodes `buf` as the type of value that the selector of `content_key` refers to.
+    ///
+    /// Content values carry no selector of their own, so this should be preferred over
+    /// `ContentValue::decode` whenever the content key is known, since the latter has to guess
+    /// the type from the bytes.
+    pub fn decode_with_key(
+        content_key: &HistoryContentKey,
+        buf: &[u8],
+    ) -> Result<Self, ContentValueError> {
+        let decode_error = |decode_error| ContentValueError::DecodeSsz {
+            decode_error,
+            input: hex_encode(buf),
+        };
+        match content_key {
+            HistoryContentKey::BlockHeaderWithProof(_)
+            | HistoryContentKey::BlockHeaderByNumber(_)
+            | HistoryContentKey::EphemeralHeader(_) => HeaderWithProof::from_ssz_bytes(buf)
+                .map(Self::BlockHeaderWithProof)
+                .map_err(decode_error),
+            HistoryContentKey::BlockBody(_) => BlockBody::from_ssz_bytes(buf)
+                .map(Self::BlockBody)
+                .map_err(decode_error),
+            HistoryContentKey::BlockReceipts(_) => Receipts::from_ssz_bytes(buf)
+                .map(Self::Receipts)
+                .map_err(decode_error),
+            HistoryContentKey::EpochAccumulator(_) => EpochAccumulator::from_ssz_bytes(buf)
+                .map(Self::EpochAccumulator)
+                .map_err(decode_error),
+        }
+    }
+
+    /// Returns true if this is the type of value that the selector of `content_key` refers to.
+    pub fn matches_key(&self, content_key: &HistoryContentKey) -> bool {
+        matches!(
+            (self, content_key),
+            (
+                Self::BlockHeaderWithProof(_),
+                HistoryContentKey::BlockHeaderWithProof(_)
+                    | HistoryContentKey::BlockHeaderByNumber(_)
+                    | HistoryContentKey::EphemeralHeader(_)
+            ) | (Self::BlockBody(_), HistoryContentKey::BlockBody(_))
+                | (Self::Receipts(_), HistoryContentKey::BlockReceipts(_))
+                | (
+                    Self::EpochAccumulator(_),
+                    HistoryContentKey::EpochAccumulator(_)
+                )
+        )
+    }
+}
+
+/// An encoded content value, as received by the JSON-RPC server. Content values carry no selector
+/// of their own, so the value is only decoded once its content key is known, with
+/// `HistoryContentValue::decode_with_key`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawContentValue(pub Vec<u8>);
+
+impl From<HistoryContentValue> for RawContentValue {
+    fn from(content_value: HistoryContentValue) -> Self {
+        Self(content_value.encode())
+    }
+}
+
+impl Serialize for RawContentValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hex_encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawContentValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        if s.as_str() == CONTENT_ABSENT {
+            return Err(ContentValueError::DeserializeAbsentContent)
+                .map_err(serde::de::Error::custom);
+        }
+        let content_bytes = hex_decode(&s).map_err(serde::de::Error::custom)?;
+        Ok(Self(content_bytes))
+    }
+}
+
 /// A content response from the RPC server.
 ///
 /// This type allows the RPC response to be non-error,
@@ -239,6 +327,46 @@ mod test {
         }
     }
 
+    #[test]
+    fn decode_with_key_fluffy_header_with_proof() {
+        let file =
+            fs::read_to_string("../trin-validation/src/assets/fluffy/header_with_proofs.json")
+                .unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let json = json.as_object().unwrap();
+        for obj in json.values() {
+            let content_key: HistoryContentKey =
+                serde_json::from_value(obj.get("content_key").unwrap().clone()).unwrap();
+            let content_value = hex_decode(obj.get("value").unwrap().as_str().unwrap()).unwrap();
+
+            let value = HistoryContentValue::decode_with_key(&content_key, &content_value).unwrap();
+            assert!(matches!(
+                value,
+                HistoryContentValue::BlockHeaderWithProof(_)
+            ));
+            assert!(value.matches_key(&content_key));
+            assert_eq!(value.encode(), content_value);
+        }
+    }
+
+    #[test]
+    fn decode_with_key_uses_key_selector() {
+        let epoch_acc_ssz = fs::read("../trin-validation/src/assets/fluffy/epoch_acc.bin").unwrap();
+        let content_key = HistoryContentKey::BlockBody(crate::content_key::BlockBodyKey {
+            block_hash: [0; 32],
+        });
+        let error = HistoryContentValue::decode_with_key(&content_key, &epoch_acc_ssz);
+        assert!(matches!(error, Err(ContentValueError::DecodeSsz { .. })));
+    }
+
+    #[test]
+    fn raw_content_value_is_not_decoded() {
+        let raw: RawContentValue = serde_json::from_str("\"0x010203\"").unwrap();
+        assert_eq!(raw, RawContentValue(vec![1, 2, 3]));
+        assert_eq!(serde_json::to_string(&raw).unwrap(), "\"0x010203\"");
+        assert!(serde_json::from_str::<RawContentValue>("\"0x\"").is_err());
+    }
+
     #[test]
     fn ssz_serde_encode_decode_fluffy_epoch_accumulator() {
         // values sourced from: https://github.com/status-im/portal-spec-tests
@@ -0,0 +1,146 @@
+//! Round-trips history network content keys and values against regression vectors, so that
+//! changes to their encoding are caught by the test suite rather than on the network.
+//!
+//! The vectors live in `trin-types/src/assets/test/content_vectors`. They use the JSON layout of
+//! portal-spec-tests, but are not the official vectors: they were built from the spec content key
+//! values and the fluffy and trin assets already in the tree.
+
+use std::fs;
+
+use ethereum_types::U256;
+use serde_json::{json, Value};
+use ssz::Decode;
+use tree_hash::TreeHash;
+
+use crate::content_key::{
+    BlockBodyKey, BlockHeaderByNumberKey, BlockHeaderKey, BlockReceiptsKey, EphemeralHeaderKey,
+    EpochAccumulatorKey, HistoryContentKey, OverlayContentKey,
+};
+use crate::content_value::{ContentValue, HistoryContentValue};
+use crate::execution::accumulator::EpochAccumulator;
+use crate::schema::history_content_key_schema;
+use trin_utils::bytes::{hex_decode, hex_encode};
+
+const VECTORS_DIR: &str = "../trin-types/src/assets/test/content_vectors";
+
+fn read_vectors(path: &str) -> Vec<Value> {
+    let file = fs::read_to_string(format!("{VECTORS_DIR}/{path}")).unwrap();
+    serde_json::from_str(&file).unwrap()
+}
+
+fn hash_input(input: &Value, field: &str) -> [u8; 32] {
+    let hash = hex_decode(input[field].as_str().unwrap()).unwrap();
+    hash.try_into().unwrap()
+}
+
+/// Builds the content key described by the `input` of a content key vector.
+fn content_key_from_input(name: &str, input: &Value) -> HistoryContentKey {
+    match name {
+        "block_header" => HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: hash_input(input, "block_hash"),
+        }),
+        "block_body" => HistoryContentKey::BlockBody(BlockBodyKey {
+            block_hash: hash_input(input, "block_hash"),
+        }),
+        "receipts" => HistoryContentKey::BlockReceipts(BlockReceiptsKey {
+            block_hash: hash_input(input, "block_hash"),
+        }),
+        "epoch_accumulator" => HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+            epoch_hash: hash_input(input, "epoch_hash").into(),
+        }),
+        "block_header_by_number" => {
+            HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey {
+                block_number: input["block_number"].as_u64().unwrap(),
+            })
+        }
+        "ephemeral_header" => HistoryContentKey::EphemeralHeader(EphemeralHeaderKey {
+            block_hash: hash_input(input, "block_hash"),
+        }),
+        _ => panic!("Unknown content key vector: {name}"),
+    }
+}
+
+#[test]
+fn history_content_keys() {
+    let vectors = read_vectors("history/content_keys.json");
+    let schema = history_content_key_schema();
+    let variants = schema["oneOf"].as_array().unwrap();
+    assert_eq!(vectors.len(), variants.len(), "every key type has a vector");
+
+    for vector in vectors {
+        let name = vector["name"].as_str().unwrap();
+        let encoded = vector["content_key"].as_str().unwrap();
+        let bytes = hex_decode(encoded).unwrap();
+
+        // Decoding and re-encoding is lossless.
+        let content_key = HistoryContentKey::try_from(bytes.clone()).unwrap();
+        assert_eq!(content_key.to_bytes(), bytes, "{name}");
+        assert_eq!(
+            content_key,
+            content_key_from_input(name, &vector["input"]),
+            "{name}"
+        );
+
+        // The JSON representation is the hex string of the vector.
+        assert_eq!(serde_json::to_value(&content_key).unwrap(), json!(encoded));
+        let deserialized: HistoryContentKey = serde_json::from_value(json!(encoded)).unwrap();
+        assert_eq!(deserialized, content_key, "{name}");
+
+        let content_id = content_key.content_id();
+        assert_eq!(
+            hex_encode(content_id),
+            vector["content_id"].as_str().unwrap(),
+            "{name}"
+        );
+        assert_eq!(
+            U256::from_big_endian(&content_id),
+            U256::from_dec_str(vector["content_id_u256"].as_str().unwrap()).unwrap(),
+            "{name}"
+        );
+
+        // The selector picks the schema variant named after the key's content type.
+        assert_eq!(
+            variants[usize::from(bytes[0])]["title"],
+            json!(content_key.content_type()),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn history_content_values() {
+    for vector in read_vectors("history/content_values.json") {
+        let name = vector["name"].as_str().unwrap();
+        let content_key: HistoryContentKey =
+            serde_json::from_value(vector["content_key"].clone()).unwrap();
+        let encoded = vector["content_value"].as_str().unwrap();
+        let bytes = hex_decode(encoded).unwrap();
+
+        let content_value = HistoryContentValue::decode_with_key(&content_key, &bytes).unwrap();
+        assert!(content_value.matches_key(&content_key), "{name}");
+        assert_eq!(content_value.encode(), bytes, "{name}");
+        assert_eq!(
+            serde_json::to_value(&content_value).unwrap(),
+            json!(encoded),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn history_epoch_accumulator_value() {
+    // The epoch accumulator is too large to inline in the JSON vectors, so the binary vector
+    // shared with fluffy is used instead.
+    let bytes = fs::read("../trin-validation/src/assets/fluffy/epoch_acc.bin").unwrap();
+    let epoch_acc = EpochAccumulator::from_ssz_bytes(&bytes).unwrap();
+    let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey {
+        epoch_hash: epoch_acc.tree_hash_root(),
+    });
+
+    let content_value = HistoryContentValue::decode_with_key(&content_key, &bytes).unwrap();
+    assert_eq!(
+        content_value,
+        HistoryContentValue::EpochAccumulator(epoch_acc)
+    );
+    assert_eq!(content_value.encode(), bytes);
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::{info_span, Span};
+
+static NEXT_CORRELATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies a single operation, such as an inbound JSON-RPC request or a lookup started by the
+/// overlay itself, across all of the log lines it generates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CorrelationId(u64);
+
+impl CorrelationId {
+    /// Returns a correlation id that is unique within this process.
+    pub fn next() -> Self {
+        Self(NEXT_CORRELATION_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+/// Returns a span for an operation, tagged with a new correlation id.
+///
+/// Every event recorded while the span is entered, including in tasks instrumented with it,
+/// carries the `cid` field.
+pub fn operation_span(operation: &'static str) -> Span {
+    info_span!("op", op = operation, cid = %CorrelationId::next())
+}
+
+/// Returns the current span if one is entered, or else a span for a new operation.
+///
+/// This lets work started on behalf of an existing operation keep its correlation id, while work
+/// started by the node itself gets a fresh one.
+pub fn current_or_operation_span(operation: &'static str) -> Span {
+    let span = Span::current();
+    if span.is_none() {
+        operation_span(operation)
+    } else {
+        span
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn correlation_ids_are_unique() {
+        let first = CorrelationId::next();
+        let second = CorrelationId::next();
+        assert_ne!(first, second);
+        assert_eq!(first.to_string().len(), 8);
+    }
+}
@@ -1,13 +1,13 @@
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
-use discv5::enr::CombinedKey;
+use enr::CombinedKey;
 use rlp::Encodable;
 use serde_json::Value;
 use ssz::DecodeError;
 use validator::ValidationError;
 
-pub type Enr = discv5::enr::Enr<CombinedKey>;
+pub type Enr = enr::Enr<CombinedKey>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct SszEnr(pub Enr);
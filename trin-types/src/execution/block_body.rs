@@ -48,6 +48,15 @@ impl BlockBody {
         let hash = Keccak256::digest(&uncles_rlp);
         Ok(H256::from_slice(&hash))
     }
+
+    /// Returns the RLP encoding of the full block, made up of the given header and this body.
+    pub fn rlp_encode_block(&self, header: &Header) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(3);
+        stream.append(header);
+        stream.append_list(&self.txs);
+        stream.append(&self.uncles);
+        stream.out().freeze().to_vec()
+    }
 }
 
 impl TryFrom<EncodedBlockBodyParts> for BlockBody {
@@ -144,10 +153,12 @@ pub enum Transaction {
     Legacy(LegacyTransaction),
     AccessList(AccessListTransaction),
     EIP1559(EIP1559Transaction),
+    Blob(BlobTransaction),
 }
 
 impl Transaction {
-    fn decode(tx: &[u8]) -> Result<Self, DecoderError> {
+    /// Decodes a transaction from its binary encoding.
+    pub fn decode(tx: &[u8]) -> Result<Self, DecoderError> {
         // at least one byte needs to be present
         if tx.is_empty() {
             return Err(DecoderError::RlpIncorrectListLen);
@@ -155,13 +166,70 @@ impl Transaction {
         let id = TransactionId::try_from(tx[0])
             .map_err(|_| DecoderError::Custom("Unknown transaction id"))?;
         match id {
+            TransactionId::Blob => Ok(Self::Blob(rlp::decode(&tx[1..])?)),
             TransactionId::EIP1559 => Ok(Self::EIP1559(rlp::decode(&tx[1..])?)),
             TransactionId::AccessList => Ok(Self::AccessList(rlp::decode(&tx[1..])?)),
             TransactionId::Legacy => Ok(Self::Legacy(rlp::decode(tx)?)),
         }
     }
 
-    fn encode(&self) -> Vec<u8> {
+    /// Returns the fee per gas paid to the block producer, on top of the block's base fee.
+    pub fn effective_tip(&self, base_fee: Option<U256>) -> U256 {
+        let base_fee = base_fee.unwrap_or_default();
+        match self {
+            Self::Legacy(tx) => tx.gas_price.saturating_sub(base_fee),
+            Self::AccessList(tx) => tx.gas_price.saturating_sub(base_fee),
+            Self::EIP1559(tx) => tx
+                .max_priority_fee_per_gas
+                .min(tx.max_fee_per_gas.saturating_sub(base_fee)),
+            Self::Blob(tx) => tx
+                .max_priority_fee_per_gas
+                .min(tx.max_fee_per_gas.saturating_sub(base_fee)),
+        }
+    }
+
+    /// Returns the fee per gas paid by the sender, including the block's base fee.
+    pub fn effective_gas_price(&self, base_fee: Option<U256>) -> U256 {
+        match self {
+            Self::Legacy(tx) => tx.gas_price,
+            Self::AccessList(tx) => tx.gas_price,
+            Self::EIP1559(_) | Self::Blob(_) => base_fee
+                .unwrap_or_default()
+                .saturating_add(self.effective_tip(base_fee)),
+        }
+    }
+
+    /// Returns the Keccak-256 hash of the binary encoding of the transaction.
+    pub fn hash(&self) -> H256 {
+        H256::from_slice(&Keccak256::digest(self.encode()))
+    }
+
+    /// Returns the recipient of the transaction, or `None` if it creates a contract.
+    pub fn to(&self) -> Option<H160> {
+        let to = match self {
+            Self::Legacy(tx) => &tx.to,
+            Self::AccessList(tx) => &tx.to,
+            Self::EIP1559(tx) => &tx.to,
+            Self::Blob(tx) => return Some(tx.to),
+        };
+        match to {
+            ToAddress::Empty => None,
+            ToAddress::Exists(address) => Some(*address),
+        }
+    }
+
+    pub fn transaction_id(&self) -> TransactionId {
+        match self {
+            Self::Legacy(_) => TransactionId::Legacy,
+            Self::AccessList(_) => TransactionId::AccessList,
+            Self::EIP1559(_) => TransactionId::EIP1559,
+            Self::Blob(_) => TransactionId::Blob,
+        }
+    }
+
+    /// Returns the binary encoding of the transaction. Typed transactions are prefixed with their
+    /// transaction type.
+    pub fn encode(&self) -> Vec<u8> {
         let mut stream = RlpStream::new();
         match self {
             Self::Legacy(tx) => {
@@ -176,6 +244,23 @@ impl Transaction {
                 tx.rlp_append(&mut stream);
                 [&[TransactionId::EIP1559 as u8], stream.as_raw()].concat()
             }
+            Self::Blob(tx) => {
+                tx.rlp_append(&mut stream);
+                [&[TransactionId::Blob as u8], stream.as_raw()].concat()
+            }
+        }
+    }
+}
+
+impl Encodable for Transaction {
+    /// Encodes the transaction as it appears in a block: legacy transactions as an RLP list, and
+    /// typed transactions as an RLP byte string of their binary encoding.
+    fn rlp_append(&self, s: &mut RlpStream) {
+        match self {
+            Self::Legacy(tx) => tx.rlp_append(s),
+            _ => {
+                s.append(&self.encode());
+            }
         }
     }
 }
@@ -212,6 +297,11 @@ impl<'de> Deserialize<'de> for Transaction {
                     EIP1559TransactionHelper::deserialize(obj).map_err(serde::de::Error::custom)?;
                 Ok(Self::EIP1559(helper.into()))
             }
+            TransactionId::Blob => {
+                let helper =
+                    BlobTransactionHelper::deserialize(obj).map_err(serde::de::Error::custom)?;
+                Ok(Self::Blob(helper.into()))
+            }
         }
     }
 }
@@ -374,6 +464,72 @@ impl Into<EIP1559Transaction> for EIP1559TransactionHelper {
     }
 }
 
+/// An EIP-4844 transaction. The blobs themselves are not part of the transaction as it appears
+/// in a block, only their versioned hashes.
+#[derive(Eq, Debug, Clone, PartialEq, RlpDecodable, RlpEncodable)]
+pub struct BlobTransaction {
+    pub chain_id: U256,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub gas_limit: U256,
+    pub to: H160,
+    pub value: U256,
+    pub data: Bytes,
+    pub access_list: AccessList,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+    pub y_parity: U64,
+    pub r: U256,
+    pub s: U256,
+}
+
+#[derive(Eq, Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlobTransactionHelper {
+    pub chain_id: U256,
+    pub nonce: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+    #[serde(rename(deserialize = "gas"))]
+    pub gas_limit: U256,
+    pub to: H160,
+    pub value: U256,
+    #[serde(rename(deserialize = "input"))]
+    pub data: JsonBytes,
+    pub access_list: Vec<AccessListItem>,
+    pub max_fee_per_blob_gas: U256,
+    pub blob_versioned_hashes: Vec<H256>,
+    #[serde(rename(deserialize = "v"))]
+    pub y_parity: U64,
+    pub r: U256,
+    pub s: U256,
+}
+
+#[allow(clippy::from_over_into)]
+impl Into<BlobTransaction> for BlobTransactionHelper {
+    fn into(self) -> BlobTransaction {
+        BlobTransaction {
+            chain_id: self.chain_id,
+            nonce: self.nonce,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            max_fee_per_gas: self.max_fee_per_gas,
+            gas_limit: self.gas_limit,
+            to: self.to,
+            value: self.value,
+            data: self.data.0,
+            access_list: AccessList {
+                list: self.access_list,
+            },
+            max_fee_per_blob_gas: self.max_fee_per_blob_gas,
+            blob_versioned_hashes: self.blob_versioned_hashes,
+            y_parity: self.y_parity,
+            r: self.r,
+            s: self.s,
+        }
+    }
+}
+
 /// Enum to represent the "to" field in a tx. Which can be an address, or Null if a contract is
 /// created.
 #[derive(Default, Eq, Debug, Clone, PartialEq)]
@@ -502,11 +658,91 @@ mod tests {
             Transaction::Legacy(tx) => assert_eq!(tx.nonce, expected_nonce),
             Transaction::AccessList(tx) => assert_eq!(tx.nonce, expected_nonce),
             Transaction::EIP1559(tx) => assert_eq!(tx.nonce, expected_nonce),
+            Transaction::Blob(tx) => assert_eq!(tx.nonce, expected_nonce),
         }
         let encoded_tx = tx.encode();
         assert_eq!(hex_encode(tx_rlp), hex_encode(encoded_tx));
     }
 
+    #[test_log::test]
+    fn effective_tip() {
+        let base_fee = Some(U256::from(100));
+
+        let legacy = Transaction::Legacy(LegacyTransaction {
+            gas_price: U256::from(150),
+            ..Default::default()
+        });
+        assert_eq!(legacy.effective_tip(base_fee), U256::from(50));
+        assert_eq!(legacy.effective_tip(None), U256::from(150));
+
+        // The tip is capped by the max fee...
+        let capped = eip1559_tx(U256::from(80), U256::from(120));
+        assert_eq!(capped.effective_tip(base_fee), U256::from(20));
+
+        // ...and otherwise equal to the max priority fee.
+        let uncapped = eip1559_tx(U256::from(10), U256::from(120));
+        assert_eq!(uncapped.effective_tip(base_fee), U256::from(10));
+    }
+
+    #[test_log::test]
+    fn effective_gas_price() {
+        let base_fee = Some(U256::from(100));
+
+        let legacy = Transaction::Legacy(LegacyTransaction {
+            gas_price: U256::from(150),
+            ..Default::default()
+        });
+        assert_eq!(legacy.effective_gas_price(base_fee), U256::from(150));
+
+        let capped = eip1559_tx(U256::from(80), U256::from(120));
+        assert_eq!(capped.effective_gas_price(base_fee), U256::from(120));
+        let uncapped = eip1559_tx(U256::from(10), U256::from(120));
+        assert_eq!(uncapped.effective_gas_price(base_fee), U256::from(110));
+        assert_eq!(uncapped.to(), None);
+        assert_eq!(uncapped.transaction_id(), TransactionId::EIP1559);
+    }
+
+    fn eip1559_tx(max_priority_fee_per_gas: U256, max_fee_per_gas: U256) -> Transaction {
+        Transaction::EIP1559(EIP1559Transaction {
+            chain_id: U256::one(),
+            nonce: U256::zero(),
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: U256::from(21000),
+            to: ToAddress::Empty,
+            value: U256::zero(),
+            data: Bytes::new(),
+            access_list: AccessList { list: vec![] },
+            y_parity: U64::zero(),
+            r: U256::zero(),
+            s: U256::zero(),
+        })
+    }
+
+    #[test_log::test]
+    fn encode_and_decode_blob_tx() {
+        let tx = Transaction::Blob(BlobTransaction {
+            chain_id: U256::one(),
+            nonce: U256::from(7),
+            max_priority_fee_per_gas: U256::from(10),
+            max_fee_per_gas: U256::from(120),
+            gas_limit: U256::from(21000),
+            to: H160::repeat_byte(0x22),
+            value: U256::zero(),
+            data: Bytes::new(),
+            access_list: AccessList { list: vec![] },
+            max_fee_per_blob_gas: U256::from(3),
+            blob_versioned_hashes: vec![H256::repeat_byte(0x01)],
+            y_parity: U64::one(),
+            r: U256::from(1),
+            s: U256::from(2),
+        });
+        let encoded_tx = tx.encode();
+        assert_eq!(encoded_tx[0], TransactionId::Blob as u8);
+        assert_eq!(Transaction::decode(&encoded_tx).unwrap(), tx);
+        assert_eq!(tx.effective_tip(Some(U256::from(100))), U256::from(10));
+    }
+
     #[test_log::test]
     fn block_body_validates_transactions_root() {
         let block_body = get_14764013_block_body();
@@ -570,6 +806,32 @@ mod tests {
         );
     }
 
+    #[test_log::test]
+    fn block_body_rlp_encodes_block() {
+        let block_body = get_14764013_block_body();
+        // Any header will do, since it's encoded as is.
+        let header = block_body.uncles.list[0].clone();
+        let encoded = block_body.rlp_encode_block(&header);
+
+        let block = Rlp::new(&encoded);
+        assert_eq!(block.item_count().unwrap(), 3);
+        assert_eq!(block.at(0).unwrap().as_raw(), rlp::encode(&header).as_ref());
+        let txs = block.at(1).unwrap();
+        assert_eq!(txs.item_count().unwrap(), block_body.txs.len());
+        for (tx, expected) in txs.iter().zip(block_body.txs.iter()) {
+            match expected {
+                // Legacy transactions are embedded as lists.
+                Transaction::Legacy(_) => assert_eq!(tx.as_raw(), expected.encode()),
+                // Typed transactions are embedded as byte strings.
+                _ => assert_eq!(tx.data().unwrap(), expected.encode()),
+            }
+        }
+        assert_eq!(
+            block.at(2).unwrap().as_raw(),
+            rlp::encode(&block_body.uncles).as_ref()
+        );
+    }
+
     #[test_log::test]
     fn block_body_ssz_encoding_decoding_round_trip() {
         let block_body = get_14764013_block_body();
@@ -4,6 +4,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use ssz::{Encode, SszDecoderBuilder, SszEncoder};
 use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum, FixedVector};
 
 use crate::bytes::ByteList;
 use crate::execution::block_body::Transaction;
@@ -314,6 +315,37 @@ impl ssz::Encode for HeaderWithProof {
 pub enum BlockHeaderProof {
     None(SszNone),
     AccumulatorProof(AccumulatorProof),
+    /// Proof for a header between the merge and Capella, against `historical_roots`.
+    HistoricalRootsBlockProof(BlockProofHistoricalRoots),
+    /// Proof for a header from Capella onwards, against `historical_summaries`.
+    HistoricalSummariesBlockProof(BlockProofHistoricalSummaries),
+}
+
+/// Proof that an execution block hash is part of a beacon block.
+pub type ExecutionBlockProof = FixedVector<H256, typenum::U11>;
+
+/// Proof that a post-merge execution block is canonical, by way of the beacon block that contains
+/// it and the `historical_roots` of the beacon state.
+#[derive(Debug, Clone, PartialEq, Eq, Decode, Encode, Serialize, Deserialize)]
+pub struct BlockProofHistoricalRoots {
+    /// Proof that the beacon block root is part of the `HistoricalBatch` for its era.
+    pub beacon_block_proof: FixedVector<H256, typenum::U14>,
+    pub beacon_block_root: H256,
+    /// Proof that the execution block hash is part of the beacon block.
+    pub execution_block_proof: ExecutionBlockProof,
+    pub slot: u64,
+}
+
+/// Proof that a post-Capella execution block is canonical, by way of the beacon block that
+/// contains it and the `historical_summaries` of the beacon state.
+#[derive(Debug, Clone, PartialEq, Eq, Decode, Encode, Serialize, Deserialize)]
+pub struct BlockProofHistoricalSummaries {
+    /// Proof that the beacon block root is part of the `block_roots` of its historical summary.
+    pub beacon_block_proof: FixedVector<H256, typenum::U13>,
+    pub beacon_block_root: H256,
+    /// Proof that the execution block hash is part of the beacon block.
+    pub execution_block_proof: ExecutionBlockProof,
+    pub slot: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -526,6 +558,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn encode_decode_header_with_beacon_chain_proofs() {
+        let file =
+            fs::read_to_string("../trin-validation/src/assets/fluffy/header_with_proofs.json")
+                .unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let hwp = json["1000001"]["value"].as_str().unwrap();
+        let header = HeaderWithProof::from_ssz_bytes(&hex_decode(hwp).unwrap())
+            .unwrap()
+            .header;
+
+        let execution_block_proof = FixedVector::from(vec![H256::repeat_byte(0x02); 11]);
+        let proofs = [
+            BlockHeaderProof::HistoricalRootsBlockProof(BlockProofHistoricalRoots {
+                beacon_block_proof: FixedVector::from(vec![H256::repeat_byte(0x01); 14]),
+                beacon_block_root: H256::repeat_byte(0x03),
+                execution_block_proof: execution_block_proof.clone(),
+                slot: 4_700_013,
+            }),
+            BlockHeaderProof::HistoricalSummariesBlockProof(BlockProofHistoricalSummaries {
+                beacon_block_proof: FixedVector::from(vec![H256::repeat_byte(0x01); 13]),
+                beacon_block_root: H256::repeat_byte(0x03),
+                execution_block_proof,
+                slot: 6_209_536,
+            }),
+        ];
+        for (selector, proof) in [2u8, 3u8].into_iter().zip(proofs) {
+            let hwp = HeaderWithProof {
+                header: header.clone(),
+                proof,
+            };
+            let encoded = hwp.as_ssz_bytes();
+            // The proof union starts at the second offset of the container.
+            let proof_offset = u32::from_le_bytes(encoded[4..8].try_into().unwrap()) as usize;
+            assert_eq!(encoded[proof_offset], selector);
+            assert_eq!(HeaderWithProof::from_ssz_bytes(&encoded).unwrap(), hwp);
+        }
+    }
+
     #[test]
     fn full_header_from_get_block_response() {
         let body =
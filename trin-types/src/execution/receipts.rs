@@ -205,13 +205,13 @@ impl<'de> Deserialize<'de> for TransactionOutcome {
         D: Deserializer<'de>,
     {
         let s: String = Deserialize::deserialize(deserializer)?;
-        let s = s.trim_start_matches("0x");
-        match s.len() {
+        let value = s.trim_start_matches("0x");
+        match value.len() {
             1 => Ok(Self::StatusCode(
-                s.parse::<u8>().map_err(serde::de::Error::custom)?,
+                u8::from_str_radix(value, 16).map_err(serde::de::Error::custom)?,
             )),
             64 => Ok(Self::StateRoot(H256::from_slice(
-                &hex_decode(s).map_err(serde::de::Error::custom)?,
+                &hex_decode(&s).map_err(serde::de::Error::custom)?,
             ))),
             _ => Err(serde::de::Error::custom("Invalid transaction outcome")),
         }
@@ -242,7 +242,8 @@ struct LegacyReceiptHelper {
     pub cumulative_gas_used: U256,
     pub logs_bloom: Bloom,
     pub logs: Vec<LogEntryHelper>,
-    #[serde(rename(deserialize = "status"))]
+    // Receipts from before the Byzantium fork contain a state root instead of a status code.
+    #[serde(rename(deserialize = "status"), alias = "root")]
     pub outcome: TransactionOutcome,
 }
 
@@ -319,6 +320,7 @@ impl Encodable for LegacyReceipt {
 #[repr(u8)]
 /// The typed transaction ID
 pub enum TransactionId {
+    Blob = 0x03,
     EIP1559 = 0x02,
     AccessList = 0x01,
     Legacy = 0x00,
@@ -329,6 +331,7 @@ impl TryFrom<u8> for TransactionId {
 
     fn try_from(val: u8) -> Result<Self, Self::Error> {
         match val {
+            id if id == TransactionId::Blob as u8 => Ok(Self::Blob),
             id if id == TransactionId::EIP1559 as u8 => Ok(Self::EIP1559),
             id if id == TransactionId::AccessList as u8 => Ok(Self::AccessList),
             id if (id & 0x80) != 0x00 => Ok(Self::Legacy),
@@ -346,9 +349,7 @@ impl TryFrom<Value> for TransactionId {
     fn try_from(val: Value) -> Result<Self, Self::Error> {
         let id = val.as_str().ok_or(DecoderError::Custom("Invalid tx id."))?;
         let id = id.trim_start_matches("0x");
-        let id = id
-            .parse::<u8>()
-            .map_err(|_| DecoderError::Custom("Invalid tx id."))?;
+        let id = u8::from_str_radix(id, 16).map_err(|_| DecoderError::Custom("Invalid tx id."))?;
         Self::try_from(id)
     }
 }
@@ -358,6 +359,7 @@ pub enum Receipt {
     Legacy(LegacyReceipt),
     AccessList(LegacyReceipt),
     EIP1559(LegacyReceipt),
+    Blob(LegacyReceipt),
 }
 
 impl Receipt {
@@ -365,6 +367,7 @@ impl Receipt {
     pub fn new(type_id: TransactionId, legacy_receipt: LegacyReceipt) -> Self {
         //curently we are using same receipt for both legacy and typed transaction
         match type_id {
+            TransactionId::Blob => Self::Blob(legacy_receipt),
             TransactionId::EIP1559 => Self::EIP1559(legacy_receipt),
             TransactionId::AccessList => Self::AccessList(legacy_receipt),
             TransactionId::Legacy => Self::Legacy(legacy_receipt),
@@ -376,6 +379,7 @@ impl Receipt {
             Self::Legacy(receipt) => receipt,
             Self::AccessList(receipt) => receipt,
             Self::EIP1559(receipt) => receipt,
+            Self::Blob(receipt) => receipt,
         }
     }
 
@@ -384,10 +388,13 @@ impl Receipt {
             Self::Legacy(receipt) => receipt,
             Self::AccessList(receipt) => receipt,
             Self::EIP1559(receipt) => receipt,
+            Self::Blob(receipt) => receipt,
         }
     }
 
-    fn encode(&self) -> Vec<u8> {
+    /// Returns the binary encoding of the receipt. Receipts of typed transactions are prefixed
+    /// with their transaction type.
+    pub fn encode(&self) -> Vec<u8> {
         let mut stream = RlpStream::new();
         match self {
             Self::Legacy(receipt) => {
@@ -402,6 +409,10 @@ impl Receipt {
                 receipt.rlp_append(&mut stream);
                 [&[TransactionId::EIP1559 as u8], stream.as_raw()].concat()
             }
+            Self::Blob(receipt) => {
+                receipt.rlp_append(&mut stream);
+                [&[TransactionId::Blob as u8], stream.as_raw()].concat()
+            }
         }
     }
 
@@ -414,6 +425,7 @@ impl Receipt {
             .map_err(|_| DecoderError::Custom("Unknown transaction id"))?;
         //other transaction types
         match id {
+            TransactionId::Blob => Ok(Self::Blob(rlp::decode(&receipt[1..])?)),
             TransactionId::EIP1559 => Ok(Self::EIP1559(rlp::decode(&receipt[1..])?)),
             TransactionId::AccessList => Ok(Self::AccessList(rlp::decode(&receipt[1..])?)),
             TransactionId::Legacy => Ok(Self::Legacy(rlp::decode(receipt)?)),
@@ -440,6 +452,9 @@ impl<'de> Deserialize<'de> for Receipt {
             TransactionId::EIP1559 => Ok(Receipt::EIP1559(
                 LegacyReceipt::deserialize(obj).map_err(serde::de::Error::custom)?,
             )),
+            TransactionId::Blob => Ok(Receipt::Blob(
+                LegacyReceipt::deserialize(obj).map_err(serde::de::Error::custom)?,
+            )),
         }
     }
 }
@@ -682,6 +697,64 @@ mod tests {
         assert_eq!(decoded, receipt);
     }
 
+    #[test]
+    fn typed_receipts_prefix_legacy_encoding() {
+        let legacy_receipt = LegacyReceipt::new(
+            TransactionOutcome::StatusCode(1),
+            U256::from_str_radix("40cae", 16).unwrap(),
+            vec![LogEntry {
+                address: H160::from_str("dcf421d093428b096ca501a7cd1a740855a7976f").unwrap(),
+                topics: vec![H256::repeat_byte(0x11)],
+                data: Bytes::from(vec![0u8; 32]),
+            }],
+        );
+        let legacy_encoding = Receipt::new(TransactionId::Legacy, legacy_receipt.clone()).encode();
+
+        for type_id in [
+            TransactionId::AccessList,
+            TransactionId::EIP1559,
+            TransactionId::Blob,
+        ] {
+            let receipt = Receipt::new(type_id, legacy_receipt.clone());
+            let encoded = receipt.encode();
+            assert_eq!(encoded[0], type_id as u8);
+            assert_eq!(&encoded[1..], &legacy_encoding[..]);
+            let decoded = Receipt::decode(&encoded).expect("decoding receipt failed");
+            assert_eq!(decoded, receipt);
+        }
+    }
+
+    #[test]
+    fn ssz_encoding_decoding_all_receipt_types() {
+        let legacy_receipt =
+            LegacyReceipt::new(TransactionOutcome::StatusCode(0), U256::from(21000), vec![]);
+        let receipts = Receipts {
+            receipt_list: vec![
+                Receipt::new(TransactionId::Legacy, legacy_receipt.clone()),
+                Receipt::new(TransactionId::AccessList, legacy_receipt.clone()),
+                Receipt::new(TransactionId::EIP1559, legacy_receipt.clone()),
+                Receipt::new(TransactionId::Blob, legacy_receipt),
+            ],
+        };
+        let encoded = receipts.as_ssz_bytes();
+        let decoded = Receipts::from_ssz_bytes(&encoded).unwrap();
+        assert_eq!(decoded, receipts);
+        assert_eq!(decoded.root().unwrap(), receipts.root().unwrap());
+    }
+
+    #[test]
+    fn transaction_id_from_json() {
+        for (id, expected) in [
+            ("0x0", TransactionId::Legacy),
+            ("0x1", TransactionId::AccessList),
+            ("0x2", TransactionId::EIP1559),
+            ("0x3", TransactionId::Blob),
+        ] {
+            assert_eq!(TransactionId::try_from(json!(id)).unwrap(), expected);
+        }
+        TransactionId::try_from(json!("0x4")).unwrap_err();
+    }
+
     #[test]
     fn status_code() {
         let expected = hex_decode("0xf901428083040caeb9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000400000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000200000000000000000000000000000000000000000000000000000000000000000f838f794dcf421d093428b096ca501a7cd1a740855a7976fc0a00000000000000000000000000000000000000000000000000000000000000000").unwrap();
@@ -729,6 +802,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_json_pre_byzantium() {
+        let response = json!({
+            "cumulativeGasUsed": "0x40cae",
+            "logs": [],
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "root": "0x2f697d671e9ae4ee24a43c4b0d7e15f1cb4ba6de1561120d43b9a4e8c4a8a6ee",
+            "type": "0x0",
+        });
+        let receipt: Receipt = serde_json::from_value(response).unwrap();
+        assert_eq!(
+            receipt.outcome,
+            TransactionOutcome::StateRoot(
+                H256::from_str("2f697d671e9ae4ee24a43c4b0d7e15f1cb4ba6de1561120d43b9a4e8c4a8a6ee")
+                    .unwrap()
+            )
+        );
+    }
+
     #[test_log::test]
     fn receipts_batch() {
         // this block (15573637) was chosen since it contains all tx types (legacy, access list, eip1559)
@@ -742,6 +834,48 @@ mod tests {
                 .unwrap(),
         );
         assert_eq!(receipts.root().unwrap(), expected_receipts_root);
+
+        // The two access list receipts of the block must survive the network encoding too.
+        let encoded = receipts.as_ssz_bytes();
+        let decoded = Receipts::from_ssz_bytes(&encoded).unwrap();
+        assert_eq!(decoded, receipts);
+        assert_eq!(decoded.root().unwrap(), expected_receipts_root);
+        let access_list_receipts = EncodedReceiptList::from_ssz_bytes(&encoded)
+            .unwrap()
+            .encoded_receipts
+            .into_iter()
+            .filter(|receipt| receipt[0] == TransactionId::AccessList as u8)
+            .count();
+        assert_eq!(access_list_receipts, 2);
+    }
+
+    #[test]
+    fn mainnet_receipts_reencode_byte_for_byte() {
+        // Receipts of block 14764013 as served by other Portal clients, checked against the
+        // receipts root in the block's header rather than against our own encoding.
+        let ssz = std::fs::read("../trin-types/src/assets/trin/receipts_14764013.bin").unwrap();
+        let header: Value = serde_json::from_str(
+            &std::fs::read_to_string("../trin-types/src/assets/trin/block_14764013_value.json")
+                .unwrap(),
+        )
+        .unwrap();
+        let expected_receipts_root =
+            H256::from_str(header["result"]["receiptsRoot"].as_str().unwrap()).unwrap();
+
+        let encoded_receipts = EncodedReceiptList::from_ssz_bytes(&ssz)
+            .unwrap()
+            .encoded_receipts;
+        let receipt_list: Vec<Receipt> = encoded_receipts
+            .iter()
+            .map(|bytes| {
+                let receipt = Receipt::decode(bytes).unwrap();
+                assert_eq!(hex_encode(receipt.encode()), hex_encode(bytes));
+                receipt
+            })
+            .collect();
+        let receipts = Receipts { receipt_list };
+        assert_eq!(receipts.root().unwrap(), expected_receipts_root);
+        assert_eq!(receipts.as_ssz_bytes(), ssz);
     }
 
     const EXPECTED_RECEIPTS_ROOT: &str =
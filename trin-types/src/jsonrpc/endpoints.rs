@@ -23,6 +23,7 @@ pub enum StateEndpoint {
     Ping,
     RecursiveFindContent,
     RoutingTableInfo,
+    Summary,
 }
 
 /// History network JSON-RPC endpoints. Start with "portal_history" prefix
@@ -42,8 +43,10 @@ pub enum HistoryEndpoint {
     Offer(Enr, HistoryContentKey, Option<HistoryContentValue>),
     /// params: [enr, data_radius]
     Ping(Enr, Option<DataRadius>),
-    /// params: content_key
-    RecursiveFindContent(HistoryContentKey),
+    /// params: [content_key, offset, max_bytes]
+    ContentChunk(HistoryContentKey, u64, u64),
+    /// params: [content_key, bypass_cache]
+    RecursiveFindContent(HistoryContentKey, bool),
     /// params: content_key
     TraceRecursiveFindContent(HistoryContentKey),
     /// params: [content_key, content_value]
@@ -55,6 +58,32 @@ pub enum HistoryEndpoint {
     PaginateLocalContentKeys(u64, u64),
     /// params: [node_id]
     RecursiveFindNodes(NodeId),
+    /// params: None
+    Summary,
+    /// params: None
+    PeerStats,
+    /// params: None
+    LifetimeStats,
+    /// params: None
+    UtpTransfers,
+    /// params: [bucket]
+    RefreshRoutingTable(Option<u8>),
+    /// params: None
+    PeerCapabilities,
+    /// params: None
+    NetworkEstimate,
+    /// params: [content_key, nodes]
+    Replication(HistoryContentKey, u64),
+    /// params: [capacity_kb]
+    SetStorageCapacity(u64),
+    /// params: [radius]
+    SetRadius(Option<DataRadius>),
+    /// Hashes of the most recent blocks verified by the light client, newest first. Not exposed
+    /// over JSON-RPC, but used by the `eth` API.
+    /// params: [count]
+    RecentBlockHashes(usize),
+    /// params: None
+    CompactStorage,
 }
 
 /// Ethereum JSON-RPC endpoints not currently supported by portal network requests, proxied to
@@ -1,3 +1,4 @@
 pub mod endpoints;
 pub mod params;
+#[cfg(feature = "native")]
 pub mod request;
@@ -3,15 +3,24 @@ extern crate lazy_static;
 
 pub mod bootnodes;
 pub mod bytes;
+#[cfg(feature = "native")]
 pub mod cli;
 pub mod consensus;
 pub mod constants;
 pub mod content_key;
 pub mod content_value;
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod content_vectors;
+pub mod correlation;
 pub mod distance;
 pub mod enr;
 pub mod execution;
 pub mod jsonrpc;
+pub mod jwt;
+pub mod merkle;
 pub mod node_id;
+#[cfg(feature = "native")]
 pub mod provider;
+pub mod schema;
 pub mod wrapped;
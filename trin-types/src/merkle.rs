@@ -0,0 +1,415 @@
+//! SSZ merkleization and Merkle proofs of nodes addressed by generalized indices.
+//!
+//! Based off specs @
+//! https://github.com/ethereum/consensus-specs/blob/dev/ssz/merkle-proofs.md
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use eth2_hashing::{hash32_concat, ZERO_HASHES, ZERO_HASHES_MAX_INDEX};
+use ethereum_types::H256;
+use thiserror::Error;
+
+/// Index of a node in a binary Merkle tree, where the root is 1 and the children of node `i` are
+/// `2 * i` and `2 * i + 1`.
+pub type GeneralizedIndex = usize;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MerkleProofError {
+    #[error("a tree of depth {depth} can't hold {leaves} leaves")]
+    TooManyLeaves { leaves: usize, depth: usize },
+
+    #[error("tree depth {0} exceeds the maximum supported depth")]
+    TreeTooDeep(usize),
+
+    #[error("generalized index {0} is not a node of the tree")]
+    InvalidGeneralizedIndex(GeneralizedIndex),
+
+    #[error("expected {expected} proof nodes, found {found}")]
+    InvalidProofLength { expected: usize, found: usize },
+
+    #[error("found {leaves} leaves for {indices} generalized indices")]
+    LeafCountMismatch { leaves: usize, indices: usize },
+}
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+    H256::from(hash32_concat(left.as_bytes(), right.as_bytes()))
+}
+
+/// Returns the root of a tree of the given depth whose leaves are all zero.
+fn zero_hash(depth: usize) -> H256 {
+    H256::from_slice(&ZERO_HASHES[depth])
+}
+
+/// Returns the depth of the node with the given generalized index, which is the length of its
+/// Merkle proof.
+pub fn generalized_index_depth(index: GeneralizedIndex) -> usize {
+    debug_assert!(index > 0, "generalized indices start at 1");
+    (usize::BITS - 1 - index.leading_zeros()) as usize
+}
+
+/// Returns the generalized index of the node at `indices[n]` in the subtree rooted at the node at
+/// `indices[n - 1]`, e.g. of a field in a container that is itself a field of another container.
+pub fn concat_generalized_indices(indices: &[GeneralizedIndex]) -> GeneralizedIndex {
+    indices.iter().fold(1, |root, &index| {
+        let depth = generalized_index_depth(index);
+        (root << depth) | (index ^ (1 << depth))
+    })
+}
+
+/// Returns the generalized indices of the sister nodes along the path from the node to the root,
+/// from the bottom up.
+fn branch_indices(index: GeneralizedIndex) -> Vec<GeneralizedIndex> {
+    let mut indices = vec![];
+    let mut index = index;
+    while index > 1 {
+        indices.push(index ^ 1);
+        index /= 2;
+    }
+    indices
+}
+
+/// Returns the generalized indices of the nodes on the path from the node to the root, excluding
+/// the root.
+fn path_indices(index: GeneralizedIndex) -> Vec<GeneralizedIndex> {
+    let mut indices = vec![];
+    let mut index = index;
+    while index > 1 {
+        indices.push(index);
+        index /= 2;
+    }
+    indices
+}
+
+/// Returns the generalized indices of the nodes required to prove all of `indices` at once, in
+/// decreasing order.
+pub fn helper_indices(indices: &[GeneralizedIndex]) -> Vec<GeneralizedIndex> {
+    let mut helpers = BTreeSet::new();
+    let mut paths = BTreeSet::new();
+    for &index in indices {
+        helpers.extend(branch_indices(index));
+        paths.extend(path_indices(index));
+    }
+    helpers.difference(&paths).rev().copied().collect()
+}
+
+/// Returns the chunk mixed into the root of an SSZ list or bitlist of the given length.
+pub fn length_chunk(length: usize) -> H256 {
+    let mut chunk = H256::zero();
+    chunk[..8].copy_from_slice(&(length as u64).to_le_bytes());
+    chunk
+}
+
+/// Returns the root of an SSZ list or bitlist of the given length whose data has the given root.
+pub fn mix_in_length(root: H256, length: usize) -> H256 {
+    hash_pair(&root, &length_chunk(length))
+}
+
+/// Returns the root of the tree of the given depth over `chunks`, padded with zero chunks.
+pub fn merkleize(chunks: &[H256], depth: usize) -> Result<H256, MerkleProofError> {
+    MerkleTree::new(chunks, depth).map(|tree| tree.root())
+}
+
+/// A Merkle tree of fixed depth over the chunks of an SSZ object, padded with zero chunks.
+///
+/// Only the populated part of the tree is stored, so sparse trees such as the one of a list far
+/// from its maximum length stay small.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree {
+    /// The populated nodes of each level of the tree, from the leaves up to the root.
+    levels: Vec<Vec<H256>>,
+}
+
+impl MerkleTree {
+    pub fn new(chunks: &[H256], depth: usize) -> Result<Self, MerkleProofError> {
+        if depth > ZERO_HASHES_MAX_INDEX {
+            return Err(MerkleProofError::TreeTooDeep(depth));
+        }
+        if depth < usize::BITS as usize && chunks.len() > 1 << depth {
+            return Err(MerkleProofError::TooManyLeaves {
+                leaves: chunks.len(),
+                depth,
+            });
+        }
+
+        let mut levels = Vec::with_capacity(depth + 1);
+        let mut nodes = chunks.to_vec();
+        for level in 0..depth {
+            let parents = nodes
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_pair(left, right),
+                    [left] => hash_pair(left, &zero_hash(level)),
+                    _ => unreachable!("chunks of two nodes"),
+                })
+                .collect();
+            levels.push(nodes);
+            nodes = parents;
+        }
+        levels.push(nodes);
+        Ok(Self { levels })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> H256 {
+        self.levels[self.depth()]
+            .first()
+            .copied()
+            .unwrap_or_else(|| zero_hash(self.depth()))
+    }
+
+    /// Returns the node with the given generalized index.
+    pub fn node(&self, index: GeneralizedIndex) -> Result<H256, MerkleProofError> {
+        if index == 0 || generalized_index_depth(index) > self.depth() {
+            return Err(MerkleProofError::InvalidGeneralizedIndex(index));
+        }
+        let node_depth = generalized_index_depth(index);
+        let level = self.depth() - node_depth;
+        let position = index ^ (1 << node_depth);
+        Ok(self.levels[level]
+            .get(position)
+            .copied()
+            .unwrap_or_else(|| zero_hash(level)))
+    }
+
+    /// Returns the proof of the node with the given generalized index, from the bottom up.
+    pub fn generate_proof(&self, index: GeneralizedIndex) -> Result<Vec<H256>, MerkleProofError> {
+        self.node(index)?;
+        branch_indices(index)
+            .into_iter()
+            .map(|index| self.node(index))
+            .collect()
+    }
+
+    /// Returns the proof of all the nodes with the given generalized indices, ordered as
+    /// `helper_indices` orders the nodes.
+    pub fn generate_multiproof(
+        &self,
+        indices: &[GeneralizedIndex],
+    ) -> Result<Vec<H256>, MerkleProofError> {
+        for &index in indices {
+            self.node(index)?;
+        }
+        helper_indices(indices)
+            .into_iter()
+            .map(|index| self.node(index))
+            .collect()
+    }
+}
+
+/// Returns the root of the tree in which `leaf` is the node with the given generalized index and
+/// `proof` holds its sister nodes, from the bottom up.
+pub fn calculate_merkle_root(
+    leaf: H256,
+    proof: &[H256],
+    index: GeneralizedIndex,
+) -> Result<H256, MerkleProofError> {
+    if index == 0 {
+        return Err(MerkleProofError::InvalidGeneralizedIndex(index));
+    }
+    let depth = generalized_index_depth(index);
+    if proof.len() != depth {
+        return Err(MerkleProofError::InvalidProofLength {
+            expected: depth,
+            found: proof.len(),
+        });
+    }
+    Ok(proof
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (i, sister)| match (index >> i) & 1 {
+            1 => hash_pair(sister, &node),
+            _ => hash_pair(&node, sister),
+        }))
+}
+
+/// Verifies that `leaf` is the node with the given generalized index in the tree with the given
+/// root.
+pub fn verify_merkle_proof(
+    leaf: H256,
+    proof: &[H256],
+    index: GeneralizedIndex,
+    root: H256,
+) -> bool {
+    calculate_merkle_root(leaf, proof, index) == Ok(root)
+}
+
+/// Returns the root of the tree in which `leaves` are the nodes with the given generalized
+/// indices, and `proof` holds the nodes of `helper_indices(indices)`.
+pub fn calculate_multi_merkle_root(
+    leaves: &[H256],
+    proof: &[H256],
+    indices: &[GeneralizedIndex],
+) -> Result<H256, MerkleProofError> {
+    if leaves.len() != indices.len() {
+        return Err(MerkleProofError::LeafCountMismatch {
+            leaves: leaves.len(),
+            indices: indices.len(),
+        });
+    }
+    if let Some(&index) = indices.iter().find(|&&index| index == 0) {
+        return Err(MerkleProofError::InvalidGeneralizedIndex(index));
+    }
+    let helpers = helper_indices(indices);
+    if proof.len() != helpers.len() {
+        return Err(MerkleProofError::InvalidProofLength {
+            expected: helpers.len(),
+            found: proof.len(),
+        });
+    }
+
+    let mut nodes: BTreeMap<GeneralizedIndex, H256> = indices
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .chain(helpers.into_iter().zip(proof.iter().copied()))
+        .collect();
+    // Hash pairs of known nodes from the deepest ones up, until the root is known.
+    let mut keys: Vec<GeneralizedIndex> = nodes.keys().rev().copied().collect();
+    let mut position = 0;
+    while position < keys.len() {
+        let index = keys[position];
+        if index > 1 && !nodes.contains_key(&(index / 2)) {
+            if let (Some(left), Some(right)) = (nodes.get(&(index & !1)), nodes.get(&(index | 1))) {
+                let parent = hash_pair(left, right);
+                nodes.insert(index / 2, parent);
+                keys.push(index / 2);
+            }
+        }
+        position += 1;
+    }
+    nodes
+        .get(&1)
+        .copied()
+        .ok_or(MerkleProofError::InvalidGeneralizedIndex(1))
+}
+
+/// Verifies that `leaves` are the nodes with the given generalized indices in the tree with the
+/// given root.
+pub fn verify_merkle_multiproof(
+    leaves: &[H256],
+    proof: &[H256],
+    indices: &[GeneralizedIndex],
+    root: H256,
+) -> bool {
+    calculate_multi_merkle_root(leaves, proof, indices) == Ok(root)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ssz_types::{typenum, VariableList};
+    use tree_hash::TreeHash;
+
+    fn chunks(count: u64) -> Vec<H256> {
+        (1..=count).map(H256::from_low_u64_be).collect()
+    }
+
+    #[test]
+    fn generalized_indices() {
+        assert_eq!(generalized_index_depth(1), 0);
+        assert_eq!(generalized_index_depth(3), 1);
+        assert_eq!(generalized_index_depth(3228), 11);
+        // BeaconBlock.body -> BeaconBlockBody.execution_payload -> ExecutionPayload.block_hash
+        assert_eq!(concat_generalized_indices(&[12, 25, 28]), 3228);
+        assert_eq!(concat_generalized_indices(&[]), 1);
+        assert_eq!(helper_indices(&[8, 9]), vec![5, 3]);
+        assert_eq!(helper_indices(&[8, 6]), vec![9, 7, 5]);
+    }
+
+    #[test]
+    fn merkleize_matches_tree_hash() {
+        let values: Vec<u64> = (0..5).collect();
+        let list: VariableList<H256, typenum::U16> = VariableList::from(
+            values
+                .iter()
+                .map(|v| H256::from_low_u64_be(*v))
+                .collect::<Vec<_>>(),
+        );
+        let leaves: Vec<H256> = list.iter().copied().collect();
+        let root = mix_in_length(merkleize(&leaves, 4).unwrap(), leaves.len());
+        assert_eq!(root, list.tree_hash_root());
+
+        let empty: VariableList<H256, typenum::U16> = VariableList::empty();
+        assert_eq!(
+            mix_in_length(merkleize(&[], 4).unwrap(), 0),
+            empty.tree_hash_root()
+        );
+    }
+
+    #[test]
+    fn single_proofs() {
+        let tree = MerkleTree::new(&chunks(5), 3).unwrap();
+        assert_eq!(tree.node(1).unwrap(), tree.root());
+        for index in 1..16 {
+            let proof = tree.generate_proof(index).unwrap();
+            let node = tree.node(index).unwrap();
+            assert!(verify_merkle_proof(node, &proof, index, tree.root()));
+            assert!(!verify_merkle_proof(
+                H256::repeat_byte(0xff),
+                &proof,
+                index,
+                tree.root()
+            ));
+        }
+        assert_eq!(
+            tree.generate_proof(16),
+            Err(MerkleProofError::InvalidGeneralizedIndex(16))
+        );
+        assert_eq!(
+            calculate_merkle_root(H256::zero(), &[], 2),
+            Err(MerkleProofError::InvalidProofLength {
+                expected: 1,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn multiproofs() {
+        let tree = MerkleTree::new(&chunks(7), 3).unwrap();
+        let indices = [8, 13, 3];
+        let leaves: Vec<H256> = indices.iter().map(|i| tree.node(*i).unwrap()).collect();
+        let proof = tree.generate_multiproof(&indices).unwrap();
+        assert_eq!(proof.len(), helper_indices(&indices).len());
+        assert!(verify_merkle_multiproof(
+            &leaves,
+            &proof,
+            &indices,
+            tree.root()
+        ));
+
+        let mut tampered = leaves.clone();
+        tampered[1] = H256::repeat_byte(0xff);
+        assert!(!verify_merkle_multiproof(
+            &tampered,
+            &proof,
+            &indices,
+            tree.root()
+        ));
+        assert!(!verify_merkle_multiproof(
+            &leaves[..2],
+            &proof,
+            &indices,
+            tree.root()
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_trees() {
+        assert_eq!(
+            MerkleTree::new(&chunks(5), 2),
+            Err(MerkleProofError::TooManyLeaves {
+                leaves: 5,
+                depth: 2
+            })
+        );
+        assert_eq!(
+            MerkleTree::new(&[], ZERO_HASHES_MAX_INDEX + 1),
+            Err(MerkleProofError::TreeTooDeep(ZERO_HASHES_MAX_INDEX + 1))
+        );
+    }
+}
@@ -13,6 +13,7 @@ use ureq::{self, Request};
 
 use crate::cli::TrinConfig;
 use crate::jsonrpc::params::Params;
+use crate::jwt::JwtAuth;
 
 pub const INFURA_BASE_HTTP_URL: &str = "https://mainnet.infura.io:443/v3/";
 pub const DEFAULT_LOCAL_PROVIDER: &str = "http://127.0.0.1:8545";
@@ -23,6 +24,8 @@ pub enum TrustedProviderType {
     Infura,
     Pandaops,
     Custom,
+    /// Run without a trusted provider, relying solely on the portal network for validation.
+    None,
 }
 
 impl fmt::Display for TrustedProviderType {
@@ -31,6 +34,7 @@ impl fmt::Display for TrustedProviderType {
             Self::Infura => write!(f, "infura"),
             Self::Pandaops => write!(f, "pandaops"),
             Self::Custom => write!(f, "custom"),
+            Self::None => write!(f, "none"),
         }
     }
 }
@@ -45,6 +49,7 @@ impl FromStr for TrustedProviderType {
             "pandaops" => Ok(TrustedProviderType::Pandaops),
             "infura" => Ok(TrustedProviderType::Infura),
             "custom" => Ok(TrustedProviderType::Custom),
+            "none" => Ok(TrustedProviderType::None),
             _ => panic!("Invalid trusted provider arg: {s} is not an option."),
         }
     }
@@ -56,16 +61,30 @@ impl FromStr for TrustedProviderType {
 #[derive(Clone, Debug)]
 pub struct TrustedProvider {
     pub http: Request,
+    /// Authentication for the engine API port of an execution client, if the provider requires
+    /// it.
+    pub jwt_auth: Option<JwtAuth>,
 }
 
+/// Error message for requests that can only be served by a trusted provider, when none is
+/// configured.
+pub const NO_TRUSTED_PROVIDER_ERROR: &str =
+    "No trusted provider configured. Use the --trusted-provider flag to configure one.";
+
 impl TrustedProvider {
-    pub fn from_trin_config(trin_config: &TrinConfig) -> Self {
+    /// Builds the trusted provider selected in the config, or returns `None` if trin was
+    /// configured to run without one. Fails if the credentials or the JWT secret of the provider
+    /// are missing.
+    pub fn from_trin_config(trin_config: &TrinConfig) -> anyhow::Result<Option<Self>> {
         let trusted_http_client = match trin_config.trusted_provider {
-            TrustedProviderType::Infura => build_infura_http_client_from_env(),
+            TrustedProviderType::None => return Ok(None),
+            TrustedProviderType::Infura => build_infura_http_client_from_env()?,
             TrustedProviderType::Pandaops => match &trin_config.trusted_provider_url {
-                Some(val) => build_pandaops_http_client_from_env(val.to_string()),
+                Some(val) => build_pandaops_http_client_from_env(val.to_string())?,
                 None => {
-                    panic!("Must supply --trusted-provider-url cli flag to use pandaops as a trusted provider.")
+                    return Err(anyhow!(
+                        "Must supply --trusted-provider-url cli flag to use pandaops as a trusted provider."
+                    ))
                 }
             },
             TrustedProviderType::Custom => match trin_config.trusted_provider_url.clone() {
@@ -76,9 +95,15 @@ impl TrustedProvider {
                 ),
             },
         };
-        Self {
+        let jwt_auth = trin_config
+            .trusted_provider_jwt_secret
+            .as_ref()
+            .map(|path| JwtAuth::from_secret_file(path))
+            .transpose()?;
+        Ok(Some(Self {
             http: trusted_http_client,
-        }
+            jwt_auth,
+        }))
     }
 
     pub fn dispatch_http_request(&self, method: String, params: Params) -> anyhow::Result<Value> {
@@ -89,7 +114,11 @@ impl TrustedProvider {
             "id": 1,
         });
 
-        match dispatch_trusted_http_request(request, self.http.clone()) {
+        let mut http = self.http.clone();
+        if let Some(jwt_auth) = &self.jwt_auth {
+            http = http.set("Authorization", &jwt_auth.authorization());
+        }
+        match dispatch_trusted_http_request(request, http) {
             Ok(val) => Ok(serde_json::from_str(&val)?),
             Err(err) => Err(anyhow!(
                 "Unable to request validation data from trusted provider: {err:?}",
@@ -125,6 +154,10 @@ fn proxy_to_url(request: &Value, trusted_http_client: Request) -> io::Result<Vec
                 format!("Error decoding response: {msg:?}"),
             )),
         },
+        Err(ureq::Error::Status(401, _response)) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Responded with status code: 401. Check that the provider's JWT secret matches --trusted-provider-jwt-secret",
+        )),
         Err(ureq::Error::Status(code, _response)) => Err(io::Error::new(
             io::ErrorKind::Other,
             format!("Responded with status code: {code:?}"),
@@ -136,49 +169,46 @@ fn proxy_to_url(request: &Value, trusted_http_client: Request) -> io::Result<Vec
     }
 }
 
-fn get_infura_project_id_from_env() -> String {
-    match env::var("TRIN_INFURA_PROJECT_ID") {
-        Ok(val) => val,
-        Err(_) => panic!(
+fn get_infura_project_id_from_env() -> anyhow::Result<String> {
+    env::var("TRIN_INFURA_PROJECT_ID").map_err(|_| {
+        anyhow!(
             "Must supply Infura key as environment variable, like:\n\
             TRIN_INFURA_PROJECT_ID=\"your-key-here\" trin"
-        ),
-    }
+        )
+    })
 }
 
-fn build_infura_http_client_from_env() -> ureq::Request {
-    let infura_project_id = get_infura_project_id_from_env();
+fn build_infura_http_client_from_env() -> anyhow::Result<ureq::Request> {
+    let infura_project_id = get_infura_project_id_from_env()?;
     let infura_url = format!("{INFURA_BASE_HTTP_URL}{infura_project_id}");
-    ureq::post(&infura_url)
+    Ok(ureq::post(&infura_url))
 }
 
-fn get_pandaops_client_id_from_env() -> String {
-    match env::var("PANDAOPS_CLIENT_ID") {
-        Ok(val) => val,
-        Err(_) => panic!(
+fn get_pandaops_client_id_from_env() -> anyhow::Result<String> {
+    env::var("PANDAOPS_CLIENT_ID").map_err(|_| {
+        anyhow!(
             "Must supply pandaops client id as environment variable, like:\n\
             PANDAOPS_CLIENT_ID=\"your-key-here\" trin"
-        ),
-    }
+        )
+    })
 }
 
-fn get_pandaops_client_secret_from_env() -> String {
-    match env::var("PANDAOPS_CLIENT_SECRET") {
-        Ok(val) => val,
-        Err(_) => panic!(
+fn get_pandaops_client_secret_from_env() -> anyhow::Result<String> {
+    env::var("PANDAOPS_CLIENT_SECRET").map_err(|_| {
+        anyhow!(
             "Must supply pandaops client secret as environment variable, like:\n\
             PANDAOPS_CLIENT_SECRET=\"your-key-here\" trin"
-        ),
-    }
+        )
+    })
 }
 
-pub fn build_pandaops_http_client_from_env(pandaops_url: String) -> ureq::Request {
-    let client_id = get_pandaops_client_id_from_env();
-    let client_secret = get_pandaops_client_secret_from_env();
-    ureq::post(&pandaops_url)
+pub fn build_pandaops_http_client_from_env(pandaops_url: String) -> anyhow::Result<ureq::Request> {
+    let client_id = get_pandaops_client_id_from_env()?;
+    let client_secret = get_pandaops_client_secret_from_env()?;
+    Ok(ureq::post(&pandaops_url)
         .set("Content-Type", "application/json")
         .set("CF-Access-Client-Id", client_id.as_str())
-        .set("CF-Access-Client-Secret", client_secret.as_str())
+        .set("CF-Access-Client-Secret", client_secret.as_str()))
 }
 
 fn build_custom_provider_http_client(node_url: Url) -> ureq::Request {
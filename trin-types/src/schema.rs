@@ -0,0 +1,80 @@
+//! JSON schemas for the JSON representation of history network content keys and values, as
+//! accepted and returned by the JSON-RPC API.
+//!
+//! The published copies live in `trin-types/src/assets/schemas` and are regenerated with the
+//! `test_vectors` binary.
+
+use serde_json::{json, Value};
+
+use crate::content_key::HISTORY_CONTENT_TYPES;
+
+const JSON_SCHEMA_DRAFT: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Returns the JSON schema of a history network content key: a "0x"-prefixed hex string of the
+/// SSZ union, whose first byte selects the type of content.
+pub fn history_content_key_schema() -> Value {
+    // Each selector is followed by a 32 byte hash, except for the block number key, which is
+    // followed by a little endian u64.
+    let variants: Vec<Value> = HISTORY_CONTENT_TYPES
+        .iter()
+        .enumerate()
+        .map(|(selector, name)| {
+            let payload_len = if *name == "block_header_by_number" {
+                8
+            } else {
+                32
+            };
+            json!({
+                "title": name,
+                "type": "string",
+                "pattern": format!("^0x{selector:02x}[0-9a-fA-F]{{{}}}$", payload_len * 2),
+            })
+        })
+        .collect();
+
+    json!({
+        "$schema": JSON_SCHEMA_DRAFT,
+        "title": "HistoryContentKey",
+        "description": "SSZ encoded history network content key, as a 0x-prefixed hex string.",
+        "oneOf": variants,
+    })
+}
+
+/// Returns the JSON schema of a history network content value: a "0x"-prefixed hex string of
+/// the SSZ encoded value. Values carry no selector, so their type follows from the content key.
+pub fn history_content_value_schema() -> Value {
+    json!({
+        "$schema": JSON_SCHEMA_DRAFT,
+        "title": "HistoryContentValue",
+        "description": "SSZ encoded history network content value, as a 0x-prefixed hex string. \
+            The type of the value is determined by the selector of its content key.",
+        "type": "string",
+        "pattern": "^0x([0-9a-fA-F]{2})+$",
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    fn published_schema(name: &str) -> Value {
+        let path = format!("../trin-types/src/assets/schemas/{name}");
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn published_schemas_are_up_to_date() {
+        assert_eq!(
+            published_schema("history_content_key.json"),
+            history_content_key_schema(),
+            "regenerate the published schemas with the test_vectors binary"
+        );
+        assert_eq!(
+            published_schema("history_content_value.json"),
+            history_content_value_schema(),
+            "regenerate the published schemas with the test_vectors binary"
+        );
+    }
+}
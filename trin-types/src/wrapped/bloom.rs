@@ -5,6 +5,18 @@ use ssz::{Decode, Encode};
 #[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
 pub struct Bloom(BloomType);
 
+impl From<BloomType> for Bloom {
+    fn from(value: BloomType) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Bloom> for BloomType {
+    fn from(value: Bloom) -> Self {
+        value.0
+    }
+}
+
 impl Decode for Bloom {
     fn is_ssz_fixed_len() -> bool {
         true
@@ -5,6 +5,18 @@ use ssz::{Decode, Encode};
 #[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
 pub struct H160(H160Type);
 
+impl From<H160Type> for H160 {
+    fn from(value: H160Type) -> Self {
+        Self(value)
+    }
+}
+
+impl From<H160> for H160Type {
+    fn from(value: H160) -> Self {
+        value.0
+    }
+}
+
 impl Decode for H160 {
     fn is_ssz_fixed_len() -> bool {
         true
@@ -1,5 +1,6 @@
 use std::env;
 use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
     let output = Command::new("git")
@@ -27,4 +28,44 @@ fn main() {
     // Printing to stdout is how build scripts communicate with cargo
     // https://doc.rust-lang.org/cargo/reference/build-scripts.html#outputs-of-the-build-script
     println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+
+    // Reproducible builds set SOURCE_DATE_EPOCH to pin the build date.
+    let build_timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System time to be after the unix epoch")
+                .as_secs()
+        });
+    println!(
+        "cargo:rustc-env=BUILD_DATE={}",
+        format_date(build_timestamp)
+    );
+    println!(
+        "cargo:rustc-env=TARGET_TRIPLE={}",
+        env::var("TARGET").expect("Cargo to set TARGET for build scripts")
+    );
+}
+
+/// Formats a unix timestamp as a UTC date, e.g. 2023-04-20.
+fn format_date(timestamp: u64) -> String {
+    // Converts days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
 }
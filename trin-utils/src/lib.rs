@@ -1,3 +1,4 @@
 pub mod bytes;
 pub mod log;
+pub mod snappy;
 pub mod version;
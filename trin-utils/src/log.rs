@@ -1,28 +1,98 @@
-use std::env;
+use std::{env, io};
+
+use tracing::error;
+use tracing_subscriber::{
+    filter::EnvFilter,
+    fmt::{format::FmtSpan, MakeWriter},
+};
 
 #[cfg(windows)]
 use ansi_term;
 #[cfg(not(windows))]
 use atty;
 
-pub fn init_tracing_logger() {
+/// If set, a log line is written when each span closes, with the time spent in the span.
+const LOG_SPAN_EVENTS_ENV: &str = "TRIN_LOG_SPAN_EVENTS";
+
+/// The filter used when neither a filter nor RUST_LOG is given, or when the given one is invalid.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// Installs the global logger, which writes to stdout.
+///
+/// `filter` is a comma-separated list of `EnvFilter` directives, e.g.
+/// `info,portalnet::overlay=debug,utp=trace`. If it is `None`, the directives are read from the
+/// RUST_LOG environment variable instead, and everything at info level or above is logged if
+/// that isn't set either.
+pub fn init_tracing_logger(filter: Option<&str>) {
+    init_logger(filter, io::stdout, Output::Stdout);
+}
+
+/// Installs the global logger like `init_tracing_logger`, but writing to stderr, for commands
+/// whose output on stdout is parsed.
+pub fn init_stderr_tracing_logger(filter: Option<&str>) {
+    init_logger(filter, io::stderr, Output::Stderr);
+}
+
+/// The stream that the logger writes to.
+#[derive(Clone, Copy)]
+enum Output {
+    Stdout,
+    Stderr,
+}
+
+fn init_logger<W>(filter: Option<&str>, writer: W, output: Output)
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let span_events = if env::var_os(LOG_SPAN_EVENTS_ENV).is_some() {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    let directives = match filter {
+        Some(filter) => Some(filter.to_string()),
+        None => env::var(EnvFilter::DEFAULT_ENV).ok(),
+    };
+    let (env_filter, invalid_directives) = match directives {
+        Some(directives) => match EnvFilter::try_new(&directives) {
+            Ok(env_filter) => (env_filter, None),
+            Err(err) => (EnvFilter::new(DEFAULT_LOG_FILTER), Some((directives, err))),
+        },
+        None => (EnvFilter::new(DEFAULT_LOG_FILTER), None),
+    };
     tracing_subscriber::fmt()
-        .with_ansi(detect_ansi_support())
+        .with_ansi(detect_ansi_support(output))
+        .with_span_events(span_events)
+        .with_env_filter(env_filter)
+        .with_writer(writer)
         .init();
+
+    if let Some((directives, err)) = invalid_directives {
+        error!(
+            directives,
+            error = %err,
+            "Invalid log filter, logging at {DEFAULT_LOG_FILTER} level instead"
+        );
+    }
 }
 
-fn detect_ansi_support() -> bool {
+fn detect_ansi_support(output: Output) -> bool {
     #[cfg(windows)]
     {
         use ansi_term::enable_ansi_support;
+        let _ = output;
         enable_ansi_support().is_ok()
     }
     #[cfg(not(windows))]
     {
-        // Detect whether our log output (which goes to stdout) is going to a terminal.
+        // Detect whether our log output is going to a terminal.
         // For example, instead of the terminal, it might be getting piped into another file, which
         // probably ought to be plain text.
-        let is_terminal = atty::is(atty::Stream::Stdout);
+        let stream = match output {
+            Output::Stdout => atty::Stream::Stdout,
+            Output::Stderr => atty::Stream::Stderr,
+        };
+        let is_terminal = atty::is(stream);
         if !is_terminal {
             return false;
         }
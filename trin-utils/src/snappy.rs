@@ -0,0 +1,166 @@
+//! Snappy compression as used by consensus layer data: the framing format for SSZ payloads on
+//! the network and in era files, and the raw block format of the consensus spec test vectors.
+
+use std::io::{Read, Write};
+
+use ssz::{Decode, DecodeError, Encode};
+use thiserror::Error;
+
+/// The maximum size of an uncompressed `ssz_snappy` payload, `MAX_CHUNK_SIZE` of the consensus
+/// p2p spec.
+pub const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
+
+/// An error from a snappy compression operation.
+#[derive(Debug, Error)]
+pub enum SnappyError {
+    #[error("Unable to compress snappy frames: {0}")]
+    Compress(std::io::Error),
+
+    #[error("Unable to decompress snappy frames: {0}")]
+    Decompress(std::io::Error),
+
+    #[error("Unable to decompress raw snappy block: {0}")]
+    DecompressRaw(#[from] snap::Error),
+
+    #[error("Decompressed snappy frames exceed the maximum size of {0} bytes")]
+    TooLarge(usize),
+
+    #[error("Invalid length prefix of ssz_snappy payload: {0}")]
+    Length(String),
+
+    #[error("Unable to decode SSZ payload: {0:?}")]
+    Ssz(DecodeError),
+}
+
+/// Compresses `data` in the snappy framing format.
+pub fn compress_frame(data: &[u8]) -> Result<Vec<u8>, SnappyError> {
+    let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+    encoder.write_all(data).map_err(SnappyError::Compress)?;
+    encoder.into_inner().map_err(|err| {
+        let err = err.error();
+        SnappyError::Compress(std::io::Error::new(err.kind(), err.to_string()))
+    })
+}
+
+/// Decompresses `data` in the snappy framing format, failing if it decompresses to more than
+/// `max_size` bytes. Decompression stops once the limit is exceeded.
+pub fn decompress_frame(data: &[u8], max_size: usize) -> Result<Vec<u8>, SnappyError> {
+    let mut decompressed = Vec::new();
+    snap::read::FrameDecoder::new(data)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(SnappyError::Decompress)?;
+    if decompressed.len() > max_size {
+        return Err(SnappyError::TooLarge(max_size));
+    }
+    Ok(decompressed)
+}
+
+/// Decompresses a raw (unframed) snappy block, the format of `*.ssz_snappy` files in the
+/// consensus spec tests.
+pub fn decompress_raw(data: &[u8]) -> Result<Vec<u8>, SnappyError> {
+    Ok(snap::raw::Decoder::new().decompress_vec(data)?)
+}
+
+/// SSZ encodes `value` in the `ssz_snappy` encoding of the consensus p2p spec: the unsigned
+/// LEB128 varint of the length of the SSZ bytes, followed by the SSZ bytes compressed in the
+/// snappy framing format.
+pub fn ssz_snappy_encode<T: Encode>(value: &T) -> Result<Vec<u8>, SnappyError> {
+    let ssz_bytes = value.as_ssz_bytes();
+    let mut encoded = Vec::new();
+    leb128::write::unsigned(&mut encoded, ssz_bytes.len() as u64).map_err(SnappyError::Compress)?;
+    encoded.extend(compress_frame(&ssz_bytes)?);
+    Ok(encoded)
+}
+
+/// Decodes `data` in the `ssz_snappy` encoding, see `ssz_snappy_encode`. Fails if the length
+/// prefix exceeds `MAX_PAYLOAD_SIZE` or doesn't match the length of the decompressed payload.
+pub fn ssz_snappy_decode<T: Decode>(data: &[u8]) -> Result<T, SnappyError> {
+    let mut frames = data;
+    let length =
+        leb128::read::unsigned(&mut frames).map_err(|err| SnappyError::Length(err.to_string()))?;
+    let length = usize::try_from(length)
+        .ok()
+        .filter(|length| *length <= MAX_PAYLOAD_SIZE)
+        .ok_or(SnappyError::TooLarge(MAX_PAYLOAD_SIZE))?;
+    let ssz_bytes = decompress_frame(frames, length)?;
+    if ssz_bytes.len() != length {
+        return Err(SnappyError::Length(format!(
+            "prefix is {length} bytes, payload is {} bytes",
+            ssz_bytes.len()
+        )));
+    }
+    T::from_ssz_bytes(&ssz_bytes).map_err(SnappyError::Ssz)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frame_round_trip() {
+        let data = vec![7u8; 100_000];
+        let compressed = compress_frame(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress_frame(&compressed, data.len()).unwrap(), data);
+        assert!(matches!(
+            decompress_frame(&compressed, data.len() - 1),
+            Err(SnappyError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn ssz_snappy_round_trip() {
+        let value: Vec<u64> = (0..1000).collect();
+        let encoded = ssz_snappy_encode(&value).unwrap();
+        // The length prefix of 8000 bytes is a two byte varint.
+        assert_eq!(encoded[..2], [0xc0, 0x3e]);
+        assert_eq!(ssz_snappy_decode::<Vec<u64>>(&encoded).unwrap(), value);
+
+        // Five bytes are not a valid u64.
+        let truncated = ssz_snappy_encode(&vec![0u8; 5]).unwrap();
+        assert!(matches!(
+            ssz_snappy_decode::<u64>(&truncated),
+            Err(SnappyError::Ssz(_))
+        ));
+    }
+
+    #[test]
+    fn ssz_snappy_rejects_invalid_length_prefix() {
+        let frames = compress_frame(&[0u8; 8]).unwrap();
+
+        let mut shorter = vec![4];
+        shorter.extend(&frames);
+        assert!(matches!(
+            ssz_snappy_decode::<u64>(&shorter),
+            Err(SnappyError::TooLarge(4))
+        ));
+
+        let mut longer = vec![9];
+        longer.extend(&frames);
+        assert!(matches!(
+            ssz_snappy_decode::<u64>(&longer),
+            Err(SnappyError::Length(_))
+        ));
+
+        let mut too_large = vec![];
+        leb128::write::unsigned(&mut too_large, MAX_PAYLOAD_SIZE as u64 + 1).unwrap();
+        too_large.extend(&frames);
+        assert!(matches!(
+            ssz_snappy_decode::<u64>(&too_large),
+            Err(SnappyError::TooLarge(MAX_PAYLOAD_SIZE))
+        ));
+    }
+
+    #[test]
+    fn decompress_raw_block() {
+        let data = b"portal network portal network".to_vec();
+        let compressed = snap::raw::Encoder::new().compress_vec(&data).unwrap();
+        assert_eq!(decompress_raw(&compressed).unwrap(), data);
+        assert!(matches!(
+            decompress_frame(&compressed, data.len()),
+            Err(SnappyError::Decompress(_))
+        ));
+    }
+}
@@ -1,10 +1,18 @@
 pub const TRIN_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The git commit that trin was built from, or an empty string if it is unknown.
+pub const GIT_HASH: &str = env!("GIT_HASH");
+
+/// The UTC date that trin was built on.
+pub const BUILD_DATE: &str = env!("BUILD_DATE");
+
+/// The target triple that trin was built for.
+pub const TARGET_TRIPLE: &str = env!("TARGET_TRIPLE");
+
 /// Returns the trin version and git revision.
 pub fn get_trin_version() -> String {
-    let git_hash = env!("GIT_HASH");
-    let git_revision_short = if git_hash.len() == 40 {
-        git_hash[..6].to_string()
+    let git_revision_short = if GIT_HASH.len() == 40 {
+        GIT_HASH[..6].to_string()
     } else {
         "unknown".to_string()
     };
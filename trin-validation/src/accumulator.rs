@@ -1,37 +1,60 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use anyhow::anyhow;
 use ethereum_types::H256;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use ssz::Decode;
-use ssz_derive::{Decode, Encode};
-use ssz_types::{typenum, VariableList};
+use ssz::{Decode, DecodeError, Encode};
+use ssz_types::{typenum, typenum::Unsigned, VariableList};
 use tokio::sync::mpsc;
-use tree_hash::TreeHash;
-use tree_hash_derive::TreeHash;
+use tree_hash::{TreeHash, TreeHashType};
 
-use crate::{
-    constants::{EPOCH_SIZE, MERGE_BLOCK_NUMBER},
-    merkle::proof::{verify_merkle_proof, MerkleTree},
+use crate::constants::{
+    BELLATRIX_FORK_SLOT, CAPELLA_FORK_SLOT, EPOCH_SIZE, MERGE_BLOCK_NUMBER, SHANGHAI_TIMESTAMP,
 };
 use trin_types::content_key::{EpochAccumulatorKey, HistoryContentKey};
 use trin_types::execution::accumulator::EpochAccumulator;
-use trin_types::execution::header::{BlockHeaderProof, Header, HeaderWithProof};
+use trin_types::execution::header::{
+    AccumulatorProof, BlockHeaderProof, ExecutionBlockProof, Header, HeaderWithProof,
+};
 use trin_types::jsonrpc::endpoints::HistoryEndpoint;
 use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
+use trin_types::merkle::{
+    concat_generalized_indices, length_chunk, verify_merkle_proof, GeneralizedIndex, MerkleTree,
+};
 use trin_utils::bytes::hex_decode;
 
 /// SSZ List[Hash256, max_length = MAX_HISTORICAL_EPOCHS]
 /// List of historical epoch accumulator merkle roots preceding current epoch.
 pub type HistoricalEpochRoots = VariableList<tree_hash::Hash256, typenum::U131072>;
 
+/// Size of the offset of the `historical_epochs` list in the SSZ encoding of the container.
+const HISTORICAL_EPOCHS_OFFSET: usize = ssz::BYTES_PER_LENGTH_OFFSET;
+
+/// Size of a historical epoch root.
+const EPOCH_ROOT_SIZE: usize = 32;
+
 /// SSZ Container
 /// Primary datatype used to maintain record of historical and current epoch.
 /// Verifies canonical-ness of a given header.
-#[derive(Clone, Debug, Decode, Encode, Eq, PartialEq, Deserialize, Serialize, TreeHash)]
+///
+/// Container {
+///     historical_epochs: HistoricalEpochRoots,
+/// }
+///
+/// The historical epoch roots are kept in their SSZ encoding and read on demand, rather than
+/// decoded into a list up front. The tree hash root and the index of each epoch root are derived
+/// the first time they are needed, and shared by all clones of the accumulator.
+///
+/// The SSZ, tree hash and serde representations are those of the container above.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(from = "MasterAccumulatorFields", into = "MasterAccumulatorFields")]
 pub struct MasterAccumulator {
-    pub historical_epochs: HistoricalEpochRoots,
+    /// The concatenated historical epoch roots, i.e. the SSZ encoding of the list.
+    epoch_roots: Arc<[u8]>,
+    root: Arc<OnceCell<H256>>,
+    epoch_indices: Arc<OnceCell<HashMap<H256, usize>>>,
 }
 
 impl MasterAccumulator {
@@ -45,6 +68,74 @@ impl MasterAccumulator {
             .map_err(|err| anyhow!("Unable to decode master accumulator: {err:?}"))
     }
 
+    /// Creates a master accumulator from a list of historical epoch roots.
+    pub fn new(historical_epochs: &HistoricalEpochRoots) -> Self {
+        Self::from_epoch_roots(historical_epochs.as_ssz_bytes().into())
+    }
+
+    fn from_epoch_roots(epoch_roots: Arc<[u8]>) -> Self {
+        Self {
+            epoch_roots,
+            root: Arc::new(OnceCell::new()),
+            epoch_indices: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Number of historical epochs in the accumulator.
+    pub fn epoch_count(&self) -> usize {
+        self.epoch_roots.len() / EPOCH_ROOT_SIZE
+    }
+
+    /// Returns the root of the epoch accumulator of the historical epoch at `epoch_index`.
+    pub fn epoch_root(&self, epoch_index: usize) -> Option<H256> {
+        let start = epoch_index.checked_mul(EPOCH_ROOT_SIZE)?;
+        self.epoch_roots
+            .get(start..start + EPOCH_ROOT_SIZE)
+            .map(H256::from_slice)
+    }
+
+    /// Returns the index of the historical epoch whose epoch accumulator has the given root.
+    pub fn epoch_index(&self, epoch_root: &H256) -> Option<usize> {
+        self.epoch_indices
+            .get_or_init(|| {
+                self.epoch_roots
+                    .chunks_exact(EPOCH_ROOT_SIZE)
+                    .enumerate()
+                    .map(|(index, root)| (H256::from_slice(root), index))
+                    .collect()
+            })
+            .get(epoch_root)
+            .copied()
+    }
+
+    /// Decodes the full list of historical epoch roots.
+    pub fn historical_epochs(&self) -> HistoricalEpochRoots {
+        let roots: Vec<H256> = self
+            .epoch_roots
+            .chunks_exact(EPOCH_ROOT_SIZE)
+            .map(H256::from_slice)
+            .collect();
+        // The length was checked against the maximum when decoding.
+        VariableList::new(roots).expect("master accumulator exceeds maximum historical epochs")
+    }
+
+    /// Returns the tree hash root of the accumulator, which is computed on the first call.
+    ///
+    /// Also available through `TreeHash`, without importing the trait.
+    pub fn tree_hash_root(&self) -> H256 {
+        // The root of a container with a single field is the root of that field.
+        *self
+            .root
+            .get_or_init(|| self.historical_epochs().tree_hash_root())
+    }
+
+    /// Returns the epoch root for `epoch_index`, or an error if the epoch is not in the
+    /// accumulator.
+    fn try_epoch_root(&self, epoch_index: usize) -> anyhow::Result<H256> {
+        self.epoch_root(epoch_index)
+            .ok_or_else(|| anyhow!("Epoch #{epoch_index} is missing from master accumulator."))
+    }
+
     /// Number of the last block to be included in the accumulator
     pub fn height(&self) -> u64 {
         MERGE_BLOCK_NUMBER
@@ -64,7 +155,7 @@ impl MasterAccumulator {
         }
         let rel_index = block_number % EPOCH_SIZE as u64;
         let epoch_index = block_number / EPOCH_SIZE as u64;
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
+        let epoch_hash = self.try_epoch_root(epoch_index as usize)?;
         let epoch_acc = self
             .lookup_epoch_acc(epoch_hash, history_jsonrpc_tx)
             .await?;
@@ -87,18 +178,77 @@ impl MasterAccumulator {
                     return Ok(());
                 }
             }
+            BlockHeaderProof::HistoricalRootsBlockProof(val) => {
+                if hwp.header.number <= MERGE_BLOCK_NUMBER
+                    || hwp.header.timestamp >= SHANGHAI_TIMESTAMP
+                {
+                    return Err(anyhow!(
+                        "Historical roots proof is only valid for headers between the merge and Capella."
+                    ));
+                }
+                if !(BELLATRIX_FORK_SLOT..CAPELLA_FORK_SLOT).contains(&val.slot) {
+                    return Err(anyhow!("Invalid slot for historical roots proof."));
+                }
+                verify_execution_block_proof(
+                    &hwp.header,
+                    &val.execution_block_proof,
+                    val.beacon_block_root,
+                )?;
+                return Err(anyhow!(UNVERIFIED_BEACON_BLOCK_ERROR));
+            }
+            BlockHeaderProof::HistoricalSummariesBlockProof(val) => {
+                if hwp.header.timestamp < SHANGHAI_TIMESTAMP {
+                    return Err(anyhow!(
+                        "Historical summaries proof is only valid for headers from Capella onwards."
+                    ));
+                }
+                if val.slot < CAPELLA_FORK_SLOT {
+                    return Err(anyhow!("Invalid slot for historical summaries proof."));
+                }
+                verify_execution_block_proof(
+                    &hwp.header,
+                    &val.execution_block_proof,
+                    val.beacon_block_root,
+                )?;
+                return Err(anyhow!(UNVERIFIED_BEACON_BLOCK_ERROR));
+            }
         };
 
         // Look up historical epoch hash for header from master accumulator
-        let gen_index = calculate_generalized_index(&hwp.header);
         let epoch_index = self.get_epoch_index_of_header(&hwp.header) as usize;
-        let epoch_hash = self.historical_epochs[epoch_index];
-        match verify_merkle_proof(hwp.header.hash(), &proof.proof, 15, gen_index, epoch_hash) {
-            true => Ok(()),
-            false => Err(anyhow!(
-                "Merkle proof validation failed for pre-merge header"
-            )),
+        let epoch_hash = self.try_epoch_root(epoch_index)?;
+        verify_accumulator_proof(&hwp.header, proof, epoch_hash)
+    }
+
+    /// Validates a contiguous range of headers, in ascending order by block number. Every header
+    /// must carry a valid proof and be the parent of the next header in the range.
+    pub fn validate_header_with_proof_range(&self, hwps: &[HeaderWithProof]) -> anyhow::Result<()> {
+        for hwp in hwps {
+            self.validate_header_with_proof(hwp)
+                .map_err(|err| anyhow!("Invalid proof for block #{}: {err}", hwp.header.number))?;
         }
+        for pair in hwps.windows(2) {
+            let (parent, child) = (&pair[0].header, &pair[1].header);
+            if child.number != parent.number + 1 || child.parent_hash != parent.hash() {
+                return Err(anyhow!(
+                    "Headers are not contiguous: block #{} does not follow block #{}",
+                    child.number,
+                    parent.number
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Validates that the root of an epoch accumulator is one of the historical epochs of this
+    /// master accumulator.
+    pub fn validate_epoch_acc(&self, epoch_acc: &EpochAccumulator) -> anyhow::Result<()> {
+        if self.epoch_index(&epoch_acc.tree_hash_root()).is_none() {
+            return Err(anyhow!(
+                "Invalid epoch accumulator, missing from master accumulator."
+            ));
+        }
+        Ok(())
     }
 
     pub async fn lookup_epoch_acc(
@@ -107,7 +257,7 @@ impl MasterAccumulator {
         history_jsonrpc_tx: mpsc::UnboundedSender<HistoryJsonRpcRequest>,
     ) -> anyhow::Result<EpochAccumulator> {
         let content_key = HistoryContentKey::EpochAccumulator(EpochAccumulatorKey { epoch_hash });
-        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key);
+        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key, false);
         let (resp_tx, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
         let request = HistoryJsonRpcRequest {
             endpoint,
@@ -143,14 +293,12 @@ impl MasterAccumulator {
         }
         // Fetch epoch accumulator for header
         let epoch_index = self.get_epoch_index_of_header(header);
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
+        let epoch_hash = self.try_epoch_root(epoch_index as usize)?;
         let epoch_acc = self
             .lookup_epoch_acc(epoch_hash, history_jsonrpc_tx)
             .await?;
 
         // Validate epoch accumulator hash matches historical hash from master accumulator
-        let epoch_index = self.get_epoch_index_of_header(header);
-        let epoch_hash = self.historical_epochs[epoch_index as usize];
         if epoch_acc.tree_hash_root() != epoch_hash {
             return Err(anyhow!(
                 "Epoch acc hash sourced from network doesn't match historical hash in master acc."
@@ -159,6 +307,37 @@ impl MasterAccumulator {
         MasterAccumulator::construct_proof(header, &epoch_acc)
     }
 
+    /// Constructs a `HeaderWithProof` for a pre-merge header, using the epoch accumulator that
+    /// contains the header.
+    pub fn construct_header_with_proof(
+        header: &Header,
+        epoch_acc: &EpochAccumulator,
+    ) -> anyhow::Result<HeaderWithProof> {
+        let proof = MasterAccumulator::construct_proof(header, epoch_acc)?;
+        Ok(HeaderWithProof {
+            header: header.clone(),
+            proof: BlockHeaderProof::AccumulatorProof(AccumulatorProof { proof }),
+        })
+    }
+
+    /// Verifies the accumulator proof of a `HeaderWithProof` against the epoch accumulator that
+    /// contains the header, without requiring the master accumulator.
+    ///
+    /// The caller is responsible for checking that the epoch accumulator is canonical, i.e. that
+    /// its root is found in a trusted master accumulator.
+    pub fn verify_header_with_proof(
+        hwp: &HeaderWithProof,
+        epoch_acc: &EpochAccumulator,
+    ) -> anyhow::Result<()> {
+        let proof = match &hwp.proof {
+            BlockHeaderProof::AccumulatorProof(val) => val,
+            _ => {
+                return Err(anyhow!("Missing accumulator proof for header."));
+            }
+        };
+        verify_accumulator_proof(&hwp.header, proof, epoch_acc.tree_hash_root())
+    }
+
     pub fn construct_proof(
         header: &Header,
         epoch_acc: &EpochAccumulator,
@@ -188,24 +367,22 @@ impl MasterAccumulator {
             // add the total difficulty as a leaf
             leaves.push(leaf);
         }
-        // Create the merkle tree from leaves
-        let merkle_tree = MerkleTree::create(&leaves, 14);
-
-        // Multiply hr_index by 2 b/c we're now using a depth of 14 rather than the original 13
-        let hr_index = hr_index * 2;
-
-        // Generating the proof for the value at hr_index (leaf)
-        let (leaf, mut proof) = merkle_tree
-            .generate_proof(hr_index, 14)
-            .map_err(|err| anyhow!("Unable to generate proof for given index: {err:?}"))?;
+        // Create the merkle tree of the list data from leaves
+        let merkle_tree = MerkleTree::new(&leaves, 14)
+            .map_err(|err| anyhow!("Unable to build epoch accumulator tree: {err}"))?;
+
+        // Generate the proof for the block hash within the list data, whose generalized index is
+        // that of the whole epoch accumulator without the leading list data node.
+        let (_, data_index) = epoch_acc_generalized_index(header);
+        let mut proof = merkle_tree
+            .generate_proof(data_index)
+            .map_err(|err| anyhow!("Unable to generate proof for given index: {err}"))?;
         // Validate that the value the proof is for (leaf) is the header hash
-        assert_eq!(leaf, header.hash());
+        assert_eq!(merkle_tree.node(data_index)?, header.hash());
 
-        // Add the be encoded EPOCH_SIZE to proof to comply with ssz merkleization spec
+        // Add the length of the list, which is mixed into the root of the epoch accumulator
         // https://github.com/ethereum/consensus-specs/blob/dev/ssz/merkle-proofs.md#ssz-object-to-index
-        proof.push(H256::from_slice(&hex_decode(
-            "0x0020000000000000000000000000000000000000000000000000000000000000",
-        )?));
+        proof.push(length_chunk(EPOCH_SIZE));
         let final_proof: [H256; 15] = proof
             .try_into()
             .map_err(|_| anyhow!("Invalid proof length."))?;
@@ -213,11 +390,158 @@ impl MasterAccumulator {
     }
 }
 
-fn calculate_generalized_index(header: &Header) -> usize {
-    // Calculate generalized index for header
-    // https://github.com/ethereum/consensus-specs/blob/v0.11.1/ssz/merkle-proofs.md#generalized-merkle-tree-index
+impl PartialEq for MasterAccumulator {
+    fn eq(&self, other: &Self) -> bool {
+        self.epoch_roots == other.epoch_roots
+    }
+}
+
+impl Eq for MasterAccumulator {}
+
+/// The fields of the master accumulator container, decoded, for its serde representation.
+#[derive(Deserialize, Serialize)]
+struct MasterAccumulatorFields {
+    historical_epochs: HistoricalEpochRoots,
+}
+
+impl From<MasterAccumulatorFields> for MasterAccumulator {
+    fn from(fields: MasterAccumulatorFields) -> Self {
+        Self::new(&fields.historical_epochs)
+    }
+}
+
+impl From<MasterAccumulator> for MasterAccumulatorFields {
+    fn from(master_acc: MasterAccumulator) -> Self {
+        Self {
+            historical_epochs: master_acc.historical_epochs(),
+        }
+    }
+}
+
+impl TreeHash for MasterAccumulator {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::Container
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("Container should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> tree_hash::Hash256 {
+        MasterAccumulator::tree_hash_root(self)
+    }
+}
+
+impl Decode for MasterAccumulator {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    /// Checks the layout of the encoding without decoding the individual epoch roots.
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < HISTORICAL_EPOCHS_OFFSET {
+            return Err(DecodeError::InvalidByteLength {
+                len: bytes.len(),
+                expected: HISTORICAL_EPOCHS_OFFSET,
+            });
+        }
+        let mut offset = [0u8; HISTORICAL_EPOCHS_OFFSET];
+        offset.copy_from_slice(&bytes[..HISTORICAL_EPOCHS_OFFSET]);
+        let offset = u32::from_le_bytes(offset) as usize;
+        if offset != HISTORICAL_EPOCHS_OFFSET {
+            return Err(DecodeError::OffsetIntoFixedPortion(offset));
+        }
+        let epoch_roots = &bytes[HISTORICAL_EPOCHS_OFFSET..];
+        if epoch_roots.len() % EPOCH_ROOT_SIZE != 0 {
+            return Err(DecodeError::InvalidByteLength {
+                len: epoch_roots.len(),
+                expected: epoch_roots.len() / EPOCH_ROOT_SIZE * EPOCH_ROOT_SIZE,
+            });
+        }
+        let max_epochs = typenum::U131072::to_usize();
+        if epoch_roots.len() / EPOCH_ROOT_SIZE > max_epochs {
+            return Err(DecodeError::BytesInvalid(format!(
+                "Master accumulator exceeds maximum of {max_epochs} historical epochs"
+            )));
+        }
+        Ok(Self::from_epoch_roots(epoch_roots.into()))
+    }
+}
+
+impl Encode for MasterAccumulator {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(HISTORICAL_EPOCHS_OFFSET as u32).to_le_bytes());
+        buf.extend_from_slice(&self.epoch_roots);
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        HISTORICAL_EPOCHS_OFFSET + self.epoch_roots.len()
+    }
+}
+
+/// Verifies that `proof` proves the inclusion of `header` in the epoch accumulator with the given
+/// root.
+fn verify_accumulator_proof(
+    header: &Header,
+    proof: &AccumulatorProof,
+    epoch_hash: H256,
+) -> anyhow::Result<()> {
+    let (gen_index, _) = epoch_acc_generalized_index(header);
+    match verify_merkle_proof(header.hash(), &proof.proof, gen_index, epoch_hash) {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "Merkle proof validation failed for pre-merge header"
+        )),
+    }
+}
+
+/// Generalized index of the execution block hash within a Bellatrix or Capella beacon block:
+/// `BeaconBlock.body` (12) -> `BeaconBlockBody.execution_payload` (25) ->
+/// `ExecutionPayload.block_hash` (28).
+const EXECUTION_BLOCK_HASH_GENERALIZED_INDEX: GeneralizedIndex = 3228;
+
+/// Error for post-merge headers whose proof can't be verified yet. The beacon block root of such a
+/// proof is supplied by the sender, so the header is only canonical if the root is proven against
+/// the frozen `historical_roots` or `historical_summaries`, which the node doesn't have yet.
+const UNVERIFIED_BEACON_BLOCK_ERROR: &str =
+    "Beacon block proofs against historical roots or summaries are not yet supported.";
+
+/// Verifies that `proof` proves the inclusion of the hash of `header` in the beacon block with the
+/// given root. This alone doesn't make the header canonical, see `UNVERIFIED_BEACON_BLOCK_ERROR`.
+fn verify_execution_block_proof(
+    header: &Header,
+    proof: &ExecutionBlockProof,
+    beacon_block_root: H256,
+) -> anyhow::Result<()> {
+    match verify_merkle_proof(
+        header.hash(),
+        proof,
+        EXECUTION_BLOCK_HASH_GENERALIZED_INDEX,
+        beacon_block_root,
+    ) {
+        true => Ok(()),
+        false => Err(anyhow!(
+            "Merkle proof validation failed for post-merge header"
+        )),
+    }
+}
+
+/// Returns the generalized index of the block hash of `header` within its epoch accumulator, and
+/// within the data of the epoch accumulator list:
+/// list data (2) -> `HeaderRecord` (EPOCH_SIZE + index in epoch) -> `HeaderRecord.block_hash` (2).
+fn epoch_acc_generalized_index(header: &Header) -> (GeneralizedIndex, GeneralizedIndex) {
+    // https://github.com/ethereum/consensus-specs/blob/dev/ssz/merkle-proofs.md#generalized-merkle-tree-index
     let hr_index = header.number as usize % EPOCH_SIZE;
-    (EPOCH_SIZE * 2 * 2) + (hr_index * 2)
+    let data_index = concat_generalized_indices(&[EPOCH_SIZE + hr_index, 2]);
+    (concat_generalized_indices(&[2, data_index]), data_index)
 }
 
 #[cfg(test)]
@@ -230,12 +554,15 @@ mod test {
     use ethereum_types::{Bloom, H160, U256};
     use rstest::*;
     use serde_json::json;
-    use ssz::Decode;
+    use ssz::{Decode, Encode};
+
+    use ssz_types::FixedVector;
 
     use crate::constants::DEFAULT_MASTER_ACC_HASH;
     use trin_types::execution::header::{
-        AccumulatorProof, BlockHeaderProof, HeaderWithProof, SszNone,
+        BlockProofHistoricalRoots, BlockProofHistoricalSummaries, SszNone,
     };
+    use trin_types::merkle::calculate_merkle_root;
     use trin_utils::bytes::hex_encode;
 
     #[rstest]
@@ -309,6 +636,132 @@ mod test {
             .contains("Merkle proof validation failed"));
     }
 
+    #[test]
+    fn construct_and_verify_header_with_proof_from_epoch_acc() {
+        let file = fs::read_to_string("./src/assets/fluffy/header_with_proofs.json").unwrap();
+        let json: Value = serde_json::from_str(&file).unwrap();
+        let raw_fluffy_hwp = json["1000001"]["value"].as_str().unwrap();
+        let raw_fluffy_hwp = hex_decode(raw_fluffy_hwp).unwrap();
+
+        let header = get_header(1_000_001);
+        let epoch_acc = get_epoch_acc(&header);
+        let hwp = MasterAccumulator::construct_header_with_proof(&header, &epoch_acc).unwrap();
+        assert_eq!(hwp.as_ssz_bytes(), raw_fluffy_hwp);
+        MasterAccumulator::verify_header_with_proof(&hwp, &epoch_acc).unwrap();
+
+        // A proof for a different header in the same epoch must not verify.
+        let other_header = get_header(1_000_002);
+        let invalid_hwp = HeaderWithProof {
+            header: other_header,
+            proof: hwp.proof,
+        };
+        MasterAccumulator::verify_header_with_proof(&invalid_hwp, &epoch_acc).unwrap_err();
+    }
+
+    #[test]
+    fn validate_header_with_proof_range() {
+        let master_acc = get_mainnet_master_acc();
+        let epoch_acc = get_epoch_acc(&get_header(1_000_001));
+        let hwps: Vec<HeaderWithProof> = (1_000_001..=1_000_010)
+            .map(|number| {
+                MasterAccumulator::construct_header_with_proof(&get_header(number), &epoch_acc)
+                    .unwrap()
+            })
+            .collect();
+        master_acc.validate_header_with_proof_range(&hwps).unwrap();
+
+        // Skipping a header breaks the range.
+        let gapped = [hwps[0].clone(), hwps[2].clone()];
+        assert!(master_acc
+            .validate_header_with_proof_range(&gapped)
+            .unwrap_err()
+            .to_string()
+            .contains("not contiguous"));
+    }
+
+    #[test]
+    fn validate_epoch_acc() {
+        let master_acc = get_mainnet_master_acc();
+        let mut epoch_acc = get_epoch_acc(&get_header(1_000_001));
+        master_acc.validate_epoch_acc(&epoch_acc).unwrap();
+
+        epoch_acc[0].total_difficulty += U256::one();
+        master_acc.validate_epoch_acc(&epoch_acc).unwrap_err();
+    }
+
+    #[test]
+    fn master_accumulator_rejects_post_merge_header_with_unverified_beacon_chain_proof() {
+        let master_acc = get_mainnet_master_acc();
+        let mut header = generate_random_header(&(MERGE_BLOCK_NUMBER + 1));
+        let execution_block_proof: Vec<H256> = (0..11).map(|_| H256::random()).collect();
+        let beacon_block_root = calculate_merkle_root(
+            header.hash(),
+            &execution_block_proof,
+            EXECUTION_BLOCK_HASH_GENERALIZED_INDEX,
+        )
+        .unwrap();
+        let historical_roots_proof = BlockProofHistoricalRoots {
+            beacon_block_proof: FixedVector::from(vec![H256::zero(); 14]),
+            beacon_block_root,
+            execution_block_proof: FixedVector::from(execution_block_proof.clone()),
+            slot: BELLATRIX_FORK_SLOT,
+        };
+        let hwp = HeaderWithProof {
+            header: header.clone(),
+            proof: BlockHeaderProof::HistoricalRootsBlockProof(historical_roots_proof.clone()),
+        };
+        // The execution block proof is valid, but the beacon block root is made up.
+        assert_eq!(
+            master_acc
+                .validate_header_with_proof(&hwp)
+                .unwrap_err()
+                .to_string(),
+            UNVERIFIED_BEACON_BLOCK_ERROR
+        );
+
+        // The proof must be for the header's hash.
+        let mut invalid_hwp = hwp.clone();
+        invalid_hwp.header.gas_used = U256::from(2);
+        assert!(master_acc
+            .validate_header_with_proof(&invalid_hwp)
+            .unwrap_err()
+            .to_string()
+            .contains("Merkle proof validation failed"));
+
+        // Post-Capella headers require a historical summaries proof.
+        header.timestamp = SHANGHAI_TIMESTAMP;
+        let beacon_block_root = calculate_merkle_root(
+            header.hash(),
+            &execution_block_proof,
+            EXECUTION_BLOCK_HASH_GENERALIZED_INDEX,
+        )
+        .unwrap();
+        let hwp = HeaderWithProof {
+            header: header.clone(),
+            proof: BlockHeaderProof::HistoricalRootsBlockProof(BlockProofHistoricalRoots {
+                beacon_block_root,
+                ..historical_roots_proof
+            }),
+        };
+        master_acc.validate_header_with_proof(&hwp).unwrap_err();
+        let hwp = HeaderWithProof {
+            header,
+            proof: BlockHeaderProof::HistoricalSummariesBlockProof(BlockProofHistoricalSummaries {
+                beacon_block_proof: FixedVector::from(vec![H256::zero(); 13]),
+                beacon_block_root,
+                execution_block_proof: FixedVector::from(execution_block_proof),
+                slot: CAPELLA_FORK_SLOT,
+            }),
+        };
+        assert_eq!(
+            master_acc
+                .validate_header_with_proof(&hwp)
+                .unwrap_err()
+                .to_string(),
+            UNVERIFIED_BEACON_BLOCK_ERROR
+        );
+    }
+
     #[tokio::test]
     #[should_panic(expected = "Missing accumulator proof for pre-merge header.")]
     async fn master_accumulator_cannot_validate_pre_merge_header_missing_proof() {
@@ -348,6 +801,50 @@ mod test {
         master_acc.validate_header_with_proof(&future_hwp).unwrap();
     }
 
+    #[test]
+    fn master_accumulator_reads_epoch_roots_on_demand() {
+        let raw = fs::read("./src/assets/merge_macc.bin").unwrap();
+        let master_acc = MasterAccumulator::from_ssz_bytes(&raw).unwrap();
+        assert_eq!(master_acc.as_ssz_bytes(), raw);
+
+        // The lazily read accumulator agrees with the fully decoded list of epoch roots.
+        let historical_epochs = HistoricalEpochRoots::from_ssz_bytes(&raw[4..]).unwrap();
+        assert_eq!(master_acc.epoch_count(), historical_epochs.len());
+        assert_eq!(master_acc.historical_epochs(), historical_epochs);
+        assert_eq!(MasterAccumulator::new(&historical_epochs), master_acc);
+        for (index, root) in historical_epochs.iter().enumerate() {
+            assert_eq!(master_acc.epoch_root(index), Some(*root));
+            assert_eq!(master_acc.epoch_index(root), Some(index));
+        }
+        assert_eq!(master_acc.epoch_root(historical_epochs.len()), None);
+        assert_eq!(master_acc.epoch_index(&H256::random()), None);
+        assert_eq!(
+            master_acc.tree_hash_root(),
+            H256::from_str(DEFAULT_MASTER_ACC_HASH).unwrap()
+        );
+        assert_eq!(
+            TreeHash::tree_hash_root(&master_acc),
+            historical_epochs.tree_hash_root()
+        );
+
+        // Serde uses the fields of the container.
+        let json = serde_json::to_value(&master_acc).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "historical_epochs": historical_epochs })
+        );
+        let decoded: MasterAccumulator = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, master_acc);
+    }
+
+    #[rstest]
+    #[case(vec![4, 0, 0])]
+    #[case(vec![8, 0, 0, 0])]
+    #[case([vec![4, 0, 0, 0], vec![0; 33]].concat())]
+    fn master_accumulator_rejects_invalid_encoding(#[case] raw: Vec<u8>) {
+        assert!(MasterAccumulator::from_ssz_bytes(&raw).is_err());
+    }
+
     //
     // Testing utils
     //
@@ -369,6 +866,15 @@ mod test {
         rlp::decode(&hex_decode(raw_header).unwrap()).unwrap()
     }
 
+    fn get_epoch_acc(header: &Header) -> EpochAccumulator {
+        let master_acc = get_mainnet_master_acc();
+        let epoch_hash = master_acc
+            .epoch_root(header.number as usize / EPOCH_SIZE)
+            .unwrap();
+        let epoch_acc = fs::read(format!("./src/assets/epoch_accs/{epoch_hash}.bin")).unwrap();
+        EpochAccumulator::from_ssz_bytes(&epoch_acc).unwrap()
+    }
+
     fn generate_random_header(height: &u64) -> Header {
         Header {
             parent_hash: H256::random(),
@@ -394,7 +900,7 @@ mod test {
     async fn spawn_mock_epoch_acc_lookup(rx: &mut mpsc::UnboundedReceiver<HistoryJsonRpcRequest>) {
         match rx.recv().await {
             Some(request) => match request.endpoint {
-                HistoryEndpoint::RecursiveFindContent(content_key) => {
+                HistoryEndpoint::RecursiveFindContent(content_key, _) => {
                     let json_value = serde_json::to_value(content_key).unwrap();
                     let response = json_value.as_str().unwrap();
                     let epoch_acc_hash = response.trim_start_matches("0x03");
@@ -0,0 +1,225 @@
+use anyhow::anyhow;
+use ethereum_types::{H256, U256};
+use ssz_types::VariableList;
+use tree_hash::TreeHash;
+
+use crate::{
+    accumulator::{HistoricalEpochRoots, MasterAccumulator},
+    constants::{EPOCH_SIZE, MERGE_BLOCK_NUMBER},
+};
+use trin_types::execution::{
+    accumulator::{EpochAccumulator, HeaderRecord},
+    header::Header,
+};
+
+/// Builds the master accumulator from pre-merge headers, which must be added in order of block
+/// number.
+///
+/// Headers are folded into epoch accumulators of `EPOCH_SIZE` records, and the root of each
+/// complete epoch accumulator is appended to the historical epochs. Once the merge block has been
+/// added, the final and partial epoch is closed by `finish`.
+#[derive(Debug, Default)]
+pub struct AccumulatorBuilder {
+    historical_epochs: Vec<H256>,
+    current_epoch: Vec<HeaderRecord>,
+    total_difficulty: U256,
+    parent_hash: Option<H256>,
+}
+
+impl AccumulatorBuilder {
+    /// Creates a builder that starts from the genesis block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder that extends `master_acc`, which must only contain complete epochs.
+    /// `total_difficulty` is the total difficulty of the last block in the accumulator.
+    ///
+    /// The accumulator does not record the hash of its last block, so the parent hash of the
+    /// first header added is not checked.
+    pub fn from_master_accumulator(master_acc: &MasterAccumulator, total_difficulty: U256) -> Self {
+        Self {
+            historical_epochs: master_acc.historical_epochs().to_vec(),
+            current_epoch: vec![],
+            total_difficulty,
+            parent_hash: None,
+        }
+    }
+
+    /// Number of the next header to be added.
+    pub fn next_block_number(&self) -> u64 {
+        (self.historical_epochs.len() * EPOCH_SIZE + self.current_epoch.len()) as u64
+    }
+
+    /// Adds the next header to the accumulator, returning the epoch accumulator that the header
+    /// completes, if any.
+    pub fn add_header(&mut self, header: &Header) -> anyhow::Result<Option<EpochAccumulator>> {
+        let expected_number = self.next_block_number();
+        if header.number != expected_number {
+            return Err(anyhow!(
+                "Expected header for block #{expected_number}, found block #{}",
+                header.number
+            ));
+        }
+        if header.number > MERGE_BLOCK_NUMBER {
+            return Err(anyhow!(
+                "Block #{} is after the merge, and not part of the master accumulator",
+                header.number
+            ));
+        }
+        if let Some(parent_hash) = self.parent_hash {
+            if header.parent_hash != parent_hash {
+                return Err(anyhow!(
+                    "Block #{} is not a child of the previous header",
+                    header.number
+                ));
+            }
+        }
+
+        let block_hash = header.hash();
+        self.parent_hash = Some(block_hash);
+        self.total_difficulty += header.difficulty;
+        self.current_epoch.push(HeaderRecord {
+            block_hash,
+            total_difficulty: self.total_difficulty,
+        });
+        if self.current_epoch.len() < EPOCH_SIZE {
+            return Ok(None);
+        }
+        let epoch_acc = self.close_epoch()?;
+        Ok(Some(epoch_acc))
+    }
+
+    /// Returns the master accumulator of the complete epochs added so far, which can be extended
+    /// later with `from_master_accumulator`.
+    pub fn complete_epochs(&self) -> anyhow::Result<MasterAccumulator> {
+        let historical_epochs = HistoricalEpochRoots::new(self.historical_epochs.clone())
+            .map_err(|err| anyhow!("Too many historical epochs: {err:?}"))?;
+        Ok(MasterAccumulator::new(&historical_epochs))
+    }
+
+    /// Closes the current epoch, including the partial epoch of the merge block, and returns the
+    /// master accumulator.
+    pub fn finish(mut self) -> anyhow::Result<MasterAccumulator> {
+        if !self.current_epoch.is_empty() {
+            self.close_epoch()?;
+        }
+        let historical_epochs = HistoricalEpochRoots::new(self.historical_epochs)
+            .map_err(|err| anyhow!("Too many historical epochs: {err:?}"))?;
+        Ok(MasterAccumulator::new(&historical_epochs))
+    }
+
+    fn close_epoch(&mut self) -> anyhow::Result<EpochAccumulator> {
+        let records = std::mem::take(&mut self.current_epoch);
+        let epoch_acc: EpochAccumulator = VariableList::new(records)
+            .map_err(|err| anyhow!("Invalid epoch accumulator: {err:?}"))?;
+        self.historical_epochs.push(epoch_acc.tree_hash_root());
+        Ok(epoch_acc)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+    use ethereum_types::{Bloom, H160};
+
+    fn header(number: u64, parent_hash: H256) -> Header {
+        Header {
+            parent_hash,
+            uncles_hash: H256::random(),
+            author: H160::random(),
+            state_root: H256::random(),
+            transactions_root: H256::random(),
+            receipts_root: H256::random(),
+            logs_bloom: Bloom::zero(),
+            difficulty: U256::from(number + 1),
+            number,
+            gas_limit: U256::from(1),
+            gas_used: U256::from(1),
+            timestamp: number,
+            extra_data: vec![],
+            mix_hash: None,
+            nonce: None,
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+        }
+    }
+
+    /// Returns a chain of `count` headers starting from the genesis block.
+    fn chain(count: usize) -> Vec<Header> {
+        let mut headers: Vec<Header> = vec![];
+        for number in 0..count as u64 {
+            let parent_hash = headers.last().map(Header::hash).unwrap_or_default();
+            headers.push(header(number, parent_hash));
+        }
+        headers
+    }
+
+    #[test]
+    fn builds_complete_and_partial_epochs() {
+        let headers = chain(EPOCH_SIZE + 3);
+        let mut builder = AccumulatorBuilder::new();
+        let mut epoch_accs = vec![];
+        for header in headers.iter() {
+            if let Some(epoch_acc) = builder.add_header(header).unwrap() {
+                epoch_accs.push(epoch_acc);
+            }
+        }
+        assert_eq!(builder.next_block_number(), EPOCH_SIZE as u64 + 3);
+        let master_acc = builder.finish().unwrap();
+
+        assert_eq!(epoch_accs.len(), 1);
+        let epoch_acc = &epoch_accs[0];
+        assert_eq!(epoch_acc.len(), EPOCH_SIZE);
+        assert_eq!(epoch_acc[10].block_hash, headers[10].hash());
+        // The difficulty of block n is n + 1.
+        assert_eq!(epoch_acc[10].total_difficulty, U256::from(66));
+
+        assert_eq!(master_acc.epoch_count(), 2);
+        assert_eq!(master_acc.epoch_root(0), Some(epoch_acc.tree_hash_root()));
+        master_acc.validate_epoch_acc(epoch_acc).unwrap();
+    }
+
+    #[test]
+    fn extends_master_accumulator() {
+        let headers = chain(EPOCH_SIZE + 3);
+        let mut builder = AccumulatorBuilder::new();
+        for header in headers.iter() {
+            builder.add_header(header).unwrap();
+        }
+        let expected = builder.finish().unwrap();
+
+        let mut builder = AccumulatorBuilder::new();
+        let mut epoch_acc = None;
+        for header in headers[..EPOCH_SIZE].iter() {
+            epoch_acc = builder.add_header(header).unwrap();
+        }
+        let epoch_acc = epoch_acc.unwrap();
+        let total_difficulty = epoch_acc[EPOCH_SIZE - 1].total_difficulty;
+        builder.add_header(&headers[EPOCH_SIZE]).unwrap();
+        // The partial epoch is left out, so that the accumulator can be extended.
+        let master_acc = builder.complete_epochs().unwrap();
+        assert_eq!(master_acc.epoch_count(), 1);
+
+        let mut builder =
+            AccumulatorBuilder::from_master_accumulator(&master_acc, total_difficulty);
+        assert_eq!(builder.next_block_number(), EPOCH_SIZE as u64);
+        for header in headers[EPOCH_SIZE..].iter() {
+            builder.add_header(header).unwrap();
+        }
+        assert_eq!(builder.finish().unwrap(), expected);
+    }
+
+    #[test]
+    fn rejects_out_of_order_headers() {
+        let headers = chain(3);
+        let mut builder = AccumulatorBuilder::new();
+        builder.add_header(&headers[0]).unwrap();
+        assert!(builder.add_header(&headers[2]).is_err());
+
+        // A header at the next height that isn't a child of the last header.
+        assert!(builder.add_header(&header(1, H256::random())).is_err());
+        builder.add_header(&headers[1]).unwrap();
+    }
+}
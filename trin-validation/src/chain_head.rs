@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use ethereum_types::H256;
+use trin_types::content_key::EPHEMERAL_HEADER_COUNT;
+
+/// Hashes of the most recent canonical blocks, as verified by a consensus light client.
+///
+/// Only blocks within `EPHEMERAL_HEADER_COUNT` of the head are kept, since older headers are not
+/// ephemeral content.
+#[derive(Clone, Debug, Default)]
+pub struct ChainHead {
+    blocks: BTreeMap<u64, H256>,
+}
+
+impl ChainHead {
+    /// Returns the number of the most recent verified block, or `None` if no block has been
+    /// verified yet.
+    pub fn head_number(&self) -> Option<u64> {
+        self.blocks.keys().next_back().copied()
+    }
+
+    /// Returns the hashes of up to `count` of the most recent verified blocks, newest first.
+    pub fn recent_block_hashes(&self, count: usize) -> Vec<H256> {
+        self.blocks.values().rev().take(count).copied().collect()
+    }
+
+    /// Returns the hash of the verified block with the given number, if it is known.
+    pub fn block_hash(&self, block_number: u64) -> Option<H256> {
+        self.blocks.get(&block_number).copied()
+    }
+
+    /// Records a block verified by the light client. A block that replaces the head, e.g. after a
+    /// reorg, drops the blocks that were ahead of it.
+    pub fn insert(&mut self, block_number: u64, block_hash: H256) {
+        let oldest = block_number.saturating_sub(EPHEMERAL_HEADER_COUNT);
+        self.blocks
+            .retain(|number, _| (oldest..block_number).contains(number));
+        self.blocks.insert(block_number, block_hash);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_only_recent_blocks() {
+        let mut chain_head = ChainHead::default();
+        assert_eq!(chain_head.head_number(), None);
+
+        for block_number in 0..=2 * EPHEMERAL_HEADER_COUNT {
+            chain_head.insert(block_number, H256::from_low_u64_be(block_number));
+        }
+        assert_eq!(chain_head.head_number(), Some(2 * EPHEMERAL_HEADER_COUNT));
+        assert_eq!(
+            chain_head.block_hash(EPHEMERAL_HEADER_COUNT),
+            Some(H256::from_low_u64_be(EPHEMERAL_HEADER_COUNT))
+        );
+        assert_eq!(chain_head.block_hash(EPHEMERAL_HEADER_COUNT - 1), None);
+        assert_eq!(
+            chain_head.recent_block_hashes(2),
+            vec![
+                H256::from_low_u64_be(2 * EPHEMERAL_HEADER_COUNT),
+                H256::from_low_u64_be(2 * EPHEMERAL_HEADER_COUNT - 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn reorg_drops_blocks_ahead_of_the_new_head() {
+        let mut chain_head = ChainHead::default();
+        chain_head.insert(10, H256::from_low_u64_be(10));
+        chain_head.insert(11, H256::from_low_u64_be(11));
+        chain_head.insert(10, H256::from_low_u64_be(100));
+        assert_eq!(chain_head.head_number(), Some(10));
+        assert_eq!(chain_head.block_hash(10), Some(H256::from_low_u64_be(100)));
+        assert_eq!(chain_head.block_hash(11), None);
+    }
+}
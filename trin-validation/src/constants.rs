@@ -1,4 +1,10 @@
 pub const MERGE_BLOCK_NUMBER: u64 = 15_537_393u64;
+/// Timestamp of the first block of the Shanghai / Capella fork.
+pub const SHANGHAI_TIMESTAMP: u64 = 1_681_338_455;
+/// First slot of the Bellatrix fork (epoch 144896).
+pub const BELLATRIX_FORK_SLOT: u64 = 4_636_672;
+/// First slot of the Capella fork (epoch 194048).
+pub const CAPELLA_FORK_SLOT: u64 = 6_209_536;
 pub const DEFAULT_MASTER_ACC_HASH: &str =
     "0x8eac399e24480dce3cfe06f4bdecba51c6e5d0c46200e3e8611a0b44a3a69ff9";
 
@@ -1,5 +1,6 @@
 pub mod accumulator;
+pub mod accumulator_builder;
+pub mod chain_head;
 pub mod constants;
-pub mod merkle;
 pub mod oracle;
 pub mod validator;
@@ -1,2 +0,0 @@
-pub mod proof;
-pub mod safe_arith;
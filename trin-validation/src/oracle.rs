@@ -1,70 +1,124 @@
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use ethereum_types::H256;
 use serde_json::{json, Value};
 use ssz::Decode;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, RwLock};
 
-use crate::accumulator::MasterAccumulator;
-use trin_types::content_key::{BlockHeaderKey, HistoryContentKey};
+use crate::{accumulator::MasterAccumulator, chain_head::ChainHead, constants::MERGE_BLOCK_NUMBER};
+use trin_types::constants::CONTENT_ABSENT;
+use trin_types::content_key::{
+    BlockHeaderByNumberKey, BlockHeaderKey, HistoryContentKey, EPHEMERAL_HEADER_COUNT,
+};
 use trin_types::execution::header::{Header, HeaderWithProof};
 use trin_types::jsonrpc::endpoints::HistoryEndpoint;
 use trin_types::jsonrpc::params::Params;
 use trin_types::jsonrpc::request::HistoryJsonRpcRequest;
-use trin_types::provider::TrustedProvider;
+use trin_types::provider::{TrustedProvider, NO_TRUSTED_PROVIDER_ERROR};
 use trin_utils::bytes::hex_decode;
 
+/// Error returned when validating recent headers before the light client has verified a head.
+pub const NO_CHAIN_HEAD_ERROR: &str =
+    "No chain head has been verified by the light client: recent headers cannot be validated";
+
 /// Responsible for dispatching cross-overlay-network requests
-/// for data to perform validation. Currently, it just proxies these requests
-/// on to the trusted provider.
+/// for data to perform validation. Requests that the portal network cannot serve are proxied on
+/// to the trusted provider, if one is configured.
 #[derive(Clone, Debug)]
 pub struct HeaderOracle {
-    pub trusted_provider: TrustedProvider,
+    /// The trusted provider, or `None` if validation relies solely on the master accumulator and
+    /// the history network.
+    pub trusted_provider: Option<TrustedProvider>,
     // We could simply store the main portal jsonrpc tx channel here, rather than each
     // individual channel. But my sense is that this will be more useful in terms of
     // determining which subnetworks are actually available.
     pub history_jsonrpc_tx: Option<mpsc::UnboundedSender<HistoryJsonRpcRequest>>,
     pub master_acc: MasterAccumulator,
+    /// Recent blocks verified by the light client, shared by the nodes of the process. Empty if
+    /// no light client is running.
+    pub chain_head: Arc<RwLock<ChainHead>>,
 }
 
 impl HeaderOracle {
-    pub fn new(trusted_provider: TrustedProvider, master_acc: MasterAccumulator) -> Self {
+    pub fn new(trusted_provider: Option<TrustedProvider>, master_acc: MasterAccumulator) -> Self {
         Self {
             trusted_provider,
             history_jsonrpc_tx: None,
             master_acc,
+            chain_head: Arc::new(RwLock::new(ChainHead::default())),
         }
     }
 
     // Only serves pre-block hashes aka. portal-network verified data only
     pub async fn get_hash_at_height(&self, block_number: u64) -> anyhow::Result<H256> {
+        // check local storage for the header by number before resolving the hash through the
+        // epoch accumulator, which requires fetching the whole epoch accumulator. the network is
+        // not searched, so that a missing header doesn't delay the accumulator lookup.
+        let content_key =
+            HistoryContentKey::BlockHeaderByNumber(BlockHeaderByNumberKey { block_number });
+        if let Ok(Some(hwp)) = self.local_hwp(content_key).await {
+            if hwp.header.number == block_number
+                && self.master_acc.validate_header_with_proof(&hwp).is_ok()
+            {
+                return Ok(hwp.header.hash());
+            }
+        }
         self.master_acc
             .lookup_premerge_hash_by_number(block_number, self.history_jsonrpc_tx()?)
             .await
     }
 
     pub async fn get_header_by_hash(&self, block_hash: H256) -> anyhow::Result<Header> {
-        // try to find the header in the history subnetwork before falling back to infura
-        // this will check local storage before making a RFC request to the history subnetwork
-        if let Ok(hwp) = self.recursive_find_hwp(block_hash).await {
-            return Ok(hwp.header);
-        }
+        // try to find the header in the history subnetwork before falling back to the trusted
+        // provider. this will check local storage before making a RFC request to the history
+        // subnetwork
+        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
+            block_hash: block_hash.0,
+        });
+        let hwp_error = match self.recursive_find_hwp(content_key).await {
+            Ok(hwp) => return Ok(hwp.header),
+            Err(err) => err,
+        };
+        let trusted_provider = self.trusted_provider().map_err(|err| {
+            anyhow!("Header {block_hash:?} not found in the history network ({hwp_error}). {err}")
+        })?;
         let block_hash = format!("0x{block_hash:02X}");
         let method = "eth_getBlockByHash".to_string();
         let params = Params::Array(vec![json!(block_hash), json!(false)]);
-        let response: Value = self
-            .trusted_provider
-            .dispatch_http_request(method, params)?;
+        let response: Value = trusted_provider.dispatch_http_request(method, params)?;
         let header: Header = serde_json::from_value(response["result"].clone())?;
         Ok(header)
     }
 
-    /// Returns the HeaderWithProof for the given block hash by performing a recursive find content
-    /// request.
-    async fn recursive_find_hwp(&self, block_hash: H256) -> anyhow::Result<HeaderWithProof> {
-        let content_key = HistoryContentKey::BlockHeaderWithProof(BlockHeaderKey {
-            block_hash: block_hash.0,
-        });
-        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key);
+    /// Returns the HeaderWithProof for the given content key by performing a recursive find
+    /// content request.
+    async fn recursive_find_hwp(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> anyhow::Result<HeaderWithProof> {
+        let endpoint = HistoryEndpoint::RecursiveFindContent(content_key, false);
+        self.request_hwp(endpoint)
+            .await?
+            .ok_or_else(|| anyhow!("Header not found in the history network"))
+    }
+
+    /// Returns the HeaderWithProof for the given content key from local storage, or `None` if
+    /// it is not stored.
+    async fn local_hwp(
+        &self,
+        content_key: HistoryContentKey,
+    ) -> anyhow::Result<Option<HeaderWithProof>> {
+        let endpoint = HistoryEndpoint::LocalContent(content_key);
+        self.request_hwp(endpoint).await
+    }
+
+    /// Sends the request to the history subnetwork, and decodes the HeaderWithProof in its
+    /// response, or returns `None` if the content is absent.
+    async fn request_hwp(
+        &self,
+        endpoint: HistoryEndpoint,
+    ) -> anyhow::Result<Option<HeaderWithProof>> {
         let (resp, mut resp_rx) = mpsc::unbounded_channel::<Result<Value, String>>();
         let request = HistoryJsonRpcRequest { endpoint, resp };
         let tx = self.history_jsonrpc_tx()?;
@@ -79,8 +133,12 @@ impl HeaderOracle {
         let hwp_ssz = hwp_ssz
             .as_str()
             .ok_or_else(|| anyhow!("Invalid HWP format."))?;
+        if hwp_ssz == CONTENT_ABSENT {
+            return Ok(None);
+        }
         let hwp_ssz = hex_decode(hwp_ssz)?;
         HeaderWithProof::from_ssz_bytes(&hwp_ssz)
+            .map(Some)
             .map_err(|err| anyhow!("Invalid HWP received from chain history network: {err:?}"))
     }
 
@@ -93,9 +151,61 @@ impl HeaderOracle {
         }
     }
 
+    /// Returns the trusted provider, or an error if none is configured.
+    pub fn trusted_provider(&self) -> anyhow::Result<&TrustedProvider> {
+        self.trusted_provider
+            .as_ref()
+            .ok_or_else(|| anyhow!(NO_TRUSTED_PROVIDER_ERROR))
+    }
+
     pub fn validate_header_with_proof(&self, hwp: HeaderWithProof) -> anyhow::Result<()> {
         self.master_acc.validate_header_with_proof(&hwp)
     }
+
+    /// Validates a recent header near the chain tip, which cannot be proven against the master
+    /// accumulator. The header is valid if it is no more than `EPHEMERAL_HEADER_COUNT` blocks
+    /// behind the chain head verified by the light client, and the light client verified a block
+    /// with its number and hash. No network requests are made, so validating offered headers is
+    /// cheap.
+    ///
+    /// Until the light client has verified a head, recent headers cannot be validated and are
+    /// rejected.
+    pub async fn validate_ephemeral_header(&self, header: &Header) -> anyhow::Result<()> {
+        if header.number <= MERGE_BLOCK_NUMBER {
+            return Err(anyhow!(
+                "Pre-merge header #{} cannot be ephemeral content",
+                header.number
+            ));
+        }
+        let chain_head = self.chain_head.read().await;
+        let head_number = chain_head
+            .head_number()
+            .ok_or_else(|| anyhow!(NO_CHAIN_HEAD_ERROR))?;
+        if header.number + EPHEMERAL_HEADER_COUNT < head_number {
+            return Err(anyhow!(
+                "Header #{} is too far behind the chain head #{head_number}",
+                header.number
+            ));
+        }
+        if header.number > head_number {
+            return Err(anyhow!(
+                "Header #{} is ahead of the chain head #{head_number}",
+                header.number
+            ));
+        }
+        match chain_head.block_hash(header.number) {
+            Some(block_hash) if block_hash == header.hash() => Ok(()),
+            Some(block_hash) => Err(anyhow!(
+                "Header #{} doesn't match the light client: {:?}, expected {block_hash:?}",
+                header.number,
+                header.hash()
+            )),
+            None => Err(anyhow!(
+                "Header #{} was not verified by the light client",
+                header.number
+            )),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -104,7 +214,7 @@ mod test {
     use super::*;
     use std::str::FromStr;
 
-    use tree_hash::TreeHash;
+    use ethereum_types::{Bloom, H160, U256};
 
     use crate::constants::DEFAULT_MASTER_ACC_HASH;
     use trin_types::cli::TrinConfig;
@@ -112,7 +222,7 @@ mod test {
     #[tokio::test]
     async fn header_oracle_bootstraps_with_default_merge_master_acc() {
         let trin_config = TrinConfig::default();
-        let trusted_provider = TrustedProvider::from_trin_config(&trin_config);
+        let trusted_provider = TrustedProvider::from_trin_config(&trin_config).unwrap();
         let master_acc = MasterAccumulator::try_from_file(trin_config.master_acc_path).unwrap();
         let header_oracle = HeaderOracle::new(trusted_provider, master_acc);
         assert_eq!(
@@ -120,4 +230,108 @@ mod test {
             H256::from_str(DEFAULT_MASTER_ACC_HASH).unwrap(),
         );
     }
+
+    #[tokio::test]
+    async fn ephemeral_header_must_be_near_head() {
+        let trin_config = TrinConfig::default();
+        let trusted_provider = TrustedProvider::from_trin_config(&trin_config).unwrap();
+        let master_acc = MasterAccumulator::try_from_file(trin_config.master_acc_path).unwrap();
+        let header_oracle = HeaderOracle::new(trusted_provider, master_acc);
+
+        let error = header_oracle
+            .validate_ephemeral_header(&header(MERGE_BLOCK_NUMBER))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Pre-merge header"));
+
+        let head_number = MERGE_BLOCK_NUMBER + 2 * EPHEMERAL_HEADER_COUNT;
+        let head = header(head_number);
+        let error = header_oracle
+            .validate_ephemeral_header(&head)
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), NO_CHAIN_HEAD_ERROR);
+
+        header_oracle
+            .chain_head
+            .write()
+            .await
+            .insert(head_number, head.hash());
+        header_oracle
+            .validate_ephemeral_header(&head)
+            .await
+            .unwrap();
+
+        let error = header_oracle
+            .validate_ephemeral_header(&header(head_number))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("doesn't match the light client"));
+
+        let error = header_oracle
+            .validate_ephemeral_header(&header(head_number - 1))
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("not verified by the light client"));
+        let error = header_oracle
+            .validate_ephemeral_header(&header(head_number - EPHEMERAL_HEADER_COUNT - 1))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("too far behind the chain head"));
+
+        let error = header_oracle
+            .validate_ephemeral_header(&header(head_number + 1))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("ahead of the chain head"));
+    }
+
+    #[tokio::test]
+    async fn header_oracle_without_trusted_provider() {
+        let trin_config = TrinConfig::default();
+        let master_acc = MasterAccumulator::try_from_file(trin_config.master_acc_path).unwrap();
+        let header_oracle = HeaderOracle::new(None, master_acc);
+
+        let error = header_oracle
+            .get_header_by_hash(H256::random())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains(NO_TRUSTED_PROVIDER_ERROR));
+
+        // Recent headers are validated by the light client alone.
+        let head = header(MERGE_BLOCK_NUMBER + 1);
+        header_oracle
+            .chain_head
+            .write()
+            .await
+            .insert(head.number, head.hash());
+        header_oracle
+            .validate_ephemeral_header(&head)
+            .await
+            .unwrap();
+    }
+
+    fn header(number: u64) -> Header {
+        Header {
+            parent_hash: H256::random(),
+            uncles_hash: H256::random(),
+            author: H160::random(),
+            state_root: H256::random(),
+            transactions_root: H256::random(),
+            receipts_root: H256::random(),
+            logs_bloom: Bloom::zero(),
+            difficulty: U256::zero(),
+            number,
+            gas_limit: U256::from(30_000_000),
+            gas_used: U256::zero(),
+            timestamp: 1,
+            extra_data: vec![],
+            mix_hash: None,
+            nonce: None,
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+        }
+    }
 }
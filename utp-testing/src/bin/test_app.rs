@@ -10,7 +10,7 @@ use utp_testing::cli::TestAppConfig;
 /// uTP test app, used for creation of a `test-app` docker image
 #[tokio::main]
 async fn main() {
-    init_tracing_logger();
+    init_tracing_logger(None);
 
     let config = TestAppConfig::from_args();
 
@@ -21,6 +21,7 @@ async fn main() {
         udp_listen_address,
         config.rpc_listen_address,
         config.rpc_port,
+        config.echo,
     )
     .await
     .unwrap();
@@ -0,0 +1,55 @@
+use jsonrpsee::http_client::HttpClientBuilder;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use structopt::StructOpt;
+use trin_utils::log::init_tracing_logger;
+use utp_testing::cli::ScenarioConfig;
+use utp_testing::run_test_app;
+use utp_testing::scenario::{run_scenario, Scenario};
+
+/// Runs a scripted scenario from a local test app against a remote test app, reporting the
+/// outcome of each step
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_tracing_logger(None);
+
+    let config = ScenarioConfig::from_args();
+    let scenario = Scenario::from_file(&config.scenario)?;
+
+    let udp_listen_address = format!("{}:{}", config.udp_listen_address, config.udp_port);
+    let udp_listen_address = SocketAddr::from_str(&udp_listen_address)?;
+    let (local_rpc_addr, _enr, _handle) = run_test_app(
+        config.udp_port,
+        udp_listen_address,
+        "127.0.0.1".to_string(),
+        config.rpc_port,
+        false,
+    )
+    .await?;
+
+    let local = HttpClientBuilder::default().build(format!("http://{local_rpc_addr}"))?;
+    let remote = HttpClientBuilder::default().build(&config.remote_rpc_url)?;
+
+    let results = run_scenario(scenario, &local, &remote).await?;
+
+    let mut failures = 0;
+    for (i, step) in results.iter().enumerate() {
+        match &step.result {
+            Ok(()) => println!("step {}: {}: PASS", i + 1, step.step),
+            Err(err) => {
+                failures += 1;
+                println!("step {}: {}: FAIL ({err})", i + 1, step.step);
+            }
+        }
+    }
+    println!(
+        "{} of {} steps passed",
+        results.len() - failures,
+        results.len()
+    );
+
+    if failures > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -6,6 +6,9 @@ use rand::{thread_rng, Rng};
 use std::time::Duration;
 
 use trin_utils::bytes::hex_encode;
+use utp_testing::payload::{PayloadCheck, PayloadSpec};
+use utp_testing::stats::BenchmarkResult;
+use utp_testing::ECHO_CID_OFFSET;
 
 const SERVER_ADDR: &str = "193.167.100.100:9041";
 const CLIENT_ADDR: &str = "193.167.0.100:9042";
@@ -14,6 +17,9 @@ const CLIENT_ADDR: &str = "193.167.0.100:9042";
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     send_10k_bytes().await?;
+    echo_10k_bytes().await?;
+    send_generated_8mb_in_chunks().await?;
+    benchmark_1mb_transfer().await?;
 
     Ok(())
 }
@@ -70,3 +76,143 @@ async fn send_10k_bytes() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Send 10k bytes payload from client to server, which runs in echo mode, and verify that the
+/// same payload is streamed back to the client
+async fn echo_10k_bytes() -> anyhow::Result<()> {
+    println!("Echoing 10k bytes uTP payload from client through server...");
+    let client_url = format!("http://{CLIENT_ADDR}");
+    let client_rpc = HttpClientBuilder::default().build(client_url)?;
+    let client_enr: String = client_rpc.request("local_enr", None).await.unwrap();
+
+    let server_url = format!("http://{SERVER_ADDR}");
+    let server_rpc = HttpClientBuilder::default().build(server_url)?;
+    let server_enr: String = server_rpc.request("local_enr", None).await.unwrap();
+
+    let client_cid_recv: u16 = thread_rng().gen();
+    let client_cid_send = client_cid_recv.wrapping_add(1);
+
+    // The server echoes over a connection with both IDs offset by `ECHO_CID_OFFSET`.
+    let params = rpc_params!(
+        server_enr.clone(),
+        client_cid_send.wrapping_add(ECHO_CID_OFFSET),
+        client_cid_recv.wrapping_add(ECHO_CID_OFFSET)
+    );
+    let response: String = client_rpc.request("prepare_to_recv", params).await.unwrap();
+    assert_eq!(response, "true");
+
+    let params = rpc_params!(client_enr, client_cid_recv, client_cid_send);
+    let response: String = server_rpc.request("prepare_to_recv", params).await.unwrap();
+    assert_eq!(response, "true");
+
+    let payload: Vec<u8> = (0..10_000).map(|_| thread_rng().gen()).collect();
+
+    let params = rpc_params!(
+        server_enr,
+        client_cid_send,
+        client_cid_recv,
+        payload.clone()
+    );
+    let response: String = client_rpc
+        .request("send_utp_payload", params)
+        .await
+        .unwrap();
+    assert_eq!(response, "true");
+
+    // Sleep to allow time for uTP transmission in both directions
+    tokio::time::sleep(Duration::from_secs(32)).await;
+
+    // Verify that the payload made it to the server and back unchanged
+    let expected_payload = hex_encode(payload);
+    let server_payload: String = server_rpc.request("get_utp_payload", None).await.unwrap();
+    assert_eq!(expected_payload, server_payload);
+    let echoed_payload: String = client_rpc.request("get_utp_payload", None).await.unwrap();
+    assert_eq!(expected_payload, echoed_payload);
+
+    println!("Echoed 10k bytes uTP payload from client through server: OK");
+
+    Ok(())
+}
+
+/// Send an 8 MiB payload, generated from a seed on both ends, from client to server in 64 KiB
+/// writes
+async fn send_generated_8mb_in_chunks() -> anyhow::Result<()> {
+    println!("Sending 8 MiB generated uTP payload from client to server in chunks...");
+    let client_url = format!("http://{CLIENT_ADDR}");
+    let client_rpc = HttpClientBuilder::default().build(client_url)?;
+    let client_enr: String = client_rpc.request("local_enr", None).await.unwrap();
+
+    let server_url = format!("http://{SERVER_ADDR}");
+    let server_rpc = HttpClientBuilder::default().build(server_url)?;
+    let server_enr: String = server_rpc.request("local_enr", None).await.unwrap();
+
+    let client_cid_recv: u16 = thread_rng().gen();
+    let client_cid_send = client_cid_recv.wrapping_add(1);
+
+    let params = rpc_params!(client_enr, client_cid_recv, client_cid_send);
+    let response: String = server_rpc.request("prepare_to_recv", params).await.unwrap();
+    assert_eq!(response, "true");
+
+    let spec = PayloadSpec {
+        length: 8 * 1024 * 1024,
+        seed: thread_rng().gen(),
+        chunk_size: Some(64 * 1024),
+    };
+    let params = rpc_params!(server_enr, client_cid_send, client_cid_recv, spec);
+    let response: String = client_rpc
+        .request("send_generated_utp_payload", params)
+        .await
+        .unwrap();
+    assert_eq!(response, "true");
+
+    // Sleep to allow time for uTP transmission
+    tokio::time::sleep(Duration::from_secs(64)).await;
+
+    let check: PayloadCheck = server_rpc
+        .request("check_utp_payload", rpc_params!(spec))
+        .await
+        .unwrap();
+    assert_eq!(check.received_length, Some(spec.length));
+    assert!(check.matches);
+
+    println!("Sent 8 MiB generated uTP payload from client to server in chunks: OK");
+
+    Ok(())
+}
+
+/// Send 1 MB payload from client to server and report the measured transfer statistics
+async fn benchmark_1mb_transfer() -> anyhow::Result<()> {
+    println!("Benchmarking 1 MB uTP transfer from client to server...");
+    let client_url = format!("http://{CLIENT_ADDR}");
+    let client_rpc = HttpClientBuilder::default().build(client_url)?;
+
+    let server_url = format!("http://{SERVER_ADDR}");
+    let server_rpc = HttpClientBuilder::default().build(server_url)?;
+    let server_enr: String = server_rpc.request("local_enr", None).await.unwrap();
+    let client_enr: String = client_rpc.request("local_enr", None).await.unwrap();
+
+    let client_cid_recv: u16 = thread_rng().gen();
+    let client_cid_send = client_cid_recv.wrapping_add(1);
+
+    let params = rpc_params!(client_enr, client_cid_recv, client_cid_send);
+    let response: String = server_rpc.request("prepare_to_recv", params).await.unwrap();
+    assert_eq!(response, "true");
+
+    let params = rpc_params!(server_enr, client_cid_send, client_cid_recv, 1_000_000);
+    let result: BenchmarkResult = client_rpc
+        .request("benchmark_utp_transfer", params)
+        .await
+        .unwrap();
+
+    assert_eq!(result.bytes_sent, 1_000_000);
+
+    println!(
+        "Benchmarked 1 MB uTP transfer: {} ms, {:.0} bytes/s goodput, {} data packets, {} retransmissions",
+        result.duration_ms,
+        result.goodput_bytes_per_sec,
+        result.data_packets_sent,
+        result.retransmissions
+    );
+
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 #[derive(Debug, StructOpt)]
@@ -14,4 +15,29 @@ pub struct TestAppConfig {
 
     #[structopt(long, required = true)]
     pub rpc_port: u16,
+
+    /// Stream every received payload back to its sender over a new outbound connection
+    #[structopt(long)]
+    pub echo: bool,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "utP scenario client CLI")]
+pub struct ScenarioConfig {
+    /// JSON file with the scenario steps
+    #[structopt(long, required = true)]
+    pub scenario: PathBuf,
+
+    /// JSON-RPC URL of the remote test app
+    #[structopt(long, required = true)]
+    pub remote_rpc_url: String,
+
+    #[structopt(long, required = true)]
+    pub udp_listen_address: String,
+
+    #[structopt(long, required = true)]
+    pub udp_port: u16,
+
+    #[structopt(long, default_value = "9043")]
+    pub rpc_port: u16,
 }
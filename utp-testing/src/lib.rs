@@ -1,31 +1,44 @@
 extern crate core;
 
 pub mod cli;
+pub mod payload;
 pub mod rpc;
+pub mod scenario;
+pub mod stats;
 
+use crate::payload::{PayloadCheck, PayloadSpec, MAX_PAYLOAD_SIZE};
 use crate::rpc::RpcServer;
+use crate::stats::{BenchmarkResult, CountingUdpSocket, PacketCounter};
 use discv5::TalkRequest;
 use jsonrpsee::core::{async_trait, RpcResult};
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::proc_macros::rpc;
 use portalnet::discovery::{Discovery, UtpEnr};
 use portalnet::types::messages::{PortalnetConfig, ProtocolId};
+use rand::{thread_rng, RngCore};
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::sync::RwLock;
 use trin_types::enr::Enr;
 use trin_utils::bytes::{hex_encode, hex_encode_upper};
 use utp_rs::{conn::ConnectionConfig, socket::UtpSocket};
 
+/// Offset added to both IDs of an inbound connection to get the IDs of the connection that
+/// echoes its payload back. The sender must prepare to receive on the offset IDs.
+pub const ECHO_CID_OFFSET: u16 = 2;
+
 /// uTP test app
 pub struct TestApp {
     pub discovery: Arc<Discovery>,
     pub utp_socket: Arc<UtpSocket<UtpEnr>>,
     pub utp_talk_req_tx: mpsc::UnboundedSender<TalkRequest>,
     pub utp_payload: Arc<RwLock<Vec<Vec<u8>>>>,
+    pub packet_counter: Arc<PacketCounter>,
+    /// Whether received payloads are sent back to their sender.
+    pub echo: bool,
 }
 
 #[async_trait]
@@ -60,6 +73,7 @@ impl RpcServer for TestApp {
 
         let utp = Arc::clone(&self.utp_socket);
         let payload_store = Arc::clone(&self.utp_payload);
+        let echo = self.echo;
         tokio::spawn(async move {
             let utp_config = ConnectionConfig {
                 max_packet_size: 1024,
@@ -68,7 +82,7 @@ impl RpcServer for TestApp {
                 initial_timeout: Duration::from_millis(1250),
                 ..Default::default()
             };
-            let mut conn = utp.accept_with_cid(cid, utp_config).await.unwrap();
+            let mut conn = utp.accept_with_cid(cid.clone(), utp_config).await.unwrap();
             let mut data = vec![];
             let n = conn.read_to_eof(&mut data).await.unwrap();
 
@@ -76,7 +90,25 @@ impl RpcServer for TestApp {
 
             conn.shutdown().unwrap();
 
-            payload_store.write().await.push(data);
+            payload_store.write().await.push(data.clone());
+
+            if echo {
+                let echo_cid = utp_rs::cid::ConnectionId {
+                    send: cid.send.wrapping_add(ECHO_CID_OFFSET),
+                    recv: cid.recv.wrapping_add(ECHO_CID_OFFSET),
+                    peer: cid.peer,
+                };
+                match utp.connect_with_cid(echo_cid, utp_config).await {
+                    Ok(mut conn) => {
+                        match conn.write(&data).await {
+                            Ok(n) => tracing::info!("echoed {n} bytes over uTP stream"),
+                            Err(err) => tracing::warn!(%err, "unable to echo uTP payload"),
+                        }
+                        let _ = conn.shutdown();
+                    }
+                    Err(err) => tracing::warn!(%err, "unable to open uTP echo connection"),
+                }
+            }
         });
 
         Ok("true".to_string())
@@ -115,6 +147,139 @@ impl RpcServer for TestApp {
 
         Ok("true".to_string())
     }
+
+    async fn send_generated_utp_payload(
+        &self,
+        dst_enr: String,
+        cid_send: u16,
+        cid_recv: u16,
+        spec: PayloadSpec,
+    ) -> RpcResult<String> {
+        spec.validate().map_err(jsonrpsee::core::Error::Custom)?;
+        let dst_enr = Enr::from_str(&dst_enr).unwrap();
+        let cid = utp_rs::cid::ConnectionId {
+            send: cid_send,
+            recv: cid_recv,
+            peer: UtpEnr(dst_enr.clone()),
+        };
+        self.discovery.add_enr(dst_enr).unwrap();
+
+        let utp = Arc::clone(&self.utp_socket);
+        let utp_config = ConnectionConfig {
+            max_packet_size: 1024,
+            max_conn_attempts: 3,
+            max_idle_timeout: Duration::from_secs(16),
+            initial_timeout: Duration::from_millis(1250),
+            ..Default::default()
+        };
+        tokio::spawn(async move {
+            let payload = spec.generate();
+            let mut conn = match utp.connect_with_cid(cid, utp_config).await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    tracing::warn!(%err, "unable to open uTP connection");
+                    return;
+                }
+            };
+            for chunk in spec.chunks(&payload) {
+                if let Err(err) = conn.write(chunk).await {
+                    tracing::warn!(%err, "unable to write uTP payload");
+                    break;
+                }
+            }
+            tracing::info!(
+                length = spec.length,
+                chunk_size = ?spec.chunk_size,
+                "sent generated uTP payload"
+            );
+            let _ = conn.shutdown();
+        });
+
+        Ok("true".to_string())
+    }
+
+    async fn check_utp_payload(&self, spec: PayloadSpec) -> RpcResult<PayloadCheck> {
+        let utp_payload = self.utp_payload.read().await;
+        let check = match utp_payload.last() {
+            Some(payload) => PayloadCheck {
+                matches: payload.len() == spec.length && *payload == spec.generate(),
+                received_length: Some(payload.len()),
+            },
+            None => PayloadCheck {
+                matches: false,
+                received_length: None,
+            },
+        };
+        Ok(check)
+    }
+
+    async fn benchmark_utp_transfer(
+        &self,
+        dst_enr: String,
+        cid_send: u16,
+        cid_recv: u16,
+        payload_size: usize,
+    ) -> RpcResult<BenchmarkResult> {
+        if payload_size == 0 || payload_size > MAX_PAYLOAD_SIZE {
+            return Err(jsonrpsee::core::Error::Custom(format!(
+                "Payload size must be between 1 and {MAX_PAYLOAD_SIZE} bytes"
+            )));
+        }
+        let dst_enr = Enr::from_str(&dst_enr)
+            .map_err(|err| jsonrpsee::core::Error::Custom(format!("Invalid ENR: {err}")))?;
+        let peer = UtpEnr(dst_enr.clone());
+        let cid = utp_rs::cid::ConnectionId {
+            send: cid_send,
+            recv: cid_recv,
+            peer: peer.clone(),
+        };
+        self.discovery
+            .add_enr(dst_enr)
+            .map_err(|err| jsonrpsee::core::Error::Custom(format!("Unable to add ENR: {err}")))?;
+
+        let mut payload = vec![0u8; payload_size];
+        thread_rng().fill_bytes(&mut payload);
+
+        let utp_config = ConnectionConfig {
+            max_packet_size: 1024,
+            max_conn_attempts: 3,
+            max_idle_timeout: Duration::from_secs(16),
+            initial_timeout: Duration::from_millis(1250),
+            ..Default::default()
+        };
+
+        // The transfer is timed from the connection attempt until the whole payload has been
+        // written to the connection.
+        let start = Instant::now();
+        let mut conn = self
+            .utp_socket
+            .connect_with_cid(cid, utp_config)
+            .await
+            .map_err(|err| jsonrpsee::core::Error::Custom(format!("Unable to connect: {err}")))?;
+        let bytes_sent = conn
+            .write(&payload)
+            .await
+            .map_err(|err| jsonrpsee::core::Error::Custom(format!("Unable to write: {err}")))?;
+        let duration = start.elapsed();
+        conn.shutdown()
+            .map_err(|err| jsonrpsee::core::Error::Custom(format!("Unable to shutdown: {err}")))?;
+
+        let counts = self.packet_counter.take(&peer, cid_send);
+        tracing::info!(
+            bytes_sent,
+            ?duration,
+            retransmissions = counts.retransmissions,
+            "uTP benchmark transfer complete"
+        );
+
+        Ok(BenchmarkResult {
+            bytes_sent,
+            duration_ms: duration.as_millis() as u64,
+            goodput_bytes_per_sec: bytes_sent as f64 / duration.as_secs_f64(),
+            data_packets_sent: counts.data_packets,
+            retransmissions: counts.retransmissions,
+        })
+    }
 }
 
 impl TestApp {
@@ -141,6 +306,7 @@ pub async fn run_test_app(
     socket_addr: SocketAddr,
     rpc_addr: String,
     rpc_port: u16,
+    echo: bool,
 ) -> anyhow::Result<(SocketAddr, Enr, HttpServerHandle)> {
     let config = PortalnetConfig {
         listen_port: udp_port,
@@ -156,7 +322,10 @@ pub async fn run_test_app(
     let (utp_talk_req_tx, utp_talk_req_rx) = mpsc::unbounded_channel();
     let discv5_utp_socket =
         portalnet::discovery::Discv5UdpSocket::new(Arc::clone(&discovery), utp_talk_req_rx);
-    let utp_socket = utp_rs::socket::UtpSocket::with_socket(discv5_utp_socket);
+    let packet_counter = Arc::new(PacketCounter::default());
+    let counting_utp_socket =
+        CountingUdpSocket::new(discv5_utp_socket, Arc::clone(&packet_counter));
+    let utp_socket = utp_rs::socket::UtpSocket::with_socket(counting_utp_socket);
     let utp_socket = Arc::new(utp_socket);
 
     let test_app = TestApp {
@@ -164,6 +333,8 @@ pub async fn run_test_app(
         utp_socket,
         utp_talk_req_tx,
         utp_payload: Arc::new(RwLock::new(Vec::new())),
+        packet_counter,
+        echo,
     };
 
     test_app.start(talk_req_rx).await;
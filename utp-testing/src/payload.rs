@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+/// Largest payload that the test app generates or sends. Keeps transfers well below the number
+/// of packets after which uTP sequence numbers wrap.
+pub const MAX_PAYLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+/// A payload that is generated from a seed, so that the sender and the receiver of a transfer can
+/// both produce it without the payload itself crossing the RPC interface.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadSpec {
+    /// Length of the payload in bytes.
+    pub length: usize,
+    /// Seed of the generated content.
+    pub seed: u64,
+    /// Size of each write to the uTP stream. The payload is written at once if not set.
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+}
+
+impl PayloadSpec {
+    /// Returns an error if the payload is empty or too large, or if the chunk size is zero.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.length == 0 || self.length > MAX_PAYLOAD_SIZE {
+            return Err(format!(
+                "Payload length must be between 1 and {MAX_PAYLOAD_SIZE} bytes"
+            ));
+        }
+        if self.chunk_size == Some(0) {
+            return Err("Chunk size must be at least 1 byte".to_string());
+        }
+        Ok(())
+    }
+
+    /// Generates the payload.
+    ///
+    /// The payload is the little-endian output of the SplitMix64 generator seeded with `seed`,
+    /// truncated to `length` bytes. The generator is simple enough to reimplement in the test
+    /// apps of other clients.
+    pub fn generate(&self) -> Vec<u8> {
+        let mut state = self.seed;
+        let mut payload = Vec::with_capacity(self.length + 8);
+        while payload.len() < self.length {
+            payload.extend_from_slice(&splitmix64(&mut state).to_le_bytes());
+        }
+        payload.truncate(self.length);
+        payload
+    }
+
+    /// Returns the writes in which the payload is sent.
+    pub fn chunks<'a>(&self, payload: &'a [u8]) -> std::slice::Chunks<'a, u8> {
+        payload.chunks(self.chunk_size.unwrap_or(payload.len()).max(1))
+    }
+}
+
+/// Advances the SplitMix64 generator and returns its next output.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Outcome of checking a received payload against a `PayloadSpec`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PayloadCheck {
+    /// Whether the last received payload is the one described by the spec.
+    pub matches: bool,
+    /// Length of the last received payload, if any payload was received.
+    pub received_length: Option<usize>,
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use super::*;
+
+    fn spec(length: usize, seed: u64, chunk_size: Option<usize>) -> PayloadSpec {
+        PayloadSpec {
+            length,
+            seed,
+            chunk_size,
+        }
+    }
+
+    #[test]
+    fn generate_is_deterministic() {
+        let payload = spec(1000, 7, None).generate();
+        assert_eq!(payload, spec(1000, 7, None).generate());
+        assert_ne!(payload, spec(1000, 8, None).generate());
+        // A shorter payload with the same seed is a prefix of the longer one.
+        assert_eq!(spec(13, 7, None).generate(), payload[..13]);
+    }
+
+    #[test]
+    fn generate_matches_splitmix64_reference() {
+        let payload = spec(24, 0, None).generate();
+        let outputs: Vec<u64> = payload
+            .chunks(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            outputs,
+            vec![0xe220a8397b1dcdaf, 0x6e789e6aa1b965f4, 0x06c45d188009454f]
+        );
+    }
+
+    #[test]
+    fn generate_has_requested_length() {
+        for length in [1, 7, 8, 9, 1000] {
+            assert_eq!(spec(length, 1, None).generate().len(), length);
+        }
+    }
+
+    #[test]
+    fn chunks_cover_payload() {
+        let payload = spec(10, 1, Some(4)).generate();
+        let chunks: Vec<&[u8]> = spec(10, 1, Some(4)).chunks(&payload).collect();
+        assert_eq!(
+            chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+        assert_eq!(chunks.concat(), payload);
+
+        // Without a chunk size, the payload is written at once.
+        assert_eq!(spec(10, 1, None).chunks(&payload).count(), 1);
+        // A chunk size larger than the payload also writes it at once.
+        assert_eq!(spec(10, 1, Some(100)).chunks(&payload).count(), 1);
+    }
+
+    #[test]
+    fn validate() {
+        assert!(spec(1, 0, None).validate().is_ok());
+        assert!(spec(MAX_PAYLOAD_SIZE, 0, Some(1)).validate().is_ok());
+        assert!(spec(0, 0, None).validate().is_err());
+        assert!(spec(MAX_PAYLOAD_SIZE + 1, 0, None).validate().is_err());
+        assert!(spec(10, 0, Some(0)).validate().is_err());
+    }
+
+    #[test]
+    fn spec_json() {
+        let parsed: PayloadSpec = serde_json::from_str(r#"{"length": 10, "seed": 3}"#).unwrap();
+        assert_eq!(parsed, spec(10, 3, None));
+        let json = serde_json::to_value(spec(10, 3, Some(4))).unwrap();
+        assert_eq!(json["chunkSize"], 4);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::payload::{PayloadCheck, PayloadSpec};
+use crate::stats::BenchmarkResult;
 use crate::{rpc, RpcResult};
 
 /// JSON-RPC endpoint for client and server
@@ -21,4 +23,31 @@ pub trait Rpc {
         cid_recv: u16,
         payload: Vec<u8>,
     ) -> RpcResult<String>;
+
+    /// Sends the payload generated from `spec` to `enr`, in writes of `spec.chunk_size` bytes.
+    /// The receiving test app must have been prepared with `prepare_to_recv`.
+    #[method(name = "send_generated_utp_payload")]
+    async fn send_generated_utp_payload(
+        &self,
+        enr: String,
+        cid_send: u16,
+        cid_recv: u16,
+        spec: PayloadSpec,
+    ) -> RpcResult<String>;
+
+    /// Checks the last received payload against the payload generated from `spec`, so that large
+    /// payloads need not be returned over RPC.
+    #[method(name = "check_utp_payload")]
+    async fn check_utp_payload(&self, spec: PayloadSpec) -> RpcResult<PayloadCheck>;
+
+    /// Sends `payload_size` random bytes to `enr` and waits for the transfer to complete. The
+    /// receiving test app must have been prepared with `prepare_to_recv`.
+    #[method(name = "benchmark_utp_transfer")]
+    async fn benchmark_utp_transfer(
+        &self,
+        enr: String,
+        cid_send: u16,
+        cid_recv: u16,
+        payload_size: usize,
+    ) -> RpcResult<BenchmarkResult>;
 }
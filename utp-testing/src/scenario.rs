@@ -0,0 +1,234 @@
+use crate::payload::PayloadSpec;
+use crate::rpc::RpcClient;
+use crate::ECHO_CID_OFFSET;
+use anyhow::{anyhow, ensure};
+use jsonrpsee::http_client::HttpClient;
+use rand::{thread_rng, Rng};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+use trin_utils::bytes::hex_encode;
+
+/// A sequence of steps executed against a remote test app.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    pub steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Reads a scenario from a JSON file.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Step {
+    /// Prepares the remote test app to accept a new connection from the local test app. If
+    /// `echo` is set, the local test app is also prepared to receive the remote's echo.
+    Connect {
+        connection: String,
+        #[serde(default)]
+        echo: bool,
+    },
+    /// Sends `size` bytes generated from `seed` over a prepared connection, in writes of
+    /// `chunk_size` bytes. The seed is random if not set, and the payload is written at once if
+    /// the chunk size is not set.
+    Send {
+        connection: String,
+        size: usize,
+        #[serde(default)]
+        seed: Option<u64>,
+        #[serde(default)]
+        chunk_size: Option<usize>,
+    },
+    Delay {
+        millis: u64,
+    },
+    /// Checks that the last payload received by the remote test app is the one sent over
+    /// `connection`.
+    ExpectReceive {
+        connection: String,
+    },
+    /// Checks that the last payload received by the local test app is the echo of the one sent
+    /// over `connection`.
+    ExpectEcho {
+        connection: String,
+    },
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Connect { connection, echo } => {
+                write!(f, "connect {connection}")?;
+                if *echo {
+                    write!(f, " (echo)")?;
+                }
+                Ok(())
+            }
+            Step::Send {
+                connection,
+                size,
+                chunk_size,
+                ..
+            } => {
+                write!(f, "send {size} bytes over {connection}")?;
+                if let Some(chunk_size) = chunk_size {
+                    write!(f, " in chunks of {chunk_size} bytes")?;
+                }
+                Ok(())
+            }
+            Step::Delay { millis } => write!(f, "delay {millis} ms"),
+            Step::ExpectReceive { connection } => write!(f, "expect receive over {connection}"),
+            Step::ExpectEcho { connection } => write!(f, "expect echo over {connection}"),
+        }
+    }
+}
+
+/// Outcome of a single scenario step.
+#[derive(Debug)]
+pub struct StepResult {
+    pub step: Step,
+    pub result: anyhow::Result<()>,
+}
+
+struct Connection {
+    cid_send: u16,
+    cid_recv: u16,
+    payload: Option<PayloadSpec>,
+}
+
+/// Executes a scenario between the test app at `local` and the one at `remote`.
+///
+/// Every step is executed even if an earlier step failed, so that a run reports the outcome of
+/// the whole scenario.
+pub async fn run_scenario(
+    scenario: Scenario,
+    local: &HttpClient,
+    remote: &HttpClient,
+) -> anyhow::Result<Vec<StepResult>> {
+    let local_enr = local.local_enr().await?;
+    let remote_enr = remote.local_enr().await?;
+    let mut connections: HashMap<String, Connection> = HashMap::new();
+
+    let mut results = vec![];
+    for step in scenario.steps {
+        let result = match &step {
+            Step::Connect { connection, echo } => {
+                let cid_recv: u16 = thread_rng().gen();
+                let cid_send = cid_recv.wrapping_add(1);
+                connections.insert(
+                    connection.clone(),
+                    Connection {
+                        cid_send,
+                        cid_recv,
+                        payload: None,
+                    },
+                );
+                connect(
+                    local,
+                    remote,
+                    &local_enr,
+                    &remote_enr,
+                    cid_send,
+                    cid_recv,
+                    *echo,
+                )
+                .await
+            }
+            Step::Send {
+                connection,
+                size,
+                seed,
+                chunk_size,
+            } => match connections.get_mut(connection) {
+                Some(conn) => {
+                    let spec = PayloadSpec {
+                        length: *size,
+                        seed: seed.unwrap_or_else(|| thread_rng().gen()),
+                        chunk_size: *chunk_size,
+                    };
+                    conn.payload = Some(spec);
+                    send(local, &remote_enr, conn.cid_send, conn.cid_recv, spec).await
+                }
+                None => Err(anyhow!("Unknown connection {connection}")),
+            },
+            Step::Delay { millis } => {
+                tokio::time::sleep(Duration::from_millis(*millis)).await;
+                Ok(())
+            }
+            Step::ExpectReceive { connection } => {
+                expect_payload(remote, connections.get(connection)).await
+            }
+            Step::ExpectEcho { connection } => {
+                expect_payload(local, connections.get(connection)).await
+            }
+        };
+        results.push(StepResult { step, result });
+    }
+
+    Ok(results)
+}
+
+async fn connect(
+    local: &HttpClient,
+    remote: &HttpClient,
+    local_enr: &str,
+    remote_enr: &str,
+    cid_send: u16,
+    cid_recv: u16,
+    echo: bool,
+) -> anyhow::Result<()> {
+    if echo {
+        let response = local
+            .prepare_to_recv(
+                remote_enr.to_string(),
+                cid_send.wrapping_add(ECHO_CID_OFFSET),
+                cid_recv.wrapping_add(ECHO_CID_OFFSET),
+            )
+            .await?;
+        ensure!(response == "true", "Local test app refused echo connection");
+    }
+    // The remote connection ID is the flipped local connection ID.
+    let response = remote
+        .prepare_to_recv(local_enr.to_string(), cid_recv, cid_send)
+        .await?;
+    ensure!(response == "true", "Remote test app refused connection");
+    Ok(())
+}
+
+async fn send(
+    local: &HttpClient,
+    remote_enr: &str,
+    cid_send: u16,
+    cid_recv: u16,
+    spec: PayloadSpec,
+) -> anyhow::Result<()> {
+    let response = local
+        .send_generated_utp_payload(remote_enr.to_string(), cid_send, cid_recv, spec)
+        .await?;
+    ensure!(response == "true", "Local test app refused to send payload");
+    Ok(())
+}
+
+/// Checks the payload received by `client` with `get_utp_payload`, which is also implemented by
+/// the test apps of other clients.
+async fn expect_payload(
+    client: &HttpClient,
+    connection: Option<&Connection>,
+) -> anyhow::Result<()> {
+    let expected = connection
+        .and_then(|conn| conn.payload.as_ref())
+        .ok_or_else(|| anyhow!("Nothing was sent over the connection"))?;
+    let received = client.get_utp_payload().await?;
+    ensure!(
+        received == hex_encode(expected.generate()),
+        "Received payload does not match the sent payload"
+    );
+    Ok(())
+}
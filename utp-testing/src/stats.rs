@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use portalnet::discovery::UtpEnr;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::{Arc, Mutex};
+use utp_rs::udp::AsyncUdpSocket;
+
+/// uTP packet type of a DATA packet.
+const ST_DATA: u8 = 0;
+
+/// Length of the fixed uTP packet header.
+const UTP_HEADER_LEN: usize = 20;
+
+/// Result of a `benchmark_utp_transfer` call.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub bytes_sent: usize,
+    pub duration_ms: u64,
+    /// Payload bytes per second, excluding uTP headers and retransmitted data.
+    pub goodput_bytes_per_sec: f64,
+    pub data_packets_sent: u64,
+    pub retransmissions: u64,
+}
+
+/// Counts of DATA packets sent over a single uTP connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SendCounts {
+    pub data_packets: u64,
+    pub retransmissions: u64,
+}
+
+#[derive(Default)]
+struct ConnectionSendState {
+    seq_nrs: HashSet<u16>,
+    counts: SendCounts,
+}
+
+/// Tracks outbound uTP DATA packets per connection.
+///
+/// A DATA packet whose sequence number was already sent on the same connection is counted as a
+/// retransmission. Sequence numbers wrap after 65536 packets, so counts are only accurate for
+/// transfers shorter than that.
+#[derive(Default)]
+pub struct PacketCounter {
+    connections: Mutex<HashMap<(UtpEnr, u16), ConnectionSendState>>,
+}
+
+impl PacketCounter {
+    fn record(&self, packet: &[u8], target: &UtpEnr) {
+        if packet.len() < UTP_HEADER_LEN || packet[0] >> 4 != ST_DATA {
+            return;
+        }
+        let conn_id = u16::from_be_bytes([packet[2], packet[3]]);
+        let seq_nr = u16::from_be_bytes([packet[16], packet[17]]);
+
+        let mut connections = self.connections.lock().unwrap();
+        let state = connections.entry((target.clone(), conn_id)).or_default();
+        state.counts.data_packets += 1;
+        if !state.seq_nrs.insert(seq_nr) {
+            state.counts.retransmissions += 1;
+        }
+    }
+
+    /// Removes and returns the counts for the connection with send ID `conn_id` to `peer`.
+    pub fn take(&self, peer: &UtpEnr, conn_id: u16) -> SendCounts {
+        self.connections
+            .lock()
+            .unwrap()
+            .remove(&(peer.clone(), conn_id))
+            .map(|state| state.counts)
+            .unwrap_or_default()
+    }
+}
+
+/// A socket that records outbound uTP packets in a `PacketCounter` before passing them on.
+pub struct CountingUdpSocket<S> {
+    inner: S,
+    counter: Arc<PacketCounter>,
+}
+
+impl<S> CountingUdpSocket<S> {
+    pub fn new(inner: S, counter: Arc<PacketCounter>) -> Self {
+        Self { inner, counter }
+    }
+}
+
+#[async_trait]
+impl<S: AsyncUdpSocket<UtpEnr>> AsyncUdpSocket<UtpEnr> for CountingUdpSocket<S> {
+    async fn send_to(&self, buf: &[u8], target: &UtpEnr) -> io::Result<usize> {
+        self.counter.record(buf, target);
+        self.inner.send_to(buf, target).await
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, UtpEnr)> {
+        self.inner.recv_from(buf).await
+    }
+}